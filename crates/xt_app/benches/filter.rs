@@ -0,0 +1,59 @@
+//! Rebuilding the grid's filtered row cache: `cargo bench -p xt_app`.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::time::Duration;
+use xt_app::state::AppState;
+use xt_core::model::Entry;
+
+const ENTRIES: usize = 100_000;
+
+/// A state holding [`ENTRIES`] rows (every fifth translated) once its search
+/// index is built.
+fn indexed_state() -> AppState {
+    let mut state = AppState::new();
+    let entries = (0..ENTRIES)
+        .map(|i| Entry {
+            key: format!("plugin:{i:08x}"),
+            source_text: format!("Source text {i} lorem ipsum dolor sit amet").into(),
+            target_text: if i % 5 == 0 {
+                format!("訳文 {i}").into()
+            } else {
+                Default::default()
+            },
+        })
+        .collect::<Vec<_>>();
+    state.set_entries_with_history(entries);
+    while state.is_indexing() {
+        state.poll_search_index();
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    state
+}
+
+fn bench_filter(c: &mut Criterion) {
+    let mut state = indexed_state();
+
+    let mut group = c.benchmark_group("filter");
+    group.throughput(Throughput::Elements(ENTRIES as u64));
+    group.bench_function("rebuild/query", |b| {
+        let mut flip = false;
+        b.iter(|| {
+            flip = !flip;
+            state.set_query(if flip { "text 4" } else { "amet" });
+            state.filtered_len()
+        })
+    });
+    state.set_query("");
+    group.bench_function("rebuild/untranslated_only", |b| {
+        let mut flip = false;
+        b.iter(|| {
+            flip = !flip;
+            state.set_untranslated_only(flip);
+            state.filtered_len()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_filter);
+criterion_main!(benches);
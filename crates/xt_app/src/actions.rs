@@ -1,58 +1,289 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::time::Instant;
 
+use xt_core::candidates::{CandidateSource, Proposal};
 use xt_core::dictionary::TranslationDictionary;
-use xt_core::diff::{update_source, DiffEntry};
-use xt_core::encoding::{decode, encode, Encoding, EncodingError};
+use xt_core::diff::{diff_plugin_versions, diff_strings, update_source, DiffEntry};
+use xt_core::encoding::{decode, encode, EncodingError};
+use xt_core::extract_cache::ExtractCache;
+use xt_core::formats::dsd::{export_dsd_json, DsdExportParams};
 use xt_core::formats::esp::{
-    apply_translations, extract_strings as extract_esp_strings, ExtractedString,
+    read_masters, read_plugin_info, ExtractedString, PluginInfo, StringStorage,
 };
 use xt_core::formats::plugin::{read_plugin, write_plugin, PluginFile};
 use xt_core::formats::plugin_binary::extract_null_terminated_utf8;
-use xt_core::formats::strings::{
-    read_dlstrings, read_ilstrings, read_strings, write_dlstrings, write_ilstrings, write_strings,
-    StringsEntry, StringsFile,
+use xt_core::formats::strings::StringsFile;
+use xt_core::game::GameProfile;
+use xt_core::hybrid::group_hybrid_records;
+use xt_core::import_export::{
+    apply_xml_with_proposals_cancelable, export_entries_json_with_meta, export_entries_with_meta,
+    export_entries_xtranslator, import_entries_any_with_meta, ApplyProfile, XmlApplyStats,
+    XtranslatorExportParams,
 };
-use xt_core::hybrid::build_hybrid_entries;
-use xt_core::import_export::{apply_xml_default, export_entries, import_entries, XmlApplyStats};
-use xt_core::model::Entry;
-use xt_core::validation::{
-    validate_alias_tags, validate_braced_placeholders, validate_printf_placeholders,
+use xt_core::loadorder::{match_fields, LoadOrderPlugin};
+use xt_core::mo2::Mo2Error;
+use xt_core::model::{EditMetaMap, Entry, NoteMap};
+use xt_core::package::{create_package, PackageContent, PackageManifest, PackageReport};
+use xt_core::pipeline::{
+    self, backup_with_policy, check_writable, convert_esp_localization, export_esp_localized,
+    list_backups, load_plugin_entries_cached, load_plugin_entries_cancelable,
+    load_plugin_entries_lenient, plan_esp_save, plan_strings_save, plugin_languages,
+    restore_backup, strings_entries, with_suffix_path, workspace_root_from_plugin, write_atomic,
+    BackupPolicy, CacheStatus, CancelToken, Canceled, LocalizationChange, NoProgress,
+    PipelineError, PlannedFile, PluginContext, ProgressSink, SavePlan, SaveReport,
 };
+use xt_core::placeholders;
+use xt_core::record_index::RecordIndex;
+use xt_core::review_sheet::{parse_corrections, parse_pasted_rows};
+use xt_core::search::MatchOptions;
+use xt_core::validation::{SaveGuard, ValidationConfig, ValidationSummary, Validator};
 
-use crate::state::{AppState, StringsKind, Tab};
+use crate::background::{run_blocking, JobRequest};
+use crate::columns::{ColumnLayout, EntrySort};
+use crate::keymap::Keymap;
+use crate::log::{self, LogLevel};
+use crate::prefs::{
+    extract_cache_dir, user_dictionary_path, DictionaryPrefs, Mo2Prefs, RewritePrefs, SavePrefs,
+};
+use crate::state::{
+    editor_ids, now_unix_seconds, AppState, CorrectionReport, ReferenceTranslation,
+    StringsDiffReport, StringsInfoReport, StringsKind, Tab,
+};
+use crate::stats::TranslationStats;
 
 pub enum AppAction {
     SetQuery(String),
+    /// Search box typing; applied as the query once typing pauses.
+    EditQuery(String),
     SelectEntry(String),
+    ToggleSelection(String),
+    SelectRange(String),
+    SetUntranslatedOnly(bool),
+    SelectNextRow,
+    SelectPreviousRow,
+    SelectNextUntranslated,
+    SelectPreviousUntranslated,
+    /// Next row with a validation result.
+    SelectNextIssue,
+    /// Row of the record with this form id (hex) or EDID.
+    GoToRecord(String),
+    /// Next/previous row in the review queue (要確認).
+    SelectNextReview,
+    SelectPreviousReview,
+    /// Marks the selected queued rows checked and moves to the next one.
+    ApproveReview,
+    /// Clears the targets of the selected queued rows and moves on.
+    RejectReview,
+    /// Shows only the review queue.
+    SetReviewOnly(bool),
+    /// Copies the editor's source text into its target text.
+    CopySourceToTarget,
+    /// Marks or unmarks the selected rows as checked.
+    ToggleValidated,
+    /// Sets a row's translator note; a blank note removes it.
+    SetNote {
+        key: String,
+        note: String,
+    },
+    SetSearchOptions(MatchOptions),
+    /// Entry list column order, widths and visibility.
+    SetColumns(ColumnLayout),
+    /// Sorts the entry list; `None` restores file order.
+    SetSort(Option<EntrySort>),
+    /// Keyboard shortcuts (オプション > ショートカット設定).
+    SetKeymap(Keymap),
     SetEditSource(String),
     SetEditTarget(String),
     SetXmlText(String),
     ExportXmlToEditor,
+    ExportXtranslatorXml(PathBuf),
+    ExportJson(PathBuf),
+    ExportSelectionXml(PathBuf),
+    ExportFilteredXml(PathBuf),
+    ExportDsdJson(PathBuf),
+    /// Writes the 統計 tab figures as CSV.
+    ExportStatsCsv(PathBuf),
+    /// Writes the log entries up to `filter`, as the ログ tab shows them.
+    ExportLog {
+        path: PathBuf,
+        filter: LogLevel,
+    },
+    /// Writes every row as a bilingual HTML review sheet.
+    ExportReviewSheet(PathBuf),
+    /// Applies the target column of an edited review sheet (HTML or CSV).
+    ImportCorrections(PathBuf),
+    /// Applies rows pasted from a spreadsheet (TSV keyed by the first
+    /// column) as one undoable edit.
+    PasteRows(String),
     ApplyXmlFromEditor,
+    SetApplyProfile(ApplyProfile),
     LoadXml(PathBuf),
     LoadStrings(PathBuf),
     LoadPlugin(PathBuf),
+    /// Makes the document tab at the index active.
+    SwitchDocument(usize),
+    /// Closes the document tab at the index, discarding unsaved edits.
+    CloseDocument(usize),
+    /// Copies the target `key` has in the open plugin named first to the
+    /// other plugins that override the same field.
+    CopyOverrideTarget(String, String),
+    /// 別プラグインから訳文をコピー: fills the current plugin's targets from the
+    /// same record fields of another plugin.
+    CopyTargetsFrom(TargetSource),
+    /// Loads the Strings files of a translated plugin (and the source-language
+    /// files beside them) as the reference translation and applies it to the
+    /// untranslated rows.
+    LoadReference(PathBuf),
+    ClearReference,
+    /// Loads a new version of the current plugin and carries translations forward.
+    CompareWithNewPlugin(PathBuf),
+    UsePreviousTranslation(String),
+    DiffStringsFiles(PathBuf, PathBuf),
+    /// Strings情報: statistics and warnings of a Strings file.
+    InspectStringsFile(PathBuf),
+    /// Writes the current plugin (with translations) converted to/from the localized form.
+    ConvertLocalization(PathBuf, LocalizationChange),
+    /// Writes a localized copy plus a new Strings trio for the target language.
+    ExportLocalized(PathBuf),
+    /// 翻訳パッケージ作成: zips the translation, its XML and a manifest.
+    CreatePackage(PackageRequest),
     ApplyEdit,
-    BuildHybrid,
+    /// Replaces one row's target as an undoable edit (grid inline editing).
+    SetEntryTarget {
+        key: String,
+        target: String,
+    },
+    /// 重複原文: sets one target on every row with the source text, as one
+    /// undoable edit.
+    SetDuplicateTarget {
+        source: String,
+        target: String,
+    },
+    /// Whether [`AppAction::SetDuplicateTarget`] replaces existing targets.
+    SetOverwriteDuplicates(bool),
+    /// 置換ルール設定: the rules and whether they run after auto-translation.
+    SetRewritePrefs(RewritePrefs),
+    /// Runs the 置換ルール over the selected rows' targets, or every row's.
+    ApplyRewriteRules {
+        selected_only: bool,
+    },
+    /// Fills untranslated proper nouns with katakana readings: the selected
+    /// rows, or every NPC name.
+    TransliterateNames {
+        selected_only: bool,
+    },
+    /// 訳揺れ: replaces every target of the rows with the source text by the
+    /// chosen one, as one undoable edit.
+    UnifyTarget {
+        source: String,
+        target: String,
+    },
+    /// Applies the editor and selects the next row.
+    ApplyEditAndNext,
     BuildDictionary,
+    /// Builds the dictionary from the translation XML/JSON files in a folder.
+    BuildDictionaryFromXml(PathBuf),
+    /// Adds a dictionary pair, or replaces `previous` (its old source) with it.
+    SetDictionaryPair {
+        previous: Option<String>,
+        source: String,
+        target: String,
+    },
+    RemoveDictionaryPair(String),
+    /// Replaces the dictionary with the user dictionary file.
+    LoadUserDictionary,
+    SaveUserDictionary,
     QuickAuto,
+    /// Quick自動翻訳 of every untranslated row passing the search/filter,
+    /// undone in one step.
+    QuickAutoAll,
+    /// Takes one source's candidate for a conflicting row.
+    ResolveConflict {
+        key: String,
+        source: CandidateSource,
+    },
+    /// Resolves every conflict to the first source in the list that proposed
+    /// a target.
+    ResolveConflicts(Vec<CandidateSource>),
     Validate,
     DiffCheck,
     EncodingCheck,
     SetDictSourceLang(String),
     SetDictTargetLang(String),
     SetDictRoot(String),
+    /// Applies the 環境設定 dialog.
+    SetPreferences {
+        dictionary: DictionaryPrefs,
+        save: SavePrefs,
+        mo2: Mo2Prefs,
+        extract_cache: bool,
+    },
     ResetDictLanguagePair,
+    SetValidationConfig(ValidationConfig),
+    IgnoreSpelling(String),
     Undo,
     Redo,
+    SetAuthor(String),
+    /// Game profile for subsequent plugin loads.
+    SetGameProfile(GameProfile),
+    /// Reloads the current plugin with the Strings files of another language.
+    SetPluginLanguage(String),
+    JumpToHistory(usize),
+    NameCheckpoint(String),
     SetActiveTab(Tab),
     SaveOverwrite,
     SaveAsAuto,
     SaveAsPath(PathBuf),
+    /// Copies a backup of the loaded file over it and reloads it.
+    RestoreBackup(PathBuf),
+    DeleteBackup(PathBuf),
+    /// Deletes the cached plugin extraction results.
+    ClearExtractCache,
 }
 
-#[derive(Clone)]
+/// What 翻訳パッケージ作成 writes and where.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageRequest {
+    pub path: PathBuf,
+    pub manifest: PackageManifest,
+    /// Ship a DSD JSON instead of the translated plugin.
+    pub dsd: bool,
+}
+
+/// Where 別プラグインから訳文をコピー takes translations from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetSource {
+    /// Targets of the plugin open in the document tab at the index.
+    Document(usize),
+    /// Texts of a translated copy of a plugin, read from the file.
+    Reference(PathBuf),
+}
+
+/// What a file dropped on the window opens as, by extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DroppedFile {
+    /// `.esp`/`.esm`/`.esl`/`.xtplugin`, loaded like ファイル > Esp/Esmファイルを開く.
+    Plugin,
+    /// `.strings`/`.dlstrings`/`.ilstrings`.
+    Strings,
+    /// Translation `.xml`/`.json`, applied through the 翻訳XMLの適用 dialog.
+    Xml,
+}
+
+impl DroppedFile {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "esp" | "esm" | "esl" | "xtplugin" => Some(DroppedFile::Plugin),
+            "strings" | "dlstrings" | "ilstrings" => Some(DroppedFile::Strings),
+            "xml" | "json" => Some(DroppedFile::Xml),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum SaveMode {
     Overwrite,
     Auto,
@@ -68,6 +299,14 @@ pub struct SaveJobData {
     pub loaded_plugin: Option<PluginFile>,
     pub loaded_plugin_path: Option<PathBuf>,
     pub loaded_esp_strings: Option<Vec<ExtractedString>>,
+    pub plugin_context: PluginContext,
+    pub backup: BackupPolicy,
+    /// Where 別名保存 writes without a chosen path.
+    pub auto_output: Option<PathBuf>,
+    /// Writes a checksum manifest next to the saved file.
+    pub write_manifest: bool,
+    /// [`AppState::revision`] being saved.
+    pub revision: u64,
 }
 
 impl SaveJobData {
@@ -80,12 +319,86 @@ impl SaveJobData {
             loaded_plugin: state.loaded_plugin.clone(),
             loaded_plugin_path: state.loaded_plugin_path.clone(),
             loaded_esp_strings: state.loaded_esp_strings.clone(),
+            plugin_context: state.plugin_context.clone(),
+            backup: state.save_prefs().backup,
+            auto_output: save_target(state, &SaveMode::Auto),
+            write_manifest: state.save_prefs().write_manifest,
+            revision: state.revision(),
+        }
+    }
+}
+
+/// File a save in `mode` writes, as [`save_overwrite`] and [`save_as`] pick it.
+pub fn save_target(state: &AppState, mode: &SaveMode) -> Option<PathBuf> {
+    let loaded = match &state.loaded_plugin_path {
+        Some(path) if state.loaded_esp_strings.is_some() || state.loaded_plugin.is_some() => path,
+        _ => state.loaded_strings_path.as_ref()?,
+    };
+    Some(match mode {
+        SaveMode::Overwrite => loaded.clone(),
+        SaveMode::Auto => state.save_prefs().output_template.expand(
+            loaded.parent().unwrap_or_else(|| Path::new(".")),
+            loaded,
+            &state.dict_target_lang,
+            now_unix_seconds(),
+        ),
+        SaveMode::Path(path) => path.clone(),
+    })
+}
+
+/// Files a save in `mode` would create, modify and back up, with the number
+/// of strings that change in each; nothing is written.
+pub fn save_plan(state: &AppState, mode: &SaveMode) -> Result<SavePlan, String> {
+    let out = save_target(state, mode).ok_or_else(|| "保存対象がありません".to_string())?;
+    let backup = state.save_prefs().backup;
+    let now = now_unix_seconds();
+    if let Some(plugin_path) = state.loaded_plugin_path.as_deref() {
+        if let Some(extracted) = state.loaded_esp_strings.as_deref() {
+            let overwrite = out == plugin_path;
+            let output_root = (!overwrite).then(|| out.parent().unwrap_or_else(|| Path::new(".")));
+            let plan = plan_esp_save(
+                state.entries(),
+                plugin_path,
+                &out,
+                output_root,
+                extracted,
+                &state.plugin_context,
+            );
+            return Ok(if overwrite {
+                plan.with_backup(plugin_path, backup, now)
+            } else {
+                plan
+            });
+        }
+        if state.loaded_plugin.is_some() {
+            let plan = SavePlan {
+                files: vec![PlannedFile::write(&out, changed_rows(state.entries()))],
+            };
+            return Ok(if matches!(mode, SaveMode::Overwrite) {
+                plan.with_backup(&out, backup, now)
+            } else {
+                plan
+            });
         }
     }
+    let strings = state
+        .loaded_strings
+        .as_ref()
+        .ok_or_else(|| "保存対象がありません".to_string())?;
+    Ok(plan_strings_save(state.entries(), strings, &out).with_backup(&out, backup, now))
+}
+
+/// Creates the folders an output template puts `path` in.
+fn create_output_dir(path: Option<&Path>) -> Result<(), String> {
+    match path.and_then(Path::parent) {
+        Some(dir) if !dir.as_os_str().is_empty() => std::fs::create_dir_all(dir)
+            .map_err(|err| format!("出力フォルダを作成できません {}: {err}", dir.display())),
+        _ => Ok(()),
+    }
 }
 
 pub fn run_save_job(data: SaveJobData, mode: SaveMode) -> Result<PathBuf, String> {
-    match mode {
+    let report = match mode {
         SaveMode::Overwrite => save_overwrite(
             &data.entries,
             data.loaded_strings.as_ref(),
@@ -94,17 +407,24 @@ pub fn run_save_job(data: SaveJobData, mode: SaveMode) -> Result<PathBuf, String
             data.loaded_plugin.as_ref(),
             data.loaded_plugin_path.as_deref(),
             data.loaded_esp_strings.as_deref(),
+            &data.plugin_context,
+            data.backup,
         ),
-        SaveMode::Auto => save_as(
-            &data.entries,
-            data.loaded_strings.as_ref(),
-            data.loaded_strings_kind,
-            data.loaded_strings_path.as_deref(),
-            data.loaded_plugin.as_ref(),
-            data.loaded_plugin_path.as_deref(),
-            data.loaded_esp_strings.as_deref(),
-            None,
-        ),
+        SaveMode::Auto => {
+            create_output_dir(data.auto_output.as_deref())?;
+            save_as(
+                &data.entries,
+                data.loaded_strings.as_ref(),
+                data.loaded_strings_kind,
+                data.loaded_strings_path.as_deref(),
+                data.loaded_plugin.as_ref(),
+                data.loaded_plugin_path.as_deref(),
+                data.loaded_esp_strings.as_deref(),
+                &data.plugin_context,
+                data.backup,
+                data.auto_output,
+            )
+        }
         SaveMode::Path(path) => save_as(
             &data.entries,
             data.loaded_strings.as_ref(),
@@ -113,8 +433,17 @@ pub fn run_save_job(data: SaveJobData, mode: SaveMode) -> Result<PathBuf, String
             data.loaded_plugin.as_ref(),
             data.loaded_plugin_path.as_deref(),
             data.loaded_esp_strings.as_deref(),
+            &data.plugin_context,
+            data.backup,
             Some(path),
         ),
+    }?;
+    finish_save(report, data.write_manifest)
+}
+
+fn select_untranslated(state: &mut AppState, forward: bool) {
+    if !state.select_next_untranslated(forward) {
+        state.set_file_status(LogLevel::Info, "他に未訳の行はありません".to_string());
     }
 }
 
@@ -123,9 +452,117 @@ pub fn dispatch(state: &mut AppState, action: AppAction) -> Result<(), String> {
         AppAction::SetQuery(query) => {
             state.set_query(&query);
         }
+        AppAction::EditQuery(query) => {
+            state.edit_query(&query, Instant::now());
+        }
         AppAction::SelectEntry(key) => {
+            state.clear_multi_selection();
             state.select(&key);
         }
+        AppAction::ToggleSelection(key) => {
+            state.toggle_selection(&key);
+        }
+        AppAction::SelectRange(key) => {
+            state.select_range(&key);
+        }
+        AppAction::SetUntranslatedOnly(enabled) => {
+            state.set_untranslated_only(enabled);
+        }
+        AppAction::SetReviewOnly(enabled) => {
+            state.set_review_only(enabled);
+        }
+        AppAction::SelectNextRow => {
+            state.select_adjacent(true);
+        }
+        AppAction::SelectPreviousRow => {
+            state.select_adjacent(false);
+        }
+        AppAction::SelectNextUntranslated => {
+            select_untranslated(state, true);
+        }
+        AppAction::SelectPreviousUntranslated => {
+            select_untranslated(state, false);
+        }
+        AppAction::GoToRecord(query) => {
+            let query = query.trim();
+            if query.is_empty() {
+                return Err("フォームID（16進）またはEDIDを入力してください".to_string());
+            }
+            if state.go_to_record(query).is_none() {
+                return Err(format!("フォームID/EDIDが見つかりません: {query}"));
+            }
+        }
+        AppAction::SelectNextIssue => {
+            if state.validation_issues.is_empty() {
+                state.set_file_status(
+                    LogLevel::Info,
+                    "検証結果がありません（検証を実行してください）".to_string(),
+                );
+            } else if !state.select_next_issue(true) {
+                state.set_file_status(
+                    LogLevel::Info,
+                    "他に検証結果のある行はありません".to_string(),
+                );
+            }
+        }
+        AppAction::SelectNextReview | AppAction::SelectPreviousReview => {
+            let forward = matches!(action, AppAction::SelectNextReview);
+            if state.review_count() == 0 {
+                state.set_file_status(LogLevel::Info, "要確認の行はありません".to_string());
+            } else if !state.select_next_review(forward) {
+                state.set_file_status(LogLevel::Info, "他に要確認の行はありません".to_string());
+            }
+        }
+        AppAction::ApproveReview => {
+            let approved = state.approve_review();
+            if approved == 0 {
+                return Err("要確認の行を選択してください".to_string());
+            }
+            state.set_file_status(
+                LogLevel::Info,
+                format!(
+                    "{approved}件を承認しました（残り {}件）",
+                    state.review_count()
+                ),
+            );
+        }
+        AppAction::RejectReview => {
+            let rejected = state.reject_review();
+            if rejected == 0 {
+                return Err("要確認の行を選択してください".to_string());
+            }
+            state.set_file_status(
+                LogLevel::Info,
+                format!(
+                    "{rejected}件を差し戻しました（残り {}件）",
+                    state.review_count()
+                ),
+            );
+        }
+        AppAction::CopySourceToTarget => {
+            state.edit_target = state.edit_source.clone();
+        }
+        AppAction::ToggleValidated => {
+            state.toggle_validated();
+        }
+        AppAction::SetNote { key, note } => {
+            if !state.entries().iter().any(|entry| entry.key == key) {
+                return Err(format!("行が見つかりません: {key}"));
+            }
+            state.set_note(&key, &note);
+        }
+        AppAction::SetSearchOptions(options) => {
+            state.set_search_options(options);
+        }
+        AppAction::SetColumns(columns) => {
+            state.set_columns(columns);
+        }
+        AppAction::SetSort(sort) => {
+            state.set_sort(sort);
+        }
+        AppAction::SetKeymap(keymap) => {
+            state.set_keymap(keymap);
+        }
         AppAction::SetEditSource(value) => {
             state.edit_source = value;
         }
@@ -136,24 +573,395 @@ pub fn dispatch(state: &mut AppState, action: AppAction) -> Result<(), String> {
             state.xml_text = value;
         }
         AppAction::ExportXmlToEditor => {
-            state.xml_text = export_entries(state.entries());
+            state.xml_text =
+                export_entries_with_meta(&state.export_entries(), &state.edit_meta, &state.notes);
             state.xml_error = None;
-            state.file_status = "XMLを書き出しました（エディタ）".to_string();
+            state.set_file_status(
+                LogLevel::Info,
+                "XMLを書き出しました（エディタ）".to_string(),
+            );
+        }
+        AppAction::ExportXtranslatorXml(path) => {
+            let xml =
+                export_entries_xtranslator(&state.export_entries(), &xtranslator_params(state));
+            write_atomic(&path, xml).map_err(|err| format!("write {}: {err}", path.display()))?;
+            state.set_file_status(
+                LogLevel::Info,
+                format!("xTranslator XMLを書き出しました: {}", path.display()),
+            );
+        }
+        AppAction::ExportJson(path) => {
+            write_atomic(
+                &path,
+                export_entries_json_with_meta(
+                    &state.export_entries(),
+                    &state.edit_meta,
+                    &state.notes,
+                ),
+            )
+            .map_err(|err| format!("write {}: {err}", path.display()))?;
+            state.set_file_status(
+                LogLevel::Info,
+                format!("JSONを書き出しました: {}", path.display()),
+            );
+        }
+        AppAction::ExportSelectionXml(path) => {
+            let entries = state.without_protected(state.selected_entries());
+            if entries.is_empty() {
+                return Err("書き出す行を選択してください".to_string());
+            }
+            write_partial_export(&path, &entries, &state.edit_meta, &state.notes)?;
+            state.set_file_status(
+                LogLevel::Info,
+                format!(
+                    "選択範囲を書き出しました: {} ({}件)",
+                    path.display(),
+                    entries.len()
+                ),
+            );
+        }
+        AppAction::ExportFilteredXml(path) => {
+            let filtered = state.filtered_entries();
+            let entries = state.without_protected(filtered);
+            write_partial_export(&path, &entries, &state.edit_meta, &state.notes)?;
+            state.set_file_status(
+                LogLevel::Info,
+                format!(
+                    "フィルタ結果を書き出しました: {} ({}件)",
+                    path.display(),
+                    entries.len()
+                ),
+            );
+        }
+        AppAction::ExportDsdJson(path) => {
+            let (Some(plugin_path), Some(strings)) = (
+                state.loaded_plugin_path.as_deref(),
+                state.loaded_esp_strings.as_deref(),
+            ) else {
+                return Err("DSD JSONはESP/ESM読込時のみ書き出せます".to_string());
+            };
+            let params = DsdExportParams {
+                plugin_name: plugin_path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                masters: read_masters(plugin_path)
+                    .map_err(|err| format!("read masters {}: {err}", plugin_path.display()))?,
+            };
+            let (json, stats) = export_dsd_json(&state.export_entries(), strings, &params);
+            write_atomic(&path, json).map_err(|err| format!("write {}: {err}", path.display()))?;
+            state.set_file_status(
+                LogLevel::Info,
+                format!(
+                    "DSD JSONを書き出しました: {} (written={} skipped={})",
+                    path.display(),
+                    stats.written,
+                    stats.skipped
+                ),
+            );
+        }
+        AppAction::ExportStatsCsv(path) => {
+            let stats =
+                TranslationStats::collect(state, state.session_started_at, now_unix_seconds());
+            write_atomic(&path, stats.to_csv())
+                .map_err(|err| format!("write {}: {err}", path.display()))?;
+            state.set_file_status(
+                LogLevel::Info,
+                format!("統計をCSVで書き出しました: {}", path.display()),
+            );
+        }
+        AppAction::ExportLog { path, filter } => {
+            let text = log::lock(&state.log).to_text(filter);
+            write_atomic(&path, text).map_err(|err| format!("write {}: {err}", path.display()))?;
+            state.set_file_status(
+                LogLevel::Info,
+                format!("ログを保存しました: {}", path.display()),
+            );
+        }
+        AppAction::ExportReviewSheet(path) => {
+            if state.entries().is_empty() {
+                return Err("書き出す行がありません".to_string());
+            }
+            let title = loaded_file_name(state).unwrap_or_else(|| "xtrans".to_string());
+            let sheet = state.review_sheet(&title);
+            write_atomic(&path, sheet.to_html())
+                .map_err(|err| format!("write {}: {err}", path.display()))?;
+            state.set_file_status(
+                LogLevel::Info,
+                format!(
+                    "レビューシートを書き出しました: {} ({}行)",
+                    path.display(),
+                    sheet.rows.len()
+                ),
+            );
+        }
+        AppAction::ImportCorrections(path) => {
+            let content = std::fs::read_to_string(&path)
+                .map_err(|err| format!("read {}: {err}", path.display()))?;
+            let corrections =
+                parse_corrections(&content).map_err(|err| format!("{}: {err}", path.display()))?;
+            let report = state.apply_corrections("レビュー修正の取り込み", &corrections);
+            report_corrections(
+                state,
+                "レビュー修正",
+                "レビュー修正を取り込みました",
+                &report,
+            );
+        }
+        AppAction::PasteRows(text) => {
+            let corrections = parse_pasted_rows(&text).map_err(|err| format!("貼り付け: {err}"))?;
+            if corrections.is_empty() {
+                return Err("貼り付ける行がありません".to_string());
+            }
+            let report = state.apply_corrections("貼り付け", &corrections);
+            report_corrections(state, "貼り付け", "貼り付けました", &report);
         }
         AppAction::ApplyXmlFromEditor => {
-            apply_xml_to_current(state, state.xml_text.clone())?;
+            let contents = state.xml_text.clone();
+            run_blocking(
+                state,
+                JobRequest::XmlApply {
+                    contents,
+                    source_label: None,
+                },
+            )?;
+        }
+        AppAction::SetApplyProfile(profile) => {
+            state.apply_profile = profile;
         }
         AppAction::LoadXml(path) => {
-            let contents = std::fs::read_to_string(&path)
-                .map_err(|err| format!("read {}: {err}", path.display()))?;
-            apply_xml_to_current(state, contents)?;
-            state.file_status = format!("XML適用: {}", path.display());
+            run_blocking(state, JobRequest::LoadXml(path))?;
         }
         AppAction::LoadStrings(path) => {
             load_strings_from_path(state, &path)?;
+            state.reload_spell_ignore();
+            auto_translate_loaded(state);
         }
         AppAction::LoadPlugin(path) => {
-            load_plugin_from_path(state, &path)?;
+            load_plugin_from_path(state, &path, None)?;
+            state.reload_spell_ignore();
+            auto_translate_loaded(state);
+        }
+        AppAction::SwitchDocument(index) => {
+            state.switch_document(index);
+        }
+        AppAction::CloseDocument(index) => {
+            state.close_document(index);
+        }
+        AppAction::CopyTargetsFrom(source) => {
+            copy_targets_from(state, &source)?;
+        }
+        AppAction::CopyOverrideTarget(plugin, key) => {
+            let overrides = state.overrides();
+            let Some((from, field)) = overrides.iter().find_map(|field| {
+                field
+                    .definitions
+                    .iter()
+                    .find(|definition| definition.plugin == plugin && definition.key == key)
+                    .map(|from| (from, field))
+            }) else {
+                return Err(format!("上書きされた行がありません: {plugin} {key}"));
+            };
+            let documents = state
+                .open_plugins()
+                .into_iter()
+                .map(|open| (open.name, open.document))
+                .collect::<HashMap<_, _>>();
+            let mut copied = 0;
+            for to in &field.definitions {
+                if std::ptr::eq(to, from) || to.target == from.target {
+                    continue;
+                }
+                let target = placeholders::transfer(&from.target, &from.source, &to.source)
+                    .unwrap_or_else(|| from.target.to_string());
+                if documents
+                    .get(&to.plugin)
+                    .is_some_and(|&document| state.set_document_target(document, &to.key, &target))
+                {
+                    copied += 1;
+                }
+            }
+            state.set_file_status(
+                LogLevel::Info,
+                format!("上書き: {copied} 件に訳文をコピーしました"),
+            );
+        }
+        AppAction::RestoreBackup(backup) => {
+            let file = listed_backup_owner(state, &backup)?;
+            restore_backup(
+                &file,
+                &backup,
+                state.save_prefs().backup,
+                now_unix_seconds(),
+            )
+            .map_err(|err| err.to_string())?;
+            if state.loaded_plugin_path.as_deref() == Some(file.as_path()) {
+                load_plugin_from_path(state, &file, None)?;
+            } else {
+                load_strings_from_path(state, &file)?;
+            }
+            state.reload_spell_ignore();
+            state.set_file_status(
+                LogLevel::Info,
+                format!("バックアップから復元しました: {}", backup.display()),
+            );
+        }
+        AppAction::DeleteBackup(backup) => {
+            listed_backup_owner(state, &backup)?;
+            std::fs::remove_file(&backup)
+                .map_err(|err| format!("delete {}: {err}", backup.display()))?;
+            state.set_file_status(
+                LogLevel::Info,
+                format!("バックアップを削除しました: {}", backup.display()),
+            );
+        }
+        AppAction::ClearExtractCache => {
+            let dir = extract_cache_dir().ok_or("設定フォルダが見つかりません")?;
+            let removed = ExtractCache::new(&dir)
+                .clear()
+                .map_err(|err| format!("delete {}: {err}", dir.display()))?;
+            state.set_file_status(
+                LogLevel::Info,
+                format!("抽出キャッシュを削除しました: {removed}件"),
+            );
+        }
+        AppAction::CompareWithNewPlugin(path) => {
+            if state.loaded_plugin_path.is_none() {
+                return Err("旧バージョンのPluginを先に読み込んでください".to_string());
+            }
+            let old_entries = state.entries().to_vec();
+            load_plugin_from_path(state, &path, None)?;
+            let diff = diff_plugin_versions(&old_entries, state.entries());
+            state.set_entries_with_history(diff.entries.clone());
+            state.reload_spell_ignore();
+            let summary = diff.summary;
+            state.set_file_status(
+                LogLevel::Info,
+                format!(
+                    "差分: 変更なし={} (引継ぎ={}) 原文変更={} 新規={} 削除={}",
+                    summary.unchanged,
+                    summary.carried,
+                    summary.source_changed,
+                    summary.added,
+                    summary.removed
+                ),
+            );
+            state.plugin_diff = Some(diff);
+            state.active_tab = Tab::Diff;
+        }
+        AppAction::DiffStringsFiles(old_path, new_path) => {
+            let (old, _) = read_strings_file(&old_path)?;
+            let (new, _) = read_strings_file(&new_path)?;
+            let diff = diff_strings(&old, &new);
+            state.set_file_status(
+                LogLevel::Info,
+                format!(
+                    "Strings差分: added={} removed={} changed={}",
+                    diff.added.len(),
+                    diff.removed.len(),
+                    diff.changed.len()
+                ),
+            );
+            state.strings_diff = Some(StringsDiffReport {
+                old_path,
+                new_path,
+                diff,
+            });
+        }
+        AppAction::InspectStringsFile(path) => {
+            let (file, _) = read_strings_file(&path)?;
+            let info = file.info().unwrap_or_default();
+            state.set_file_status(
+                LogLevel::Info,
+                format!(
+                    "Strings情報: entries={} warnings={}",
+                    info.entries,
+                    info.warnings().len()
+                ),
+            );
+            state.strings_info = Some(StringsInfoReport { path, info });
+        }
+        AppAction::ConvertLocalization(path, change) => {
+            let (plugin_path, extracted) = localization_source(state, &path)?;
+            let report = convert_esp_localization(
+                state.entries(),
+                &plugin_path,
+                &path,
+                extracted,
+                &state.plugin_context,
+                change,
+            )
+            .map_err(|err| err.to_string())?;
+            let label = match change {
+                LocalizationChange::Localize => "Localized形式",
+                LocalizationChange::Delocalize => "埋め込み文字列",
+            };
+            state.set_file_status(
+                LogLevel::Info,
+                format!(
+                    "{label}に変換しました: {} (converted={} skipped={} strings={})",
+                    path.display(),
+                    report.converted,
+                    report.skipped,
+                    report.strings_files.len()
+                ),
+            );
+        }
+        AppAction::ExportLocalized(path) => {
+            let (plugin_path, extracted) = localization_source(state, &path)?;
+            let report = export_esp_localized(
+                state.entries(),
+                &plugin_path,
+                &path,
+                extracted,
+                &state.plugin_context,
+                &state.dict_target_lang,
+            )
+            .map_err(|err| err.to_string())?;
+            let strings_dir = report
+                .strings_files
+                .first()
+                .and_then(|file| file.parent())
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default();
+            state.set_file_status(
+                LogLevel::Info,
+                format!(
+                    "Localizedとして書き出しました: {} + Strings {}件 ({strings_dir}) converted={}",
+                    path.display(),
+                    report.strings_files.len(),
+                    report.converted
+                ),
+            );
+        }
+        AppAction::CreatePackage(request) => {
+            let report = create_translation_package(state, &request)?;
+            state.set_file_status(
+                LogLevel::Info,
+                format!(
+                    "翻訳パッケージを作成しました: {} ({}ファイル)",
+                    request.path.display(),
+                    report.files.len()
+                ),
+            );
+        }
+        AppAction::UsePreviousTranslation(key) => {
+            let Some(row) = state
+                .plugin_diff
+                .as_ref()
+                .and_then(|diff| diff.rows.iter().find(|row| row.key == key))
+            else {
+                return Err(format!("差分行がありません: {key}"));
+            };
+            let Some(entry) = state.entries().iter().find(|entry| entry.key == key) else {
+                return Err(format!("新バージョンに存在しない行です: {key}"));
+            };
+            let source = entry.source_text.clone();
+            let target = row.old_target.clone();
+            if state.update_entry(&key, &source, &target) {
+                state.select(&key);
+                state.set_file_status(LogLevel::Info, format!("旧訳を適用しました: {key}"));
+            }
         }
         AppAction::ApplyEdit => {
             let Some(key) = state.selected_key() else {
@@ -162,86 +970,230 @@ pub fn dispatch(state: &mut AppState, action: AppAction) -> Result<(), String> {
             let source = state.edit_source.clone();
             let target = state.edit_target.clone();
             if state.update_entry(&key, &source, &target) {
-                state.file_status = "編集を反映しました".to_string();
+                state.set_file_status(LogLevel::Info, "編集を反映しました".to_string());
             }
         }
-        AppAction::BuildHybrid => {
-            let p = state.loaded_plugin.clone();
-            let s = state.loaded_strings.clone();
-            match (p, s) {
-                (Some(plugin), Some(strings)) => {
-                    state.hybrid_preview = build_hybrid_entries(&plugin, &strings);
-                    state.hybrid_error = None;
-                }
-                _ => {
-                    state.hybrid_error = Some("Plugin/Stringsを先に読み込んでください".to_string());
+        AppAction::SetDuplicateTarget { source, target } => {
+            let overwrite = state.overwrite_duplicates();
+            let changed = set_duplicate_target(state, &source, &target, overwrite)?;
+            state.set_file_status(
+                LogLevel::Info,
+                format!("同じ原文の {changed}行に訳文を設定しました"),
+            );
+        }
+        AppAction::UnifyTarget { source, target } => {
+            let changed = set_duplicate_target(state, &source, &target, true)?;
+            state.set_file_status(LogLevel::Info, format!("訳文を統一しました: {changed}行"));
+        }
+        AppAction::SetOverwriteDuplicates(overwrite) => {
+            state.set_overwrite_duplicates(overwrite);
+        }
+        AppAction::SetRewritePrefs(rewrite) => {
+            state.set_rewrite_prefs(rewrite)?;
+        }
+        AppAction::ApplyRewriteRules { selected_only } => {
+            let changed = state
+                .apply_rewrite_rules(selected_only)
+                .ok_or_else(|| "有効な置換ルールがありません".to_string())?;
+            if let Some(entry) = state.selected_entry().cloned() {
+                state.edit_target = entry.target_text.to_string();
+            }
+            state.set_file_status(
+                LogLevel::Info,
+                format!("置換ルールを適用しました: {changed}行"),
+            );
+        }
+        AppAction::TransliterateNames { selected_only } => {
+            let filled = state.transliterate_names(selected_only);
+            if filled == 0 {
+                return Err("カタカナ化できる未訳の固有名詞がありません".to_string());
+            }
+            if let Some(entry) = state.selected_entry().cloned() {
+                state.edit_target = entry.target_text.to_string();
+            }
+            state.set_file_status(
+                LogLevel::Info,
+                format!("カタカナ化しました: {filled}行（要確認）"),
+            );
+        }
+        AppAction::SetEntryTarget { key, target } => {
+            let Some(source) = state
+                .entries()
+                .iter()
+                .find(|entry| entry.key == key)
+                .map(|entry| entry.source_text.to_string())
+            else {
+                return Err(format!("行が見つかりません: {key}"));
+            };
+            if state.update_entry(&key, &source, &target) {
+                if state.selected_key().as_deref() == Some(key.as_str()) {
+                    state.edit_target = target;
                 }
+                state.set_file_status(LogLevel::Info, "編集を反映しました".to_string());
+            }
+        }
+        AppAction::ApplyEditAndNext => {
+            if state.apply_edit_and_advance() {
+                state.set_file_status(LogLevel::Info, "編集を反映しました".to_string());
             }
         }
         AppAction::BuildDictionary => {
-            let root = PathBuf::from(&state.dict_root);
-            match TranslationDictionary::build_from_strings_dir(
-                &root,
-                &state.dict_source_lang,
-                &state.dict_target_lang,
-            ) {
-                Ok((built, stats)) => {
-                    let pairs = built.len();
-                    state.dict = Some(built);
-                    state.mark_dictionary_built(pairs, stats.files_seen, stats.file_pairs);
-                    state.dict_status = format!(
-                        "辞書構築: pairs={} files={} pair_files={}",
-                        pairs, stats.files_seen, stats.file_pairs
-                    );
-                }
-                Err(err) => {
-                    state.dict_status = format!("辞書構築失敗: {err}");
-                    return Err(state.dict_status.clone());
-                }
+            run_blocking(state, JobRequest::BuildDictionary)?;
+        }
+        AppAction::BuildDictionaryFromXml(dir) => {
+            run_blocking(state, JobRequest::BuildDictionaryFromXml(dir))?;
+        }
+        AppAction::SetDictionaryPair {
+            previous,
+            source,
+            target,
+        } => {
+            if source.is_empty() || target.is_empty() {
+                return Err("原文と訳文を入力してください".to_string());
+            }
+            let dict = state
+                .dict
+                .get_or_insert_with(TranslationDictionary::default);
+            if let Some(previous) = previous.filter(|previous| *previous != source) {
+                dict.remove(&previous);
+            }
+            let replaced = dict.insert(source.as_str().into(), target.into());
+            state.dict_revision += 1;
+            state.dict_unsaved = true;
+            let verb = if replaced.is_some() {
+                "更新"
+            } else {
+                "追加"
+            };
+            state.set_dict_status(LogLevel::Info, format!("辞書を{verb}しました: {source}"));
+        }
+        AppAction::RemoveDictionaryPair(source) => {
+            let removed = state.dict.as_mut().and_then(|dict| dict.remove(&source));
+            if removed.is_none() {
+                return Err(format!("辞書にありません: {source}"));
+            }
+            state.dict_revision += 1;
+            state.dict_unsaved = true;
+            state.set_dict_status(LogLevel::Info, format!("辞書から削除しました: {source}"));
+        }
+        AppAction::LoadReference(path) => {
+            let (dict, _) = TranslationDictionary::build_reference(&path, &state.dict_source_lang)
+                .map_err(|err| {
+                    let message = format!("参照訳の読込に失敗しました: {err}");
+                    state.set_dict_status(LogLevel::Error, message.clone());
+                    message
+                })?;
+            if dict.is_empty() {
+                let message = format!(
+                    "参照訳がありません: {} と対になる {} のStringsを同じフォルダに置いてください",
+                    path.display(),
+                    state.dict_source_lang
+                );
+                state.set_dict_status(LogLevel::Error, message.clone());
+                return Err(message);
+            }
+            let (next, proposals) = dict.apply_reference_with_proposals(state.entries(), &[], true);
+            let updated = state.apply_target_updates_with_history("参照訳を適用", next);
+            state.propose_targets(keyed_proposals(state.entries(), proposals));
+            let pairs = dict.len();
+            state.set_dict_status(
+                LogLevel::Info,
+                format!(
+                    "参照訳を読み込みました: {} ({pairs}件) / 適用 {updated}行",
+                    path.display()
+                ),
+            );
+            state.reference = Some(ReferenceTranslation { path, dict });
+        }
+        AppAction::ClearReference => {
+            if state.reference.take().is_some() {
+                state.set_dict_status(LogLevel::Info, "参照訳を解除しました".to_string());
+            }
+        }
+        AppAction::LoadUserDictionary => {
+            let path = user_dictionary_path(&state.dict_user_path)
+                .ok_or("ユーザー辞書の保存先がありません")?;
+            let dict = TranslationDictionary::load_from_path(&path).map_err(|err| {
+                let message = format!("ユーザー辞書の読込に失敗しました: {err}");
+                state.set_dict_status(LogLevel::Error, message.clone());
+                message
+            })?;
+            let pairs = dict.len();
+            state.dict = Some(dict);
+            state.dict_revision += 1;
+            state.dict_unsaved = false;
+            state.set_dict_status(
+                LogLevel::Info,
+                format!(
+                    "ユーザー辞書を読み込みました: {} ({pairs}件)",
+                    path.display()
+                ),
+            );
+        }
+        AppAction::SaveUserDictionary => {
+            let path = user_dictionary_path(&state.dict_user_path)
+                .ok_or("ユーザー辞書の保存先がありません")?;
+            let dict = state.dict.as_ref().ok_or("辞書未構築")?;
+            let result = path
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .map_err(|err| err.to_string())
+                .and_then(|()| dict.save_to_path(&path).map_err(|err| err.to_string()));
+            let pairs = dict.len();
+            if let Err(err) = result {
+                let message = format!("ユーザー辞書の保存に失敗しました: {err}");
+                state.set_dict_status(LogLevel::Error, message.clone());
+                return Err(message);
             }
+            state.dict_unsaved = false;
+            state.set_dict_status(
+                LogLevel::Info,
+                format!("ユーザー辞書を保存しました: {} ({pairs}件)", path.display()),
+            );
         }
         AppAction::QuickAuto => {
             let selected = state.selected_key();
-            let entries = state.entries().to_vec();
-            let result = {
-                let current = state.dict.as_ref();
-                apply_quick_auto_selection(current, &entries, selected)
+            run_blocking(state, JobRequest::QuickAuto { selected })?;
+        }
+        AppAction::QuickAutoAll => run_blocking(state, JobRequest::QuickAutoAll)?,
+        AppAction::ResolveConflict { key, source } => {
+            let Some(chosen) = state.candidates.choose(&key, source) else {
+                return Err(format!("候補がありません: {key} ({})", source.label()));
             };
-            match result {
-                Ok((next, updated)) => {
-                    if updated > 0 {
-                        state.apply_target_updates_with_history(next);
-                    }
-                    state.dict_status = format!("Quick自動翻訳: updated={updated}");
-                }
-                Err(err) => {
-                    state.dict_status = err.to_string();
-                    return Err(err.to_string());
-                }
+            let mut next = state.entries().to_vec();
+            if let Some(entry) = next.iter_mut().find(|entry| entry.key == key) {
+                entry.target_text = chosen.target;
+            }
+            state.apply_target_updates_with_history("競合を解決", next);
+            state.provenance.insert(key.clone(), chosen.provenance);
+            state.set_file_status(
+                LogLevel::Info,
+                format!("競合を解決しました: {key} ({})", source.label()),
+            );
+        }
+        AppAction::ResolveConflicts(priority) => {
+            let entries = state.entries().to_vec();
+            let (next, resolved) = state.candidates.resolve_by_priority(&entries, &priority);
+            state.apply_target_updates_with_history("競合を一括解決", next);
+            for &(index, provenance) in &resolved {
+                state
+                    .provenance
+                    .insert(entries[index].key.clone(), provenance);
             }
+            state.set_file_status(
+                LogLevel::Info,
+                format!("競合を{}件解決しました", resolved.len()),
+            );
         }
         AppAction::Validate => {
             let Some(entry) = state.selected_entry() else {
                 state.validation_issues.clear();
                 return Ok(());
             };
-            let mut issues = Vec::new();
-            issues.extend(validate_braced_placeholders(
-                &entry.key,
-                &state.edit_source,
-                &state.edit_target,
-            ));
-            issues.extend(validate_printf_placeholders(
-                &entry.key,
-                &state.edit_source,
-                &state.edit_target,
-            ));
-            issues.extend(validate_alias_tags(
-                &entry.key,
-                &state.edit_source,
-                &state.edit_target,
-            ));
-            state.validation_issues = issues;
+            let validator = state.validator()?;
+            state.validation_issues =
+                validator.validate(&entry.key, &state.edit_source, &state.edit_target);
         }
         AppAction::DiffCheck => {
             let Some(entry) = state.selected_entry() else {
@@ -253,14 +1205,15 @@ pub fn dispatch(state: &mut AppState, action: AppAction) -> Result<(), String> {
             state.diff_status = Some(diff.status);
         }
         AppAction::EncodingCheck => {
-            state.encoding_status = match encode(&state.edit_target, Encoding::Latin1)
-                .and_then(|bytes| decode(&bytes, Encoding::Latin1))
+            let encoding = state.text_encoding();
+            state.encoding_status = match encode(&state.edit_target, encoding)
+                .and_then(|bytes| decode(&bytes, encoding))
             {
-                Ok(_) => "Latin1 OK".to_string(),
+                Ok(_) => format!("{encoding:?} OK"),
                 Err(EncodingError::UnrepresentableChar) => {
-                    "Latin1 error: unrepresentable".to_string()
+                    format!("{encoding:?} error: unrepresentable")
                 }
-                Err(EncodingError::InvalidUtf8) => "Latin1 error: invalid utf8".to_string(),
+                Err(EncodingError::InvalidUtf8) => format!("{encoding:?} error: invalid utf8"),
             };
         }
         AppAction::SetDictSourceLang(value) => {
@@ -275,88 +1228,195 @@ pub fn dispatch(state: &mut AppState, action: AppAction) -> Result<(), String> {
             state.dict_root = value;
             state.persist_dictionary_prefs();
         }
+        AppAction::SetPreferences {
+            dictionary,
+            save,
+            mo2,
+            extract_cache,
+        } => {
+            state.dict_source_lang = dictionary.source_lang;
+            state.dict_target_lang = dictionary.target_lang;
+            state.dict_root = dictionary.root;
+            state.dict_match = dictionary.match_options;
+            state.dict_user_path = dictionary.user_path;
+            state.dict_protect_vanilla = dictionary.protect_vanilla;
+            state.dict_apply_on_load = dictionary.apply_on_load;
+            state.persist_dictionary_prefs();
+            state.set_save_prefs(save);
+            state.set_mo2_prefs(mo2);
+            state.set_extract_cache(extract_cache);
+        }
         AppAction::ResetDictLanguagePair => {
             state.reset_dictionary_lang_pair();
         }
+        AppAction::SetValidationConfig(config) => {
+            Validator::new(&config).map_err(|err| format!("検証設定が不正です: {err}"))?;
+            let dictionary_changed =
+                state.validation_config.spell_dictionary != config.spell_dictionary;
+            state.validation_config = config;
+            state.persist_validation_prefs();
+            if dictionary_changed {
+                state.reload_spellchecker();
+            }
+        }
+        AppAction::IgnoreSpelling(word) => {
+            state.add_spell_ignore_word(&word)?;
+            state.set_file_status(LogLevel::Info, format!("無視リストに追加: {}", word.trim()));
+        }
         AppAction::Undo => {
             state.undo();
         }
         AppAction::Redo => {
             state.redo();
         }
+        AppAction::SetGameProfile(profile) => {
+            state.set_game_profile(profile);
+            if state.loaded_esp_strings.is_some() && state.plugin_context.profile != profile {
+                state.set_file_status(LogLevel::Info, format!(
+                    "ゲームプロファイルを{profile}に変更しました（読込中のPluginは{}のまま。再読込で反映）",
+                    state.plugin_context.profile
+                ));
+            }
+        }
+        AppAction::SetPluginLanguage(language) => {
+            let Some(path) = state.loaded_plugin_path.clone() else {
+                return Err("Pluginが読み込まれていません".to_string());
+            };
+            if state.loaded_esp_strings.is_none() {
+                return Err("Strings言語はESP/ESM読込時のみ切り替えられます".to_string());
+            }
+            let targets = state
+                .entries()
+                .iter()
+                .filter(|entry| !entry.target_text.is_empty())
+                .map(|entry| (entry.key.clone(), entry.target_text.clone()))
+                .collect::<HashMap<_, _>>();
+            load_plugin_from_path(state, &path, Some(&language))?;
+            if !targets.is_empty() {
+                let mut entries = state.entries().to_vec();
+                for entry in &mut entries {
+                    if let Some(target) = targets.get(&entry.key) {
+                        entry.target_text = target.clone();
+                    }
+                }
+                state.apply_target_updates_with_history("訳文を引継ぎ", entries);
+            }
+            state.reload_spell_ignore();
+        }
+        AppAction::SetAuthor(author) => {
+            state.set_author(&author);
+        }
+        AppAction::JumpToHistory(position) => {
+            if !state.jump_to_history(position) {
+                return Err("履歴の位置に移動できませんでした".to_string());
+            }
+            state.set_file_status(LogLevel::Info, format!("履歴 {position} に移動しました"));
+        }
+        AppAction::NameCheckpoint(name) => {
+            if !state.history.name_checkpoint(&name) {
+                return Err("名前を付ける操作がありません".to_string());
+            }
+        }
         AppAction::SetActiveTab(tab) => {
             state.active_tab = tab;
         }
         AppAction::SaveOverwrite => {
-            let path = save_overwrite(
-                state.entries(),
-                state.loaded_strings.as_ref(),
-                state.loaded_strings_kind,
-                state.loaded_strings_path.as_deref(),
-                state.loaded_plugin.as_ref(),
-                state.loaded_plugin_path.as_deref(),
-                state.loaded_esp_strings.as_deref(),
-            )?;
-            state.file_status = format!("保存: {}", path.display());
+            run_blocking(state, JobRequest::Save(SaveMode::Overwrite))?;
         }
         AppAction::SaveAsAuto => {
-            let path = save_as(
-                state.entries(),
-                state.loaded_strings.as_ref(),
-                state.loaded_strings_kind,
-                state.loaded_strings_path.as_deref(),
-                state.loaded_plugin.as_ref(),
-                state.loaded_plugin_path.as_deref(),
-                state.loaded_esp_strings.as_deref(),
-                None,
-            )?;
-            state.file_status = format!("別名保存: {}", path.display());
+            run_blocking(state, JobRequest::Save(SaveMode::Auto))?;
         }
         AppAction::SaveAsPath(path) => {
-            let path = save_as(
-                state.entries(),
-                state.loaded_strings.as_ref(),
-                state.loaded_strings_kind,
-                state.loaded_strings_path.as_deref(),
-                state.loaded_plugin.as_ref(),
-                state.loaded_plugin_path.as_deref(),
-                state.loaded_esp_strings.as_deref(),
-                Some(path),
-            )?;
-            state.file_status = format!("別名保存: {}", path.display());
+            run_blocking(state, JobRequest::Save(SaveMode::Path(path)))?;
         }
     }
 
     Ok(())
 }
 
-fn load_strings_from_path(state: &mut AppState, path: &Path) -> Result<(), String> {
-    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
-    let Some(kind) = StringsKind::from_extension(ext) else {
-        let msg = format!("unsupported strings extension: {ext}");
-        state.file_status = msg.clone();
-        return Err(msg);
-    };
-
-    let bytes = std::fs::read(path).map_err(|err| format!("Strings read error: {err}"))?;
-    let parsed = match kind {
-        StringsKind::Strings => read_strings(&bytes),
-        StringsKind::DlStrings => read_dlstrings(&bytes),
-        StringsKind::IlStrings => read_ilstrings(&bytes),
+/// Runs the configured validation over all entries before saving.
+///
+/// Returns a warning message for [`SaveGuard::Warn`], and an error that aborts
+/// the save for [`SaveGuard::Refuse`], when error-level issues remain.
+pub fn check_save_guard(state: &mut AppState) -> Result<Option<String>, String> {
+    let guard = state.validation_config.save_guard;
+    if guard == SaveGuard::Off {
+        return Ok(None);
+    }
+    let validator = state.validator()?;
+    let issues = validator.validate_entries(state.entries().iter().map(|e| {
+        (
+            e.key.as_str(),
+            e.source_text.as_str(),
+            e.target_text.as_str(),
+        )
+    }));
+    let summary = ValidationSummary::from_issues(&issues);
+    state.validation_issues = issues;
+    if summary.errors == 0 {
+        return Ok(None);
+    }
+    let message = format!(
+        "検証エラーが{}件あります (warn={})",
+        summary.errors, summary.warnings
+    );
+    match guard {
+        SaveGuard::Refuse => {
+            state.set_file_status(LogLevel::Warn, format!("保存中止: {message}"));
+            Err(state.file_status.clone())
+        }
+        _ => Ok(Some(message)),
     }
-    .map_err(|err| format!("Strings parse error: {err:?}"))?;
+}
 
-    let entries = parsed
-        .entries
+/// Warns when the plugin's Localized flag disagrees with where the
+/// translated strings will be written (Strings files vs. the plugin itself).
+pub fn plugin_save_warning(
+    info: Option<&PluginInfo>,
+    extracted: Option<&[ExtractedString]>,
+) -> Option<String> {
+    let (info, extracted) = (info?, extracted?);
+    let localized = extracted
         .iter()
-        .map(|e| Entry {
-            key: format!("strings:{}", e.id),
-            source_text: e.text.clone(),
-            target_text: String::new(),
-        })
-        .collect::<Vec<_>>();
+        .filter(|item| matches!(item.storage, StringStorage::Localized { .. }))
+        .count();
+    let inline = extracted.len() - localized;
+    if info.is_localized() && localized == 0 && inline > 0 {
+        return Some(
+            "Localizedフラグ付きですがStringsが読み込まれていないため、訳をプラグインに直接書き込みます"
+                .to_string(),
+        );
+    }
+    if !info.is_localized() && localized > 0 {
+        return Some(format!(
+            "Localizedフラグが無いプラグインに Strings 参照の訳 {localized} 件を保存します"
+        ));
+    }
+    None
+}
 
-    state.set_entries_with_history(entries);
+pub fn with_save_warning(status: String, warning: Option<String>) -> String {
+    match warning {
+        Some(warning) => format!("{status} [警告: {warning}]"),
+        None => status,
+    }
+}
+
+fn read_strings_file(path: &Path) -> Result<(StringsFile, StringsKind), String> {
+    pipeline::read_strings_file(path).map_err(|err| err.to_string())
+}
+
+fn load_strings_from_path(state: &mut AppState, path: &Path) -> Result<(), String> {
+    let (parsed, kind) = match read_strings_file(path) {
+        Ok(read) => read,
+        Err(err) => {
+            state.set_file_status(LogLevel::Error, err.clone());
+            return Err(err);
+        }
+    };
+
+    state.open_document_for(path);
+    state.set_entries_with_history(strings_entries(&parsed));
     state.loaded_strings = Some(parsed);
     state.loaded_strings_kind = Some(kind);
     state.loaded_strings_path = Some(path.to_path_buf());
@@ -364,12 +1424,183 @@ fn load_strings_from_path(state: &mut AppState, path: &Path) -> Result<(), Strin
     state.loaded_plugin = None;
     state.loaded_plugin_path = None;
     state.loaded_esp_strings = None;
+    state.hybrid.clear();
+    state.editor_ids.clear();
+    state.record_index = RecordIndex::default();
+    state.plugin_info = None;
 
-    state.file_status = "Stringsを読み込みました".to_string();
+    let warnings = state
+        .loaded_strings
+        .as_ref()
+        .and_then(StringsFile::info)
+        .map_or(0, |info| info.warnings().len());
+    if warnings > 0 {
+        state.set_file_status(
+            LogLevel::Warn,
+            format!("Stringsを読み込みました（警告 {warnings} 件: ツール > Strings情報 で確認）"),
+        );
+    } else {
+        state.set_file_status(LogLevel::Info, "Stringsを読み込みました".to_string());
+    }
     Ok(())
 }
 
-fn load_plugin_from_path(state: &mut AppState, path: &Path) -> Result<(), String> {
+/// Writes a subset of entries as xtrans XML, or JSON for a `.json` path.
+fn write_partial_export(
+    path: &Path,
+    entries: &[Entry],
+    meta: &EditMetaMap,
+    notes: &NoteMap,
+) -> Result<(), String> {
+    let is_json = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let content = if is_json {
+        export_entries_json_with_meta(entries, meta, notes)
+    } else {
+        export_entries_with_meta(entries, meta, notes)
+    };
+    write_atomic(path, content).map_err(|err| format!("write {}: {err}", path.display()))
+}
+
+/// Loaded ESP and its extracted strings, for writing a converted copy to `output`.
+fn localization_source<'a>(
+    state: &'a AppState,
+    output: &Path,
+) -> Result<(PathBuf, &'a [ExtractedString]), String> {
+    let (Some(plugin_path), Some(extracted)) = (
+        state.loaded_plugin_path.as_deref(),
+        state.loaded_esp_strings.as_deref(),
+    ) else {
+        return Err("Localized変換はESP/ESM読込時のみ実行できます".to_string());
+    };
+    if output == plugin_path {
+        return Err("変換先は読込中のPluginと別のファイルを指定してください".to_string());
+    }
+    Ok((plugin_path.to_path_buf(), extracted))
+}
+
+fn create_translation_package(
+    state: &AppState,
+    request: &PackageRequest,
+) -> Result<PackageReport, String> {
+    let strings_name = state
+        .loaded_strings_path
+        .as_deref()
+        .and_then(Path::file_name)
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let content = match (
+        state.loaded_plugin_path.as_deref(),
+        state.loaded_esp_strings.as_deref(),
+        state.loaded_strings.as_ref(),
+        state.loaded_strings_kind,
+    ) {
+        (Some(input_path), Some(extracted), _, _) if request.dsd => PackageContent::Dsd {
+            input_path,
+            extracted,
+        },
+        (Some(input_path), Some(extracted), _, _) => PackageContent::Plugin {
+            input_path,
+            extracted,
+            context: &state.plugin_context,
+        },
+        (_, _, Some(_), Some(_)) if request.dsd => {
+            return Err("DSD JSONはESP/ESM読込時のみ書き出せます".to_string());
+        }
+        (_, _, Some(base), Some(kind)) => PackageContent::Strings {
+            base,
+            kind,
+            file_name: &strings_name,
+        },
+        _ => return Err("翻訳パッケージはESP/ESMかStringsの読込時のみ作成できます".to_string()),
+    };
+    let mut params = xtranslator_params(state);
+    params.dest_lang = request.manifest.language.clone();
+    let xml = export_entries_xtranslator(&state.export_entries(), &params);
+    create_package(
+        &request.path,
+        state.entries(),
+        content,
+        &xml,
+        &request.manifest,
+    )
+    .map_err(|err| err.to_string())
+}
+
+pub fn xtranslator_params(state: &AppState) -> XtranslatorExportParams {
+    let addon = loaded_file_name(state).unwrap_or_default();
+    let params =
+        XtranslatorExportParams::new(&addon, &state.dict_source_lang, &state.dict_target_lang);
+    match state.loaded_esp_strings.as_deref() {
+        Some(strings) => params.with_extracted(strings),
+        None => params,
+    }
+}
+
+/// Sets `target` on the rows with `source` and refreshes the editor when
+/// the selected row is one of them.
+fn set_duplicate_target(
+    state: &mut AppState,
+    source: &str,
+    target: &str,
+    overwrite: bool,
+) -> Result<usize, String> {
+    let changed = state
+        .set_duplicate_target(source, target, overwrite)
+        .ok_or_else(|| format!("原文が見つかりません: {source}"))?;
+    if let Some(entry) = state
+        .selected_entry()
+        .filter(|entry| entry.source_text == source)
+    {
+        state.edit_target = entry.target_text.to_string();
+    }
+    Ok(changed)
+}
+
+/// Logs the unmatched keys of applied corrections under `what` and reports
+/// the counts after `done`.
+fn report_corrections(state: &mut AppState, what: &str, done: &str, report: &CorrectionReport) {
+    for key in &report.unmatched {
+        log::emit(
+            LogLevel::Warn,
+            &format!("{what}: 該当する行がありません: {key}"),
+        );
+    }
+    let level = if report.unmatched.is_empty() {
+        LogLevel::Info
+    } else {
+        LogLevel::Warn
+    };
+    state.set_file_status(
+        level,
+        format!(
+            "{done}: 更新 {} / 変更なし {} / 不一致 {}",
+            report.updated,
+            report.unchanged,
+            report.unmatched.len()
+        ),
+    );
+}
+
+/// File name of the loaded plugin, or else of the loaded Strings file.
+fn loaded_file_name(state: &AppState) -> Option<String> {
+    state
+        .loaded_plugin_path
+        .as_deref()
+        .or(state.loaded_strings_path.as_deref())
+        .and_then(|path| path.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+/// Loads an .esp/.esm/.esl (or xtplugin); `language` picks the Strings files
+/// of a localized plugin, auto-detected when `None`.
+fn load_plugin_from_path(
+    state: &mut AppState,
+    path: &Path,
+    language: Option<&str>,
+) -> Result<(), String> {
     let ext = path
         .extension()
         .and_then(|e| e.to_str())
@@ -379,16 +1610,16 @@ fn load_plugin_from_path(state: &mut AppState, path: &Path) -> Result<(), String
     if ext == "xtplugin" {
         let content =
             std::fs::read_to_string(path).map_err(|err| format!("xtplugin read error: {err}"))?;
-        let plugin =
-            read_plugin(&content).map_err(|err| format!("xtplugin parse error: {err:?}"))?;
+        let plugin = read_plugin(&content).map_err(|err| format!("xtplugin parse error: {err}"))?;
+        state.open_document_for(path);
 
         let entries = plugin
             .entries
             .iter()
             .map(|e| Entry {
                 key: format!("plugin:{}", e.id),
-                source_text: e.source_text.clone(),
-                target_text: String::new(),
+                source_text: e.source_text.as_str().into(),
+                target_text: Default::default(),
             })
             .collect::<Vec<_>>();
 
@@ -396,89 +1627,426 @@ fn load_plugin_from_path(state: &mut AppState, path: &Path) -> Result<(), String
         state.loaded_plugin = Some(plugin);
         state.loaded_plugin_path = Some(path.to_path_buf());
         state.loaded_esp_strings = None;
+        state.hybrid.clear();
+        state.editor_ids.clear();
+        state.record_index = RecordIndex::default();
+        state.plugin_info = None;
         state.loaded_strings = None;
         state.loaded_strings_kind = None;
         state.loaded_strings_path = None;
-        state.file_status = "xtpluginを読み込みました".to_string();
+        state.set_file_status(LogLevel::Info, "xtpluginを読み込みました".to_string());
         return Ok(());
     }
 
-    let bytes = std::fs::read(path).map_err(|err| format!("plugin read error: {err}"))?;
-    let workspace_root = workspace_root_from_plugin(path);
-    let entries = match extract_esp_strings(path, &workspace_root, Some("english")) {
-        Ok(strings) => {
-            state.loaded_esp_strings = Some(strings.clone());
-            strings
-                .iter()
-                .map(|s| Entry {
-                    key: s.get_unique_key(),
-                    source_text: s.text.clone(),
-                    target_text: String::new(),
-                })
-                .collect::<Vec<_>>()
+    let loaded = read_esp_plugin(
+        path,
+        state.game_profile,
+        state.mo2_prefs(),
+        language,
+        state.plugin_cache().as_ref(),
+        &CancelToken::new(),
+        &NoProgress,
+    )?;
+    apply_loaded_plugin(state, loaded);
+    Ok(())
+}
+
+/// Fills the targets of the active plugin's rows that `source` defines by
+/// form id and subrecord. Texts equal to the row's source are not
+/// translations and are skipped.
+fn copy_targets_from(state: &mut AppState, source: &TargetSource) -> Result<(), String> {
+    let plugin_of = |path: &Path, info: Option<&PluginInfo>| LoadOrderPlugin {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        masters: info.map(|info| info.masters.clone()).unwrap_or_default(),
+    };
+    let Some(path) = state.loaded_plugin_path.as_deref() else {
+        return Err("Pluginが読み込まれていません".to_string());
+    };
+    let plugin = plugin_of(path, state.plugin_info.as_ref());
+    // (source, text) of each row of the other plugin; a reference file's
+    // text is its translation and has no source to transfer placeholders from.
+    let (matches, texts) = match source {
+        TargetSource::Document(index) => {
+            let open = state.open_plugins();
+            let Some(other) = open
+                .iter()
+                .find(|open| open.document == *index && *index != state.active_document())
+            else {
+                return Err("コピー元のPluginタブがありません".to_string());
+            };
+            let texts = other
+                .entries
+                .iter()
+                .map(|entry| (Some(entry.source_text.clone()), entry.target_text.clone()))
+                .collect::<Vec<_>>();
+            let other_plugin = LoadOrderPlugin {
+                name: other.name.clone(),
+                masters: other.masters.to_vec(),
+            };
+            let matches = match_fields(&plugin, state.entries(), &other_plugin, other.entries);
+            (matches, texts)
+        }
+        TargetSource::Reference(path) => {
+            let loaded = read_esp_plugin(
+                path,
+                state.game_profile,
+                state.mo2_prefs(),
+                None,
+                state.plugin_cache().as_ref(),
+                &CancelToken::new(),
+                &NoProgress,
+            )?;
+            let other = plugin_of(path, loaded.info.as_ref());
+            let matches = match_fields(&plugin, state.entries(), &other, &loaded.entries);
+            let texts = loaded
+                .entries
+                .into_iter()
+                .map(|entry| (None, entry.source_text))
+                .collect();
+            (matches, texts)
+        }
+    };
+    let matched = matches.iter().flatten().count();
+    let unmatched = matches.len() - matched;
+    let mut next = state.entries().to_vec();
+    for (entry, found) in next.iter_mut().zip(&matches) {
+        let Some((from_source, text)) = found.map(|index| &texts[index]) else {
+            continue;
+        };
+        if text.is_empty() || *text == entry.source_text {
+            continue;
         }
+        entry.target_text = match from_source {
+            Some(from_source) => placeholders::transfer(text, from_source, &entry.source_text)
+                .map(Into::into)
+                .unwrap_or_else(|| text.clone()),
+            None => text.clone(),
+        };
+    }
+    let changed = next
+        .iter()
+        .zip(state.entries())
+        .filter(|(after, before)| after.target_text != before.target_text)
+        .map(|(after, _)| after.key.clone())
+        .collect::<Vec<_>>();
+    let updated = state.apply_target_updates_with_history("別プラグインからコピー", next);
+    for key in &changed {
+        state.provenance.remove(key);
+    }
+    state.set_file_status(
+        LogLevel::Info,
+        format!(
+            "別プラグインから訳文をコピー: 一致 {matched} / 不一致 {unmatched} / 更新 {updated}"
+        ),
+    );
+    Ok(())
+}
+
+/// ESP/ESM/ESL contents read off the UI thread by [`read_esp_plugin`].
+pub struct LoadedPlugin {
+    path: PathBuf,
+    entries: Vec<Entry>,
+    extracted: Option<Vec<ExtractedString>>,
+    /// Errors of the records left out of a partial extraction.
+    skipped: Vec<String>,
+    /// Parse error that made the loader fall back to raw string scanning.
+    fallback_error: Option<String>,
+    info: Option<PluginInfo>,
+    languages: Vec<String>,
+    context: PluginContext,
+}
+
+/// Reads and extracts a plugin without touching the app state. Returns
+/// `Err` only for unreadable files or when `cancel` is canceled. Records
+/// that fail to parse are skipped; only when nothing parses does the loader
+/// fall back to scanning null-terminated strings.
+pub(crate) fn read_esp_plugin(
+    path: &Path,
+    profile: GameProfile,
+    mo2: &Mo2Prefs,
+    language: Option<&str>,
+    cache: Option<&ExtractCache>,
+    cancel: &CancelToken,
+    progress: &dyn ProgressSink,
+) -> Result<LoadedPlugin, String> {
+    let bytes = std::fs::read(path).map_err(|err| format!("plugin read error: {err}"))?;
+    let context = mo2
+        .enabled
+        .then(|| PluginContext::resolve_mo2(path, Some(&mo2.profile), Some(profile), language))
+        .and_then(|resolved| match resolved {
+            Ok(context) => Some(context),
+            // Not under an MO2 instance: read it like any other plugin.
+            Err(PipelineError::Mo2(Mo2Error::NotInMods(_))) => None,
+            Err(err) => {
+                log::emit(LogLevel::Warn, &format!("MO2連携を使えません: {err}"));
+                None
+            }
+        });
+    let context = context.unwrap_or_else(|| {
+        let workspace_root = workspace_root_from_plugin(path);
+        PluginContext::resolve(path, Some(&workspace_root), Some(profile), language).unwrap_or_else(
+            |_| PluginContext {
+                workspace_root,
+                profile,
+                ..PluginContext::default()
+            },
+        )
+    });
+    let languages = plugin_languages(path, context.strings_location(), profile).unwrap_or_default();
+    let loaded = match cache {
+        Some(cache) => load_plugin_entries_cached(path, &context, cache, cancel, progress).map(
+            |(entries, strings, status)| {
+                match status {
+                    CacheStatus::Hit => log::emit(
+                        LogLevel::Info,
+                        &format!("抽出キャッシュを使用しました: {}", path.display()),
+                    ),
+                    CacheStatus::Stored => {}
+                    CacheStatus::StoreFailed(err) => log::emit(
+                        LogLevel::Warn,
+                        &format!("抽出キャッシュを書き込めません: {err}"),
+                    ),
+                }
+                (entries, strings)
+            },
+        ),
+        None => load_plugin_entries_cancelable(path, &context, cancel, progress),
+    };
+    let partial = match loaded {
+        Ok(loaded) => Ok((loaded, Vec::new())),
+        Err(_) if cancel.is_canceled() => return Err(Canceled.to_string()),
+        Err(err) => match load_plugin_entries_lenient(path, &context, cancel, progress) {
+            Ok((entries, partial)) if !partial.strings.is_empty() => Ok((
+                (entries, partial.strings),
+                partial.skipped.iter().map(ToString::to_string).collect(),
+            )),
+            Err(_) if cancel.is_canceled() => return Err(Canceled.to_string()),
+            _ => Err(err),
+        },
+    };
+    let (entries, extracted, skipped, fallback_error) = match partial {
+        Ok(((entries, strings), skipped)) => (entries, Some(strings), skipped, None),
         Err(err) => {
-            state.file_status = format!("ESP parse error (fallback): {err}");
-            extract_null_terminated_utf8(&bytes, 4)
+            let entries = extract_null_terminated_utf8(&bytes, 4)
                 .into_iter()
                 .map(|x| Entry {
                     key: format!("plugin:{:08x}", x.offset),
-                    source_text: x.text,
-                    target_text: String::new(),
+                    source_text: x.text.into(),
+                    target_text: Default::default(),
                 })
-                .collect::<Vec<_>>()
+                .collect::<Vec<_>>();
+            (entries, None, Vec::new(), Some(err.to_string()))
         }
     };
+    Ok(LoadedPlugin {
+        path: path.to_path_buf(),
+        entries,
+        extracted,
+        skipped,
+        fallback_error,
+        info: read_plugin_info(path).ok(),
+        languages,
+        context,
+    })
+}
 
+pub(crate) fn apply_loaded_plugin(state: &mut AppState, loaded: LoadedPlugin) {
+    let LoadedPlugin {
+        path,
+        entries,
+        extracted,
+        skipped,
+        fallback_error,
+        info,
+        languages,
+        context,
+    } = loaded;
+    state.open_document_for(&path);
+    if let Some(err) = fallback_error {
+        state.set_file_status(LogLevel::Warn, format!("ESP parse error (fallback): {err}"));
+    }
+    for err in &skipped {
+        log::emit(
+            LogLevel::Warn,
+            &format!("解析できないレコードをスキップしました: {err}"),
+        );
+    }
+    state.plugin_languages = languages;
+    state.hybrid = extracted
+        .as_deref()
+        .map(group_hybrid_records)
+        .unwrap_or_default();
+    state.editor_ids = extracted.as_deref().map(editor_ids).unwrap_or_default();
+    state.record_index = extracted
+        .as_deref()
+        .map(RecordIndex::build)
+        .unwrap_or_default();
+    state.loaded_esp_strings = extracted;
     state.set_entries_with_history(entries);
+    state.plugin_info = info;
     state.loaded_plugin = None;
-    state.loaded_plugin_path = Some(path.to_path_buf());
+    state.loaded_plugin_path = Some(path);
     state.loaded_strings = None;
     state.loaded_strings_kind = None;
     state.loaded_strings_path = None;
-    state.file_status = "Pluginを読み込みました".to_string();
-    Ok(())
-}
-
-fn apply_xml_to_current(state: &mut AppState, contents: String) -> Result<(), String> {
-    state.xml_text = contents.clone();
-    let current_entries = state.entries().to_vec();
-    let (merged, stats) = apply_xml_payload(&current_entries, &contents)?;
-    if stats.updated > 0 {
-        state.apply_target_updates_with_history(merged);
+    let profile = context.profile;
+    let localized = state
+        .plugin_info
+        .as_ref()
+        .is_some_and(|info| info.is_localized());
+    let loaded = if localized {
+        format!("Pluginを読み込みました（{profile} / {}）", context.language)
+    } else {
+        format!("Pluginを読み込みました（{profile}）")
+    };
+    let loaded = if skipped.is_empty() {
+        loaded
+    } else {
+        format!(
+            "{loaded} / 解析できないレコード {} 件をスキップしました（詳細はログ）",
+            skipped.len()
+        )
+    };
+    match state.plugin_info.as_ref().map(GameProfile::detect) {
+        Some(detected) if detected != profile => state.set_file_status(
+            LogLevel::Warn,
+            format!("{loaded} / ヘッダは{detected}形式です。オプション > ゲームを確認してください"),
+        ),
+        _ if !skipped.is_empty() => state.set_file_status(LogLevel::Warn, loaded),
+        _ => state.set_file_status(LogLevel::Info, loaded),
     }
-    state.file_status = format!(
-        "XML適用: updated={} unchanged={} missing={}",
-        stats.updated, stats.unchanged, stats.missing
-    );
-    state.last_xml_stats = Some(stats);
-    state.xml_error = None;
-    Ok(())
+    state.plugin_context = context;
 }
 
+/// Quick AutoTranslate of the selected row: the reference translation fills
+/// it first, then the dictionary. Also returns their proposals for the row,
+/// which are kept as candidates even when the row already had a translation
+/// and was left alone.
 pub(crate) fn apply_quick_auto_selection(
+    reference: Option<&TranslationDictionary>,
     dict: Option<&TranslationDictionary>,
     entries: &[Entry],
     selected_key: Option<String>,
-) -> Result<(Vec<Entry>, usize), &'static str> {
-    let Some(dict) = dict else {
+    options: MatchOptions,
+) -> Result<(Vec<Entry>, usize, Proposals), &'static str> {
+    if reference.is_none() && dict.is_none() {
         return Err("辞書未構築");
-    };
+    }
     let Some(selected_key) = selected_key else {
         return Err("Quick自動翻訳対象の行を選択してください");
     };
-    let selected = vec![selected_key];
-    Ok(dict.apply_quick(entries, &selected, true))
+    apply_quick_auto_keys(reference, dict, entries, &[selected_key], options)
 }
 
-fn apply_xml_payload(
+/// [`apply_quick_auto_selection`] of the rows with `keys`.
+pub(crate) fn apply_quick_auto_keys(
+    reference: Option<&TranslationDictionary>,
+    dict: Option<&TranslationDictionary>,
+    entries: &[Entry],
+    keys: &[String],
+    options: MatchOptions,
+) -> Result<(Vec<Entry>, usize, Proposals), &'static str> {
+    if reference.is_none() && dict.is_none() {
+        return Err("辞書未構築");
+    }
+    if keys.is_empty() {
+        return Err("Quick自動翻訳の対象になる未訳の行がありません");
+    }
+    let (mut next, mut proposals) = match reference {
+        Some(reference) => reference.apply_reference_with_proposals(entries, keys, true),
+        None => (entries.to_vec(), Vec::new()),
+    };
+    if let Some(dict) = dict {
+        let (filled, more) = dict.apply_quick_with_proposals(&next, keys, true, options);
+        next = filled;
+        proposals.extend(more);
+    }
+    let updated = proposals.iter().filter(|proposal| proposal.applied).count();
+    Ok((next, updated, keyed_proposals(entries, proposals)))
+}
+
+/// With [`DictionaryPrefs::apply_on_load`] on, fills the untranslated rows
+/// of the file just loaded from exact dictionary matches, as one undoable
+/// edit whose rows go to the review queue.
+pub(crate) fn auto_translate_loaded(state: &mut AppState) {
+    if !state.dict_apply_on_load {
+        return;
+    }
+    let Some(dict) = state.dict.as_ref() else {
+        return;
+    };
+    let (mut next, proposals) =
+        dict.apply_quick_with_proposals(state.entries(), &[], true, MatchOptions::EXACT);
+    let mut proposals = keyed_proposals(state.entries(), proposals);
+    state.keep_vanilla_targets(&mut next, &mut proposals);
+    state.rewrite_auto_targets(&mut next, &mut proposals);
+    let updated = proposals.iter().filter(|(_, p)| p.applied).count();
+    if updated > 0 {
+        state.apply_target_updates_with_history("読み込み時の辞書適用", next);
+    }
+    state.propose_targets(proposals);
+    state.set_dict_status(
+        LogLevel::Info,
+        format!("読み込み時の辞書適用: updated={updated}"),
+    );
+}
+
+/// XML or dictionary matches with the key of the row each one is for.
+pub(crate) type Proposals = Vec<(String, Proposal)>;
+
+fn keyed_proposals(entries: &[Entry], proposals: Vec<Proposal>) -> Proposals {
+    proposals
+        .into_iter()
+        .map(|proposal| (entries[proposal.index].key.clone(), proposal))
+        .collect()
+}
+
+/// An XML/JSON payload merged into the current entries: merged entries,
+/// stats, edit metadata, notes and proposals.
+pub(crate) type AppliedXml = (Vec<Entry>, XmlApplyStats, EditMetaMap, NoteMap, Proposals);
+
+/// Merges an XML/JSON payload into `current`. The returned edit metadata
+/// only covers rows whose merged target equals the imported target; the
+/// notes cover rows with the same key and the proposals every matched row,
+/// applied or not.
+pub(crate) fn apply_xml_payload(
     current: &[Entry],
     xml_contents: &str,
-) -> Result<(Vec<Entry>, XmlApplyStats), String> {
-    let imported = import_entries(xml_contents).map_err(|err| format!("{err:?}"))?;
-    Ok(apply_xml_default(current, &imported))
+    profile: ApplyProfile,
+    cancel: &CancelToken,
+    progress: &dyn ProgressSink,
+) -> Result<AppliedXml, String> {
+    let (imported, mut meta, mut notes) =
+        import_entries_any_with_meta(xml_contents).map_err(|err| err.to_string())?;
+    let (merged, stats, proposals) =
+        apply_xml_with_proposals_cancelable(current, &imported, profile, cancel, progress)
+            .map_err(|err| err.to_string())?;
+    let proposals = keyed_proposals(current, proposals);
+    if !meta.is_empty() {
+        let imported_targets: HashMap<&str, &str> = imported
+            .iter()
+            .map(|entry| (entry.key.as_str(), entry.target_text.as_str()))
+            .collect();
+        let merged_targets: HashMap<&str, &str> = merged
+            .iter()
+            .map(|entry| (entry.key.as_str(), entry.target_text.as_str()))
+            .collect();
+        meta.retain(|key, _| {
+            let imported = imported_targets.get(key.as_str());
+            imported.is_some() && merged_targets.get(key.as_str()) == imported
+        });
+    }
+    if !notes.is_empty() {
+        let keys: HashSet<&str> = current.iter().map(|entry| entry.key.as_str()).collect();
+        notes.retain(|key, _| keys.contains(key.as_str()));
+    }
+    Ok((merged, stats, meta, notes, proposals))
 }
 
+#[allow(clippy::too_many_arguments)]
 fn save_overwrite(
     entries: &[Entry],
     loaded_strings: Option<&StringsFile>,
@@ -487,29 +2055,42 @@ fn save_overwrite(
     loaded_plugin: Option<&PluginFile>,
     loaded_plugin_path: Option<&Path>,
     loaded_esp_strings: Option<&[ExtractedString]>,
-) -> Result<PathBuf, String> {
+    context: &PluginContext,
+    backup: BackupPolicy,
+) -> Result<SaveReport, String> {
     if let Some(plugin_path) = loaded_plugin_path {
         if let Some(extracted) = loaded_esp_strings {
-            return save_esp(entries, plugin_path, plugin_path, extracted);
+            return save_esp(
+                entries,
+                plugin_path,
+                plugin_path,
+                extracted,
+                context,
+                backup,
+            );
         }
         if let Some(plugin) = loaded_plugin {
-            ensure_backup(&plugin_path)?;
-            let encoded = write_plugin(&plugin).map_err(|e| format!("{e:?}"))?;
-            std::fs::write(&plugin_path, encoded)
+            check_writable(plugin_path)
+                .map_err(|block| format!("{}: {}", plugin_path.display(), block.label()))?;
+            backup_with_policy(plugin_path, backup, now_unix_seconds())
+                .map_err(|e| e.to_string())?;
+            let encoded = write_plugin(plugin).map_err(|e| e.to_string())?;
+            write_atomic(plugin_path, &encoded)
                 .map_err(|e| format!("plugin save {}: {e}", plugin_path.display()))?;
-            return Ok(plugin_path.to_path_buf());
+            return plugin_report(entries, plugin_path);
         }
     }
 
     if let (Some(strings), Some(kind), Some(path)) =
         (loaded_strings, loaded_strings_kind, loaded_strings_path)
     {
-        return save_strings(entries, &strings, kind, &path);
+        return save_strings(entries, strings, kind, path, backup);
     }
 
     Err("保存対象がありません".to_string())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn save_as(
     entries: &[Entry],
     loaded_strings: Option<&StringsFile>,
@@ -518,229 +2099,150 @@ fn save_as(
     loaded_plugin: Option<&PluginFile>,
     loaded_plugin_path: Option<&Path>,
     loaded_esp_strings: Option<&[ExtractedString]>,
+    context: &PluginContext,
+    backup: BackupPolicy,
     output_override: Option<PathBuf>,
-) -> Result<PathBuf, String> {
+) -> Result<SaveReport, String> {
     if let Some(plugin_path) = loaded_plugin_path {
         if let Some(extracted) = loaded_esp_strings {
             let out =
-                output_override.unwrap_or_else(|| with_suffix_path(&plugin_path, "_translated"));
-            return save_esp(entries, &plugin_path, &out, extracted);
+                output_override.unwrap_or_else(|| with_suffix_path(plugin_path, "_translated"));
+            return save_esp(entries, plugin_path, &out, extracted, context, backup);
         }
         if let Some(plugin) = loaded_plugin {
             let out =
-                output_override.unwrap_or_else(|| with_suffix_path(&plugin_path, "_translated"));
-            let encoded = write_plugin(&plugin).map_err(|e| format!("{e:?}"))?;
-            std::fs::write(&out, encoded)
+                output_override.unwrap_or_else(|| with_suffix_path(plugin_path, "_translated"));
+            check_writable(&out)
+                .map_err(|block| format!("{}: {}", out.display(), block.label()))?;
+            let encoded = write_plugin(plugin).map_err(|e| e.to_string())?;
+            write_atomic(&out, &encoded)
                 .map_err(|e| format!("plugin save {}: {e}", out.display()))?;
-            return Ok(out);
+            return plugin_report(entries, &out);
         }
     }
 
     if let (Some(strings), Some(kind), Some(path)) =
         (loaded_strings, loaded_strings_kind, loaded_strings_path)
     {
-        let out = output_override.unwrap_or_else(|| with_suffix_path(&path, "_translated"));
-        return save_strings(entries, &strings, kind, &out);
+        let out = output_override.unwrap_or_else(|| with_suffix_path(path, "_translated"));
+        return save_strings(entries, strings, kind, &out, backup);
     }
 
     Err("保存対象がありません".to_string())
 }
 
-fn save_strings(
-    entries: &[Entry],
-    base: &StringsFile,
-    kind: StringsKind,
-    path: &Path,
-) -> Result<PathBuf, String> {
-    if path.exists() {
-        ensure_backup(path)?;
-    }
-    let updated = apply_entries_to_strings(base, entries);
-    let bytes = match kind {
-        StringsKind::Strings => write_strings(&updated),
-        StringsKind::DlStrings => write_dlstrings(&updated),
-        StringsKind::IlStrings => write_ilstrings(&updated),
-    }
-    .map_err(|e| format!("{e:?}"))?;
-    std::fs::write(path, bytes).map_err(|e| format!("write {}: {e}", path.display()))?;
-    Ok(path.to_path_buf())
-}
-
-fn save_esp(
-    entries: &[Entry],
-    input_path: &Path,
-    output_path: &Path,
-    extracted: &[ExtractedString],
-) -> Result<PathBuf, String> {
-    if input_path == output_path && input_path.exists() {
-        ensure_backup(input_path)?;
-    }
-
-    let mut targets: HashMap<&str, &str> = HashMap::new();
-    for entry in entries {
-        if !entry.target_text.is_empty() {
-            targets.insert(entry.key.as_str(), entry.target_text.as_str());
-        }
-    }
-
-    let mut translated = extracted.to_vec();
-    for item in &mut translated {
-        let key = item.get_unique_key();
-        if let Some(target) = targets.get(key.as_str()) {
-            item.text = (*target).to_string();
-        }
-    }
-
-    let out_dir = output_path.parent().unwrap_or_else(|| Path::new("."));
-    let workspace_root = workspace_root_from_plugin(input_path);
-    let written = apply_translations(
-        input_path,
-        &workspace_root,
-        out_dir,
-        translated,
-        Some("english"),
-    )
-    .map_err(|e| format!("esp apply failed {}: {e}", input_path.display()))?;
-
-    if written == output_path {
-        return Ok(written);
-    }
-
-    std::fs::copy(&written, output_path).map_err(|e| {
-        format!(
-            "copy {} -> {} failed: {e}",
-            written.display(),
-            output_path.display()
-        )
-    })?;
-    Ok(output_path.to_path_buf())
+/// Report of an `.xtplugin` written to `path`.
+fn plugin_report(entries: &[Entry], path: &Path) -> Result<SaveReport, String> {
+    let plan = SavePlan {
+        files: vec![PlannedFile::write(path, changed_rows(entries))],
+    };
+    SaveReport::from_plan(path, &plan).map_err(|e| e.to_string())
 }
 
-fn apply_entries_to_strings(base: &StringsFile, entries: &[Entry]) -> StringsFile {
-    let mut by_id: HashMap<u32, &str> = HashMap::new();
-    for entry in entries {
-        if let Some(id) = parse_strings_id(&entry.key) {
-            if !entry.target_text.is_empty() {
-                by_id.insert(id, entry.target_text.as_str());
-            }
-        }
-    }
-    let out = base
-        .entries
+/// Rows whose target replaces the source text.
+fn changed_rows(entries: &[Entry]) -> usize {
+    entries
         .iter()
-        .map(|entry| {
-            if let Some(target) = by_id.get(&entry.id) {
-                StringsEntry {
-                    id: entry.id,
-                    text: (*target).to_string(),
-                }
-            } else {
-                entry.clone()
-            }
-        })
-        .collect::<Vec<_>>();
-    StringsFile { entries: out }
+        .filter(|entry| !entry.target_text.is_empty() && entry.target_text != entry.source_text)
+        .count()
 }
 
-fn parse_strings_id(key: &str) -> Option<u32> {
-    let (_, id) = key.rsplit_once(':')?;
-    id.parse::<u32>().ok()
-}
-
-fn ensure_backup(path: &Path) -> Result<(), String> {
-    if !path.exists() {
-        return Ok(());
+/// Writes the report's manifest when `write_manifest` is set and returns the
+/// saved file.
+fn finish_save(report: SaveReport, write_manifest: bool) -> Result<PathBuf, String> {
+    if write_manifest {
+        report.write_manifest().map_err(|e| e.to_string())?;
     }
-    let backup = next_backup_path(path);
-    std::fs::copy(path, &backup).map_err(|e| {
-        format!(
-            "backup failed {} -> {}: {e}",
-            path.display(),
-            backup.display()
-        )
-    })?;
-    Ok(())
+    Ok(report.output)
 }
 
-fn next_backup_path(path: &Path) -> PathBuf {
-    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
-    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
-    let parent = path.parent().unwrap_or_else(|| Path::new("."));
-
-    for i in 0usize..1000usize {
-        let name = if i == 0 {
-            if ext.is_empty() {
-                format!("{stem}.bak")
-            } else {
-                format!("{stem}.bak.{ext}")
-            }
-        } else if ext.is_empty() {
-            format!("{stem}.bak{i}")
-        } else {
-            format!("{stem}.bak{i}.{ext}")
-        };
-        let p = parent.join(name);
-        if !p.exists() {
-            return p;
-        }
+/// The loaded file `backup` belongs to; only backups [`list_backups`] finds
+/// for it can be restored or deleted.
+fn listed_backup_owner(state: &AppState, backup: &Path) -> Result<PathBuf, String> {
+    let file = state
+        .session_file()
+        .ok_or_else(|| "ファイルが読み込まれていません".to_string())?
+        .to_path_buf();
+    let listed = list_backups(&file).map_err(|err| err.to_string())?;
+    if !listed.iter().any(|found| found.path == backup) {
+        return Err(format!(
+            "{} は {} のバックアップではありません",
+            backup.display(),
+            file.display()
+        ));
     }
-
-    with_suffix_path(path, ".bak999")
+    Ok(file)
 }
 
-fn with_suffix_path(path: &Path, suffix: &str) -> PathBuf {
-    let stem = path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("output");
-    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
-    let file = if ext.is_empty() {
-        format!("{stem}{suffix}")
-    } else {
-        format!("{stem}{suffix}.{ext}")
-    };
-    path.parent().unwrap_or_else(|| Path::new(".")).join(file)
+fn save_strings(
+    entries: &[Entry],
+    base: &StringsFile,
+    kind: StringsKind,
+    path: &Path,
+    backup: BackupPolicy,
+) -> Result<SaveReport, String> {
+    backup_with_policy(path, backup, now_unix_seconds()).map_err(|e| e.to_string())?;
+    pipeline::save_strings(entries, base, kind, path).map_err(|e| e.to_string())
 }
 
-fn workspace_root_from_plugin(path: &Path) -> PathBuf {
-    let Some(parent) = path.parent() else {
-        return PathBuf::from(".");
+/// Backs up the plugin when saving over it.
+fn save_esp(
+    entries: &[Entry],
+    input_path: &Path,
+    output_path: &Path,
+    extracted: &[ExtractedString],
+    context: &PluginContext,
+    backup: BackupPolicy,
+) -> Result<SaveReport, String> {
+    // Overwriting updates the Strings files in place; a copy keeps its own
+    // next to it.
+    let output_root = if input_path == output_path {
+        backup_with_policy(input_path, backup, now_unix_seconds()).map_err(|e| e.to_string())?;
+        None
+    } else {
+        Some(output_path.parent().unwrap_or_else(|| Path::new(".")))
     };
-    let is_data_dir = parent
-        .file_name()
-        .and_then(|name| name.to_str())
-        .map(|name| name.eq_ignore_ascii_case("Data"))
-        .unwrap_or(false);
-    if is_data_dir {
-        if let Some(root) = parent.parent() {
-            return root.to_path_buf();
-        }
-    }
-    parent.to_path_buf()
+    pipeline::save_esp(
+        entries,
+        input_path,
+        output_path,
+        output_root,
+        extracted,
+        context,
+    )
+    .map_err(|e| e.to_string())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::columns::{EntryColumn, EntrySort};
+    use xt_core::candidates::Provenance;
+    use xt_core::formats::strings::{write_strings, StringsEntry};
+    use xt_core::import_export::{export_entries, import_entries, import_entries_json};
+    use xt_core::model::EditMeta;
+    use xt_core::pipeline::{
+        apply_entries_to_strings, next_backup_path, parse_strings_id, OutputTemplate, PlannedChange,
+    };
+    use xt_core::review_sheet::RowStatus;
+    use xt_core::validation::{Severity, ValidationIssue};
 
     #[test]
     fn t_app_001_apply_entries_to_strings_updates_target() {
-        let base = StringsFile {
-            entries: vec![
-                StringsEntry {
-                    id: 1,
-                    text: "Iron Sword".to_string(),
-                },
-                StringsEntry {
-                    id: 2,
-                    text: "Steel Sword".to_string(),
-                },
-            ],
-        };
+        let base = StringsFile::new(vec![
+            StringsEntry {
+                id: 1,
+                text: "Iron Sword".to_string(),
+            },
+            StringsEntry {
+                id: 2,
+                text: "Steel Sword".to_string(),
+            },
+        ]);
         let entries = vec![Entry {
             key: "strings:1".to_string(),
-            source_text: "Iron Sword".to_string(),
-            target_text: "鉄の剣".to_string(),
+            source_text: "Iron Sword".into(),
+            target_text: "鉄の剣".into(),
         }];
         let updated = apply_entries_to_strings(&base, &entries);
         assert_eq!(updated.entries[0].text, "鉄の剣");
@@ -771,16 +2273,17 @@ mod tests {
     fn t_app_005_quick_auto_requires_selection() {
         let entries = vec![Entry {
             key: "k1".to_string(),
-            source_text: "Iron Sword".to_string(),
-            target_text: String::new(),
+            source_text: "Iron Sword".into(),
+            target_text: Default::default(),
         }];
         let dict = TranslationDictionary::build_from_entries(&[Entry {
             key: "d".to_string(),
-            source_text: "Iron Sword".to_string(),
-            target_text: "鉄の剣".to_string(),
+            source_text: "Iron Sword".into(),
+            target_text: "鉄の剣".into(),
         }]);
         let err =
-            apply_quick_auto_selection(Some(&dict), &entries, None).expect_err("selection error");
+            apply_quick_auto_selection(None, Some(&dict), &entries, None, MatchOptions::EXACT)
+                .expect_err("selection error");
         assert_eq!(err, "Quick自動翻訳対象の行を選択してください");
     }
 
@@ -788,17 +2291,1757 @@ mod tests {
     fn t_app_006_apply_xml_payload_updates_entry() {
         let current = vec![Entry {
             key: "k1".to_string(),
-            source_text: "Iron Sword".to_string(),
-            target_text: String::new(),
+            source_text: "Iron Sword".into(),
+            target_text: Default::default(),
         }];
         let xml = export_entries(&[Entry {
             key: "k1".to_string(),
-            source_text: "Iron Sword".to_string(),
-            target_text: "鉄の剣".to_string(),
+            source_text: "Iron Sword".into(),
+            target_text: "鉄の剣".into(),
         }]);
-        let (merged, stats) = apply_xml_payload(&current, &xml).expect("apply xml");
+        let cancel = CancelToken::new();
+        let (merged, stats, ..) = apply_xml_payload(
+            &current,
+            &xml,
+            ApplyProfile::SourceFallback,
+            &cancel,
+            &NoProgress,
+        )
+        .expect("apply xml");
         assert_eq!(stats.updated, 1);
         assert_eq!(stats.missing, 0);
         assert_eq!(merged[0].target_text, "鉄の剣");
+
+        cancel.cancel();
+        let err = apply_xml_payload(
+            &current,
+            &xml,
+            ApplyProfile::SourceFallback,
+            &cancel,
+            &NoProgress,
+        )
+        .expect_err("canceled");
+        assert_eq!(err, "canceled");
+    }
+
+    #[test]
+    fn t_app_009_partial_export_selection_and_filter() {
+        let mut state = AppState::new();
+        state.set_entries_with_history(
+            (0..5)
+                .map(|i| Entry {
+                    key: format!("k{i}"),
+                    source_text: format!("Line {i}").into(),
+                    target_text: if i % 2 == 0 {
+                        Default::default()
+                    } else {
+                        format!("訳{i}").into()
+                    },
+                })
+                .collect(),
+        );
+        dispatch(&mut state, AppAction::SelectEntry("k3".to_string())).expect("select");
+        dispatch(&mut state, AppAction::SelectRange("k1".to_string())).expect("range");
+        dispatch(&mut state, AppAction::ToggleSelection("k2".to_string())).expect("toggle");
+        let keys = state
+            .selected_entries()
+            .into_iter()
+            .map(|entry| entry.key)
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["k1", "k3"]);
+
+        let root = std::env::temp_dir().join(format!("xt_app_partial_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let selection_path = root.join("selection.xml");
+        dispatch(
+            &mut state,
+            AppAction::ExportSelectionXml(selection_path.clone()),
+        )
+        .expect("export selection");
+        let xml = std::fs::read_to_string(&selection_path).expect("read selection");
+        let exported = import_entries(&xml).expect("import selection");
+        assert_eq!(exported.len(), 2);
+        assert_eq!(exported[0].key, "k1");
+
+        dispatch(&mut state, AppAction::SetUntranslatedOnly(true)).expect("filter");
+        let filtered_path = root.join("filtered.json");
+        dispatch(
+            &mut state,
+            AppAction::ExportFilteredXml(filtered_path.clone()),
+        )
+        .expect("export filtered");
+        let json = std::fs::read_to_string(&filtered_path).expect("read filtered");
+        let exported = import_entries_json(&json).expect("import filtered");
+        let keys = exported
+            .iter()
+            .map(|entry| entry.key.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["k0", "k2", "k4"]);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_app_011_edit_meta_stamped_and_imported() {
+        let mut state = AppState::new();
+        state.author = "tester".to_string();
+        state.set_entries_with_history(vec![
+            Entry {
+                key: "k1".to_string(),
+                source_text: "Iron Sword".into(),
+                target_text: Default::default(),
+            },
+            Entry {
+                key: "k2".to_string(),
+                source_text: "Steel Sword".into(),
+                target_text: "鋼鉄の剣".into(),
+            },
+        ]);
+        dispatch(&mut state, AppAction::SelectEntry("k1".to_string())).expect("select");
+        dispatch(&mut state, AppAction::SetEditTarget("鉄の剣".to_string())).expect("edit");
+        dispatch(&mut state, AppAction::ApplyEdit).expect("apply");
+        let stamped = state.edit_meta.get("k1").expect("stamped");
+        assert_eq!(stamped.author, "tester");
+        assert!(stamped.modified_at > 0);
+        assert!(!state.edit_meta.contains_key("k2"));
+
+        let mut meta = EditMetaMap::new();
+        for key in ["k1", "k2"] {
+            meta.insert(
+                key.to_string(),
+                EditMeta {
+                    author: "hanako".to_string(),
+                    modified_at: 86_400 * 365,
+                },
+            );
+        }
+        let imported = vec![
+            Entry {
+                key: "k1".to_string(),
+                source_text: "Iron Sword".into(),
+                target_text: "鉄の剣(改)".into(),
+            },
+            Entry {
+                key: "k2".to_string(),
+                source_text: "Steel Sword".into(),
+                target_text: "鋼の剣".into(),
+            },
+        ];
+        let xml = export_entries_with_meta(&imported, &meta, &NoteMap::new());
+        let (_, stats, kept, ..) = apply_xml_payload(
+            state.entries(),
+            &xml,
+            ApplyProfile::OnlyEmptyTargets,
+            &CancelToken::new(),
+            &NoProgress,
+        )
+        .expect("apply");
+        assert_eq!(stats.kept_existing, 2);
+        assert!(kept.is_empty());
+
+        state.apply_profile = ApplyProfile::KeyExact;
+        state.xml_text = xml;
+        dispatch(&mut state, AppAction::ApplyXmlFromEditor).expect("apply xml");
+        assert_eq!(state.edit_meta["k2"].author, "hanako");
+        assert_eq!(
+            crate::state::format_unix_utc(state.edit_meta["k2"].modified_at),
+            "1971-01-01 00:00 UTC"
+        );
+    }
+
+    #[test]
+    fn t_app_012_plugin_save_warning_on_localized_mismatch() {
+        let inline = ExtractedString::new(
+            *b"WEAP",
+            *b"FULL",
+            0x0100_0800,
+            0,
+            "Iron Sword".to_string(),
+            StringStorage::Inline,
+        );
+        let localized = ExtractedString::new(
+            *b"WEAP",
+            *b"FULL",
+            0x0100_0801,
+            0,
+            "Steel Sword".to_string(),
+            StringStorage::Localized {
+                kind: StringsKind::Strings,
+                id: 1,
+            },
+        );
+        let flagged = PluginInfo {
+            flags: xt_core::formats::esp::PLUGIN_FLAG_LOCALIZED,
+            ..PluginInfo::default()
+        };
+        let plain = PluginInfo::default();
+
+        let warning = plugin_save_warning(Some(&flagged), Some(std::slice::from_ref(&inline)));
+        assert!(warning.expect("warn").contains("Localizedフラグ付き"));
+        let warning = plugin_save_warning(Some(&plain), Some(&[inline.clone(), localized.clone()]));
+        assert!(warning.expect("warn").contains("1 件"));
+        assert_eq!(
+            plugin_save_warning(Some(&flagged), Some(&[inline.clone(), localized])),
+            None
+        );
+        assert_eq!(
+            plugin_save_warning(Some(&plain), Some(std::slice::from_ref(&inline))),
+            None
+        );
+        assert_eq!(plugin_save_warning(None, Some(&[inline])), None);
+    }
+
+    #[test]
+    fn t_app_013_keyboard_workflow_navigation() {
+        let mut state = AppState::new();
+        state.set_entries_with_history(
+            (0..5)
+                .map(|i| Entry {
+                    key: format!("k{i}"),
+                    source_text: format!("Line {i}").into(),
+                    target_text: if i % 2 == 0 {
+                        Default::default()
+                    } else {
+                        format!("訳{i}").into()
+                    },
+                })
+                .collect(),
+        );
+        let selected = |state: &AppState| state.selected_key().unwrap_or_default();
+
+        dispatch(&mut state, AppAction::SelectNextRow).expect("first row");
+        assert_eq!(selected(&state), "k0");
+        dispatch(&mut state, AppAction::SelectNextUntranslated).expect("next");
+        assert_eq!(selected(&state), "k2");
+        dispatch(&mut state, AppAction::SelectPreviousUntranslated).expect("previous");
+        assert_eq!(selected(&state), "k0");
+        dispatch(&mut state, AppAction::SelectPreviousUntranslated).expect("wraps");
+        assert_eq!(selected(&state), "k4");
+        dispatch(&mut state, AppAction::SelectPreviousRow).expect("up");
+        assert_eq!(selected(&state), "k3");
+
+        state.validation_issues.push(ValidationIssue {
+            entry_key: "k1".to_string(),
+            severity: Severity::Error,
+            rule_id: "test".to_string(),
+            message: String::new(),
+        });
+        dispatch(&mut state, AppAction::SelectNextIssue).expect("issue");
+        assert_eq!(selected(&state), "k1");
+
+        dispatch(&mut state, AppAction::ToggleValidated).expect("mark");
+        assert!(state.validated_keys.contains("k1"));
+        dispatch(&mut state, AppAction::ToggleValidated).expect("unmark");
+        assert!(state.validated_keys.is_empty());
+
+        state.set_untranslated_only(true);
+        dispatch(&mut state, AppAction::SelectEntry("k2".to_string())).expect("select");
+        dispatch(&mut state, AppAction::CopySourceToTarget).expect("copy");
+        assert_eq!(state.edit_target, "Line 2");
+        dispatch(&mut state, AppAction::ApplyEditAndNext).expect("apply");
+        assert_eq!(state.entries()[2].target_text, "Line 2");
+        assert_eq!(
+            selected(&state),
+            "k4",
+            "row that took the applied row's place"
+        );
+        dispatch(&mut state, AppAction::ApplyEditAndNext).expect("apply last");
+        assert_eq!(state.filtered_len(), 2, "unchanged row stays listed");
+        assert_eq!(selected(&state), "k4");
+    }
+
+    #[test]
+    fn t_app_014_inline_target_edit_is_undoable() {
+        let mut state = AppState::new();
+        state.set_entries_with_history(vec![
+            Entry {
+                key: "k0".to_string(),
+                source_text: "Iron".into(),
+                target_text: Default::default(),
+            },
+            Entry {
+                key: "k1".to_string(),
+                source_text: "Steel".into(),
+                target_text: "鋼".into(),
+            },
+        ]);
+        dispatch(&mut state, AppAction::SelectEntry("k1".to_string())).expect("select");
+        dispatch(
+            &mut state,
+            AppAction::SetEntryTarget {
+                key: "k1".to_string(),
+                target: "鋼鉄".to_string(),
+            },
+        )
+        .expect("inline edit");
+        assert_eq!(state.entries()[1].target_text, "鋼鉄");
+        assert_eq!(state.entries()[1].source_text, "Steel");
+        assert_eq!(state.edit_target, "鋼鉄", "editor follows the selected row");
+        assert_eq!(state.history.items().len(), 1);
+        assert!(state.edit_meta.contains_key("k1"));
+
+        dispatch(&mut state, AppAction::Undo).expect("undo");
+        assert_eq!(state.entries()[1].target_text, "鋼");
+        assert!(dispatch(
+            &mut state,
+            AppAction::SetEntryTarget {
+                key: "missing".to_string(),
+                target: String::new(),
+            },
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn t_app_017_unsaved_changes_and_backup_policy() {
+        let root = std::env::temp_dir().join(format!("xt_app_policy_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let path = root.join("mod_japanese.strings");
+        let base = StringsFile::new(vec![StringsEntry {
+            id: 1,
+            text: "Iron Sword".to_string(),
+        }]);
+        let mut state = AppState::new();
+        state.set_entries_with_history(strings_entries(&base));
+        state.loaded_strings = Some(base);
+        state.loaded_strings_kind = Some(StringsKind::Strings);
+        state.loaded_strings_path = Some(path.clone());
+        assert!(!state.has_unsaved_changes(), "a fresh load is saved");
+
+        assert!(state.update_entry("strings:1", "Iron Sword", "鉄の剣"));
+        assert!(state.has_unsaved_changes());
+        let mut data = SaveJobData::from_state(&state);
+        data.backup = BackupPolicy::Off;
+        run_save_job(data, SaveMode::Overwrite).expect("first save");
+        state.mark_saved(state.revision());
+        assert!(!state.has_unsaved_changes());
+
+        state.undo();
+        assert!(state.has_unsaved_changes(), "undo after saving is a change");
+        let mut data = SaveJobData::from_state(&state);
+        data.backup = BackupPolicy::Off;
+        run_save_job(data, SaveMode::Overwrite).expect("save without backup");
+        assert!(!root.join("mod_japanese.bak.strings").exists());
+
+        let mut data = SaveJobData::from_state(&state);
+        data.backup = BackupPolicy::Single;
+        run_save_job(data, SaveMode::Overwrite).expect("save with backup");
+        let bak = root.join("mod_japanese.bak.strings");
+        assert!(bak.exists());
+
+        assert!(dispatch(
+            &mut state,
+            AppAction::DeleteBackup(root.join("mod_japanese.strings"))
+        )
+        .is_err());
+        dispatch(&mut state, AppAction::RestoreBackup(bak.clone())).expect("restore");
+        assert!(!state.has_unsaved_changes(), "a restore reloads the file");
+        let listed = list_backups(&path).expect("list");
+        assert_eq!(listed.len(), 2, "restoring backs up the current file");
+        for backup in listed {
+            dispatch(&mut state, AppAction::DeleteBackup(backup.path)).expect("delete");
+        }
+        assert!(list_backups(&path).expect("list").is_empty());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_app_018_read_only_save_target_is_reported() {
+        let root = std::env::temp_dir().join(format!("xt_app_readonly_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let path = root.join("mod_japanese.strings");
+        let base = StringsFile::new(vec![StringsEntry {
+            id: 1,
+            text: "Iron Sword".to_string(),
+        }]);
+        let mut state = AppState::new();
+        assert_eq!(save_target(&state, &SaveMode::Overwrite), None);
+        state.set_entries_with_history(strings_entries(&base));
+        state.loaded_strings = Some(base);
+        state.loaded_strings_kind = Some(StringsKind::Strings);
+        state.loaded_strings_path = Some(path.clone());
+        assert_eq!(
+            save_target(&state, &SaveMode::Overwrite),
+            Some(path.clone())
+        );
+        assert_eq!(
+            save_target(&state, &SaveMode::Auto),
+            Some(root.join("mod_japanese_translated.strings"))
+        );
+        let mut data = SaveJobData::from_state(&state);
+        data.backup = BackupPolicy::Off;
+        run_save_job(data, SaveMode::Overwrite).expect("first save");
+
+        let mut permissions = std::fs::metadata(&path).expect("meta").permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&path, permissions.clone()).expect("read-only");
+        assert!(state.update_entry("strings:1", "Iron Sword", "鉄の剣"));
+        let mut data = SaveJobData::from_state(&state);
+        data.backup = BackupPolicy::Off;
+        let err = run_save_job(data, SaveMode::Overwrite).unwrap_err();
+        assert!(err.contains("読み取り専用"), "{err}");
+        assert!(state.has_unsaved_changes());
+
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        std::fs::set_permissions(&path, permissions).expect("writable");
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_app_019_create_package_from_loaded_strings() {
+        let root = std::env::temp_dir().join(format!("xt_app_package_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let manifest = PackageManifest {
+            mod_name: "ModA".to_string(),
+            mod_version: "1.0".to_string(),
+            language: "japanese".to_string(),
+            author: "tester".to_string(),
+            created_at: 0,
+        };
+        let mut request = PackageRequest {
+            path: root.join(manifest.file_name()),
+            manifest,
+            dsd: false,
+        };
+        let mut state = AppState::new();
+        assert!(dispatch(&mut state, AppAction::CreatePackage(request.clone())).is_err());
+
+        let base = StringsFile::new(vec![StringsEntry {
+            id: 1,
+            text: "Iron Sword".to_string(),
+        }]);
+        state.set_entries_with_history(strings_entries(&base));
+        state.loaded_strings = Some(base);
+        state.loaded_strings_kind = Some(StringsKind::Strings);
+        state.loaded_strings_path = Some(root.join("moda_english.strings"));
+        assert!(state.update_entry("strings:1", "Iron Sword", "鉄の剣"));
+        dispatch(&mut state, AppAction::CreatePackage(request.clone())).expect("package");
+        assert!(root.join("ModA-japanese-1.0.zip").is_file());
+        assert!(
+            state.file_status.contains("3ファイル"),
+            "{}",
+            state.file_status
+        );
+
+        request.dsd = true;
+        let err = dispatch(&mut state, AppAction::CreatePackage(request)).unwrap_err();
+        assert!(err.contains("DSD"), "{err}");
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_app_020_candidate_conflicts_resolved_per_entry_and_rule() {
+        let mut state = AppState::new();
+        let row = |key: &str, source: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        };
+        state.set_entries_with_history(vec![row("k0", "Iron Sword", ""), row("k1", "Steel", "")]);
+        state.xml_text =
+            export_entries(&[row("k0", "Iron Sword", "鉄の剣"), row("k1", "Steel", "鋼")]);
+        dispatch(&mut state, AppAction::ApplyXmlFromEditor).expect("apply xml");
+        assert_eq!(state.candidates.conflict_count(), 0);
+
+        state.dict = Some(TranslationDictionary::build_from_entries(&[row(
+            "d0",
+            "Iron Sword",
+            "鉄のソード",
+        )]));
+        dispatch(&mut state, AppAction::SelectEntry("k0".to_string())).expect("select");
+        dispatch(&mut state, AppAction::QuickAuto).expect("quick");
+        assert_eq!(
+            state.entries()[0].target_text,
+            "鉄の剣",
+            "translated row kept"
+        );
+        dispatch(
+            &mut state,
+            AppAction::SetEntryTarget {
+                key: "k1".to_string(),
+                target: "鋼鉄".to_string(),
+            },
+        )
+        .expect("manual edit");
+        assert_eq!(
+            state.candidates.conflicts(state.entries()),
+            vec!["k0", "k1"]
+        );
+
+        dispatch(
+            &mut state,
+            AppAction::ResolveConflict {
+                key: "k0".to_string(),
+                source: CandidateSource::Dictionary,
+            },
+        )
+        .expect("pick");
+        assert_eq!(state.entries()[0].target_text, "鉄のソード");
+        dispatch(
+            &mut state,
+            AppAction::ResolveConflicts(vec![CandidateSource::Xml, CandidateSource::Manual]),
+        )
+        .expect("by rule");
+        assert_eq!(state.entries()[1].target_text, "鋼");
+        assert_eq!(state.candidates.conflict_count(), 0);
+        assert!(dispatch(
+            &mut state,
+            AppAction::ResolveConflict {
+                key: "k1".to_string(),
+                source: CandidateSource::Manual,
+            },
+        )
+        .is_err());
+
+        dispatch(&mut state, AppAction::Undo).expect("undo");
+        assert_eq!(state.entries()[1].target_text, "鋼鉄");
+    }
+
+    #[test]
+    fn t_app_021_provenance_follows_xml_dictionary_and_edits() {
+        let mut state = AppState::new();
+        let row = |key: &str, source: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        };
+        state.set_entries_with_history(vec![
+            row("k0", "Iron Sword", ""),
+            row("k1", "Steel", ""),
+            row("k2", "IRON SWORD", ""),
+            row("k3", "Gold", "金"),
+        ]);
+        state.xml_text =
+            export_entries(&[row("k0", "Iron Sword", "鉄の剣"), row("x", "Steel", "鋼")]);
+        dispatch(&mut state, AppAction::ApplyXmlFromEditor).expect("apply xml");
+        assert_eq!(state.provenance["k0"], Provenance::XmlKey);
+        assert_eq!(state.provenance["k1"], Provenance::XmlSource);
+
+        state.dict = Some(TranslationDictionary::build_from_entries(&[row(
+            "d0",
+            "Iron Sword",
+            "鉄のソード",
+        )]));
+        state.dict_match = MatchOptions::LOOSE;
+        dispatch(&mut state, AppAction::SelectEntry("k2".to_string())).expect("select");
+        dispatch(&mut state, AppAction::QuickAuto).expect("quick");
+        assert_eq!(
+            state.provenance["k2"],
+            Provenance::Dictionary {
+                score: 30,
+                ambiguous: false
+            }
+        );
+        assert_eq!(state.provenance["k2"].label(), "辞書 30%");
+
+        dispatch(
+            &mut state,
+            AppAction::SetEntryTarget {
+                key: "k1".to_string(),
+                target: "鋼鉄".to_string(),
+            },
+        )
+        .expect("manual edit");
+        assert_eq!(state.provenance["k1"], Provenance::Manual);
+        assert!(!state.provenance.contains_key("k3"), "loaded target");
+
+        state.set_sort(Some(EntrySort {
+            column: EntryColumn::Origin,
+            descending: false,
+        }));
+        let order = (0..4)
+            .map(|row| state.filtered_entry(row).expect("row").key.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(order, ["k3", "k1", "k0", "k2"]);
+
+        dispatch(
+            &mut state,
+            AppAction::SetEntryTarget {
+                key: "k1".to_string(),
+                target: String::new(),
+            },
+        )
+        .expect("clear");
+        assert!(!state.provenance.contains_key("k1"));
+    }
+
+    #[test]
+    fn t_app_022_dictionary_pairs_edited_and_saved() {
+        let root = std::env::temp_dir().join(format!("xt_app_022_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let mut state = AppState::new();
+        state.dict = None;
+        state.dict_user_path = root.join("user").join("dict.tsv").display().to_string();
+        let set =
+            |previous: Option<&str>, source: &str, target: &str| AppAction::SetDictionaryPair {
+                previous: previous.map(str::to_string),
+                source: source.to_string(),
+                target: target.to_string(),
+            };
+
+        dispatch(&mut state, set(None, "Iron Sword", "鉄の剣")).expect("add");
+        dispatch(&mut state, set(None, "Steel", "鋼")).expect("add");
+        assert!(dispatch(&mut state, set(None, "Gold", "")).is_err());
+        dispatch(
+            &mut state,
+            set(Some("Steel"), "Steel Ingot", "鋼鉄のインゴット"),
+        )
+        .expect("edit");
+        let dict = state.dict.as_ref().expect("dict");
+        assert_eq!(dict.len(), 2);
+        assert!(dict.get("Steel").is_none());
+        assert!(state.dict_unsaved);
+
+        dispatch(&mut state, AppAction::SaveUserDictionary).expect("save");
+        assert!(!state.dict_unsaved);
+        dispatch(
+            &mut state,
+            AppAction::RemoveDictionaryPair("Iron Sword".to_string()),
+        )
+        .expect("remove");
+        assert!(dispatch(
+            &mut state,
+            AppAction::RemoveDictionaryPair("Iron Sword".to_string()),
+        )
+        .is_err());
+        assert_eq!(state.dict.as_ref().map(TranslationDictionary::len), Some(1));
+
+        let revision = state.dict_revision;
+        dispatch(&mut state, AppAction::LoadUserDictionary).expect("load");
+        assert!(state.dict_revision > revision);
+        let dict = state.dict.as_ref().expect("dict");
+        assert_eq!(dict.get("Iron Sword").map(|t| t.as_str()), Some("鉄の剣"));
+        assert_eq!(
+            dict.get("Steel Ingot").map(|t| t.as_str()),
+            Some("鋼鉄のインゴット")
+        );
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_app_023_quick_auto_marks_ambiguous_dictionary_targets() {
+        let mut state = AppState::new();
+        let row = |key: &str, source: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        };
+        state.set_entries_with_history(vec![row("k0", "Iron Sword", ""), row("k1", "Steel", "")]);
+        state.dict = Some(TranslationDictionary::build_from_entries(&[
+            row("d0", "Iron Sword", "鉄のソード"),
+            row("d1", "Iron Sword", "鉄の剣"),
+            row("d2", "Iron Sword", "鉄の剣"),
+            row("d3", "Steel", "鋼"),
+        ]));
+
+        dispatch(&mut state, AppAction::SelectEntry("k0".to_string())).expect("select");
+        dispatch(&mut state, AppAction::QuickAuto).expect("quick");
+        assert_eq!(state.entries()[0].target_text, "鉄の剣", "most used");
+        assert!(state.provenance["k0"].is_suspect());
+        assert_eq!(state.provenance["k0"].label(), "辞書 完全一致（要確認）");
+
+        dispatch(&mut state, AppAction::SelectEntry("k1".to_string())).expect("select");
+        dispatch(&mut state, AppAction::QuickAuto).expect("quick");
+        assert!(!state.provenance["k1"].is_suspect());
+
+        dispatch(&mut state, AppAction::SelectEntry("k0".to_string())).expect("select");
+        let dict = state.dict.as_ref().expect("dict");
+        let suggestion = dict.alternatives("Iron Sword")[0].text.to_string();
+        dispatch(&mut state, AppAction::SetEditTarget(suggestion)).expect("suggest");
+        dispatch(&mut state, AppAction::ApplyEdit).expect("apply");
+        assert_eq!(state.entries()[0].target_text, "鉄のソード");
+        assert_eq!(state.provenance["k0"], Provenance::Manual);
+    }
+
+    #[test]
+    fn t_app_024_dropped_files_classified_by_extension() {
+        for (path, kind) in [
+            ("Data/mod.ESP", Some(DroppedFile::Plugin)),
+            ("Data/mod.esm", Some(DroppedFile::Plugin)),
+            ("Data/mod.esl", Some(DroppedFile::Plugin)),
+            ("mod.xtplugin", Some(DroppedFile::Plugin)),
+            ("Strings/mod_english.strings", Some(DroppedFile::Strings)),
+            ("Strings/mod_english.DLSTRINGS", Some(DroppedFile::Strings)),
+            ("Strings/mod_english.ilstrings", Some(DroppedFile::Strings)),
+            ("tr.xml", Some(DroppedFile::Xml)),
+            ("tr.json", Some(DroppedFile::Xml)),
+            ("mod.xtproj", None),
+            ("readme", None),
+        ] {
+            assert_eq!(DroppedFile::from_path(Path::new(path)), kind, "{path}");
+        }
+    }
+
+    #[test]
+    fn t_app_025_documents_keep_their_own_edits() {
+        let root = std::env::temp_dir().join(format!("xt_app_documents_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let esm = root.join("Base_english.strings");
+        let patch = root.join("Patch_english.strings");
+        for (path, text) in [(&esm, "Iron Sword"), (&patch, "Steel Sword")] {
+            let file = StringsFile::new(vec![StringsEntry {
+                id: 1,
+                text: text.to_string(),
+            }]);
+            std::fs::write(path, write_strings(&file).expect("encode")).expect("write");
+        }
+
+        let mut state = AppState::new();
+        dispatch(&mut state, AppAction::LoadStrings(esm.clone())).expect("load esm");
+        assert_eq!(
+            state.document_tabs().len(),
+            1,
+            "an empty document is reused"
+        );
+        assert!(state.update_entry("strings:1", "Iron Sword", "鉄の剣"));
+        let first = state.document_id();
+
+        dispatch(&mut state, AppAction::LoadStrings(patch.clone())).expect("load patch");
+        let tabs = state.document_tabs();
+        assert_eq!(
+            tabs.iter()
+                .map(|tab| (tab.title.as_str(), tab.unsaved))
+                .collect::<Vec<_>>(),
+            [
+                ("Base_english.strings", true),
+                ("Patch_english.strings", false)
+            ]
+        );
+        assert_eq!(state.active_document(), 1);
+        assert_ne!(state.document_id(), first);
+        assert_eq!(state.entries()[0].source_text, "Steel Sword");
+        assert!(state.history.items().is_empty());
+
+        dispatch(&mut state, AppAction::SwitchDocument(0)).expect("switch");
+        assert_eq!(state.document_id(), first);
+        assert_eq!(state.entries()[0].target_text, "鉄の剣");
+        assert_eq!(state.filtered_len(), 1);
+        state.undo();
+        assert_eq!(state.entries()[0].target_text, "");
+
+        dispatch(&mut state, AppAction::LoadStrings(patch.clone())).expect("reopen patch");
+        assert_eq!(
+            state.document_tabs().len(),
+            2,
+            "an open file is not opened twice"
+        );
+        assert_eq!(state.active_document(), 1);
+
+        dispatch(&mut state, AppAction::CloseDocument(1)).expect("close");
+        assert_eq!(state.active_document(), 0);
+        assert_eq!(state.session_file(), Some(esm.as_path()));
+        dispatch(&mut state, AppAction::CloseDocument(0)).expect("close last");
+        assert_eq!(state.document_tabs().len(), 1);
+        assert!(state.entries().is_empty());
+        assert_eq!(state.document_tabs()[0].title, "無題");
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    /// Opens `name` in a new document tab as a plugin with `masters`.
+    fn open_plugin_document(
+        state: &mut AppState,
+        name: &str,
+        masters: &[&str],
+        entries: &[(&str, &str, &str)],
+    ) {
+        state.open_document_for(Path::new(name));
+        state.set_entries_without_history(
+            entries
+                .iter()
+                .map(|(key, source, target)| Entry {
+                    key: key.to_string(),
+                    source_text: (*source).into(),
+                    target_text: (*target).into(),
+                })
+                .collect(),
+        );
+        state.loaded_plugin_path = Some(PathBuf::from(name));
+        state.plugin_info = Some(PluginInfo {
+            masters: masters.iter().map(|master| master.to_string()).collect(),
+            ..PluginInfo::default()
+        });
+    }
+
+    #[test]
+    fn t_app_026_override_target_copied_between_open_plugins() {
+        let mut state = AppState::new();
+        open_plugin_document(
+            &mut state,
+            "Skyrim.esm",
+            &[],
+            &[("WEAP:00012EB7:FULL:0", "Iron Sword {0}", "鉄の剣 {0}")],
+        );
+        open_plugin_document(
+            &mut state,
+            "Patch.esp",
+            &["Skyrim.esm"],
+            &[
+                ("WEAP:00012EB7:FULL:0", "Iron Sword {1}", ""),
+                ("WEAP:01000800:FULL:0", "New Sword", ""),
+            ],
+        );
+        assert_eq!(state.document_tabs().len(), 2);
+
+        let overrides = state.overrides();
+        assert_eq!(overrides.len(), 1);
+        assert_eq!(overrides[0].winner().plugin, "Patch.esp");
+        assert_eq!(overrides[0].definitions[0].plugin, "Skyrim.esm");
+
+        dispatch(
+            &mut state,
+            AppAction::CopyOverrideTarget(
+                "Skyrim.esm".to_string(),
+                "WEAP:00012EB7:FULL:0".to_string(),
+            ),
+        )
+        .expect("copy");
+        assert_eq!(state.active_document(), 1);
+        assert_eq!(state.entries()[0].target_text, "鉄の剣 {1}");
+        assert_eq!(state.file_status, "上書き: 1 件に訳文をコピーしました");
+        state.undo();
+        assert_eq!(state.entries()[0].target_text, "");
+
+        assert!(dispatch(
+            &mut state,
+            AppAction::CopyOverrideTarget("Patch.esp".to_string(), "WEAP:01000800:FULL:0".into()),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn t_app_027_targets_copied_from_another_plugin_by_form_id() {
+        let mut state = AppState::new();
+        open_plugin_document(
+            &mut state,
+            "Translated.esp",
+            &["Skyrim.esm"],
+            &[
+                ("WEAP:00012EB7:FULL:0", "Iron Sword", "鉄の剣"),
+                ("WEAP:00000800:DESC:0", "Deal %d damage", "%d のダメージ"),
+                ("WEAP:00000801:FULL:0", "Same", "Same"),
+            ],
+        );
+        open_plugin_document(
+            &mut state,
+            "Patch.esp",
+            &["Update.esm", "Skyrim.esm"],
+            &[
+                ("WEAP:01012EB7:FULL:0", "Iron Sword", "古い訳"),
+                ("WEAP:01000800:DESC:0", "Deal %s damage", ""),
+                ("WEAP:01000801:FULL:0", "Same", ""),
+                ("WEAP:00000001:FULL:0", "Update Sword", ""),
+            ],
+        );
+        state.provenance.insert(
+            "WEAP:01012EB7:FULL:0".to_string(),
+            Provenance::MachineTranslation,
+        );
+
+        dispatch(
+            &mut state,
+            AppAction::CopyTargetsFrom(TargetSource::Document(0)),
+        )
+        .expect("copy");
+        let targets = state
+            .entries()
+            .iter()
+            .map(|entry| entry.target_text.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(targets, ["鉄の剣", "%s のダメージ", "", ""]);
+        assert_eq!(
+            state.file_status,
+            "別プラグインから訳文をコピー: 一致 3 / 不一致 1 / 更新 2"
+        );
+        assert!(state.provenance.is_empty());
+        state.undo();
+        assert_eq!(state.entries()[0].target_text, "古い訳");
+
+        assert!(dispatch(
+            &mut state,
+            AppAction::CopyTargetsFrom(TargetSource::Document(1)),
+        )
+        .is_err());
+        assert!(dispatch(
+            &mut state,
+            AppAction::CopyTargetsFrom(TargetSource::Reference(PathBuf::from(
+                "missing/Translated.esp"
+            ))),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn t_app_028_reference_translation_consulted_before_dictionary() {
+        let root = std::env::temp_dir().join(format!("xt_app_028_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        for (name, texts) in [
+            ("Skyrim_english.strings", ["Iron Sword", "Steel Sword"]),
+            ("Skyrim_japanese.strings", ["鉄の剣", "鋼鉄の剣"]),
+        ] {
+            let file = StringsFile::new(
+                texts
+                    .iter()
+                    .enumerate()
+                    .map(|(id, text)| StringsEntry {
+                        id: id as u32 + 1,
+                        text: text.to_string(),
+                    })
+                    .collect(),
+            );
+            std::fs::write(root.join(name), write_strings(&file).expect("encode")).expect("write");
+        }
+        let row = |key: &str, source: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        };
+        let mut state = AppState::new();
+        state.set_entries_with_history(vec![
+            row("k0", "Iron Sword", ""),
+            row("k1", "Steel Sword", "鋼の剣"),
+            row("k2", "Mod Sword", ""),
+        ]);
+        state.dict = Some(TranslationDictionary::build_from_entries(&[
+            row("d0", "Iron Sword", "鉄のソード"),
+            row("d1", "Mod Sword", "改造剣"),
+        ]));
+
+        let reference = root.join("Skyrim_japanese.strings");
+        dispatch(&mut state, AppAction::LoadReference(reference)).expect("reference");
+        let targets = |state: &AppState| {
+            state
+                .entries()
+                .iter()
+                .map(|entry| entry.target_text.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(targets(&state), ["鉄の剣", "鋼の剣", ""]);
+        assert_eq!(state.provenance["k0"], Provenance::Reference);
+        assert!(
+            state.dict_status.contains("適用 1行"),
+            "{}",
+            state.dict_status
+        );
+
+        state.undo();
+        dispatch(&mut state, AppAction::SelectEntry("k0".to_string())).expect("select");
+        dispatch(&mut state, AppAction::QuickAuto).expect("quick");
+        assert_eq!(state.entries()[0].target_text, "鉄の剣");
+        assert!(state.candidates.is_conflict("k0"));
+        dispatch(&mut state, AppAction::SelectEntry("k2".to_string())).expect("select");
+        dispatch(&mut state, AppAction::QuickAuto).expect("quick");
+        assert_eq!(targets(&state), ["鉄の剣", "鋼の剣", "改造剣"]);
+
+        dispatch(&mut state, AppAction::ClearReference).expect("clear");
+        assert!(state.reference.is_none());
+        assert!(dispatch(
+            &mut state,
+            AppAction::LoadReference(root.join("Skyrim_english.strings"))
+        )
+        .is_err());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_app_029_vanilla_rows_protected_from_apply_and_export() {
+        let row = |key: &str, source: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        };
+        let mut state = AppState::new();
+        state.set_entries_with_history(vec![
+            row("k0", "Iron Sword", ""),
+            row("k1", "Mod Sword", ""),
+        ]);
+        state.reference = Some(ReferenceTranslation {
+            path: PathBuf::from("Skyrim_japanese.strings"),
+            dict: TranslationDictionary::build_from_entries(&[row("r0", "Iron Sword", "鉄の剣")]),
+        });
+        state.dict_protect_vanilla = true;
+        assert!(state.is_vanilla("Iron Sword"));
+        assert!(!state.is_vanilla("Mod Sword"));
+
+        state.xml_text = export_entries(&[
+            row("k0", "Iron Sword", "アイアンソード"),
+            row("k1", "Mod Sword", "改造剣"),
+        ]);
+        dispatch(&mut state, AppAction::ApplyXmlFromEditor).expect("apply xml");
+        assert_eq!(state.entries()[0].target_text, "");
+        assert_eq!(state.entries()[1].target_text, "改造剣");
+        assert!(!state.provenance.contains_key("k0"));
+
+        dispatch(&mut state, AppAction::SelectEntry("k0".to_string())).expect("select");
+        dispatch(&mut state, AppAction::QuickAuto).expect("quick");
+        assert_eq!(
+            state.entries()[0].target_text,
+            "鉄の剣",
+            "official text applies"
+        );
+
+        dispatch(&mut state, AppAction::ExportXmlToEditor).expect("export");
+        assert!(!state.xml_text.contains("Iron Sword"));
+        assert!(state.xml_text.contains("Mod Sword"));
+
+        state.dict_protect_vanilla = false;
+        dispatch(&mut state, AppAction::ExportXmlToEditor).expect("export");
+        assert!(state.xml_text.contains("Iron Sword"));
+        state.xml_text = export_entries(&[row("k0", "Iron Sword", "アイアンソード")]);
+        dispatch(&mut state, AppAction::ApplyXmlFromEditor).expect("apply xml");
+        assert_eq!(state.entries()[0].target_text, "アイアンソード");
+    }
+
+    #[test]
+    fn t_app_030_save_as_auto_writes_templated_output() {
+        let root = std::env::temp_dir().join(format!("xt_app_template_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let path = root.join("mod_english.strings");
+        let base = StringsFile::new(vec![StringsEntry {
+            id: 1,
+            text: "Iron Sword".to_string(),
+        }]);
+        let mut state = AppState::new();
+        state.set_entries_with_history(strings_entries(&base));
+        state.loaded_strings = Some(base);
+        state.loaded_strings_kind = Some(StringsKind::Strings);
+        state.loaded_strings_path = Some(path.clone());
+        assert!(state.update_entry("strings:1", "Iron Sword", "鉄の剣"));
+
+        let template =
+            OutputTemplate::parse("translated/{lang}/{plugin}_{lang}.{ext}").expect("template");
+        let mut data = SaveJobData::from_state(&state);
+        data.backup = BackupPolicy::Off;
+        data.auto_output = Some(template.expand(&root, &path, "japanese", 0));
+        let saved = run_save_job(data, SaveMode::Auto).expect("save as");
+        assert_eq!(
+            saved,
+            root.join("translated")
+                .join("japanese")
+                .join("mod_japanese.strings")
+        );
+        let (written, _) = read_strings_file(&saved).expect("read");
+        assert_eq!(written.entries[0].text, "鉄の剣");
+        assert!(!root.join("mod_english_translated.strings").exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_app_031_save_plan_previews_files_and_changed_strings() {
+        let root = std::env::temp_dir().join(format!("xt_app_plan_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let path = root.join("mod_japanese.strings");
+        let base = StringsFile::new(vec![
+            StringsEntry {
+                id: 1,
+                text: "Iron Sword".to_string(),
+            },
+            StringsEntry {
+                id: 2,
+                text: "Bread".to_string(),
+            },
+        ]);
+        let mut state = AppState::new();
+        assert!(save_plan(&state, &SaveMode::Overwrite).is_err());
+        state.set_entries_with_history(strings_entries(&base));
+        state.loaded_strings = Some(base);
+        state.loaded_strings_kind = Some(StringsKind::Strings);
+        state.loaded_strings_path = Some(path.clone());
+        let mut data = SaveJobData::from_state(&state);
+        data.backup = BackupPolicy::Off;
+        run_save_job(data, SaveMode::Overwrite).expect("first save");
+        assert!(state.update_entry("strings:1", "Iron Sword", "鉄の剣"));
+
+        let before = std::fs::read(&path).expect("read");
+        let plan = save_plan(&state, &SaveMode::Overwrite).expect("plan");
+        let written = plan.files.last().expect("target");
+        assert_eq!(written.path, path);
+        assert_eq!(written.change, PlannedChange::Modify);
+        assert_eq!(written.changed_strings, 1);
+        let backups = plan
+            .files
+            .iter()
+            .filter(|file| file.change == PlannedChange::Backup)
+            .count();
+        assert_eq!(
+            backups,
+            usize::from(state.save_prefs().backup != BackupPolicy::Off)
+        );
+
+        let plan = save_plan(&state, &SaveMode::Auto).expect("plan");
+        assert_eq!(
+            plan.files.last().expect("copy").change,
+            PlannedChange::Create
+        );
+        assert_eq!(plan.changed_strings(), 1);
+        assert_eq!(
+            std::fs::read(&path).expect("read"),
+            before,
+            "nothing written"
+        );
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_app_033_go_to_record_by_form_id_or_editor_id() {
+        let extracted = [
+            ("Iron Sword", 0x0001_2EB7, Some("IronSword")),
+            ("Guard", 0x0001_2EB8, None),
+        ]
+        .map(|(text, form_id, edid)| {
+            ExtractedString::new(
+                *b"WEAP",
+                *b"FULL",
+                form_id,
+                0,
+                text.to_string(),
+                StringStorage::Inline,
+            )
+            .with_editor_id(edid.map(str::to_string))
+        });
+        let mut state = AppState::new();
+        state.set_entries_with_history(
+            extracted
+                .iter()
+                .map(|string| Entry {
+                    key: string.get_unique_key(),
+                    source_text: string.text.clone().into(),
+                    target_text: Default::default(),
+                })
+                .collect(),
+        );
+        state.record_index = RecordIndex::build(&extracted);
+        state.set_query("Guard");
+
+        dispatch(&mut state, AppAction::GoToRecord("ironsword".to_string())).expect("edid");
+        assert_eq!(
+            state.selected_key().as_deref(),
+            Some("WEAP:00012EB7:FULL:0")
+        );
+        assert_eq!(state.query_input(), "", "filter hiding the row is cleared");
+        dispatch(&mut state, AppAction::GoToRecord("0x12EB8".to_string())).expect("form id");
+        assert_eq!(
+            state.selected_key().as_deref(),
+            Some("WEAP:00012EB8:FULL:0")
+        );
+        assert!(dispatch(&mut state, AppAction::GoToRecord(" ".to_string())).is_err());
+        assert!(dispatch(&mut state, AppAction::GoToRecord("Steel".to_string())).is_err());
+        assert!(matches!(
+            crate::keymap::Command::GoToRecord.action(),
+            AppAction::GoToRecord(query) if query.is_empty()
+        ));
+    }
+
+    #[test]
+    fn t_app_034_notes_set_exported_and_imported() {
+        let entries = vec![
+            Entry {
+                key: "k1".to_string(),
+                source_text: "Her Sword".into(),
+                target_text: "彼女の剣".into(),
+            },
+            Entry {
+                key: "k2".to_string(),
+                source_text: "Steel Sword".into(),
+                target_text: Default::default(),
+            },
+        ];
+        let mut state = AppState::new();
+        state.set_entries_with_history(entries.clone());
+        let note = |key: &str, note: &str| AppAction::SetNote {
+            key: key.to_string(),
+            note: note.to_string(),
+        };
+        dispatch(&mut state, note("k1", "  check gender ")).expect("note");
+        dispatch(&mut state, note("k2", "保留")).expect("note");
+        assert!(dispatch(&mut state, note("k9", "x")).is_err());
+        dispatch(&mut state, note("k2", " ")).expect("clear");
+        assert_eq!(state.notes.len(), 1);
+        assert_eq!(state.notes["k1"], "check gender");
+        assert_eq!(state.entries()[0].target_text, "彼女の剣");
+
+        dispatch(&mut state, AppAction::ExportXmlToEditor).expect("export");
+        assert!(state.xml_text.contains(r#"note="check gender""#));
+        let xml = state.xml_text.clone();
+
+        let mut fresh = AppState::new();
+        fresh.set_entries_with_history(entries[..1].to_vec());
+        fresh.set_note("k1", "old");
+        let mut extra = xml.replace("</xtrans>", "");
+        extra
+            .push_str("  <entry key=\"k9\" source=\"\" target=\"\" note=\"stray\" />\n</xtrans>\n");
+        fresh.xml_text = extra;
+        dispatch(&mut fresh, AppAction::ApplyXmlFromEditor).expect("apply xml");
+        assert_eq!(fresh.notes.len(), 1);
+        assert_eq!(fresh.notes["k1"], "check gender");
+
+        fresh.set_entries_with_history(entries);
+        assert!(fresh.notes.is_empty());
+    }
+
+    #[test]
+    fn t_app_035_review_queue_approved_and_rejected() {
+        let row = |key: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: format!("source {key}").into(),
+            target_text: target.into(),
+        };
+        let mut state = AppState::new();
+        state.set_entries_with_history(vec![
+            row("k0", "XML"),
+            row("k1", "辞書"),
+            row("k2", "原文一致"),
+            row("k3", "辞書2"),
+            row("k4", "手動"),
+        ]);
+        let dictionary = Provenance::Dictionary {
+            score: 80,
+            ambiguous: false,
+        };
+        state.provenance.extend([
+            ("k0".to_string(), Provenance::XmlKey),
+            ("k1".to_string(), dictionary),
+            ("k2".to_string(), Provenance::XmlSource),
+            ("k3".to_string(), dictionary),
+            ("k4".to_string(), Provenance::Manual),
+        ]);
+        assert_eq!(state.review_count(), 3);
+        assert!(dispatch(&mut state, AppAction::ApproveReview).is_err());
+
+        dispatch(&mut state, AppAction::SelectEntry("k0".to_string())).expect("select");
+        dispatch(&mut state, AppAction::SelectNextReview).expect("next");
+        assert_eq!(state.selected_key().as_deref(), Some("k1"));
+        dispatch(&mut state, AppAction::SetReviewOnly(true)).expect("review mode");
+        assert_eq!(state.filtered_len(), 3);
+
+        dispatch(&mut state, AppAction::ApproveReview).expect("approve");
+        assert!(state.validated_keys.contains("k1"));
+        assert_eq!(state.review_count(), 2);
+        assert_eq!(state.filtered_len(), 2);
+        assert_eq!(state.selected_key().as_deref(), Some("k2"));
+
+        dispatch(&mut state, AppAction::RejectReview).expect("reject");
+        assert_eq!(state.entries()[2].target_text, "");
+        assert!(!state.provenance.contains_key("k2"));
+        assert_eq!(state.review_count(), 1);
+        assert_eq!(state.selected_key().as_deref(), Some("k3"));
+        assert!(state.file_status.contains("残り 1件"));
+
+        dispatch(&mut state, AppAction::SelectPreviousReview).expect("previous");
+        assert_eq!(state.selected_key().as_deref(), Some("k3"));
+        dispatch(&mut state, AppAction::Undo).expect("undo");
+        assert_eq!(state.entries()[2].target_text, "原文一致");
+
+        dispatch(&mut state, AppAction::ApproveReview).expect("approve last");
+        assert_eq!(state.review_count(), 0);
+        assert_eq!(state.filtered_len(), 0);
+        dispatch(&mut state, AppAction::SelectNextReview).expect("empty queue");
+        assert_eq!(state.file_status, "要確認の行はありません");
+    }
+
+    #[test]
+    fn t_app_036_review_sheet_written_with_status_and_issues() {
+        let row = |key: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: format!("Source {key}").into(),
+            target_text: target.into(),
+        };
+        let mut state = AppState::new();
+        assert!(dispatch(&mut state, AppAction::ExportReviewSheet("x.html".into())).is_err());
+        state.set_entries_with_history(vec![
+            row("k0", ""),
+            row("k1", "訳1"),
+            row("k2", "訳2"),
+            row("k3", "訳3 {0}"),
+        ]);
+        state
+            .editor_ids
+            .insert("k1".to_string(), "IronSword".to_string());
+        state
+            .provenance
+            .insert("k2".to_string(), Provenance::XmlSource);
+        state.validated_keys.insert("k1".to_string());
+        state.set_note("k3", "check gender");
+        state.validation_issues = vec![ValidationIssue {
+            entry_key: "k3".to_string(),
+            severity: Severity::Error,
+            rule_id: "braced_placeholder".to_string(),
+            message: "Braced placeholders do not match.".to_string(),
+        }];
+
+        let sheet = state.review_sheet("Mod.esp");
+        let statuses = sheet.rows.iter().map(|row| row.status).collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [
+                RowStatus::Untranslated,
+                RowStatus::Validated,
+                RowStatus::NeedsReview,
+                RowStatus::Translated
+            ]
+        );
+        assert_eq!(sheet.rows[1].editor_id.as_deref(), Some("IronSword"));
+        assert_eq!(sheet.rows[3].note.as_deref(), Some("check gender"));
+        assert_eq!(sheet.rows[3].issues, state.validation_issues);
+
+        let root = std::env::temp_dir().join(format!("xt_app_review_{}", std::process::id()));
+        std::fs::create_dir_all(&root).expect("dir");
+        let path = root.join("review.html");
+        dispatch(&mut state, AppAction::ExportReviewSheet(path.clone())).expect("export");
+        let html = std::fs::read_to_string(&path).expect("html");
+        assert!(html.contains("<h1>xtrans</h1>"));
+        assert!(html.contains("<td class=\"edid\">IronSword</td>"));
+        assert!(html.contains("check gender"));
+        assert!(state.file_status.contains("(4行)"));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_app_037_review_corrections_applied_as_one_undoable_batch() {
+        let row = |key: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: format!("Source {key}").into(),
+            target_text: target.into(),
+        };
+        let mut state = AppState::new();
+        state.set_entries_with_history(vec![
+            row("k0", "古い訳"),
+            row("k1", ""),
+            row("k2", "そのまま"),
+        ]);
+        let root = std::env::temp_dir().join(format!("xt_app_corrections_{}", std::process::id()));
+        std::fs::create_dir_all(&root).expect("dir");
+
+        let sheet = root.join("review.html");
+        dispatch(&mut state, AppAction::ExportReviewSheet(sheet.clone())).expect("export");
+        let html = std::fs::read_to_string(&sheet)
+            .expect("html")
+            .replace(">古い訳</td>", ">新しい訳</td>")
+            .replace("plaintext-only\"></td>", "plaintext-only\">追加した訳</td>");
+        std::fs::write(&sheet, html).expect("edit");
+        dispatch(&mut state, AppAction::ImportCorrections(sheet)).expect("import html");
+        let targets = |state: &AppState| {
+            state
+                .entries()
+                .iter()
+                .map(|entry| entry.target_text.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(targets(&state), ["新しい訳", "追加した訳", "そのまま"]);
+        assert_eq!(state.provenance["k1"], Provenance::Manual);
+        assert!(state.edit_meta.contains_key("k0"));
+        assert!(state.file_status.contains("更新 2 / 変更なし 1 / 不一致 0"));
+
+        dispatch(&mut state, AppAction::Undo).expect("undo");
+        assert_eq!(targets(&state), ["古い訳", "", "そのまま"]);
+
+        let csv = root.join("review.csv");
+        std::fs::write(&csv, "key,target\nk2,直した\nmissing,x\n").expect("csv");
+        dispatch(&mut state, AppAction::ImportCorrections(csv)).expect("import csv");
+        assert_eq!(targets(&state)[2], "直した");
+        assert!(state.file_status.contains("更新 1 / 変更なし 0 / 不一致 1"));
+        assert_eq!(
+            state.apply_corrections("レビュー修正の取り込み", &[]),
+            crate::state::CorrectionReport::default()
+        );
+
+        let bad = root.join("bad.csv");
+        std::fs::write(&bad, "key,source\nk0,x\n").expect("bad");
+        assert!(dispatch(&mut state, AppAction::ImportCorrections(bad)).is_err());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_app_038_selected_rows_copied_and_pasted_as_tsv() {
+        let row = |key: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: format!("Source {key}").into(),
+            target_text: target.into(),
+        };
+        let mut state = AppState::new();
+        state.set_entries_with_history(vec![row("k0", "剣"), row("k1", ""), row("k2", "盾")]);
+        state.select("k0");
+        state.toggle_selection("k2");
+        let (tsv, count) = state.selected_rows_tsv().expect("selection");
+        assert_eq!(count, 2);
+        assert_eq!(
+            tsv,
+            "key\tsource\ttarget\nk0\tSource k0\t剣\nk2\tSource k2\t盾\n"
+        );
+
+        let pasted =
+            "k0\tSource k0\t鉄の剣\nk1\tSource k1\t\"二行\n目\"\nk2\tSource k2\t盾\nk9\tx\ty\n";
+        dispatch(&mut state, AppAction::PasteRows(pasted.to_string())).expect("paste");
+        let targets = |state: &AppState| {
+            state
+                .entries()
+                .iter()
+                .map(|entry| entry.target_text.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(targets(&state), ["鉄の剣", "二行\n目", "盾"]);
+        assert_eq!(state.provenance["k1"], Provenance::Manual);
+        assert!(state
+            .file_status
+            .contains("貼り付けました: 更新 2 / 変更なし 1 / 不一致 1"));
+
+        dispatch(&mut state, AppAction::Undo).expect("undo");
+        assert_eq!(targets(&state), ["剣", "", "盾"]);
+        assert!(dispatch(&mut state, AppAction::PasteRows("\n".to_string())).is_err());
+    }
+
+    #[test]
+    fn t_app_039_duplicate_sources_translated_together() {
+        let row = |key: &str, source: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        };
+        let mut state = AppState::new();
+        state.set_entries_with_history(vec![
+            row("k0", "Gold", ""),
+            row("k1", "Iron Sword", "剣"),
+            row("k2", "Gold", "金"),
+            row("k3", "Gold", ""),
+        ]);
+        let groups = state.duplicate_sources();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].rows, [0, 2, 3]);
+        let targets = |state: &AppState| {
+            state
+                .entries()
+                .iter()
+                .map(|entry| entry.target_text.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            state.set_duplicate_target("Gold", "ゴールド", false),
+            Some(2)
+        );
+        assert_eq!(targets(&state), ["ゴールド", "剣", "金", "ゴールド"]);
+        assert_eq!(state.provenance["k3"], Provenance::Manual);
+        assert!(state.edit_meta.contains_key("k0"));
+        assert_eq!(
+            state.set_duplicate_target("Gold", "ゴールド", true),
+            Some(1)
+        );
+        assert_eq!(targets(&state), ["ゴールド", "剣", "ゴールド", "ゴールド"]);
+        assert_eq!(
+            state.set_duplicate_target("Gold", "ゴールド", true),
+            Some(0)
+        );
+        assert_eq!(state.set_duplicate_target("Silver", "銀", true), None);
+
+        dispatch(&mut state, AppAction::Undo).expect("undo");
+        assert_eq!(targets(&state), ["ゴールド", "剣", "金", "ゴールド"]);
+        dispatch(&mut state, AppAction::Undo).expect("undo");
+        assert_eq!(targets(&state), ["", "剣", "金", ""]);
+        assert!(dispatch(
+            &mut state,
+            AppAction::SetDuplicateTarget {
+                source: "Silver".to_string(),
+                target: "銀".to_string(),
+            },
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn t_app_040_inconsistent_targets_unified_in_one_step() {
+        let row = |key: &str, source: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        };
+        let mut state = AppState::new();
+        state.set_entries_with_history(vec![
+            row("k0", "Gold", "金"),
+            row("k1", "Gold", "ゴールド"),
+            row("k2", "Iron", "鉄"),
+            row("k3", "Gold", "ゴールド"),
+        ]);
+        state.select("k0");
+        let groups = state.duplicate_sources();
+        assert!(groups[0].is_inconsistent(state.entries()));
+
+        dispatch(
+            &mut state,
+            AppAction::UnifyTarget {
+                source: "Gold".to_string(),
+                target: "ゴールド".to_string(),
+            },
+        )
+        .expect("unify");
+        let targets = |state: &AppState| {
+            state
+                .entries()
+                .iter()
+                .map(|entry| entry.target_text.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(targets(&state), ["ゴールド", "ゴールド", "鉄", "ゴールド"]);
+        assert_eq!(state.edit_target, "ゴールド");
+        assert!(state.file_status.contains("訳文を統一しました: 1行"));
+        assert!(!state.duplicate_sources()[0].is_inconsistent(state.entries()));
+
+        dispatch(&mut state, AppAction::Undo).expect("undo");
+        assert_eq!(targets(&state), ["金", "ゴールド", "鉄", "ゴールド"]);
+    }
+
+    #[test]
+    fn t_app_042_npc_names_transliterated_into_review_queue() {
+        let row = |key: &str, source: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        };
+        let mut state = AppState::new();
+        state.set_entries_with_history(vec![
+            row("NPC_:00012EB8:FULL:0", "Lydia", ""),
+            row("NPC_:00012EB9:FULL:0", "Ulfric Stormcloak", "ウルフリック"),
+            row("NPC_:00012EBA:SHRT:0", "Farkas", ""),
+            row("NPC_:00012EBB:FULL:0", "the guard", ""),
+            row("WEAP:00012EB7:FULL:0", "Dawnbreaker", ""),
+        ]);
+        let targets = |state: &AppState| {
+            state
+                .entries()
+                .iter()
+                .map(|entry| entry.target_text.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        dispatch(
+            &mut state,
+            AppAction::TransliterateNames {
+                selected_only: false,
+            },
+        )
+        .expect("npc names");
+        assert_eq!(
+            targets(&state),
+            ["リディア", "ウルフリック", "ファーカス", "", ""]
+        );
+        assert!(state.in_review("NPC_:00012EB8:FULL:0"));
+        assert_eq!(
+            state.provenance["NPC_:00012EBA:SHRT:0"],
+            Provenance::Transliteration
+        );
+        assert!(!state.in_review("NPC_:00012EB9:FULL:0"));
+        assert!(state
+            .file_status
+            .contains("カタカナ化しました: 2行（要確認）"));
+
+        state.select("WEAP:00012EB7:FULL:0");
+        dispatch(
+            &mut state,
+            AppAction::TransliterateNames {
+                selected_only: true,
+            },
+        )
+        .expect("selected row");
+        assert_eq!(state.entries()[4].target_text, "ドーンブリーカー");
+        assert_eq!(state.edit_target, "ドーンブリーカー");
+
+        dispatch(&mut state, AppAction::Undo).expect("undo");
+        assert_eq!(state.entries()[4].target_text, "");
+        state.select("NPC_:00012EBB:FULL:0");
+        let err = dispatch(
+            &mut state,
+            AppAction::TransliterateNames {
+                selected_only: true,
+            },
+        )
+        .expect_err("not a proper noun");
+        assert!(err.contains("固有名詞がありません"));
+    }
+
+    #[test]
+    fn t_app_043_dictionary_built_from_xml_folder() {
+        let dir = std::env::temp_dir().join(format!("xt_app_dict_xml_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("create");
+        std::fs::write(
+            dir.join("Mod.xml"),
+            export_entries(&[Entry {
+                key: "k1".to_string(),
+                source_text: "Iron Sword".into(),
+                target_text: "鉄の剣".into(),
+            }]),
+        )
+        .expect("write xml");
+        let mut state = AppState::new();
+
+        dispatch(&mut state, AppAction::BuildDictionaryFromXml(dir.clone())).expect("build");
+        let dict = state.dict.as_ref().expect("dictionary");
+        assert_eq!(dict.get("Iron Sword").map(|t| &**t), Some("鉄の剣"));
+        assert_eq!(state.dict_status, "辞書構築: pairs=1 files=1 pair_files=1");
+
+        let err = dispatch(
+            &mut state,
+            AppAction::BuildDictionaryFromXml(dir.join("missing")),
+        )
+        .expect_err("missing folder");
+        assert!(err.starts_with("辞書構築失敗: read dir"), "{err}");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn t_app_045_quick_auto_all_fills_filtered_untranslated_rows() {
+        let mut state = AppState::new();
+        let row = |key: &str, source: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        };
+        state.set_entries_with_history(vec![
+            row("k0", "Iron Sword", ""),
+            row("k1", "IRON SWORD", ""),
+            row("k2", "Steel Sword", "鋼の剣"),
+            row("k3", "Iron Shield", ""),
+            row("k4", "Glass Sword", ""),
+        ]);
+        assert!(
+            dispatch(&mut state, AppAction::QuickAutoAll).is_err(),
+            "no dictionary"
+        );
+        state.dict = Some(TranslationDictionary::build_from_entries(&[
+            row("d0", "Iron Sword", "鉄の剣"),
+            row("d1", "Steel Sword", "鋼鉄の剣"),
+            row("d2", "Iron Shield", "鉄の盾"),
+        ]));
+        state.dict_match = MatchOptions::LOOSE;
+        dispatch(&mut state, AppAction::SetQuery("Sword".to_string())).expect("filter");
+
+        dispatch(&mut state, AppAction::QuickAutoAll).expect("quick all");
+        let targets = state
+            .entries()
+            .iter()
+            .map(|entry| &*entry.target_text)
+            .collect::<Vec<_>>();
+        assert_eq!(targets, ["鉄の剣", "鉄の剣", "鋼の剣", "", ""]);
+        assert_eq!(
+            state.dict_status,
+            "Quick自動翻訳(全件): updated=2 exact=1 fuzzy=1"
+        );
+        assert_eq!(
+            state.provenance["k1"],
+            Provenance::Dictionary {
+                score: 30,
+                ambiguous: false
+            }
+        );
+
+        dispatch(&mut state, AppAction::Undo).expect("undo");
+        assert!(state.entries()[..2]
+            .iter()
+            .all(|entry| entry.target_text.is_empty()));
+
+        dispatch(&mut state, AppAction::SetQuery("Glass".to_string())).expect("filter");
+        dispatch(&mut state, AppAction::QuickAutoAll).expect("quick all");
+        assert_eq!(
+            state.dict_status,
+            "Quick自動翻訳(全件): updated=0 exact=0 fuzzy=0"
+        );
+        dispatch(&mut state, AppAction::SetQuery("Steel".to_string())).expect("filter");
+        let err = dispatch(&mut state, AppAction::QuickAutoAll).expect_err("nothing untranslated");
+        assert!(err.contains("未訳の行がありません"), "{err}");
+        assert_eq!(state.entries()[2].target_text, "鋼の剣");
+    }
+
+    #[test]
+    fn t_app_046_dictionary_applied_on_load() {
+        let root = std::env::temp_dir().join(format!("xt_app_load_dict_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let path = root.join("Mod_english.strings");
+        let file = StringsFile::new(
+            [(1, "Iron Sword"), (2, "IRON SWORD"), (3, "Bow")]
+                .into_iter()
+                .map(|(id, text)| StringsEntry {
+                    id,
+                    text: text.to_string(),
+                })
+                .collect(),
+        );
+        std::fs::write(&path, write_strings(&file).expect("encode")).expect("write");
+        let mut state = AppState::new();
+        state.dict = Some(TranslationDictionary::build_from_entries(&[Entry {
+            key: "d0".to_string(),
+            source_text: "Iron Sword".into(),
+            target_text: "鉄の剣".into(),
+        }]));
+        state.dict_match = MatchOptions::LOOSE;
+        state.dict_apply_on_load = false;
+        dispatch(&mut state, AppAction::LoadStrings(path.clone())).expect("load");
+        assert!(state.entries().iter().all(|e| e.target_text.is_empty()));
+
+        state.dict_apply_on_load = true;
+        dispatch(&mut state, AppAction::LoadStrings(path.clone())).expect("reload");
+        let targets = state
+            .entries()
+            .iter()
+            .map(|entry| &*entry.target_text)
+            .collect::<Vec<_>>();
+        assert_eq!(targets, ["鉄の剣", "", ""], "exact matches only");
+        assert_eq!(state.dict_status, "読み込み時の辞書適用: updated=1");
+        let key = state.entries()[0].key.clone();
+        assert!(state.provenance[&key].needs_review());
+        dispatch(&mut state, AppAction::Undo).expect("undo");
+        assert_eq!(state.entries()[0].target_text, "");
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_app_047_damaged_plugin_loads_the_records_that_parse() {
+        fn record(tag: &[u8; 4], form_id: u32, full: &[u8]) -> Vec<u8> {
+            let mut data = b"FULL".to_vec();
+            data.extend_from_slice(&(full.len() as u16).to_le_bytes());
+            data.extend_from_slice(full);
+            let mut out = tag.to_vec();
+            out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            out.extend_from_slice(&0u32.to_le_bytes());
+            out.extend_from_slice(&form_id.to_le_bytes());
+            out.extend_from_slice(&[0u8; 8]);
+            out.extend(data);
+            out
+        }
+        let root = std::env::temp_dir().join(format!("xt_app_damaged_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let path = root.join("Damaged.esp");
+        let mut plugin = record(b"WEAP", 0x0000_0A01, b"Iron Sword\0");
+        let mut broken = record(b"WEAP", 0x0000_0A02, b"Broken\0");
+        // FULL claims more bytes than the record holds.
+        broken[28] = 0x40;
+        plugin.extend(broken);
+        plugin.extend(record(b"ARMO", 0x0000_0A03, b"Iron Helmet\0"));
+        std::fs::write(&path, &plugin).expect("write");
+
+        let mut state = AppState::new();
+        dispatch(&mut state, AppAction::LoadPlugin(path.clone())).expect("load");
+        let sources = state
+            .entries()
+            .iter()
+            .map(|entry| &*entry.source_text)
+            .collect::<Vec<_>>();
+        assert_eq!(sources, ["Iron Helmet", "Iron Sword"]);
+        assert!(state.loaded_esp_strings.is_some(), "not the byte scanner");
+        assert!(
+            state
+                .file_status
+                .ends_with("解析できないレコード 1 件をスキップしました（詳細はログ）"),
+            "{}",
+            state.file_status
+        );
+        let _ = std::fs::remove_dir_all(&root);
     }
 }
@@ -1,35 +1,71 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
-use std::thread;
 use std::time::{Duration, Instant};
 
 use eframe::egui::{
     self, Align, Align2, Color32, FontData, FontDefinitions, FontFamily, Layout, RichText,
     ScrollArea, TextEdit, TopBottomPanel,
 };
-use xt_core::dictionary::{DictionaryBuildStats, TranslationDictionary};
-use xt_core::import_export::{apply_xml_default, import_entries, XmlApplyStats};
-use xt_core::model::Entry;
+use xt_core::candidates::{CandidateSource, Provenance};
+use xt_core::dictionary::{detect_languages, DictionaryTarget, TranslationDictionary};
+use xt_core::diff::{char_diff, PluginDiffKind, SpanKind, TextSpan};
+use xt_core::duplicates::DuplicateSource;
+use xt_core::encoding::Encoding;
+use xt_core::formats::esp::{record_flag_names, StringStorage, StringsLocation};
+use xt_core::formats::strings::StringsWarning;
+use xt_core::game::{default_encoding, GameProfile};
+use xt_core::hybrid::hybrid_counts;
+use xt_core::import_export::ApplyProfile;
+use xt_core::katakana;
+use xt_core::loadorder::Override;
+use xt_core::model::SharedText;
+use xt_core::package::PackageManifest;
+use xt_core::pipeline::{
+    list_backups, with_suffix_path, BackupFile, BackupPolicy, LocalizationChange, OutputTemplate,
+    PlannedChange, SavePlan, WriteBlock,
+};
+use xt_core::placeholders;
+use xt_core::rewrite::{RewriteRule, Rewriter};
+use xt_core::search::MatchOptions;
+use xt_core::spellcheck::SpellChecker;
+use xt_core::validation::{CustomRegexRule, SaveGuard, Severity, ValidationConfig};
 
 use crate::actions::{
-    apply_quick_auto_selection, dispatch, run_save_job, AppAction, SaveJobData, SaveMode,
+    dispatch, save_plan, AppAction, DroppedFile, PackageRequest, SaveMode, TargetSource,
+};
+use crate::background::{
+    BackgroundJobs, JobNotice, JobRequest, SaveBlocked, LARGE_XML_EDITOR_THRESHOLD_BYTES,
 };
-use crate::state::{row_fields, AppState, Tab};
+use crate::columns::{ColumnLayout, EntryColumn, EntrySort};
+use crate::keymap::{Command, KeyChord, Keymap};
+use crate::log::{self, LogLevel};
+use crate::prefs::{
+    load_session_prefs, save_session_prefs, user_dictionary_path, DictionaryPrefs, Mo2Prefs,
+    RewritePrefs, SavePrefs, SessionPrefs, WindowGeometry,
+};
+use crate::state::{format_unix_utc, now_unix_seconds, row_fields, AppState, Tab};
+use crate::stats::{Tally, TranslationStats};
 
-const LARGE_XML_EDITOR_THRESHOLD_BYTES: usize = 256 * 1024;
-const ENTRY_COL_EDID_WIDTH: f32 = 120.0;
-const ENTRY_COL_RECORD_WIDTH: f32 = 84.0;
-const ENTRY_COL_TEXT_WIDTH: f32 = 240.0;
-const ENTRY_COL_LD_WIDTH: f32 = 26.0;
 const XT_ACCENT: Color32 = Color32::from_rgb(42, 157, 194);
 
 pub fn launch() -> eframe::Result<()> {
     crate::hotpatch::init_hotpatch();
-    let options = eframe::NativeOptions::default();
+    let session = load_session_prefs().unwrap_or_else(|err| {
+        log::emit(LogLevel::Warn, &err);
+        SessionPrefs::default()
+    });
+    let mut options = eframe::NativeOptions::default();
+    if let Some(window) = session.window {
+        options.viewport = options
+            .viewport
+            .with_position([window.x, window.y])
+            .with_inner_size([window.width, window.height])
+            .with_maximized(window.maximized);
+    }
     eframe::run_native(
         "xtrans-rs",
         options,
-        Box::new(|_cc| Ok(Box::new(XtransApp::default()))),
+        Box::new(|_cc| Ok(Box::new(XtransApp::with_session(session)))),
     )
 }
 
@@ -38,271 +74,499 @@ pub struct XtransApp {
     state: AppState,
     fonts_configured: bool,
     style_configured: bool,
-    pending_job: Option<PendingJob>,
+    jobs: BackgroundJobs,
     show_large_xml_editor: bool,
+    validation_settings: Option<ValidationConfig>,
+    rewrite_settings: Option<RewriteDraft>,
+    pending_xml_file: Option<PathBuf>,
+    checkpoint_name: String,
+    /// Column layout while a header edge is being dragged; saved on release.
+    column_draft: Option<ColumnLayout>,
+    keymap_settings: Option<KeymapDraft>,
+    preferences: Option<PreferencesDraft>,
+    backup_manager: Option<BackupManager>,
+    /// 競合の解決 dialog; holds the priority used by 一括解決.
+    conflicts: Option<Vec<CandidateSource>>,
+    package: Option<PackageDraft>,
+    save_blocked: Option<SaveBlocked>,
+    save_preview: Option<SavePreview>,
+    go_to: Option<GoToDialog>,
+    /// When the entries first had changes not yet autosaved.
+    unsaved_since: Option<Instant>,
+    /// Grid rows drawn in the last frame.
+    visible_rows: std::ops::Range<usize>,
+    /// Scroll the grid to the selected row if it is out of view.
+    scroll_to_selection: bool,
+    focus_target_editor: bool,
+    inline_edit: Option<InlineEdit>,
+    /// Target being edited in the ハイブリッド tab.
+    hybrid_edit: Option<InlineEdit>,
+    /// Translator note being edited in the ホーム tab.
+    note_edit: Option<InlineEdit>,
+    dictionary_view: DictionaryView,
+    duplicate_view: DuplicateView,
+    /// Dictionary targets for the editor's row, by row key, dictionary
+    /// revision and match options.
+    suggestions: Option<((String, u64, MatchOptions), Vec<DictionaryTarget>)>,
+    source_diff: DiffCache,
+    target_diff: DiffCache,
+    /// 上書き tab list, by the open plugins' documents and revisions.
+    overrides: Option<OverrideList>,
+    /// 統計 tab figures and when they were collected.
+    stats: Option<(Instant, TranslationStats)>,
+    /// Window, tab and per-file state as of the last frame.
+    session: SessionPrefs,
+    /// File whose state `session` tracks; a different one was just loaded.
+    session_file: Option<PathBuf>,
+    /// First unsaved change to `session`.
+    session_changed_at: Option<Instant>,
+    /// [`AppState::document_id`] the per-document view state above is for.
+    document: u64,
+    /// Document tab waiting for confirmation to close with unsaved edits.
+    pending_close: Option<usize>,
+}
+
+/// Quiet period before session changes (e.g. a window drag) are written.
+const SESSION_SAVE_DELAY: Duration = Duration::from_secs(1);
+
+/// How often the 統計 tab recounts while it is shown.
+const STATS_REFRESH: Duration = Duration::from_secs(1);
+
+/// Fields overridden among the open plugins, by the documents and
+/// revisions they were found in.
+type OverrideList = (Vec<(usize, u64)>, Vec<Override>);
+
+/// Last character diff shown in the editor pane, recomputed when either
+/// text changes.
+#[derive(Default)]
+struct DiffCache {
+    old: String,
+    new: String,
+    spans: Vec<TextSpan>,
+}
+
+impl DiffCache {
+    fn get(&mut self, old: &str, new: &str) -> &[TextSpan] {
+        if self.old != old || self.new != new {
+            old.clone_into(&mut self.old);
+            new.clone_into(&mut self.new);
+            self.spans = char_diff(old, new);
+        }
+        &self.spans
+    }
+}
+
+/// Target cell being edited in the grid; committed on Enter.
+struct InlineEdit {
+    key: String,
+    text: String,
+    /// Focus the text field when it is first drawn.
+    focus: bool,
 }
 
-struct PendingJob {
-    started_at: Instant,
-    label: String,
-    receiver: Receiver<JobResult>,
+/// 辞書 tab: filter, add/edit form and the rows listed for them.
+#[derive(Default)]
+struct DictionaryView {
+    query: String,
+    by_frequency: bool,
+    /// Source of the pair loaded into the form; `None` adds a new pair.
+    editing: Option<String>,
+    source: String,
+    target: String,
+    rows: Vec<DictionaryRow>,
+    /// Dictionary and entries revisions, query and order `rows` were built for.
+    rows_for: Option<(u64, u64, String, bool)>,
+}
+
+/// 重複原文 tab: filters, the group target being edited (keyed by source
+/// text) and the groups, by document and revision.
+#[derive(Default)]
+struct DuplicateView {
+    untranslated_only: bool,
+    /// Only groups whose rows disagree on the target (訳揺れ).
+    inconsistent_only: bool,
+    edit: Option<InlineEdit>,
+    groups: Option<((u64, u64), Vec<DuplicateSource>)>,
+}
+
+struct DictionaryRow {
+    source: SharedText,
+    target: SharedText,
+    /// Loaded rows with this exact source.
+    count: usize,
+    alternatives: Vec<DictionaryTarget>,
+}
+
+/// 置換ルール設定 being edited, with the text tried in its sandbox.
+struct RewriteDraft {
+    prefs: RewritePrefs,
+    sample: String,
 }
 
-enum JobResult {
-    Xml(Result<XmlApplyResult, String>),
-    BuildDictionary(Result<BuildDictionaryResult, String>),
-    QuickAuto(Result<QuickAutoResult, String>),
-    Save(Result<SaveResult, String>),
+/// Shortcut settings being edited.
+struct KeymapDraft {
+    keymap: Keymap,
+    /// Command waiting for its new chord.
+    capturing: Option<Command>,
+    note: String,
 }
 
-struct XmlApplyResult {
-    source_label: Option<String>,
-    xml_text: String,
-    merged: Vec<Entry>,
-    stats: XmlApplyStats,
+/// 環境設定 being edited.
+struct PreferencesDraft {
+    dictionary: DictionaryPrefs,
+    save: SavePrefs,
+    /// 出力ファイル名 as typed; saved once it parses.
+    output_template: String,
+    mo2: Mo2Prefs,
+    extract_cache: bool,
+    /// Languages found under `scanned_root`.
+    languages: Vec<String>,
+    scanned_root: Option<String>,
+    scan_error: String,
 }
 
-struct BuildDictionaryResult {
-    dict: TranslationDictionary,
-    stats: DictionaryBuildStats,
+/// バックアップ管理 contents for the loaded file.
+struct BackupManager {
+    backups: Vec<BackupFile>,
+    error: String,
+    /// Backup waiting for the user to confirm a restore or delete.
+    confirm: Option<(PathBuf, BackupChange)>,
 }
 
-struct QuickAutoResult {
-    next: Vec<Entry>,
-    updated: usize,
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BackupChange {
+    Restore,
+    Delete,
 }
 
-struct SaveResult {
-    path: PathBuf,
+/// 翻訳パッケージ作成 being edited.
+struct PackageDraft {
+    manifest: PackageManifest,
+    dsd: bool,
+    error: String,
+}
+
+/// 保存内容をプレビュー: what a save in `mode` would write.
+struct SavePreview {
     mode: SaveMode,
+    plan: Result<SavePlan, String>,
+}
+
+/// フォームID/EDIDへ移動: the typed form id or EDID.
+#[derive(Default)]
+struct GoToDialog {
+    query: String,
+    error: Option<String>,
+    focus: bool,
 }
 
 impl XtransApp {
+    /// App restoring the active tab of `session`; per-file state is restored
+    /// when that file is loaded again.
+    pub fn with_session(session: SessionPrefs) -> Self {
+        let mut app = Self::default();
+        app.state.active_tab = session.active_tab;
+        app.session = session;
+        app
+    }
+
+    /// Records the window, tab and loaded file's filter/sort/selection,
+    /// restores a newly loaded file's saved state, and writes the session
+    /// prefs once changes settle or the window is closing.
+    fn sync_session(&mut self, ctx: &egui::Context, now: Instant) {
+        let mut next = self.session.clone();
+        next.active_tab = self.state.active_tab;
+        let (outer, inner, maximized, minimized, closing) = ctx.input(|input| {
+            let viewport = input.viewport();
+            (
+                viewport.outer_rect,
+                viewport.inner_rect,
+                viewport.maximized.unwrap_or(false),
+                viewport.minimized.unwrap_or(false),
+                viewport.close_requested(),
+            )
+        });
+        if let (Some(outer), Some(inner), false) = (outer, inner, minimized) {
+            // A maximized window keeps the size it is restored to.
+            next.window = match (next.window, maximized) {
+                (Some(window), true) => Some(WindowGeometry {
+                    maximized: true,
+                    ..window
+                }),
+                _ => Some(WindowGeometry {
+                    x: outer.min.x,
+                    y: outer.min.y,
+                    width: inner.width(),
+                    height: inner.height(),
+                    maximized,
+                }),
+            };
+        }
+
+        let file = self.state.session_file().map(Path::to_path_buf);
+        if file != self.session_file {
+            if let Some(saved) = file.as_deref().and_then(|file| next.file(file)).cloned() {
+                self.state.restore_file_session(&saved);
+                self.scroll_to_selection = true;
+            }
+            self.session_file = file;
+        } else if let Some(file) = &file {
+            next.remember_file(file, self.state.file_session());
+        }
+
+        if next != self.session {
+            self.session = next;
+            self.session_changed_at.get_or_insert(now);
+        }
+        let Some(changed_at) = self.session_changed_at else {
+            return;
+        };
+        let wait = SESSION_SAVE_DELAY.saturating_sub(now.saturating_duration_since(changed_at));
+        if wait.is_zero() || closing {
+            if let Err(err) = save_session_prefs(&self.session) {
+                log::emit(LogLevel::Warn, &err);
+            }
+            self.session_changed_at = None;
+        } else {
+            ctx.request_repaint_after(wait);
+        }
+    }
+
+    /// Runs `action`, as a background job when it has one.
     fn run_action(&mut self, action: AppAction) {
-        if let Err(err) = dispatch(&mut self.state, action) {
-            if self.state.file_status.is_empty() {
-                self.state.file_status = err;
+        match self.jobs.run(&mut self.state, action) {
+            Ok(notice) => self.show_job_notices(notice),
+            Err(err) => {
+                // Failures that already went through a status line are logged there.
+                if self.state.file_status != err && self.state.dict_status != err {
+                    log::emit(LogLevel::Error, &err);
+                }
+                if self.state.file_status.is_empty() {
+                    self.state.file_status = err.clone();
+                }
+                self.state.last_error = Some(err);
+            }
+        }
+    }
+
+    fn run_command(&mut self, command: Command) {
+        if command == Command::GoToRecord {
+            if self.state.record_index.is_empty() {
+                self.state.set_file_status(
+                    LogLevel::Info,
+                    "フォームID/EDIDへの移動にはPluginを読み込んでください".to_string(),
+                );
+                return;
             }
+            self.go_to = Some(GoToDialog {
+                focus: true,
+                ..GoToDialog::default()
+            });
+            return;
+        }
+        self.run_action(command.action());
+        self.scroll_to_selection |= command.moves_selection();
+        self.focus_target_editor |= command.focuses_editor();
+    }
+
+    /// Hover text naming the chord bound to `command`.
+    fn shortcut_hint(&self, command: Command) -> String {
+        match self.state.keymap().chord(command) {
+            Some(chord) => format!("{} ({})", command.label(), chord.label()),
+            None => command.label().to_string(),
         }
     }
 
     fn is_blocked(&self) -> bool {
-        self.pending_job.is_some()
-    }
-
-    fn try_start_job<F>(&mut self, label: impl Into<String>, spawn: F) -> bool
-    where
-        F: FnOnce(Sender<JobResult>) + Send + 'static,
-    {
-        if self.pending_job.is_some() {
-            self.state.file_status = "重い処理を実行中です".to_string();
-            return false;
-        }
-        let label = label.into();
-        let (tx, rx) = mpsc::channel::<JobResult>();
-        thread::spawn(move || spawn(tx));
-        self.pending_job = Some(PendingJob {
-            started_at: Instant::now(),
-            label: label.clone(),
-            receiver: rx,
-        });
-        self.state.file_status = format!("{label}...");
-        true
+        self.jobs.locks_entries()
     }
 
-    fn start_xml_apply(&mut self, contents: String, source_label: Option<String>) {
-        let current_entries = self.state.entries().to_vec();
-        let source_label_for_job = source_label.clone();
-        if !self.try_start_job("XML適用", move |tx| {
-            let result = import_entries(&contents)
-                .map_err(|err| format!("{err:?}"))
-                .map(|imported| {
-                    let (merged, stats) = apply_xml_default(&current_entries, &imported);
-                    XmlApplyResult {
-                        source_label: source_label_for_job,
-                        xml_text: contents,
-                        merged,
-                        stats,
-                    }
-                });
-            let _ = tx.send(JobResult::Xml(result));
-        }) {
-            return;
+    fn submit_job(&mut self, request: JobRequest) {
+        let notice = self.jobs.submit(&mut self.state, request);
+        self.show_job_notices(notice);
+    }
+
+    fn cancel_job(&mut self, id: u64) {
+        self.jobs.cancel(&mut self.state, id);
+    }
+
+    fn poll_jobs(&mut self) {
+        let notices = self.jobs.poll(&mut self.state);
+        self.show_job_notices(notices);
+    }
+
+    fn show_job_notices(&mut self, notices: impl IntoIterator<Item = JobNotice>) {
+        for notice in notices {
+            match notice {
+                JobNotice::SaveBlocked(blocked) => self.save_blocked = Some(blocked),
+                JobNotice::XmlApplied { show_text } => self.show_large_xml_editor = show_text,
+            }
         }
-        self.state.xml_error = None;
     }
 
-    fn start_build_dictionary_job(&mut self) {
-        let root = self.state.dict_root.clone();
-        let source_lang = self.state.dict_source_lang.clone();
-        let target_lang = self.state.dict_target_lang.clone();
-        if !self.try_start_job("辞書構築", move |tx| {
-            let result = TranslationDictionary::build_from_strings_dir(
-                &PathBuf::from(root),
-                &source_lang,
-                &target_lang,
-            )
-            .map_err(|err| format!("辞書構築失敗: {err}"))
-            .map(|(dict, stats)| BuildDictionaryResult { dict, stats });
-            let _ = tx.send(JobResult::BuildDictionary(result));
-        }) {
-            return;
+    /// Ctrl+C on the grid copies the selected rows as TSV; Ctrl+Shift+V
+    /// pastes TSV rows back into the matching keys. Text fields keep their
+    /// own copy and paste.
+    fn handle_clipboard(&mut self, ctx: &egui::Context) {
+        let (events, shift) = ctx.input(|input| (input.events.clone(), input.modifiers.shift));
+        for event in events {
+            match event {
+                egui::Event::Copy => {
+                    let Some((tsv, count)) = self.state.selected_rows_tsv() else {
+                        continue;
+                    };
+                    ctx.output_mut(|output| output.copied_text = tsv);
+                    self.state
+                        .set_file_status(LogLevel::Info, format!("{count}行をコピーしました"));
+                }
+                egui::Event::Paste(text) if shift => {
+                    self.run_action(AppAction::PasteRows(text));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Opens files dropped on the window: plugins and Strings load, XML/JSON
+    /// goes to the 翻訳XMLの適用 dialog.
+    fn handle_dropped_files(&mut self, ctx: &egui::Context, blocked: bool) {
+        let dropped = ctx.input(|input| input.raw.dropped_files.clone());
+        for path in dropped.into_iter().filter_map(|file| file.path) {
+            if blocked {
+                self.state.set_file_status(
+                    LogLevel::Warn,
+                    format!("処理中のためドロップを無視しました: {}", path.display()),
+                );
+                continue;
+            }
+            match DroppedFile::from_path(&path) {
+                Some(DroppedFile::Plugin) => self.run_action(AppAction::LoadPlugin(path)),
+                Some(DroppedFile::Strings) => self.run_action(AppAction::LoadStrings(path)),
+                Some(DroppedFile::Xml) => self.pending_xml_file = Some(path),
+                None => self.state.set_file_status(
+                    LogLevel::Warn,
+                    format!("未対応のファイル形式です: {}", path.display()),
+                ),
+            }
         }
-        self.state.dict_status = "辞書構築中...".to_string();
     }
 
-    fn start_quick_auto_job(&mut self) {
-        let dict = self.state.dict.clone();
-        let entries = self.state.entries().to_vec();
-        let selected = self.state.selected_key();
-        if !self.try_start_job("Quick自動翻訳", move |tx| {
-            let result = apply_quick_auto_selection(dict.as_ref(), &entries, selected)
-                .map_err(|err| err.to_string())
-                .map(|(next, updated)| QuickAutoResult { next, updated });
-            let _ = tx.send(JobResult::QuickAuto(result));
-        }) {
+    fn draw_drop_hint(&self, ctx: &egui::Context) {
+        if ctx.input(|input| input.raw.hovered_files.is_empty()) {
             return;
         }
-        self.state.dict_status = "Quick自動翻訳中...".to_string();
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("drop_hint"),
+        ));
+        let screen = ctx.screen_rect();
+        painter.rect_filled(screen, 0.0, Color32::from_black_alpha(160));
+        painter.text(
+            screen.center(),
+            Align2::CENTER_CENTER,
+            "ドロップして開く（Plugin / Strings / 翻訳XML）",
+            egui::FontId::proportional(20.0),
+            Color32::WHITE,
+        );
     }
 
-    fn start_save_job(&mut self, mode: SaveMode) {
-        let data = SaveJobData::from_state(&self.state);
-        let label = match &mode {
-            SaveMode::Overwrite => "保存",
-            SaveMode::Auto | SaveMode::Path(_) => "別名保存",
-        };
-        let mode_for_job = mode.clone();
-        let _ = self.try_start_job(label, move |tx| {
-            let result = run_save_job(data, mode_for_job.clone())
-                .map(|path| SaveResult {
-                    path,
-                    mode: mode_for_job,
-                })
-                .map_err(|err| format!("保存失敗: {err}"));
-            let _ = tx.send(JobResult::Save(result));
-        });
+    /// Drops edits in progress and caches that belong to the document that
+    /// was active.
+    fn reset_document_view(&mut self) {
+        self.document = self.state.document_id();
+        self.inline_edit = None;
+        self.hybrid_edit = None;
+        self.note_edit = None;
+        self.duplicate_view.edit = None;
+        self.suggestions = None;
+        self.stats = None;
+        self.unsaved_since = None;
+        self.scroll_to_selection = true;
     }
 
-    fn poll_job(&mut self) {
-        let Some(pending) = self.pending_job.as_mut() else {
+    fn draw_document_tabs(&mut self, ui: &mut egui::Ui) {
+        let tabs = self.state.document_tabs();
+        if tabs.len() < 2 && tabs.iter().all(|tab| tab.file.is_none()) {
             return;
-        };
-
-        match pending.receiver.try_recv() {
-            Ok(job_result) => {
-                let elapsed = pending.started_at.elapsed();
-                self.pending_job = None;
-                match job_result {
-                    JobResult::Xml(Ok(done)) => {
-                        let xml_len = done.xml_text.len();
-                        let source_label = done.source_label;
-                        let drop_large_xml_text =
-                            source_label.is_some() && xml_len > LARGE_XML_EDITOR_THRESHOLD_BYTES;
-                        if drop_large_xml_text {
-                            self.state.xml_text.clear();
-                        } else {
-                            self.state.xml_text = done.xml_text;
-                        }
-                        if done.stats.updated > 0 {
-                            self.state.apply_target_updates_with_history(done.merged);
-                        }
-                        self.state.last_xml_stats = Some(done.stats);
-                        self.state.xml_error = None;
-                        self.show_large_xml_editor =
-                            !drop_large_xml_text && xml_len <= LARGE_XML_EDITOR_THRESHOLD_BYTES;
-                        let src = source_label.unwrap_or_else(|| "エディタ".to_string());
-                        let mut status = format!(
-                            "XML適用({src}): updated={} unchanged={} missing={} [{:.2}s]",
-                            self.state
-                                .last_xml_stats
-                                .as_ref()
-                                .map(|s| s.updated)
-                                .unwrap_or(0),
-                            self.state
-                                .last_xml_stats
-                                .as_ref()
-                                .map(|s| s.unchanged)
-                                .unwrap_or(0),
-                            self.state
-                                .last_xml_stats
-                                .as_ref()
-                                .map(|s| s.missing)
-                                .unwrap_or(0),
-                            elapsed.as_secs_f32()
-                        );
-                        if drop_large_xml_text {
-                            status.push_str(" [XML本文は保持しません]");
-                        }
-                        self.state.file_status = status;
-                    }
-                    JobResult::Xml(Err(err)) => {
-                        self.state.xml_error = Some(err.clone());
-                        self.state.file_status =
-                            format!("XML適用失敗 [{:.2}s]", elapsed.as_secs_f32());
-                    }
-                    JobResult::BuildDictionary(Ok(done)) => {
-                        let pairs = done.dict.len();
-                        self.state.dict = Some(done.dict);
-                        self.state.mark_dictionary_built(
-                            pairs,
-                            done.stats.files_seen,
-                            done.stats.file_pairs,
-                        );
-                        self.state.dict_status = format!(
-                            "辞書構築: pairs={} files={} pair_files={}",
-                            pairs, done.stats.files_seen, done.stats.file_pairs
-                        );
-                        self.state.file_status =
-                            format!("辞書構築完了 [{:.2}s]", elapsed.as_secs_f32());
-                    }
-                    JobResult::BuildDictionary(Err(err)) => {
-                        self.state.dict_status = err.clone();
-                        self.state.file_status =
-                            format!("辞書構築失敗 [{:.2}s]", elapsed.as_secs_f32());
-                    }
-                    JobResult::QuickAuto(Ok(done)) => {
-                        if done.updated > 0 {
-                            self.state.apply_target_updates_with_history(done.next);
-                        }
-                        self.state.dict_status = format!("Quick自動翻訳: updated={}", done.updated);
-                        self.state.file_status =
-                            format!("Quick自動翻訳完了 [{:.2}s]", elapsed.as_secs_f32());
-                    }
-                    JobResult::QuickAuto(Err(err)) => {
-                        self.state.dict_status = err.clone();
-                        self.state.file_status =
-                            format!("Quick自動翻訳失敗 [{:.2}s]", elapsed.as_secs_f32());
-                    }
-                    JobResult::Save(Ok(done)) => {
-                        let prefix = match done.mode {
-                            SaveMode::Overwrite => "保存",
-                            SaveMode::Auto | SaveMode::Path(_) => "別名保存",
-                        };
-                        self.state.file_status = format!(
-                            "{}: {} [{:.2}s]",
-                            prefix,
-                            done.path.display(),
-                            elapsed.as_secs_f32()
-                        );
-                    }
-                    JobResult::Save(Err(err)) => {
-                        self.state.file_status = format!("{err} [{:.2}s]", elapsed.as_secs_f32());
-                    }
+        }
+        let active = self.state.active_document();
+        let mut switch = None;
+        let mut close = None;
+        ui.horizontal_wrapped(|ui| {
+            for (index, tab) in tabs.iter().enumerate() {
+                let title = if tab.unsaved {
+                    format!("{} *", tab.title)
+                } else {
+                    tab.title.clone()
+                };
+                let mut response = ui.selectable_label(index == active, title);
+                if let Some(file) = &tab.file {
+                    response = response.on_hover_text(file.display().to_string());
                 }
+                if response.clicked() {
+                    switch = Some(index);
+                }
+                if ui.small_button("×").on_hover_text("閉じる").clicked() {
+                    close = Some(index);
+                }
+                ui.separator();
             }
-            Err(TryRecvError::Empty) => {}
-            Err(TryRecvError::Disconnected) => {
-                self.pending_job = None;
-                self.state.file_status = "重い処理ワーカーが異常終了しました".to_string();
+        });
+        if let Some(index) = switch {
+            self.run_action(AppAction::SwitchDocument(index));
+            self.reset_document_view();
+        }
+        if let Some(index) = close {
+            if tabs[index].unsaved {
+                self.pending_close = Some(index);
+            } else {
+                self.run_action(AppAction::CloseDocument(index));
+                self.reset_document_view();
             }
         }
     }
 
-    fn draw_busy_overlay(&self, ctx: &egui::Context) {
-        let Some(pending) = self.pending_job.as_ref() else {
+    fn draw_close_document(&mut self, ctx: &egui::Context) {
+        let Some(index) = self.pending_close else {
+            return;
+        };
+        let Some(tab) = self.state.document_tabs().into_iter().nth(index) else {
+            self.pending_close = None;
             return;
         };
+        let mut close = false;
+        let mut cancel = false;
+        egui::Window::new("タブを閉じる")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} には未保存の変更があります。破棄して閉じますか？",
+                    tab.title
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("破棄して閉じる").clicked() {
+                        close = true;
+                    }
+                    if ui.button("キャンセル").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+        if close {
+            self.pending_close = None;
+            self.run_action(AppAction::CloseDocument(index));
+            self.reset_document_view();
+        } else if cancel {
+            self.pending_close = None;
+        }
+    }
+
+    /// Modal listing the jobs that lock the entries; returns the id of a job
+    /// whose キャンセル button was clicked.
+    fn draw_busy_overlay(&self, ctx: &egui::Context) -> Option<u64> {
+        let mut cancel = None;
         let rect = ctx.screen_rect();
         let layer =
             egui::LayerId::new(egui::Order::Foreground, egui::Id::new("xml_apply_backdrop"));
@@ -315,42 +579,151 @@ impl XtransApp {
             .show(ctx, |ui| {
                 egui::Frame::window(ui.style()).show(ui, |ui| {
                     ui.vertical_centered(|ui| {
-                        ui.add(egui::Spinner::new());
-                        ui.label(format!("{}を実行しています", pending.label));
-                        ui.label(format!(
-                            "経過: {:.1}s",
-                            pending.started_at.elapsed().as_secs_f32()
-                        ));
-                        ui.label("完了まで操作はできません");
+                        for job in self.jobs.running() {
+                            if !job.kind.uses_entries() {
+                                continue;
+                            }
+                            ui.label(format!("{}を実行しています", job.label));
+                            let progress = job.progress.snapshot();
+                            match progress.fraction() {
+                                Some(fraction) => {
+                                    ui.add(
+                                        egui::ProgressBar::new(fraction).desired_width(280.0).text(
+                                            format!("{}/{}", progress.processed, progress.total),
+                                        ),
+                                    );
+                                }
+                                None => {
+                                    ui.add(egui::Spinner::new());
+                                }
+                            }
+                            if !progress.current_file.is_empty() {
+                                ui.label(RichText::new(&progress.current_file).small());
+                            }
+                            ui.label(format!(
+                                "経過: {:.1}s",
+                                job.started_at.elapsed().as_secs_f32()
+                            ));
+                            if job.kind.cancelable() {
+                                if ui.button("キャンセル").clicked() {
+                                    cancel = Some(job.id);
+                                }
+                            } else {
+                                ui.label("完了まで操作はできません");
+                            }
+                        }
+                        if self.jobs.queued_len() > 0 {
+                            ui.label(format!("待機中: {}件", self.jobs.queued_len()));
+                        }
                     });
                 });
             });
+        cancel
     }
 
-    fn draw_entry_header(&self, ui: &mut egui::Ui) {
-        ui.horizontal(|ui| {
-            ui.add_sized(
-                [ENTRY_COL_EDID_WIDTH, 18.0],
-                egui::Label::new(RichText::new("EDID").color(XT_ACCENT).small().monospace()),
-            );
-            ui.add_sized(
-                [ENTRY_COL_RECORD_WIDTH, 18.0],
-                egui::Label::new(RichText::new("Record").color(XT_ACCENT).small()),
-            );
-            ui.add_sized(
-                [ENTRY_COL_TEXT_WIDTH, 18.0],
-                egui::Label::new(RichText::new("Source").color(XT_ACCENT).small()),
-            );
-            ui.add_sized(
-                [ENTRY_COL_TEXT_WIDTH, 18.0],
-                egui::Label::new(RichText::new("Target").color(XT_ACCENT).small()),
-            );
-            ui.add_sized(
-                [ENTRY_COL_LD_WIDTH, 18.0],
-                egui::Label::new(RichText::new("LD").color(XT_ACCENT).small().monospace()),
+    /// One entry per running job in the status bar; returns the id of a job
+    /// whose × button was clicked.
+    fn draw_job_status(&self, ui: &mut egui::Ui) -> Option<u64> {
+        let mut cancel = None;
+        for job in self.jobs.running() {
+            ui.separator();
+            match job.progress.snapshot().fraction() {
+                Some(fraction) => {
+                    ui.add(egui::ProgressBar::new(fraction).desired_width(80.0));
+                }
+                None => {
+                    ui.add(egui::Spinner::new().size(12.0));
+                }
+            }
+            ui.label(
+                RichText::new(format!(
+                    "{} {:.1}s",
+                    job.label,
+                    job.started_at.elapsed().as_secs_f32()
+                ))
+                .small(),
             );
+            if job.kind.cancelable() && ui.small_button("×").on_hover_text("キャンセル").clicked()
+            {
+                cancel = Some(job.id);
+            }
+        }
+        if self.jobs.queued_len() > 0 {
+            ui.label(RichText::new(format!("待機中 {}件", self.jobs.queued_len())).small());
+        }
+        cancel
+    }
+
+    /// Column titles. Dragging a title's right edge resizes the column; the
+    /// context menu reorders, hides and shows columns.
+    fn draw_entry_header(&mut self, ui: &mut egui::Ui) {
+        let mut layout = self
+            .column_draft
+            .clone()
+            .unwrap_or_else(|| self.state.columns().clone());
+        let sort = self.state.sort();
+        let mut sort_by = None;
+        let mut resizing = false;
+        let mut commit = false;
+        ui.horizontal(|ui| {
+            let visible = layout.visible().copied().collect::<Vec<_>>();
+            for setting in visible {
+                let arrow = match sort {
+                    Some(sort) if sort.column == setting.column && sort.descending => " ▼",
+                    Some(sort) if sort.column == setting.column => " ▲",
+                    _ => "",
+                };
+                let mut title = RichText::new(format!("{}{arrow}", setting.column.label()))
+                    .color(XT_ACCENT)
+                    .small();
+                if matches!(setting.column, EntryColumn::Edid | EntryColumn::Ld) {
+                    title = title.monospace();
+                }
+                let label = ui
+                    .add_sized(
+                        [f32::from(setting.width), 18.0],
+                        egui::Label::new(title).sense(egui::Sense::click()),
+                    )
+                    .on_hover_text("クリックで並べ替え、右クリックで列の並べ替え・表示切替");
+                if label.clicked() {
+                    sort_by = Some(setting.column);
+                }
+                label.context_menu(|ui| {
+                    commit |= column_menu_ui(ui, &mut layout, setting.column);
+                });
+
+                let edge = egui::Rect::from_x_y_ranges(
+                    label.rect.right()..=label.rect.right() + ui.spacing().item_spacing.x,
+                    label.rect.y_range(),
+                );
+                let handle = ui
+                    .interact(
+                        edge,
+                        ui.id().with(("column_resize", setting.column.id())),
+                        egui::Sense::drag(),
+                    )
+                    .on_hover_cursor(egui::CursorIcon::ResizeHorizontal);
+                if handle.dragged() {
+                    if let Some(pointer) = handle.interact_pointer_pos() {
+                        let width = (pointer.x - label.rect.left()).clamp(0.0, f32::from(u16::MAX));
+                        layout.set_width(setting.column, width as u16);
+                    }
+                    resizing = true;
+                }
+                commit |= handle.drag_stopped();
+            }
         });
         ui.separator();
+        if let Some(column) = sort_by {
+            self.run_action(AppAction::SetSort(EntrySort::cycle(sort, column)));
+            self.scroll_to_selection = true;
+        }
+        if commit {
+            self.column_draft = None;
+            self.run_action(AppAction::SetColumns(layout));
+        } else if resizing {
+            self.column_draft = Some(layout);
+        }
     }
 
     fn draw_menu(&mut self, ui: &mut egui::Ui) {
@@ -374,59 +747,346 @@ impl XtransApp {
                         self.run_action(AppAction::LoadPlugin(path));
                     }
                 }
+                if ui.button("新バージョンのPluginと比較").clicked() {
+                    ui.close_menu();
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("Plugin", &["esp", "esm", "esl", "xtplugin"])
+                        .pick_file()
+                    {
+                        self.run_action(AppAction::CompareWithNewPlugin(path));
+                    }
+                }
                 if ui.button("翻訳XMLを開く").clicked() {
                     ui.close_menu();
                     if let Some(path) = rfd::FileDialog::new()
-                        .add_filter("XML", &["xml"])
+                        .add_filter("XML/JSON", &["xml", "json"])
                         .pick_file()
                     {
-                        match std::fs::read_to_string(&path) {
-                            Ok(contents) => {
-                                self.start_xml_apply(contents, Some(path.display().to_string()))
-                            }
-                            Err(err) => {
-                                self.state.file_status = format!("read {}: {err}", path.display());
-                            }
-                        }
+                        self.pending_xml_file = Some(path);
                     }
                 }
                 if ui.button("翻訳XMLを書き出し").clicked() {
                     ui.close_menu();
                     self.run_action(AppAction::ExportXmlToEditor);
                 }
+                if ui.button("選択範囲をXML書き出し").clicked() {
+                    ui.close_menu();
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("XML/JSON", &["xml", "json"])
+                        .save_file()
+                    {
+                        self.run_action(AppAction::ExportSelectionXml(path));
+                    }
+                }
+                if ui.button("フィルタ結果をXML書き出し").clicked() {
+                    ui.close_menu();
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("XML/JSON", &["xml", "json"])
+                        .save_file()
+                    {
+                        self.run_action(AppAction::ExportFilteredXml(path));
+                    }
+                }
+                if ui.button("翻訳JSONを書き出し").clicked() {
+                    ui.close_menu();
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("JSON", &["json"])
+                        .save_file()
+                    {
+                        self.run_action(AppAction::ExportJson(path));
+                    }
+                }
+                if ui.button("xTranslator XMLを書き出し").clicked() {
+                    ui.close_menu();
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("XML", &["xml"])
+                        .save_file()
+                    {
+                        self.run_action(AppAction::ExportXtranslatorXml(path));
+                    }
+                }
+                if ui
+                    .button("レビューシート(HTML)を書き出し")
+                    .on_hover_text(
+                        "原文・訳文・状態・検証結果をブラウザで確認できる1ファイルにまとめる",
+                    )
+                    .clicked()
+                {
+                    ui.close_menu();
+                    let file_name = format!(
+                        "{}_review.html",
+                        self.state
+                            .loaded_plugin_path
+                            .as_deref()
+                            .or(self.state.loaded_strings_path.as_deref())
+                            .and_then(|path| path.file_stem())
+                            .map_or("xtrans".into(), |stem| stem.to_string_lossy())
+                    );
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("HTML", &["html"])
+                        .set_file_name(file_name)
+                        .save_file()
+                    {
+                        self.run_action(AppAction::ExportReviewSheet(path));
+                    }
+                }
+                if ui
+                    .button("レビュー修正を取り込み")
+                    .on_hover_text(
+                        "編集したレビューシート(HTML)かキー・訳文列のあるCSVの訳文をキーで反映する",
+                    )
+                    .clicked()
+                {
+                    ui.close_menu();
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("HTML/CSV", &["html", "htm", "csv", "tsv", "txt"])
+                        .pick_file()
+                    {
+                        self.run_action(AppAction::ImportCorrections(path));
+                    }
+                }
+                if ui.button("DSD JSONで保存").clicked() {
+                    ui.close_menu();
+                    let file_name = self
+                        .state
+                        .loaded_plugin_path
+                        .as_deref()
+                        .and_then(|path| path.file_stem())
+                        .map(|stem| format!("{}_translation.json", stem.to_string_lossy()))
+                        .unwrap_or_else(|| "translation.json".to_string());
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("JSON", &["json"])
+                        .set_file_name(file_name)
+                        .save_file()
+                    {
+                        self.run_action(AppAction::ExportDsdJson(path));
+                    }
+                }
+                if ui.button("Localizedとして書き出し").clicked() {
+                    ui.close_menu();
+                    let file_name = self
+                        .state
+                        .loaded_plugin_path
+                        .as_deref()
+                        .and_then(|path| path.file_name())
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    if let Some(path) = rfd::FileDialog::new()
+                        .set_title("出力先Plugin（Stringsは同じフォルダのStrings/へ）")
+                        .add_filter("Plugin", &["esp", "esm", "esl"])
+                        .set_file_name(file_name)
+                        .save_file()
+                    {
+                        self.run_action(AppAction::ExportLocalized(path));
+                    }
+                }
+                if ui.button("翻訳パッケージ作成").clicked() {
+                    ui.close_menu();
+                    self.open_package();
+                }
                 if ui.button("上書き保存").clicked() {
                     ui.close_menu();
-                    self.start_save_job(SaveMode::Overwrite);
+                    self.run_action(AppAction::SaveOverwrite);
                 }
                 if ui.button("別名保存").clicked() {
                     ui.close_menu();
                     if let Some(path) = rfd::FileDialog::new().save_file() {
-                        self.start_save_job(SaveMode::Path(path));
+                        self.run_action(AppAction::SaveAsPath(path));
                     } else {
-                        self.start_save_job(SaveMode::Auto);
+                        self.run_action(AppAction::SaveAsAuto);
                     }
                 }
+                if ui
+                    .add_enabled(
+                        self.state.session_file().is_some(),
+                        egui::Button::new("保存内容をプレビュー"),
+                    )
+                    .clicked()
+                {
+                    ui.close_menu();
+                    self.open_save_preview(SaveMode::Overwrite);
+                }
+                if ui
+                    .add_enabled(
+                        self.state.session_file().is_some(),
+                        egui::Button::new("バックアップ管理"),
+                    )
+                    .clicked()
+                {
+                    ui.close_menu();
+                    self.open_backup_manager();
+                }
             });
 
             ui.menu_button("翻訳", |ui| {
                 if ui.button("辞書を構築").clicked() {
                     ui.close_menu();
-                    self.start_build_dictionary_job();
+                    self.run_action(AppAction::BuildDictionary);
+                }
+                if ui.button("XMLフォルダから辞書を構築").clicked() {
+                    ui.close_menu();
+                    if let Some(dir) = rfd::FileDialog::new()
+                        .set_title("翻訳XML/JSONのフォルダ")
+                        .pick_folder()
+                    {
+                        self.run_action(AppAction::BuildDictionaryFromXml(dir));
+                    }
                 }
                 if ui.button("Quick自動翻訳 (Ctrl-R)").clicked() {
                     ui.close_menu();
-                    self.start_quick_auto_job();
+                    self.run_action(AppAction::QuickAuto);
+                }
+                if ui
+                    .button("Quick自動翻訳 (表示中の未訳すべて)")
+                    .on_hover_text("検索・フィルタで表示中の未訳行すべてに辞書と参照訳を適用する")
+                    .clicked()
+                {
+                    ui.close_menu();
+                    self.run_action(AppAction::QuickAutoAll);
+                }
+                if ui.button("参照訳を読み込む").clicked() {
+                    ui.close_menu();
+                    if let Some(path) = rfd::FileDialog::new()
+                        .set_title("翻訳済みStrings (例: Skyrim_japanese.strings)")
+                        .add_filter("Strings", &["strings", "dlstrings", "ilstrings"])
+                        .pick_file()
+                    {
+                        self.run_action(AppAction::LoadReference(path));
+                    }
+                }
+                if let Some(reference) = &self.state.reference {
+                    let name = reference
+                        .path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    if ui.button(format!("参照訳を解除 ({name})")).clicked() {
+                        ui.close_menu();
+                        self.run_action(AppAction::ClearReference);
+                    }
+                }
+                let plugin = self.state.loaded_plugin_path.is_some();
+                ui.add_enabled_ui(plugin, |ui| {
+                    ui.menu_button("別プラグインから訳文をコピー", |ui| {
+                        let active = self.state.active_document();
+                        let others = self
+                            .state
+                            .open_plugins()
+                            .into_iter()
+                            .filter(|open| open.document != active)
+                            .map(|open| (open.document, open.name))
+                            .collect::<Vec<_>>();
+                        for (document, name) in others {
+                            if ui.button(format!("タブ: {name}")).clicked() {
+                                ui.close_menu();
+                                self.run_action(AppAction::CopyTargetsFrom(
+                                    TargetSource::Document(document),
+                                ));
+                            }
+                        }
+                        if ui.button("翻訳済みPluginファイル...").clicked() {
+                            ui.close_menu();
+                            if let Some(path) = rfd::FileDialog::new()
+                                .set_title("翻訳済みPlugin")
+                                .add_filter("Plugin", &["esp", "esm", "esl"])
+                                .pick_file()
+                            {
+                                self.run_action(AppAction::CopyTargetsFrom(
+                                    TargetSource::Reference(path),
+                                ));
+                            }
+                        }
+                    });
+                });
+                ui.separator();
+                for (label, selected_only) in [
+                    ("置換ルールを適用（選択行）", true),
+                    ("置換ルールを適用（全行）", false),
+                ] {
+                    if ui.button(label).clicked() {
+                        ui.close_menu();
+                        self.run_action(AppAction::ApplyRewriteRules { selected_only });
+                    }
+                }
+                for (label, selected_only) in [
+                    ("カタカナ化（選択行）", true),
+                    ("カタカナ化（NPC名）", false),
+                ] {
+                    if ui
+                        .button(label)
+                        .on_hover_text("未訳の固有名詞に原文のカタカナ読みを入れる（要確認）")
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.run_action(AppAction::TransliterateNames { selected_only });
+                    }
+                }
+                let conflicts = self.state.candidates.conflict_count();
+                if ui.button(format!("競合の解決 ({conflicts})")).clicked() {
+                    ui.close_menu();
+                    self.conflicts = Some(CandidateSource::PRIORITY.to_vec());
                 }
             });
 
             ui.menu_button("オプション", |ui| {
+                if ui.button("環境設定").clicked() {
+                    ui.close_menu();
+                    self.open_preferences();
+                }
                 if ui.button("言語ペアを既定に戻す").clicked() {
                     ui.close_menu();
                     self.run_action(AppAction::ResetDictLanguagePair);
                 }
+                ui.horizontal(|ui| {
+                    ui.label("作成者名");
+                    let response = ui.text_edit_singleline(&mut self.state.author);
+                    if response.lost_focus() {
+                        let author = self.state.author.clone();
+                        self.run_action(AppAction::SetAuthor(author));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("ゲーム");
+                    for profile in GameProfile::ALL {
+                        if ui
+                            .radio(self.state.game_profile == profile, profile.label())
+                            .clicked()
+                        {
+                            self.run_action(AppAction::SetGameProfile(profile));
+                        }
+                    }
+                });
+                if !self.state.user_prefs_error.is_empty() {
+                    ui.colored_label(Color32::LIGHT_RED, &self.state.user_prefs_error);
+                }
+                if ui.button("検証ルール設定").clicked() {
+                    ui.close_menu();
+                    self.validation_settings = Some(self.state.validation_config.clone());
+                }
+                if ui.button("置換ルール設定").clicked() {
+                    ui.close_menu();
+                    self.rewrite_settings = Some(RewriteDraft {
+                        prefs: self.state.rewrite_prefs().clone(),
+                        sample: String::new(),
+                    });
+                }
+                if ui.button("ショートカット設定").clicked() {
+                    ui.close_menu();
+                    self.keymap_settings = Some(KeymapDraft {
+                        keymap: self.state.keymap().clone(),
+                        capturing: None,
+                        note: String::new(),
+                    });
+                }
             });
 
             ui.menu_button("ツール", |ui| {
+                let plugin = !self.state.record_index.is_empty();
+                let hint = self.shortcut_hint(Command::GoToRecord);
+                if ui.add_enabled(plugin, egui::Button::new(hint)).clicked() {
+                    ui.close_menu();
+                    self.run_command(Command::GoToRecord);
+                }
                 if ui.button("Undo").clicked() {
                     ui.close_menu();
                     self.run_action(AppAction::Undo);
@@ -435,6 +1095,63 @@ impl XtransApp {
                     ui.close_menu();
                     self.run_action(AppAction::Redo);
                 }
+                if ui.button("Strings差分レポート").clicked() {
+                    ui.close_menu();
+                    let pick = |title: &str| {
+                        rfd::FileDialog::new()
+                            .set_title(title)
+                            .add_filter("Strings", &["strings", "dlstrings", "ilstrings"])
+                            .pick_file()
+                    };
+                    if let Some(old) = pick("旧Strings") {
+                        if let Some(new) = pick("新Strings") {
+                            self.run_action(AppAction::DiffStringsFiles(old, new));
+                        }
+                    }
+                }
+                if ui.button("Strings情報").clicked() {
+                    ui.close_menu();
+                    if let Some(path) = rfd::FileDialog::new()
+                        .set_title("Strings情報")
+                        .add_filter("Strings", &["strings", "dlstrings", "ilstrings"])
+                        .pick_file()
+                    {
+                        self.run_action(AppAction::InspectStringsFile(path));
+                    }
+                }
+                ui.separator();
+                for (label, suffix, change) in [
+                    (
+                        "Localized形式に変換",
+                        "_localized",
+                        LocalizationChange::Localize,
+                    ),
+                    (
+                        "埋め込み文字列に変換",
+                        "_delocalized",
+                        LocalizationChange::Delocalize,
+                    ),
+                ] {
+                    if ui.button(label).clicked() {
+                        ui.close_menu();
+                        let file_name = self
+                            .state
+                            .loaded_plugin_path
+                            .as_deref()
+                            .map(|path| with_suffix_path(path, suffix))
+                            .and_then(|path| path.file_name().map(|name| name.to_owned()))
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("Plugin", &["esp", "esm", "esl"])
+                            .set_file_name(file_name)
+                            .save_file()
+                        {
+                            self.run_action(AppAction::ConvertLocalization(path, change));
+                        }
+                    }
+                }
+                ui.separator();
                 if ui.button("ログタブを開く").clicked() {
                     ui.close_menu();
                     self.run_action(AppAction::SetActiveTab(Tab::Log));
@@ -446,12 +1163,31 @@ impl XtransApp {
     fn draw_toolbar(&mut self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
             ui.label("検索");
-            let mut query = self.state.pane.query().to_string();
+            let mut query = self.state.query_input().to_string();
+            let response = ui.add(TextEdit::singleline(&mut query).desired_width(280.0));
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                self.run_action(AppAction::SetQuery(query));
+            } else if response.changed() {
+                self.run_action(AppAction::EditQuery(query));
+            }
+            let mut search_options = self.state.search_options();
+            if match_options_ui(ui, &mut search_options) {
+                self.run_action(AppAction::SetSearchOptions(search_options));
+            }
+            if self.state.is_indexing() {
+                ui.weak("索引作成中…");
+            }
+            let mut untranslated_only = self.state.untranslated_only;
+            if ui.checkbox(&mut untranslated_only, "未翻訳のみ").changed() {
+                self.run_action(AppAction::SetUntranslatedOnly(untranslated_only));
+            }
+            let mut review_only = self.state.review_only;
             if ui
-                .add(TextEdit::singleline(&mut query).desired_width(280.0))
+                .checkbox(&mut review_only, "要確認のみ")
+                .on_hover_text("辞書・機械翻訳・原文一致で入り、まだ承認していない行")
                 .changed()
             {
-                self.run_action(AppAction::SetQuery(query));
+                self.run_action(AppAction::SetReviewOnly(review_only));
             }
 
             if ui.button("Validate").clicked() {
@@ -482,56 +1218,134 @@ impl XtransApp {
 
     fn draw_entry_list(&mut self, ui: &mut egui::Ui) {
         let filtered_len = self.state.filtered_len();
-        let selected_key = self.state.selected_key();
         let mut next_selection = None;
+        let modifiers = ui.input(|input| input.modifiers);
         ui.label(RichText::new("Entries").color(XT_ACCENT).strong());
         ui.separator();
         self.draw_entry_header(ui);
+        let columns = self
+            .column_draft
+            .clone()
+            .unwrap_or_else(|| self.state.columns().clone());
+
+        let mut scroll = ScrollArea::vertical();
+        if std::mem::take(&mut self.scroll_to_selection) {
+            let selected_row = self
+                .state
+                .selected_key()
+                .and_then(|key| self.state.filtered_position(&key));
+            if let Some(row) = selected_row {
+                let first = self.visible_rows.start;
+                // Keep a row of margin at either edge of the view.
+                if row <= first || row + 2 >= self.visible_rows.end {
+                    let row_height = 22.0 + ui.spacing().item_spacing.y;
+                    scroll =
+                        scroll.vertical_scroll_offset(row.saturating_sub(2) as f32 * row_height);
+                }
+            }
+        }
 
-        ScrollArea::vertical().show_rows(ui, 22.0, filtered_len, |ui, row_range| {
+        let mut inline = self.inline_edit.take();
+        // Some(true) commits the inline edit, Some(false) drops it.
+        let mut inline_done = None;
+        scroll.show_rows(ui, 22.0, filtered_len, |ui, row_range| {
+            self.visible_rows = row_range.clone();
             for row in row_range {
-                let Some(entry) = self.state.filtered_entry(row) else {
+                let Some(entry) = self.state.filtered_entry(row).cloned() else {
                     continue;
                 };
-                let selected = selected_key.as_deref() == Some(entry.key.as_str());
-                let (edid, record_id, ld) = row_fields(&entry.key, &entry.target_text);
+                let selected = self.state.is_row_selected(&entry.key);
+                let (edid, record_id, ld) =
+                    row_fields(&self.state.editor_ids, &entry.key, &entry.target_text);
+                let ld = if self.state.validated_keys.contains(&entry.key) {
+                    "V"
+                } else {
+                    ld
+                };
+                let note = self.state.notes.get(&entry.key);
+                let ld = match note {
+                    Some(_) => format!("{ld}*"),
+                    None => ld.to_string(),
+                };
+                let provenance = self.state.provenance.get(&entry.key).copied();
+                let suspect = provenance.is_some_and(Provenance::is_suspect);
+                let origin = provenance.map(Provenance::label);
+                let vanilla = self.state.is_vanilla(&entry.source_text);
                 ui.horizontal(|ui| {
-                    let source_preview = text_preview(&entry.source_text, 72);
-                    let target_preview = text_preview(&entry.target_text, 72);
-                    let clicked = ui
-                        .add_sized(
-                            [ENTRY_COL_EDID_WIDTH, 18.0],
-                            egui::SelectableLabel::new(
-                                selected,
-                                RichText::new(edid).monospace().size(12.0),
+                    let mut clicked = false;
+                    for (at, setting) in columns.visible().enumerate() {
+                        let size = [f32::from(setting.width), 18.0];
+                        if let Some(edit) = inline
+                            .as_mut()
+                            .filter(|edit| edit.key == entry.key)
+                            .filter(|_| setting.column == EntryColumn::Target)
+                        {
+                            let response = ui.add_sized(
+                                size,
+                                TextEdit::singleline(&mut edit.text).id_source("inline_target"),
+                            );
+                            if std::mem::take(&mut edit.focus) {
+                                response.request_focus();
+                            }
+                            if response.lost_focus() {
+                                inline_done =
+                                    Some(ui.input(|input| input.key_pressed(egui::Key::Enter)));
+                            }
+                            continue;
+                        }
+                        let text = match setting.column {
+                            EntryColumn::Edid => RichText::new(edid).monospace().size(12.0),
+                            EntryColumn::Record => RichText::new(record_id),
+                            EntryColumn::Source if vanilla => {
+                                RichText::new(text_preview(&entry.source_text, 72))
+                                    .color(Color32::LIGHT_BLUE)
+                            }
+                            EntryColumn::Source => {
+                                RichText::new(text_preview(&entry.source_text, 72))
+                            }
+                            EntryColumn::Target => {
+                                RichText::new(text_preview(&entry.target_text, 72))
+                            }
+                            EntryColumn::Ld if note.is_some() => RichText::new(ld.as_str())
+                                .monospace()
+                                .color(Color32::YELLOW),
+                            EntryColumn::Ld => RichText::new(ld.as_str()).monospace(),
+                            EntryColumn::Origin if suspect => {
+                                RichText::new(origin.as_deref().unwrap_or_default())
+                                    .small()
+                                    .color(Color32::YELLOW)
+                            }
+                            EntryColumn::Origin => {
+                                RichText::new(origin.as_deref().unwrap_or_default()).small()
+                            }
+                        };
+                        // The first column shows the row selection.
+                        let response = if at == 0 {
+                            ui.add_sized(size, egui::SelectableLabel::new(selected, text))
+                        } else {
+                            ui.add_sized(size, egui::Label::new(text).sense(egui::Sense::click()))
+                        };
+                        let response = match (setting.column, &origin) {
+                            (EntryColumn::Target | EntryColumn::Origin, Some(origin)) => {
+                                response.on_hover_text(format!("訳文の出所: {origin}"))
+                            }
+                            (EntryColumn::Origin, None) if !entry.target_text.is_empty() => {
+                                response.on_hover_text("読み込んだときの訳文")
+                            }
+                            (EntryColumn::Ld, _) if note.is_some() => response.on_hover_text(
+                                format!("メモ: {}", note.map(String::as_str).unwrap_or_default()),
                             ),
-                        )
-                        .clicked()
-                        || ui
-                            .add_sized(
-                                [ENTRY_COL_RECORD_WIDTH, 18.0],
-                                egui::Label::new(record_id).sense(egui::Sense::click()),
-                            )
-                            .clicked()
-                        || ui
-                            .add_sized(
-                                [ENTRY_COL_TEXT_WIDTH, 18.0],
-                                egui::Label::new(source_preview).sense(egui::Sense::click()),
-                            )
-                            .clicked()
-                        || ui
-                            .add_sized(
-                                [ENTRY_COL_TEXT_WIDTH, 18.0],
-                                egui::Label::new(target_preview).sense(egui::Sense::click()),
-                            )
-                            .clicked()
-                        || ui
-                            .add_sized(
-                                [ENTRY_COL_LD_WIDTH, 18.0],
-                                egui::Label::new(RichText::new(ld).monospace())
-                                    .sense(egui::Sense::click()),
-                            )
-                            .clicked();
+                            _ => response,
+                        };
+                        clicked |= response.clicked();
+                        if setting.column == EntryColumn::Target && response.double_clicked() {
+                            inline = Some(InlineEdit {
+                                key: entry.key.clone(),
+                                text: entry.target_text.to_string(),
+                                focus: true,
+                            });
+                        }
+                    }
                     if clicked {
                         next_selection = Some(entry.key.clone());
                     }
@@ -540,168 +1354,2707 @@ impl XtransApp {
             }
         });
 
-        if let Some(key) = next_selection {
-            self.run_action(AppAction::SelectEntry(key));
+        match (inline_done, inline) {
+            (None, edit) => self.inline_edit = edit,
+            (Some(true), Some(edit)) => self.run_action(AppAction::SetEntryTarget {
+                key: edit.key,
+                target: edit.text,
+            }),
+            (Some(_), _) => {}
+        }
+
+        if let Some(key) = next_selection {
+            let action = if modifiers.command {
+                AppAction::ToggleSelection(key)
+            } else if modifiers.shift {
+                AppAction::SelectRange(key)
+            } else {
+                AppAction::SelectEntry(key)
+            };
+            self.run_action(action);
+        }
+    }
+
+    fn draw_tabs(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            for (tab, label) in Tab::all() {
+                let selected = self.state.active_tab == tab;
+                let text = if selected {
+                    RichText::new(label).color(XT_ACCENT).strong()
+                } else {
+                    RichText::new(label)
+                };
+                if ui
+                    .add_sized([110.0, 22.0], egui::SelectableLabel::new(selected, text))
+                    .clicked()
+                {
+                    self.run_action(AppAction::SetActiveTab(tab));
+                }
+            }
+        });
+    }
+
+    /// Type, form id, EDID and flags of the selected row's record, and its
+    /// other translatable strings; clicking one selects its row.
+    fn draw_record_context(&mut self, ui: &mut egui::Ui, key: &str) {
+        let Some(record) = self.state.record_context(key) else {
+            return;
+        };
+        let mut select = None;
+        egui::CollapsingHeader::new("レコード情報")
+            .id_source("record_context")
+            .default_open(true)
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(RichText::new(&record.record_type).monospace().strong());
+                    ui.label(RichText::new(format!("{:08X}", record.form_id)).monospace());
+                    ui.label(format!(
+                        "EDID: {}",
+                        record.editor_id.as_deref().unwrap_or("(なし)")
+                    ));
+                    let names = record_flag_names(record.flags);
+                    let flags = if names.is_empty() {
+                        format!("{:#010X}", record.flags)
+                    } else {
+                        format!("{:#010X} ({})", record.flags, names.join(", "))
+                    };
+                    ui.label(RichText::new(format!("フラグ: {flags}")).weak());
+                });
+                egui::Grid::new("record_context_strings")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label(RichText::new("サブレコード").small().weak());
+                        ui.label(RichText::new("原文").small().weak());
+                        ui.label(RichText::new("訳文").small().weak());
+                        ui.end_row();
+                        for string in &record.strings {
+                            let label = format!("{} #{}", string.subrecord_type, string.index);
+                            let current = string.key == key;
+                            if ui
+                                .selectable_label(current, RichText::new(label).monospace())
+                                .clicked()
+                                && !current
+                            {
+                                select = Some(string.key.clone());
+                            }
+                            ui.label(text_preview(&string.source_text, 48));
+                            ui.label(text_preview(&string.target_text, 48));
+                            ui.end_row();
+                        }
+                    });
+            });
+        if let Some(key) = select {
+            self.run_action(AppAction::SelectEntry(key));
+        }
+    }
+
+    /// The row's translator note. It is stored when the field loses focus;
+    /// until then the note shown follows the row.
+    fn draw_note(&mut self, ui: &mut egui::Ui, key: &str) {
+        let mut edit = match self.note_edit.take() {
+            Some(edit) if edit.key == key => edit,
+            _ => InlineEdit {
+                key: key.to_string(),
+                text: self.state.notes.get(key).cloned().unwrap_or_default(),
+                focus: false,
+            },
+        };
+        let response = ui
+            .horizontal(|ui| {
+                ui.label("メモ:");
+                ui.add(
+                    TextEdit::singleline(&mut edit.text)
+                        .id_source("entry_note")
+                        .desired_width(f32::INFINITY)
+                        .hint_text("レビュー用のコメント（訳文には含まれません）"),
+                )
+            })
+            .inner;
+        if response.lost_focus() {
+            self.run_action(AppAction::SetNote {
+                key: edit.key,
+                note: edit.text,
+            });
+        } else if response.has_focus() {
+            self.note_edit = Some(edit);
+        }
+    }
+
+    fn draw_home_tab(&mut self, ui: &mut egui::Ui) {
+        if let Some(key) = self.state.selected_key() {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(format!("Key: {key}")).color(XT_ACCENT));
+                if let Some(edit) = self.state.edit_meta.get(&key) {
+                    let author = if edit.author.is_empty() {
+                        "(作成者未設定)"
+                    } else {
+                        edit.author.as_str()
+                    };
+                    ui.label(
+                        RichText::new(format!(
+                            "最終更新: {author} {}",
+                            format_unix_utc(edit.modified_at)
+                        ))
+                        .weak(),
+                    );
+                }
+            });
+            self.draw_record_context(ui, &key);
+            self.draw_note(ui, &key);
+            ui.add(
+                TextEdit::multiline(&mut self.state.edit_source)
+                    .desired_rows(4)
+                    .hint_text("原文"),
+            );
+            let checker = self.state.spellchecker.clone();
+            let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+                let mut job = spell_layout_job(ui, text, checker.as_deref());
+                job.wrap.max_width = wrap_width;
+                ui.fonts(|fonts| fonts.layout_job(job))
+            };
+            let mut target_edit = TextEdit::multiline(&mut self.state.edit_target)
+                .id_source("target_editor")
+                .desired_rows(4)
+                .hint_text("訳文");
+            if checker.is_some() {
+                target_edit = target_edit.layouter(&mut layouter);
+            }
+            let target_response = ui.add(target_edit);
+            if std::mem::take(&mut self.focus_target_editor) {
+                target_response.request_focus();
+            }
+            self.draw_dictionary_suggestions(ui, &key);
+            self.draw_katakana_suggestion(ui);
+            if let Some(checker) = &checker {
+                let mut words: Vec<String> = checker
+                    .misspelled_words(&self.state.edit_target)
+                    .into_iter()
+                    .map(|span| span.word)
+                    .collect();
+                words.sort();
+                words.dedup();
+                if !words.is_empty() {
+                    let mut ignore = None;
+                    ui.horizontal_wrapped(|ui| {
+                        ui.colored_label(Color32::RED, "スペル:");
+                        for word in &words {
+                            if ui
+                                .small_button(word)
+                                .on_hover_text("クリックで無視リストに追加")
+                                .clicked()
+                            {
+                                ignore = Some(word.clone());
+                            }
+                        }
+                    });
+                    if let Some(word) = ignore {
+                        self.run_action(AppAction::IgnoreSpelling(word));
+                    }
+                }
+            }
+
+            self.draw_edit_diff(ui, &key);
+
+            ui.horizontal(|ui| {
+                if ui.button("Apply Edit").clicked() {
+                    self.run_action(AppAction::ApplyEdit);
+                }
+                let hint = self.shortcut_hint(Command::ApplyEditAndNext);
+                if ui.button("反映して次へ").on_hover_text(hint).clicked() {
+                    self.run_command(Command::ApplyEditAndNext);
+                }
+                let hint = self.shortcut_hint(Command::CopySourceToTarget);
+                if ui.button("原文コピー").on_hover_text(hint).clicked() {
+                    self.run_command(Command::CopySourceToTarget);
+                }
+                let mut validated = self.state.validated_keys.contains(&key);
+                let hint = self.shortcut_hint(Command::ToggleValidated);
+                if ui
+                    .checkbox(&mut validated, "検証済み")
+                    .on_hover_text(hint)
+                    .changed()
+                {
+                    self.run_command(Command::ToggleValidated);
+                }
+                if self.state.in_review(&key) {
+                    let hint = self.shortcut_hint(Command::ApproveReview);
+                    if ui.button("承認").on_hover_text(hint).clicked() {
+                        self.run_command(Command::ApproveReview);
+                    }
+                    let hint = self.shortcut_hint(Command::RejectReview);
+                    if ui.button("差し戻し").on_hover_text(hint).clicked() {
+                        self.run_command(Command::RejectReview);
+                    }
+                }
+                let hint = self.shortcut_hint(Command::QuickAuto);
+                if ui.button("Quick Auto").on_hover_text(hint).clicked() {
+                    self.run_command(Command::QuickAuto);
+                }
+                if ui.button("Undo").clicked() {
+                    self.run_action(AppAction::Undo);
+                }
+                if ui.button("Redo").clicked() {
+                    self.run_action(AppAction::Redo);
+                }
+            });
+        } else {
+            ui.label("行を選択してください。");
+        }
+    }
+
+    /// The dictionary's targets for the row's source, most used first and
+    /// with the row's placeholders; a click puts one in the editor.
+    fn draw_dictionary_suggestions(&mut self, ui: &mut egui::Ui, key: &str) {
+        let cache_key = (
+            key.to_string(),
+            self.state.dict_revision,
+            self.state.dict_match,
+        );
+        if self.suggestions.as_ref().map(|(at, _)| at) != Some(&cache_key) {
+            let source = self
+                .state
+                .selected_entry()
+                .map(|entry| entry.source_text.clone())
+                .unwrap_or_default();
+            let targets = self
+                .state
+                .dict
+                .as_ref()
+                .and_then(|dict| dict.lookup_targets(&source, self.state.dict_match))
+                .map(|(matched, targets)| {
+                    targets
+                        .iter()
+                        .map(|target| DictionaryTarget {
+                            text: placeholders::transfer(&target.text, matched, &source)
+                                .map_or_else(|| target.text.clone(), SharedText::from),
+                            count: target.count,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            self.suggestions = Some((cache_key, targets));
+        }
+        let Some((_, targets)) = &self.suggestions else {
+            return;
+        };
+        if targets.is_empty() {
+            return;
+        }
+        let mut chosen = None;
+        ui.horizontal_wrapped(|ui| {
+            ui.label(RichText::new("辞書候補:").small().color(XT_ACCENT));
+            for target in targets {
+                let current = target.text.as_str() == self.state.edit_target;
+                let text = format!("{} ({})", text_preview(&target.text, 40), target.count);
+                if ui
+                    .selectable_label(current, text)
+                    .on_hover_text(format!(
+                        "辞書で{}件使われている訳。クリックで訳文に入力",
+                        target.count
+                    ))
+                    .clicked()
+                {
+                    chosen = Some(target.text.to_string());
+                }
+            }
+            if targets.len() > 1 {
+                ui.colored_label(Color32::YELLOW, "複数の訳あり");
+            }
+        });
+        if let Some(target) = chosen {
+            self.run_action(AppAction::SetEditTarget(target));
+        }
+    }
+
+    /// Katakana reading of an untranslated proper noun; a click puts it in
+    /// the editor.
+    fn draw_katakana_suggestion(&mut self, ui: &mut egui::Ui) {
+        let Some(kana) = self
+            .state
+            .selected_entry()
+            .filter(|entry| entry.target_text.is_empty())
+            .and_then(|entry| katakana::transliterate(&entry.source_text))
+        else {
+            return;
+        };
+        let mut chosen = false;
+        ui.horizontal_wrapped(|ui| {
+            ui.label(RichText::new("カタカナ候補:").small().color(XT_ACCENT));
+            let current = kana == self.state.edit_target;
+            chosen = ui
+                .selectable_label(current, &kana)
+                .on_hover_text("原文から規則で作った読み。クリックで訳文に入力")
+                .clicked();
+        });
+        if chosen {
+            self.run_action(AppAction::SetEditTarget(kana));
+        }
+    }
+
+    /// Old/new source from the plugin comparison and the saved (or previous
+    /// version's) target against the editor, side by side.
+    fn draw_edit_diff(&mut self, ui: &mut egui::Ui, key: &str) {
+        let change = self.state.source_change(key).cloned();
+        let saved_target = self
+            .state
+            .selected_entry()
+            .map(|entry| entry.target_text.clone())
+            .unwrap_or_default();
+        let (target_label, old_target) = match &change {
+            Some(row) if !row.old_target.is_empty() => {
+                ("訳文（旧訳 → 編集中）", row.old_target.clone())
+            }
+            _ => ("訳文（保存済み → 編集中）", saved_target),
+        };
+        let target_changed = old_target.as_str() != self.state.edit_target;
+        if change.is_none() && !target_changed {
+            return;
+        }
+        egui::CollapsingHeader::new("差分")
+            .default_open(true)
+            .show(ui, |ui| {
+                if let Some(row) = &change {
+                    ui.label(RichText::new("原文（旧 → 新）").small().color(XT_ACCENT));
+                    diff_columns(ui, self.source_diff.get(&row.old_source, &row.new_source));
+                }
+                if target_changed {
+                    ui.label(RichText::new(target_label).small().color(XT_ACCENT));
+                    let spans = self.target_diff.get(&old_target, &self.state.edit_target);
+                    diff_columns(ui, spans);
+                }
+            });
+    }
+
+    fn draw_diff_tab(&mut self, ui: &mut egui::Ui) {
+        let Some(diff) = self.state.plugin_diff.as_ref() else {
+            ui.label("ファイル > 新バージョンのPluginと比較 で差分を作成します。");
+            return;
+        };
+        let summary = diff.summary;
+        ui.label(format!(
+            "変更なし: {} (訳を引継ぎ: {}) / 原文変更: {} / 新規: {} / 削除: {}",
+            summary.unchanged,
+            summary.carried,
+            summary.source_changed,
+            summary.added,
+            summary.removed
+        ));
+        let rows = diff.review_rows().cloned().collect::<Vec<_>>();
+        let mut action = None;
+        ScrollArea::vertical()
+            .id_source("plugin_diff_rows")
+            .max_height(160.0)
+            .show_rows(ui, 20.0, rows.len(), |ui, range| {
+                for row in &rows[range] {
+                    ui.horizontal(|ui| {
+                        let color = match row.kind {
+                            PluginDiffKind::SourceChanged => Color32::YELLOW,
+                            PluginDiffKind::New => Color32::LIGHT_GREEN,
+                            _ => Color32::GRAY,
+                        };
+                        ui.colored_label(color, row.kind.label());
+                        let text = match row.kind {
+                            PluginDiffKind::SourceChanged => format!(
+                                "{}: {} → {}",
+                                row.key,
+                                text_preview(&row.old_source, 40),
+                                text_preview(&row.new_source, 40)
+                            ),
+                            PluginDiffKind::Removed => {
+                                format!("{}: {}", row.key, text_preview(&row.old_source, 72))
+                            }
+                            _ => format!("{}: {}", row.key, text_preview(&row.new_source, 72)),
+                        };
+                        if row.kind == PluginDiffKind::Removed {
+                            ui.label(text);
+                        } else if ui.link(text).clicked() {
+                            action = Some(AppAction::SelectEntry(row.key.clone()));
+                        }
+                        if row.kind == PluginDiffKind::SourceChanged
+                            && !row.old_target.is_empty()
+                            && ui
+                                .small_button("旧訳を適用")
+                                .on_hover_text(row.old_target.as_str())
+                                .clicked()
+                        {
+                            action = Some(AppAction::UsePreviousTranslation(row.key.clone()));
+                        }
+                    });
+                }
+            });
+        if let Some(action) = action {
+            self.run_action(action);
+        }
+    }
+
+    fn draw_overrides_tab(&mut self, ui: &mut egui::Ui) {
+        let revisions = self
+            .state
+            .open_plugins()
+            .iter()
+            .map(|plugin| (plugin.document, plugin.revision))
+            .collect::<Vec<_>>();
+        if self
+            .overrides
+            .as_ref()
+            .is_none_or(|(cached, _)| *cached != revisions)
+        {
+            self.overrides = Some((revisions.clone(), self.state.overrides()));
+        }
+        let Some((_, overrides)) = self.overrides.as_ref() else {
+            return;
+        };
+        if revisions.len() < 2 {
+            ui.label(
+                "マスターとパッチのPluginを両方開くと、同じレコードを定義するPluginを表示します。",
+            );
+            return;
+        }
+        ui.label(format!(
+            "{} 件のフィールドを複数のPluginが定義しています (★ がロード順で優先されるPlugin)",
+            overrides.len()
+        ));
+        let mut action = None;
+        ScrollArea::vertical()
+            .id_source("override_rows")
+            .show(ui, |ui| {
+                for field in overrides {
+                    ui.separator();
+                    let differs = if field.source_differs() {
+                        " 原文が異なります"
+                    } else {
+                        ""
+                    };
+                    ui.label(format!(
+                        "{} {} {}[{}]{differs}",
+                        field.form, field.record_type, field.subrecord_type, field.index
+                    ));
+                    let winner = field.definitions.len() - 1;
+                    for (index, definition) in field.definitions.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            let mark = if index == winner { "★" } else { "　" };
+                            ui.label(format!("{mark} {}", definition.plugin));
+                            ui.label(text_preview(&definition.source, 40));
+                            ui.label("→");
+                            ui.label(text_preview(&definition.target, 40));
+                            let copyable = !definition.target.is_empty()
+                                && field
+                                    .definitions
+                                    .iter()
+                                    .any(|other| other.target != definition.target);
+                            if ui
+                                .add_enabled(copyable, egui::Button::new("この訳を他へコピー"))
+                                .clicked()
+                            {
+                                action = Some(AppAction::CopyOverrideTarget(
+                                    definition.plugin.clone(),
+                                    definition.key.clone(),
+                                ));
+                            }
+                        });
+                    }
+                }
+            });
+        if let Some(action) = action {
+            self.run_action(action);
+        }
+    }
+
+    fn draw_duplicates_tab(&mut self, ui: &mut egui::Ui) {
+        let revision = (self.state.document_id(), self.state.revision());
+        let view = &mut self.duplicate_view;
+        if view
+            .groups
+            .as_ref()
+            .is_none_or(|(cached, _)| *cached != revision)
+        {
+            view.groups = Some((revision, self.state.duplicate_sources()));
+        }
+        let Some((_, groups)) = view.groups.as_ref() else {
+            return;
+        };
+        let entries = self.state.entries();
+        let mut overwrite = self.state.overwrite_duplicates();
+        let inconsistent = groups
+            .iter()
+            .filter(|group| group.is_inconsistent(entries))
+            .count();
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} 種類の原文が複数の行にあります（{} 行、訳揺れ {inconsistent}件）",
+                groups.len(),
+                groups.iter().map(|group| group.rows.len()).sum::<usize>()
+            ));
+            ui.checkbox(&mut view.untranslated_only, "未訳を含むのみ");
+            ui.checkbox(&mut view.inconsistent_only, "訳揺れのみ");
+            ui.checkbox(&mut overwrite, "訳済みの行も上書き")
+                .on_hover_text("オフのときは未訳の行だけに訳文を設定します");
+        });
+        let shown = groups
+            .iter()
+            .filter(|group| !view.untranslated_only || group.untranslated(entries) > 0)
+            .filter(|group| !view.inconsistent_only || group.is_inconsistent(entries))
+            .collect::<Vec<_>>();
+        let mut edit = view.edit.take();
+        // Some(true) commits the edit, Some(false) drops it.
+        let mut edit_done = None;
+        let mut select = None;
+        let mut unify = None;
+        ScrollArea::vertical()
+            .id_source("duplicate_rows")
+            .auto_shrink([false, false])
+            .max_height(ui.available_height().max(160.0))
+            .show_rows(ui, 20.0, shown.len(), |ui, range| {
+                for group in &shown[range] {
+                    let targets = group.target_counts(entries);
+                    let untranslated = group.untranslated(entries);
+                    ui.horizontal(|ui| {
+                        let count = if untranslated > 0 {
+                            format!("{}行 (未訳 {untranslated})", group.rows.len())
+                        } else {
+                            format!("{}行", group.rows.len())
+                        };
+                        ui.add_sized([110.0, 18.0], egui::Label::new(count));
+                        let source = ui
+                            .add_sized(
+                                [320.0, 18.0],
+                                egui::Label::new(text_preview(&group.source, 60))
+                                    .sense(egui::Sense::click()),
+                            )
+                            .on_hover_text("クリックで最初の行を選択");
+                        if source.clicked() {
+                            select = Some(entries[group.rows[0]].key.clone());
+                        }
+                        if let Some(edit) = edit.as_mut().filter(|edit| edit.key == *group.source) {
+                            let response = ui.add_sized(
+                                [320.0, 18.0],
+                                TextEdit::singleline(&mut edit.text).id_source("duplicate_target"),
+                            );
+                            if std::mem::take(&mut edit.focus) {
+                                response.request_focus();
+                            }
+                            if response.lost_focus() {
+                                edit_done =
+                                    Some(ui.input(|input| input.key_pressed(egui::Key::Enter)));
+                            }
+                            return;
+                        }
+                        let label = match targets.as_slice() {
+                            [] => RichText::new("（未訳）").weak(),
+                            [(target, _)] => RichText::new(text_preview(target, 60)),
+                            [(first, _), ..] => RichText::new(format!(
+                                "{} ほか {}種類",
+                                text_preview(first, 40),
+                                targets.len() - 1
+                            ))
+                            .color(Color32::YELLOW),
+                        };
+                        let target = ui
+                            .add_sized(
+                                [320.0, 18.0],
+                                egui::Label::new(label).sense(egui::Sense::click()),
+                            )
+                            .on_hover_text("クリックで全行の訳文を編集");
+                        if target.clicked() {
+                            edit = Some(InlineEdit {
+                                key: group.source.to_string(),
+                                text: targets
+                                    .first()
+                                    .map(|(target, _)| target.to_string())
+                                    .unwrap_or_default(),
+                                focus: true,
+                            });
+                        }
+                        if targets.len() > 1 {
+                            ui.menu_button("統一", |ui| {
+                                for (target, count) in &targets {
+                                    let label = format!("{} ({count}行)", text_preview(target, 40));
+                                    if ui.button(label).clicked() {
+                                        unify =
+                                            Some((group.source.to_string(), target.to_string()));
+                                        ui.close_menu();
+                                    }
+                                }
+                            })
+                            .response
+                            .on_hover_text("選んだ訳文で全行をそろえます");
+                        }
+                    });
+                }
+            });
+
+        match (edit_done, edit) {
+            (None, edit) => self.duplicate_view.edit = edit,
+            (Some(true), Some(edit)) => self.run_action(AppAction::SetDuplicateTarget {
+                source: edit.key,
+                target: edit.text,
+            }),
+            (Some(_), _) => {}
+        }
+        if let Some((source, target)) = unify {
+            self.run_action(AppAction::UnifyTarget { source, target });
+        }
+        if overwrite != self.state.overwrite_duplicates() {
+            self.run_action(AppAction::SetOverwriteDuplicates(overwrite));
+        }
+        if let Some(key) = select {
+            self.run_action(AppAction::SelectEntry(key));
+        }
+    }
+
+    fn draw_history_tab(&mut self, ui: &mut egui::Ui) {
+        let items = self.state.history.items();
+        let position = self.state.history.position();
+        ui.horizontal(|ui| {
+            ui.label("チェックポイント名");
+            ui.text_edit_singleline(&mut self.checkpoint_name);
+            if ui
+                .add_enabled(position > 0, egui::Button::new("現在位置に名前を付ける"))
+                .clicked()
+            {
+                let name = std::mem::take(&mut self.checkpoint_name);
+                self.run_action(AppAction::NameCheckpoint(name));
+            }
+        });
+        let mut jump = None;
+        ScrollArea::vertical()
+            .id_source("history_items")
+            .max_height(140.0)
+            .show(ui, |ui| {
+                if ui.selectable_label(position == 0, "(読込直後)").clicked() {
+                    jump = Some(0);
+                }
+                for (idx, item) in items.iter().enumerate() {
+                    let mut text = RichText::new(&item.label);
+                    if !item.applied {
+                        text = text.weak();
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.selectable_label(position == idx + 1, text).clicked() {
+                            jump = Some(idx + 1);
+                        }
+                        if let Some(name) = &item.checkpoint {
+                            ui.label(RichText::new(format!("★ {name}")).color(XT_ACCENT));
+                        }
+                    });
+                }
+            });
+        if let Some(position) = jump {
+            self.run_action(AppAction::JumpToHistory(position));
+        }
+    }
+
+    fn draw_hybrid_tab(&mut self, ui: &mut egui::Ui) {
+        if self.state.hybrid.is_empty() {
+            ui.label(
+                "ESP/ESM を読み込むと、埋め込み文字列と STRINGS の文字列をレコードごとに表示します",
+            );
+            return;
+        }
+        let counts = hybrid_counts(&self.state.hybrid);
+        let plugin = self
+            .state
+            .loaded_plugin_path
+            .as_deref()
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let context = &self.state.plugin_context;
+        let strings_dir = match context.strings_location() {
+            StringsLocation::Root(root) => context.profile.strings_dir(root),
+            StringsLocation::Overlay { output, .. } => output.to_path_buf(),
+        };
+        ui.label(format!(
+            "{} レコード / 埋め込み {}件 → {plugin} / STRINGS {}件 → {}",
+            self.state.hybrid.len(),
+            counts.inline,
+            counts.localized,
+            strings_dir.display()
+        ));
+        ui.label(
+            RichText::new(
+                "保存時は各文字列を元の格納先（Plugin 本体か Strings ファイル）へ書き戻します",
+            )
+            .small()
+            .weak(),
+        );
+
+        // (record, string) per line; `None` is the record's heading.
+        let lines = self
+            .state
+            .hybrid
+            .iter()
+            .enumerate()
+            .flat_map(|(at, record)| {
+                std::iter::once((at, None))
+                    .chain((0..record.strings.len()).map(move |i| (at, Some(i))))
+            })
+            .collect::<Vec<_>>();
+        let mut edit = self.hybrid_edit.take();
+        // Some(true) commits the edit, Some(false) drops it.
+        let mut edit_done = None;
+        let mut select = None;
+        ScrollArea::vertical()
+            .id_source("hybrid_rows")
+            .auto_shrink([false, false])
+            .max_height(ui.available_height().max(160.0))
+            .show_rows(ui, 20.0, lines.len(), |ui, range| {
+                for &(at, line) in &lines[range] {
+                    let record = &self.state.hybrid[at];
+                    let Some(i) = line else {
+                        ui.label(
+                            RichText::new(format!(
+                                "{} {:08X}",
+                                String::from_utf8_lossy(&record.record_type),
+                                record.form_id
+                            ))
+                            .color(XT_ACCENT)
+                            .monospace(),
+                        );
+                        continue;
+                    };
+                    let string = &record.strings[i];
+                    let entries = self.state.entries();
+                    let entry = entries
+                        .get(string.position)
+                        .filter(|entry| entry.key == string.key)
+                        .or_else(|| entries.iter().find(|entry| entry.key == string.key));
+                    let Some(entry) = entry else {
+                        continue;
+                    };
+                    let storage = match string.storage {
+                        StringStorage::Inline => "埋め込み".to_string(),
+                        StringStorage::Localized { kind, id } => {
+                            format!("STRINGS #{id} (.{kind})")
+                        }
+                    };
+                    ui.horizontal(|ui| {
+                        ui.add_space(16.0);
+                        ui.add_sized(
+                            [48.0, 18.0],
+                            egui::Label::new(
+                                RichText::new(String::from_utf8_lossy(&string.subrecord_type))
+                                    .monospace(),
+                            ),
+                        );
+                        ui.add_sized([160.0, 18.0], egui::Label::new(storage));
+                        let source = ui.add_sized(
+                            [320.0, 18.0],
+                            egui::Label::new(text_preview(&entry.source_text, 60))
+                                .sense(egui::Sense::click()),
+                        );
+                        if source.clicked() {
+                            select = Some(entry.key.clone());
+                        }
+                        if let Some(edit) = edit.as_mut().filter(|edit| edit.key == string.key) {
+                            let response = ui.add_sized(
+                                [320.0, 18.0],
+                                TextEdit::singleline(&mut edit.text).id_source("hybrid_target"),
+                            );
+                            if std::mem::take(&mut edit.focus) {
+                                response.request_focus();
+                            }
+                            if response.lost_focus() {
+                                edit_done =
+                                    Some(ui.input(|input| input.key_pressed(egui::Key::Enter)));
+                            }
+                        } else {
+                            let target = ui
+                                .add_sized(
+                                    [320.0, 18.0],
+                                    egui::Label::new(text_preview(&entry.target_text, 60))
+                                        .sense(egui::Sense::click()),
+                                )
+                                .on_hover_text("クリックで編集");
+                            if target.clicked() {
+                                select = Some(entry.key.clone());
+                                edit = Some(InlineEdit {
+                                    key: entry.key.clone(),
+                                    text: entry.target_text.to_string(),
+                                    focus: true,
+                                });
+                            }
+                        }
+                    });
+                }
+            });
+
+        match (edit_done, edit) {
+            (None, edit) => self.hybrid_edit = edit,
+            (Some(true), Some(edit)) => self.run_action(AppAction::SetEntryTarget {
+                key: edit.key,
+                target: edit.text,
+            }),
+            (Some(_), _) => {}
+        }
+        if let Some(key) = select {
+            self.run_action(AppAction::SelectEntry(key));
+        }
+    }
+
+    fn refresh_dictionary_rows(&mut self) {
+        let view = &mut self.dictionary_view;
+        let key = (
+            self.state.dict_revision,
+            self.state.revision(),
+            view.query.clone(),
+            view.by_frequency,
+        );
+        if view.rows_for.as_ref() == Some(&key) {
+            return;
+        }
+        let mut counts = HashMap::<&str, usize>::new();
+        for entry in self.state.entries() {
+            *counts.entry(entry.source_text.as_str()).or_default() += 1;
+        }
+        let query = MatchOptions::LOOSE.fold(view.query.trim()).into_owned();
+        let matches =
+            |text: &str| query.is_empty() || MatchOptions::LOOSE.fold(text).contains(&query);
+        view.rows = self
+            .state
+            .dict
+            .iter()
+            .flat_map(|dict| dict.iter().map(move |pair| (dict, pair)))
+            .filter(|(dict, (source, target))| {
+                matches(source)
+                    || matches(target)
+                    || dict
+                        .alternatives(source)
+                        .iter()
+                        .any(|alt| matches(&alt.text))
+            })
+            .map(|(dict, (source, target))| DictionaryRow {
+                source: source.clone(),
+                target: target.clone(),
+                count: counts.get(source.as_str()).copied().unwrap_or(0),
+                alternatives: dict.alternatives(source).to_vec(),
+            })
+            .collect();
+        if view.by_frequency {
+            view.rows
+                .sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.source.cmp(&b.source)));
+        } else {
+            view.rows.sort_by(|a, b| a.source.cmp(&b.source));
+        }
+        view.rows_for = Some(key);
+    }
+
+    fn draw_dictionary_tab(&mut self, ui: &mut egui::Ui) {
+        let total = self
+            .state
+            .dict
+            .as_ref()
+            .map_or(0, TranslationDictionary::len);
+        let path = user_dictionary_path(&self.state.dict_user_path)
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+        let mut action = None;
+        ui.horizontal(|ui| {
+            if self.state.dict.is_none() {
+                ui.label("辞書未構築");
+            } else {
+                ui.label(format!("{total}件"));
+            }
+            if self.state.dict_unsaved {
+                ui.colored_label(Color32::YELLOW, "未保存の変更あり");
+            }
+            ui.separator();
+            ui.label(
+                RichText::new(format!("ユーザー辞書: {path}"))
+                    .small()
+                    .weak(),
+            )
+            .on_hover_text("オプション > 環境設定 で変更できます");
+            if ui.button("読込").clicked() {
+                action = Some(AppAction::LoadUserDictionary);
+            }
+            if ui
+                .add_enabled(self.state.dict.is_some(), egui::Button::new("保存"))
+                .clicked()
+            {
+                action = Some(AppAction::SaveUserDictionary);
+            }
+        });
+
+        let view = &mut self.dictionary_view;
+        ui.horizontal(|ui| {
+            ui.label("原文");
+            ui.add(TextEdit::singleline(&mut view.source).desired_width(240.0));
+            ui.label("訳文");
+            ui.add(TextEdit::singleline(&mut view.target).desired_width(240.0));
+            let label = if view.editing.is_some() {
+                "更新"
+            } else {
+                "追加"
+            };
+            if ui.button(label).clicked() {
+                action = Some(AppAction::SetDictionaryPair {
+                    previous: view.editing.clone(),
+                    source: view.source.clone(),
+                    target: view.target.clone(),
+                });
+            }
+            if view.editing.is_some() && ui.button("キャンセル").clicked() {
+                view.editing = None;
+                view.source.clear();
+                view.target.clear();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("検索");
+            ui.add(TextEdit::singleline(&mut view.query).desired_width(240.0));
+            ui.checkbox(&mut view.by_frequency, "頻度順")
+                .on_hover_text("読み込んだファイルで原文が現れる行数の多い順");
+        });
+
+        self.refresh_dictionary_rows();
+        let view = &mut self.dictionary_view;
+        ui.label(RichText::new(format!("表示 {}件", view.rows.len())).small());
+        let mut remove = None;
+        ScrollArea::vertical()
+            .id_source("dictionary_rows")
+            .auto_shrink([false, false])
+            .max_height(ui.available_height().max(160.0))
+            .show_rows(ui, 20.0, view.rows.len(), |ui, range| {
+                for row in &view.rows[range] {
+                    ui.horizontal(|ui| {
+                        let editing = view.editing.as_deref() == Some(row.source.as_str());
+                        let source = ui
+                            .add_sized(
+                                [320.0, 18.0],
+                                egui::SelectableLabel::new(editing, text_preview(&row.source, 60)),
+                            )
+                            .on_hover_text("クリックで編集");
+                        ui.add_sized(
+                            [320.0, 18.0],
+                            egui::Label::new(text_preview(&row.target, 60)),
+                        );
+                        ui.add_sized([48.0, 18.0], egui::Label::new(row.count.to_string()))
+                            .on_hover_text("読み込んだファイルの該当行数");
+                        let alternatives = if row.alternatives.is_empty() {
+                            String::new()
+                        } else {
+                            format!("+{}", row.alternatives.len())
+                        };
+                        let label = ui.add_sized(
+                            [40.0, 18.0],
+                            egui::Label::new(RichText::new(alternatives).color(Color32::YELLOW)),
+                        );
+                        if !row.alternatives.is_empty() {
+                            let list = row
+                                .alternatives
+                                .iter()
+                                .map(|alt| {
+                                    format!("{} ({}件)", text_preview(&alt.text, 60), alt.count)
+                                })
+                                .collect::<Vec<_>>()
+                                .join("\n");
+                            label.on_hover_text(format!("ほかの訳:\n{list}"));
+                        }
+                        if source.clicked() {
+                            view.editing = Some(row.source.to_string());
+                            view.source = row.source.to_string();
+                            view.target = row.target.to_string();
+                        }
+                        if ui.small_button("削除").clicked() {
+                            remove = Some(row.source.to_string());
+                        }
+                    });
+                }
+            });
+
+        if let Some(source) = remove {
+            if view.editing.as_deref() == Some(source.as_str()) {
+                view.editing = None;
+            }
+            action = Some(AppAction::RemoveDictionaryPair(source));
+        }
+        let Some(action) = action else {
+            return;
+        };
+        let pair = match &action {
+            AppAction::SetDictionaryPair { source, target, .. } => {
+                Some((source.clone(), target.clone()))
+            }
+            _ => None,
+        };
+        self.run_action(action);
+        let stored = pair.filter(|(source, target)| {
+            let dict = self.state.dict.as_ref();
+            dict.and_then(|dict| dict.get(source))
+                .is_some_and(|stored| stored == target)
+        });
+        if stored.is_some() {
+            let view = &mut self.dictionary_view;
+            view.editing = None;
+            view.source.clear();
+            view.target.clear();
+        }
+    }
+
+    fn draw_stats_tab(&mut self, ui: &mut egui::Ui) {
+        let stale = self
+            .stats
+            .as_ref()
+            .is_none_or(|(at, _)| at.elapsed() >= STATS_REFRESH);
+        if stale {
+            let stats = TranslationStats::collect(
+                &self.state,
+                self.state.session_started_at,
+                now_unix_seconds(),
+            );
+            self.stats = Some((Instant::now(), stats));
+        }
+        let Some((_, stats)) = self.stats.clone() else {
+            return;
+        };
+        ui.ctx().request_repaint_after(STATS_REFRESH);
+
+        let total = stats.total;
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "訳済み {}/{} 行 ({:.1}%) / 単語 {}/{} / 文字 原文 {} 訳文 {}",
+                total.translated,
+                total.rows,
+                total.percent(),
+                total.translated_source_words,
+                total.source_words,
+                total.source_chars,
+                total.target_chars
+            ));
+            if ui.button("CSVで保存").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("CSV", &["csv"])
+                    .set_file_name("translation_stats.csv")
+                    .save_file()
+                {
+                    self.run_action(AppAction::ExportStatsCsv(path));
+                }
+            }
+        });
+        let minutes = stats.session_secs / 60;
+        let pace = match (stats.rows_per_hour(), stats.words_per_hour()) {
+            (Some(rows), Some(words)) => format!("{rows:.0} 行/時 {words:.0} 単語/時"),
+            _ => "-".to_string(),
+        };
+        let remaining = match stats.remaining_hours() {
+            Some(hours) => format!("約 {hours:.1} 時間"),
+            None => "-".to_string(),
+        };
+        ui.label(format!(
+            "このセッション: {}時間{}分 編集 {} 行 ({} 単語) / ペース {} / 残り {} 単語 {}",
+            minutes / 60,
+            minutes % 60,
+            stats.session.rows,
+            stats.session.source_words,
+            pace,
+            total.remaining_words(),
+            remaining
+        ));
+
+        ScrollArea::vertical()
+            .id_source("stats_rows")
+            .max_height(240.0)
+            .show(ui, |ui| {
+                egui::Grid::new("stats_grid").striped(true).show(ui, |ui| {
+                    for title in [
+                        "区分",
+                        "名前",
+                        "行",
+                        "訳済み",
+                        "未訳",
+                        "%",
+                        "原文単語",
+                        "残り単語",
+                    ] {
+                        ui.strong(title);
+                    }
+                    ui.end_row();
+                    let groups = stats
+                        .by_status
+                        .iter()
+                        .map(|(status, tally)| ("状態", status.label().to_string(), tally))
+                        .chain(
+                            stats
+                                .by_channel
+                                .iter()
+                                .map(|(channel, tally)| ("チャネル", channel.to_string(), tally)),
+                        )
+                        .chain(
+                            stats
+                                .by_record
+                                .iter()
+                                .map(|(record, tally)| ("レコード", record.clone(), tally)),
+                        );
+                    for (group, name, tally) in groups {
+                        stats_row(ui, group, &name, tally);
+                    }
+                });
+            });
+    }
+
+    fn draw_log_tab(&mut self, ui: &mut egui::Ui) {
+        if let Some(err) = &self.state.last_error {
+            ui.colored_label(egui::Color32::RED, format!("エラー: {err}"));
+        }
+        if !self.state.file_status.is_empty() {
+            ui.label(&self.state.file_status);
+        }
+        if !self.state.dict_status.is_empty() {
+            ui.label(&self.state.dict_status);
+        }
+        if !self.state.dict_prefs_error.is_empty() {
+            ui.colored_label(egui::Color32::RED, &self.state.dict_prefs_error);
+        }
+        if !self.state.validation_prefs_error.is_empty() {
+            ui.colored_label(egui::Color32::RED, &self.state.validation_prefs_error);
+        }
+        if !self.state.spell_status.is_empty() {
+            ui.label(&self.state.spell_status);
+        }
+        if let Some(summary) = &self.state.dict_build_summary {
+            ui.label(format!(
+                "辞書情報: built_at(unix)={} pairs={} files={} pair_files={}",
+                summary.built_at_unix, summary.pairs, summary.files_seen, summary.file_pairs
+            ));
+        }
+        if let Some(err) = &self.state.xml_error {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+        if let Some(status) = &self.state.diff_status {
+            ui.label(format!("Diff status: {status:?}"));
+        }
+        if !self.state.encoding_status.is_empty() {
+            ui.label(&self.state.encoding_status);
+        }
+        for issue in &self.state.validation_issues {
+            let text = format!(
+                "[{}] {} {}: {}",
+                issue.severity.as_str(),
+                issue.entry_key,
+                issue.rule_id,
+                issue.message
+            );
+            match issue.severity {
+                Severity::Error => ui.colored_label(egui::Color32::RED, text),
+                Severity::Warn => ui.colored_label(egui::Color32::YELLOW, text),
+                Severity::Info => ui.label(text),
+            };
+        }
+        ui.separator();
+        self.draw_log_entries(ui);
+    }
+
+    fn draw_log_entries(&mut self, ui: &mut egui::Ui) {
+        let mut filter = self.state.log_filter;
+        ui.horizontal(|ui| {
+            ui.label("表示:");
+            egui::ComboBox::from_id_source("log_filter")
+                .selected_text(filter.label())
+                .show_ui(ui, |ui| {
+                    for level in LogLevel::ALL {
+                        ui.selectable_value(&mut filter, level, level.label());
+                    }
+                });
+            if ui.button("コピー").clicked() {
+                let text = log::lock(&self.state.log).to_text(filter);
+                ui.output_mut(|output| output.copied_text = text);
+            }
+            if ui.button("ファイルに保存").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("Log", &["log", "txt"])
+                    .set_file_name("xtrans.log")
+                    .save_file()
+                {
+                    self.run_action(AppAction::ExportLog { path, filter });
+                }
+            }
+            if ui.button("クリア").clicked() {
+                log::lock(&self.state.log).clear();
+            }
+        });
+        self.state.log_filter = filter;
+
+        let buffer = log::lock(&self.state.log);
+        ScrollArea::vertical()
+            .id_source("log_entries")
+            .max_height(240.0)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for entry in buffer.filtered(filter) {
+                    let color = match entry.level {
+                        LogLevel::Error => Color32::RED,
+                        LogLevel::Warn => Color32::YELLOW,
+                        LogLevel::Info => ui.visuals().text_color(),
+                        LogLevel::Debug => Color32::GRAY,
+                    };
+                    ui.label(RichText::new(entry.to_string()).color(color).monospace());
+                }
+            });
+    }
+
+    fn draw_aux_panel(&mut self, ui: &mut egui::Ui) {
+        let mut switch_language = None;
+        if let Some(info) = &self.state.plugin_info {
+            ui.separator();
+            ui.label(RichText::new("Plugin").color(XT_ACCENT).strong());
+            let mut flags = Vec::new();
+            if info.is_master() {
+                flags.push("ESM");
+            }
+            if info.is_light() {
+                flags.push("ESL");
+            }
+            if info.is_localized() {
+                flags.push("Localized");
+            }
+            let flags = if flags.is_empty() {
+                "-".to_string()
+            } else {
+                flags.join(" / ")
+            };
+            ui.label(format!(
+                "フラグ: {flags}  v{:.2}  records={}",
+                info.version, info.record_count
+            ));
+            ui.label(format!(
+                "ゲーム: {} (form version {})",
+                self.state.plugin_context.profile, info.form_version
+            ));
+            if info.is_localized() {
+                let current = &self.state.plugin_context.language;
+                ui.horizontal(|ui| {
+                    ui.label("Strings言語");
+                    egui::ComboBox::from_id_source("plugin_strings_language")
+                        .selected_text(current.as_str())
+                        .show_ui(ui, |ui| {
+                            for language in &self.state.plugin_languages {
+                                if ui.selectable_label(language == current, language).clicked()
+                                    && language != current
+                                {
+                                    switch_language = Some(language.clone());
+                                }
+                            }
+                        });
+                });
+            }
+            if !info.author.is_empty() {
+                ui.label(format!("作成者: {}", info.author));
+            }
+            if !info.description.is_empty() {
+                ui.label(RichText::new(&info.description).weak());
+            }
+            if info.masters.is_empty() {
+                ui.label("マスター: なし");
+            } else {
+                egui::CollapsingHeader::new(format!("マスター ({})", info.masters.len()))
+                    .id_source("plugin_masters")
+                    .show(ui, |ui| {
+                        for master in &info.masters {
+                            ui.label(master);
+                        }
+                    });
+            }
+        }
+        if let Some(language) = switch_language {
+            self.run_action(AppAction::SetPluginLanguage(language));
+        }
+
+        ui.separator();
+        ui.label(RichText::new("Dictionary").color(XT_ACCENT).strong());
+        ui.label(format!(
+            "{} → {}",
+            self.state.dict_source_lang, self.state.dict_target_lang
+        ));
+        ui.label(RichText::new(&self.state.dict_root).small().weak());
+
+        ui.horizontal(|ui| {
+            if ui.button("環境設定").clicked() {
+                self.open_preferences();
+            }
+            if ui.button("辞書を構築").clicked() {
+                self.run_action(AppAction::BuildDictionary);
+            }
+            if ui.button("言語ペア初期化").clicked() {
+                self.run_action(AppAction::ResetDictLanguagePair);
+            }
+        });
+
+        ui.separator();
+        ui.label(RichText::new("XML").color(XT_ACCENT).strong());
+        let xml_len = self.state.xml_text.len();
+        let suppress_large_editor =
+            xml_len > LARGE_XML_EDITOR_THRESHOLD_BYTES && !self.show_large_xml_editor;
+        if suppress_large_editor {
+            ui.label(format!(
+                "XMLエディタを省略中: {} KB (閾値 {} KB)",
+                xml_len / 1024,
+                LARGE_XML_EDITOR_THRESHOLD_BYTES / 1024
+            ));
+            ui.horizontal(|ui| {
+                if ui.button("XMLエディタを開く（重い）").clicked() {
+                    self.show_large_xml_editor = true;
+                }
+                if ui.button("XMLテキストをクリア").clicked() {
+                    self.state.xml_text.clear();
+                    self.show_large_xml_editor = false;
+                }
+            });
+        } else {
+            ui.add(
+                TextEdit::multiline(&mut self.state.xml_text)
+                    .code_editor()
+                    .desired_rows(8)
+                    .desired_width(f32::INFINITY),
+            );
+            if xml_len > LARGE_XML_EDITOR_THRESHOLD_BYTES
+                && ui.button("XMLエディタを閉じる（軽量表示へ）").clicked()
+            {
+                self.show_large_xml_editor = false;
+            }
+        }
+        let mut profile = self.state.apply_profile;
+        apply_profile_combo(ui, "xml_apply_profile_panel", &mut profile);
+        if profile != self.state.apply_profile {
+            self.run_action(AppAction::SetApplyProfile(profile));
+        }
+        ui.horizontal(|ui| {
+            if ui.button("XML適用").clicked() {
+                self.run_action(AppAction::ApplyXmlFromEditor);
+            }
+            if ui.button("XML書き出し").clicked() {
+                self.run_action(AppAction::ExportXmlToEditor);
+            }
+        });
+    }
+
+    fn draw_strings_diff_report(&mut self, ctx: &egui::Context) {
+        let Some(report) = self.state.strings_diff.as_ref() else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new("Strings差分レポート")
+            .open(&mut open)
+            .default_width(640.0)
+            .show(ctx, |ui| {
+                ui.label(format!("旧: {}", report.old_path.display()));
+                ui.label(format!("新: {}", report.new_path.display()));
+                let diff = &report.diff;
+                ui.label(
+                    RichText::new(format!(
+                        "追加 {} / 削除 {} / 変更 {} / 同一 {}",
+                        diff.added.len(),
+                        diff.removed.len(),
+                        diff.changed.len(),
+                        diff.unchanged
+                    ))
+                    .strong(),
+                );
+                ui.separator();
+                ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                    for entry in &diff.added {
+                        ui.colored_label(
+                            Color32::LIGHT_GREEN,
+                            format!("+ {:08X} {}", entry.id, text_preview(&entry.text, 96)),
+                        );
+                    }
+                    for entry in &diff.removed {
+                        ui.colored_label(
+                            Color32::LIGHT_RED,
+                            format!("- {:08X} {}", entry.id, text_preview(&entry.text, 96)),
+                        );
+                    }
+                    for change in &diff.changed {
+                        ui.colored_label(Color32::YELLOW, format!("~ {:08X}", change.id));
+                        ui.label(format!("    {}", text_preview(&change.before, 96)));
+                        ui.label(format!("  → {}", text_preview(&change.after, 96)));
+                    }
+                });
+                ui.separator();
+                if ui.button("レポートをコピー").clicked() {
+                    ui.output_mut(|output| output.copied_text = diff.report());
+                }
+            });
+        if !open {
+            self.state.strings_diff = None;
+        }
+    }
+
+    fn draw_strings_info_report(&mut self, ctx: &egui::Context) {
+        let Some(report) = self.state.strings_info.as_ref() else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new("Strings情報")
+            .open(&mut open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                ui.label(format!("ファイル: {}", report.path.display()));
+                let info = &report.info;
+                egui::Grid::new("strings_info_grid")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        for (label, value) in [
+                            ("サイズ", format!("{} バイト", info.size)),
+                            ("エントリ数", info.entries.to_string()),
+                            ("文字列数（共有を除く）", info.strings.to_string()),
+                            ("未使用バイト", info.unused_bytes.to_string()),
+                            ("データ末尾以降のバイト", info.trailing_bytes.to_string()),
+                        ] {
+                            ui.label(label);
+                            ui.label(value);
+                            ui.end_row();
+                        }
+                    });
+                ui.separator();
+                let warnings = info.warnings();
+                if warnings.is_empty() {
+                    ui.label("問題は見つかりませんでした");
+                } else {
+                    ui.label(RichText::new(format!("警告 {} 件", warnings.len())).strong());
+                    ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                        for warning in &warnings {
+                            ui.colored_label(Color32::YELLOW, strings_warning_label(warning));
+                        }
+                    });
+                }
+                ui.separator();
+                if ui.button("レポートをコピー").clicked() {
+                    ui.output_mut(|output| output.copied_text = info.report());
+                }
+            });
+        if !open {
+            self.state.strings_info = None;
+        }
+    }
+
+    fn draw_xml_apply_dialog(&mut self, ctx: &egui::Context) {
+        let Some(path) = self.pending_xml_file.clone() else {
+            return;
+        };
+        let mut open = true;
+        let mut apply = false;
+        let mut cancel = false;
+        let mut profile = self.state.apply_profile;
+        egui::Window::new("翻訳XMLの適用")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(path.display().to_string());
+                apply_profile_combo(ui, "xml_apply_profile_dialog", &mut profile);
+                ui.horizontal(|ui| {
+                    if ui.button("適用").clicked() {
+                        apply = true;
+                    }
+                    if ui.button("キャンセル").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+        if profile != self.state.apply_profile {
+            self.run_action(AppAction::SetApplyProfile(profile));
+        }
+        if apply {
+            self.pending_xml_file = None;
+            self.run_action(AppAction::LoadXml(path));
+        } else if cancel || !open {
+            self.pending_xml_file = None;
+        }
+    }
+
+    fn draw_validation_settings(&mut self, ctx: &egui::Context) {
+        let Some(draft) = self.validation_settings.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        let mut save = false;
+        let mut cancel = false;
+        egui::Window::new("検証ルール設定")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(RichText::new("組み込みルール").color(XT_ACCENT).strong());
+                egui::Grid::new("validation_rules_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for rule in &mut draft.rules {
+                            ui.checkbox(&mut rule.enabled, rule.rule_id.as_str());
+                            severity_combo(ui, &rule.rule_id, &mut rule.severity);
+                            ui.end_row();
+                        }
+                    });
+
+                ui.separator();
+                ui.label(
+                    RichText::new("カスタム正規表現ルール（訳文に一致で報告）")
+                        .color(XT_ACCENT)
+                        .strong(),
+                );
+                let mut remove = None;
+                for (index, rule) in draft.custom_rules.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut rule.enabled, "");
+                        ui.add(
+                            TextEdit::singleline(&mut rule.rule_id)
+                                .desired_width(120.0)
+                                .hint_text("ID"),
+                        );
+                        ui.add(
+                            TextEdit::singleline(&mut rule.pattern)
+                                .code_editor()
+                                .desired_width(160.0)
+                                .hint_text("正規表現"),
+                        );
+                        ui.add(
+                            TextEdit::singleline(&mut rule.message)
+                                .desired_width(180.0)
+                                .hint_text("メッセージ"),
+                        );
+                        severity_combo(ui, ("custom_rule", index), &mut rule.severity);
+                        if ui.button("削除").clicked() {
+                            remove = Some(index);
+                        }
+                    });
+                }
+                if let Some(index) = remove {
+                    draft.custom_rules.remove(index);
+                }
+                if ui.button("ルールを追加").clicked() {
+                    let index = draft.custom_rules.len() + 1;
+                    draft.custom_rules.push(CustomRegexRule {
+                        rule_id: format!("custom.{index}"),
+                        enabled: true,
+                        severity: Severity::Warn,
+                        pattern: String::new(),
+                        message: String::new(),
+                    });
+                }
+
+                ui.separator();
+                ui.label(
+                    RichText::new("スペルチェック（Hunspell .dic）")
+                        .color(XT_ACCENT)
+                        .strong(),
+                );
+                ui.horizontal(|ui| {
+                    ui.add(
+                        TextEdit::singleline(&mut draft.spell_dictionary)
+                            .desired_width(320.0)
+                            .hint_text("例: en_US.dic（未指定で無効）"),
+                    );
+                    if ui.button("参照").clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("Hunspell", &["dic"])
+                            .pick_file()
+                        {
+                            draft.spell_dictionary = path.display().to_string();
+                        }
+                    }
+                });
+
+                ui.separator();
+                ui.label(RichText::new("保存時の検証").color(XT_ACCENT).strong());
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut draft.save_guard, SaveGuard::Off, "しない");
+                    ui.radio_value(&mut draft.save_guard, SaveGuard::Warn, "エラー時に警告");
+                    ui.radio_value(
+                        &mut draft.save_guard,
+                        SaveGuard::Refuse,
+                        "エラー時は保存しない",
+                    );
+                });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("保存").clicked() {
+                        save = true;
+                    }
+                    if ui.button("キャンセル").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if save {
+            let config = draft.clone();
+            match dispatch(&mut self.state, AppAction::SetValidationConfig(config)) {
+                Ok(()) => {
+                    self.validation_settings = None;
+                    self.state.set_file_status(
+                        LogLevel::Info,
+                        "検証ルール設定を保存しました".to_string(),
+                    );
+                }
+                Err(err) => self.state.set_file_status(LogLevel::Error, err),
+            }
+        } else if cancel || !open {
+            self.validation_settings = None;
+        }
+    }
+
+    fn draw_rewrite_settings(&mut self, ctx: &egui::Context) {
+        let Some(draft) = self.rewrite_settings.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        let mut save = false;
+        let mut cancel = false;
+        egui::Window::new("置換ルール設定")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(
+                    RichText::new("上から順に訳文へ適用します（置換は $1 でグループを参照）")
+                        .small()
+                        .weak(),
+                );
+                let rules = &mut draft.prefs.rules;
+                let mut remove = None;
+                let mut swap = None;
+                for (index, rule) in rules.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut rule.enabled, "");
+                        ui.add(
+                            TextEdit::singleline(&mut rule.name)
+                                .desired_width(100.0)
+                                .hint_text("名前"),
+                        );
+                        ui.add(
+                            TextEdit::singleline(&mut rule.pattern)
+                                .code_editor()
+                                .desired_width(180.0)
+                                .hint_text("正規表現"),
+                        );
+                        ui.label("→");
+                        ui.add(
+                            TextEdit::singleline(&mut rule.replacement)
+                                .code_editor()
+                                .desired_width(140.0)
+                                .hint_text("置換"),
+                        );
+                        if ui.add_enabled(index > 0, egui::Button::new("↑")).clicked() {
+                            swap = Some(index - 1);
+                        }
+                        if ui.button("↓").clicked() {
+                            swap = Some(index);
+                        }
+                        if ui.button("削除").clicked() {
+                            remove = Some(index);
+                        }
+                    });
+                }
+                if let Some(index) = swap.filter(|index| index + 1 < rules.len()) {
+                    rules.swap(index, index + 1);
+                }
+                if let Some(index) = remove {
+                    rules.remove(index);
+                }
+                if ui.button("ルールを追加").clicked() {
+                    rules.push(RewriteRule {
+                        name: format!("ルール{}", rules.len() + 1),
+                        enabled: true,
+                        pattern: String::new(),
+                        replacement: String::new(),
+                    });
+                }
+                ui.checkbox(
+                    &mut draft.prefs.after_auto,
+                    "Quick自動翻訳で入った訳文にも適用する",
+                );
+
+                ui.separator();
+                ui.label(RichText::new("テスト").color(XT_ACCENT).strong());
+                ui.add(
+                    TextEdit::multiline(&mut draft.sample)
+                        .desired_rows(2)
+                        .desired_width(f32::INFINITY)
+                        .hint_text("試す訳文"),
+                );
+                let tried = draft
+                    .prefs
+                    .rules
+                    .iter()
+                    .filter(|rule| !rule.pattern.is_empty())
+                    .cloned()
+                    .collect::<Vec<_>>();
+                match Rewriter::new(&tried) {
+                    Ok(rewriter) => {
+                        let steps = rewriter.trace(&draft.sample);
+                        for step in &steps {
+                            ui.label(format!("{}: {}", step.name, step.output));
+                        }
+                        let result = steps
+                            .last()
+                            .map_or(draft.sample.as_str(), |step| step.output.as_str());
+                        ui.label(RichText::new(format!("結果: {result}")).strong());
+                    }
+                    Err(err) => {
+                        ui.colored_label(Color32::LIGHT_RED, err.to_string());
+                    }
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("保存").clicked() {
+                        save = true;
+                    }
+                    if ui.button("キャンセル").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+        if save {
+            let mut prefs = draft.prefs.clone();
+            prefs.rules.retain(|rule| !rule.pattern.is_empty());
+            match dispatch(&mut self.state, AppAction::SetRewritePrefs(prefs)) {
+                Ok(()) => {
+                    self.rewrite_settings = None;
+                    self.state.set_file_status(
+                        LogLevel::Info,
+                        "置換ルール設定を保存しました".to_string(),
+                    );
+                }
+                Err(err) => self.state.set_file_status(LogLevel::Error, err),
+            }
+        } else if cancel || !open {
+            self.rewrite_settings = None;
+        }
+    }
+
+    fn draw_keymap_settings(&mut self, ctx: &egui::Context) {
+        let Some(draft) = self.keymap_settings.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        let mut save = false;
+        let mut cancel = false;
+        egui::Window::new("ショートカット設定")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("keymap_grid").striped(true).show(ui, |ui| {
+                    let bindings = draft.keymap.bindings().collect::<Vec<_>>();
+                    for (command, chord) in bindings {
+                        ui.label(command.label());
+                        let text = if draft.capturing == Some(command) {
+                            "キーを押してください（Escで中止）".to_string()
+                        } else {
+                            chord.map_or_else(|| "（なし）".to_string(), |chord| chord.label())
+                        };
+                        if ui
+                            .add_sized([200.0, 20.0], egui::Button::new(text))
+                            .on_hover_text("クリックして新しいキーを押す")
+                            .clicked()
+                        {
+                            draft.capturing = Some(command);
+                            draft.note.clear();
+                        }
+                        if ui
+                            .add_enabled(chord.is_some(), egui::Button::new("解除"))
+                            .clicked()
+                        {
+                            draft.keymap.bind(command, None);
+                        }
+                        ui.end_row();
+                    }
+                });
+                ui.label(
+                    RichText::new("修飾キーなしのキーは入力欄にフォーカスがない時だけ働きます")
+                        .small()
+                        .weak(),
+                );
+                if !draft.note.is_empty() {
+                    ui.label(RichText::new(&draft.note).color(XT_ACCENT));
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("保存").clicked() {
+                        save = true;
+                    }
+                    if ui.button("キャンセル").clicked() {
+                        cancel = true;
+                    }
+                    if ui.button("既定に戻す").clicked() {
+                        draft.keymap = Keymap::default();
+                        draft.capturing = None;
+                        draft.note.clear();
+                    }
+                });
+            });
+
+        if save {
+            let keymap = draft.keymap.clone();
+            self.keymap_settings = None;
+            self.run_action(AppAction::SetKeymap(keymap));
+            self.state.set_file_status(
+                LogLevel::Info,
+                "ショートカット設定を保存しました".to_string(),
+            );
+        } else if cancel || !open {
+            self.keymap_settings = None;
+        }
+    }
+
+    fn open_preferences(&mut self) {
+        self.preferences = Some(PreferencesDraft {
+            dictionary: DictionaryPrefs {
+                source_lang: self.state.dict_source_lang.clone(),
+                target_lang: self.state.dict_target_lang.clone(),
+                root: self.state.dict_root.clone(),
+                match_options: self.state.dict_match,
+                user_path: self.state.dict_user_path.clone(),
+                protect_vanilla: self.state.dict_protect_vanilla,
+                apply_on_load: self.state.dict_apply_on_load,
+            },
+            save: self.state.save_prefs().clone(),
+            output_template: self.state.save_prefs().output_template.to_string(),
+            mo2: self.state.mo2_prefs().clone(),
+            extract_cache: self.state.extract_cache(),
+            languages: Vec::new(),
+            scanned_root: None,
+            scan_error: String::new(),
+        });
+    }
+
+    fn draw_preferences(&mut self, ctx: &egui::Context) {
+        let Some(draft) = self.preferences.as_mut() else {
+            return;
+        };
+        if draft.scanned_root.as_deref() != Some(draft.dictionary.root.as_str()) {
+            let root = draft.dictionary.root.clone();
+            match detect_languages(Path::new(&root)) {
+                Ok(languages) => {
+                    draft.languages = languages;
+                    draft.scan_error.clear();
+                }
+                Err(err) => {
+                    draft.languages.clear();
+                    draft.scan_error = format!("言語を検出できません: {err}");
+                }
+            }
+            draft.scanned_root = Some(root);
+        }
+        let mut languages = draft.languages.clone();
+        for language in self
+            .state
+            .plugin_languages
+            .iter()
+            .chain([&draft.dictionary.source_lang, &draft.dictionary.target_lang])
+        {
+            let language = language.to_ascii_lowercase();
+            if !languages.contains(&language) {
+                languages.push(language);
+            }
+        }
+        languages.sort();
+
+        let mut open = true;
+        let mut save = false;
+        let mut cancel = false;
+        let mut clear_cache = false;
+        egui::Window::new("環境設定")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(RichText::new("辞書").color(XT_ACCENT).strong());
+                egui::Grid::new("preferences_dict_grid").show(ui, |ui| {
+                    ui.label("辞書フォルダ");
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut draft.dictionary.root)
+                                .desired_width(260.0),
+                        );
+                        if ui.button("参照").clicked() {
+                            if let Some(dir) = rfd::FileDialog::new()
+                                .set_directory(&draft.dictionary.root)
+                                .pick_folder()
+                            {
+                                draft.dictionary.root = dir.to_string_lossy().into_owned();
+                            }
+                        }
+                    });
+                    ui.end_row();
+                    for (label, id, value) in [
+                        (
+                            "原文の言語",
+                            "pref_source_lang",
+                            &mut draft.dictionary.source_lang,
+                        ),
+                        (
+                            "訳文の言語",
+                            "pref_target_lang",
+                            &mut draft.dictionary.target_lang,
+                        ),
+                    ] {
+                        ui.label(label);
+                        egui::ComboBox::from_id_source(id)
+                            .selected_text(value.as_str())
+                            .show_ui(ui, |ui| {
+                                for language in &languages {
+                                    ui.selectable_value(value, language.clone(), language);
+                                }
+                            });
+                        ui.end_row();
+                    }
+                    ui.label("原文照合");
+                    ui.horizontal(|ui| {
+                        match_options_ui(ui, &mut draft.dictionary.match_options);
+                    });
+                    ui.end_row();
+                    ui.label("");
+                    ui.horizontal(|ui| {
+                        key_normalization_ui(ui, &mut draft.dictionary.match_options);
+                    });
+                    ui.end_row();
+                    ui.label("バニラ文字列");
+                    ui.checkbox(&mut draft.dictionary.protect_vanilla, "保護する")
+                        .on_hover_text(
+                            "参照訳にある原文の行を書き出しに含めず、XML・辞書の訳で上書きしない",
+                        );
+                    ui.end_row();
+                    ui.label("読み込み");
+                    ui.checkbox(
+                        &mut draft.dictionary.apply_on_load,
+                        "読み込み時に辞書を自動適用",
+                    )
+                    .on_hover_text(
+                        "Plugin・Strings を読み込んだら、原文が完全一致する未訳行に辞書の訳を入れる（要確認）",
+                    );
+                    ui.end_row();
+                    ui.label("ユーザー辞書");
+                    ui.horizontal(|ui| {
+                        let default = user_dictionary_path("")
+                            .map(|path| path.display().to_string())
+                            .unwrap_or_default();
+                        ui.add(
+                            egui::TextEdit::singleline(&mut draft.dictionary.user_path)
+                                .hint_text(default)
+                                .desired_width(260.0),
+                        )
+                        .on_hover_text("辞書タブの読込・保存先。空欄は設定フォルダ");
+                        if ui.button("参照").clicked() {
+                            if let Some(path) = rfd::FileDialog::new()
+                                .add_filter("辞書", &["tsv", "txt"])
+                                .save_file()
+                            {
+                                draft.dictionary.user_path = path.to_string_lossy().into_owned();
+                            }
+                        }
+                    });
+                    ui.end_row();
+                });
+                if !draft.scan_error.is_empty() {
+                    ui.colored_label(Color32::LIGHT_RED, &draft.scan_error);
+                } else if draft.languages.is_empty() {
+                    ui.label(
+                        RichText::new("辞書フォルダにStringsファイルがありません")
+                            .small()
+                            .weak(),
+                    );
+                }
+
+                ui.separator();
+                ui.label(RichText::new("保存").color(XT_ACCENT).strong());
+                egui::Grid::new("preferences_save_grid").show(ui, |ui| {
+                    ui.label("文字コード");
+                    let game_default = default_encoding(self.state.game_profile);
+                    egui::ComboBox::from_id_source("pref_encoding")
+                        .selected_text(draft.save.encoding.map_or_else(
+                            || format!("ゲーム既定 ({})", game_default.label()),
+                            |encoding| encoding.label().to_string(),
+                        ))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(
+                                &mut draft.save.encoding,
+                                None,
+                                format!("ゲーム既定 ({})", game_default.label()),
+                            );
+                            for encoding in Encoding::ALL {
+                                ui.selectable_value(
+                                    &mut draft.save.encoding,
+                                    Some(encoding),
+                                    encoding.label(),
+                                );
+                            }
+                        });
+                    ui.end_row();
+                    ui.label("バックアップ");
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_source("pref_backup")
+                            .selected_text(draft.save.backup.label())
+                            .show_ui(ui, |ui| {
+                                for policy in BackupPolicy::KINDS {
+                                    let current = draft.save.backup.same_kind(policy);
+                                    if ui.selectable_label(current, policy.label()).clicked()
+                                        && !current
+                                    {
+                                        draft.save.backup = policy;
+                                    }
+                                }
+                            });
+                        if let BackupPolicy::Rotating(count) = &mut draft.save.backup {
+                            ui.add(egui::DragValue::new(count).range(1..=99).suffix(" 世代"));
+                        }
+                    });
+                    ui.end_row();
+                    ui.label("自動保存");
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::DragValue::new(&mut draft.save.autosave_minutes)
+                                .range(0..=240)
+                                .suffix(" 分"),
+                        );
+                        ui.label(RichText::new("0 で無効").small().weak());
+                    });
+                    ui.end_row();
+                    ui.label("出力ファイル名");
+                    ui.vertical(|ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut draft.output_template)
+                                .hint_text(OutputTemplate::DEFAULT)
+                                .desired_width(260.0),
+                        );
+                        let placeholders = OutputTemplate::PLACEHOLDERS
+                            .map(|name| format!("{{{name}}}"))
+                            .join(" ");
+                        match OutputTemplate::parse(&draft.output_template) {
+                            Some(template) => {
+                                draft.save.output_template = template;
+                                ui.label(
+                                    RichText::new(format!("別名保存で使用: {placeholders}"))
+                                        .small()
+                                        .weak(),
+                                );
+                            }
+                            None => {
+                                ui.colored_label(
+                                    Color32::LIGHT_RED,
+                                    format!("テンプレートが不正です（使用可能: {placeholders}）"),
+                                );
+                            }
+                        }
+                    });
+                    ui.end_row();
+                    ui.label("マニフェスト");
+                    ui.checkbox(
+                        &mut draft.save.write_manifest,
+                        "保存時にマニフェスト（SHA-256）を書き出す",
+                    );
+                    ui.end_row();
+                });
+
+                ui.separator();
+                ui.label(RichText::new("Mod Organizer 2").color(XT_ACCENT).strong());
+                ui.checkbox(
+                    &mut draft.mo2.enabled,
+                    "MO2 の mods フォルダから読み込んだ Plugin は有効な Mod の Strings を参照する",
+                );
+                ui.add_enabled_ui(draft.mo2.enabled, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("プロファイル");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut draft.mo2.profile)
+                                .hint_text("ModOrganizer.ini の選択中")
+                                .desired_width(200.0),
+                        );
+                    });
+                });
+                ui.label(
+                    RichText::new("次回の Plugin 読込から反映されます")
+                        .small()
+                        .weak(),
+                );
+
+                ui.separator();
+                ui.label(RichText::new("抽出キャッシュ").color(XT_ACCENT).strong());
+                ui.horizontal(|ui| {
+                    ui.checkbox(
+                        &mut draft.extract_cache,
+                        "Plugin の抽出結果をキャッシュし、変更がなければ再抽出しない",
+                    );
+                    if ui.button("キャッシュを削除").clicked() {
+                        clear_cache = true;
+                    }
+                });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let valid = OutputTemplate::parse(&draft.output_template).is_some();
+                    if ui.add_enabled(valid, egui::Button::new("保存")).clicked() {
+                        save = true;
+                    }
+                    if ui.button("キャンセル").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if save {
+            let Some(draft) = self.preferences.take() else {
+                return;
+            };
+            self.run_action(AppAction::SetPreferences {
+                dictionary: draft.dictionary,
+                save: draft.save,
+                mo2: draft.mo2,
+                extract_cache: draft.extract_cache,
+            });
+            self.state
+                .set_file_status(LogLevel::Info, "環境設定を保存しました".to_string());
+        } else if cancel || !open {
+            self.preferences = None;
+        } else if clear_cache {
+            self.run_action(AppAction::ClearExtractCache);
+        }
+    }
+
+    fn draw_save_blocked(&mut self, ctx: &egui::Context) {
+        let Some(blocked) = self.save_blocked.as_ref() else {
+            return;
+        };
+        let mut open = true;
+        let mut retry = false;
+        let mut save_as = false;
+        let mut close = false;
+        egui::Window::new("保存できません")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(RichText::new(blocked.block.label()).strong());
+                ui.label(blocked.path.display().to_string());
+                ui.add_space(4.0);
+                let hint = match blocked.block {
+                    WriteBlock::ReadOnly => {
+                        "ファイルのプロパティで読み取り専用を外してから再試行してください"
+                    }
+                    WriteBlock::InUse => "ゲームや Mod Organizer 2 を閉じてから再試行してください",
+                    WriteBlock::Denied => {
+                        "書き込み権限のあるフォルダへ別名で保存するか、権限を確認してください"
+                    }
+                };
+                ui.label(RichText::new(hint).small().weak());
+                ui.separator();
+                ui.horizontal(|ui| {
+                    retry = ui.button("再試行").clicked();
+                    save_as = ui.button("別名で保存").clicked();
+                    if ui.button("パスをコピー").clicked() {
+                        let path = blocked.path.display().to_string();
+                        ui.output_mut(|output| output.copied_text = path);
+                    }
+                    close = ui.button("閉じる").clicked();
+                });
+            });
+
+        if retry {
+            if let Some(blocked) = self.save_blocked.take() {
+                self.submit_job(JobRequest::Save(blocked.mode));
+            }
+        } else if save_as {
+            let file_name = self
+                .save_blocked
+                .as_ref()
+                .and_then(|blocked| blocked.path.file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if let Some(path) = rfd::FileDialog::new().set_file_name(file_name).save_file() {
+                self.save_blocked = None;
+                self.submit_job(JobRequest::Save(SaveMode::Path(path)));
+            }
+        } else if close || !open {
+            self.save_blocked = None;
+        }
+    }
+
+    fn open_save_preview(&mut self, mode: SaveMode) {
+        let plan = save_plan(&self.state, &mode);
+        self.save_preview = Some(SavePreview { mode, plan });
+    }
+
+    fn draw_save_preview(&mut self, ctx: &egui::Context) {
+        let Some(preview) = self.save_preview.as_ref() else {
+            return;
+        };
+        let mut open = true;
+        let mut mode = None;
+        let mut save = false;
+        let mut close = false;
+        egui::Window::new("保存内容をプレビュー")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    for (candidate, label) in [
+                        (SaveMode::Overwrite, "上書き保存"),
+                        (SaveMode::Auto, "別名保存"),
+                    ] {
+                        if ui.radio(preview.mode == candidate, label).clicked() {
+                            mode = Some(candidate);
+                        }
+                    }
+                });
+                ui.separator();
+                match &preview.plan {
+                    Ok(plan) => {
+                        egui::Grid::new("save_preview_grid")
+                            .striped(true)
+                            .num_columns(3)
+                            .show(ui, |ui| {
+                                ui.label(RichText::new("操作").strong());
+                                ui.label(RichText::new("ファイル").strong());
+                                ui.label(RichText::new("変更文字列").strong());
+                                ui.end_row();
+                                for file in &plan.files {
+                                    let color = match file.change {
+                                        PlannedChange::Create => Color32::LIGHT_GREEN,
+                                        PlannedChange::Modify => Color32::YELLOW,
+                                        PlannedChange::Backup => Color32::GRAY,
+                                    };
+                                    ui.colored_label(color, file.change.label());
+                                    ui.label(file.path.display().to_string());
+                                    if file.change == PlannedChange::Backup {
+                                        ui.label("");
+                                    } else {
+                                        ui.label(format!("{} 件", file.changed_strings));
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                        ui.label(format!(
+                            "変更される文字列: 合計 {} 件",
+                            plan.changed_strings()
+                        ));
+                    }
+                    Err(err) => {
+                        ui.colored_label(Color32::LIGHT_RED, err);
+                    }
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    save = ui
+                        .add_enabled(preview.plan.is_ok(), egui::Button::new("この内容で保存"))
+                        .clicked();
+                    close = ui.button("閉じる").clicked();
+                });
+            });
+
+        if let Some(mode) = mode {
+            self.open_save_preview(mode);
+        } else if save {
+            if let Some(preview) = self.save_preview.take() {
+                self.submit_job(JobRequest::Save(preview.mode));
+            }
+        } else if close || !open {
+            self.save_preview = None;
         }
     }
 
-    fn draw_tabs(&mut self, ui: &mut egui::Ui) {
-        ui.horizontal_wrapped(|ui| {
-            for (tab, label) in Tab::all() {
-                let selected = self.state.active_tab == tab;
-                let text = if selected {
-                    RichText::new(label).color(XT_ACCENT).strong()
-                } else {
-                    RichText::new(label)
-                };
-                if ui
-                    .add_sized([110.0, 22.0], egui::SelectableLabel::new(selected, text))
-                    .clicked()
-                {
-                    self.run_action(AppAction::SetActiveTab(tab));
+    fn draw_go_to(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = self.go_to.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        let mut jump = false;
+        let mut close = false;
+        egui::Window::new("フォームID/EDIDへ移動")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    TextEdit::singleline(&mut dialog.query)
+                        .hint_text("フォームID（16進）または EDID")
+                        .desired_width(240.0),
+                );
+                if std::mem::take(&mut dialog.focus) {
+                    response.request_focus();
+                }
+                jump =
+                    response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+                if let Some(error) = &dialog.error {
+                    ui.colored_label(Color32::LIGHT_RED, error);
+                }
+                ui.horizontal(|ui| {
+                    jump |= ui.button("移動").clicked();
+                    close = ui.button("閉じる").clicked();
+                });
+            });
+
+        if jump {
+            let query = dialog.query.clone();
+            match dispatch(&mut self.state, AppAction::GoToRecord(query)) {
+                Ok(()) => {
+                    self.go_to = None;
+                    self.scroll_to_selection = true;
+                }
+                Err(err) => {
+                    dialog.error = Some(err);
+                    dialog.focus = true;
                 }
             }
-        });
+        } else if close || !open {
+            self.go_to = None;
+        }
     }
 
-    fn draw_home_tab(&mut self, ui: &mut egui::Ui) {
-        if let Some(key) = self.state.selected_key() {
-            ui.label(RichText::new(format!("Key: {key}")).color(XT_ACCENT));
-            ui.add(
-                TextEdit::multiline(&mut self.state.edit_source)
-                    .desired_rows(4)
-                    .hint_text("原文"),
-            );
-            ui.add(
-                TextEdit::multiline(&mut self.state.edit_target)
-                    .desired_rows(4)
-                    .hint_text("訳文"),
-            );
+    fn open_backup_manager(&mut self) {
+        let mut manager = BackupManager {
+            backups: Vec::new(),
+            error: String::new(),
+            confirm: None,
+        };
+        self.refresh_backups(&mut manager);
+        self.backup_manager = Some(manager);
+    }
 
-            ui.horizontal(|ui| {
-                if ui.button("Apply Edit").clicked() {
-                    self.run_action(AppAction::ApplyEdit);
-                }
-                if ui.button("Quick Auto").clicked() {
-                    self.start_quick_auto_job();
+    fn refresh_backups(&self, manager: &mut BackupManager) {
+        let Some(file) = self.state.session_file() else {
+            manager.backups.clear();
+            manager.error = "ファイルが読み込まれていません".to_string();
+            return;
+        };
+        match list_backups(file) {
+            Ok(backups) => {
+                manager.backups = backups;
+                manager.error.clear();
+            }
+            Err(err) => {
+                manager.backups.clear();
+                manager.error = err.to_string();
+            }
+        }
+    }
+
+    fn draw_backup_manager(&mut self, ctx: &egui::Context) {
+        let Some(manager) = self.backup_manager.as_mut() else {
+            return;
+        };
+        let file = self
+            .state
+            .session_file()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+        let unsaved = self.state.has_unsaved_changes();
+        let mut open = true;
+        let mut confirmed = None;
+        egui::Window::new("バックアップ管理")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(RichText::new(&file).small().weak());
+                ui.label(
+                    RichText::new(format!(
+                        "現在の設定: {}",
+                        self.state.save_prefs().backup.label()
+                    ))
+                    .small()
+                    .weak(),
+                );
+                if !manager.error.is_empty() {
+                    ui.colored_label(Color32::LIGHT_RED, &manager.error);
                 }
-                if ui.button("Undo").clicked() {
-                    self.run_action(AppAction::Undo);
+                if manager.backups.is_empty() {
+                    ui.label("バックアップはありません");
                 }
-                if ui.button("Redo").clicked() {
-                    self.run_action(AppAction::Redo);
+                ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                    egui::Grid::new("backup_manager_grid")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for backup in &manager.backups {
+                                let name = backup
+                                    .path
+                                    .file_name()
+                                    .map(|name| name.to_string_lossy().into_owned())
+                                    .unwrap_or_default();
+                                ui.label(name)
+                                    .on_hover_text(backup.path.display().to_string());
+                                ui.label(format_unix_utc(backup.modified_at));
+                                ui.label(format!("{} bytes", backup.size));
+                                if ui.button("復元").clicked() {
+                                    manager.confirm =
+                                        Some((backup.path.clone(), BackupChange::Restore));
+                                }
+                                if ui.button("削除").clicked() {
+                                    manager.confirm =
+                                        Some((backup.path.clone(), BackupChange::Delete));
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+                if let Some((path, change)) = manager.confirm.clone() {
+                    ui.separator();
+                    let name = path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    let question = match change {
+                        BackupChange::Restore if unsaved => {
+                            format!("{name} で上書きしますか？ 未保存の編集は失われます")
+                        }
+                        BackupChange::Restore => format!("{name} で上書きしますか？"),
+                        BackupChange::Delete => format!("{name} を削除しますか？"),
+                    };
+                    ui.label(question);
+                    ui.horizontal(|ui| {
+                        if ui.button("実行").clicked() {
+                            confirmed = Some((path, change));
+                        }
+                        if ui.button("キャンセル").clicked() {
+                            manager.confirm = None;
+                        }
+                    });
                 }
             });
-        } else {
-            ui.label("行を選択してください。");
-        }
-    }
 
-    fn draw_log_tab(&mut self, ui: &mut egui::Ui) {
-        if !self.state.file_status.is_empty() {
-            ui.label(&self.state.file_status);
-        }
-        if !self.state.dict_status.is_empty() {
-            ui.label(&self.state.dict_status);
-        }
-        if !self.state.dict_prefs_error.is_empty() {
-            ui.colored_label(egui::Color32::RED, &self.state.dict_prefs_error);
-        }
-        if let Some(summary) = &self.state.dict_build_summary {
-            ui.label(format!(
-                "辞書情報: built_at(unix)={} pairs={} files={} pair_files={}",
-                summary.built_at_unix, summary.pairs, summary.files_seen, summary.file_pairs
-            ));
-        }
-        if let Some(err) = &self.state.xml_error {
-            ui.colored_label(egui::Color32::RED, err);
-        }
-        if let Some(err) = &self.state.hybrid_error {
-            ui.colored_label(egui::Color32::RED, err);
-        }
-        if let Some(status) = &self.state.diff_status {
-            ui.label(format!("Diff status: {status:?}"));
-        }
-        if !self.state.encoding_status.is_empty() {
-            ui.label(&self.state.encoding_status);
+        if !open {
+            self.backup_manager = None;
+            return;
         }
-        for issue in &self.state.validation_issues {
-            ui.label(format!("{}: {}", issue.rule_id, issue.message));
+        let Some((path, change)) = confirmed else {
+            return;
+        };
+        self.run_action(match change {
+            BackupChange::Restore => AppAction::RestoreBackup(path),
+            BackupChange::Delete => AppAction::DeleteBackup(path),
+        });
+        if let Some(mut manager) = self.backup_manager.take() {
+            manager.confirm = None;
+            self.refresh_backups(&mut manager);
+            self.backup_manager = Some(manager);
         }
     }
 
-    fn draw_aux_panel(&mut self, ui: &mut egui::Ui) {
-        ui.separator();
-        ui.label(RichText::new("Dictionary").color(XT_ACCENT).strong());
-
-        if ui
-            .text_edit_singleline(&mut self.state.dict_source_lang)
-            .changed()
-        {
-            self.state.persist_dictionary_prefs();
-        }
+    fn draw_conflicts(&mut self, ctx: &egui::Context) {
+        let Some(priority) = self.conflicts.as_mut() else {
+            return;
+        };
+        let entries = self.state.entries();
+        let candidates = &self.state.candidates;
+        let conflicts = candidates.conflicts(entries);
+        let mut open = true;
+        let mut action = None;
+        egui::Window::new("競合の解決")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(640.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("優先順位");
+                    for at in 0..priority.len() {
+                        if at > 0 {
+                            if ui
+                                .small_button("<")
+                                .on_hover_text("優先度を上げる")
+                                .clicked()
+                            {
+                                priority.swap(at - 1, at);
+                            }
+                            ui.label(">");
+                        }
+                        ui.label(priority[at].label());
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let resolve = ui.add_enabled(
+                        !conflicts.is_empty(),
+                        egui::Button::new("優先順位で一括解決"),
+                    );
+                    if resolve.clicked() {
+                        action = Some(AppAction::ResolveConflicts(priority.clone()));
+                    }
+                    ui.label(format!("{}件", conflicts.len()));
+                });
+                ui.separator();
+                if conflicts.is_empty() {
+                    ui.label("競合はありません");
+                }
+                ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                    egui::Grid::new("conflicts_grid")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for &key in &conflicts {
+                                let Some(entry) = entries.iter().find(|entry| entry.key == key)
+                                else {
+                                    continue;
+                                };
+                                ui.label(text_preview(&entry.source_text, 40))
+                                    .on_hover_text(key);
+                                ui.vertical(|ui| {
+                                    for candidate in candidates.candidates(key) {
+                                        let current = candidate.target == entry.target_text;
+                                        let text = format!(
+                                            "{}: {}",
+                                            candidate.provenance.label(),
+                                            text_preview(&candidate.target, 60)
+                                        );
+                                        if ui.selectable_label(current, text).clicked() {
+                                            action = Some(AppAction::ResolveConflict {
+                                                key: key.to_string(),
+                                                source: candidate.source(),
+                                            });
+                                        }
+                                    }
+                                });
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
 
-        if ui
-            .text_edit_singleline(&mut self.state.dict_target_lang)
-            .changed()
-        {
-            self.state.persist_dictionary_prefs();
+        if !open {
+            self.conflicts = None;
         }
-
-        if ui.text_edit_singleline(&mut self.state.dict_root).changed() {
-            self.state.persist_dictionary_prefs();
+        if let Some(action) = action {
+            self.run_action(action);
         }
+    }
 
-        ui.horizontal(|ui| {
-            if ui.button("辞書を構築").clicked() {
-                self.start_build_dictionary_job();
-            }
-            if ui.button("言語ペア初期化").clicked() {
-                self.run_action(AppAction::ResetDictLanguagePair);
-            }
+    fn open_package(&mut self) {
+        let mod_name = self
+            .state
+            .loaded_plugin_path
+            .as_deref()
+            .or(self.state.loaded_strings_path.as_deref())
+            .and_then(|path| path.file_stem())
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        self.package = Some(PackageDraft {
+            manifest: PackageManifest {
+                mod_name,
+                mod_version: String::new(),
+                language: self.state.dict_target_lang.clone(),
+                author: self.state.author.clone(),
+                created_at: 0,
+            },
+            dsd: false,
+            error: String::new(),
         });
+    }
 
-        ui.separator();
-        ui.label(RichText::new("XML").color(XT_ACCENT).strong());
-        let xml_len = self.state.xml_text.len();
-        let suppress_large_editor =
-            xml_len > LARGE_XML_EDITOR_THRESHOLD_BYTES && !self.show_large_xml_editor;
-        if suppress_large_editor {
-            ui.label(format!(
-                "XMLエディタを省略中: {} KB (閾値 {} KB)",
-                xml_len / 1024,
-                LARGE_XML_EDITOR_THRESHOLD_BYTES / 1024
-            ));
-            ui.horizontal(|ui| {
-                if ui.button("XMLエディタを開く（重い）").clicked() {
-                    self.show_large_xml_editor = true;
-                }
-                if ui.button("XMLテキストをクリア").clicked() {
-                    self.state.xml_text.clear();
-                    self.show_large_xml_editor = false;
+    fn draw_package(&mut self, ctx: &egui::Context) {
+        let Some(draft) = self.package.as_mut() else {
+            return;
+        };
+        let plugin = self.state.loaded_esp_strings.is_some();
+        let mut open = true;
+        let mut create = false;
+        let mut cancel = false;
+        egui::Window::new("翻訳パッケージ作成")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("package_grid").show(ui, |ui| {
+                    for (label, hint, value) in [
+                        ("Mod名", "", &mut draft.manifest.mod_name),
+                        (
+                            "Modバージョン",
+                            "例: 1.2.0",
+                            &mut draft.manifest.mod_version,
+                        ),
+                        ("言語", "", &mut draft.manifest.language),
+                        ("作成者", "", &mut draft.manifest.author),
+                    ] {
+                        ui.label(label);
+                        ui.add(
+                            egui::TextEdit::singleline(value)
+                                .hint_text(hint)
+                                .desired_width(240.0),
+                        );
+                        ui.end_row();
+                    }
+                    if plugin {
+                        ui.label("内容");
+                        ui.horizontal(|ui| {
+                            ui.radio_value(&mut draft.dsd, false, "翻訳済みPlugin");
+                            ui.radio_value(&mut draft.dsd, true, "DSD JSON");
+                        });
+                        ui.end_row();
+                    }
+                });
+                ui.label(
+                    RichText::new("翻訳XMLとマニフェストは xtrans/ に同梱されます")
+                        .small()
+                        .weak(),
+                );
+                if !draft.error.is_empty() {
+                    ui.colored_label(Color32::LIGHT_RED, &draft.error);
                 }
+                let ready = [
+                    &draft.manifest.mod_name,
+                    &draft.manifest.mod_version,
+                    &draft.manifest.language,
+                ]
+                .iter()
+                .all(|value| !value.trim().is_empty());
+                ui.horizontal(|ui| {
+                    create = ui.add_enabled(ready, egui::Button::new("作成")).clicked();
+                    cancel = ui.button("キャンセル").clicked();
+                });
             });
-        } else {
-            ui.add(
-                TextEdit::multiline(&mut self.state.xml_text)
-                    .code_editor()
-                    .desired_rows(8)
-                    .desired_width(f32::INFINITY),
-            );
-            if xml_len > LARGE_XML_EDITOR_THRESHOLD_BYTES {
-                if ui.button("XMLエディタを閉じる（軽量表示へ）").clicked() {
-                    self.show_large_xml_editor = false;
+
+        if cancel || !open {
+            self.package = None;
+            return;
+        }
+        if !create {
+            return;
+        }
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Zip", &["zip"])
+            .set_file_name(draft.manifest.file_name())
+            .save_file()
+        else {
+            return;
+        };
+        let mut manifest = draft.manifest.clone();
+        manifest.created_at = now_unix_seconds();
+        let request = PackageRequest {
+            path,
+            manifest,
+            dsd: plugin && draft.dsd,
+        };
+        match dispatch(&mut self.state, AppAction::CreatePackage(request)) {
+            Ok(()) => self.package = None,
+            Err(err) => {
+                log::emit(LogLevel::Error, &err);
+                if let Some(draft) = self.package.as_mut() {
+                    draft.error = err;
                 }
             }
         }
-        ui.horizontal(|ui| {
-            if ui.button("XML適用").clicked() {
-                self.start_xml_apply(self.state.xml_text.clone(), None);
-            }
-            if ui.button("XML書き出し").clicked() {
-                self.run_action(AppAction::ExportXmlToEditor);
-            }
-        });
+    }
+
+    /// Overwrites the loaded file once edits have gone unsaved for the
+    /// configured interval; a failed save is retried after another interval.
+    fn autosave(&mut self, ctx: &egui::Context, now: Instant) {
+        let minutes = self.state.save_prefs().autosave_minutes;
+        if minutes == 0 || !self.state.has_unsaved_changes() || self.state.session_file().is_none()
+        {
+            self.unsaved_since = None;
+            return;
+        }
+        let since = *self.unsaved_since.get_or_insert(now);
+        let interval = Duration::from_secs(u64::from(minutes) * 60);
+        let wait = interval.saturating_sub(now.saturating_duration_since(since));
+        if !wait.is_zero() {
+            ctx.request_repaint_after(wait);
+            return;
+        }
+        if !self.jobs.is_idle()
+            || self.inline_edit.is_some()
+            || self.hybrid_edit.is_some()
+            || self.duplicate_view.edit.is_some()
+        {
+            return;
+        }
+        self.unsaved_since = Some(now);
+        log::emit(LogLevel::Info, "自動保存します");
+        self.submit_job(JobRequest::Save(SaveMode::Overwrite));
     }
 
     fn update_inner(&mut self, ctx: &egui::Context) {
@@ -713,16 +4066,42 @@ impl XtransApp {
             configure_xtranslator_style(ctx);
             self.style_configured = true;
         }
-        self.poll_job();
+        self.poll_jobs();
+        if self.document != self.state.document_id() {
+            self.reset_document_view();
+        }
+        self.state.poll_search_index();
+        let now = Instant::now();
+        self.state.flush_query(now);
+        self.sync_session(ctx, now);
+        self.autosave(ctx, now);
+        if let Some(wait) = self.state.query_debounce_remaining(now) {
+            ctx.request_repaint_after(wait);
+        }
         let blocked = self.is_blocked();
-        if blocked {
+        if !self.jobs.is_idle() || self.state.is_indexing() {
             ctx.request_repaint_after(Duration::from_millis(16));
         }
 
-        if !blocked && ctx.input(|i| i.modifiers.ctrl && i.key_pressed(egui::Key::R)) {
-            self.start_quick_auto_job();
+        if let Some(draft) = self.keymap_settings.as_mut() {
+            if let Some(command) = draft.capturing {
+                capture_chord(ctx, draft, command);
+            }
+        } else if !blocked {
+            let typing = ctx.wants_keyboard_input();
+            let keymap = self.state.keymap().clone();
+            let commands = ctx.input_mut(|input| keymap.take_commands(input, typing));
+            for command in commands {
+                self.run_command(command);
+            }
+            if !typing {
+                self.handle_clipboard(ctx);
+            }
         }
 
+        self.handle_dropped_files(ctx, blocked);
+        self.draw_drop_hint(ctx);
+
         TopBottomPanel::top("menu_toolbar").show(ctx, |ui| {
             ui.add_enabled_ui(!blocked, |ui| {
                 self.draw_menu(ui);
@@ -744,14 +4123,37 @@ impl XtransApp {
                     "[{}] -> [{}]",
                     self.state.dict_source_lang, self.state.dict_target_lang
                 ));
+                ui.label(self.state.game_profile.label());
                 ui.label(RichText::new(&self.state.file_status).small());
                 ui.label(format!("{}/{}", counts.translated, counts.total));
+                let conflicts = self.state.candidates.conflict_count();
+                if conflicts > 0
+                    && ui
+                        .button(RichText::new(format!("競合 {conflicts}件")).color(Color32::YELLOW))
+                        .on_hover_text("XML・辞書・手動の訳文候補が食い違う行")
+                        .clicked()
+                {
+                    self.conflicts = Some(CandidateSource::PRIORITY.to_vec());
+                }
+                let review = self.state.review_count();
+                if review > 0
+                    && ui
+                        .button(format!("要確認 {review}件"))
+                        .on_hover_text(self.shortcut_hint(Command::NextReview))
+                        .clicked()
+                {
+                    self.run_command(Command::NextReview);
+                }
+                if let Some(id) = self.draw_job_status(ui) {
+                    self.cancel_job(id);
+                }
             });
         });
 
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.add_enabled_ui(!blocked, |ui| {
                 ui.vertical(|ui| {
+                    self.draw_document_tabs(ui);
                     let list_height = (ui.available_height() * 0.46).max(200.0);
                     ui.allocate_ui_with_layout(
                         egui::vec2(ui.available_width(), list_height),
@@ -766,6 +4168,20 @@ impl XtransApp {
                     ui.separator();
                     if self.state.active_tab == Tab::Home {
                         self.draw_home_tab(ui);
+                    } else if self.state.active_tab == Tab::Hybrid {
+                        self.draw_hybrid_tab(ui);
+                    } else if self.state.active_tab == Tab::Dictionary {
+                        self.draw_dictionary_tab(ui);
+                    } else if self.state.active_tab == Tab::Diff {
+                        self.draw_diff_tab(ui);
+                    } else if self.state.active_tab == Tab::Overrides {
+                        self.draw_overrides_tab(ui);
+                    } else if self.state.active_tab == Tab::Duplicates {
+                        self.draw_duplicates_tab(ui);
+                    } else if self.state.active_tab == Tab::History {
+                        self.draw_history_tab(ui);
+                    } else if self.state.active_tab == Tab::Stats {
+                        self.draw_stats_tab(ui);
                     } else if self.state.active_tab == Tab::Log {
                         self.draw_log_tab(ui);
                     } else {
@@ -777,8 +4193,27 @@ impl XtransApp {
             });
         });
 
+        if !blocked {
+            self.draw_validation_settings(ctx);
+            self.draw_rewrite_settings(ctx);
+            self.draw_keymap_settings(ctx);
+            self.draw_preferences(ctx);
+            self.draw_backup_manager(ctx);
+            self.draw_conflicts(ctx);
+            self.draw_package(ctx);
+            self.draw_save_blocked(ctx);
+            self.draw_save_preview(ctx);
+            self.draw_go_to(ctx);
+            self.draw_xml_apply_dialog(ctx);
+            self.draw_strings_diff_report(ctx);
+            self.draw_strings_info_report(ctx);
+            self.draw_close_document(ctx);
+        }
+
         if blocked {
-            self.draw_busy_overlay(ctx);
+            if let Some(id) = self.draw_busy_overlay(ctx) {
+                self.cancel_job(id);
+            }
         }
     }
 }
@@ -804,7 +4239,7 @@ fn configure_japanese_font(ctx: &egui::Context) {
     let mut fonts = FontDefinitions::default();
     fonts
         .font_data
-        .insert("xtrans-jp".to_string(), FontData::from_owned(bytes).into());
+        .insert("xtrans-jp".to_string(), FontData::from_owned(bytes));
 
     if let Some(family) = fonts.families.get_mut(&FontFamily::Proportional) {
         family.insert(0, "xtrans-jp".to_string());
@@ -907,6 +4342,187 @@ fn load_japanese_font_bytes() -> Option<Vec<u8>> {
     None
 }
 
+fn spell_layout_job(
+    ui: &egui::Ui,
+    text: &str,
+    checker: Option<&SpellChecker>,
+) -> egui::text::LayoutJob {
+    let font_id = egui::TextStyle::Body.resolve(ui.style());
+    let plain = egui::TextFormat::simple(font_id, ui.visuals().text_color());
+    let misspelled = egui::TextFormat {
+        underline: egui::Stroke::new(1.5, Color32::RED),
+        ..plain.clone()
+    };
+    let mut job = egui::text::LayoutJob::default();
+    let mut cursor = 0usize;
+    if let Some(checker) = checker {
+        for span in checker.misspelled_words(text) {
+            job.append(&text[cursor..span.start], 0.0, plain.clone());
+            job.append(&text[span.start..span.end], 0.0, misspelled.clone());
+            cursor = span.end;
+        }
+    }
+    job.append(&text[cursor..], 0.0, plain);
+    job
+}
+
+/// Old text with deletions on the left, new text with insertions on the right.
+fn diff_columns(ui: &mut egui::Ui, spans: &[TextSpan]) {
+    ui.columns(2, |columns| {
+        let old = diff_layout_job(&columns[0], spans, SpanKind::Insert);
+        columns[0].label(old);
+        let new = diff_layout_job(&columns[1], spans, SpanKind::Delete);
+        columns[1].label(new);
+    });
+}
+
+/// One side of a character diff; spans of kind `skip` belong to the other side.
+fn diff_layout_job(ui: &egui::Ui, spans: &[TextSpan], skip: SpanKind) -> egui::text::LayoutJob {
+    let font_id = egui::TextStyle::Body.resolve(ui.style());
+    let plain = egui::TextFormat::simple(font_id, ui.visuals().text_color());
+    let deleted = egui::TextFormat {
+        background: Color32::from_rgb(110, 30, 30),
+        strikethrough: egui::Stroke::new(1.0, Color32::LIGHT_RED),
+        ..plain.clone()
+    };
+    let inserted = egui::TextFormat {
+        background: Color32::from_rgb(30, 95, 40),
+        ..plain.clone()
+    };
+    let mut job = egui::text::LayoutJob::default();
+    for span in spans.iter().filter(|span| span.kind != skip) {
+        let format = match span.kind {
+            SpanKind::Equal => plain.clone(),
+            SpanKind::Delete => deleted.clone(),
+            SpanKind::Insert => inserted.clone(),
+        };
+        job.append(&span.text, 0.0, format);
+    }
+    job
+}
+
+fn apply_profile_combo(ui: &mut egui::Ui, id_source: &str, profile: &mut ApplyProfile) {
+    ui.horizontal(|ui| {
+        ui.label("適用プロファイル");
+        egui::ComboBox::from_id_source(id_source)
+            .selected_text(profile.label())
+            .show_ui(ui, |ui| {
+                for candidate in ApplyProfile::ALL {
+                    ui.selectable_value(profile, candidate, candidate.label());
+                }
+            });
+    });
+}
+
+/// Takes the next key press as the chord of `command`; Escape cancels.
+fn capture_chord(ctx: &egui::Context, draft: &mut KeymapDraft, command: Command) {
+    let pressed = ctx.input_mut(|input| {
+        let at = input
+            .events
+            .iter()
+            .position(|event| matches!(event, egui::Event::Key { pressed: true, .. }))?;
+        match input.events.remove(at) {
+            egui::Event::Key { key, modifiers, .. } => Some(KeyChord::pressed(key, modifiers)),
+            _ => None,
+        }
+    });
+    let Some(chord) = pressed else {
+        return;
+    };
+    draft.capturing = None;
+    if chord == KeyChord::plain(egui::Key::Escape) {
+        return;
+    }
+    draft.note = match draft.keymap.bind(command, Some(chord)) {
+        Some(previous) => format!(
+            "{} を「{}」から「{}」に付け替えました",
+            chord.label(),
+            previous.label(),
+            command.label()
+        ),
+        None => String::new(),
+    };
+}
+
+/// Context menu of the `column` title; returns true when `layout` changed.
+fn column_menu_ui(ui: &mut egui::Ui, layout: &mut ColumnLayout, column: EntryColumn) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        changed |= ui.button("◀ 左へ").clicked() && layout.move_column(column, false);
+        changed |= ui.button("右へ ▶").clicked() && layout.move_column(column, true);
+    });
+    ui.separator();
+    ui.label(RichText::new("表示する列").small());
+    for setting in layout.columns().to_vec() {
+        let mut visible = setting.visible;
+        if ui.checkbox(&mut visible, setting.column.label()).changed() {
+            changed |= layout.set_visible(setting.column, visible);
+        }
+    }
+    ui.separator();
+    if ui.button("既定に戻す").clicked() {
+        *layout = ColumnLayout::default();
+        changed = true;
+        ui.close_menu();
+    }
+    changed
+}
+
+/// Case/width toggles; returns true when one changed.
+fn match_options_ui(ui: &mut egui::Ui, options: &mut MatchOptions) -> bool {
+    let case = ui
+        .checkbox(&mut options.ignore_case, "大/小文字無視")
+        .on_hover_text("Iron と iron を同じとみなす");
+    let width = ui
+        .checkbox(&mut options.ignore_width, "全角/半角無視")
+        .on_hover_text("Ｉｒｏｎ と Iron、ｶﾞｰﾄﾞ と ガード を同じとみなす");
+    case.changed() || width.changed()
+}
+
+/// Whitespace/punctuation toggles of dictionary matching.
+fn key_normalization_ui(ui: &mut egui::Ui, options: &mut MatchOptions) {
+    ui.checkbox(&mut options.trim, "前後の空白無視")
+        .on_hover_text("先頭・末尾の空白や改行を無視する");
+    ui.checkbox(&mut options.collapse_whitespace, "連続空白を1つに")
+        .on_hover_text("連続する空白・タブ・改行を1つの空白とみなす");
+    ui.checkbox(&mut options.strip_trailing_punctuation, "末尾の句読点無視")
+        .on_hover_text("Iron Sword. と Iron Sword を同じとみなす（完全一致が優先）");
+}
+
+fn severity_combo(ui: &mut egui::Ui, id_source: impl std::hash::Hash, severity: &mut Severity) {
+    egui::ComboBox::from_id_source(id_source)
+        .selected_text(severity.as_str())
+        .width(70.0)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(severity, Severity::Error, Severity::Error.as_str());
+            ui.selectable_value(severity, Severity::Warn, Severity::Warn.as_str());
+            ui.selectable_value(severity, Severity::Info, Severity::Info.as_str());
+        });
+}
+
+fn stats_row(ui: &mut egui::Ui, group: &str, name: &str, tally: &Tally) {
+    ui.label(group);
+    ui.label(name);
+    ui.label(tally.rows.to_string());
+    ui.label(tally.translated.to_string());
+    ui.label(tally.untranslated().to_string());
+    ui.label(format!("{:.1}", tally.percent()));
+    ui.label(tally.source_words.to_string());
+    ui.label(tally.remaining_words().to_string());
+    ui.end_row();
+}
+
+fn strings_warning_label(warning: &StringsWarning) -> String {
+    match warning {
+        StringsWarning::DuplicateId(id) => format!("{id:08X}: id が重複しています"),
+        StringsWarning::NonUtf8(id) => format!("{id:08X}: UTF-8 でないバイトを含みます"),
+        StringsWarning::EmbeddedNull(id) => format!("{id:08X}: 途中に NUL を含みます"),
+        StringsWarning::TrailingBytes(bytes) => {
+            format!("データ領域の後に {bytes} バイトあります")
+        }
+    }
+}
+
 fn text_preview(text: &str, max_chars: usize) -> &str {
     if max_chars == 0 {
         return "";
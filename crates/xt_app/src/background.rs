@@ -0,0 +1,841 @@
+//! Background jobs on an [`AppState`], independent of the UI toolkit: what
+//! each job takes from the state when it starts, the work its worker thread
+//! does and how its result is merged back. A view runs its actions through
+//! [`BackgroundJobs::run`], polls once per frame and draws the running jobs;
+//! it never blocks on one. [`dispatch`] runs the same jobs inline, so a
+//! headless caller loads, applies and saves exactly as the view does.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use xt_core::candidates::Provenance;
+use xt_core::dictionary::{DictionaryBuildStats, TranslationDictionary};
+use xt_core::import_export::XmlApplyStats;
+use xt_core::model::{EditMetaMap, Entry, NoteMap};
+use xt_core::pipeline::{check_writable, CancelToken, ProgressSink, WriteBlock};
+
+use crate::actions::{
+    apply_loaded_plugin, apply_quick_auto_keys, apply_quick_auto_selection, apply_xml_payload,
+    auto_translate_loaded, check_save_guard, dispatch, plugin_save_warning, read_esp_plugin,
+    run_save_job, save_target, with_save_warning, AppAction, LoadedPlugin, Proposals, SaveJobData,
+    SaveMode,
+};
+use crate::jobs::{FinishedJob, JobKind, JobProgress, JobQueue, RunningJob};
+use crate::log::{self, LogLevel};
+use crate::state::AppState;
+
+/// Applied XML larger than this is not kept as editor text.
+pub const LARGE_XML_EDITOR_THRESHOLD_BYTES: usize = 256 * 1024;
+
+/// Job parameters captured when it is requested; state-dependent inputs
+/// (entries, dictionary) are read when the job actually starts.
+pub enum JobRequest {
+    XmlApply {
+        contents: String,
+        source_label: Option<String>,
+    },
+    /// XML read from a file by the worker.
+    LoadXml(PathBuf),
+    BuildDictionary,
+    /// Dictionary of the translation files in a folder.
+    BuildDictionaryFromXml(PathBuf),
+    QuickAuto {
+        selected: Option<String>,
+    },
+    /// Untranslated rows passing the filter when the job starts.
+    QuickAutoAll,
+    LoadPlugin(PathBuf),
+    Save(SaveMode),
+}
+
+impl JobRequest {
+    /// The job that does `action`'s work, if it has one. `.xtplugin` files
+    /// are small and load without a job.
+    pub fn from_action(state: &AppState, action: &AppAction) -> Option<Self> {
+        Some(match action {
+            AppAction::ApplyXmlFromEditor => JobRequest::XmlApply {
+                contents: state.xml_text.clone(),
+                source_label: None,
+            },
+            AppAction::LoadXml(path) => JobRequest::LoadXml(path.clone()),
+            AppAction::LoadPlugin(path) if !is_xtplugin(path) => {
+                JobRequest::LoadPlugin(path.clone())
+            }
+            AppAction::BuildDictionary => JobRequest::BuildDictionary,
+            AppAction::BuildDictionaryFromXml(dir) => {
+                JobRequest::BuildDictionaryFromXml(dir.clone())
+            }
+            AppAction::QuickAuto => JobRequest::QuickAuto {
+                selected: state.selected_key(),
+            },
+            AppAction::QuickAutoAll => JobRequest::QuickAutoAll,
+            AppAction::SaveOverwrite => JobRequest::Save(SaveMode::Overwrite),
+            AppAction::SaveAsAuto => JobRequest::Save(SaveMode::Auto),
+            AppAction::SaveAsPath(path) => JobRequest::Save(SaveMode::Path(path.clone())),
+            _ => return None,
+        })
+    }
+
+    pub fn kind(&self) -> JobKind {
+        match self {
+            JobRequest::XmlApply { .. } | JobRequest::LoadXml(_) => JobKind::XmlApply,
+            JobRequest::BuildDictionary | JobRequest::BuildDictionaryFromXml(_) => {
+                JobKind::BuildDictionary
+            }
+            JobRequest::QuickAuto { .. } | JobRequest::QuickAutoAll => JobKind::QuickAuto,
+            JobRequest::LoadPlugin(_) => JobKind::LoadPlugin,
+            JobRequest::Save(_) => JobKind::Save,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            JobRequest::XmlApply { .. } | JobRequest::LoadXml(_) => "XML適用",
+            JobRequest::BuildDictionary | JobRequest::BuildDictionaryFromXml(_) => "辞書構築",
+            JobRequest::QuickAuto { .. } => "Quick自動翻訳",
+            JobRequest::QuickAutoAll => "Quick自動翻訳(全件)",
+            JobRequest::LoadPlugin(_) => "Plugin読込",
+            JobRequest::Save(SaveMode::Overwrite) => "保存",
+            JobRequest::Save(SaveMode::Auto | SaveMode::Path(_)) => "別名保存",
+        }
+    }
+}
+
+/// What a worker sends back.
+pub enum JobResult {
+    Xml(Result<XmlApplyResult, String>),
+    LoadPlugin(Result<LoadedPlugin, String>),
+    BuildDictionary(Result<BuildDictionaryResult, String>),
+    QuickAuto(Result<QuickAutoResult, String>),
+    /// A failure keeps its mode so a blocked target can be retried.
+    Save(Result<SaveResult, (SaveMode, String)>),
+}
+
+pub struct XmlApplyResult {
+    source_label: Option<String>,
+    xml_text: String,
+    merged: Vec<Entry>,
+    stats: XmlApplyStats,
+    meta: EditMetaMap,
+    notes: NoteMap,
+    proposals: Proposals,
+}
+
+pub struct BuildDictionaryResult {
+    dict: TranslationDictionary,
+    stats: DictionaryBuildStats,
+}
+
+pub struct QuickAutoResult {
+    next: Vec<Entry>,
+    updated: usize,
+    proposals: Proposals,
+    /// Over the filtered rows rather than the selected one.
+    all: bool,
+}
+
+pub struct SaveResult {
+    path: PathBuf,
+    mode: SaveMode,
+    warning: Option<String>,
+    revision: u64,
+}
+
+/// Save stopped because its target is read-only or in use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveBlocked {
+    pub mode: SaveMode,
+    pub path: PathBuf,
+    pub block: WriteBlock,
+}
+
+/// What the view has to show after a job was submitted or finished; the
+/// state already has the statuses and results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobNotice {
+    /// Offer to retry or save elsewhere.
+    SaveBlocked(SaveBlocked),
+    /// XML was applied; `show_text` is whether the applied XML was kept as
+    /// editor text small enough to show.
+    XmlApplied { show_text: bool },
+}
+
+/// The running and queued jobs of one [`AppState`].
+#[derive(Default)]
+pub struct BackgroundJobs {
+    queue: JobQueue<JobRequest, JobResult>,
+}
+
+impl BackgroundJobs {
+    pub fn is_idle(&self) -> bool {
+        self.queue.is_idle()
+    }
+
+    /// Whether a running job uses the entry list; editing is locked then.
+    pub fn locks_entries(&self) -> bool {
+        self.queue.locks_entries()
+    }
+
+    pub fn running(&self) -> &[RunningJob<JobResult>] {
+        self.queue.running()
+    }
+
+    pub fn queued_len(&self) -> usize {
+        self.queue.queued_len()
+    }
+
+    /// Runs `action`: as a job when it has one, otherwise right away through
+    /// [`dispatch`].
+    pub fn run(
+        &mut self,
+        state: &mut AppState,
+        action: AppAction,
+    ) -> Result<Option<JobNotice>, String> {
+        match JobRequest::from_action(state, &action) {
+            Some(request) => Ok(self.submit(state, request)),
+            None => dispatch(state, action).map(|()| None),
+        }
+    }
+
+    /// Starts `request` now, or queues it behind a running job that uses the
+    /// same entries or dictionary.
+    pub fn submit(&mut self, state: &mut AppState, request: JobRequest) -> Option<JobNotice> {
+        let kind = request.kind();
+        if self.queue.can_start(kind) {
+            self.start(state, request)
+        } else {
+            state.set_file_status(
+                LogLevel::Info,
+                format!("{}: 実行中の処理の完了後に開始します", request.label()),
+            );
+            self.queue.enqueue(kind, request);
+            None
+        }
+    }
+
+    /// Stops waiting for the running job. Its worker notices the token at the
+    /// next check and its result, if any, is dropped.
+    pub fn cancel(&mut self, state: &mut AppState, id: u64) {
+        let Some(pending) = self.queue.cancel(id) else {
+            return;
+        };
+        let message = format!(
+            "{}をキャンセルしました [{:.2}s]",
+            pending.label,
+            pending.started_at.elapsed().as_secs_f32()
+        );
+        if pending.kind.reports_dict_status() {
+            state.set_dict_status(LogLevel::Debug, message.clone());
+        }
+        state.set_file_status(LogLevel::Warn, message);
+    }
+
+    /// Merges the results of finished jobs into `state` and starts queued
+    /// requests that no longer conflict.
+    pub fn poll(&mut self, state: &mut AppState) -> Vec<JobNotice> {
+        let mut notices = Vec::new();
+        for finished in self.queue.poll() {
+            notices.extend(finish(state, finished).notice);
+        }
+        while let Some((_, request)) = self.queue.next_startable() {
+            notices.extend(self.start(state, request));
+        }
+        notices
+    }
+
+    fn start(&mut self, state: &mut AppState, request: JobRequest) -> Option<JobNotice> {
+        let (kind, label) = (request.kind(), request.label());
+        match prepare(state, request) {
+            Ok(work) => {
+                self.queue.spawn(kind, label, work);
+                state.set_file_status(LogLevel::Info, format!("{label}..."));
+                None
+            }
+            Err(stopped) => stopped.blocked.map(JobNotice::SaveBlocked),
+        }
+    }
+}
+
+/// A job's worker, holding what it took from the state.
+type Work = Box<dyn FnOnce(CancelToken, Arc<JobProgress>) -> JobResult + Send>;
+
+/// Why a job did not start; the status bar already says so.
+struct Stopped {
+    message: String,
+    blocked: Option<SaveBlocked>,
+}
+
+impl Stopped {
+    fn refused(message: String) -> Self {
+        Self {
+            message,
+            blocked: None,
+        }
+    }
+}
+
+/// Takes what `request` needs from `state` and returns the work to run.
+fn prepare(state: &mut AppState, request: JobRequest) -> Result<Work, Stopped> {
+    Ok(match request {
+        JobRequest::XmlApply {
+            contents,
+            source_label,
+        } => prepare_xml_apply(state, move || Ok(contents), source_label),
+        JobRequest::LoadXml(path) => {
+            let label = path.display().to_string();
+            let read = move || {
+                std::fs::read_to_string(&path)
+                    .map_err(|err| format!("read {}: {err}", path.display()))
+            };
+            prepare_xml_apply(state, read, Some(label))
+        }
+        JobRequest::BuildDictionary => prepare_build_dictionary(state),
+        JobRequest::BuildDictionaryFromXml(dir) => prepare_build_dictionary_from_xml(state, dir),
+        JobRequest::QuickAuto { selected } => prepare_quick_auto(state, selected),
+        JobRequest::QuickAutoAll => prepare_quick_auto_all(state),
+        JobRequest::LoadPlugin(path) => prepare_load_plugin(state, path),
+        JobRequest::Save(mode) => prepare_save(state, mode)?,
+    })
+}
+
+/// `read` gives the XML on the worker thread.
+fn prepare_xml_apply(
+    state: &mut AppState,
+    read: impl FnOnce() -> Result<String, String> + Send + 'static,
+    source_label: Option<String>,
+) -> Work {
+    let current_entries = state.entries().to_vec();
+    let profile = state.apply_profile;
+    state.xml_error = None;
+    Box::new(move |cancel, progress| {
+        if let Some(label) = &source_label {
+            progress.set_current_file(label);
+        }
+        let result = read().and_then(|contents| {
+            apply_xml_payload(
+                &current_entries,
+                &contents,
+                profile,
+                &cancel,
+                progress.as_ref(),
+            )
+            .map(|(merged, stats, meta, notes, proposals)| XmlApplyResult {
+                source_label,
+                xml_text: contents,
+                merged,
+                stats,
+                meta,
+                notes,
+                proposals,
+            })
+        });
+        JobResult::Xml(result)
+    })
+}
+
+fn prepare_build_dictionary(state: &mut AppState) -> Work {
+    let root = state.dict_root.clone();
+    let source_lang = state.dict_source_lang.clone();
+    let target_lang = state.dict_target_lang.clone();
+    state.set_dict_status(LogLevel::Info, "辞書構築中...".to_string());
+    Box::new(move |cancel, progress| {
+        let result = TranslationDictionary::build_from_strings_dir_cancelable(
+            &PathBuf::from(root),
+            &source_lang,
+            &target_lang,
+            &cancel,
+            progress.as_ref(),
+        )
+        .map_err(|err| format!("辞書構築失敗: {err}"))
+        .map(|(dict, stats)| BuildDictionaryResult { dict, stats });
+        JobResult::BuildDictionary(result)
+    })
+}
+
+fn prepare_build_dictionary_from_xml(state: &mut AppState, dir: PathBuf) -> Work {
+    state.set_dict_status(
+        LogLevel::Info,
+        format!("辞書構築中 (XML): {}", dir.display()),
+    );
+    Box::new(move |cancel, progress| {
+        let result =
+            TranslationDictionary::build_from_xml_dir_cancelable(&dir, &cancel, progress.as_ref())
+                .map_err(|err| format!("辞書構築失敗: {err}"))
+                .map(|(dict, stats)| BuildDictionaryResult { dict, stats });
+        JobResult::BuildDictionary(result)
+    })
+}
+
+fn prepare_quick_auto(state: &mut AppState, selected: Option<String>) -> Work {
+    let reference = state
+        .reference
+        .as_ref()
+        .map(|reference| reference.dict.clone());
+    let dict = state.dict.clone();
+    let options = state.dict_match;
+    let entries = state.entries().to_vec();
+    state.set_dict_status(LogLevel::Info, "Quick自動翻訳中...".to_string());
+    Box::new(move |_cancel, _progress| {
+        let result = apply_quick_auto_selection(
+            reference.as_ref(),
+            dict.as_ref(),
+            &entries,
+            selected,
+            options,
+        )
+        .map_err(|err| err.to_string())
+        .map(|(next, updated, proposals)| QuickAutoResult {
+            next,
+            updated,
+            proposals,
+            all: false,
+        });
+        JobResult::QuickAuto(result)
+    })
+}
+
+fn prepare_quick_auto_all(state: &mut AppState) -> Work {
+    let reference = state
+        .reference
+        .as_ref()
+        .map(|reference| reference.dict.clone());
+    let dict = state.dict.clone();
+    let options = state.dict_match;
+    let keys = state
+        .filtered_entries()
+        .into_iter()
+        .filter(|entry| entry.target_text.is_empty())
+        .map(|entry| entry.key)
+        .collect::<Vec<_>>();
+    let entries = state.entries().to_vec();
+    state.set_dict_status(
+        LogLevel::Info,
+        format!("Quick自動翻訳中 (全件 {}行)...", keys.len()),
+    );
+    Box::new(move |_cancel, _progress| {
+        let result =
+            apply_quick_auto_keys(reference.as_ref(), dict.as_ref(), &entries, &keys, options)
+                .map_err(|err| err.to_string())
+                .map(|(next, updated, proposals)| QuickAutoResult {
+                    next,
+                    updated,
+                    proposals,
+                    all: true,
+                });
+        JobResult::QuickAuto(result)
+    })
+}
+
+fn prepare_load_plugin(state: &mut AppState, path: PathBuf) -> Work {
+    let profile = state.game_profile;
+    let mo2 = state.mo2_prefs().clone();
+    let cache = state.plugin_cache();
+    Box::new(move |cancel, progress| {
+        JobResult::LoadPlugin(read_esp_plugin(
+            &path,
+            profile,
+            &mo2,
+            None,
+            cache.as_ref(),
+            &cancel,
+            progress.as_ref(),
+        ))
+    })
+}
+
+/// Fails when the save guard or a read-only or in-use target stopped the
+/// save.
+fn prepare_save(state: &mut AppState, mode: SaveMode) -> Result<Work, Stopped> {
+    let warning = check_save_guard(state).map_err(|err| {
+        state.set_file_status(LogLevel::Warn, err.clone());
+        Stopped::refused(err)
+    })?;
+    if let Some(blocked) = blocked_save(state, &mode) {
+        return Err(Stopped {
+            message: state.file_status.clone(),
+            blocked: Some(blocked),
+        });
+    }
+    let plugin_warning = plugin_save_warning(
+        state.plugin_info.as_ref(),
+        state.loaded_esp_strings.as_deref(),
+    );
+    let warning = match (warning, plugin_warning) {
+        (Some(a), Some(b)) => Some(format!("{a} / {b}")),
+        (a, b) => a.or(b),
+    };
+    let data = SaveJobData::from_state(state);
+    let revision = data.revision;
+    Ok(Box::new(move |_cancel, _progress| {
+        let result = match run_save_job(data, mode.clone()) {
+            Ok(path) => Ok(SaveResult {
+                path,
+                mode,
+                warning,
+                revision,
+            }),
+            Err(err) => Err((mode, format!("保存失敗: {err}"))),
+        };
+        JobResult::Save(result)
+    }))
+}
+
+/// Runs `request` to completion on the calling thread, with the same
+/// statuses and merge as a background run. [`dispatch`] runs the actions
+/// that have a job this way.
+pub fn run_blocking(state: &mut AppState, request: JobRequest) -> Result<(), String> {
+    let (kind, label) = (request.kind(), request.label());
+    let work = prepare(state, request).map_err(|stopped| stopped.message)?;
+    let started_at = Instant::now();
+    let result = work(CancelToken::new(), Arc::new(JobProgress::default()));
+    let finished = FinishedJob {
+        kind,
+        label: label.to_string(),
+        elapsed: started_at.elapsed(),
+        result: Some(result),
+    };
+    match finish(state, finished).error {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+fn is_xtplugin(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("xtplugin"))
+}
+
+/// The target of a save in `mode` when it is read-only or in use, reported
+/// in the status bar.
+pub fn blocked_save(state: &mut AppState, mode: &SaveMode) -> Option<SaveBlocked> {
+    let path = save_target(state, mode)?;
+    let block = check_writable(&path).err()?;
+    state.set_file_status(
+        LogLevel::Warn,
+        format!("保存できません: {}: {}", path.display(), block.label()),
+    );
+    Some(SaveBlocked {
+        mode: mode.clone(),
+        path,
+        block,
+    })
+}
+
+/// What merging a job's result left for the view, and its failure.
+#[derive(Default)]
+struct Outcome {
+    notice: Option<JobNotice>,
+    error: Option<String>,
+}
+
+impl Outcome {
+    fn failed(error: String) -> Self {
+        Self {
+            notice: None,
+            error: Some(error),
+        }
+    }
+}
+
+fn finish(state: &mut AppState, finished: FinishedJob<JobResult>) -> Outcome {
+    let elapsed = finished.elapsed;
+    let Some(job_result) = finished.result else {
+        state.set_file_status(
+            LogLevel::Error,
+            format!("重い処理ワーカーが異常終了しました（{}）", finished.label),
+        );
+        return Outcome::failed(state.file_status.clone());
+    };
+    match job_result {
+        JobResult::Xml(Ok(done)) => {
+            return Outcome {
+                notice: Some(finish_xml_apply(state, done, elapsed)),
+                error: None,
+            }
+        }
+        JobResult::LoadPlugin(Ok(loaded)) => {
+            apply_loaded_plugin(state, loaded);
+            state.reload_spell_ignore();
+            auto_translate_loaded(state);
+        }
+        JobResult::LoadPlugin(Err(err)) => {
+            state.set_file_status(
+                LogLevel::Error,
+                format!("Plugin読込失敗: {err} [{:.2}s]", elapsed.as_secs_f32()),
+            );
+            state.last_error = Some(err.clone());
+            return Outcome::failed(err);
+        }
+        JobResult::Xml(Err(err)) => {
+            state.xml_error = Some(err.clone());
+            state.set_file_status(
+                LogLevel::Error,
+                format!("XML適用失敗 [{:.2}s]", elapsed.as_secs_f32()),
+            );
+            return Outcome::failed(err);
+        }
+        JobResult::BuildDictionary(Ok(done)) => {
+            let pairs = done.dict.len();
+            state.dict = Some(done.dict);
+            state.mark_dictionary_built(pairs, done.stats.files_seen, done.stats.file_pairs);
+            state.set_dict_status(
+                LogLevel::Info,
+                format!(
+                    "辞書構築: pairs={} files={} pair_files={}",
+                    pairs, done.stats.files_seen, done.stats.file_pairs
+                ),
+            );
+            state.set_file_status(
+                LogLevel::Info,
+                format!("辞書構築完了 [{:.2}s]", elapsed.as_secs_f32()),
+            );
+        }
+        JobResult::BuildDictionary(Err(err)) => {
+            state.set_dict_status(LogLevel::Error, err.clone());
+            state.set_file_status(
+                LogLevel::Error,
+                format!("辞書構築失敗 [{:.2}s]", elapsed.as_secs_f32()),
+            );
+            return Outcome::failed(err);
+        }
+        JobResult::QuickAuto(Ok(done)) => {
+            let mut next = done.next;
+            let mut proposals = done.proposals;
+            let kept = state.keep_vanilla_targets(&mut next, &mut proposals);
+            state.rewrite_auto_targets(&mut next, &mut proposals);
+            let label = if done.all {
+                "Quick自動翻訳(全件)"
+            } else {
+                "Quick自動翻訳"
+            };
+            if done.updated > kept {
+                state.apply_target_updates_with_history(label, next);
+            }
+            let status = if done.all {
+                let (exact, fuzzy) = exact_and_fuzzy(&proposals);
+                format!(
+                    "{label}: updated={} exact={exact} fuzzy={fuzzy}",
+                    done.updated - kept
+                )
+            } else {
+                format!("{label}: updated={}", done.updated - kept)
+            };
+            state.propose_targets(proposals);
+            state.set_dict_status(LogLevel::Info, status);
+            state.set_file_status(
+                LogLevel::Info,
+                format!("Quick自動翻訳完了 [{:.2}s]", elapsed.as_secs_f32()),
+            );
+        }
+        JobResult::QuickAuto(Err(err)) => {
+            state.set_dict_status(LogLevel::Error, err.clone());
+            state.set_file_status(
+                LogLevel::Error,
+                format!("Quick自動翻訳失敗 [{:.2}s]", elapsed.as_secs_f32()),
+            );
+            return Outcome::failed(err);
+        }
+        JobResult::Save(Ok(done)) => {
+            state.mark_saved(done.revision);
+            let prefix = JobRequest::Save(done.mode).label();
+            state.set_file_status(
+                LogLevel::Info,
+                with_save_warning(
+                    format!(
+                        "{}: {} [{:.2}s]",
+                        prefix,
+                        done.path.display(),
+                        elapsed.as_secs_f32()
+                    ),
+                    done.warning,
+                ),
+            );
+        }
+        JobResult::Save(Err((mode, err))) => {
+            state.set_file_status(
+                LogLevel::Error,
+                format!("{err} [{:.2}s]", elapsed.as_secs_f32()),
+            );
+            // The file may have been opened by another program meanwhile.
+            return Outcome {
+                notice: blocked_save(state, &mode).map(JobNotice::SaveBlocked),
+                error: Some(err),
+            };
+        }
+    }
+    Outcome::default()
+}
+
+/// Applied fills from an exact source match (reference translation or a
+/// 100% dictionary hit) and from a folded one.
+fn exact_and_fuzzy(proposals: &Proposals) -> (usize, usize) {
+    let applied = proposals.iter().filter(|(_, proposal)| proposal.applied);
+    let fuzzy = applied
+        .clone()
+        .filter(|(_, proposal)| {
+            matches!(proposal.provenance, Provenance::Dictionary { score, .. } if score < 100)
+        })
+        .count();
+    (applied.count() - fuzzy, fuzzy)
+}
+
+fn finish_xml_apply(state: &mut AppState, done: XmlApplyResult, elapsed: Duration) -> JobNotice {
+    let xml_len = done.xml_text.len();
+    let source_label = done.source_label;
+    let drop_large_xml_text = source_label.is_some() && xml_len > LARGE_XML_EDITOR_THRESHOLD_BYTES;
+    if drop_large_xml_text {
+        state.xml_text.clear();
+    } else {
+        state.xml_text = done.xml_text;
+    }
+    let mut merged = done.merged;
+    let mut proposals = done.proposals;
+    let kept = state.keep_vanilla_targets(&mut merged, &mut proposals);
+    if done.stats.updated > kept {
+        state.apply_target_updates_with_history("XML適用", merged);
+        state.edit_meta.extend(done.meta);
+    }
+    state.notes.extend(done.notes);
+    state.propose_targets(proposals);
+    if kept > 0 {
+        log::emit(
+            LogLevel::Info,
+            &format!("バニラ文字列 {kept} 行はXMLの訳で上書きしませんでした"),
+        );
+    }
+    let stats = done.stats;
+    let src = source_label.unwrap_or_else(|| "エディタ".to_string());
+    let mut status = format!(
+        "XML適用({src}): updated={} unchanged={} missing={} [{:.2}s]",
+        stats.updated,
+        stats.unchanged,
+        stats.missing,
+        elapsed.as_secs_f32()
+    );
+    status.push_str(&format!(
+        " (key={} source={} ambiguous={} kept={} cleared={})",
+        stats.by_key, stats.by_source, stats.ambiguous, stats.kept_existing, stats.cleared
+    ));
+    if drop_large_xml_text {
+        status.push_str(" [XML本文は保持しません]");
+    }
+    state.last_xml_stats = Some(stats);
+    state.xml_error = None;
+    state.set_file_status(LogLevel::Info, status);
+    JobNotice::XmlApplied {
+        show_text: !drop_large_xml_text && xml_len <= LARGE_XML_EDITOR_THRESHOLD_BYTES,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use xt_core::import_export::export_entries;
+
+    #[test]
+    fn t_job_003_jobs_run_on_state_without_a_view() {
+        let row = |key: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: "Iron Sword".into(),
+            target_text: target.into(),
+        };
+        let mut state = AppState::new();
+        state.set_entries_with_history(vec![row("k0", "")]);
+        let mut jobs = BackgroundJobs::default();
+        let xml = export_entries(&[row("k0", "鉄の剣")]);
+        let notice = jobs.submit(
+            &mut state,
+            JobRequest::XmlApply {
+                contents: xml,
+                source_label: None,
+            },
+        );
+        assert_eq!(notice, None);
+        assert!(jobs.locks_entries());
+        assert_eq!(state.file_status, "XML適用...");
+
+        jobs.submit(&mut state, JobRequest::QuickAuto { selected: None });
+        assert_eq!(jobs.queued_len(), 1);
+        assert!(state
+            .file_status
+            .contains("実行中の処理の完了後に開始します"));
+
+        let mut notices = Vec::new();
+        for _ in 0..1000 {
+            notices.extend(jobs.poll(&mut state));
+            if jobs.is_idle() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(2));
+        }
+        assert!(jobs.is_idle());
+        assert_eq!(notices, [JobNotice::XmlApplied { show_text: true }]);
+        assert_eq!(state.entries()[0].target_text, "鉄の剣");
+        assert_eq!(state.last_xml_stats.as_ref().map(|s| s.updated), Some(1));
+        assert!(state.file_status.contains("Quick自動翻訳失敗"));
+
+        state.undo();
+        assert_eq!(state.entries()[0].target_text, "");
+    }
+
+    #[test]
+    fn t_job_004_dispatch_and_view_share_the_job_flow() {
+        let row = |key: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: "Iron Sword".into(),
+            target_text: target.into(),
+        };
+        let xml = export_entries(&[row("k0", "鉄の剣")]);
+        let mut inline = AppState::new();
+        inline.set_entries_with_history(vec![row("k0", "")]);
+        inline.xml_text = xml.clone();
+        dispatch(&mut inline, AppAction::ApplyXmlFromEditor).expect("apply xml");
+
+        let mut viewed = AppState::new();
+        viewed.set_entries_with_history(vec![row("k0", "")]);
+        viewed.xml_text = xml;
+        let mut jobs = BackgroundJobs::default();
+        assert_eq!(
+            jobs.run(&mut viewed, AppAction::ApplyXmlFromEditor),
+            Ok(None)
+        );
+        assert!(jobs.locks_entries());
+        let mut notices = Vec::new();
+        for _ in 0..1000 {
+            notices.extend(jobs.poll(&mut viewed));
+            if jobs.is_idle() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(2));
+        }
+        assert_eq!(notices, [JobNotice::XmlApplied { show_text: true }]);
+        assert_eq!(inline.entries(), viewed.entries());
+        assert_eq!(inline.last_xml_stats, viewed.last_xml_stats);
+        assert!(inline
+            .file_status
+            .starts_with("XML適用(エディタ): updated=1"));
+        assert!(viewed
+            .file_status
+            .starts_with("XML適用(エディタ): updated=1"));
+
+        let missing = PathBuf::from("no_such_dir/missing.xml");
+        let err = dispatch(&mut inline, AppAction::LoadXml(missing)).expect_err("missing");
+        assert!(err.starts_with("read no_such_dir"));
+        assert_eq!(inline.xml_error.as_deref(), Some(err.as_str()));
+        assert!(inline.file_status.starts_with("XML適用失敗"));
+
+        let esp = AppAction::LoadPlugin(PathBuf::from("Mod.esp"));
+        let xtplugin = AppAction::LoadPlugin(PathBuf::from("mod.XTPLUGIN"));
+        assert!(matches!(
+            JobRequest::from_action(&inline, &esp),
+            Some(JobRequest::LoadPlugin(_))
+        ));
+        assert!(JobRequest::from_action(&inline, &xtplugin).is_none());
+        assert!(JobRequest::from_action(&inline, &AppAction::SelectNextRow).is_none());
+        assert_eq!(jobs.run(&mut viewed, AppAction::SelectNextRow), Ok(None));
+        assert!(jobs.is_idle());
+    }
+}
@@ -1,411 +1,4189 @@
-use std::collections::HashMap;
+// The batch tool reads and writes folders, so it has no browser build.
+#![cfg_attr(target_arch = "wasm32", no_main)]
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
-use xt_core::dictionary::TranslationDictionary;
-use xt_core::formats::esp::{apply_translations, extract_strings, ExtractedString};
-use xt_core::formats::strings::{
-    read_dlstrings, read_ilstrings, read_strings, write_dlstrings, write_ilstrings, write_strings,
-    StringsEntry, StringsFile,
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use rayon::prelude::*;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use xt_app::prefs::{parse_validation_prefs, DEFAULT_DICT_TARGET_LANG};
+use xt_app::stats::{StatusGroup, Tally, TranslationStats};
+use xt_core::dictionary::{MergePolicy, MergeStats, TranslationDictionary};
+#[cfg(feature = "sqlite")]
+use xt_core::dictionary_store::SqliteDictionaryStore;
+use xt_core::dictionary_store::{is_sqlite_path, open_store, DictionaryStore};
+use xt_core::diff::{diff_strings, one_line};
+use xt_core::formats::dsd::{export_dsd_json, DsdExportParams};
+use xt_core::formats::esp::{read_masters, ExtractedString};
+use xt_core::formats::strings::StringsFile;
+use xt_core::import_export::{
+    apply_xml_with_profile, export_entries_csv, export_entries_xtranslator, import_entries_any,
+    ApplyProfile, XmlApplyStats, XtranslatorExportParams,
 };
-use xt_core::import_export::{apply_xml_default, export_entries, import_entries};
 use xt_core::model::Entry;
+use xt_core::package::{create_package, PackageContent, PackageManifest};
+use xt_core::pipeline::{
+    export_esp_localized, load_plugin_entries, plan_esp_save, plan_strings_save, read_strings_file,
+    save_esp, save_strings, strings_entries, write_atomic, write_entries_file, GameProfile,
+    OutputTemplate, PlannedFile, PluginContext, SavePlan, SaveReport, StringsKind, PLUGIN_LANGUAGE,
+};
+use xt_core::search::MatchOptions;
+use xt_core::spellcheck::SpellChecker;
+use xt_core::validation::{ValidationConfig, ValidationIssue, ValidationSummary, Validator};
 
 fn main() {
-    if let Err(err) = run() {
-        eprintln!("{err}");
-        std::process::exit(1);
-    }
-}
-
-fn run() -> Result<(), String> {
-    let args: Vec<String> = std::env::args().skip(1).collect();
-    let opts = parse_args(&args)?;
-
-    if let Some(dir) = opts.generate_dictionary.clone() {
-        let source = opts.source.clone().unwrap_or_else(|| "english".to_string());
-        let target = opts
-            .target
-            .clone()
-            .unwrap_or_else(|| "japanese".to_string());
-        let out = opts
-            .dict_out
-            .clone()
-            .ok_or_else(|| "--dict-out is required with --generate-dictionary".to_string())?;
-        let (dict, stats) = TranslationDictionary::build_from_strings_dir(&dir, &source, &target)
-            .map_err(|e| e.to_string())?;
-        dict.save_to_path(&out).map_err(|e| e.to_string())?;
-        println!(
-            "generated dictionary: pairs={} files_seen={} file_pairs={} out={}",
-            dict.len(),
-            stats.files_seen,
-            stats.file_pairs,
-            out.display()
-        );
-        return Ok(());
+    let args = normalize_legacy_args(std::env::args().collect());
+    let config = if args.iter().any(|arg| arg == "--no-config") {
+        Ok(None)
+    } else {
+        std::env::current_dir()
+            .map_err(|e| format!("current dir: {e}"))
+            .and_then(|dir| BatchConfig::discover(&dir))
+    };
+    let args = match &config {
+        Ok(Some(config)) => config.apply(args),
+        _ => args,
+    };
+    let cli = Cli::parse_from(args);
+    let name = cli.command.name();
+    let mut report = Report::new(cli.format);
+    let result = config.map_err(CliError::from).and_then(|config| {
+        if let Some(config) = config {
+            report.text(format!("config: {}", config.path.display()));
+            report.set("config", config.path.display().to_string().into());
+        }
+        run(cli.command, &mut report)
+    });
+    if cli.format == OutputFormat::Json {
+        println!("{}", report.into_json(name, &result));
     }
+    if let Err(err) = result {
+        eprintln!("{}", err.message);
+        std::process::exit(err.code);
+    }
+}
 
-    let import_xml = opts
-        .importxml
-        .clone()
-        .ok_or_else(|| "--importxml <translation.xml> is required".to_string())?;
-    let finalize = opts
-        .finalize
-        .clone()
-        .ok_or_else(|| "--finalize <output> is required".to_string())?;
+/// Process exit codes; usage errors exit with clap's status 2.
+const EXIT_FAILED: i32 = 1;
+const EXIT_VALIDATION_ERRORS: i32 = 3;
+const EXIT_PARTIAL_FAILURE: i32 = 4;
 
-    let (base_entries, base_kind) = load_base(&opts)?;
-    let trans_xml = std::fs::read_to_string(&import_xml)
-        .map_err(|e| format!("read {}: {e}", import_xml.display()))?;
-    let imported = import_entries(&trans_xml).map_err(|e| format!("parse import xml: {e:?}"))?;
-    let (mut merged, stats) = apply_xml_default(&base_entries, &imported);
-    println!(
-        "xml apply: updated={} unchanged={} missing={}",
-        stats.updated, stats.unchanged, stats.missing
-    );
+#[derive(Debug, Parser)]
+#[command(name = "xt_batch", about = "xtrans-rs batch tool")]
+struct Cli {
+    /// `json` prints a single result object to stdout instead of the log.
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+    /// Ignore `xtrans.toml`; otherwise the first one found from the current
+    /// folder upward supplies defaults for flags not given.
+    #[arg(long, global = true)]
+    no_config: bool,
+    #[command(subcommand)]
+    command: Command,
+}
 
-    let mut dict_updated = 0usize;
-    if let Some(dict_path) = opts.dict_in.clone() {
-        let dict = TranslationDictionary::load_from_path(&dict_path).map_err(|e| e.to_string())?;
-        let all_keys = merged.iter().map(|e| e.key.clone()).collect::<Vec<_>>();
-        let (next, updated) = dict.apply_quick(&merged, &all_keys, true);
-        merged = next;
-        dict_updated = updated;
-        println!("quick auto-translate applied: updated={dict_updated}");
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
 
-    if let Some(dict_out) = opts.dict_out.clone() {
-        let dict = TranslationDictionary::build_from_entries(&merged);
-        dict.save_to_path(&dict_out).map_err(|e| e.to_string())?;
-        println!(
-            "saved dictionary: pairs={} out={}",
-            dict.len(),
-            dict_out.display()
-        );
-    }
+#[derive(Debug, PartialEq, Eq)]
+struct CliError {
+    code: i32,
+    message: String,
+}
 
-    finalize_output(&base_kind, &merged, &finalize, &opts)?;
-    println!(
-        "finalized: xml_updated={} xml_unchanged={} xml_missing={} dict_updated={} out={}",
-        stats.updated,
-        stats.unchanged,
-        stats.missing,
-        dict_updated,
-        finalize.display()
-    );
-    Ok(())
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        Self {
+            code: EXIT_FAILED,
+            message,
+        }
+    }
 }
 
-#[derive(Clone)]
-enum BaseKind {
-    Xml,
-    Strings {
-        base: StringsFile,
-        kind: StringsKindCli,
-    },
-    Esp {
-        input_path: PathBuf,
-        extracted: Vec<ExtractedString>,
-        workspace_root: PathBuf,
-    },
+/// Log lines in text mode, result fields in JSON mode.
+struct Report {
+    format: OutputFormat,
+    fields: Vec<(String, JsonValue)>,
+    /// Log lines held back until [`Report::merge`], for work run on another
+    /// thread.
+    buffer: Option<RefCell<Vec<String>>>,
 }
 
-fn load_base(opts: &BatchOptions) -> Result<(Vec<Entry>, BaseKind), String> {
-    let mut count = 0usize;
-    if opts.load.is_some() {
-        count += 1;
-    }
-    if opts.load_strings.is_some() {
-        count += 1;
+impl Report {
+    fn new(format: OutputFormat) -> Self {
+        Self {
+            format,
+            fields: Vec::new(),
+            buffer: None,
+        }
     }
-    if opts.load_plugin.is_some() {
-        count += 1;
+
+    fn buffered(format: OutputFormat) -> Self {
+        Self {
+            buffer: Some(RefCell::default()),
+            ..Self::new(format)
+        }
     }
-    if count != 1 {
-        return Err("exactly one of --load, --load-strings, --load-plugin is required".to_string());
+
+    fn text(&self, line: impl AsRef<str>) {
+        if self.format != OutputFormat::Text {
+            return;
+        }
+        match &self.buffer {
+            Some(buffer) => buffer.borrow_mut().push(line.as_ref().to_string()),
+            None => println!("{}", line.as_ref()),
+        }
     }
 
-    if let Some(path) = opts.load.clone() {
-        let xml =
-            std::fs::read_to_string(&path).map_err(|e| format!("read {}: {e}", path.display()))?;
-        let entries = import_entries(&xml).map_err(|e| format!("parse base xml: {e:?}"))?;
-        return Ok((entries, BaseKind::Xml));
+    /// Logs `other`'s buffered lines and takes its fields; arrays are
+    /// appended to, other values replaced.
+    fn merge(&mut self, other: Report) {
+        for line in other.buffer.map(RefCell::into_inner).unwrap_or_default() {
+            self.text(line);
+        }
+        for (key, value) in other.fields {
+            match value {
+                JsonValue::Array(items) => {
+                    for item in items {
+                        self.push(&key, item);
+                    }
+                }
+                value => self.set(&key, value),
+            }
+        }
     }
 
-    if let Some(path) = opts.load_strings.clone() {
-        let kind = StringsKindCli::from_path(&path)?;
-        let bytes = std::fs::read(&path).map_err(|e| format!("read {}: {e}", path.display()))?;
-        let base = match kind {
-            StringsKindCli::Strings => read_strings(&bytes),
-            StringsKindCli::DlStrings => read_dlstrings(&bytes),
-            StringsKindCli::IlStrings => read_ilstrings(&bytes),
+    fn set(&mut self, key: &str, value: JsonValue) {
+        match self.fields.iter_mut().find(|(name, _)| name == key) {
+            Some((_, slot)) => *slot = value,
+            None => self.fields.push((key.to_string(), value)),
         }
-        .map_err(|e| format!("parse strings {}: {e:?}", path.display()))?;
-        let entries = base
-            .entries
-            .iter()
-            .map(|entry| Entry {
-                key: format!("strings:{}", entry.id),
-                source_text: entry.text.clone(),
-                target_text: String::new(),
-            })
-            .collect::<Vec<_>>();
-        return Ok((entries, BaseKind::Strings { base, kind }));
     }
 
-    let path = opts
-        .load_plugin
-        .clone()
-        .ok_or_else(|| "--load-plugin required".to_string())?;
-    let ext = path
-        .extension()
-        .and_then(|s| s.to_str())
-        .unwrap_or("")
-        .to_ascii_lowercase();
-    if !matches!(ext.as_str(), "esp" | "esm" | "esl") {
-        return Err("load-plugin supports only .esp/.esm/.esl".to_string());
+    fn push(&mut self, key: &str, value: JsonValue) {
+        match self.fields.iter_mut().find(|(name, _)| name == key) {
+            Some((_, JsonValue::Array(items))) => items.push(value),
+            _ => self.set(key, JsonValue::Array(vec![value])),
+        }
     }
-    let workspace_root = opts
-        .workspace_root
-        .clone()
-        .unwrap_or_else(|| workspace_root_from_plugin(&path));
-    let extracted = extract_strings(&path, &workspace_root, Some("english"))
-        .map_err(|e| format!("extract strings {}: {e}", path.display()))?;
-    let entries = extracted
-        .iter()
-        .map(|entry| Entry {
-            key: entry.get_unique_key(),
-            source_text: entry.text.clone(),
-            target_text: String::new(),
-        })
-        .collect::<Vec<_>>();
-    Ok((
-        entries,
-        BaseKind::Esp {
-            input_path: path,
-            extracted,
-            workspace_root,
-        },
-    ))
-}
 
-fn finalize_output(
-    base: &BaseKind,
-    entries: &[Entry],
-    finalize: &Path,
-    _opts: &BatchOptions,
-) -> Result<(), String> {
-    if let Some(parent) = finalize.parent() {
-        std::fs::create_dir_all(parent).map_err(|e| format!("create {}: {e}", parent.display()))?;
+    fn output(&mut self, kind: &str, path: &Path) {
+        self.push(
+            "outputs",
+            object(vec![
+                ("kind", kind.into()),
+                ("path", path.display().to_string().into()),
+            ]),
+        );
     }
-    match base {
-        BaseKind::Xml => {
-            let out_xml = export_entries(entries);
-            std::fs::write(finalize, out_xml)
-                .map_err(|e| format!("write {}: {e}", finalize.display()))?;
-            Ok(())
-        }
-        BaseKind::Strings { base, kind } => {
-            let updated = apply_entries_to_strings(base, entries);
-            let bytes = match kind {
-                StringsKindCli::Strings => write_strings(&updated),
-                StringsKindCli::DlStrings => write_dlstrings(&updated),
-                StringsKindCli::IlStrings => write_ilstrings(&updated),
-            }
-            .map_err(|e| format!("{e:?}"))?;
-            std::fs::write(finalize, bytes)
-                .map_err(|e| format!("write {}: {e}", finalize.display()))?;
-            Ok(())
-        }
-        BaseKind::Esp {
-            input_path,
-            extracted,
-            workspace_root,
-        } => {
-            let mut map: HashMap<&str, &str> = HashMap::new();
-            for entry in entries {
-                if !entry.target_text.is_empty() {
-                    map.insert(entry.key.as_str(), entry.target_text.as_str());
-                }
-            }
-            let mut translated = extracted.clone();
-            for item in &mut translated {
-                let key = item.get_unique_key();
-                if let Some(target) = map.get(key.as_str()) {
-                    item.text = (*target).to_string();
-                }
-            }
-            let output_dir = finalize.parent().unwrap_or_else(|| Path::new("."));
-            let written = apply_translations(
-                input_path,
-                workspace_root,
-                output_dir,
-                translated,
-                Some("english"),
-            )
-            .map_err(|e| format!("apply translations: {e}"))?;
-            if written != finalize {
-                std::fs::copy(&written, finalize).map_err(|e| {
-                    format!(
-                        "copy {} -> {} failed: {e}",
-                        written.display(),
-                        finalize.display()
-                    )
-                })?;
-            }
-            Ok(())
+
+    fn into_json(self, command: &str, result: &Result<(), CliError>) -> JsonValue {
+        let (status, code) = match result {
+            Ok(()) => ("ok", 0),
+            Err(err) => ("error", err.code),
+        };
+        let mut fields = vec![
+            ("command".to_string(), command.into()),
+            ("status".to_string(), status.into()),
+            ("exit_code".to_string(), code.into()),
+        ];
+        if let Err(err) = result {
+            fields.push(("error".to_string(), err.message.as_str().into()));
         }
+        fields.extend(self.fields);
+        JsonValue::Object(fields.into_iter().collect())
     }
 }
 
-fn apply_entries_to_strings(base: &StringsFile, entries: &[Entry]) -> StringsFile {
-    let mut by_id: HashMap<u32, &str> = HashMap::new();
-    for entry in entries {
-        if let Some(id) = parse_strings_id(&entry.key) {
-            if !entry.target_text.is_empty() {
-                by_id.insert(id, entry.target_text.as_str());
-            }
-        }
-    }
-    let out = base
-        .entries
-        .iter()
-        .map(|entry| {
-            if let Some(target) = by_id.get(&entry.id) {
-                StringsEntry {
-                    id: entry.id,
-                    text: (*target).to_string(),
-                }
-            } else {
-                entry.clone()
-            }
-        })
-        .collect::<Vec<_>>();
-    StringsFile { entries: out }
+fn object(fields: Vec<(&str, JsonValue)>) -> JsonValue {
+    JsonValue::Object(
+        fields
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+    )
 }
 
-fn parse_strings_id(key: &str) -> Option<u32> {
-    let (_, id) = key.rsplit_once(':')?;
-    id.parse::<u32>().ok()
+fn count(value: usize) -> JsonValue {
+    value.into()
 }
 
-fn workspace_root_from_plugin(path: &Path) -> PathBuf {
-    let Some(parent) = path.parent() else {
-        return PathBuf::from(".");
-    };
-    let is_data_dir = parent
-        .file_name()
-        .and_then(|name| name.to_str())
-        .map(|name| name.eq_ignore_ascii_case("Data"))
-        .unwrap_or(false);
-    if is_data_dir {
-        if let Some(root) = parent.parent() {
-            return root.to_path_buf();
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Apply a translation XML/JSON to a base file and write the result.
+    Apply(ApplyArgs),
+    /// Apply a dictionary and/or per-plugin XML to every plugin in a folder.
+    ApplyDir(ApplyDirArgs),
+    /// Write the entries of a plugin/strings/XML base as XML or JSON.
+    Extract(ExtractArgs),
+    /// Zip a translation with its XML and a manifest for upload.
+    Package(PackageArgs),
+    /// Dictionary operations.
+    #[command(subcommand)]
+    Dict(DictCommand),
+    /// Validate translations; exits non-zero when error-level issues remain.
+    Validate(ValidateArgs),
+    /// Count entries by record type and channel, translated rows and
+    /// validation issues; always exits zero.
+    Stats(ValidateArgs),
+    /// Compare two .strings files by id.
+    Diff(DiffArgs),
+    /// Show the statistics of a .strings file and what may be wrong with it.
+    StringsInfo(StringsInfoArgs),
+    /// Re-run `apply` whenever the translation XML or dictionary changes.
+    Watch(WatchArgs),
+}
+
+impl Command {
+    fn name(&self) -> &'static str {
+        match self {
+            Command::Apply(_) => "apply",
+            Command::ApplyDir(_) => "apply-dir",
+            Command::Extract(_) => "extract",
+            Command::Package(_) => "package",
+            Command::Dict(DictCommand::Build(_)) => "dict build",
+            Command::Dict(DictCommand::Merge(_)) => "dict merge",
+            Command::Validate(_) => "validate",
+            Command::Stats(_) => "stats",
+            Command::Diff(_) => "diff",
+            Command::StringsInfo(_) => "strings-info",
+            Command::Watch(_) => "watch",
         }
     }
-    parent.to_path_buf()
 }
 
-#[derive(Clone, Copy)]
-enum StringsKindCli {
-    Strings,
-    DlStrings,
-    IlStrings,
+/// Exactly one base input.
+#[derive(Debug, Clone, Args)]
+#[group(required = true, multiple = false)]
+struct BaseArgs {
+    /// xtrans XML or JSON entries.
+    #[arg(long)]
+    load: Option<PathBuf>,
+    #[arg(long)]
+    load_strings: Option<PathBuf>,
+    /// .esp/.esm/.esl
+    #[arg(long)]
+    load_plugin: Option<PathBuf>,
+}
+
+/// Mod Organizer 2 integration for plugins inside an instance's `mods` folder.
+#[derive(Debug, Clone, Default, Args)]
+struct Mo2Args {
+    /// Read Strings files through the enabled mods of the plugin's MO2
+    /// profile, the way the game sees its virtual Data folder.
+    #[arg(long, conflicts_with = "workspace_root")]
+    mo2: bool,
+    /// MO2 profile; the one selected in ModOrganizer.ini when omitted.
+    #[arg(long, requires = "mo2")]
+    mo2_profile: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct ApplyArgs {
+    #[command(flatten)]
+    base: BaseArgs,
+    #[arg(long)]
+    workspace_root: Option<PathBuf>,
+    #[command(flatten)]
+    mo2: Mo2Args,
+    /// Game profile for plugins (`skyrim`, `fallout4`); detected from the header when omitted.
+    #[arg(long, value_parser = parse_game)]
+    game: Option<GameProfile>,
+    /// Strings language of localized plugins (`english`, `en`, ...); detected
+    /// from the Strings files present when omitted.
+    #[arg(long)]
+    plugin_language: Option<String>,
+    /// Translation XML/JSON; repeat to apply several in order, later files
+    /// over earlier ones (a base translation, then patches).
+    #[arg(long, required = true)]
+    importxml: Vec<PathBuf>,
+    #[arg(long, required_unless_present_any = ["export_dsd", "export_localized", "output_template"])]
+    finalize: Option<PathBuf>,
+    /// Finalize to a name built from a template instead, relative to the
+    /// input's folder: `{stem}`, `{ext}`, `{plugin}`, `{lang}`, `{date}`.
+    #[arg(long, value_parser = parse_output_template, conflicts_with = "finalize")]
+    output_template: Option<OutputTemplate>,
+    /// `{lang}` of `--output-template`.
+    #[arg(long, default_value = DEFAULT_DICT_TARGET_LANG, requires = "output_template")]
+    output_language: String,
+    /// Write `<output>.manifest.json` with the size and SHA-256 of each
+    /// finalized file.
+    #[arg(long)]
+    manifest: bool,
+    #[arg(long)]
+    export_dsd: Option<PathBuf>,
+    /// Write a localized plugin here plus a new Strings trio in `Strings/` next to it.
+    #[arg(long)]
+    export_localized: Option<PathBuf>,
+    /// Language in the generated Strings file names (`<plugin>_<language>.strings`).
+    #[arg(long, default_value = PLUGIN_LANGUAGE, requires = "export_localized")]
+    strings_language: String,
+    #[arg(long)]
+    dict_in: Option<PathBuf>,
+    /// How rows match `--dict-in` sources besides exactly:
+    /// comma-separated `case`, `width`, `trim`, `space`, `punct`.
+    #[arg(long, default_value = "", value_parser = parse_match_options, requires = "dict_in")]
+    dict_match: MatchOptions,
+    #[arg(long)]
+    dict_out: Option<PathBuf>,
+    #[arg(long, default_value = "source-fallback", value_parser = parse_apply_profile)]
+    apply_profile: ApplyProfile,
+    /// Apply and validate in memory, report what would change, write nothing.
+    #[arg(long)]
+    dry_run: bool,
+    /// With `--dry-run`, list every changed entry.
+    #[arg(long, requires = "dry_run")]
+    show_diff: bool,
+    /// Validation settings used by `--dry-run` (`validation_prefs.v1` format).
+    #[arg(long, requires = "dry_run")]
+    validation_config: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct ApplyDirArgs {
+    /// Folder with .esp/.esm/.esl files (usually the game's `Data`).
+    #[arg(long, alias = "data")]
+    load_plugin_dir: PathBuf,
+    #[arg(long)]
+    workspace_root: Option<PathBuf>,
+    #[command(flatten)]
+    mo2: Mo2Args,
+    /// Game profile for plugins (`skyrim`, `fallout4`); detected from the header when omitted.
+    #[arg(long, value_parser = parse_game)]
+    game: Option<GameProfile>,
+    /// Strings language of localized plugins (`english`, `en`, ...); detected
+    /// from the Strings files present when omitted.
+    #[arg(long)]
+    plugin_language: Option<String>,
+    /// Folder with translations paired to plugins by name:
+    /// `<stem>.xml`, `<plugin>.xml` or `<stem>_<language>.xml` (or `.json`).
+    #[arg(long, alias = "translations", required_unless_present = "dict_in")]
+    importxml_dir: Option<PathBuf>,
+    /// `<language>` of `<stem>_<language>.xml` translation names.
+    #[arg(long, default_value = DEFAULT_DICT_TARGET_LANG)]
+    language: String,
+    #[arg(long)]
+    dict_in: Option<PathBuf>,
+    /// How rows match `--dict-in` sources besides exactly:
+    /// comma-separated `case`, `width`, `trim`, `space`, `punct`.
+    #[arg(long, default_value = "", value_parser = parse_match_options, requires = "dict_in")]
+    dict_match: MatchOptions,
+    /// Finalized plugins are written here under their original file names.
+    #[arg(long, required_unless_present = "export_dsd_dir")]
+    out_dir: Option<PathBuf>,
+    /// Name finalized plugins from a template instead, relative to
+    /// `--out-dir`: `{stem}`, `{ext}`, `{plugin}`, `{lang}`, `{date}`.
+    #[arg(long, value_parser = parse_output_template, requires = "out_dir")]
+    output_template: Option<OutputTemplate>,
+    /// `{lang}` of `--output-template`.
+    #[arg(long, default_value = DEFAULT_DICT_TARGET_LANG, requires = "output_template")]
+    output_language: String,
+    /// Write `<output>.manifest.json` next to each finalized plugin.
+    #[arg(long, requires = "out_dir")]
+    manifest: bool,
+    /// DSD JSON is written to `<dir>/<plugin file>/<plugin stem>.json`.
+    #[arg(long)]
+    export_dsd_dir: Option<PathBuf>,
+    #[arg(long, default_value = "source-fallback", value_parser = parse_apply_profile)]
+    apply_profile: ApplyProfile,
+    /// Apply in memory and print the summary table without writing outputs.
+    #[arg(long)]
+    dry_run: bool,
+    /// Plugins processed at once; 0 uses one thread per CPU.
+    #[arg(long, default_value_t = 0)]
+    jobs: usize,
+}
+
+#[derive(Debug, Args)]
+struct ExtractArgs {
+    #[command(flatten)]
+    base: BaseArgs,
+    #[arg(long)]
+    workspace_root: Option<PathBuf>,
+    #[command(flatten)]
+    mo2: Mo2Args,
+    /// Game profile for plugins (`skyrim`, `fallout4`); detected from the header when omitted.
+    #[arg(long, value_parser = parse_game)]
+    game: Option<GameProfile>,
+    /// Strings language of localized plugins (`english`, `en`, ...); detected
+    /// from the Strings files present when omitted.
+    #[arg(long)]
+    plugin_language: Option<String>,
+    /// Output path.
+    #[arg(long)]
+    out: PathBuf,
+    /// Picked from the `--out` extension when omitted: `.json` and `.csv`,
+    /// xtrans XML otherwise.
+    #[arg(long, value_enum)]
+    out_format: Option<ExtractFormat>,
+    /// `Dest` language of xTranslator XML.
+    #[arg(long, default_value = DEFAULT_DICT_TARGET_LANG)]
+    language: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ExtractFormat {
+    /// xtrans XML.
+    Xml,
+    /// xtrans JSON entries.
+    Json,
+    /// xTranslator SSTXML with the EDID and REC of each plugin string.
+    Xtranslator,
+    /// Spreadsheet with key, edid, rec, source and target columns.
+    Csv,
 }
 
-impl StringsKindCli {
-    fn from_path(path: &Path) -> Result<Self, String> {
+impl ExtractFormat {
+    fn from_path(path: &Path) -> Self {
         let ext = path
             .extension()
-            .and_then(|s| s.to_str())
+            .and_then(|ext| ext.to_str())
             .unwrap_or("")
             .to_ascii_lowercase();
         match ext.as_str() {
-            "strings" => Ok(Self::Strings),
-            "dlstrings" => Ok(Self::DlStrings),
-            "ilstrings" => Ok(Self::IlStrings),
-            _ => Err(format!("unsupported strings extension: {ext}")),
+            "json" => ExtractFormat::Json,
+            "csv" => ExtractFormat::Csv,
+            _ => ExtractFormat::Xml,
+        }
+    }
+
+    fn id(self) -> &'static str {
+        match self {
+            ExtractFormat::Xml => "xml",
+            ExtractFormat::Json => "json",
+            ExtractFormat::Xtranslator => "xtranslator",
+            ExtractFormat::Csv => "csv",
         }
     }
 }
 
-#[derive(Default, Clone)]
-struct BatchOptions {
-    load: Option<PathBuf>,
-    load_strings: Option<PathBuf>,
-    load_plugin: Option<PathBuf>,
-    importxml: Option<PathBuf>,
-    finalize: Option<PathBuf>,
+#[derive(Debug, Args)]
+struct PackageArgs {
+    #[command(flatten)]
+    base: BaseArgs,
+    #[arg(long)]
+    workspace_root: Option<PathBuf>,
+    #[command(flatten)]
+    mo2: Mo2Args,
+    /// Game profile for plugins (`skyrim`, `fallout4`); detected from the header when omitted.
+    #[arg(long, value_parser = parse_game)]
+    game: Option<GameProfile>,
+    /// Strings language of localized plugins (`english`, `en`, ...); detected
+    /// from the Strings files present when omitted.
+    #[arg(long)]
+    plugin_language: Option<String>,
+    /// Translation XML/JSON; repeat to apply several in order, later files
+    /// over earlier ones (a base translation, then patches).
+    #[arg(long, required = true)]
+    importxml: Vec<PathBuf>,
+    #[arg(long, default_value = "source-fallback", value_parser = parse_apply_profile)]
+    apply_profile: ApplyProfile,
+    /// Name of the translated mod; the plugin or Strings file stem when omitted.
+    #[arg(long)]
+    mod_name: Option<String>,
+    /// Version of the translated mod the translation was made for.
+    #[arg(long)]
+    mod_version: String,
+    /// Translation language, recorded in the manifest and the zip name.
+    #[arg(long, default_value = DEFAULT_DICT_TARGET_LANG)]
+    language: String,
+    #[arg(long, default_value = "")]
+    author: String,
+    /// Ship a DSD JSON instead of the translated plugin.
+    #[arg(long)]
+    dsd: bool,
+    /// Zip to write; `<mod>-<language>-<version>.zip` in the current folder
+    /// when omitted.
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+enum DictCommand {
+    /// Build a dictionary from paired Strings files.
+    Build(DictBuildArgs),
+    /// Merge dictionaries into one, in order.
+    Merge(DictMergeArgs),
+}
+
+#[derive(Debug, Args)]
+struct DictBuildArgs {
+    /// Directory with `<name>_<lang>.strings` pairs.
+    #[arg(
+        long = "strings-dir",
+        alias = "generate-dictionary",
+        required_unless_present_any = ["original", "xml_dir"]
+    )]
+    strings_dir: Option<PathBuf>,
+    /// Folder of translation XML/JSON files (xTranslator or SSTXML) to
+    /// collect source/target pairs from instead.
+    #[arg(long = "xml-dir", conflicts_with_all = ["strings_dir", "original"])]
+    xml_dir: Option<PathBuf>,
+    /// Untranslated plugin to pair with `--translated` instead.
+    #[arg(long, requires = "translated", conflicts_with = "strings_dir")]
+    original: Option<PathBuf>,
+    /// Translated build of `--original`; strings pair by record, form id and
+    /// subrecord.
+    #[arg(long, requires = "original")]
+    translated: Option<PathBuf>,
+    #[arg(long, default_value = "english")]
+    source: String,
+    #[arg(long, default_value = "japanese")]
+    target: String,
+    #[arg(long = "dict-out", alias = "out")]
+    dict_out: PathBuf,
+}
+
+#[derive(Debug, Args)]
+struct DictMergeArgs {
+    /// Dictionaries to merge; later ones are merged into the first.
+    #[arg(required = true, num_args = 2..)]
+    inputs: Vec<PathBuf>,
+    #[arg(short = 'o', long = "out")]
+    out: PathBuf,
+    /// Source translated differently by two dictionaries: `keep-existing`,
+    /// `prefer-other` or `keep-both` (the other target as an alternative).
+    #[arg(long, default_value = "keep-existing", value_parser = parse_merge_policy)]
+    policy: MergePolicy,
+}
+
+#[derive(Debug, Args)]
+struct ValidateArgs {
+    #[command(flatten)]
+    base: BaseArgs,
+    #[arg(long)]
+    workspace_root: Option<PathBuf>,
+    #[command(flatten)]
+    mo2: Mo2Args,
+    /// Game profile for plugins (`skyrim`, `fallout4`); detected from the header when omitted.
+    #[arg(long, value_parser = parse_game)]
+    game: Option<GameProfile>,
+    /// Strings language of localized plugins (`english`, `en`, ...); detected
+    /// from the Strings files present when omitted.
+    #[arg(long)]
+    plugin_language: Option<String>,
+    /// Translation XML/JSON applied before validating; repeat to apply
+    /// several in order.
+    #[arg(long)]
+    importxml: Vec<PathBuf>,
+    #[arg(long, default_value = "source-fallback", value_parser = parse_apply_profile)]
+    apply_profile: ApplyProfile,
+    /// Rule settings in the app's `validation_prefs.v1` format.
+    #[arg(long)]
+    config: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct WatchArgs {
+    #[command(flatten)]
+    apply: ApplyArgs,
+    /// Polling interval for file changes.
+    #[arg(long, default_value_t = 500)]
+    interval_ms: u64,
+}
+
+#[derive(Debug, Args)]
+struct DiffArgs {
+    old: PathBuf,
+    new: PathBuf,
+    /// Write the report to a file instead of stdout.
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct StringsInfoArgs {
+    file: PathBuf,
+}
+
+fn parse_apply_profile(value: &str) -> Result<ApplyProfile, String> {
+    ApplyProfile::parse(value).ok_or_else(|| {
+        let names = ApplyProfile::ALL.map(ApplyProfile::as_str).join(", ");
+        format!("invalid --apply-profile: {value} (expected one of: {names})")
+    })
+}
+
+fn parse_match_options(value: &str) -> Result<MatchOptions, String> {
+    let mut options = MatchOptions::EXACT;
+    for name in value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        let flag = match name {
+            "case" => &mut options.ignore_case,
+            "width" => &mut options.ignore_width,
+            "trim" => &mut options.trim,
+            "space" => &mut options.collapse_whitespace,
+            "punct" => &mut options.strip_trailing_punctuation,
+            _ => {
+                return Err(format!(
+                    "invalid --dict-match: {name} (expected case, width, trim, space or punct)"
+                ))
+            }
+        };
+        *flag = true;
+    }
+    Ok(options)
+}
+
+fn parse_merge_policy(value: &str) -> Result<MergePolicy, String> {
+    MergePolicy::parse(value).ok_or_else(|| {
+        let names = MergePolicy::ALL.map(MergePolicy::as_str).join(", ");
+        format!("invalid --policy: {value} (expected one of: {names})")
+    })
+}
+
+fn parse_output_template(value: &str) -> Result<OutputTemplate, String> {
+    OutputTemplate::parse(value).ok_or_else(|| {
+        let names = OutputTemplate::PLACEHOLDERS.map(|name| format!("{{{name}}}"));
+        format!(
+            "invalid --output-template: {value} (placeholders: {})",
+            names.join(", ")
+        )
+    })
+}
+
+fn parse_game(value: &str) -> Result<GameProfile, String> {
+    GameProfile::parse(value).ok_or_else(|| {
+        let names = GameProfile::ALL.map(GameProfile::name).join(", ");
+        format!("invalid --game: {value} (expected one of: {names})")
+    })
+}
+
+/// Maps the pre-subcommand invocation (`xt_batch --load ... --finalize ...`)
+/// onto `apply`, `--load-plugin-dir` onto `apply-dir` and
+/// `--generate-dictionary` onto `dict build`, and `apply --data` onto
+/// `apply-dir`. Global flags may come before the subcommand.
+fn normalize_legacy_args(mut args: Vec<String>) -> Vec<String> {
+    let mut rest = args.iter().skip(1);
+    let first = loop {
+        match rest.next().map(String::as_str) {
+            Some("--format") => {
+                rest.next();
+            }
+            Some(arg) if arg.starts_with("--format=") || arg == "--no-config" => {}
+            Some(arg) => break arg,
+            None => return args,
+        }
+    };
+    if first == "apply" && args.iter().any(|arg| arg == "--data") {
+        if let Some(apply) = args.iter_mut().find(|arg| *arg == "apply") {
+            *apply = "apply-dir".to_string();
+        }
+        return args;
+    }
+    if !first.starts_with("--") || matches!(first, "--help" | "--version") {
+        return args;
+    }
+    if args.iter().any(|arg| arg == "--generate-dictionary") {
+        args.splice(1..1, ["dict".to_string(), "build".to_string()]);
+    } else if args.iter().any(|arg| arg == "--load-plugin-dir") {
+        args.insert(1, "apply-dir".to_string());
+    } else {
+        args.insert(1, "apply".to_string());
+    }
+    args
+}
+
+/// Defaults file looked up from the current folder upward.
+const CONFIG_FILE_NAME: &str = "xtrans.toml";
+
+/// Defaults read from `xtrans.toml`. Paths are relative to the file's folder.
+///
+/// ```toml
+/// workspace_root = "Data"
+/// source_language = "english"
+/// target_language = "japanese"
+/// dict_in = "dict/skyrim.tsv"
+/// apply_profile = "source-fallback"
+/// output_template = "{stem}_{lang}.{ext}"
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BatchConfig {
+    #[serde(skip)]
+    path: PathBuf,
     workspace_root: Option<PathBuf>,
+    source_language: Option<String>,
+    target_language: Option<String>,
     dict_in: Option<PathBuf>,
-    dict_out: Option<PathBuf>,
-    source: Option<String>,
-    target: Option<String>,
-    generate_dictionary: Option<PathBuf>,
-}
-
-fn parse_args(args: &[String]) -> Result<BatchOptions, String> {
-    let mut opts = BatchOptions::default();
-    let mut map: HashMap<String, String> = HashMap::new();
-    let mut i = 0usize;
-    while i < args.len() {
-        let key = args[i].as_str();
-        if !key.starts_with("--") {
-            return Err(format!("invalid argument: {}", args[i]));
-        }
-        let Some(value) = args.get(i + 1) else {
-            return Err(format!("missing value for {key}"));
-        };
-        map.insert(key.to_string(), value.to_string());
-        i += 2;
-    }
-    opts.load = map.get("--load").map(PathBuf::from);
-    opts.load_strings = map.get("--load-strings").map(PathBuf::from);
-    opts.load_plugin = map.get("--load-plugin").map(PathBuf::from);
-    opts.importxml = map.get("--importxml").map(PathBuf::from);
-    opts.finalize = map.get("--finalize").map(PathBuf::from);
-    opts.workspace_root = map.get("--workspace-root").map(PathBuf::from);
-    opts.dict_in = map.get("--dict-in").map(PathBuf::from);
-    opts.dict_out = map.get("--dict-out").map(PathBuf::from);
-    opts.source = map.get("--source").cloned();
-    opts.target = map.get("--target").cloned();
-    opts.generate_dictionary = map.get("--generate-dictionary").map(PathBuf::from);
-    Ok(opts)
+    apply_profile: Option<String>,
+    output_template: Option<String>,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::path::Path;
+/// A flag a config value fills in. It is added only when the subcommand
+/// accepts it, none of `unless` is given and every flag of `requires` that
+/// the subcommand accepts is given.
+struct ConfigFlag {
+    flag: &'static str,
+    value: String,
+    unless: &'static [&'static str],
+    requires: &'static [&'static str],
+}
 
-    #[test]
-    fn t_batch_001_parse_pipeline_args() {
-        let args = vec![
-            "--load".to_string(),
-            "base.xml".to_string(),
-            "--importxml".to_string(),
-            "tr.xml".to_string(),
-            "--finalize".to_string(),
-            "out.xml".to_string(),
-        ];
-        let opts = parse_args(&args).expect("parse");
-        assert_eq!(opts.load.as_deref(), Some(Path::new("base.xml")));
-        assert_eq!(opts.importxml.as_deref(), Some(Path::new("tr.xml")));
-        assert_eq!(opts.finalize.as_deref(), Some(Path::new("out.xml")));
+impl BatchConfig {
+    /// The first `xtrans.toml` in `start` or one of its parents.
+    fn discover(start: &Path) -> Result<Option<Self>, String> {
+        start
+            .ancestors()
+            .map(|dir| dir.join(CONFIG_FILE_NAME))
+            .find(|path| path.is_file())
+            .map(|path| Self::load(&path))
+            .transpose()
     }
 
-    #[test]
-    fn t_batch_002_parse_strings_plugin_args() {
-        let args = vec![
-            "--load-strings".to_string(),
-            "a.strings".to_string(),
-            "--importxml".to_string(),
-            "x.xml".to_string(),
-            "--finalize".to_string(),
-            "out.strings".to_string(),
-            "--workspace-root".to_string(),
-            "/game".to_string(),
-        ];
-        let opts = parse_args(&args).expect("parse");
-        assert_eq!(opts.load_strings.as_deref(), Some(Path::new("a.strings")));
-        assert_eq!(opts.workspace_root.as_deref(), Some(Path::new("/game")));
+    fn load(path: &Path) -> Result<Self, String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("read {}: {e}", path.display()))?;
+        Self::parse(&text, path).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    fn parse(text: &str, path: &Path) -> Result<Self, String> {
+        let mut config: Self = toml::from_str(text).map_err(|e| e.to_string())?;
+        if let Some(profile) = &config.apply_profile {
+            parse_apply_profile(profile)?;
+        }
+        if let Some(template) = &config.output_template {
+            parse_output_template(template)?;
+        }
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        config.workspace_root = config.workspace_root.map(|root| dir.join(root));
+        config.dict_in = config.dict_in.map(|dict| dir.join(dict));
+        config.path = path.to_path_buf();
+        Ok(config)
+    }
+
+    fn flags(&self) -> Vec<ConfigFlag> {
+        let flag = |flag, value: &str, unless, requires| ConfigFlag {
+            flag,
+            value: value.to_string(),
+            unless,
+            requires,
+        };
+        let mut flags = Vec::new();
+        if let Some(root) = &self.workspace_root {
+            flags.push(flag(
+                "workspace-root",
+                &root.to_string_lossy(),
+                &["mo2"],
+                &[],
+            ));
+        }
+        if let Some(language) = &self.source_language {
+            flags.push(flag("source", language, &[], &[]));
+            flags.push(flag("plugin-language", language, &[], &[]));
+        }
+        if let Some(language) = &self.target_language {
+            flags.push(flag("target", language, &[], &[]));
+            flags.push(flag("language", language, &[], &[]));
+            flags.push(flag("output-language", language, &[], &["output-template"]));
+        }
+        if let Some(dict) = &self.dict_in {
+            flags.push(flag("dict-in", &dict.to_string_lossy(), &[], &[]));
+        }
+        if let Some(profile) = &self.apply_profile {
+            flags.push(flag("apply-profile", profile, &[], &[]));
+        }
+        if let Some(template) = &self.output_template {
+            flags.push(flag(
+                "output-template",
+                template,
+                &["finalize"],
+                &["out-dir"],
+            ));
+        }
+        flags
+    }
+
+    /// Appends the config's flags that the invoked subcommand accepts and
+    /// the command line does not already give.
+    fn apply(&self, mut args: Vec<String>) -> Vec<String> {
+        let mut command = Cli::command();
+        for arg in args.iter().skip(1) {
+            if !command.has_subcommands() {
+                break;
+            }
+            if let Some(sub) = command.find_subcommand(arg) {
+                command = sub.clone();
+            }
+        }
+        let accepts = |flag: &str| {
+            command
+                .get_arguments()
+                .any(|arg| arg.get_long() == Some(flag))
+        };
+        for config_flag in self.flags() {
+            let given = |flag: &str| {
+                let long = format!("--{flag}");
+                args.iter()
+                    .any(|arg| *arg == long || arg.starts_with(&format!("{long}=")))
+            };
+            if accepts(config_flag.flag)
+                && !given(config_flag.flag)
+                && !config_flag.unless.iter().any(|flag| given(flag))
+                && config_flag
+                    .requires
+                    .iter()
+                    .all(|flag| !accepts(flag) || given(flag))
+            {
+                args.push(format!("--{}", config_flag.flag));
+                args.push(config_flag.value);
+            }
+        }
+        args
+    }
+}
+
+fn run(command: Command, report: &mut Report) -> Result<(), CliError> {
+    match command {
+        Command::Apply(args) => run_apply(&args, report),
+        Command::ApplyDir(args) => run_apply_dir(&args, report),
+        Command::Extract(args) => run_extract(&args, report),
+        Command::Package(args) => run_package(&args, report),
+        Command::Dict(DictCommand::Build(args)) => run_dict_build(&args, report),
+        Command::Dict(DictCommand::Merge(args)) => run_dict_merge(&args, report),
+        Command::Validate(args) => run_validate(&args, report),
+        Command::Stats(args) => run_stats(&args, report),
+        Command::Diff(args) => run_diff(&args, report),
+        Command::StringsInfo(args) => run_strings_info(&args, report),
+        Command::Watch(args) => run_watch(&args, report.format),
+    }
+}
+
+fn run_dict_build(args: &DictBuildArgs, report: &mut Report) -> Result<(), CliError> {
+    if let (Some(original), Some(translated)) = (&args.original, &args.translated) {
+        return run_dict_build_from_plugins(args, original, translated, report);
+    }
+    let (dict, stats) = match &args.xml_dir {
+        Some(xml_dir) => TranslationDictionary::build_from_xml_dir(xml_dir),
+        None => {
+            let strings_dir = args.strings_dir.as_deref().unwrap_or(Path::new("."));
+            TranslationDictionary::build_from_strings_dir(strings_dir, &args.source, &args.target)
+        }
+    }
+    .map_err(|e| e.to_string())?;
+    save_dictionary(&dict, &args.dict_out)?;
+    report.text(format!(
+        "generated dictionary: pairs={} files_seen={} file_pairs={} out={}",
+        dict.len(),
+        stats.files_seen,
+        stats.file_pairs,
+        args.dict_out.display()
+    ));
+    report.set(
+        "dictionary",
+        object(vec![
+            ("pairs", count(dict.len())),
+            ("files_seen", count(stats.files_seen)),
+            ("file_pairs", count(stats.file_pairs)),
+        ]),
+    );
+    report.output("dictionary", &args.dict_out);
+    Ok(())
+}
+
+/// `--source`/`--target` pick the Strings files of localized plugins.
+fn run_dict_build_from_plugins(
+    args: &DictBuildArgs,
+    original: &Path,
+    translated: &Path,
+    report: &mut Report,
+) -> Result<(), CliError> {
+    let context = |plugin: &Path, language: &str| {
+        PluginContext::resolve(plugin, None, None, Some(language)).map_err(|e| e.to_string())
+    };
+    let (dict, stats) = TranslationDictionary::build_from_plugin_pair_with(
+        original,
+        &context(original, &args.source)?,
+        translated,
+        &context(translated, &args.target)?,
+    )
+    .map_err(|e| e.to_string())?;
+    save_dictionary(&dict, &args.dict_out)?;
+    report.text(format!(
+        "generated dictionary: pairs={} matched={} untranslated={} missing={} out={}",
+        dict.len(),
+        stats.matched,
+        stats.untranslated,
+        stats.missing,
+        args.dict_out.display()
+    ));
+    report.set(
+        "dictionary",
+        object(vec![
+            ("pairs", count(dict.len())),
+            ("matched", count(stats.matched)),
+            ("untranslated", count(stats.untranslated)),
+            ("missing", count(stats.missing)),
+        ]),
+    );
+    report.output("dictionary", &args.dict_out);
+    Ok(())
+}
+
+fn run_dict_merge(args: &DictMergeArgs, report: &mut Report) -> Result<(), CliError> {
+    let load = |path: &PathBuf| load_dictionary(path);
+    let mut merged = load(&args.inputs[0])?;
+    let mut total = MergeStats::default();
+    for path in &args.inputs[1..] {
+        let stats = merged.merge(&load(path)?, args.policy);
+        report.text(format!(
+            "merged {}: added={} unchanged={} kept={} replaced={} alternatives={}",
+            path.display(),
+            stats.added,
+            stats.unchanged,
+            stats.kept,
+            stats.replaced,
+            stats.alternatives
+        ));
+        total.added += stats.added;
+        total.unchanged += stats.unchanged;
+        total.kept += stats.kept;
+        total.replaced += stats.replaced;
+        total.alternatives += stats.alternatives;
+    }
+    save_dictionary(&merged, &args.out)?;
+    report.text(format!(
+        "merged dictionary: pairs={} policy={} out={}",
+        merged.len(),
+        args.policy.as_str(),
+        args.out.display()
+    ));
+    report.set(
+        "dictionary",
+        object(vec![
+            ("pairs", count(merged.len())),
+            ("policy", args.policy.as_str().into()),
+            ("added", count(total.added)),
+            ("unchanged", count(total.unchanged)),
+            ("kept", count(total.kept)),
+            ("replaced", count(total.replaced)),
+            ("alternatives", count(total.alternatives)),
+        ]),
+    );
+    report.output("dictionary", &args.out);
+    Ok(())
+}
+
+/// Reads a dictionary file or, by extension, an SQLite dictionary.
+fn load_dictionary(path: &Path) -> Result<TranslationDictionary, String> {
+    open_store(path)
+        .and_then(|store| store.to_dictionary())
+        .map_err(|e| e.to_string())
+}
+
+/// Writes `dict` to `path`, replacing it; `.sqlite`/`.db` paths get an
+/// SQLite dictionary (feature `sqlite`).
+fn save_dictionary(dict: &TranslationDictionary, path: &Path) -> Result<(), String> {
+    if !is_sqlite_path(path) {
+        return dict.save_to_path(path).map_err(|e| e.to_string());
+    }
+    save_sqlite_dictionary(dict, path)
+}
+
+#[cfg(feature = "sqlite")]
+fn save_sqlite_dictionary(dict: &TranslationDictionary, path: &Path) -> Result<(), String> {
+    if path.exists() {
+        std::fs::remove_file(path).map_err(|e| format!("remove {}: {e}", path.display()))?;
+    }
+    let mut store = SqliteDictionaryStore::create(path).map_err(|e| e.to_string())?;
+    store
+        .append_all(dict)
+        .and_then(|()| store.save())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn save_sqlite_dictionary(_dict: &TranslationDictionary, path: &Path) -> Result<(), String> {
+    Err(format!(
+        "{}: SQLite dictionaries need xt_batch built with feature `sqlite`",
+        path.display()
+    ))
+}
+
+fn import_and_apply(
+    base_entries: &[Entry],
+    import_xml: &Path,
+    profile: ApplyProfile,
+    report: &Report,
+) -> Result<(Vec<Entry>, XmlApplyStats), String> {
+    let trans_xml = std::fs::read_to_string(import_xml)
+        .map_err(|e| format!("read {}: {e}", import_xml.display()))?;
+    let imported = import_entries_any(&trans_xml).map_err(|e| format!("parse import xml: {e}"))?;
+    let (merged, stats) = apply_xml_with_profile(base_entries, &imported, profile);
+    report.text(format!(
+        "xml apply ({}): updated={} unchanged={} missing={}",
+        profile.as_str(),
+        stats.updated,
+        stats.unchanged,
+        stats.missing
+    ));
+    report.text(format!(
+        "xml apply decisions: by_key={} by_source={} ambiguous={} kept_existing={} cleared={}",
+        stats.by_key, stats.by_source, stats.ambiguous, stats.kept_existing, stats.cleared
+    ));
+    Ok((merged, stats))
+}
+
+/// Applies each of `import_xmls` in turn, later files over earlier ones, and
+/// reports them as `xml_apply` with the counts summed over the files and a
+/// `files` breakdown.
+fn import_and_apply_all(
+    base_entries: &[Entry],
+    import_xmls: &[PathBuf],
+    profile: ApplyProfile,
+    report: &mut Report,
+) -> Result<(Vec<Entry>, XmlApplyStats), String> {
+    let mut merged = base_entries.to_vec();
+    let mut total = XmlApplyStats::default();
+    let mut files = Vec::new();
+    for (index, import_xml) in import_xmls.iter().enumerate() {
+        if import_xmls.len() > 1 {
+            report.text(format!(
+                "xml file {}/{}: {}",
+                index + 1,
+                import_xmls.len(),
+                import_xml.display()
+            ));
+        }
+        let (next, stats) = import_and_apply(&merged, import_xml, profile, report)?;
+        merged = next;
+        total.updated += stats.updated;
+        total.unchanged += stats.unchanged;
+        total.missing += stats.missing;
+        total.by_key += stats.by_key;
+        total.by_source += stats.by_source;
+        total.ambiguous += stats.ambiguous;
+        total.kept_existing += stats.kept_existing;
+        total.cleared += stats.cleared;
+        let mut file = xml_stats_json(profile, &stats);
+        if let JsonValue::Object(fields) = &mut file {
+            fields.shift_insert(
+                0,
+                "path".to_string(),
+                import_xml.display().to_string().into(),
+            );
+        }
+        files.push(file);
+    }
+    if import_xmls.len() > 1 {
+        report.text(format!(
+            "xml apply total ({} files): updated={} changed={}",
+            import_xmls.len(),
+            total.updated,
+            changed_targets(base_entries, &merged).len()
+        ));
+    }
+    let mut json = xml_stats_json(profile, &total);
+    if let JsonValue::Object(fields) = &mut json {
+        fields.insert("files".to_string(), JsonValue::Array(files));
+    }
+    report.set("xml_apply", json);
+    Ok((merged, total))
+}
+
+fn xml_stats_json(profile: ApplyProfile, stats: &XmlApplyStats) -> JsonValue {
+    object(vec![
+        ("profile", profile.as_str().into()),
+        ("updated", count(stats.updated)),
+        ("unchanged", count(stats.unchanged)),
+        ("missing", count(stats.missing)),
+        ("by_key", count(stats.by_key)),
+        ("by_source", count(stats.by_source)),
+        ("ambiguous", count(stats.ambiguous)),
+        ("kept_existing", count(stats.kept_existing)),
+        ("cleared", count(stats.cleared)),
+    ])
+}
+
+impl ApplyArgs {
+    /// `--finalize`, or the `--output-template` name next to the input.
+    fn finalize_path(&self) -> Option<PathBuf> {
+        if self.finalize.is_some() {
+            return self.finalize.clone();
+        }
+        let template = self.output_template.as_ref()?;
+        let input = [
+            &self.base.load_plugin,
+            &self.base.load_strings,
+            &self.base.load,
+        ]
+        .into_iter()
+        .find_map(Option::as_ref)?;
+        let dir = input.parent().unwrap_or_else(|| Path::new("."));
+        Some(template.expand(dir, input, &self.output_language, unix_now()))
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+fn run_apply(args: &ApplyArgs, report: &mut Report) -> Result<(), CliError> {
+    let (base_entries, base_kind) = load_base(
+        &args.base,
+        args.workspace_root.as_deref(),
+        &args.mo2,
+        args.game,
+        args.plugin_language.as_deref(),
+    )?;
+    let (mut merged, stats) =
+        import_and_apply_all(&base_entries, &args.importxml, args.apply_profile, report)?;
+    report.set("entries", count(merged.len()));
+
+    let mut dict_updated = 0usize;
+    if let Some(dict_path) = args.dict_in.clone() {
+        let store = open_store(&dict_path).map_err(|e| e.to_string())?;
+        let all_keys = merged.iter().map(|e| e.key.clone()).collect::<Vec<_>>();
+        let (next, proposals) = store
+            .apply_quick(&merged, &all_keys, true, args.dict_match)
+            .map_err(|e| e.to_string())?;
+        merged = next;
+        dict_updated = proposals.iter().filter(|p| p.applied).count();
+        report.text(format!(
+            "quick auto-translate applied: updated={dict_updated}"
+        ));
+        report.set("dict_updated", count(dict_updated));
+    }
+
+    if args.dry_run {
+        return dry_run_apply(args, &base_kind, &base_entries, &merged, report);
+    }
+
+    if let Some(dict_out) = args.dict_out.clone() {
+        let dict = TranslationDictionary::build_from_entries(&merged);
+        save_dictionary(&dict, &dict_out)?;
+        report.text(format!(
+            "saved dictionary: pairs={} out={}",
+            dict.len(),
+            dict_out.display()
+        ));
+        report.output("dictionary", &dict_out);
+    }
+
+    if let Some(dsd_out) = args.export_dsd.clone() {
+        export_dsd(&base_kind, &merged, &dsd_out, report)?;
+    }
+
+    if let Some(localized_out) = args.export_localized.clone() {
+        export_localized(
+            &base_kind,
+            &merged,
+            &localized_out,
+            &args.strings_language,
+            report,
+        )?;
+    }
+
+    let Some(finalize) = args.finalize_path() else {
+        return Ok(());
+    };
+    let changed = changed_targets(&base_entries, &merged).len();
+    let saved = finalize_output(&base_kind, &merged, changed, &finalize)?;
+    report.text(format!(
+        "finalized: xml_updated={} xml_unchanged={} xml_missing={} dict_updated={} out={}",
+        stats.updated,
+        stats.unchanged,
+        stats.missing,
+        dict_updated,
+        finalize.display()
+    ));
+    report.output("finalize", &finalize);
+    if args.manifest {
+        write_manifest(&saved, report)?;
+    }
+    Ok(())
+}
+
+/// Reports changed targets, validation results and the outputs `apply` would
+/// have written.
+fn dry_run_apply(
+    args: &ApplyArgs,
+    base_kind: &BaseKind,
+    base_entries: &[Entry],
+    merged: &[Entry],
+    report: &mut Report,
+) -> Result<(), CliError> {
+    report.set("dry_run", JsonValue::Bool(true));
+    let changes = changed_targets(base_entries, merged);
+    report.text(format!("dry run: would change {} entries", changes.len()));
+    report.set("would_change", count(changes.len()));
+    if args.show_diff {
+        report.set("changes", JsonValue::Array(Vec::new()));
+        for (key, before, after) in &changes {
+            report.text(format!(
+                "~ {key}: {} => {}",
+                one_line(before),
+                one_line(after)
+            ));
+            report.push(
+                "changes",
+                object(vec![
+                    ("key", (*key).into()),
+                    ("before", (*before).into()),
+                    ("after", (*after).into()),
+                ]),
+            );
+        }
+    }
+
+    let config = load_validation_config(args.validation_config.as_deref())?;
+    let summary = validate_entries(merged, &config, report)?;
+
+    let planned = [
+        ("dictionary", args.dict_out.as_ref()),
+        ("dsd", args.export_dsd.as_ref()),
+        ("localized", args.export_localized.as_ref()),
+    ];
+    for (kind, path) in planned {
+        let Some(path) = path else {
+            continue;
+        };
+        report.text(format!("would write {kind}: {}", path.display()));
+        report.push(
+            "planned_outputs",
+            object(vec![
+                ("kind", kind.into()),
+                ("path", path.display().to_string().into()),
+            ]),
+        );
+    }
+    if let Some(finalize) = args.finalize_path() {
+        let plan = plan_finalize(base_kind, merged, changes.len(), &finalize);
+        for (index, file) in plan.files.iter().enumerate() {
+            let kind = if index == 0 { "finalize" } else { "strings" };
+            report.text(format!(
+                "would {} {kind}: {} ({} strings)",
+                file.change.as_str(),
+                file.path.display(),
+                file.changed_strings
+            ));
+            report.push(
+                "planned_outputs",
+                object(vec![
+                    ("kind", kind.into()),
+                    ("path", file.path.display().to_string().into()),
+                    ("change", file.change.as_str().into()),
+                    ("strings", count(file.changed_strings)),
+                ]),
+            );
+        }
+    }
+    if summary.errors > 0 {
+        return Err(CliError {
+            code: EXIT_VALIDATION_ERRORS,
+            message: format!("dry run: validation errors={}", summary.errors),
+        });
+    }
+    Ok(())
+}
+
+/// `(key, before, after)` for every entry whose target differs from the base.
+fn changed_targets<'a>(base: &'a [Entry], merged: &'a [Entry]) -> Vec<(&'a str, &'a str, &'a str)> {
+    let before = base
+        .iter()
+        .map(|entry| (entry.key.as_str(), entry.target_text.as_str()))
+        .collect::<HashMap<_, _>>();
+    merged
+        .iter()
+        .filter_map(|entry| {
+            let old = before.get(entry.key.as_str()).copied().unwrap_or_default();
+            (old != entry.target_text).then_some((
+                entry.key.as_str(),
+                old,
+                entry.target_text.as_str(),
+            ))
+        })
+        .collect()
+}
+
+/// Runs `apply` once, then again after every change to the watched inputs.
+/// Failed runs are reported and the watch continues; stop with Ctrl-C.
+fn run_watch(args: &WatchArgs, format: OutputFormat) -> Result<(), CliError> {
+    let paths = watched_paths(&args.apply);
+    let interval = Duration::from_millis(args.interval_ms.max(50));
+    let mut last = fingerprint(&paths);
+    loop {
+        let mut report = Report::new(format);
+        let result = run_apply(&args.apply, &mut report);
+        match format {
+            OutputFormat::Json => println!("{}", report.into_json("watch", &result)),
+            OutputFormat::Text => {
+                if let Err(err) = &result {
+                    eprintln!("{}", err.message);
+                }
+                let names = paths
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                println!("watching: {names}");
+            }
+        }
+        loop {
+            std::thread::sleep(interval);
+            let next = fingerprint(&paths);
+            if next != last {
+                // Let editors finish writing before re-reading.
+                std::thread::sleep(interval);
+                last = fingerprint(&paths);
+                break;
+            }
+        }
+        if format == OutputFormat::Text {
+            println!("change detected, re-applying");
+        }
+    }
+}
+
+fn watched_paths(args: &ApplyArgs) -> Vec<PathBuf> {
+    args.importxml
+        .iter()
+        .cloned()
+        .chain(args.dict_in.clone())
+        .collect()
+}
+
+/// Modification time and length per path; `None` while a file is missing.
+fn fingerprint(paths: &[PathBuf]) -> Vec<Option<(SystemTime, u64)>> {
+    paths
+        .iter()
+        .map(|path| {
+            let meta = std::fs::metadata(path).ok()?;
+            Some((meta.modified().ok()?, meta.len()))
+        })
+        .collect()
+}
+
+/// One line of the `apply-dir` summary table.
+struct PluginDirRow {
+    plugin: String,
+    entries: usize,
+    xml_updated: Option<usize>,
+    dict_updated: usize,
+    status: String,
+}
+
+fn run_apply_dir(args: &ApplyDirArgs, report: &mut Report) -> Result<(), CliError> {
+    let plugins = list_plugins(&args.load_plugin_dir)?;
+    if plugins.is_empty() {
+        return Err(format!(
+            "no .esp/.esm/.esl found in {}",
+            args.load_plugin_dir.display()
+        )
+        .into());
+    }
+    if let Some(out_dir) = &args.out_dir {
+        if same_dir(out_dir, &args.load_plugin_dir) {
+            return Err("--out-dir must differ from --load-plugin-dir"
+                .to_string()
+                .into());
+        }
+    }
+    // One store for all plugins, looked up through the trait as in `apply`,
+    // so an SQLite dictionary stays on disk. Lookups take turns; loading and
+    // writing the plugins still runs in parallel.
+    let dict = match &args.dict_in {
+        Some(path) => Some(Mutex::new(open_store(path).map_err(|e| e.to_string())?)),
+        None => None,
+    };
+    let pairs = match &args.importxml_dir {
+        Some(dir) => {
+            let pairs = pair_translations(dir, &plugins, &args.language)?;
+            report_unmatched(&plugins, &pairs, report);
+            pairs
+        }
+        None => TranslationPairs {
+            by_plugin: vec![None; plugins.len()],
+            unmatched: Vec::new(),
+            ambiguous: Vec::new(),
+        },
+    };
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(args.jobs)
+        .build()
+        .map_err(|e| format!("thread pool: {e}"))?;
+    // Each plugin logs into its own buffer; the buffers are replayed in
+    // plugin order so the output does not depend on scheduling.
+    let results = pool.install(|| {
+        plugins
+            .par_iter()
+            .zip(&pairs.by_plugin)
+            .map(|(plugin, xml)| {
+                let name = plugin
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let mut plugin_report = Report::buffered(report.format);
+                plugin_report.text(format!("== {name}"));
+                let row = apply_one_plugin(
+                    args,
+                    plugin,
+                    xml.as_deref(),
+                    dict.as_ref(),
+                    &mut plugin_report,
+                )
+                .unwrap_or_else(|err| PluginDirRow {
+                    plugin: String::new(),
+                    entries: 0,
+                    xml_updated: None,
+                    dict_updated: 0,
+                    status: format!("error: {err}"),
+                });
+                (
+                    PluginDirRow {
+                        plugin: name,
+                        ..row
+                    },
+                    plugin_report,
+                )
+            })
+            .collect::<Vec<_>>()
+    });
+    let mut rows = Vec::with_capacity(results.len());
+    for (row, plugin_report) in results {
+        report.merge(plugin_report);
+        rows.push(row);
+    }
+
+    report.text(format_plugin_dir_table(&rows).trim_end());
+    let failed = rows
+        .iter()
+        .filter(|row| row.status.starts_with("error"))
+        .map(|row| row.plugin.as_str())
+        .collect::<Vec<_>>();
+    for row in &rows {
+        report.push(
+            "plugins",
+            object(vec![
+                ("plugin", row.plugin.as_str().into()),
+                ("entries", count(row.entries)),
+                (
+                    "xml_updated",
+                    row.xml_updated.map_or(JsonValue::Null, count),
+                ),
+                ("dict_updated", count(row.dict_updated)),
+                ("status", row.status.as_str().into()),
+            ]),
+        );
+    }
+    if !failed.is_empty() {
+        return Err(CliError {
+            code: EXIT_PARTIAL_FAILURE,
+            message: format!(
+                "{} of {} plugins failed: {}",
+                failed.len(),
+                rows.len(),
+                failed.join(", ")
+            ),
+        });
+    }
+    Ok(())
+}
+
+fn apply_one_plugin(
+    args: &ApplyDirArgs,
+    plugin: &Path,
+    xml: Option<&Path>,
+    dict: Option<&Mutex<Box<dyn DictionaryStore + Send>>>,
+    report: &mut Report,
+) -> Result<PluginDirRow, String> {
+    let base = BaseArgs {
+        load: None,
+        load_strings: None,
+        load_plugin: Some(plugin.to_path_buf()),
+    };
+    let (mut merged, base_kind) = load_base(
+        &base,
+        args.workspace_root.as_deref(),
+        &args.mo2,
+        args.game,
+        args.plugin_language.as_deref(),
+    )?;
+    let mut row = PluginDirRow {
+        plugin: String::new(),
+        entries: merged.len(),
+        xml_updated: None,
+        dict_updated: 0,
+        status: String::new(),
+    };
+
+    if let Some(xml) = xml {
+        let (next, stats) = import_and_apply(&merged, xml, args.apply_profile, report)?;
+        merged = next;
+        row.xml_updated = Some(stats.updated);
+    }
+    if let Some(dict) = dict {
+        let all_keys = merged.iter().map(|e| e.key.clone()).collect::<Vec<_>>();
+        let store = dict.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let (next, proposals) = store
+            .apply_quick(&merged, &all_keys, true, args.dict_match)
+            .map_err(|e| e.to_string())?;
+        merged = next;
+        row.dict_updated = proposals.iter().filter(|p| p.applied).count();
+    }
+    if xml.is_none() && dict.is_none() {
+        row.status = "skipped (no xml)".to_string();
+        return Ok(row);
+    }
+    if args.dry_run {
+        row.status = "ok (dry-run)".to_string();
+        return Ok(row);
+    }
+
+    let file_name = plugin.file_name().unwrap_or_default();
+    if let Some(dsd_dir) = &args.export_dsd_dir {
+        let stem = plugin.file_stem().unwrap_or_default();
+        let out = dsd_dir
+            .join(file_name)
+            .join(Path::new(stem).with_extension("json"));
+        export_dsd(&base_kind, &merged, &out, report)?;
+    }
+    if let Some(out_dir) = &args.out_dir {
+        let out = match &args.output_template {
+            Some(template) => template.expand(out_dir, plugin, &args.output_language, unix_now()),
+            None => out_dir.join(file_name),
+        };
+        let changed = row.xml_updated.unwrap_or_default() + row.dict_updated;
+        let saved = finalize_output(&base_kind, &merged, changed, &out)?;
+        report.output("finalize", &out);
+        if args.manifest {
+            write_manifest(&saved, report)?;
+        }
+    }
+    row.status = "ok".to_string();
+    Ok(row)
+}
+
+/// Plugins directly inside `dir`, sorted case-insensitively by file name.
+fn list_plugins(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let read = std::fs::read_dir(dir).map_err(|e| format!("read {}: {e}", dir.display()))?;
+    let mut plugins = read
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| {
+                    matches!(ext.to_ascii_lowercase().as_str(), "esp" | "esm" | "esl")
+                })
+        })
+        .collect::<Vec<_>>();
+    plugins.sort_by_key(|path| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().to_ascii_lowercase())
+    });
+    Ok(plugins)
+}
+
+/// Translations of `--importxml-dir` paired with plugins.
+struct TranslationPairs {
+    /// Translation of each plugin, in plugin order.
+    by_plugin: Vec<Option<PathBuf>>,
+    /// `.xml`/`.json` files no plugin paired with, sorted by name.
+    unmatched: Vec<PathBuf>,
+    /// Files named after a stem several plugins share (`Foo.esp` and
+    /// `Foo.esm` both wanting `foo.xml`), with those plugins; applied to none.
+    ambiguous: Vec<(PathBuf, Vec<PathBuf>)>,
+}
+
+/// Pairs each plugin with `<plugin file name>.xml` in `dir` or, failing
+/// that, the first of `<stem>` and `<stem>_<language>` that names a `.xml`
+/// (before `.json`) file, ignoring case. A stem file more than one plugin
+/// would take is ambiguous and goes to none of them.
+fn pair_translations(
+    dir: &Path,
+    plugins: &[PathBuf],
+    language: &str,
+) -> Result<TranslationPairs, String> {
+    let read = std::fs::read_dir(dir).map_err(|e| format!("read {}: {e}", dir.display()))?;
+    let mut files = read
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .filter_map(|path| {
+            let name = path.file_name()?.to_string_lossy().to_lowercase();
+            (name.ends_with(".xml") || name.ends_with(".json")).then_some((name, path))
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    let find = |names: &[String], used: &[bool]| {
+        names
+            .iter()
+            .flat_map(|name| [format!("{name}.xml"), format!("{name}.json")])
+            .find_map(|wanted| {
+                files
+                    .iter()
+                    .enumerate()
+                    .position(|(index, (name, _))| !used[index] && *name == wanted)
+            })
+    };
+    let mut used = vec![false; files.len()];
+    let mut matched = vec![None; plugins.len()];
+    for (plugin, matched) in plugins.iter().zip(&mut matched) {
+        let Some(file_name) = plugin.file_name() else {
+            continue;
+        };
+        if let Some(index) = find(&[file_name.to_string_lossy().to_lowercase()], &used) {
+            used[index] = true;
+            *matched = Some(index);
+        }
+    }
+    let language = language.to_lowercase();
+    let mut claims: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (plugin_index, plugin) in plugins.iter().enumerate() {
+        if matched[plugin_index].is_some() {
+            continue;
+        }
+        let Some(stem) = plugin.file_stem() else {
+            continue;
+        };
+        let stem = stem.to_string_lossy().to_lowercase();
+        if let Some(index) = find(&[stem.clone(), format!("{stem}_{language}")], &used) {
+            claims.entry(index).or_default().push(plugin_index);
+        }
+    }
+    let mut ambiguous = Vec::new();
+    for (index, claimants) in claims {
+        used[index] = true;
+        if let [plugin_index] = claimants[..] {
+            matched[plugin_index] = Some(index);
+        } else {
+            let claimants = claimants.iter().map(|&i| plugins[i].clone()).collect();
+            ambiguous.push((files[index].1.clone(), claimants));
+        }
+    }
+    let by_plugin = matched
+        .into_iter()
+        .map(|index| index.map(|index| files[index].1.clone()))
+        .collect();
+    let unmatched = files
+        .into_iter()
+        .zip(used)
+        .filter(|(_, used)| !used)
+        .map(|((_, path), _)| path)
+        .collect();
+    Ok(TranslationPairs {
+        by_plugin,
+        unmatched,
+        ambiguous,
+    })
+}
+
+/// Lists plugins without a translation and translations without a plugin.
+fn report_unmatched(plugins: &[PathBuf], pairs: &TranslationPairs, report: &mut Report) {
+    let file_name = |path: &PathBuf| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+    let plugins = plugins
+        .iter()
+        .zip(&pairs.by_plugin)
+        .filter(|(_, xml)| xml.is_none())
+        .map(|(plugin, _)| file_name(plugin))
+        .collect::<Vec<_>>();
+    let translations = pairs.unmatched.iter().map(file_name).collect::<Vec<_>>();
+    if !plugins.is_empty() {
+        report.text(format!(
+            "plugins without translation: {}",
+            plugins.join(", ")
+        ));
+    }
+    if !translations.is_empty() {
+        report.text(format!(
+            "translations without plugin: {}",
+            translations.join(", ")
+        ));
+    }
+    for (translation, claimants) in &pairs.ambiguous {
+        report.text(format!(
+            "ambiguous translation {} (not applied): {}",
+            file_name(translation),
+            claimants
+                .iter()
+                .map(file_name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    let names = |names: Vec<String>| JsonValue::Array(names.into_iter().map(Into::into).collect());
+    let ambiguous = pairs
+        .ambiguous
+        .iter()
+        .map(|(translation, claimants)| {
+            object(vec![
+                ("translation", file_name(translation).into()),
+                ("plugins", names(claimants.iter().map(file_name).collect())),
+            ])
+        })
+        .collect();
+    report.set(
+        "unmatched",
+        object(vec![
+            ("plugins", names(plugins)),
+            ("translations", names(translations)),
+            ("ambiguous", JsonValue::Array(ambiguous)),
+        ]),
+    );
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn format_plugin_dir_table(rows: &[PluginDirRow]) -> String {
+    let width = rows
+        .iter()
+        .map(|row| row.plugin.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("plugin".len());
+    let mut out = format!(
+        "{:<width$}  {:>7}  {:>11}  {:>12}  status\n",
+        "plugin", "entries", "xml_updated", "dict_updated"
+    );
+    for row in rows {
+        let xml = row
+            .xml_updated
+            .map(|n| n.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        out.push_str(&format!(
+            "{:<width$}  {:>7}  {:>11}  {:>12}  {}\n",
+            row.plugin, row.entries, xml, row.dict_updated, row.status
+        ));
+    }
+    out
+}
+
+fn run_extract(args: &ExtractArgs, report: &mut Report) -> Result<(), CliError> {
+    let (entries, base_kind) = load_base(
+        &args.base,
+        args.workspace_root.as_deref(),
+        &args.mo2,
+        args.game,
+        args.plugin_language.as_deref(),
+    )?;
+    if let Some(parent) = args.out.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("create {}: {e}", parent.display()))?;
+    }
+    let format = args
+        .out_format
+        .unwrap_or_else(|| ExtractFormat::from_path(&args.out));
+    match format {
+        ExtractFormat::Xml | ExtractFormat::Json => {
+            write_entries_file(&args.out, &entries).map_err(|e| e.to_string())?;
+        }
+        ExtractFormat::Xtranslator | ExtractFormat::Csv => {
+            let params = sheet_params(&args.base, &base_kind, &args.language);
+            let content = if format == ExtractFormat::Csv {
+                export_entries_csv(&entries, &params)
+            } else {
+                export_entries_xtranslator(&entries, &params)
+            };
+            write_atomic(&args.out, content)
+                .map_err(|e| format!("write {}: {e}", args.out.display()))?;
+        }
+    }
+    report.text(format!(
+        "extracted: entries={} format={} out={}",
+        entries.len(),
+        format.id(),
+        args.out.display()
+    ));
+    report.set("entries", count(entries.len()));
+    report.set("out_format", format.id().into());
+    report.output("entries", &args.out);
+    Ok(())
+}
+
+/// Addon, languages and, for a plugin, the EDID/REC of each string.
+fn sheet_params(base: &BaseArgs, kind: &BaseKind, dest_lang: &str) -> XtranslatorExportParams {
+    let file_name = |path: &Path| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+    match kind {
+        BaseKind::Esp {
+            input_path,
+            extracted,
+            context,
+        } => XtranslatorExportParams::new(&file_name(input_path), &context.language, dest_lang)
+            .with_extracted(extracted),
+        _ => {
+            let addon = base.load_strings.as_deref().map(file_name);
+            XtranslatorExportParams::new(&addon.unwrap_or_default(), PLUGIN_LANGUAGE, dest_lang)
+        }
+    }
+}
+
+fn run_package(args: &PackageArgs, report: &mut Report) -> Result<(), CliError> {
+    let (base_entries, base_kind) = load_base(
+        &args.base,
+        args.workspace_root.as_deref(),
+        &args.mo2,
+        args.game,
+        args.plugin_language.as_deref(),
+    )?;
+    let (merged, _) =
+        import_and_apply_all(&base_entries, &args.importxml, args.apply_profile, report)?;
+
+    let strings_name = args
+        .base
+        .load_strings
+        .as_deref()
+        .and_then(Path::file_name)
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let (content, source_lang) = match &base_kind {
+        BaseKind::Xml => {
+            return Err("package requires --load-plugin or --load-strings"
+                .to_string()
+                .into())
+        }
+        BaseKind::Strings { .. } if args.dsd => {
+            return Err("--dsd requires --load-plugin".to_string().into())
+        }
+        BaseKind::Strings { base, kind } => (
+            PackageContent::Strings {
+                base,
+                kind: *kind,
+                file_name: &strings_name,
+            },
+            PLUGIN_LANGUAGE,
+        ),
+        BaseKind::Esp {
+            input_path,
+            extracted,
+            context,
+        } if args.dsd => (
+            PackageContent::Dsd {
+                input_path,
+                extracted,
+            },
+            context.language.as_str(),
+        ),
+        BaseKind::Esp {
+            input_path,
+            extracted,
+            context,
+        } => (
+            PackageContent::Plugin {
+                input_path,
+                extracted,
+                context,
+            },
+            context.language.as_str(),
+        ),
+    };
+    let (addon, extracted) = match &base_kind {
+        BaseKind::Esp {
+            input_path,
+            extracted,
+            ..
+        } => (
+            input_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            extracted.as_slice(),
+        ),
+        _ => (strings_name.clone(), &[][..]),
+    };
+    let xml = export_entries_xtranslator(
+        &merged,
+        &XtranslatorExportParams::new(&addon, source_lang, &args.language)
+            .with_extracted(extracted),
+    );
+
+    let manifest = PackageManifest {
+        mod_name: args.mod_name.clone().unwrap_or_else(|| {
+            Path::new(&addon)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        }),
+        mod_version: args.mod_version.clone(),
+        language: args.language.clone(),
+        author: args.author.clone(),
+        created_at: unix_now(),
+    };
+    let out = args
+        .out
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(manifest.file_name()));
+    if let Some(parent) = out.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("create {}: {e}", parent.display()))?;
+    }
+    let packaged =
+        create_package(&out, &merged, content, &xml, &manifest).map_err(|e| e.to_string())?;
+    report.text(format!(
+        "packaged: content={} files={} out={}",
+        content.id(),
+        packaged.files.len(),
+        out.display()
+    ));
+    for file in &packaged.files {
+        report.text(format!("  {file}"));
+    }
+    report.set(
+        "package",
+        object(vec![
+            ("content", content.id().into()),
+            (
+                "files",
+                JsonValue::Array(packaged.files.iter().map(|f| f.as_str().into()).collect()),
+            ),
+        ]),
+    );
+    report.output("package", &out);
+    Ok(())
+}
+
+/// The base entries with `--importxml` applied, as validated.
+fn load_validated_entries(args: &ValidateArgs, report: &mut Report) -> Result<Vec<Entry>, String> {
+    let (mut entries, _) = load_base(
+        &args.base,
+        args.workspace_root.as_deref(),
+        &args.mo2,
+        args.game,
+        args.plugin_language.as_deref(),
+    )?;
+    if !args.importxml.is_empty() {
+        (entries, _) = import_and_apply_all(&entries, &args.importxml, args.apply_profile, report)?;
+    }
+    Ok(entries)
+}
+
+fn run_validate(args: &ValidateArgs, report: &mut Report) -> Result<(), CliError> {
+    let entries = load_validated_entries(args, report)?;
+    let config = load_validation_config(args.config.as_deref())?;
+    let summary = validate_entries(&entries, &config, report)?;
+    if summary.errors > 0 {
+        return Err(CliError {
+            code: EXIT_VALIDATION_ERRORS,
+            message: format!("validation failed: errors={}", summary.errors),
+        });
+    }
+    Ok(())
+}
+
+fn load_validation_config(path: Option<&Path>) -> Result<ValidationConfig, String> {
+    let Some(path) = path else {
+        return Ok(ValidationConfig::default());
+    };
+    let content =
+        std::fs::read_to_string(path).map_err(|e| format!("read {}: {e}", path.display()))?;
+    parse_validation_prefs(&content)
+}
+
+fn run_stats(args: &ValidateArgs, report: &mut Report) -> Result<(), CliError> {
+    let entries = load_validated_entries(args, report)?;
+    let config = load_validation_config(args.config.as_deref())?;
+    let issues = find_issues(&entries, &config)?;
+    let stats = TranslationStats::of_entries(&entries, &issues);
+    report.text(format!(
+        "entries: total={} translated={} untranslated={} percent={:.1}",
+        stats.total.rows,
+        stats.total.translated,
+        stats.total.untranslated(),
+        stats.total.percent()
+    ));
+    report.set("total", tally_json(&stats.total));
+    let mut by_record = Vec::new();
+    for (record, tally) in &stats.by_record {
+        report.text(format!("record {record}: {}", tally_text(tally)));
+        by_record.push((record.as_str(), tally_json(tally)));
+    }
+    report.set("by_record", object(by_record));
+    let mut by_channel = Vec::new();
+    for (channel, tally) in &stats.by_channel {
+        report.text(format!("channel {channel}: {}", tally_text(tally)));
+        by_channel.push((*channel, tally_json(tally)));
+    }
+    report.set("by_channel", object(by_channel));
+
+    let summary = ValidationSummary::from_issues(&issues);
+    let mut by_rule = BTreeMap::<&str, usize>::new();
+    for issue in &issues {
+        *by_rule.entry(issue.rule_id.as_str()).or_default() += 1;
+    }
+    let placeholder = issues
+        .iter()
+        .filter(|issue| issue.rule_id.starts_with("placeholder."))
+        .count();
+    let rows = stats
+        .by_status
+        .get(&StatusGroup::Issues)
+        .map_or(0, |tally| tally.rows);
+    report.text(format!(
+        "issues: rows={rows} errors={} warnings={} infos={} placeholder={placeholder}",
+        summary.errors, summary.warnings, summary.infos
+    ));
+    for (rule, n) in &by_rule {
+        report.text(format!("rule {rule}: {n}"));
+    }
+    report.set(
+        "issues",
+        object(vec![
+            ("rows", count(rows)),
+            ("errors", count(summary.errors)),
+            ("warnings", count(summary.warnings)),
+            ("infos", count(summary.infos)),
+            ("placeholder", count(placeholder)),
+            (
+                "by_rule",
+                object(
+                    by_rule
+                        .into_iter()
+                        .map(|(rule, n)| (rule, count(n)))
+                        .collect(),
+                ),
+            ),
+        ]),
+    );
+    Ok(())
+}
+
+fn tally_text(tally: &Tally) -> String {
+    format!(
+        "entries={} translated={} untranslated={}",
+        tally.rows,
+        tally.translated,
+        tally.untranslated()
+    )
+}
+
+fn tally_json(tally: &Tally) -> JsonValue {
+    object(vec![
+        ("entries", count(tally.rows)),
+        ("translated", count(tally.translated)),
+        ("untranslated", count(tally.untranslated())),
+        (
+            "percent",
+            JsonValue::from((f64::from(tally.percent()) * 10.0).round() / 10.0),
+        ),
+    ])
+}
+
+/// Runs the validator, with the spellchecker when `config` names a dictionary.
+fn find_issues(
+    entries: &[Entry],
+    config: &ValidationConfig,
+) -> Result<Vec<ValidationIssue>, String> {
+    let mut validator = Validator::new(config).map_err(|e| e.to_string())?;
+    let dictionary = config.spell_dictionary.trim();
+    if !dictionary.is_empty() {
+        let checker = SpellChecker::load(Path::new(dictionary)).map_err(|e| e.to_string())?;
+        validator = validator.with_spellchecker(Arc::new(checker));
+    }
+    Ok(validator.validate_entries(entries.iter().map(|e| {
+        (
+            e.key.as_str(),
+            e.source_text.as_str(),
+            e.target_text.as_str(),
+        )
+    })))
+}
+
+/// Reports each issue and a summary.
+fn validate_entries(
+    entries: &[Entry],
+    config: &ValidationConfig,
+    report: &mut Report,
+) -> Result<ValidationSummary, String> {
+    let issues = find_issues(entries, config)?;
+    report.set("issues", JsonValue::Array(Vec::new()));
+    for issue in &issues {
+        report.text(format!(
+            "{}: {} [{}] {}",
+            issue.severity.as_str(),
+            issue.entry_key,
+            issue.rule_id,
+            issue.message
+        ));
+        report.push(
+            "issues",
+            object(vec![
+                ("key", issue.entry_key.as_str().into()),
+                ("severity", issue.severity.as_str().into()),
+                ("rule", issue.rule_id.as_str().into()),
+                ("message", issue.message.as_str().into()),
+            ]),
+        );
+    }
+    let summary = ValidationSummary::from_issues(&issues);
+    report.text(format!(
+        "validated: entries={} errors={} warnings={} infos={}",
+        entries.len(),
+        summary.errors,
+        summary.warnings,
+        summary.infos
+    ));
+    report.set(
+        "validation",
+        object(vec![
+            ("entries", count(entries.len())),
+            ("errors", count(summary.errors)),
+            ("warnings", count(summary.warnings)),
+            ("infos", count(summary.infos)),
+        ]),
+    );
+    Ok(summary)
+}
+
+fn run_diff(args: &DiffArgs, report: &mut Report) -> Result<(), CliError> {
+    let (old, _) = read_strings_file(&args.old).map_err(|e| e.to_string())?;
+    let (new, _) = read_strings_file(&args.new).map_err(|e| e.to_string())?;
+    let diff = diff_strings(&old, &new);
+    let text = diff.report();
+    report.set(
+        "strings_diff",
+        object(vec![
+            ("added", count(diff.added.len())),
+            ("removed", count(diff.removed.len())),
+            ("changed", count(diff.changed.len())),
+            ("unchanged", count(diff.unchanged)),
+        ]),
+    );
+    match &args.out {
+        Some(out) => {
+            write_atomic(out, &text).map_err(|e| format!("write {}: {e}", out.display()))?;
+            report.text(text.lines().next().unwrap_or_default());
+            report.output("report", out);
+        }
+        None => {
+            report.text(text.trim_end());
+            report.set("report", text.as_str().into());
+        }
+    }
+    Ok(())
+}
+
+fn run_strings_info(args: &StringsInfoArgs, report: &mut Report) -> Result<(), CliError> {
+    let (file, _) = read_strings_file(&args.file).map_err(|e| e.to_string())?;
+    let info = file.info().unwrap_or_default();
+    let ids = |ids: &[u32]| JsonValue::Array(ids.iter().map(|&id| count(id as usize)).collect());
+    report.set(
+        "strings_info",
+        object(vec![
+            ("size", count(info.size)),
+            ("entries", count(info.entries)),
+            ("strings", count(info.strings)),
+            ("unused_bytes", count(info.unused_bytes)),
+            ("trailing_bytes", count(info.trailing_bytes)),
+            ("duplicate_ids", ids(&info.duplicate_ids)),
+            ("non_utf8_ids", ids(&info.non_utf8_ids)),
+            ("embedded_null_ids", ids(&info.embedded_null_ids)),
+            (
+                "warnings",
+                JsonValue::Array(
+                    info.warnings()
+                        .iter()
+                        .map(|warning| warning.to_string().into())
+                        .collect(),
+                ),
+            ),
+        ]),
+    );
+    report.text(info.report().trim_end());
+    Ok(())
+}
+
+#[derive(Clone)]
+enum BaseKind {
+    Xml,
+    Strings {
+        base: StringsFile,
+        kind: StringsKind,
+    },
+    Esp {
+        input_path: PathBuf,
+        extracted: Vec<ExtractedString>,
+        context: PluginContext,
+    },
+}
+
+fn load_base(
+    base: &BaseArgs,
+    workspace_root: Option<&Path>,
+    mo2: &Mo2Args,
+    game: Option<GameProfile>,
+    language: Option<&str>,
+) -> Result<(Vec<Entry>, BaseKind), String> {
+    if let Some(path) = base.load.clone() {
+        let xml =
+            std::fs::read_to_string(&path).map_err(|e| format!("read {}: {e}", path.display()))?;
+        let entries = import_entries_any(&xml).map_err(|e| format!("parse base xml: {e}"))?;
+        return Ok((entries, BaseKind::Xml));
+    }
+
+    if let Some(path) = base.load_strings.clone() {
+        let (base, kind) = read_strings_file(&path).map_err(|e| e.to_string())?;
+        let entries = strings_entries(&base);
+        return Ok((entries, BaseKind::Strings { base, kind }));
+    }
+
+    let path = base
+        .load_plugin
+        .clone()
+        .ok_or_else(|| "one of --load, --load-strings, --load-plugin is required".to_string())?;
+    let ext = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    if !matches!(ext.as_str(), "esp" | "esm" | "esl") {
+        return Err("load-plugin supports only .esp/.esm/.esl".to_string());
+    }
+    let context = if mo2.mo2 {
+        PluginContext::resolve_mo2(&path, mo2.mo2_profile.as_deref(), game, language)
+    } else {
+        PluginContext::resolve(&path, workspace_root, game, language)
+    }
+    .map_err(|e| e.to_string())?;
+    let (entries, extracted) = load_plugin_entries(&path, &context).map_err(|e| e.to_string())?;
+    Ok((
+        entries,
+        BaseKind::Esp {
+            input_path: path,
+            extracted,
+            context,
+        },
+    ))
+}
+
+fn export_dsd(
+    base: &BaseKind,
+    entries: &[Entry],
+    out: &Path,
+    report: &mut Report,
+) -> Result<(), String> {
+    let BaseKind::Esp {
+        input_path,
+        extracted,
+        ..
+    } = base
+    else {
+        return Err("--export-dsd requires --load-plugin".to_string());
+    };
+    let params = DsdExportParams {
+        plugin_name: input_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_string(),
+        masters: read_masters(input_path)
+            .map_err(|e| format!("read masters {}: {e}", input_path.display()))?,
+    };
+    let (json, stats) = export_dsd_json(entries, extracted, &params);
+    if let Some(parent) = out.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("create {}: {e}", parent.display()))?;
+    }
+    write_atomic(out, json).map_err(|e| format!("write {}: {e}", out.display()))?;
+    report.text(format!(
+        "exported dsd json: written={} skipped={} out={}",
+        stats.written,
+        stats.skipped,
+        out.display()
+    ));
+    report.output("dsd", out);
+    Ok(())
+}
+
+fn export_localized(
+    base: &BaseKind,
+    entries: &[Entry],
+    out: &Path,
+    language: &str,
+    report: &mut Report,
+) -> Result<(), String> {
+    let BaseKind::Esp {
+        input_path,
+        extracted,
+        context,
+    } = base
+    else {
+        return Err("--export-localized requires --load-plugin".to_string());
+    };
+    if out == input_path.as_path() {
+        return Err("--export-localized must not overwrite the input plugin".to_string());
+    }
+    let result = export_esp_localized(entries, input_path, out, extracted, context, language)
+        .map_err(|e| e.to_string())?;
+    report.text(format!(
+        "exported localized plugin: converted={} skipped={} out={}",
+        result.converted,
+        result.skipped,
+        out.display()
+    ));
+    report.output("localized", out);
+    for path in &result.strings_files {
+        report.text(format!("  strings: {}", path.display()));
+        report.output("strings", path);
+    }
+    Ok(())
+}
+
+/// Files [`finalize_output`] would write; an XML base counts `changed`
+/// entries.
+fn plan_finalize(base: &BaseKind, entries: &[Entry], changed: usize, finalize: &Path) -> SavePlan {
+    match base {
+        BaseKind::Xml => SavePlan {
+            files: vec![PlannedFile::write(finalize, changed)],
+        },
+        BaseKind::Strings { base, .. } => plan_strings_save(entries, base, finalize),
+        BaseKind::Esp {
+            input_path,
+            extracted,
+            context,
+        } => plan_esp_save(
+            entries,
+            input_path,
+            finalize,
+            esp_output_root(input_path, finalize),
+            extracted,
+            context,
+        ),
+    }
+}
+
+/// Strings files go to `Strings/` next to the output, not back into the
+/// game's Data folder, unless the plugin is overwritten.
+fn esp_output_root<'a>(input_path: &Path, finalize: &'a Path) -> Option<&'a Path> {
+    (input_path != finalize).then(|| finalize.parent().unwrap_or_else(|| Path::new(".")))
+}
+
+/// Writes the finalized output; an XML base counts `changed` entries.
+fn finalize_output(
+    base: &BaseKind,
+    entries: &[Entry],
+    changed: usize,
+    finalize: &Path,
+) -> Result<SaveReport, String> {
+    if let Some(parent) = finalize.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("create {}: {e}", parent.display()))?;
+    }
+    match base {
+        BaseKind::Xml => write_entries_file(finalize, entries).and_then(|()| {
+            SaveReport::from_plan(finalize, &plan_finalize(base, entries, changed, finalize))
+        }),
+        BaseKind::Strings { base, kind } => save_strings(entries, base, *kind, finalize),
+        BaseKind::Esp {
+            input_path,
+            extracted,
+            context,
+        } => save_esp(
+            entries,
+            input_path,
+            finalize,
+            esp_output_root(input_path, finalize),
+            extracted,
+            context,
+        ),
+    }
+    .map_err(|e| e.to_string())
+}
+
+fn write_manifest(saved: &SaveReport, report: &mut Report) -> Result<(), String> {
+    let path = saved.write_manifest().map_err(|e| e.to_string())?;
+    report.text(format!(
+        "manifest: files={} out={}",
+        saved.files.len(),
+        path.display()
+    ));
+    report.output("manifest", &path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use xt_core::import_export::{export_entries, export_entries_json};
+
+    fn parse(args: &[&str]) -> Result<Command, clap::Error> {
+        let args = std::iter::once("xt_batch")
+            .chain(args.iter().copied())
+            .map(String::from)
+            .collect::<Vec<_>>();
+        Cli::try_parse_from(normalize_legacy_args(args)).map(|cli| cli.command)
+    }
+
+    fn parse_apply(args: &[&str]) -> ApplyArgs {
+        match parse(args).expect("parse") {
+            Command::Apply(args) => args,
+            other => panic!("expected apply, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn t_batch_001_parse_pipeline_args() {
+        let opts = parse_apply(&[
+            "apply",
+            "--load",
+            "base.xml",
+            "--importxml",
+            "tr.xml",
+            "--finalize",
+            "out.xml",
+        ]);
+        assert_eq!(opts.base.load.as_deref(), Some(Path::new("base.xml")));
+        assert_eq!(opts.importxml, [Path::new("tr.xml")]);
+        assert_eq!(opts.finalize.as_deref(), Some(Path::new("out.xml")));
+    }
+
+    #[test]
+    fn t_batch_002_parse_strings_plugin_args() {
+        let opts = parse_apply(&[
+            "apply",
+            "--load-strings",
+            "a.strings",
+            "--importxml",
+            "x.xml",
+            "--finalize",
+            "out.strings",
+            "--workspace-root",
+            "/game",
+        ]);
+        assert_eq!(
+            opts.base.load_strings.as_deref(),
+            Some(Path::new("a.strings"))
+        );
+        assert_eq!(opts.workspace_root.as_deref(), Some(Path::new("/game")));
+        assert!(parse(&["apply", "--load", "a.xml", "--load-plugin", "b.esp"]).is_err());
+
+        let base = ["apply", "--load-plugin", "A.esp", "--importxml", "x.xml"];
+        let opts = parse_apply(&[&base[..], &["--finalize", "o.esp", "--mo2"]].concat());
+        assert!(opts.mo2.mo2);
+        assert_eq!(opts.mo2.mo2_profile, None);
+        let opts = parse_apply(
+            &[
+                &base[..],
+                &["--finalize", "o.esp", "--mo2", "--mo2-profile", "JP"],
+            ]
+            .concat(),
+        );
+        assert_eq!(opts.mo2.mo2_profile.as_deref(), Some("JP"));
+        assert!(
+            parse(&[&base[..], &["--finalize", "o.esp", "--mo2-profile", "JP"]].concat()).is_err()
+        );
+        assert!(parse(
+            &[
+                &base[..],
+                &["--finalize", "o.esp", "--mo2", "--workspace-root", "/game"]
+            ]
+            .concat()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn t_batch_003_parse_export_dsd_args() {
+        let opts = parse_apply(&[
+            "apply",
+            "--load-plugin",
+            "MyMod.esp",
+            "--importxml",
+            "x.xml",
+            "--export-dsd",
+            "out/MyMod.json",
+        ]);
+        assert_eq!(
+            opts.export_dsd.as_deref(),
+            Some(Path::new("out/MyMod.json"))
+        );
+        assert!(opts.finalize.is_none());
+        assert!(parse(&["apply", "--load", "a.xml", "--importxml", "x.xml"]).is_err());
+    }
+
+    #[test]
+    fn t_batch_004_parse_apply_profile() {
+        let base = [
+            "apply",
+            "--load",
+            "a.xml",
+            "--importxml",
+            "x.xml",
+            "--finalize",
+            "o.xml",
+        ];
+        let opts = parse_apply(&[&base[..], &["--apply-profile", "only-empty"]].concat());
+        assert_eq!(opts.apply_profile, ApplyProfile::OnlyEmptyTargets);
+
+        let default = parse_apply(&base);
+        assert_eq!(default.apply_profile, ApplyProfile::SourceFallback);
+
+        let err = parse(&[&base[..], &["--apply-profile", "newest"]].concat())
+            .expect_err("invalid profile");
+        assert!(err.to_string().contains("key-exact"));
+    }
+
+    #[test]
+    fn t_batch_005_parse_diff_strings_args() {
+        let Command::Diff(parsed) =
+            parse(&["diff", "a.strings", "b.strings", "--out", "r.txt"]).expect("parse")
+        else {
+            panic!("expected diff");
+        };
+        assert_eq!(parsed.old, Path::new("a.strings"));
+        assert_eq!(parsed.new, Path::new("b.strings"));
+        assert_eq!(parsed.out.as_deref(), Some(Path::new("r.txt")));
+        assert!(parse(&["diff", "a.strings"]).is_err());
+        assert!(parse(&["diff", "a", "b", "--x", "y"]).is_err());
+    }
+
+    #[test]
+    fn t_batch_006_legacy_flags_map_to_subcommands() {
+        let opts = parse_apply(&[
+            "--load",
+            "base.xml",
+            "--importxml",
+            "tr.xml",
+            "--finalize",
+            "o.xml",
+        ]);
+        assert_eq!(opts.base.load.as_deref(), Some(Path::new("base.xml")));
+
+        let Command::Dict(DictCommand::Build(dict)) = parse(&[
+            "--generate-dictionary",
+            "Data/Strings/Translations",
+            "--dict-out",
+            "dict.tsv",
+        ])
+        .expect("parse") else {
+            panic!("expected dict build");
+        };
+        assert_eq!(
+            dict.strings_dir.as_deref(),
+            Some(Path::new("Data/Strings/Translations"))
+        );
+        assert_eq!(
+            (dict.source.as_str(), dict.target.as_str()),
+            ("english", "japanese")
+        );
+    }
+
+    #[test]
+    fn t_batch_007_validate_reports_errors() {
+        let root = std::env::temp_dir().join(format!("xt_batch_validate_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let base = root.join("base.xml");
+        let entries = vec![
+            Entry {
+                key: "k1".to_string(),
+                source_text: "Hello {0}".into(),
+                target_text: Default::default(),
+            },
+            Entry {
+                key: "k2".to_string(),
+                source_text: "Iron Sword".into(),
+                target_text: "鉄の剣".into(),
+            },
+        ];
+        std::fs::write(&base, export_entries(&entries)).expect("write base");
+        let translation = root.join("tr.json");
+        std::fs::write(
+            &translation,
+            export_entries_json(&[Entry {
+                key: "k1".to_string(),
+                source_text: "Hello {0}".into(),
+                target_text: "こんにちは".into(),
+            }]),
+        )
+        .expect("write translation");
+
+        let base_arg = base.to_string_lossy().to_string();
+        let Command::Validate(args) = parse(&["validate", "--load", &base_arg]).expect("parse")
+        else {
+            panic!("expected validate");
+        };
+        assert!(run_validate(&args, &mut Report::new(OutputFormat::Text)).is_ok());
+
+        let translation_arg = translation.to_string_lossy().to_string();
+        let Command::Validate(args) = parse(&[
+            "validate",
+            "--load",
+            &base_arg,
+            "--importxml",
+            &translation_arg,
+        ])
+        .expect("parse") else {
+            panic!("expected validate");
+        };
+        let err = run_validate(&args, &mut Report::new(OutputFormat::Text))
+            .expect_err("placeholder mismatch");
+        assert_eq!(err.code, EXIT_VALIDATION_ERRORS);
+        assert_eq!(err.message, "validation failed: errors=1");
+
+        let mut report = Report::new(OutputFormat::Json);
+        let result = run_validate(&args, &mut report);
+        let json = report.into_json("validate", &result);
+        assert_eq!(
+            json.get("status").and_then(JsonValue::as_str),
+            Some("error")
+        );
+        assert_eq!(json.get("exit_code"), Some(&JsonValue::from(3)));
+        let validation = json.get("validation").expect("validation");
+        assert_eq!(validation.get("errors"), Some(&JsonValue::from(1)));
+        let issues = json
+            .get("issues")
+            .and_then(JsonValue::as_array)
+            .expect("issues");
+        assert_eq!(issues[0].get("key").and_then(JsonValue::as_str), Some("k1"));
+        let xml = json.get("xml_apply").expect("xml_apply");
+        assert_eq!(xml.get("updated"), Some(&JsonValue::from(1)));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_batch_008_plugin_dir_listing_and_table() {
+        let Command::ApplyDir(parsed) = parse(&[
+            "--load-plugin-dir",
+            "Data",
+            "--dict-in",
+            "dict.tsv",
+            "--out-dir",
+            "out",
+        ])
+        .expect("parse") else {
+            panic!("expected apply-dir");
+        };
+        assert_eq!(parsed.load_plugin_dir, Path::new("Data"));
+        assert!(parse(&["apply-dir", "--load-plugin-dir", "Data", "--out-dir", "o"]).is_err());
+        assert!(parse(&["apply-dir", "--load-plugin-dir", "Data", "--dict-in", "d"]).is_err());
+
+        let root = std::env::temp_dir().join(format!("xt_batch_dir_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let data = root.join("Data");
+        let xml = root.join("xml");
+        std::fs::create_dir_all(&data).expect("create data");
+        std::fs::create_dir_all(&xml).expect("create xml");
+        for name in ["b.esp", "A.ESM", "c.esl", "readme.txt"] {
+            std::fs::write(data.join(name), b"").expect("write");
+        }
+        std::fs::write(xml.join("A.json"), "[]").expect("write");
+        std::fs::write(xml.join("b.xml"), "").expect("write");
+
+        let plugins = list_plugins(&data).expect("list");
+        let names = plugins
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["A.ESM", "b.esp", "c.esl"]);
+        let pairs = pair_translations(&xml, &plugins, "japanese").expect("pair");
+        assert_eq!(pairs.by_plugin[0], Some(xml.join("A.json")));
+        assert_eq!(pairs.by_plugin[1], Some(xml.join("b.xml")));
+        assert_eq!(pairs.by_plugin[2], None);
+        assert!(same_dir(&data, &root.join("xml/../Data")));
+        let _ = std::fs::remove_dir_all(&root);
+
+        let table = format_plugin_dir_table(&[PluginDirRow {
+            plugin: "Skyrim.esm".to_string(),
+            entries: 12,
+            xml_updated: None,
+            dict_updated: 3,
+            status: "ok".to_string(),
+        }]);
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("plugin"));
+        assert!(lines[1].starts_with("Skyrim.esm"));
+        assert!(lines[1].contains(" - "));
+        assert!(lines[1].ends_with("ok"));
+    }
+
+    #[test]
+    fn t_batch_009_format_flag_and_json_report() {
+        let cli = Cli::try_parse_from(normalize_legacy_args(
+            [
+                "xt_batch",
+                "--format",
+                "json",
+                "--load",
+                "a.xml",
+                "--importxml",
+                "x.xml",
+                "--finalize",
+                "o.xml",
+            ]
+            .map(String::from)
+            .to_vec(),
+        ))
+        .expect("legacy parse");
+        assert_eq!(cli.format, OutputFormat::Json);
+        assert_eq!(cli.command.name(), "apply");
+
+        let cli =
+            Cli::try_parse_from(["xt_batch", "diff", "a", "b", "--format", "json"]).expect("parse");
+        assert_eq!(cli.format, OutputFormat::Json);
+        assert!(Cli::try_parse_from(["xt_batch", "diff", "a", "b", "--format", "yaml"]).is_err());
+
+        let mut report = Report::new(OutputFormat::Json);
+        report.set("entries", count(2));
+        report.output("finalize", Path::new("out/a.xml"));
+        report.output("dsd", Path::new("out/a.json"));
+        let text = report.into_json("apply", &Ok(())).to_string();
+        assert_eq!(
+            text,
+            r#"{"command":"apply","status":"ok","exit_code":0,"entries":2,"outputs":[{"kind":"finalize","path":"out/a.xml"},{"kind":"dsd","path":"out/a.json"}]}"#
+        );
+    }
+
+    #[test]
+    fn t_batch_010_dry_run_writes_nothing() {
+        let root = std::env::temp_dir().join(format!("xt_batch_dry_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let base = root.join("base.xml");
+        std::fs::write(
+            &base,
+            export_entries(&[
+                Entry {
+                    key: "k1".to_string(),
+                    source_text: "Iron Sword".into(),
+                    target_text: Default::default(),
+                },
+                Entry {
+                    key: "k2".to_string(),
+                    source_text: "Bread".into(),
+                    target_text: "パン".into(),
+                },
+            ]),
+        )
+        .expect("write base");
+        let translation = root.join("tr.xml");
+        std::fs::write(
+            &translation,
+            export_entries(&[Entry {
+                key: "k1".to_string(),
+                source_text: "Iron Sword".into(),
+                target_text: "鉄の剣\n改".into(),
+            }]),
+        )
+        .expect("write translation");
+        let out = root.join("out.xml");
+        let arg = |path: &Path| path.to_string_lossy().to_string();
+
+        assert!(parse(&[
+            "apply",
+            "--load",
+            "a",
+            "--importxml",
+            "b",
+            "--finalize",
+            "c",
+            "--show-diff"
+        ])
+        .is_err());
+        let (base_arg, tr_arg, out_arg) = (arg(&base), arg(&translation), arg(&out));
+        let args = parse_apply(&[
+            "apply",
+            "--load",
+            &base_arg,
+            "--importxml",
+            &tr_arg,
+            "--finalize",
+            &out_arg,
+            "--dry-run",
+            "--show-diff",
+        ]);
+        let mut report = Report::new(OutputFormat::Json);
+        let result = run_apply(&args, &mut report);
+        assert!(result.is_ok());
+        assert!(!out.exists());
+
+        let json = report.into_json("apply", &result);
+        assert_eq!(json.get("dry_run"), Some(&JsonValue::Bool(true)));
+        assert_eq!(json.get("would_change"), Some(&JsonValue::from(1)));
+        let changes = json
+            .get("changes")
+            .and_then(JsonValue::as_array)
+            .expect("changes");
+        assert_eq!(
+            changes[0].get("key").and_then(JsonValue::as_str),
+            Some("k1")
+        );
+        assert_eq!(
+            changes[0].get("after").and_then(JsonValue::as_str),
+            Some("鉄の剣\n改")
+        );
+        let planned = json
+            .get("planned_outputs")
+            .and_then(JsonValue::as_array)
+            .expect("planned");
+        assert_eq!(
+            planned[0].get("kind").and_then(JsonValue::as_str),
+            Some("finalize")
+        );
+        assert_eq!(
+            planned[0].get("change").and_then(JsonValue::as_str),
+            Some("create")
+        );
+        assert_eq!(planned[0].get("strings"), Some(&JsonValue::from(1)));
+        assert!(json.get("outputs").is_none());
+        assert_eq!(one_line("a\r\nb"), "a\\r\\nb");
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_batch_011_watch_args_and_fingerprint() {
+        let Command::Watch(parsed) = parse(&[
+            "watch",
+            "--load-plugin",
+            "X.esp",
+            "--importxml",
+            "X.xml",
+            "--dict-in",
+            "dict.tsv",
+            "--finalize",
+            "out/X.esp",
+        ])
+        .expect("parse") else {
+            panic!("expected watch");
+        };
+        assert_eq!(parsed.interval_ms, 500);
+        assert_eq!(
+            watched_paths(&parsed.apply),
+            vec![PathBuf::from("X.xml"), PathBuf::from("dict.tsv")]
+        );
+
+        let root = std::env::temp_dir().join(format!("xt_batch_watch_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let file = root.join("X.xml");
+        let paths = vec![file.clone()];
+        assert_eq!(fingerprint(&paths), vec![None]);
+        std::fs::write(&file, "a").expect("write");
+        let first = fingerprint(&paths);
+        assert!(first[0].is_some());
+        assert_eq!(fingerprint(&paths), first);
+        std::fs::write(&file, "ab").expect("write");
+        assert_ne!(fingerprint(&paths), first);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_batch_012_export_localized_args() {
+        let opts = parse_apply(&[
+            "apply",
+            "--load-plugin",
+            "Data/MyMod.esp",
+            "--importxml",
+            "x.xml",
+            "--export-localized",
+            "out/MyMod.esp",
+            "--strings-language",
+            "japanese",
+        ]);
+        assert_eq!(
+            opts.export_localized.as_deref(),
+            Some(Path::new("out/MyMod.esp"))
+        );
+        assert_eq!(opts.strings_language, "japanese");
+        assert!(opts.finalize.is_none());
+        assert!(parse(&[
+            "apply",
+            "--load",
+            "a.xml",
+            "--importxml",
+            "x.xml",
+            "--finalize",
+            "out.xml",
+            "--strings-language",
+            "japanese",
+        ])
+        .is_err());
+
+        let mut report = Report::new(OutputFormat::Text);
+        let err = export_localized(
+            &BaseKind::Xml,
+            &[],
+            Path::new("out/MyMod.esp"),
+            PLUGIN_LANGUAGE,
+            &mut report,
+        )
+        .expect_err("xml base");
+        assert!(err.contains("--load-plugin"));
+    }
+
+    #[test]
+    fn t_batch_013_parse_game_profile() {
+        let opts = parse_apply(&[
+            "apply",
+            "--load-plugin",
+            "Data/Vault.esp",
+            "--importxml",
+            "x.xml",
+            "--finalize",
+            "out/Vault.esp",
+            "--game",
+            "fallout4",
+        ]);
+        assert_eq!(opts.game, Some(GameProfile::Fallout4));
+        let opts = parse_apply(&[
+            "apply",
+            "--load-plugin",
+            "Data/Mod.esp",
+            "--importxml",
+            "x.xml",
+            "--finalize",
+            "out/Mod.esp",
+        ]);
+        assert_eq!(opts.game, None);
+        let err = parse(&[
+            "extract",
+            "--load-plugin",
+            "Data/Mod.esp",
+            "--out",
+            "mod.xml",
+            "--game",
+            "morrowind",
+        ])
+        .expect_err("unknown game");
+        assert!(err.to_string().contains("fallout4"));
+    }
+
+    #[test]
+    fn t_batch_014_package_zips_strings_translation() {
+        let root = std::env::temp_dir().join(format!("xt_batch_package_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let strings = root.join("moda_english.strings");
+        let base = StringsFile::new(vec![xt_core::formats::strings::StringsEntry {
+            id: 1,
+            text: "Iron Sword".to_string(),
+        }]);
+        save_strings(&[], &base, StringsKind::Strings, &strings).expect("write strings");
+        let translation = root.join("tr.xml");
+        std::fs::write(
+            &translation,
+            export_entries(&[Entry {
+                key: "strings:1".to_string(),
+                source_text: "Iron Sword".into(),
+                target_text: "鉄の剣".into(),
+            }]),
+        )
+        .expect("write translation");
+        let out = root.join("out").join("moda.zip");
+        let arg = |path: &Path| path.to_string_lossy().to_string();
+        let (strings_arg, tr_arg, out_arg) = (arg(&strings), arg(&translation), arg(&out));
+
+        assert!(parse(&["package", "--load-strings", "a", "--importxml", "b"]).is_err());
+        let Command::Package(args) = parse(&[
+            "package",
+            "--load-strings",
+            &strings_arg,
+            "--importxml",
+            &tr_arg,
+            "--mod-version",
+            "1.0",
+            "--author",
+            "hanako",
+            "--out",
+            &out_arg,
+        ])
+        .expect("parse") else {
+            panic!("expected package");
+        };
+        assert_eq!(args.language, "japanese");
+        assert_eq!(args.mod_name, None);
+        let mut report = Report::new(OutputFormat::Json);
+        let result = run_package(&args, &mut report);
+        assert!(result.is_ok(), "{:?}", result.err().map(|e| e.message));
+        assert!(out.is_file());
+        let json = report.into_json("package", &result);
+        let files = json
+            .get("package")
+            .and_then(|package| package.get("files"))
+            .and_then(JsonValue::as_array)
+            .expect("files");
+        assert_eq!(
+            files
+                .iter()
+                .filter_map(JsonValue::as_str)
+                .collect::<Vec<_>>(),
+            [
+                "Strings/moda_english.strings",
+                "xtrans/moda_english_japanese.xml",
+                "xtrans/package.json"
+            ]
+        );
+
+        let Command::Package(args) = parse(&[
+            "package",
+            "--load-strings",
+            &strings_arg,
+            "--importxml",
+            &tr_arg,
+            "--mod-version",
+            "1.0",
+            "--dsd",
+        ])
+        .expect("parse") else {
+            panic!("expected package");
+        };
+        let err = run_package(&args, &mut Report::new(OutputFormat::Text)).unwrap_err();
+        assert!(err.message.contains("--load-plugin"), "{}", err.message);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_batch_015_dict_merge_policies() {
+        let root = std::env::temp_dir().join(format!("xt_batch_merge_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let community = root.join("community.tsv");
+        let personal = root.join("personal.tsv");
+        std::fs::write(&community, "Iron Sword\t鉄の剣\nSteel\t鋼\n").expect("write");
+        std::fs::write(&personal, "Iron Sword\t鉄のソード\nBow\t弓\n").expect("write");
+        let out = root.join("merged.tsv");
+        let arg = |path: &Path| path.to_string_lossy().to_string();
+        let (a, b, o) = (arg(&community), arg(&personal), arg(&out));
+
+        assert!(parse(&["dict", "merge", &a, "-o", &o]).is_err());
+        assert!(parse(&["dict", "merge", &a, &b, "-o", &o, "--policy", "both"]).is_err());
+        let Command::Dict(DictCommand::Merge(args)) =
+            parse(&["dict", "merge", &a, &b, "-o", &o]).expect("parse")
+        else {
+            panic!("expected dict merge");
+        };
+        assert_eq!(args.policy, MergePolicy::KeepExisting);
+
+        let Command::Dict(DictCommand::Merge(args)) =
+            parse(&["dict", "merge", &a, &b, "-o", &o, "--policy", "keep-both"]).expect("parse")
+        else {
+            panic!("expected dict merge");
+        };
+        let mut report = Report::new(OutputFormat::Json);
+        let result = run_dict_merge(&args, &mut report);
+        assert!(result.is_ok(), "{:?}", result.err().map(|e| e.message));
+        let json = report.into_json("dict merge", &result);
+        let dictionary = json.get("dictionary").expect("dictionary");
+        assert_eq!(dictionary.get("pairs"), Some(&JsonValue::from(3)));
+        assert_eq!(dictionary.get("alternatives"), Some(&JsonValue::from(1)));
+        let merged = TranslationDictionary::load_from_path(&out).expect("load");
+        assert_eq!(merged.get("Iron Sword").map(|t| t.as_str()), Some("鉄の剣"));
+        assert_eq!(merged.alternatives("Iron Sword")[0].text, "鉄のソード");
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_batch_016_strings_info_reports_warnings() {
+        let root = std::env::temp_dir().join(format!("xt_batch_info_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let path = root.join("Skyrim_english.strings");
+        let mut bytes = Vec::new();
+        for value in [2u32, 3, 1, 0, 1, 0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(b"Hi\0");
+        std::fs::write(&path, &bytes).expect("write");
+
+        let Command::StringsInfo(args) =
+            parse(&["strings-info", &path.to_string_lossy()]).expect("parse")
+        else {
+            panic!("expected strings-info");
+        };
+        assert!(parse(&["strings-info"]).is_err());
+        let mut report = Report::new(OutputFormat::Json);
+        let result = run_strings_info(&args, &mut report);
+        assert!(result.is_ok(), "{:?}", result.err().map(|e| e.message));
+        let json = report.into_json("strings-info", &result);
+        let info = json.get("strings_info").expect("strings_info");
+        assert_eq!(info.get("entries"), Some(&JsonValue::from(2)));
+        assert_eq!(info.get("strings"), Some(&JsonValue::from(1)));
+        assert_eq!(
+            info.get("duplicate_ids"),
+            Some(&JsonValue::Array(vec![JsonValue::from(1)]))
+        );
+        assert_eq!(
+            info.get("warnings"),
+            Some(&JsonValue::Array(vec![
+                "duplicate string id: 00000001".into()
+            ]))
+        );
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_batch_017_output_template_names_finalized_file() {
+        let root = std::env::temp_dir().join(format!("xt_batch_template_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let base = root.join("Mod.xml");
+        let entry = |target: &str| Entry {
+            key: "k1".to_string(),
+            source_text: "Iron Sword".into(),
+            target_text: target.into(),
+        };
+        std::fs::write(&base, export_entries(&[entry("")])).expect("write base");
+        let translation = root.join("tr.xml");
+        std::fs::write(&translation, export_entries(&[entry("鉄の剣")]))
+            .expect("write translation");
+        let (base_arg, tr_arg) = (
+            base.to_string_lossy().to_string(),
+            translation.to_string_lossy().to_string(),
+        );
+        let apply = ["apply", "--load", &base_arg, "--importxml", &tr_arg];
+
+        assert!(parse(&[&apply[..], &["--output-template", "{name}.xml"]].concat()).is_err());
+        assert!(parse(
+            &[
+                &apply[..],
+                &["--finalize", "o.xml", "--output-template", "{stem}.xml"]
+            ]
+            .concat()
+        )
+        .is_err());
+        assert!(parse(&[&apply[..], &["--output-language", "french"]].concat()).is_err());
+
+        let args = parse_apply(
+            &[
+                &apply[..],
+                &[
+                    "--output-template",
+                    "translated/{lang}/{plugin}_{lang}.{ext}",
+                    "--output-language",
+                    "french",
+                ],
+            ]
+            .concat(),
+        );
+        let mut report = Report::new(OutputFormat::Text);
+        run_apply(&args, &mut report).expect("apply");
+        let out = root
+            .join("translated")
+            .join("french")
+            .join("Mod_french.xml");
+        let written = std::fs::read_to_string(&out).expect("finalized");
+        assert!(written.contains("鉄の剣"));
+
+        assert!(parse(&[
+            "apply-dir",
+            "--load-plugin-dir",
+            "in",
+            "--export-dsd-dir",
+            "dsd",
+            "--output-template",
+            "{stem}.{ext}",
+        ])
+        .is_err());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_batch_018_apply_writes_manifest_of_finalized_file() {
+        let root = std::env::temp_dir().join(format!("xt_batch_manifest_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let base = root.join("Mod.xml");
+        let entry = |target: &str| Entry {
+            key: "k1".to_string(),
+            source_text: "Iron Sword".into(),
+            target_text: target.into(),
+        };
+        std::fs::write(&base, export_entries(&[entry("")])).expect("write base");
+        let translation = root.join("tr.xml");
+        std::fs::write(&translation, export_entries(&[entry("鉄の剣")]))
+            .expect("write translation");
+        let out = root.join("out").join("Mod.xml");
+        let args = parse_apply(&[
+            "apply",
+            "--load",
+            &base.to_string_lossy(),
+            "--importxml",
+            &translation.to_string_lossy(),
+            "--finalize",
+            &out.to_string_lossy(),
+            "--manifest",
+        ]);
+        let mut report = Report::new(OutputFormat::Json);
+        run_apply(&args, &mut report).expect("apply");
+
+        let manifest_path = root.join("out").join("Mod.xml.manifest.json");
+        let manifest: xt_core::pipeline::SaveManifest =
+            serde_json::from_str(&std::fs::read_to_string(&manifest_path).expect("manifest"))
+                .expect("json");
+        let bytes = std::fs::read(&out).expect("finalized");
+        assert_eq!(manifest.changed_strings, 1);
+        assert_eq!(manifest.files.len(), 1);
+        assert_eq!(manifest.files[0].path, "Mod.xml");
+        assert_eq!(
+            manifest.files[0].sha256,
+            xt_core::checksum::sha256_hex(&bytes)
+        );
+        assert!(parse(&[
+            "apply-dir",
+            "--load-plugin-dir",
+            "in",
+            "--export-dsd-dir",
+            "dsd",
+            "--manifest"
+        ])
+        .is_err());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_batch_019_extract_plugin_as_sheet_with_edid_and_rec() {
+        let root = std::env::temp_dir().join(format!("xt_batch_extract_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let subrecord = |tag: &[u8; 4], data: &[u8]| {
+            [tag.as_slice(), &(data.len() as u16).to_le_bytes(), data].concat()
+        };
+        let data = [
+            subrecord(b"EDID", b"IronSword\0"),
+            subrecord(b"FULL", b"Iron Sword\0"),
+        ]
+        .concat();
+        let mut record = Vec::new();
+        record.extend_from_slice(b"WEAP");
+        record.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        record.extend_from_slice(&0u32.to_le_bytes());
+        record.extend_from_slice(&0x0001_2EB7u32.to_le_bytes());
+        record.extend_from_slice(&[0; 8]);
+        record.extend_from_slice(&data);
+        let plugin = root.join("Mod.esp");
+        std::fs::write(&plugin, record).expect("write plugin");
+        let extract = |out: &Path, format: &[&str]| {
+            let plugin = plugin.to_string_lossy();
+            let out = out.to_string_lossy();
+            let args = [
+                &["extract", "--load-plugin", &plugin, "--out", &out][..],
+                format,
+            ]
+            .concat();
+            let Command::Extract(args) = parse(&args).expect("parse") else {
+                panic!("expected extract");
+            };
+            let mut report = Report::new(OutputFormat::Json);
+            let result = run_extract(&args, &mut report);
+            assert!(result.is_ok(), "{:?}", result.err().map(|e| e.message));
+            report.into_json("extract", &result)
+        };
+
+        let csv = root.join("Mod_english.csv");
+        let json = extract(&csv, &[]);
+        assert_eq!(json.get("out_format").and_then(|v| v.as_str()), Some("csv"));
+        assert_eq!(
+            std::fs::read_to_string(&csv).expect("csv"),
+            "key,edid,rec,source,target\n\
+             WEAP:00012EB7:FULL:0,IronSword,WEAP:FULL,Iron Sword,\n"
+        );
+
+        let xml = root.join("Mod_english.xml");
+        extract(&xml, &["--out-format", "xtranslator"]);
+        let written = std::fs::read_to_string(&xml).expect("xml");
+        assert!(written.contains("<Addon>Mod.esp</Addon>"));
+        assert!(written.contains("<Dest>japanese</Dest>"));
+        assert!(written.contains("<EDID>IronSword</EDID>"));
+        assert!(written.contains(r#"<REC id="0" idMax="1">WEAP:FULL</REC>"#));
+        assert!(written.contains("<Source>Iron Sword</Source>"));
+
+        extract(&xml, &[]);
+        let written = std::fs::read_to_string(&xml).expect("xml");
+        assert!(written.contains(r#"<xtrans version="1">"#));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_batch_020_stats_counts_records_and_issues() {
+        let root = std::env::temp_dir().join(format!("xt_batch_stats_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let subrecord = |tag: &[u8; 4], data: &[u8]| {
+            [tag.as_slice(), &(data.len() as u16).to_le_bytes(), data].concat()
+        };
+        let record = |kind: &[u8; 4], form_id: u32, name: &[u8]| {
+            let data = subrecord(b"FULL", name);
+            let mut record = Vec::new();
+            record.extend_from_slice(kind);
+            record.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            record.extend_from_slice(&0u32.to_le_bytes());
+            record.extend_from_slice(&form_id.to_le_bytes());
+            record.extend_from_slice(&[0; 8]);
+            record.extend_from_slice(&data);
+            record
+        };
+        let plugin = root.join("Mod.esp");
+        std::fs::write(
+            &plugin,
+            [
+                record(b"WEAP", 0x0001_2EB7, b"Iron Sword\0"),
+                record(b"WEAP", 0x0001_2EB8, b"Steel Sword\0"),
+                record(b"ARMO", 0x0001_2E4D, b"Hide {0}\0"),
+            ]
+            .concat(),
+        )
+        .expect("write plugin");
+        let translation = root.join("tr.json");
+        std::fs::write(
+            &translation,
+            export_entries_json(&[
+                Entry {
+                    key: "WEAP:00012EB7:FULL:0".to_string(),
+                    source_text: "Iron Sword".into(),
+                    target_text: "鉄の剣".into(),
+                },
+                Entry {
+                    key: "ARMO:00012E4D:FULL:0".to_string(),
+                    source_text: "Hide {0}".into(),
+                    target_text: "革".into(),
+                },
+            ]),
+        )
+        .expect("write translation");
+
+        let plugin_arg = plugin.to_string_lossy().to_string();
+        let translation_arg = translation.to_string_lossy().to_string();
+        let Command::Stats(args) = parse(&[
+            "stats",
+            "--load-plugin",
+            &plugin_arg,
+            "--importxml",
+            &translation_arg,
+        ])
+        .expect("parse") else {
+            panic!("expected stats");
+        };
+        let mut report = Report::new(OutputFormat::Json);
+        let result = run_stats(&args, &mut report);
+        assert!(result.is_ok(), "{:?}", result.err().map(|e| e.message));
+        let json = report.into_json("stats", &result);
+        let number = |value: &JsonValue, key: &str| match value.get(key) {
+            Some(JsonValue::Number(n)) => n.as_f64().expect("finite"),
+            other => panic!("{key}: {other:?}"),
+        };
+        let total = json.get("total").expect("total");
+        assert_eq!(number(total, "entries"), 3.0);
+        assert_eq!(number(total, "translated"), 2.0);
+        assert_eq!(number(total, "untranslated"), 1.0);
+        assert_eq!(number(total, "percent"), 66.7);
+        let by_record = json.get("by_record").expect("by_record");
+        let weap = by_record.get("WEAP").expect("WEAP");
+        assert_eq!(number(weap, "entries"), 2.0);
+        assert_eq!(number(weap, "untranslated"), 1.0);
+        assert_eq!(
+            number(by_record.get("ARMO").expect("ARMO"), "translated"),
+            1.0
+        );
+        let strings = json.get("by_channel").and_then(|c| c.get("strings"));
+        assert_eq!(number(strings.expect("strings"), "entries"), 3.0);
+        let issues = json.get("issues").expect("issues");
+        assert_eq!(number(issues, "rows"), 1.0);
+        assert_eq!(number(issues, "errors"), 1.0);
+        assert_eq!(number(issues, "placeholder"), 1.0);
+        let by_rule = issues.get("by_rule").expect("by_rule");
+        assert_eq!(number(by_rule, "placeholder.braced.mismatch"), 1.0);
+        assert_eq!(json.get("status").and_then(JsonValue::as_str), Some("ok"));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_batch_021_config_file_fills_flags_not_given() {
+        let root = std::env::temp_dir().join(format!("xt_batch_config_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let nested = root.join("mods").join("Mod");
+        std::fs::create_dir_all(&nested).expect("create");
+        assert_eq!(BatchConfig::discover(&nested).expect("discover"), None);
+        std::fs::write(
+            root.join(CONFIG_FILE_NAME),
+            "# project defaults\n\
+             workspace_root = \"Data\"\n\
+             source_language = 'english'\n\
+             target_language = \"japanese\" # Dest\n\
+             dict_in = \"dict/skyrim.tsv\"\n\
+             apply_profile = \"only-empty\"\n\
+             output_template = \"{stem}_{lang}.{ext}\"\n",
+        )
+        .expect("write config");
+        let config = BatchConfig::discover(&nested)
+            .expect("discover")
+            .expect("config");
+        assert_eq!(config.path, root.join(CONFIG_FILE_NAME));
+        assert_eq!(config.workspace_root, Some(root.join("Data")));
+        assert_eq!(config.dict_in, Some(root.join("dict/skyrim.tsv")));
+        assert_eq!(config.target_language.as_deref(), Some("japanese"));
+
+        let with_config = |args: &[&str]| {
+            let args = std::iter::once("xt_batch")
+                .chain(args.iter().copied())
+                .map(String::from)
+                .collect::<Vec<_>>();
+            Cli::try_parse_from(config.apply(normalize_legacy_args(args)))
+                .map(|cli| cli.command)
+                .expect("parse")
+        };
+        let Command::Apply(apply) = with_config(&[
+            "--format",
+            "json",
+            "apply",
+            "--load-plugin",
+            "Mod.esp",
+            "--importxml",
+            "tr.xml",
+            "--apply-profile=key-exact",
+        ]) else {
+            panic!("expected apply");
+        };
+        assert_eq!(apply.workspace_root, Some(root.join("Data")));
+        assert_eq!(apply.plugin_language.as_deref(), Some("english"));
+        assert_eq!(apply.dict_in, Some(root.join("dict/skyrim.tsv")));
+        assert_eq!(apply.apply_profile, ApplyProfile::KeyExact);
+        assert_eq!(apply.finalize, None);
+        assert!(apply.output_template.is_some());
+        assert_eq!(apply.output_language, "japanese");
+
+        let Command::Apply(apply) = with_config(&[
+            "apply",
+            "--load-plugin",
+            "Mod.esp",
+            "--importxml",
+            "tr.xml",
+            "--mo2",
+            "--finalize",
+            "out.esp",
+        ]) else {
+            panic!("expected apply");
+        };
+        assert_eq!(apply.workspace_root, None);
+        assert_eq!(apply.output_template, None);
+        assert_eq!(apply.output_language, DEFAULT_DICT_TARGET_LANG);
+        assert_eq!(apply.apply_profile, ApplyProfile::OnlyEmptyTargets);
+
+        let Command::ApplyDir(apply_dir) = with_config(&[
+            "apply-dir",
+            "--load-plugin-dir",
+            "Data",
+            "--export-dsd-dir",
+            "dsd",
+        ]) else {
+            panic!("expected apply-dir");
+        };
+        assert_eq!(apply_dir.output_template, None);
+        let Command::Dict(DictCommand::Build(build)) = with_config(&[
+            "dict",
+            "build",
+            "--strings-dir",
+            "Strings",
+            "--dict-out",
+            "d.tsv",
+        ]) else {
+            panic!("expected dict build");
+        };
+        assert_eq!(
+            (build.source.as_str(), build.target.as_str()),
+            ("english", "japanese")
+        );
+        assert_eq!(build.dict_out, PathBuf::from("d.tsv"));
+
+        let err = BatchConfig::parse("dict = \"a\"\n", Path::new("xtrans.toml")).expect_err("key");
+        assert!(
+            err.contains("line 1") && err.contains("unknown field `dict`"),
+            "{err}"
+        );
+        let err = BatchConfig::parse("\napply_profile = newest\n", Path::new("xtrans.toml"))
+            .expect_err("unquoted");
+        assert!(err.contains("line 2"), "{err}");
+        let err = BatchConfig::parse("apply_profile = \"newest\"", Path::new("xtrans.toml"))
+            .expect_err("profile");
+        assert!(err.starts_with("invalid --apply-profile: newest"), "{err}");
+        let config = BatchConfig::parse(
+            "dict_in = 'C:\\Data\\d.tsv' # note\nsource_language = \"a\\\"b\"\n",
+            Path::new("xtrans.toml"),
+        )
+        .expect("escapes");
+        assert_eq!(config.dict_in, Some(PathBuf::from(r"C:\Data\d.tsv")));
+        assert_eq!(config.source_language.as_deref(), Some(r#"a"b"#));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_batch_022_importxml_files_apply_in_order() {
+        let root = std::env::temp_dir().join(format!("xt_batch_layers_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let entry = |key: &str, source: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        };
+        let base = root.join("base.xml");
+        std::fs::write(
+            &base,
+            export_entries(&[
+                entry("k1", "Iron Sword", ""),
+                entry("k2", "Bread", ""),
+                entry("k3", "Apple", ""),
+            ]),
+        )
+        .expect("write base");
+        let community = root.join("community.xml");
+        std::fs::write(
+            &community,
+            export_entries(&[
+                entry("k1", "Iron Sword", "鉄の剣"),
+                entry("k2", "Bread", "ぱん"),
+            ]),
+        )
+        .expect("write community");
+        let patch = root.join("patch.json");
+        std::fs::write(
+            &patch,
+            export_entries_json(&[entry("k2", "Bread", "パン"), entry("k3", "Apple", "リンゴ")]),
+        )
+        .expect("write patch");
+        let out = root.join("out.xml");
+        let arg = |path: &Path| path.to_string_lossy().to_string();
+        let (base_arg, community_arg, patch_arg, out_arg) =
+            (arg(&base), arg(&community), arg(&patch), arg(&out));
+        let args = parse_apply(&[
+            "apply",
+            "--load",
+            &base_arg,
+            "--importxml",
+            &community_arg,
+            "--importxml",
+            &patch_arg,
+            "--finalize",
+            &out_arg,
+        ]);
+        assert_eq!(args.importxml, [community.clone(), patch.clone()]);
+
+        let mut report = Report::new(OutputFormat::Json);
+        let result = run_apply(&args, &mut report);
+        assert!(result.is_ok(), "{:?}", result.err().map(|e| e.message));
+        let written =
+            import_entries_any(&std::fs::read_to_string(&out).expect("out")).expect("parse out");
+        let targets = written
+            .iter()
+            .map(|e| e.target_text.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(targets, ["鉄の剣", "パン", "リンゴ"]);
+
+        let json = report.into_json("apply", &result);
+        let xml = json.get("xml_apply").expect("xml_apply");
+        assert_eq!(xml.get("updated"), Some(&JsonValue::from(4)));
+        let files = xml
+            .get("files")
+            .and_then(JsonValue::as_array)
+            .expect("files");
+        assert_eq!(files.len(), 2);
+        assert_eq!(
+            files[0].get("path").and_then(JsonValue::as_str),
+            Some(community_arg.as_str())
+        );
+        assert_eq!(files[0].get("updated"), Some(&JsonValue::from(2)));
+        assert_eq!(files[0].get("missing"), Some(&JsonValue::from(1)));
+        assert_eq!(
+            files[1].get("path").and_then(JsonValue::as_str),
+            Some(patch_arg.as_str())
+        );
+        assert_eq!(files[1].get("updated"), Some(&JsonValue::from(2)));
+        assert_eq!(files[1].get("missing"), Some(&JsonValue::from(1)));
+
+        assert!(parse(&["apply", "--load", &base_arg, "--finalize", &out_arg]).is_err());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_batch_023_apply_dir_runs_plugins_in_parallel_in_order() {
+        let root = std::env::temp_dir().join(format!("xt_batch_parallel_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let (data, xml_dir, out_dir) = (root.join("Data"), root.join("xml"), root.join("out"));
+        for dir in [&data, &xml_dir, &out_dir] {
+            std::fs::create_dir_all(dir).expect("create");
+        }
+        let plugin = |name: &[u8]| {
+            let data = [b"FULL".as_slice(), &(name.len() as u16).to_le_bytes(), name].concat();
+            let mut record = Vec::new();
+            record.extend_from_slice(b"WEAP");
+            record.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            record.extend_from_slice(&0u32.to_le_bytes());
+            record.extend_from_slice(&0x0001_2EB7u32.to_le_bytes());
+            record.extend_from_slice(&[0; 8]);
+            record.extend_from_slice(&data);
+            record
+        };
+        for stem in ["A", "C", "D"] {
+            std::fs::write(data.join(format!("{stem}.esp")), plugin(b"Iron Sword\0"))
+                .expect("write plugin");
+            std::fs::write(
+                xml_dir.join(format!("{stem}.xml")),
+                export_entries(&[Entry {
+                    key: "WEAP:00012EB7:FULL:0".to_string(),
+                    source_text: "Iron Sword".into(),
+                    target_text: format!("鉄の剣{stem}").into(),
+                }]),
+            )
+            .expect("write xml");
+        }
+        std::fs::write(data.join("B.esp"), b"TES4").expect("write broken plugin");
+
+        let arg = |path: &Path| path.to_string_lossy().to_string();
+        let (data_arg, xml_arg, out_arg) = (arg(&data), arg(&xml_dir), arg(&out_dir));
+        let Command::ApplyDir(args) = parse(&[
+            "apply-dir",
+            "--load-plugin-dir",
+            &data_arg,
+            "--importxml-dir",
+            &xml_arg,
+            "--out-dir",
+            &out_arg,
+            "--jobs",
+            "2",
+        ])
+        .expect("parse") else {
+            panic!("expected apply-dir");
+        };
+        assert_eq!(args.jobs, 2);
+        let mut report = Report::new(OutputFormat::Json);
+        let result = run_apply_dir(&args, &mut report);
+        let err = result.as_ref().expect_err("B.esp fails");
+        assert_eq!(err.code, EXIT_PARTIAL_FAILURE);
+        assert_eq!(err.message, "1 of 4 plugins failed: B.esp");
+
+        let json = report.into_json("apply-dir", &result);
+        let plugins = json
+            .get("plugins")
+            .and_then(JsonValue::as_array)
+            .expect("plugins");
+        let rows = plugins
+            .iter()
+            .map(|row| {
+                let field = |key| row.get(key).and_then(JsonValue::as_str).unwrap_or("");
+                (
+                    field("plugin"),
+                    field("status").split(':').next().unwrap_or(""),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            [
+                ("A.esp", "ok"),
+                ("B.esp", "error"),
+                ("C.esp", "ok"),
+                ("D.esp", "ok")
+            ]
+        );
+        let outputs = json
+            .get("outputs")
+            .and_then(JsonValue::as_array)
+            .expect("outputs")
+            .iter()
+            .filter_map(|output| output.get("path").and_then(JsonValue::as_str))
+            .map(|path| Path::new(path).file_name().unwrap_or_default().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(outputs, ["A.esp", "C.esp", "D.esp"]);
+        for stem in ["A", "C", "D"] {
+            let written = std::fs::read(out_dir.join(format!("{stem}.esp"))).expect("out");
+            let target = format!("鉄の剣{stem}");
+            assert!(written
+                .windows(target.len())
+                .any(|window| window == target.as_bytes()));
+        }
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_batch_024_apply_data_pairs_translations_by_name() {
+        let root = std::env::temp_dir().join(format!("xt_batch_pairs_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let (data, translations) = (root.join("Data"), root.join("translations"));
+        std::fs::create_dir_all(&data).expect("create data");
+        std::fs::create_dir_all(&translations).expect("create translations");
+        let record = [
+            b"WEAP".as_slice(),
+            &15u32.to_le_bytes(),
+            &0u32.to_le_bytes(),
+            &0x0001_2EB7u32.to_le_bytes(),
+            &[0; 8],
+            b"FULL",
+            &9u16.to_le_bytes(),
+            b"Iron Axe\0",
+        ]
+        .concat();
+        for name in ["A.esp", "B.esm", "C.esp", "D.esp"] {
+            std::fs::write(data.join(name), &record).expect("write plugin");
+        }
+        let translation = export_entries(&[Entry {
+            key: "WEAP:00012EB7:FULL:0".to_string(),
+            source_text: "Iron Axe".into(),
+            target_text: "鉄の斧".into(),
+        }]);
+        for name in [
+            "a.XML",
+            "B.esm.json",
+            "C_Japanese.xml",
+            "C_french.xml",
+            "notes.txt",
+        ] {
+            std::fs::write(translations.join(name), &translation).expect("write translation");
+        }
+
+        let arg = |path: &Path| path.to_string_lossy().to_string();
+        let (data_arg, translations_arg, out_arg) =
+            (arg(&data), arg(&translations), arg(&root.join("out")));
+        let Command::ApplyDir(args) = parse(&[
+            "apply",
+            "--data",
+            &data_arg,
+            "--translations",
+            &translations_arg,
+            "--out-dir",
+            &out_arg,
+            "--dry-run",
+        ])
+        .expect("parse") else {
+            panic!("expected apply-dir");
+        };
+        assert_eq!(args.importxml_dir.as_deref(), Some(translations.as_path()));
+        let mut report = Report::new(OutputFormat::Json);
+        let result = run_apply_dir(&args, &mut report);
+        assert!(result.is_ok(), "{:?}", result.err().map(|e| e.message));
+        let json = report.into_json("apply-dir", &result);
+        let plugins = json
+            .get("plugins")
+            .and_then(JsonValue::as_array)
+            .expect("plugins");
+        let xml_updated = plugins
+            .iter()
+            .map(|row| row.get("xml_updated").cloned())
+            .collect::<Vec<_>>();
+        let one = Some(JsonValue::from(1));
+        assert_eq!(
+            xml_updated,
+            [one.clone(), one.clone(), one, Some(JsonValue::Null)]
+        );
+        let unmatched = json.get("unmatched").expect("unmatched");
+        let names = |key: &str| {
+            unmatched
+                .get(key)
+                .and_then(JsonValue::as_array)
+                .expect(key)
+                .iter()
+                .filter_map(JsonValue::as_str)
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names("plugins"), ["D.esp"]);
+        assert_eq!(names("translations"), ["C_french.xml"]);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_batch_025_dict_build_from_plugin_pair() {
+        let root = std::env::temp_dir().join(format!("xt_batch_pair_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("ja")).expect("create");
+        let plugin = |name: &str| {
+            let name = [name.as_bytes(), b"\0"].concat();
+            let data = [
+                b"FULL".as_slice(),
+                &(name.len() as u16).to_le_bytes(),
+                &name,
+            ]
+            .concat();
+            [
+                b"WEAP".as_slice(),
+                &(data.len() as u32).to_le_bytes(),
+                &0u32.to_le_bytes(),
+                &0x0001_2EB7u32.to_le_bytes(),
+                &[0; 8],
+                &data,
+            ]
+            .concat()
+        };
+        let (original, translated) = (root.join("Mod.esp"), root.join("ja/Mod.esp"));
+        std::fs::write(&original, plugin("Iron Sword")).expect("write original");
+        std::fs::write(&translated, plugin("鉄の剣")).expect("write translated");
+        let out = root.join("dict.tsv");
+
+        let arg = |path: &Path| path.to_string_lossy().to_string();
+        let (original_arg, translated_arg, out_arg) = (arg(&original), arg(&translated), arg(&out));
+        assert!(parse(&[
+            "dict",
+            "build",
+            "--original",
+            &original_arg,
+            "--dict-out",
+            "d"
+        ])
+        .is_err());
+        assert!(parse(&[
+            "dict",
+            "build",
+            "--strings-dir",
+            "Strings",
+            "--original",
+            &original_arg,
+            "--translated",
+            &translated_arg,
+            "--dict-out",
+            "d",
+        ])
+        .is_err());
+        let Command::Dict(DictCommand::Build(args)) = parse(&[
+            "dict",
+            "build",
+            "--original",
+            &original_arg,
+            "--translated",
+            &translated_arg,
+            "--dict-out",
+            &out_arg,
+        ])
+        .expect("parse") else {
+            panic!("expected dict build");
+        };
+        let mut report = Report::new(OutputFormat::Json);
+        let result = run_dict_build(&args, &mut report);
+        assert!(result.is_ok(), "{:?}", result.err().map(|e| e.message));
+        let json = report.into_json("dict build", &result);
+        let dictionary = json.get("dictionary").expect("dictionary");
+        assert_eq!(dictionary.get("pairs"), Some(&JsonValue::from(1)));
+        assert_eq!(dictionary.get("matched"), Some(&JsonValue::from(1)));
+        let dict = TranslationDictionary::load_from_path(&out).expect("load");
+        assert_eq!(dict.get("Iron Sword").map(|t| &**t), Some("鉄の剣"));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_batch_026_dict_build_from_xml_dir() {
+        let root = std::env::temp_dir().join(format!("xt_batch_xmldict_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let entry = |key: &str, source: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        };
+        std::fs::write(
+            root.join("A.xml"),
+            export_entries(&[
+                entry("k1", "Iron Sword", "鉄の剣"),
+                entry("k2", "Gold", "Gold"),
+            ]),
+        )
+        .expect("write xml");
+        std::fs::write(
+            root.join("B.json"),
+            export_entries_json(&[entry("k3", "Iron Sword", "鉄の剣")]),
+        )
+        .expect("write json");
+        let out = root.join("dict.tsv");
+        let (dir_arg, out_arg) = (
+            root.to_string_lossy().to_string(),
+            out.to_string_lossy().to_string(),
+        );
+
+        assert!(parse(&[
+            "dict",
+            "build",
+            "--xml-dir",
+            &dir_arg,
+            "--strings-dir",
+            "Strings",
+            "--dict-out",
+            "d",
+        ])
+        .is_err());
+        let Command::Dict(DictCommand::Build(args)) = parse(&[
+            "dict",
+            "build",
+            "--xml-dir",
+            &dir_arg,
+            "--dict-out",
+            &out_arg,
+        ])
+        .expect("parse") else {
+            panic!("expected dict build");
+        };
+        let mut report = Report::new(OutputFormat::Json);
+        let result = run_dict_build(&args, &mut report);
+        assert!(result.is_ok(), "{:?}", result.err().map(|e| e.message));
+        let json = report.into_json("dict build", &result);
+        let dictionary = json.get("dictionary").expect("dictionary");
+        assert_eq!(dictionary.get("pairs"), Some(&JsonValue::from(1)));
+        assert_eq!(dictionary.get("files_seen"), Some(&JsonValue::from(2)));
+        let dict = TranslationDictionary::load_from_path(&out).expect("load");
+        assert_eq!(dict.get("Iron Sword").map(|t| &**t), Some("鉄の剣"));
+        assert_eq!(dict.get("Gold"), None);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn t_batch_027_sqlite_dictionary_in_and_out() {
+        let root = std::env::temp_dir().join(format!("xt_batch_sqlite_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let (community, personal) = (root.join("community.tsv"), root.join("personal.tsv"));
+        std::fs::write(&community, "Iron Sword\t鉄の剣\nSteel\t鋼\n").expect("write");
+        std::fs::write(&personal, "Bow\t弓\n").expect("write");
+        let entry = |key: &str, source: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        };
+        let base = root.join("base.xml");
+        std::fs::write(
+            &base,
+            export_entries(&[entry("k1", "Iron Sword", ""), entry("k2", "Bow", "")]),
+        )
+        .expect("write base");
+        let translation = root.join("tr.xml");
+        std::fs::write(&translation, export_entries(&[entry("k9", "Pear", "梨")]))
+            .expect("write translation");
+        let (dict, out) = (root.join("merged.sqlite"), root.join("out.xml"));
+        let arg = |path: &Path| path.to_string_lossy().to_string();
+        let (a, b, d) = (arg(&community), arg(&personal), arg(&dict));
+
+        for _ in 0..2 {
+            let Command::Dict(DictCommand::Merge(args)) =
+                parse(&["dict", "merge", &a, &b, "-o", &d]).expect("parse")
+            else {
+                panic!("expected dict merge");
+            };
+            let result = run_dict_merge(&args, &mut Report::new(OutputFormat::Text));
+            assert!(result.is_ok(), "{:?}", result.err().map(|e| e.message));
+        }
+        let store = open_store(&dict).expect("open sqlite");
+        assert_eq!(store.len().expect("len"), 3);
+        assert_eq!(store.targets("Iron Sword").expect("targets")[0].count, 1);
+        drop(store);
+
+        let (base_arg, tr_arg, out_arg) = (arg(&base), arg(&translation), arg(&out));
+        let args = parse_apply(&[
+            "apply",
+            "--load",
+            &base_arg,
+            "--importxml",
+            &tr_arg,
+            "--dict-in",
+            &d,
+            "--finalize",
+            &out_arg,
+        ]);
+        let mut report = Report::new(OutputFormat::Json);
+        let result = run_apply(&args, &mut report);
+        assert!(result.is_ok(), "{:?}", result.err().map(|e| e.message));
+        let json = report.into_json("apply", &result);
+        assert_eq!(json.get("dict_updated"), Some(&JsonValue::from(2)));
+        let written = std::fs::read_to_string(&out).expect("read out");
+        assert!(
+            written.contains("鉄の剣") && written.contains("弓"),
+            "{written}"
+        );
+
+        // apply-dir looks rows up in the same store.
+        let data = root.join("Data");
+        std::fs::create_dir_all(&data).expect("create data");
+        let full = [b"FULL".as_slice(), &11u16.to_le_bytes(), b"Iron Sword\0"].concat();
+        let mut record = Vec::new();
+        record.extend_from_slice(b"WEAP");
+        record.extend_from_slice(&(full.len() as u32).to_le_bytes());
+        record.extend_from_slice(&0u32.to_le_bytes());
+        record.extend_from_slice(&0x0001_2EB7u32.to_le_bytes());
+        record.extend_from_slice(&[0; 8]);
+        record.extend_from_slice(&full);
+        std::fs::write(data.join("Mod.esp"), record).expect("write plugin");
+        let (data_arg, out_dir_arg) = (arg(&data), arg(&root.join("out")));
+        let Command::ApplyDir(args) = parse(&[
+            "apply-dir",
+            "--load-plugin-dir",
+            &data_arg,
+            "--dict-in",
+            &d,
+            "--out-dir",
+            &out_dir_arg,
+            "--dry-run",
+        ])
+        .expect("parse") else {
+            panic!("expected apply-dir");
+        };
+        let mut report = Report::new(OutputFormat::Json);
+        let result = run_apply_dir(&args, &mut report);
+        assert!(result.is_ok(), "{:?}", result.err().map(|e| e.message));
+        let json = report.into_json("apply-dir", &result);
+        let plugins = json
+            .get("plugins")
+            .and_then(JsonValue::as_array)
+            .expect("plugins");
+        assert_eq!(plugins[0].get("dict_updated"), Some(&JsonValue::from(1)));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_batch_028_dict_match_options() {
+        let args = parse_apply(&[
+            "apply",
+            "--load",
+            "a.xml",
+            "--importxml",
+            "b.xml",
+            "--finalize",
+            "c.xml",
+        ]);
+        assert_eq!(args.dict_match, MatchOptions::EXACT);
+        let args = parse_apply(&[
+            "apply",
+            "--load",
+            "a.xml",
+            "--importxml",
+            "b.xml",
+            "--finalize",
+            "c.xml",
+            "--dict-in",
+            "d.tsv",
+            "--dict-match",
+            "case, trim,punct",
+        ]);
+        assert_eq!(
+            args.dict_match,
+            MatchOptions {
+                ignore_case: true,
+                trim: true,
+                strip_trailing_punctuation: true,
+                ..MatchOptions::EXACT
+            }
+        );
+        assert!(parse(&[
+            "apply",
+            "--load",
+            "a.xml",
+            "--importxml",
+            "b.xml",
+            "--finalize",
+            "c.xml",
+            "--dict-match",
+            "case",
+        ])
+        .is_err());
+        assert!(parse(&[
+            "apply-dir",
+            "--load-plugin-dir",
+            "Data",
+            "--out-dir",
+            "out",
+            "--dict-in",
+            "d.tsv",
+            "--dict-match",
+            "accents",
+        ])
+        .is_err());
+
+        let root = std::env::temp_dir().join(format!("xt_batch_dictmatch_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let entry = |key: &str, source: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        };
+        let (base, translation, dict, out) = (
+            root.join("base.xml"),
+            root.join("tr.xml"),
+            root.join("dict.tsv"),
+            root.join("out.xml"),
+        );
+        std::fs::write(&base, export_entries(&[entry("k1", "Iron  sword.", "")]))
+            .expect("write base");
+        std::fs::write(&translation, export_entries(&[])).expect("write translation");
+        std::fs::write(&dict, "Iron Sword\t鉄の剣\n").expect("write dict");
+        let arg = |path: &Path| path.to_string_lossy().to_string();
+        let (base_arg, tr_arg, dict_arg, out_arg) =
+            (arg(&base), arg(&translation), arg(&dict), arg(&out));
+        let run = |options: &str| {
+            let args = parse_apply(&[
+                "apply",
+                "--load",
+                &base_arg,
+                "--importxml",
+                &tr_arg,
+                "--dict-in",
+                &dict_arg,
+                "--dict-match",
+                options,
+                "--finalize",
+                &out_arg,
+            ]);
+            let mut report = Report::new(OutputFormat::Json);
+            let result = run_apply(&args, &mut report);
+            assert!(result.is_ok(), "{:?}", result.err().map(|e| e.message));
+            report
+                .into_json("apply", &result)
+                .get("dict_updated")
+                .cloned()
+        };
+        assert_eq!(run(""), Some(JsonValue::from(0)));
+        assert_eq!(run("case,space,punct"), Some(JsonValue::from(1)));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_batch_029_pairing_prefers_file_name_and_flags_shared_stems() {
+        let root = std::env::temp_dir().join(format!("xt_batch_pair_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let plugins = ["Foo.esm", "Foo.esp", "Bar.esm", "Bar.esp"].map(|name| root.join(name));
+        for name in ["foo.xml", "Bar.xml", "bar.esp.xml"] {
+            std::fs::write(root.join(name), "").expect("write");
+        }
+
+        let pairs = pair_translations(&root, &plugins, "japanese").expect("pair");
+        assert_eq!(
+            pairs.by_plugin,
+            [
+                None,
+                None,
+                Some(root.join("Bar.xml")),
+                Some(root.join("bar.esp.xml"))
+            ]
+        );
+        assert_eq!(
+            pairs.ambiguous,
+            [(
+                root.join("foo.xml"),
+                vec![plugins[0].clone(), plugins[1].clone()]
+            )]
+        );
+        assert!(pairs.unmatched.is_empty());
+
+        let mut report = Report::new(OutputFormat::Json);
+        report_unmatched(&plugins, &pairs, &mut report);
+        let json = report.into_json("apply-dir", &Ok(()));
+        let ambiguous = json
+            .get("unmatched")
+            .and_then(|unmatched| unmatched.get("ambiguous"))
+            .and_then(JsonValue::as_array)
+            .expect("ambiguous");
+        assert_eq!(
+            ambiguous[0].get("translation").and_then(JsonValue::as_str),
+            Some("foo.xml")
+        );
+        let _ = std::fs::remove_dir_all(&root);
     }
 }
@@ -0,0 +1,307 @@
+//! Entry list columns: display order, visibility and widths, kept in user
+//! prefs, and sorting by a column.
+
+use std::cmp::Ordering;
+
+use xt_core::candidates::ProvenanceMap;
+use xt_core::model::Entry;
+
+use crate::state::{row_fields, EditorIdMap};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EntryColumn {
+    Edid,
+    Record,
+    Source,
+    Target,
+    Ld,
+    /// How the target was produced ([`Provenance`]).
+    Origin,
+}
+
+impl EntryColumn {
+    pub const ALL: [EntryColumn; 6] = [
+        EntryColumn::Edid,
+        EntryColumn::Record,
+        EntryColumn::Source,
+        EntryColumn::Target,
+        EntryColumn::Ld,
+        EntryColumn::Origin,
+    ];
+
+    /// Key used in the prefs file.
+    pub fn id(self) -> &'static str {
+        match self {
+            EntryColumn::Edid => "edid",
+            EntryColumn::Record => "record",
+            EntryColumn::Source => "source",
+            EntryColumn::Target => "target",
+            EntryColumn::Ld => "ld",
+            EntryColumn::Origin => "origin",
+        }
+    }
+
+    pub fn parse(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|column| column.id() == id)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            EntryColumn::Edid => "EDID",
+            EntryColumn::Record => "Record",
+            EntryColumn::Source => "Source",
+            EntryColumn::Target => "Target",
+            EntryColumn::Ld => "LD",
+            EntryColumn::Origin => "Origin",
+        }
+    }
+
+    pub fn default_width(self) -> u16 {
+        match self {
+            EntryColumn::Edid => 120,
+            EntryColumn::Record => 84,
+            EntryColumn::Source | EntryColumn::Target => 240,
+            EntryColumn::Ld => 26,
+            EntryColumn::Origin => 96,
+        }
+    }
+}
+
+pub const MIN_COLUMN_WIDTH: u16 = 20;
+pub const MAX_COLUMN_WIDTH: u16 = 1200;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColumnSetting {
+    pub column: EntryColumn,
+    pub width: u16,
+    pub visible: bool,
+}
+
+/// Every [`EntryColumn`] exactly once, in display order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnLayout {
+    columns: Vec<ColumnSetting>,
+}
+
+impl Default for ColumnLayout {
+    fn default() -> Self {
+        Self {
+            columns: EntryColumn::ALL
+                .into_iter()
+                .map(|column| ColumnSetting {
+                    column,
+                    width: column.default_width(),
+                    visible: true,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl ColumnLayout {
+    /// Layout in `order`; unknown or repeated columns are dropped and missing
+    /// ones appended with their defaults.
+    pub fn with_order(order: &[EntryColumn]) -> Self {
+        let mut layout = Self {
+            columns: Vec::with_capacity(EntryColumn::ALL.len()),
+        };
+        for &column in order.iter().chain(EntryColumn::ALL.iter()) {
+            if layout.get(column).is_none() {
+                layout.columns.push(ColumnSetting {
+                    column,
+                    width: column.default_width(),
+                    visible: true,
+                });
+            }
+        }
+        layout
+    }
+
+    pub fn columns(&self) -> &[ColumnSetting] {
+        &self.columns
+    }
+
+    pub fn visible(&self) -> impl Iterator<Item = &ColumnSetting> {
+        self.columns.iter().filter(|setting| setting.visible)
+    }
+
+    pub fn get(&self, column: EntryColumn) -> Option<&ColumnSetting> {
+        self.columns.iter().find(|setting| setting.column == column)
+    }
+
+    fn get_mut(&mut self, column: EntryColumn) -> &mut ColumnSetting {
+        self.columns
+            .iter_mut()
+            .find(|setting| setting.column == column)
+            .expect("layout holds every column")
+    }
+
+    pub fn set_width(&mut self, column: EntryColumn, width: u16) {
+        self.get_mut(column).width = width.clamp(MIN_COLUMN_WIDTH, MAX_COLUMN_WIDTH);
+    }
+
+    /// Hiding the last visible column is refused; returns whether it changed.
+    pub fn set_visible(&mut self, column: EntryColumn, visible: bool) -> bool {
+        if !visible && self.visible().all(|setting| setting.column == column) {
+            return false;
+        }
+        let setting = self.get_mut(column);
+        let changed = setting.visible != visible;
+        setting.visible = visible;
+        changed
+    }
+
+    /// Moves `column` past the nearest visible column to its left or right;
+    /// returns whether it moved.
+    pub fn move_column(&mut self, column: EntryColumn, right: bool) -> bool {
+        let Some(from) = self
+            .columns
+            .iter()
+            .position(|setting| setting.column == column)
+        else {
+            return false;
+        };
+        let neighbour = if right {
+            (from + 1..self.columns.len()).find(|&at| self.columns[at].visible)
+        } else {
+            (0..from).rev().find(|&at| self.columns[at].visible)
+        };
+        let Some(to) = neighbour else {
+            return false;
+        };
+        let setting = self.columns.remove(from);
+        self.columns.insert(to, setting);
+        true
+    }
+}
+
+/// Entry list order by one column; rows with equal values keep file order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EntrySort {
+    pub column: EntryColumn,
+    pub descending: bool,
+}
+
+impl EntrySort {
+    /// Header click: ascending, then descending, then file order.
+    pub fn cycle(current: Option<EntrySort>, column: EntryColumn) -> Option<EntrySort> {
+        match current {
+            Some(sort) if sort.column == column && sort.descending => None,
+            Some(sort) if sort.column == column => Some(EntrySort {
+                column,
+                descending: true,
+            }),
+            _ => Some(EntrySort {
+                column,
+                descending: false,
+            }),
+        }
+    }
+
+    /// Untranslated rows sort first by [`EntryColumn::Ld`], rows with their
+    /// loaded target first by [`EntryColumn::Origin`].
+    pub fn compare(
+        &self,
+        a: &Entry,
+        b: &Entry,
+        provenance: &ProvenanceMap,
+        editor_ids: &EditorIdMap,
+    ) -> Ordering {
+        let ordering = match self.column {
+            EntryColumn::Edid => row_fields(editor_ids, &a.key, &a.target_text)
+                .0
+                .cmp(row_fields(editor_ids, &b.key, &b.target_text).0),
+            EntryColumn::Record => a.key.cmp(&b.key),
+            EntryColumn::Source => a.source_text.cmp(&b.source_text),
+            EntryColumn::Target => a.target_text.cmp(&b.target_text),
+            EntryColumn::Ld => (!a.target_text.is_empty()).cmp(&!b.target_text.is_empty()),
+            EntryColumn::Origin => provenance.get(&a.key).cmp(&provenance.get(&b.key)),
+        };
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    /// Prefs form, e.g. `target,desc`.
+    pub fn id(&self) -> String {
+        let direction = if self.descending { "desc" } else { "asc" };
+        format!("{},{direction}", self.column.id())
+    }
+
+    pub fn parse(id: &str) -> Option<Self> {
+        let (column, direction) = id.split_once(',')?;
+        let descending = match direction {
+            "asc" => false,
+            "desc" => true,
+            _ => return None,
+        };
+        Some(Self {
+            column: EntryColumn::parse(column)?,
+            descending,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_cols_001_layout_keeps_every_column_once() {
+        let mut layout = ColumnLayout::with_order(&[
+            EntryColumn::Target,
+            EntryColumn::Source,
+            EntryColumn::Target,
+        ]);
+        let order = layout
+            .columns()
+            .iter()
+            .map(|setting| setting.column)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            [
+                EntryColumn::Target,
+                EntryColumn::Source,
+                EntryColumn::Edid,
+                EntryColumn::Record,
+                EntryColumn::Ld,
+                EntryColumn::Origin,
+            ]
+        );
+
+        assert!(layout.move_column(EntryColumn::Target, true));
+        assert_eq!(layout.columns()[1].column, EntryColumn::Target);
+        assert!(layout.set_visible(EntryColumn::Edid, false));
+        assert!(
+            layout.move_column(EntryColumn::Record, false),
+            "skips hidden EDID"
+        );
+        assert_eq!(layout.columns()[1].column, EntryColumn::Record);
+        assert!(!layout.move_column(EntryColumn::Source, false));
+        assert!(layout.set_visible(EntryColumn::Edid, true));
+
+        layout.set_width(EntryColumn::Edid, 3);
+        assert_eq!(
+            layout.get(EntryColumn::Edid).unwrap().width,
+            MIN_COLUMN_WIDTH
+        );
+
+        for column in [
+            EntryColumn::Edid,
+            EntryColumn::Record,
+            EntryColumn::Source,
+            EntryColumn::Target,
+            EntryColumn::Origin,
+        ] {
+            assert!(layout.set_visible(column, false));
+        }
+        assert!(
+            !layout.set_visible(EntryColumn::Ld, false),
+            "last column stays"
+        );
+        assert_eq!(layout.visible().count(), 1);
+    }
+}
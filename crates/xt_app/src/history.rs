@@ -1,21 +1,23 @@
-use xt_core::model::Entry;
+use xt_core::model::{Entry, SharedText};
 
 pub const DEFAULT_HISTORY_LIMIT: usize = 100;
 
+/// Before/after texts of one row. Texts are shared with the entry list, so a
+/// recorded operation costs a few pointers per row rather than a copy.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SingleEditOp {
     pub index: usize,
-    pub before_source: String,
-    pub before_target: String,
-    pub after_source: String,
-    pub after_target: String,
+    pub before_source: SharedText,
+    pub before_target: SharedText,
+    pub after_source: SharedText,
+    pub after_target: SharedText,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct BatchTargetChange {
     pub index: usize,
-    pub before_target: String,
-    pub after_target: String,
+    pub before_target: SharedText,
+    pub after_target: SharedText,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -24,10 +26,45 @@ enum EntryOp {
     BatchTargetEdit(Vec<BatchTargetChange>),
 }
 
+impl EntryOp {
+    fn rows(&self) -> usize {
+        match self {
+            EntryOp::SingleEdit(_) => 1,
+            EntryOp::BatchTargetEdit(changes) => changes.len(),
+        }
+    }
+
+    fn indices(&self) -> Vec<usize> {
+        match self {
+            EntryOp::SingleEdit(op) => vec![op.index],
+            EntryOp::BatchTargetEdit(changes) => changes.iter().map(|c| c.index).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct RecordedOp {
+    label: String,
+    checkpoint: Option<String>,
+    op: EntryOp,
+}
+
+/// One row of the history panel.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryItem {
+    /// e.g. `XML適用 (1520 rows)`.
+    pub label: String,
+    pub rows: usize,
+    /// User-assigned checkpoint name.
+    pub checkpoint: Option<String>,
+    /// False for operations that were undone and can be redone.
+    pub applied: bool,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct EntryHistory {
-    past: Vec<EntryOp>,
-    future: Vec<EntryOp>,
+    past: Vec<RecordedOp>,
+    future: Vec<RecordedOp>,
     limit: usize,
 }
 
@@ -46,50 +83,131 @@ impl EntryHistory {
     }
 
     pub fn record_single_edit(&mut self, op: SingleEditOp) -> bool {
+        self.record_single_edit_labeled("行編集", op)
+    }
+
+    pub fn record_single_edit_labeled(&mut self, label: &str, op: SingleEditOp) -> bool {
         if op.before_source == op.after_source && op.before_target == op.after_target {
             return false;
         }
-        self.push_op(EntryOp::SingleEdit(op));
+        self.push_op(label, EntryOp::SingleEdit(op));
         true
     }
 
-    pub fn record_batch_target_edit(&mut self, mut changes: Vec<BatchTargetChange>) -> bool {
+    pub fn record_batch_target_edit(&mut self, changes: Vec<BatchTargetChange>) -> bool {
+        self.record_batch_target_edit_labeled("一括編集", changes)
+    }
+
+    pub fn record_batch_target_edit_labeled(
+        &mut self,
+        label: &str,
+        mut changes: Vec<BatchTargetChange>,
+    ) -> bool {
         changes.retain(|c| c.before_target != c.after_target);
         if changes.is_empty() {
             return false;
         }
-        self.push_op(EntryOp::BatchTargetEdit(changes));
+        self.push_op(label, EntryOp::BatchTargetEdit(changes));
         true
     }
 
     pub fn undo(&mut self, entries: &mut [Entry]) -> bool {
-        let Some(op) = self.past.pop() else {
+        let Some(recorded) = self.past.pop() else {
             return false;
         };
-        if !apply_op(entries, &op, false) {
+        if !apply_op(entries, &recorded.op, false) {
             self.past.clear();
             self.future.clear();
             return false;
         }
-        self.future.push(op);
+        self.future.push(recorded);
         true
     }
 
     pub fn redo(&mut self, entries: &mut [Entry]) -> bool {
-        let Some(op) = self.future.pop() else {
+        let Some(recorded) = self.future.pop() else {
             return false;
         };
-        if !apply_op(entries, &op, true) {
+        if !apply_op(entries, &recorded.op, true) {
             self.past.clear();
             self.future.clear();
             return false;
         }
-        self.past.push(op);
+        self.past.push(recorded);
+        true
+    }
+
+    /// Operations oldest first: applied ones, then undone ones in redo order.
+    pub fn items(&self) -> Vec<HistoryItem> {
+        let applied = self.past.iter().map(|recorded| (recorded, true));
+        let undone = self.future.iter().rev().map(|recorded| (recorded, false));
+        applied
+            .chain(undone)
+            .map(|(recorded, applied)| HistoryItem {
+                label: format!("{} ({} rows)", recorded.label, recorded.op.rows()),
+                rows: recorded.op.rows(),
+                checkpoint: recorded.checkpoint.clone(),
+                applied,
+            })
+            .collect()
+    }
+
+    /// Number of applied operations; `items()[..position()]` is the current state.
+    pub fn position(&self) -> usize {
+        self.past.len()
+    }
+
+    /// Undoes or redoes until exactly `position` operations are applied.
+    pub fn jump_to(&mut self, position: usize, entries: &mut [Entry]) -> bool {
+        if position > self.past.len() + self.future.len() {
+            return false;
+        }
+        while self.past.len() > position {
+            if !self.undo(entries) {
+                return false;
+            }
+        }
+        while self.past.len() < position {
+            if !self.redo(entries) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Rows changed by the operations between history positions `a` and `b`
+    /// (either order), e.g. to refresh them after [`EntryHistory::jump_to`].
+    pub fn rows_between(&self, a: usize, b: usize) -> Vec<usize> {
+        let (from, to) = (a.min(b), a.max(b));
+        let mut rows = self
+            .past
+            .iter()
+            .chain(self.future.iter().rev())
+            .skip(from)
+            .take(to - from)
+            .flat_map(|recorded| recorded.op.indices())
+            .collect::<Vec<_>>();
+        rows.sort_unstable();
+        rows.dedup();
+        rows
+    }
+
+    /// Names the most recent applied operation so it can be found in the panel.
+    pub fn name_checkpoint(&mut self, name: &str) -> bool {
+        let name = name.trim();
+        let Some(recorded) = self.past.last_mut() else {
+            return false;
+        };
+        recorded.checkpoint = (!name.is_empty()).then(|| name.to_string());
         true
     }
 
-    fn push_op(&mut self, op: EntryOp) {
-        self.past.push(op);
+    fn push_op(&mut self, label: &str, op: EntryOp) {
+        self.past.push(RecordedOp {
+            label: label.to_string(),
+            checkpoint: None,
+            op,
+        });
         if self.past.len() > self.limit {
             let overflow = self.past.len() - self.limit;
             self.past.drain(..overflow);
@@ -140,8 +258,8 @@ mod tests {
     fn entry(key: &str, src: &str, dst: &str) -> Entry {
         Entry {
             key: key.to_string(),
-            source_text: src.to_string(),
-            target_text: dst.to_string(),
+            source_text: src.into(),
+            target_text: dst.into(),
         }
     }
 
@@ -151,10 +269,10 @@ mod tests {
         let mut entries = vec![entry("k1", "x", "y")];
         hist.record_single_edit(SingleEditOp {
             index: 0,
-            before_source: "a".to_string(),
-            before_target: "b".to_string(),
-            after_source: "x".to_string(),
-            after_target: "y".to_string(),
+            before_source: "a".into(),
+            before_target: "b".into(),
+            after_source: "x".into(),
+            after_target: "y".into(),
         });
         assert!(hist.undo(&mut entries));
         assert_eq!(entries[0].source_text, "a");
@@ -171,13 +289,13 @@ mod tests {
         hist.record_batch_target_edit(vec![
             BatchTargetChange {
                 index: 0,
-                before_target: "0".to_string(),
-                after_target: "1".to_string(),
+                before_target: "0".into(),
+                after_target: "1".into(),
             },
             BatchTargetChange {
                 index: 1,
-                before_target: "0".to_string(),
-                after_target: "2".to_string(),
+                before_target: "0".into(),
+                after_target: "2".into(),
             },
         ]);
         assert!(hist.undo(&mut entries));
@@ -194,8 +312,8 @@ mod tests {
         for i in 0..10usize {
             hist.record_batch_target_edit(vec![BatchTargetChange {
                 index: 0,
-                before_target: format!("{i}"),
-                after_target: format!("{}", i + 1),
+                before_target: format!("{i}").into(),
+                after_target: format!("{}", i + 1).into(),
             }]);
         }
         let mut entries = vec![entry("k1", "a", "10")];
@@ -205,4 +323,68 @@ mod tests {
         }
         assert_eq!(undo_count, 3);
     }
+
+    #[test]
+    fn t_hist_004_items_and_jump_to_checkpoint() {
+        let mut hist = EntryHistory::with_limit(10);
+        let mut entries = vec![entry("k1", "a", "3"), entry("k2", "b", "")];
+        for (label, before, after) in [("XML適用", "", "1"), ("Quick自動翻訳", "1", "2")] {
+            hist.record_batch_target_edit_labeled(
+                label,
+                vec![BatchTargetChange {
+                    index: 0,
+                    before_target: before.into(),
+                    after_target: after.into(),
+                }],
+            );
+        }
+        assert!(hist.name_checkpoint("レビュー前"));
+        hist.record_single_edit(SingleEditOp {
+            index: 0,
+            before_source: "a".into(),
+            before_target: "2".into(),
+            after_source: "a".into(),
+            after_target: "3".into(),
+        });
+
+        assert!(hist.jump_to(0, &mut entries));
+        assert_eq!(entries[0].target_text, "");
+        let items = hist.items();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].label, "XML適用 (1 rows)");
+        assert_eq!(items[1].checkpoint.as_deref(), Some("レビュー前"));
+        assert!(items.iter().all(|item| !item.applied));
+
+        assert!(hist.jump_to(2, &mut entries));
+        assert_eq!(entries[0].target_text, "2");
+        assert_eq!(hist.position(), 2);
+        assert!(hist.items()[1].applied && !hist.items()[2].applied);
+        assert!(!hist.jump_to(4, &mut entries));
+    }
+
+    #[test]
+    fn t_hist_005_undo_redo_share_text_with_entries() {
+        let mut hist = EntryHistory::with_limit(10);
+        let before = entry("k1", "Iron Sword", "");
+        let after: SharedText = "鉄の剣".into();
+        let mut entries = vec![before.clone()];
+        entries[0].target_text = after.clone();
+        hist.record_batch_target_edit(vec![BatchTargetChange {
+            index: 0,
+            before_target: before.target_text.clone(),
+            after_target: after.clone(),
+        }]);
+
+        assert!(hist.undo(&mut entries));
+        assert!(SharedText::ptr_eq(
+            &entries[0].target_text,
+            &before.target_text
+        ));
+        assert!(hist.redo(&mut entries));
+        assert!(SharedText::ptr_eq(&entries[0].target_text, &after));
+        assert!(SharedText::ptr_eq(
+            &entries[0].source_text,
+            &before.source_text
+        ));
+    }
 }
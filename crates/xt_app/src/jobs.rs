@@ -0,0 +1,321 @@
+//! Background jobs: worker threads, cancellation and which jobs may run at
+//! the same time. Jobs that read or replace the entry list run one at a time,
+//! as do jobs that build or use the dictionary; the rest run side by side.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use xt_core::pipeline::{CancelToken, ProgressSink};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobKind {
+    XmlApply,
+    BuildDictionary,
+    QuickAuto,
+    LoadPlugin,
+    Save,
+}
+
+impl JobKind {
+    /// Saves are never interrupted half-way through writing files.
+    pub fn cancelable(self) -> bool {
+        self != JobKind::Save
+    }
+
+    pub fn reports_dict_status(self) -> bool {
+        matches!(self, JobKind::BuildDictionary | JobKind::QuickAuto)
+    }
+
+    /// Reads or replaces the entry list; editing is locked while one runs.
+    pub fn uses_entries(self) -> bool {
+        self != JobKind::BuildDictionary
+    }
+
+    pub fn uses_dictionary(self) -> bool {
+        matches!(self, JobKind::BuildDictionary | JobKind::QuickAuto)
+    }
+
+    pub fn conflicts_with(self, other: JobKind) -> bool {
+        (self.uses_entries() && other.uses_entries())
+            || (self.uses_dictionary() && other.uses_dictionary())
+    }
+}
+
+/// Progress a worker reports through [`ProgressSink`] and the UI reads back.
+#[derive(Debug, Default)]
+pub struct JobProgress {
+    processed: AtomicUsize,
+    total: AtomicUsize,
+    current_file: Mutex<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ProgressSnapshot {
+    pub processed: usize,
+    /// 0 while the number of items is unknown.
+    pub total: usize,
+    pub current_file: String,
+}
+
+impl ProgressSnapshot {
+    /// Completed share in `0.0..=1.0`, or `None` while the total is unknown.
+    pub fn fraction(&self) -> Option<f32> {
+        (self.total > 0).then(|| (self.processed.min(self.total) as f32) / self.total as f32)
+    }
+}
+
+impl JobProgress {
+    pub fn snapshot(&self) -> ProgressSnapshot {
+        ProgressSnapshot {
+            processed: self.processed.load(Ordering::Relaxed),
+            total: self.total.load(Ordering::Relaxed),
+            current_file: self
+                .current_file
+                .lock()
+                .map(|file| file.clone())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl ProgressSink for JobProgress {
+    fn set_total(&self, total: usize) {
+        self.processed.store(0, Ordering::Relaxed);
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    fn set_current_file(&self, file: &str) {
+        if let Ok(mut current) = self.current_file.lock() {
+            file.clone_into(&mut current);
+        }
+    }
+
+    fn advance(&self, items: usize) {
+        self.processed.fetch_add(items, Ordering::Relaxed);
+    }
+}
+
+pub struct RunningJob<R> {
+    pub id: u64,
+    pub kind: JobKind,
+    pub label: String,
+    pub started_at: Instant,
+    pub progress: Arc<JobProgress>,
+    cancel: CancelToken,
+    receiver: Receiver<R>,
+}
+
+pub struct FinishedJob<R> {
+    pub kind: JobKind,
+    pub label: String,
+    pub elapsed: Duration,
+    /// `None` when the worker ended without a result (it panicked).
+    pub result: Option<R>,
+}
+
+/// Running jobs plus requests (`Q`) waiting for a conflicting job to finish,
+/// started in submission order.
+pub struct JobQueue<Q, R> {
+    running: Vec<RunningJob<R>>,
+    queued: VecDeque<(JobKind, Q)>,
+    next_id: u64,
+}
+
+impl<Q, R> Default for JobQueue<Q, R> {
+    fn default() -> Self {
+        Self {
+            running: Vec::new(),
+            queued: VecDeque::new(),
+            next_id: 1,
+        }
+    }
+}
+
+impl<Q, R: Send + 'static> JobQueue<Q, R> {
+    pub fn is_idle(&self) -> bool {
+        self.running.is_empty() && self.queued.is_empty()
+    }
+
+    pub fn running(&self) -> &[RunningJob<R>] {
+        &self.running
+    }
+
+    pub fn queued_len(&self) -> usize {
+        self.queued.len()
+    }
+
+    /// Whether a running job uses the entry list.
+    pub fn locks_entries(&self) -> bool {
+        self.running.iter().any(|job| job.kind.uses_entries())
+    }
+
+    /// A `kind` job may start now: nothing running or queued conflicts with it.
+    pub fn can_start(&self, kind: JobKind) -> bool {
+        !self.running.iter().any(|job| job.kind.conflicts_with(kind))
+            && !self
+                .queued
+                .iter()
+                .any(|(queued, _)| queued.conflicts_with(kind))
+    }
+
+    pub fn enqueue(&mut self, kind: JobKind, request: Q) {
+        self.queued.push_back((kind, request));
+    }
+
+    /// Removes and returns the first queued request that no running or
+    /// earlier queued job conflicts with.
+    pub fn next_startable(&mut self) -> Option<(JobKind, Q)> {
+        let index = (0..self.queued.len()).find(|&index| {
+            let kind = self.queued[index].0;
+            !self.running.iter().any(|job| job.kind.conflicts_with(kind))
+                && !self
+                    .queued
+                    .iter()
+                    .take(index)
+                    .any(|(earlier, _)| earlier.conflicts_with(kind))
+        })?;
+        self.queued.remove(index)
+    }
+
+    /// Runs `work` on a worker thread and returns the job id. `work` gets the
+    /// job's cancellation token and progress sink.
+    pub fn spawn<F>(&mut self, kind: JobKind, label: impl Into<String>, work: F) -> u64
+    where
+        F: FnOnce(CancelToken, Arc<JobProgress>) -> R + Send + 'static,
+    {
+        let label = label.into();
+        let id = self.next_id;
+        self.next_id += 1;
+        let (tx, receiver) = mpsc::channel();
+        let cancel = CancelToken::new();
+        let job_cancel = cancel.clone();
+        let progress = Arc::new(JobProgress::default());
+        let job_progress = Arc::clone(&progress);
+        let job_label = label.clone();
+        tracing::debug!(target: "xt_app::job", "start #{id}: {label}");
+        thread::spawn(move || {
+            let _ = tx.send(work(job_cancel, job_progress));
+            tracing::debug!(target: "xt_app::job", "worker finished #{id}: {job_label}");
+        });
+        self.running.push(RunningJob {
+            id,
+            kind,
+            label,
+            started_at: Instant::now(),
+            progress,
+            cancel,
+            receiver,
+        });
+        id
+    }
+
+    /// Collects jobs whose worker has finished.
+    pub fn poll(&mut self) -> Vec<FinishedJob<R>> {
+        let mut finished = Vec::new();
+        let mut index = 0;
+        while index < self.running.len() {
+            let result = match self.running[index].receiver.try_recv() {
+                Ok(result) => Some(result),
+                Err(TryRecvError::Empty) => {
+                    index += 1;
+                    continue;
+                }
+                Err(TryRecvError::Disconnected) => None,
+            };
+            let job = self.running.remove(index);
+            finished.push(FinishedJob {
+                kind: job.kind,
+                label: job.label,
+                elapsed: job.started_at.elapsed(),
+                result,
+            });
+        }
+        finished
+    }
+
+    /// Cancels the running job `id` and stops waiting for it; its worker
+    /// stops at the next check and its result is dropped.
+    pub fn cancel(&mut self, id: u64) -> Option<RunningJob<R>> {
+        let index = self.running.iter().position(|job| job.id == id)?;
+        let job = self.running.remove(index);
+        job.cancel.cancel();
+        tracing::debug!(target: "xt_app::job", "cancel #{id}: {}", job.label);
+        Some(job)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wait_for<R: Send + 'static>(jobs: &mut JobQueue<&str, R>) -> FinishedJob<R> {
+        for _ in 0..1000 {
+            if let Some(done) = jobs.poll().pop() {
+                return done;
+            }
+            thread::sleep(Duration::from_millis(2));
+        }
+        panic!("job did not finish");
+    }
+
+    #[test]
+    fn t_job_001_conflicting_jobs_wait_independent_jobs_run() {
+        let mut jobs = JobQueue::<&str, u32>::default();
+        let build = jobs.spawn(JobKind::BuildDictionary, "辞書構築", |cancel, _| {
+            while !cancel.is_canceled() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            0
+        });
+        assert!(!jobs.locks_entries());
+        assert!(jobs.can_start(JobKind::XmlApply));
+        assert!(!jobs.can_start(JobKind::QuickAuto));
+        assert!(!jobs.can_start(JobKind::BuildDictionary));
+
+        jobs.spawn(JobKind::XmlApply, "XML適用", |_, _| 7);
+        assert!(jobs.locks_entries());
+        let done = wait_for(&mut jobs);
+        assert_eq!((done.kind, done.result), (JobKind::XmlApply, Some(7)));
+
+        jobs.enqueue(JobKind::QuickAuto, "quick");
+        assert!(
+            !jobs.can_start(JobKind::Save),
+            "queued quick auto uses entries"
+        );
+        jobs.enqueue(JobKind::Save, "save");
+        assert!(jobs.next_startable().is_none());
+
+        assert!(jobs.cancel(build).is_some());
+        assert_eq!(jobs.running().len(), 0);
+        assert_eq!(jobs.next_startable(), Some((JobKind::QuickAuto, "quick")));
+        jobs.spawn(JobKind::QuickAuto, "Quick自動翻訳", |_, _| 1);
+        assert!(jobs.next_startable().is_none(), "save waits for quick auto");
+        wait_for(&mut jobs);
+        assert_eq!(jobs.next_startable(), Some((JobKind::Save, "save")));
+        assert!(jobs.is_idle());
+    }
+
+    #[test]
+    fn t_job_002_progress_sink_snapshot() {
+        let mut jobs = JobQueue::<&str, usize>::default();
+        jobs.spawn(JobKind::LoadPlugin, "Plugin読込", |_, progress| {
+            progress.set_total(4);
+            progress.set_current_file("mod.esp");
+            progress.advance(1);
+            progress.snapshot().processed
+        });
+        let progress = Arc::clone(&jobs.running()[0].progress);
+        let done = wait_for(&mut jobs);
+        assert_eq!(done.result, Some(1));
+        let snapshot = progress.snapshot();
+        assert_eq!(snapshot.current_file, "mod.esp");
+        assert_eq!(snapshot.fraction(), Some(0.25));
+
+        progress.set_total(0);
+        assert_eq!(progress.snapshot().fraction(), None);
+    }
+}
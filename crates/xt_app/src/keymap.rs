@@ -0,0 +1,427 @@
+//! Keyboard shortcuts: which key chord runs which [`Command`].
+
+use eframe::egui::{self, Key, Modifiers};
+
+use crate::actions::AppAction;
+
+/// Actions that can be bound to a key chord.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Command {
+    NextRow,
+    PreviousRow,
+    NextUntranslated,
+    PreviousUntranslated,
+    NextIssue,
+    ApplyEditAndNext,
+    CopySourceToTarget,
+    ToggleValidated,
+    QuickAuto,
+    GoToRecord,
+    NextReview,
+    PreviousReview,
+    ApproveReview,
+    RejectReview,
+}
+
+impl Command {
+    pub const ALL: [Command; 14] = [
+        Command::NextRow,
+        Command::PreviousRow,
+        Command::NextUntranslated,
+        Command::PreviousUntranslated,
+        Command::NextIssue,
+        Command::ApplyEditAndNext,
+        Command::CopySourceToTarget,
+        Command::ToggleValidated,
+        Command::QuickAuto,
+        Command::GoToRecord,
+        Command::NextReview,
+        Command::PreviousReview,
+        Command::ApproveReview,
+        Command::RejectReview,
+    ];
+
+    /// Key used in the prefs file.
+    pub fn id(self) -> &'static str {
+        match self {
+            Command::NextRow => "next_row",
+            Command::PreviousRow => "previous_row",
+            Command::NextUntranslated => "next_untranslated",
+            Command::PreviousUntranslated => "previous_untranslated",
+            Command::NextIssue => "next_issue",
+            Command::ApplyEditAndNext => "apply_edit_and_next",
+            Command::CopySourceToTarget => "copy_source_to_target",
+            Command::ToggleValidated => "toggle_validated",
+            Command::QuickAuto => "quick_auto",
+            Command::GoToRecord => "go_to_record",
+            Command::NextReview => "next_review",
+            Command::PreviousReview => "previous_review",
+            Command::ApproveReview => "approve_review",
+            Command::RejectReview => "reject_review",
+        }
+    }
+
+    pub fn parse(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|command| command.id() == id)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Command::NextRow => "次の行",
+            Command::PreviousRow => "前の行",
+            Command::NextUntranslated => "次の未訳",
+            Command::PreviousUntranslated => "前の未訳",
+            Command::NextIssue => "次の検証結果",
+            Command::ApplyEditAndNext => "反映して次へ",
+            Command::CopySourceToTarget => "原文を訳文へコピー",
+            Command::ToggleValidated => "検証済み切替",
+            Command::QuickAuto => "Quick自動翻訳",
+            Command::GoToRecord => "フォームID/EDIDへ移動",
+            Command::NextReview => "次の要確認",
+            Command::PreviousReview => "前の要確認",
+            Command::ApproveReview => "要確認を承認",
+            Command::RejectReview => "要確認を差し戻し",
+        }
+    }
+
+    /// The action run by this command; the desktop app runs
+    /// [`Command::QuickAuto`] as a background job.
+    /// [`Command::GoToRecord`] maps to a jump without a query; the desktop
+    /// app asks for one first.
+    pub fn action(self) -> AppAction {
+        match self {
+            Command::NextRow => AppAction::SelectNextRow,
+            Command::PreviousRow => AppAction::SelectPreviousRow,
+            Command::NextUntranslated => AppAction::SelectNextUntranslated,
+            Command::PreviousUntranslated => AppAction::SelectPreviousUntranslated,
+            Command::NextIssue => AppAction::SelectNextIssue,
+            Command::ApplyEditAndNext => AppAction::ApplyEditAndNext,
+            Command::CopySourceToTarget => AppAction::CopySourceToTarget,
+            Command::ToggleValidated => AppAction::ToggleValidated,
+            Command::QuickAuto => AppAction::QuickAuto,
+            Command::GoToRecord => AppAction::GoToRecord(String::new()),
+            Command::NextReview => AppAction::SelectNextReview,
+            Command::PreviousReview => AppAction::SelectPreviousReview,
+            Command::ApproveReview => AppAction::ApproveReview,
+            Command::RejectReview => AppAction::RejectReview,
+        }
+    }
+
+    /// Leaves the cursor in the target editor so the row can be translated
+    /// right away. Row-by-row moves keep focus in the grid.
+    pub fn focuses_editor(self) -> bool {
+        matches!(
+            self,
+            Command::NextUntranslated
+                | Command::PreviousUntranslated
+                | Command::NextIssue
+                | Command::ApplyEditAndNext
+                | Command::CopySourceToTarget
+                | Command::NextReview
+                | Command::PreviousReview
+                | Command::ApproveReview
+                | Command::RejectReview
+        )
+    }
+
+    /// Moves the selection; the grid scrolls to the new row.
+    pub fn moves_selection(self) -> bool {
+        matches!(
+            self,
+            Command::NextRow
+                | Command::PreviousRow
+                | Command::NextUntranslated
+                | Command::PreviousUntranslated
+                | Command::NextIssue
+                | Command::ApplyEditAndNext
+                | Command::NextReview
+                | Command::PreviousReview
+                | Command::ApproveReview
+                | Command::RejectReview
+        )
+    }
+}
+
+/// A key with exact modifiers. `ctrl` is Ctrl, or Cmd on macOS.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub key: Key,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl KeyChord {
+    pub const fn plain(key: Key) -> Self {
+        Self {
+            key,
+            ctrl: false,
+            shift: false,
+            alt: false,
+        }
+    }
+
+    pub const fn ctrl(key: Key) -> Self {
+        Self {
+            ctrl: true,
+            ..Self::plain(key)
+        }
+    }
+
+    pub const fn with_shift(self) -> Self {
+        Self {
+            shift: true,
+            ..self
+        }
+    }
+
+    /// The chord of a key press.
+    pub fn pressed(key: Key, modifiers: Modifiers) -> Self {
+        Self {
+            key,
+            ctrl: modifiers.command,
+            shift: modifiers.shift,
+            alt: modifiers.alt,
+        }
+    }
+
+    pub fn matches(&self, key: Key, modifiers: Modifiers) -> bool {
+        self.key == key
+            && self.ctrl == modifiers.command
+            && self.shift == modifiers.shift
+            && self.alt == modifiers.alt
+    }
+
+    /// Plain keys would type into a focused text field, so they only fire
+    /// while no text field has focus.
+    pub fn works_while_typing(&self) -> bool {
+        let function_key = self
+            .key
+            .name()
+            .strip_prefix('F')
+            .is_some_and(|n| n.parse::<u8>().is_ok());
+        self.ctrl || self.alt || function_key
+    }
+
+    /// e.g. `Ctrl+Shift+Enter`.
+    pub fn label(&self) -> String {
+        let mut out = String::new();
+        if self.ctrl {
+            out.push_str("Ctrl+");
+        }
+        if self.shift {
+            out.push_str("Shift+");
+        }
+        if self.alt {
+            out.push_str("Alt+");
+        }
+        out.push_str(self.key.name());
+        out
+    }
+
+    /// Reads [`KeyChord::label`] back.
+    pub fn parse(label: &str) -> Option<Self> {
+        let mut chord = Self::plain(Key::A);
+        let mut rest = label;
+        loop {
+            if let Some(next) = rest.strip_prefix("Ctrl+") {
+                chord.ctrl = true;
+                rest = next;
+            } else if let Some(next) = rest.strip_prefix("Shift+") {
+                chord.shift = true;
+                rest = next;
+            } else if let Some(next) = rest.strip_prefix("Alt+") {
+                chord.alt = true;
+                rest = next;
+            } else {
+                break;
+            }
+        }
+        chord.key = Key::from_name(rest)?;
+        Some(chord)
+    }
+}
+
+/// Chords bound to commands; a command has at most one chord and a chord
+/// runs at most one command.
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(Command, KeyChord)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            bindings: vec![
+                (Command::NextRow, KeyChord::plain(Key::ArrowDown)),
+                (Command::PreviousRow, KeyChord::plain(Key::ArrowUp)),
+                (Command::NextUntranslated, KeyChord::plain(Key::F3)),
+                (
+                    Command::PreviousUntranslated,
+                    KeyChord::plain(Key::F3).with_shift(),
+                ),
+                (Command::NextIssue, KeyChord::plain(Key::F4)),
+                (Command::ApplyEditAndNext, KeyChord::ctrl(Key::Enter)),
+                (Command::CopySourceToTarget, KeyChord::ctrl(Key::D)),
+                (Command::ToggleValidated, KeyChord::ctrl(Key::M)),
+                (Command::QuickAuto, KeyChord::ctrl(Key::R)),
+                (Command::GoToRecord, KeyChord::ctrl(Key::G)),
+                (Command::NextReview, KeyChord::plain(Key::F6)),
+                (
+                    Command::PreviousReview,
+                    KeyChord::plain(Key::F6).with_shift(),
+                ),
+                (
+                    Command::ApproveReview,
+                    KeyChord::ctrl(Key::Enter).with_shift(),
+                ),
+                (
+                    Command::RejectReview,
+                    KeyChord::ctrl(Key::Backspace).with_shift(),
+                ),
+            ],
+        }
+    }
+}
+
+/// Keymaps are equal when they bind the same chords, whatever order the
+/// bindings were made in.
+impl PartialEq for Keymap {
+    fn eq(&self, other: &Self) -> bool {
+        self.bindings().eq(other.bindings())
+    }
+}
+
+impl Eq for Keymap {}
+
+impl Keymap {
+    /// Commands with their chords, in [`Command::ALL`] order.
+    pub fn bindings(&self) -> impl Iterator<Item = (Command, Option<KeyChord>)> + '_ {
+        Command::ALL
+            .into_iter()
+            .map(|command| (command, self.chord(command)))
+    }
+
+    /// Binds `chord` to `command`, or unbinds it for `None`. Returns the
+    /// command that lost `chord` to this one.
+    pub fn bind(&mut self, command: Command, chord: Option<KeyChord>) -> Option<Command> {
+        self.bindings.retain(|(bound, _)| *bound != command);
+        let chord = chord?;
+        let displaced = self
+            .bindings
+            .iter()
+            .position(|(_, bound)| *bound == chord)
+            .map(|at| self.bindings.remove(at).0);
+        self.bindings.push((command, chord));
+        displaced
+    }
+
+    pub fn chord(&self, command: Command) -> Option<KeyChord> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == command)
+            .map(|(_, chord)| *chord)
+    }
+
+    pub fn command(&self, key: Key, modifiers: Modifiers) -> Option<Command> {
+        self.bindings
+            .iter()
+            .find(|(_, chord)| chord.matches(key, modifiers))
+            .map(|(command, _)| *command)
+    }
+
+    /// Removes bound key presses from `input` and returns their commands in
+    /// order, so text fields drawn later do not also receive them.
+    /// `typing` is whether a text field has keyboard focus.
+    pub fn take_commands(&self, input: &mut egui::InputState, typing: bool) -> Vec<Command> {
+        let mut commands = Vec::new();
+        input.events.retain(|event| {
+            let egui::Event::Key {
+                key,
+                pressed: true,
+                modifiers,
+                ..
+            } = event
+            else {
+                return true;
+            };
+            let Some(command) = self.command(*key, *modifiers) else {
+                return true;
+            };
+            let chord = self.chord(command).expect("bound command");
+            if typing && !chord.works_while_typing() {
+                return true;
+            }
+            commands.push(command);
+            false
+        });
+        commands
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_keys_001_default_chords_match_exact_modifiers() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.command(Key::F3, Modifiers::NONE),
+            Some(Command::NextUntranslated)
+        );
+        assert_eq!(
+            keymap.command(Key::F3, Modifiers::SHIFT),
+            Some(Command::PreviousUntranslated)
+        );
+        assert_eq!(
+            keymap.command(Key::Enter, Modifiers::COMMAND),
+            Some(Command::ApplyEditAndNext)
+        );
+        assert_eq!(keymap.command(Key::Enter, Modifiers::NONE), None);
+        assert_eq!(
+            keymap.command(Key::R, Modifiers::COMMAND | Modifiers::SHIFT),
+            None
+        );
+        for command in Command::ALL {
+            assert!(keymap.chord(command).is_some(), "{command:?} is bound");
+        }
+
+        let arrow = keymap.chord(Command::NextRow).unwrap();
+        assert!(!arrow.works_while_typing());
+        assert!(keymap
+            .chord(Command::ApplyEditAndNext)
+            .unwrap()
+            .works_while_typing());
+        assert_eq!(
+            keymap.chord(Command::PreviousUntranslated).unwrap().label(),
+            "Shift+F3"
+        );
+    }
+
+    #[test]
+    fn t_keys_002_rebinding_moves_chord_between_commands() {
+        let mut keymap = Keymap::default();
+        let ctrl_d = KeyChord::ctrl(Key::D);
+        assert_eq!(
+            keymap.bind(Command::ToggleValidated, Some(ctrl_d)),
+            Some(Command::CopySourceToTarget)
+        );
+        assert_eq!(
+            keymap.command(Key::D, Modifiers::COMMAND),
+            Some(Command::ToggleValidated)
+        );
+        assert_eq!(keymap.chord(Command::CopySourceToTarget), None);
+        assert_eq!(keymap.command(Key::M, Modifiers::COMMAND), None);
+        assert_eq!(keymap.bind(Command::QuickAuto, None), None);
+        assert_eq!(keymap.command(Key::R, Modifiers::COMMAND), None);
+
+        for label in ["Ctrl+Shift+Enter", "Alt+F3", "Ctrl+Plus", "Equals", "Down"] {
+            let chord = KeyChord::parse(label).expect(label);
+            assert_eq!(chord.label(), label);
+        }
+        assert_eq!(KeyChord::parse("Ctrl+="), KeyChord::parse("Ctrl+Equals"));
+        assert_eq!(KeyChord::parse("Ctrl+"), None);
+        assert_eq!(KeyChord::parse("Hyper+X"), None);
+    }
+}
@@ -1,9 +1,35 @@
+#[cfg(not(target_arch = "wasm32"))]
 pub mod actions;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod app;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod background;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod columns;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod driver;
 pub mod history;
+#[cfg(not(target_arch = "wasm32"))]
 mod hotpatch;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod jobs;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod keymap;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod log;
+pub mod picked;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod prefs;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod search;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod state;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod stats;
+#[cfg(target_arch = "wasm32")]
+pub mod web;
 
+#[cfg(not(target_arch = "wasm32"))]
 pub use app::launch;
+#[cfg(target_arch = "wasm32")]
+pub use web::start;
@@ -0,0 +1,291 @@
+//! In-app log: a `tracing` subscriber that keeps the latest events from the
+//! app and its background jobs in a ring buffer for the ログ tab.
+
+use std::collections::VecDeque;
+use std::fmt::{self, Write as _};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tracing::field::{Field, Visit};
+use tracing::{span, Event, Level, Metadata, Subscriber};
+
+/// Events kept before the oldest are dropped.
+pub const LOG_CAPACITY: usize = 2000;
+/// Only events whose target starts with this are collected (not dependencies).
+const LOG_TARGET_PREFIX: &str = "xt_";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 4] = [
+        LogLevel::Error,
+        LogLevel::Warn,
+        LogLevel::Info,
+        LogLevel::Debug,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Error => "ERROR",
+            LogLevel::Warn => "WARN",
+            LogLevel::Info => "INFO",
+            LogLevel::Debug => "DEBUG",
+        }
+    }
+
+    /// Label of the ログ tab filter (this level and more severe ones).
+    pub fn label(self) -> &'static str {
+        match self {
+            LogLevel::Error => "エラーのみ",
+            LogLevel::Warn => "警告以上",
+            LogLevel::Info => "情報以上",
+            LogLevel::Debug => "すべて",
+        }
+    }
+
+    fn from_tracing(level: &Level) -> Self {
+        match *level {
+            Level::ERROR => LogLevel::Error,
+            Level::WARN => LogLevel::Warn,
+            Level::INFO => LogLevel::Info,
+            _ => LogLevel::Debug,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub unix_ms: u64,
+    pub level: LogLevel,
+    pub target: String,
+    pub message: String,
+}
+
+impl LogEntry {
+    /// `HH:MM:SS.mmm` (UTC).
+    pub fn time_label(&self) -> String {
+        let secs = self.unix_ms / 1000;
+        format!(
+            "{:02}:{:02}:{:02}.{:03}",
+            secs / 3600 % 24,
+            secs / 60 % 60,
+            secs % 60,
+            self.unix_ms % 1000
+        )
+    }
+}
+
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:<5} {}: {}",
+            self.time_label(),
+            self.level.as_str(),
+            self.target,
+            self.message
+        )
+    }
+}
+
+/// Ring buffer of the latest [`LOG_CAPACITY`] entries.
+#[derive(Debug, Clone)]
+pub struct LogBuffer {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+}
+
+impl Default for LogBuffer {
+    fn default() -> Self {
+        Self::with_capacity(LOG_CAPACITY)
+    }
+}
+
+impl LogBuffer {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity.min(LOG_CAPACITY)),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn push(&mut self, entry: LogEntry) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Entries at `max_level` or more severe, oldest first.
+    pub fn filtered(&self, max_level: LogLevel) -> impl Iterator<Item = &LogEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.level <= max_level)
+    }
+
+    /// One line per entry (as shown in the ログ tab), for copying and export.
+    pub fn to_text(&self, max_level: LogLevel) -> String {
+        let mut out = String::new();
+        for entry in self.filtered(max_level) {
+            let _ = writeln!(out, "{entry}");
+        }
+        out
+    }
+}
+
+pub type SharedLog = Arc<Mutex<LogBuffer>>;
+
+pub fn lock(log: &SharedLog) -> MutexGuard<'_, LogBuffer> {
+    log.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// `tracing` subscriber that appends `xt_*` events to a [`SharedLog`].
+/// Spans are not tracked.
+pub struct LogCollector {
+    log: SharedLog,
+}
+
+impl LogCollector {
+    pub fn new(log: SharedLog) -> Self {
+        Self { log }
+    }
+}
+
+impl Subscriber for LogCollector {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target().starts_with(LOG_TARGET_PREFIX) && *metadata.level() <= Level::DEBUG
+    }
+
+    fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        lock(&self.log).push(LogEntry {
+            unix_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_millis() as u64)
+                .unwrap_or(0),
+            level: LogLevel::from_tracing(metadata.level()),
+            target: metadata.target().to_string(),
+            message: visitor.message,
+        });
+    }
+
+    fn enter(&self, _span: &span::Id) {}
+
+    fn exit(&self, _span: &span::Id) {}
+}
+
+/// Collects the `message` field, then other fields as ` key=value`.
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.insert_str(0, value);
+        } else {
+            let _ = write!(self.message, " {}={value}", field.name());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message.insert_str(0, &format!("{value:?}"));
+        } else {
+            let _ = write!(self.message, " {}={value:?}", field.name());
+        }
+    }
+}
+
+/// Process-wide log, installed as the global `tracing` subscriber on first use.
+pub fn global() -> SharedLog {
+    static LOG: OnceLock<SharedLog> = OnceLock::new();
+    LOG.get_or_init(|| {
+        let log = SharedLog::default();
+        let _ = tracing::subscriber::set_global_default(LogCollector::new(log.clone()));
+        log
+    })
+    .clone()
+}
+
+/// Records `message` at `level` under the `xt_app` target.
+pub fn emit(level: LogLevel, message: &str) {
+    match level {
+        LogLevel::Error => tracing::error!(target: "xt_app", "{message}"),
+        LogLevel::Warn => tracing::warn!(target: "xt_app", "{message}"),
+        LogLevel::Info => tracing::info!(target: "xt_app", "{message}"),
+        LogLevel::Debug => tracing::debug!(target: "xt_app", "{message}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_log_001_collector_ring_buffer_and_filter() {
+        let log = SharedLog::new(Mutex::new(LogBuffer::with_capacity(3)));
+        tracing::subscriber::with_default(LogCollector::new(log.clone()), || {
+            emit(LogLevel::Info, "loaded");
+            tracing::warn!(target: "xt_app::job", path = "a.esp", "fallback");
+            tracing::info!(target: "eframe", "ignored dependency event");
+            emit(LogLevel::Error, "save failed");
+            emit(LogLevel::Debug, "job start");
+        });
+
+        let buffer = lock(&log);
+        assert_eq!(buffer.len(), 3);
+        let messages = buffer
+            .filtered(LogLevel::Debug)
+            .map(|entry| entry.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec!["fallback path=a.esp", "save failed", "job start"]
+        );
+        let warnings = buffer.to_text(LogLevel::Warn);
+        assert_eq!(warnings.lines().count(), 2);
+        assert!(warnings
+            .lines()
+            .next()
+            .is_some_and(|line| line.ends_with("WARN  xt_app::job: fallback path=a.esp")));
+
+        let entry = LogEntry {
+            unix_ms: 3_723_004,
+            level: LogLevel::Info,
+            target: "xt_app".to_string(),
+            message: "m".to_string(),
+        };
+        assert_eq!(entry.to_string(), "01:02:03.004 INFO  xt_app: m");
+    }
+}
@@ -1,3 +1,9 @@
+#[cfg(not(target_arch = "wasm32"))]
 fn main() -> eframe::Result<()> {
     xt_app::launch()
 }
+
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    xt_app::start();
+}
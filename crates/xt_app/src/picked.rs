@@ -0,0 +1,251 @@
+//! A plugin opened from picked files instead of a `Data` folder, as in the
+//! browser build: the plugin and its Strings files come in as bytes, and the
+//! translated copies go back out the same way.
+
+use xt_core::formats::esp::{
+    apply_translations_to_bytes, extract_strings_from_bytes, parse_plugin_info, plugin_entries,
+    translated_strings, CancelToken, ExtractedString, NoProgress, PluginStrings, StringsKind,
+};
+use xt_core::formats::strings::{
+    read_dlstrings, read_ilstrings, read_strings, write_dlstrings, write_ilstrings, write_strings,
+};
+use xt_core::game::GameProfile;
+use xt_core::import_export::{
+    apply_xml_with_profile, export_entries, import_entries, ApplyProfile, XmlApplyStats,
+};
+use xt_core::model::Entry;
+
+/// A file's name (without folders) and contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PickedFile {
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// What a picked file is, by extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PickedKind {
+    Plugin,
+    Strings(StringsKind),
+}
+
+impl PickedKind {
+    fn of(name: &str) -> Option<Self> {
+        let (_, ext) = name.rsplit_once('.')?;
+        match ext.to_ascii_lowercase().as_str() {
+            "esp" | "esm" | "esl" => Some(Self::Plugin),
+            "strings" => Some(Self::Strings(StringsKind::Strings)),
+            "dlstrings" => Some(Self::Strings(StringsKind::DlStrings)),
+            "ilstrings" => Some(Self::Strings(StringsKind::IlStrings)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PickedPlugin {
+    pub name: String,
+    pub profile: GameProfile,
+    pub entries: Vec<Entry>,
+    bytes: Vec<u8>,
+    strings: PluginStrings,
+    /// Names of the picked Strings files; saving writes them back under the
+    /// same names.
+    strings_names: Vec<(StringsKind, String)>,
+    extracted: Vec<ExtractedString>,
+}
+
+impl PickedPlugin {
+    /// One plugin and, when it is localized, its Strings files of one
+    /// language, picked together.
+    pub fn open(files: Vec<PickedFile>) -> Result<Self, String> {
+        let mut plugin = None;
+        let mut strings = PluginStrings::default();
+        let mut strings_names = Vec::new();
+        for file in files {
+            match PickedKind::of(&file.name) {
+                Some(PickedKind::Plugin) => {
+                    if let Some(PickedFile { name, .. }) = plugin.replace(file) {
+                        return Err(format!("more than one plugin picked ({name})"));
+                    }
+                }
+                Some(PickedKind::Strings(kind)) => {
+                    if strings.get(kind).is_some() {
+                        return Err(format!("more than one .{kind} file picked ({})", file.name));
+                    }
+                    let parsed = match kind {
+                        StringsKind::Strings => read_strings(&file.bytes),
+                        StringsKind::DlStrings => read_dlstrings(&file.bytes),
+                        StringsKind::IlStrings => read_ilstrings(&file.bytes),
+                    }
+                    .map_err(|err| format!("{}: {err}", file.name))?;
+                    match kind {
+                        StringsKind::Strings => strings.strings = Some(parsed),
+                        StringsKind::DlStrings => strings.dlstrings = Some(parsed),
+                        StringsKind::IlStrings => strings.ilstrings = Some(parsed),
+                    }
+                    strings_names.push((kind, file.name));
+                }
+                None => {
+                    return Err(format!(
+                        "{}: not a plugin (.esp, .esm, .esl) or Strings file",
+                        file.name
+                    ))
+                }
+            }
+        }
+        let PickedFile { name, bytes } = plugin.ok_or("no plugin picked")?;
+        let info = parse_plugin_info(&bytes).map_err(|err| format!("{name}: {err}"))?;
+        let profile = GameProfile::detect(&info);
+        let extracted =
+            extract_strings_from_bytes(&bytes, &strings, profile, &CancelToken::new(), &NoProgress)
+                .map_err(|err| format!("{name}: {err}"))?;
+        Ok(Self {
+            name,
+            profile,
+            entries: plugin_entries(&extracted),
+            bytes,
+            strings,
+            strings_names,
+            extracted,
+        })
+    }
+
+    /// Applies a translation XML to the entries.
+    pub fn import_xml(
+        &mut self,
+        xml: &str,
+        profile: ApplyProfile,
+    ) -> Result<XmlApplyStats, String> {
+        let imported = import_entries(xml).map_err(|err| err.to_string())?;
+        let (entries, stats) = apply_xml_with_profile(&self.entries, &imported, profile);
+        self.entries = entries;
+        Ok(stats)
+    }
+
+    /// The entries as a translation XML named after the plugin.
+    pub fn export_xml(&self) -> PickedFile {
+        let stem = self
+            .name
+            .rsplit_once('.')
+            .map_or(self.name.as_str(), |(stem, _)| stem);
+        PickedFile {
+            name: format!("{stem}.xml"),
+            bytes: export_entries(&self.entries).into_bytes(),
+        }
+    }
+
+    /// The translated plugin, then its Strings files, under the names they
+    /// were picked with.
+    pub fn save(&self) -> Result<Vec<PickedFile>, String> {
+        let mut strings = self.strings.clone();
+        let plugin = apply_translations_to_bytes(
+            &self.bytes,
+            &mut strings,
+            translated_strings(&self.entries, &self.extracted),
+            self.profile,
+        )
+        .map_err(|err| format!("{}: {err}", self.name))?;
+        let mut files = vec![PickedFile {
+            name: self.name.clone(),
+            bytes: plugin,
+        }];
+        for (kind, name) in &self.strings_names {
+            let Some(file) = strings.get(*kind) else {
+                continue;
+            };
+            let bytes = match kind {
+                StringsKind::Strings => write_strings(file),
+                StringsKind::DlStrings => write_dlstrings(file),
+                StringsKind::IlStrings => write_ilstrings(file),
+            }
+            .map_err(|err| format!("{name}: {err}"))?;
+            files.push(PickedFile {
+                name: name.clone(),
+                bytes,
+            });
+        }
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xt_core::formats::strings::{StringsEntry, StringsFile};
+
+    /// A record with a single subrecord.
+    fn record(tag: &[u8; 4], form_id: u32, flags: u32, sub: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut body = sub.to_vec();
+        body.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        body.extend_from_slice(data);
+        let mut out = tag.to_vec();
+        out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        out.extend_from_slice(&flags.to_le_bytes());
+        out.extend_from_slice(&form_id.to_le_bytes());
+        out.extend_from_slice(&[0u8; 8]);
+        out.extend(body);
+        out
+    }
+
+    fn file(name: &str, bytes: Vec<u8>) -> PickedFile {
+        PickedFile {
+            name: name.to_string(),
+            bytes,
+        }
+    }
+
+    #[test]
+    fn t_picked_001_localized_plugin_round_trips_in_memory() {
+        let mut hedr = 1.71f32.to_le_bytes().to_vec();
+        hedr.extend_from_slice(&1u32.to_le_bytes());
+        hedr.extend_from_slice(&0x800u32.to_le_bytes());
+        let mut plugin = record(b"TES4", 0, 0x80, b"HEDR", &hedr);
+        plugin.extend(record(
+            b"NPC_",
+            0x0000_0E01,
+            0,
+            b"FULL",
+            &100u32.to_le_bytes(),
+        ));
+        let strings = write_strings(&StringsFile::new(vec![StringsEntry {
+            id: 100,
+            text: "Guard".to_string(),
+        }]))
+        .expect("write strings");
+
+        let err = PickedPlugin::open(vec![file("Mod.esp", plugin.clone())]).expect_err("strings");
+        assert!(err.starts_with("Mod.esp: "), "{err}");
+        let err = PickedPlugin::open(vec![file("notes.txt", Vec::new())]).expect_err("kind");
+        assert!(err.starts_with("notes.txt: not a plugin"), "{err}");
+
+        let mut picked = PickedPlugin::open(vec![
+            file("Mod_english.STRINGS", strings),
+            file("Mod.esp", plugin.clone()),
+        ])
+        .expect("open");
+        assert_eq!(picked.entries.len(), 1);
+        assert_eq!(picked.entries[0].source_text, "Guard");
+
+        let xml = export_entries(&[Entry {
+            key: picked.entries[0].key.clone(),
+            source_text: "Guard".into(),
+            target_text: "衛兵".into(),
+        }]);
+        let stats = picked
+            .import_xml(&xml, ApplyProfile::default())
+            .expect("import");
+        assert_eq!(stats.updated, 1);
+        assert_eq!(picked.export_xml().name, "Mod.xml");
+
+        let saved = picked.save().expect("save");
+        let names = saved
+            .iter()
+            .map(|file| file.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Mod.esp", "Mod_english.STRINGS"]);
+        assert_eq!(saved[0].bytes, plugin, "localized texts live in Strings");
+        let reopened = PickedPlugin::open(saved).expect("reopen");
+        assert_eq!(reopened.entries[0].source_text, "衛兵");
+    }
+}
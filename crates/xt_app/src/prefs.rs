@@ -1,15 +1,48 @@
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::columns::{ColumnLayout, EntryColumn, EntrySort};
+use crate::keymap::{Command, KeyChord, Keymap};
+use crate::state::Tab;
+
+use xt_core::encoding::Encoding;
+use xt_core::game::GameProfile;
+use xt_core::pipeline::{write_atomic, BackupPolicy, OutputTemplate};
+use xt_core::rewrite::RewriteRule;
+use xt_core::search::MatchOptions;
+use xt_core::validation::{CustomRegexRule, RuleSetting, SaveGuard, Severity, ValidationConfig};
 
 pub const DEFAULT_DICT_SOURCE_LANG: &str = "english";
 pub const DEFAULT_DICT_TARGET_LANG: &str = "japanese";
 pub const DEFAULT_DICT_ROOT: &str = "./Data/Strings/Translations";
 const DICT_PREFS_FILE: &str = "dict_prefs.v1";
+pub const USER_DICT_FILE: &str = "user_dictionary.tsv";
+const VALIDATION_PREFS_FILE: &str = "validation_prefs.v1";
+const USER_PREFS_FILE: &str = "user_prefs.v1";
+const SESSION_PREFS_FILE: &str = "session_prefs.v1";
+/// Files whose working state is kept in the session prefs.
+pub const SESSION_FILE_LIMIT: usize = 20;
+const SPELL_IGNORE_DIR: &str = "spell_ignore";
+const EXTRACT_CACHE_DIR: &str = "extract_cache";
+/// Ignore-list name used while no plugin or strings file is loaded.
+pub const GLOBAL_SPELL_PROJECT: &str = "_global";
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct DictionaryPrefs {
     pub source_lang: String,
     pub target_lang: String,
     pub root: String,
+    /// Source matching for Quick自動翻訳.
+    pub match_options: MatchOptions,
+    /// File the 辞書 tab loads and saves; empty uses [`USER_DICT_FILE`] in
+    /// the config directory.
+    pub user_path: String,
+    /// Keep vanilla rows (sources the reference translation has) out of
+    /// exports and off XML and dictionary applies.
+    pub protect_vanilla: bool,
+    /// Fill the untranslated rows of each loaded plugin or Strings file from
+    /// exact dictionary matches (読み込み時に辞書を自動適用).
+    pub apply_on_load: bool,
 }
 
 impl Default for DictionaryPrefs {
@@ -18,35 +51,236 @@ impl Default for DictionaryPrefs {
             source_lang: DEFAULT_DICT_SOURCE_LANG.to_string(),
             target_lang: DEFAULT_DICT_TARGET_LANG.to_string(),
             root: DEFAULT_DICT_ROOT.to_string(),
+            match_options: MatchOptions::EXACT,
+            user_path: String::new(),
+            protect_vanilla: false,
+            apply_on_load: false,
         }
     }
 }
 
-pub fn dictionary_prefs_path() -> Option<PathBuf> {
+/// Translator identity recorded with each edit, the selected game, how the
+/// list filter compares text, the entry list columns, keyboard shortcuts,
+/// save behavior, Mod Organizer 2 integration, how 重複原文 edits spread, the
+/// 置換ルール and whether plugin extraction results are cached.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserPrefs {
+    pub author: String,
+    pub game: GameProfile,
+    pub search: MatchOptions,
+    pub columns: ColumnLayout,
+    pub keymap: Keymap,
+    pub save: SavePrefs,
+    pub mo2: Mo2Prefs,
+    /// A 重複原文 group's target also replaces differing targets of its
+    /// translated rows, not just the untranslated ones.
+    pub overwrite_duplicates: bool,
+    pub rewrite: RewritePrefs,
+    /// Keep extracted plugin strings in [`extract_cache_dir`] and reuse them
+    /// while the plugin and its Strings files are unchanged.
+    pub extract_cache: bool,
+}
+
+/// Regex search/replace rules (置換ルール) run over targets, in order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RewritePrefs {
+    pub rules: Vec<RewriteRule>,
+    /// Also runs them on targets Quick自動翻訳 fills.
+    pub after_auto: bool,
+}
+
+/// Mod Organizer 2 integration: plugins loaded from an instance's `mods`
+/// folder read Strings files through the profile's enabled mods.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Mo2Prefs {
+    pub enabled: bool,
+    /// Empty uses the profile selected in `ModOrganizer.ini`.
+    pub profile: String,
+}
+
+/// How, when and where the loaded file is written.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SavePrefs {
+    /// Encoding for the 文字コード check; `None` uses the game's default.
+    pub encoding: Option<Encoding>,
+    pub backup: BackupPolicy,
+    /// Minutes of unsaved edits before an automatic 上書き保存; 0 is off.
+    pub autosave_minutes: u32,
+    /// Output file name of 別名保存.
+    pub output_template: OutputTemplate,
+    /// Writes a checksum manifest next to each saved file.
+    pub write_manifest: bool,
+}
+
+impl Default for UserPrefs {
+    fn default() -> Self {
+        Self {
+            author: String::new(),
+            game: GameProfile::default(),
+            search: MatchOptions::LOOSE,
+            columns: ColumnLayout::default(),
+            keymap: Keymap::default(),
+            save: SavePrefs::default(),
+            mo2: Mo2Prefs::default(),
+            overwrite_duplicates: true,
+            rewrite: RewritePrefs::default(),
+            extract_cache: false,
+        }
+    }
+}
+
+/// Outer window position and inner size, in points.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindowGeometry {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub maximized: bool,
+}
+
+/// Where the translator left off in one plugin or Strings file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileSession {
+    pub query: String,
+    pub untranslated_only: bool,
+    pub sort: Option<EntrySort>,
+    pub selected_key: Option<String>,
+}
+
+/// Working context restored on the next start: window, tab and, per file,
+/// the filter, sort and selected row.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionPrefs {
+    pub window: Option<WindowGeometry>,
+    pub active_tab: Tab,
+    /// Most recently used first, at most [`SESSION_FILE_LIMIT`].
+    pub files: Vec<(PathBuf, FileSession)>,
+}
+
+impl Default for SessionPrefs {
+    fn default() -> Self {
+        Self {
+            window: None,
+            active_tab: Tab::Home,
+            files: Vec::new(),
+        }
+    }
+}
+
+impl SessionPrefs {
+    pub fn file(&self, path: &Path) -> Option<&FileSession> {
+        self.files
+            .iter()
+            .find(|(file, _)| file == path)
+            .map(|(_, session)| session)
+    }
+
+    /// Stores `session` for `path` as the most recent file.
+    pub fn remember_file(&mut self, path: &Path, session: FileSession) {
+        if self
+            .files
+            .first()
+            .is_some_and(|(file, current)| file == path && *current == session)
+        {
+            return;
+        }
+        self.files.retain(|(file, _)| file != path);
+        self.files.insert(0, (path.to_path_buf(), session));
+        self.files.truncate(SESSION_FILE_LIMIT);
+    }
+}
+
+pub fn config_file_path(file_name: &str) -> Option<PathBuf> {
     if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
-        return Some(PathBuf::from(dir).join("xtrans-rs").join(DICT_PREFS_FILE));
+        return Some(PathBuf::from(dir).join("xtrans-rs").join(file_name));
     }
     if let Ok(home) = std::env::var("HOME") {
         return Some(
             PathBuf::from(home)
                 .join(".config")
                 .join("xtrans-rs")
-                .join(DICT_PREFS_FILE),
+                .join(file_name),
         );
     }
     #[cfg(target_os = "windows")]
     {
         if let Ok(appdata) = std::env::var("APPDATA") {
-            return Some(
-                PathBuf::from(appdata)
-                    .join("xtrans-rs")
-                    .join(DICT_PREFS_FILE),
-            );
+            return Some(PathBuf::from(appdata).join("xtrans-rs").join(file_name));
         }
     }
     None
 }
 
+pub fn dictionary_prefs_path() -> Option<PathBuf> {
+    config_file_path(DICT_PREFS_FILE)
+}
+
+/// User dictionary file for the `user_path` dictionary pref.
+pub fn user_dictionary_path(user_path: &str) -> Option<PathBuf> {
+    if user_path.is_empty() {
+        config_file_path(USER_DICT_FILE)
+    } else {
+        Some(PathBuf::from(user_path))
+    }
+}
+
+/// Folder of the plugin extraction cache.
+pub fn extract_cache_dir() -> Option<PathBuf> {
+    config_file_path(EXTRACT_CACHE_DIR)
+}
+
+pub fn validation_prefs_path() -> Option<PathBuf> {
+    config_file_path(VALIDATION_PREFS_FILE)
+}
+
+pub fn user_prefs_path() -> Option<PathBuf> {
+    config_file_path(USER_PREFS_FILE)
+}
+
+/// Per-project spellcheck ignore list, one word per line.
+pub fn session_prefs_path() -> Option<PathBuf> {
+    config_file_path(SESSION_PREFS_FILE)
+}
+
+pub fn spell_ignore_path(project: &str) -> Option<PathBuf> {
+    let name: String = project
+        .chars()
+        .map(|ch| match ch {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            _ => ch,
+        })
+        .collect();
+    config_file_path(SPELL_IGNORE_DIR).map(|dir| dir.join(format!("{name}.txt")))
+}
+
+pub fn load_spell_ignore(project: &str) -> Result<Vec<String>, String> {
+    let Some(path) = spell_ignore_path(project) else {
+        return Ok(Vec::new());
+    };
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        std::fs::read_to_string(&path).map_err(|err| format!("read {}: {err}", path.display()))?;
+    Ok(parse_spell_ignore(&content))
+}
+
+pub fn save_spell_ignore(project: &str, words: &[String]) -> Result<(), String> {
+    let mut content = words.join("\n");
+    content.push('\n');
+    write_prefs_file(spell_ignore_path(project), &content)
+}
+
+pub fn parse_spell_ignore(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
 pub fn load_dictionary_prefs() -> Result<DictionaryPrefs, String> {
     let Some(path) = dictionary_prefs_path() else {
         return Ok(DictionaryPrefs::default());
@@ -60,15 +294,516 @@ pub fn load_dictionary_prefs() -> Result<DictionaryPrefs, String> {
 }
 
 pub fn save_dictionary_prefs(prefs: &DictionaryPrefs) -> Result<(), String> {
-    let Some(path) = dictionary_prefs_path() else {
+    write_prefs_file(dictionary_prefs_path(), &serialize_dictionary_prefs(prefs))
+}
+
+pub fn load_validation_prefs() -> Result<ValidationConfig, String> {
+    let Some(path) = validation_prefs_path() else {
+        return Ok(ValidationConfig::default());
+    };
+    if !path.exists() {
+        return Ok(ValidationConfig::default());
+    }
+    let content =
+        std::fs::read_to_string(&path).map_err(|err| format!("read {}: {err}", path.display()))?;
+    parse_validation_prefs(&content)
+}
+
+pub fn save_validation_prefs(config: &ValidationConfig) -> Result<(), String> {
+    write_prefs_file(validation_prefs_path(), &serialize_validation_prefs(config))
+}
+
+pub fn load_user_prefs() -> Result<UserPrefs, String> {
+    let Some(path) = user_prefs_path() else {
+        return Ok(UserPrefs::default());
+    };
+    if !path.exists() {
+        return Ok(UserPrefs::default());
+    }
+    let content =
+        std::fs::read_to_string(&path).map_err(|err| format!("read {}: {err}", path.display()))?;
+    parse_user_prefs(&content)
+}
+
+pub fn save_user_prefs(prefs: &UserPrefs) -> Result<(), String> {
+    write_prefs_file(user_prefs_path(), &serialize_user_prefs(prefs))
+}
+
+pub fn serialize_user_prefs(prefs: &UserPrefs) -> String {
+    let order = prefs
+        .columns
+        .columns()
+        .iter()
+        .map(|setting| setting.column.id())
+        .collect::<Vec<_>>();
+    let mut lines = vec![
+        "version=1".to_string(),
+        format!("author={}", escape_pref_value(&prefs.author)),
+        format!("game={}", prefs.game.name()),
+        format!("search_ignore_case={}", u8::from(prefs.search.ignore_case)),
+        format!(
+            "search_ignore_width={}",
+            u8::from(prefs.search.ignore_width)
+        ),
+        format!("columns={}", order.join(",")),
+        format!(
+            "encoding={}",
+            prefs.save.encoding.map_or("auto", Encoding::name)
+        ),
+        format!("backup={}", prefs.save.backup.id()),
+        format!("autosave_minutes={}", prefs.save.autosave_minutes),
+        format!(
+            "output_template={}",
+            escape_pref_value(&prefs.save.output_template.to_string())
+        ),
+        format!("write_manifest={}", u8::from(prefs.save.write_manifest)),
+        format!("mo2={}", u8::from(prefs.mo2.enabled)),
+        format!("mo2_profile={}", escape_pref_value(&prefs.mo2.profile)),
+        format!(
+            "overwrite_duplicates={}",
+            u8::from(prefs.overwrite_duplicates)
+        ),
+        format!("rewrite_after_auto={}", u8::from(prefs.rewrite.after_auto)),
+        format!("extract_cache={}", u8::from(prefs.extract_cache)),
+    ];
+    for (index, rule) in prefs.rewrite.rules.iter().enumerate() {
+        lines.push(format!(
+            "rewrite.{index}.name={}",
+            escape_pref_value(&rule.name)
+        ));
+        lines.push(format!(
+            "rewrite.{index}.enabled={}",
+            u8::from(rule.enabled)
+        ));
+        lines.push(format!(
+            "rewrite.{index}.pattern={}",
+            escape_pref_value(&rule.pattern)
+        ));
+        lines.push(format!(
+            "rewrite.{index}.replacement={}",
+            escape_pref_value(&rule.replacement)
+        ));
+    }
+    for setting in prefs.columns.columns() {
+        let id = setting.column.id();
+        lines.push(format!("column.{id}.width={}", setting.width));
+        lines.push(format!("column.{id}.visible={}", u8::from(setting.visible)));
+    }
+    // Unbound commands are written empty so they stay unbound.
+    for (command, chord) in prefs.keymap.bindings() {
+        let chord = chord.map(|chord| chord.label()).unwrap_or_default();
+        lines.push(format!(
+            "key.{}={}",
+            command.id(),
+            escape_pref_value(&chord)
+        ));
+    }
+    lines.join("\n")
+}
+
+pub fn parse_user_prefs(content: &str) -> Result<UserPrefs, String> {
+    let mut out = UserPrefs::default();
+    let mut version = None::<u32>;
+    let mut column_fields = Vec::new();
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err("ユーザー設定フォーマットが不正です".to_string());
+        };
+        match key {
+            "version" => {
+                let v = value
+                    .parse::<u32>()
+                    .map_err(|_| "ユーザー設定versionが不正です".to_string())?;
+                version = Some(v);
+            }
+            "author" => out.author = unescape_pref_value(value)?,
+            "game" => {
+                out.game = GameProfile::parse(value)
+                    .ok_or_else(|| format!("ユーザー設定gameが不正です: {value}"))?;
+            }
+            "search_ignore_case" => out.search.ignore_case = parse_pref_bool(value)?,
+            "search_ignore_width" => out.search.ignore_width = parse_pref_bool(value)?,
+            "columns" => {
+                let order = value
+                    .split(',')
+                    .filter_map(EntryColumn::parse)
+                    .collect::<Vec<_>>();
+                out.columns = ColumnLayout::with_order(&order);
+            }
+            "encoding" => {
+                out.save.encoding = match value {
+                    "auto" => None,
+                    _ => Some(
+                        Encoding::parse(value)
+                            .ok_or_else(|| format!("ユーザー設定encodingが不正です: {value}"))?,
+                    ),
+                };
+            }
+            "backup" => {
+                out.save.backup = BackupPolicy::parse(value)
+                    .ok_or_else(|| format!("ユーザー設定backupが不正です: {value}"))?;
+            }
+            "autosave_minutes" => {
+                out.save.autosave_minutes = value
+                    .parse::<u32>()
+                    .map_err(|_| format!("ユーザー設定の自動保存間隔が不正です: {value}"))?;
+            }
+            "output_template" => {
+                let template = unescape_pref_value(value)?;
+                out.save.output_template = OutputTemplate::parse(&template).ok_or_else(|| {
+                    format!("ユーザー設定の出力ファイル名テンプレートが不正です: {template}")
+                })?;
+            }
+            "write_manifest" => out.save.write_manifest = parse_pref_bool(value)?,
+            "mo2" => out.mo2.enabled = parse_pref_bool(value)?,
+            "mo2_profile" => out.mo2.profile = unescape_pref_value(value)?,
+            "overwrite_duplicates" => out.overwrite_duplicates = parse_pref_bool(value)?,
+            "rewrite_after_auto" => out.rewrite.after_auto = parse_pref_bool(value)?,
+            "extract_cache" => out.extract_cache = parse_pref_bool(value)?,
+            _ => {
+                if let Some(id) = key.strip_prefix("key.") {
+                    if let Some(command) = Command::parse(id) {
+                        out.keymap.bind(command, parse_pref_chord(value)?);
+                    }
+                } else if let Some((index, field)) = key
+                    .strip_prefix("rewrite.")
+                    .and_then(|rest| rest.split_once('.'))
+                {
+                    let index = index
+                        .parse::<usize>()
+                        .map_err(|_| "ユーザー設定の置換ルール番号が不正です".to_string())?;
+                    while out.rewrite.rules.len() <= index {
+                        out.rewrite.rules.push(RewriteRule {
+                            name: String::new(),
+                            enabled: true,
+                            pattern: String::new(),
+                            replacement: String::new(),
+                        });
+                    }
+                    let rule = &mut out.rewrite.rules[index];
+                    match field {
+                        "name" => rule.name = unescape_pref_value(value)?,
+                        "enabled" => rule.enabled = parse_pref_bool(value)?,
+                        "pattern" => rule.pattern = unescape_pref_value(value)?,
+                        "replacement" => rule.replacement = unescape_pref_value(value)?,
+                        _ => {}
+                    }
+                } else if let Some((id, field)) = key
+                    .strip_prefix("column.")
+                    .and_then(|rest| rest.split_once('.'))
+                {
+                    if let Some(column) = EntryColumn::parse(id) {
+                        column_fields.push((column, field, value));
+                    }
+                }
+            }
+        }
+    }
+    // Widths and visibility may precede the `columns` order line.
+    for (column, field, value) in column_fields {
+        match field {
+            "width" => {
+                let width = value
+                    .parse::<u16>()
+                    .map_err(|_| format!("ユーザー設定の列幅が不正です: {value}"))?;
+                out.columns.set_width(column, width);
+            }
+            "visible" => {
+                out.columns.set_visible(column, parse_pref_bool(value)?);
+            }
+            _ => {}
+        }
+    }
+    out.rewrite.rules.retain(|rule| !rule.pattern.is_empty());
+    match version {
+        Some(1) => Ok(out),
+        Some(v) => Err(format!("未対応のユーザー設定version: {v}")),
+        None => Err("ユーザー設定versionがありません".to_string()),
+    }
+}
+
+pub fn load_session_prefs() -> Result<SessionPrefs, String> {
+    let Some(path) = session_prefs_path() else {
+        return Ok(SessionPrefs::default());
+    };
+    if !path.exists() {
+        return Ok(SessionPrefs::default());
+    }
+    let content =
+        std::fs::read_to_string(&path).map_err(|err| format!("read {}: {err}", path.display()))?;
+    parse_session_prefs(&content)
+}
+
+pub fn save_session_prefs(prefs: &SessionPrefs) -> Result<(), String> {
+    write_prefs_file(session_prefs_path(), &serialize_session_prefs(prefs))
+}
+
+pub fn serialize_session_prefs(prefs: &SessionPrefs) -> String {
+    let mut lines = vec![
+        "version=1".to_string(),
+        format!("tab={}", prefs.active_tab.id()),
+    ];
+    if let Some(window) = prefs.window {
+        lines.push(format!(
+            "window={},{},{},{}",
+            window.x, window.y, window.width, window.height
+        ));
+        lines.push(format!("window_maximized={}", u8::from(window.maximized)));
+    }
+    for (at, (path, session)) in prefs.files.iter().enumerate() {
+        lines.push(format!(
+            "file.{at}.path={}",
+            escape_pref_value(&path.to_string_lossy())
+        ));
+        lines.push(format!(
+            "file.{at}.query={}",
+            escape_pref_value(&session.query)
+        ));
+        lines.push(format!(
+            "file.{at}.untranslated_only={}",
+            u8::from(session.untranslated_only)
+        ));
+        if let Some(sort) = session.sort {
+            lines.push(format!("file.{at}.sort={}", sort.id()));
+        }
+        if let Some(key) = &session.selected_key {
+            lines.push(format!("file.{at}.selected={}", escape_pref_value(key)));
+        }
+    }
+    lines.join("\n")
+}
+
+pub fn parse_session_prefs(content: &str) -> Result<SessionPrefs, String> {
+    let mut out = SessionPrefs::default();
+    let mut version = None::<u32>;
+    let mut window = None::<[f32; 4]>;
+    let mut maximized = false;
+    let mut files = BTreeMap::<usize, (Option<PathBuf>, FileSession)>::new();
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err("セッション設定フォーマットが不正です".to_string());
+        };
+        match key {
+            "version" => {
+                let v = value
+                    .parse::<u32>()
+                    .map_err(|_| "セッション設定versionが不正です".to_string())?;
+                version = Some(v);
+            }
+            // A tab that no longer exists falls back to ホーム.
+            "tab" => out.active_tab = Tab::parse(value).unwrap_or(Tab::Home),
+            "window" => {
+                let values = value
+                    .split(',')
+                    .map(|part| part.parse::<f32>().ok().filter(|v| v.is_finite()))
+                    .collect::<Option<Vec<_>>>();
+                window = Some(
+                    values
+                        .and_then(|values| values.try_into().ok())
+                        .ok_or_else(|| {
+                            format!("セッション設定のウィンドウ位置が不正です: {value}")
+                        })?,
+                );
+            }
+            "window_maximized" => maximized = parse_pref_bool(value)?,
+            _ => {
+                let Some((at, field)) = key
+                    .strip_prefix("file.")
+                    .and_then(|rest| rest.split_once('.'))
+                else {
+                    continue;
+                };
+                let at = at
+                    .parse::<usize>()
+                    .map_err(|_| format!("セッション設定のファイル番号が不正です: {key}"))?;
+                let (path, session) = files.entry(at).or_default();
+                match field {
+                    "path" => *path = Some(PathBuf::from(unescape_pref_value(value)?)),
+                    "query" => session.query = unescape_pref_value(value)?,
+                    "untranslated_only" => session.untranslated_only = parse_pref_bool(value)?,
+                    "sort" => {
+                        session.sort = Some(EntrySort::parse(value).ok_or_else(|| {
+                            format!("セッション設定の並べ替えが不正です: {value}")
+                        })?);
+                    }
+                    "selected" => session.selected_key = Some(unescape_pref_value(value)?),
+                    _ => {}
+                }
+            }
+        }
+    }
+    out.window = window.map(|[x, y, width, height]| WindowGeometry {
+        x,
+        y,
+        width,
+        height,
+        maximized,
+    });
+    // Entries without a path cannot be matched to a file.
+    out.files = files
+        .into_values()
+        .filter_map(|(path, session)| Some((path?, session)))
+        .take(SESSION_FILE_LIMIT)
+        .collect();
+    match version {
+        Some(1) => Ok(out),
+        Some(v) => Err(format!("未対応のセッション設定version: {v}")),
+        None => Err("セッション設定versionがありません".to_string()),
+    }
+}
+
+fn write_prefs_file(path: Option<PathBuf>, content: &str) -> Result<(), String> {
+    let Some(path) = path else {
         return Err("設定保存先を解決できません".to_string());
     };
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)
             .map_err(|err| format!("create {}: {err}", parent.display()))?;
     }
-    std::fs::write(&path, serialize_dictionary_prefs(prefs))
-        .map_err(|err| format!("write {}: {err}", path.display()))
+    write_atomic(&path, content).map_err(|err| format!("write {}: {err}", path.display()))
+}
+
+pub fn serialize_validation_prefs(config: &ValidationConfig) -> String {
+    let mut lines = Vec::new();
+    lines.push("version=1".to_string());
+    lines.push(format!("save_guard={}", config.save_guard.as_str()));
+    lines.push(format!(
+        "spell_dictionary={}",
+        escape_pref_value(&config.spell_dictionary)
+    ));
+    for rule in &config.rules {
+        let id = escape_pref_value(&rule.rule_id);
+        lines.push(format!("rule.{id}.enabled={}", u8::from(rule.enabled)));
+        lines.push(format!("rule.{id}.severity={}", rule.severity.as_str()));
+    }
+    for (index, rule) in config.custom_rules.iter().enumerate() {
+        lines.push(format!(
+            "custom.{index}.id={}",
+            escape_pref_value(&rule.rule_id)
+        ));
+        lines.push(format!("custom.{index}.enabled={}", u8::from(rule.enabled)));
+        lines.push(format!(
+            "custom.{index}.severity={}",
+            rule.severity.as_str()
+        ));
+        lines.push(format!(
+            "custom.{index}.pattern={}",
+            escape_pref_value(&rule.pattern)
+        ));
+        lines.push(format!(
+            "custom.{index}.message={}",
+            escape_pref_value(&rule.message)
+        ));
+    }
+    lines.join("\n")
+}
+
+pub fn parse_validation_prefs(content: &str) -> Result<ValidationConfig, String> {
+    let mut out = ValidationConfig::default();
+    let mut custom: Vec<CustomRegexRule> = Vec::new();
+    let mut version = None::<u32>;
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err("検証設定フォーマットが不正です".to_string());
+        };
+        if key == "version" {
+            let v = value
+                .parse::<u32>()
+                .map_err(|_| "検証設定versionが不正です".to_string())?;
+            version = Some(v);
+        } else if key == "save_guard" {
+            out.save_guard = SaveGuard::parse(value)
+                .ok_or_else(|| format!("検証設定save_guardが不正です: {value}"))?;
+        } else if key == "spell_dictionary" {
+            out.spell_dictionary = unescape_pref_value(value)?;
+        } else if let Some(rest) = key.strip_prefix("rule.") {
+            let Some((id, field)) = rest.rsplit_once('.') else {
+                continue;
+            };
+            let id = unescape_pref_value(id)?;
+            if out.rule(&id).is_none() {
+                out.rules.push(RuleSetting {
+                    rule_id: id.clone(),
+                    enabled: true,
+                    severity: Severity::Error,
+                });
+            }
+            let Some(rule) = out.rule_mut(&id) else {
+                continue;
+            };
+            match field {
+                "enabled" => rule.enabled = parse_pref_bool(value)?,
+                "severity" => rule.severity = parse_pref_severity(value)?,
+                _ => {}
+            }
+        } else if let Some(rest) = key.strip_prefix("custom.") {
+            let Some((index, field)) = rest.split_once('.') else {
+                continue;
+            };
+            let index = index
+                .parse::<usize>()
+                .map_err(|_| "検証設定customの番号が不正です".to_string())?;
+            while custom.len() <= index {
+                custom.push(CustomRegexRule {
+                    rule_id: String::new(),
+                    enabled: true,
+                    severity: Severity::Warn,
+                    pattern: String::new(),
+                    message: String::new(),
+                });
+            }
+            let rule = &mut custom[index];
+            match field {
+                "id" => rule.rule_id = unescape_pref_value(value)?,
+                "enabled" => rule.enabled = parse_pref_bool(value)?,
+                "severity" => rule.severity = parse_pref_severity(value)?,
+                "pattern" => rule.pattern = unescape_pref_value(value)?,
+                "message" => rule.message = unescape_pref_value(value)?,
+                _ => {}
+            }
+        }
+    }
+    out.custom_rules = custom
+        .into_iter()
+        .filter(|rule| !rule.rule_id.is_empty())
+        .collect();
+    match version {
+        Some(1) => Ok(out),
+        Some(v) => Err(format!("未対応の検証設定version: {v}")),
+        None => Err("検証設定versionがありません".to_string()),
+    }
+}
+
+fn parse_pref_bool(value: &str) -> Result<bool, String> {
+    match value {
+        "1" | "true" => Ok(true),
+        "0" | "false" => Ok(false),
+        _ => Err(format!("真偽値が不正です: {value}")),
+    }
+}
+
+/// Empty means unbound.
+fn parse_pref_chord(value: &str) -> Result<Option<KeyChord>, String> {
+    let value = unescape_pref_value(value)?;
+    if value.is_empty() {
+        return Ok(None);
+    }
+    KeyChord::parse(&value)
+        .map(Some)
+        .ok_or_else(|| format!("ユーザー設定のショートカットが不正です: {value}"))
+}
+
+fn parse_pref_severity(value: &str) -> Result<Severity, String> {
+    Severity::parse(value).ok_or_else(|| format!("重大度が不正です: {value}"))
 }
 
 pub fn serialize_dictionary_prefs(prefs: &DictionaryPrefs) -> String {
@@ -83,6 +818,29 @@ pub fn serialize_dictionary_prefs(prefs: &DictionaryPrefs) -> String {
         escape_pref_value(&prefs.target_lang)
     ));
     lines.push(format!("root={}", escape_pref_value(&prefs.root)));
+    lines.push(format!(
+        "match_ignore_case={}",
+        u8::from(prefs.match_options.ignore_case)
+    ));
+    lines.push(format!(
+        "match_ignore_width={}",
+        u8::from(prefs.match_options.ignore_width)
+    ));
+    lines.push(format!("match_trim={}", u8::from(prefs.match_options.trim)));
+    lines.push(format!(
+        "match_collapse_whitespace={}",
+        u8::from(prefs.match_options.collapse_whitespace)
+    ));
+    lines.push(format!(
+        "match_strip_trailing_punctuation={}",
+        u8::from(prefs.match_options.strip_trailing_punctuation)
+    ));
+    lines.push(format!("user_dict={}", escape_pref_value(&prefs.user_path)));
+    lines.push(format!(
+        "protect_vanilla={}",
+        u8::from(prefs.protect_vanilla)
+    ));
+    lines.push(format!("apply_on_load={}", u8::from(prefs.apply_on_load)));
     lines.join("\n")
 }
 
@@ -106,6 +864,18 @@ pub fn parse_dictionary_prefs(content: &str) -> Result<DictionaryPrefs, String>
             "source_lang" => out.source_lang = unescape_pref_value(value)?,
             "target_lang" => out.target_lang = unescape_pref_value(value)?,
             "root" => out.root = unescape_pref_value(value)?,
+            "match_ignore_case" => out.match_options.ignore_case = parse_pref_bool(value)?,
+            "match_ignore_width" => out.match_options.ignore_width = parse_pref_bool(value)?,
+            "match_trim" => out.match_options.trim = parse_pref_bool(value)?,
+            "match_collapse_whitespace" => {
+                out.match_options.collapse_whitespace = parse_pref_bool(value)?
+            }
+            "match_strip_trailing_punctuation" => {
+                out.match_options.strip_trailing_punctuation = parse_pref_bool(value)?
+            }
+            "user_dict" => out.user_path = unescape_pref_value(value)?,
+            "protect_vanilla" => out.protect_vanilla = parse_pref_bool(value)?,
+            "apply_on_load" => out.apply_on_load = parse_pref_bool(value)?,
             _ => {}
         }
     }
@@ -118,13 +888,13 @@ pub fn parse_dictionary_prefs(content: &str) -> Result<DictionaryPrefs, String>
 
 fn escape_pref_value(input: &str) -> String {
     let mut out = String::with_capacity(input.len());
-    for b in input.bytes() {
-        match b {
-            b'%' => out.push_str("%25"),
-            b'=' => out.push_str("%3D"),
-            b'\n' => out.push_str("%0A"),
-            b'\r' => out.push_str("%0D"),
-            _ => out.push(b as char),
+    for ch in input.chars() {
+        match ch {
+            '%' => out.push_str("%25"),
+            '=' => out.push_str("%3D"),
+            '\n' => out.push_str("%0A"),
+            '\r' => out.push_str("%0D"),
+            _ => out.push(ch),
         }
     }
     out
@@ -165,9 +935,208 @@ mod tests {
             source_lang: "english".to_string(),
             target_lang: "japanese".to_string(),
             root: "/tmp/with=equals".to_string(),
+            match_options: MatchOptions {
+                ignore_case: true,
+                ..MatchOptions::EXACT
+            },
+            user_path: "D:\\dict\\user=1.tsv".to_string(),
+            protect_vanilla: true,
+            apply_on_load: true,
         };
         let encoded = serialize_dictionary_prefs(&prefs);
         let decoded = parse_dictionary_prefs(&encoded).expect("parse prefs");
         assert_eq!(decoded, prefs);
     }
+
+    #[test]
+    fn t_app_007_validation_prefs_round_trip() {
+        let mut config = ValidationConfig {
+            save_guard: SaveGuard::Refuse,
+            spell_dictionary: "C:\\dict\\en_US.dic".to_string(),
+            ..ValidationConfig::default()
+        };
+        config.rules[0].enabled = false;
+        config.rules[1].severity = Severity::Warn;
+        config.custom_rules.push(CustomRegexRule {
+            rule_id: "custom.ascii-period".to_string(),
+            enabled: true,
+            severity: Severity::Warn,
+            pattern: r"\.$|a=b".to_string(),
+            message: "末尾が半角ピリオド".to_string(),
+        });
+        let encoded = serialize_validation_prefs(&config);
+        let decoded = parse_validation_prefs(&encoded).expect("parse prefs");
+        assert_eq!(decoded, config);
+    }
+
+    #[test]
+    fn t_app_008_spell_ignore_list_parse() {
+        let words = parse_spell_ignore("# project words\nDovahkiin\n\n  Whiterun  \n");
+        assert_eq!(words, vec!["Dovahkiin".to_string(), "Whiterun".to_string()]);
+        let path = spell_ignore_path("Mods/My:Plugin.esp");
+        if let Some(path) = path {
+            assert!(path.ends_with("Mods_My_Plugin.esp.txt"));
+        }
+    }
+
+    #[test]
+    fn t_app_010_user_prefs_round_trip() {
+        let mut prefs = UserPrefs {
+            author: "山田 = 100%".to_string(),
+            game: GameProfile::Fallout4,
+            search: MatchOptions {
+                ignore_width: true,
+                ..MatchOptions::EXACT
+            },
+            columns: ColumnLayout::default(),
+            keymap: Keymap::default(),
+            save: SavePrefs {
+                encoding: Some(Encoding::Latin1),
+                backup: BackupPolicy::Off,
+                autosave_minutes: 5,
+                output_template: OutputTemplate::parse("translated/{lang}/{stem}.{ext}")
+                    .expect("template"),
+                write_manifest: true,
+            },
+            mo2: Mo2Prefs {
+                enabled: true,
+                profile: "日本語=JP".to_string(),
+            },
+            overwrite_duplicates: false,
+            rewrite: RewritePrefs {
+                rules: vec![
+                    RewriteRule {
+                        name: "全角括弧".to_string(),
+                        enabled: true,
+                        pattern: r"\((.+?)\)".to_string(),
+                        replacement: "（$1）".to_string(),
+                    },
+                    RewriteRule {
+                        name: "句点前の空白".to_string(),
+                        enabled: false,
+                        pattern: r"\s+。".to_string(),
+                        replacement: "。".to_string(),
+                    },
+                ],
+                after_auto: true,
+            },
+            extract_cache: true,
+        };
+        prefs.columns.move_column(EntryColumn::Target, false);
+        prefs.columns.set_width(EntryColumn::Source, 333);
+        prefs.columns.set_visible(EntryColumn::Record, false);
+        prefs
+            .keymap
+            .bind(Command::QuickAuto, KeyChord::parse("Ctrl+="));
+        prefs.keymap.bind(Command::NextRow, None);
+        let text = serialize_user_prefs(&prefs);
+        assert_eq!(parse_user_prefs(&text).expect("parse"), prefs);
+        assert_eq!(
+            parse_user_prefs("version=1\nauthor=a")
+                .expect("old prefs")
+                .search,
+            MatchOptions::LOOSE
+        );
+        let columns = parse_user_prefs("column.ld.width=40\nversion=1\ncolumns=ld,unknown")
+            .expect("column order after widths")
+            .columns;
+        assert_eq!(columns.columns()[0].column, EntryColumn::Ld);
+        assert_eq!(columns.columns()[0].width, 40);
+        assert_eq!(columns.columns().len(), EntryColumn::ALL.len());
+        assert!(parse_user_prefs("version=1\ncolumn.edid.width=wide").is_err());
+        assert!(parse_user_prefs("version=1\nkey.quick_auto=Ctrl+Nope").is_err());
+        assert_eq!(
+            parse_user_prefs("version=1").expect("no save prefs").save,
+            SavePrefs::default()
+        );
+        assert!(parse_user_prefs("version=1\nbackup=always").is_err());
+        assert!(parse_user_prefs("version=1\nbackup=rotating:0").is_err());
+        assert_eq!(
+            parse_user_prefs("version=1\nbackup=rotating:3")
+                .expect("rotating backups")
+                .save
+                .backup,
+            BackupPolicy::Rotating(3)
+        );
+        assert!(parse_user_prefs("version=1\nautosave_minutes=-1").is_err());
+        assert!(parse_user_prefs("version=1\noutput_template={name}.esp").is_err());
+        assert!(parse_user_prefs("author=x").is_err());
+        assert!(parse_user_prefs("version=2").is_err());
+    }
+
+    #[test]
+    fn t_app_015_session_prefs_round_trip() {
+        let mut prefs = SessionPrefs {
+            window: Some(WindowGeometry {
+                x: 12.5,
+                y: -4.0,
+                width: 1280.0,
+                height: 800.0,
+                maximized: true,
+            }),
+            active_tab: Tab::Stats,
+            files: Vec::new(),
+        };
+        prefs.remember_file(
+            Path::new("Data/a.esp"),
+            FileSession {
+                query: "剣 = sword".to_string(),
+                untranslated_only: true,
+                sort: EntrySort::parse("target,desc"),
+                selected_key: Some("WEAP:00000001:FULL:0".to_string()),
+            },
+        );
+        prefs.remember_file(Path::new("Data/b.esp"), FileSession::default());
+        prefs.remember_file(
+            Path::new("Data/a.esp"),
+            FileSession {
+                query: "盾".to_string(),
+                ..FileSession::default()
+            },
+        );
+        assert_eq!(prefs.files.len(), 2);
+        assert_eq!(prefs.files[0].0, Path::new("Data/a.esp"));
+        assert_eq!(prefs.file(Path::new("Data/a.esp")).unwrap().query, "盾");
+        prefs.files[1].1.sort = EntrySort::parse("ld,asc");
+
+        let text = serialize_session_prefs(&prefs);
+        assert_eq!(parse_session_prefs(&text).expect("parse"), prefs);
+
+        for at in 0..SESSION_FILE_LIMIT + 1 {
+            prefs.remember_file(Path::new(&format!("{at}.esp")), FileSession::default());
+        }
+        assert_eq!(prefs.files.len(), SESSION_FILE_LIMIT);
+        assert_eq!(prefs.files[0].0, Path::new("20.esp"));
+
+        let old = parse_session_prefs("version=1\ntab=npc\nfile.0.query=x").expect("lenient");
+        assert_eq!(old.active_tab, Tab::Home);
+        assert!(old.files.is_empty(), "file without a path is dropped");
+        assert!(parse_session_prefs("version=1\nwindow=1,2,3").is_err());
+        assert!(parse_session_prefs("version=1\nfile.0.sort=target,up").is_err());
+        assert!(parse_session_prefs("tab=home").is_err());
+    }
+
+    #[test]
+    fn t_app_044_dict_key_normalization_prefs_round_trip() {
+        let prefs = DictionaryPrefs {
+            match_options: MatchOptions {
+                trim: true,
+                collapse_whitespace: true,
+                strip_trailing_punctuation: true,
+                ..MatchOptions::LOOSE
+            },
+            ..DictionaryPrefs::default()
+        };
+        let encoded = serialize_dictionary_prefs(&prefs);
+        assert!(encoded.contains("match_strip_trailing_punctuation=1"));
+        assert_eq!(parse_dictionary_prefs(&encoded).expect("parse"), prefs);
+        let old = parse_dictionary_prefs("version=1\nmatch_ignore_case=1\n").expect("old prefs");
+        assert_eq!(
+            old.match_options,
+            MatchOptions {
+                ignore_case: true,
+                ..MatchOptions::EXACT
+            }
+        );
+    }
 }
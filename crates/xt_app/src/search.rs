@@ -0,0 +1,221 @@
+//! Search over the entry list under [`MatchOptions`]. A trigram index narrows
+//! a query to candidate rows, which are then checked against each row's
+//! folded text; queries shorter than three characters scan that text.
+
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use xt_core::model::Entry;
+use xt_core::search::MatchOptions;
+
+/// Lists at least this long are indexed on a worker thread; shorter ones are
+/// indexed immediately.
+pub const BACKGROUND_INDEX_MIN_ROWS: usize = 20_000;
+
+/// Keeps source and target apart so a query never matches across them.
+const FIELD_SEPARATOR: char = '\0';
+
+#[derive(Debug, Clone)]
+pub struct SearchIndex {
+    options: MatchOptions,
+    /// Folded `source \0 target` per row.
+    rows: Vec<Box<str>>,
+    /// Rows containing each trigram, ascending. May list rows that no longer
+    /// contain it after [`SearchIndex::update_row`]; matches are re-checked.
+    trigrams: HashMap<u64, Vec<u32>>,
+}
+
+impl SearchIndex {
+    pub fn build(entries: &[Entry], options: MatchOptions) -> Self {
+        let mut index = Self {
+            options,
+            rows: Vec::with_capacity(entries.len()),
+            trigrams: HashMap::new(),
+        };
+        for (row, entry) in entries.iter().enumerate() {
+            let text = row_text(entry, options);
+            index.add_trigrams(row as u32, &text);
+            index.rows.push(text.into_boxed_str());
+        }
+        index
+    }
+
+    pub fn options(&self) -> MatchOptions {
+        self.options
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Re-indexes `row` after its texts changed.
+    pub fn update_row(&mut self, row: usize, entry: &Entry) {
+        let Some(slot) = self.rows.get_mut(row) else {
+            return;
+        };
+        let text = row_text(entry, self.options);
+        if **slot == *text {
+            return;
+        }
+        *slot = text.clone().into_boxed_str();
+        self.add_trigrams(row as u32, &text);
+    }
+
+    /// Rows whose source or target contains `query`, ascending.
+    pub fn matches(&self, query: &str) -> Vec<usize> {
+        if query.contains(FIELD_SEPARATOR) {
+            return Vec::new();
+        }
+        let query = self.options.fold(query);
+        let keys = trigrams(&query).collect::<Vec<_>>();
+        if keys.is_empty() {
+            return self.scan(0..self.rows.len(), &query);
+        }
+        let mut postings = Vec::with_capacity(keys.len());
+        for key in &keys {
+            match self.trigrams.get(key) {
+                Some(rows) => postings.push(rows.as_slice()),
+                None => return Vec::new(),
+            }
+        }
+        postings.sort_by_key(|rows| rows.len());
+        let (shortest, rest) = postings.split_first().expect("query has trigrams");
+        let candidates = shortest
+            .iter()
+            .copied()
+            .filter(|row| rest.iter().all(|rows| rows.binary_search(row).is_ok()))
+            .map(|row| row as usize);
+        self.scan(candidates, &query)
+    }
+
+    /// Whether `row` contains an already folded query.
+    pub fn row_matches(&self, row: usize, folded_query: &str) -> bool {
+        !folded_query.contains(FIELD_SEPARATOR)
+            && self
+                .rows
+                .get(row)
+                .is_some_and(|text| text.contains(folded_query))
+    }
+
+    fn scan(&self, rows: impl Iterator<Item = usize>, query: &str) -> Vec<usize> {
+        rows.filter(|&row| self.rows[row].contains(query)).collect()
+    }
+
+    fn add_trigrams(&mut self, row: u32, text: &str) {
+        for key in trigrams(text) {
+            let rows = self.trigrams.entry(key).or_default();
+            match rows.last() {
+                Some(&last) if last == row => {}
+                Some(&last) if last < row => rows.push(row),
+                None => rows.push(row),
+                Some(_) => {
+                    if let Err(at) = rows.binary_search(&row) {
+                        rows.insert(at, row);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whether `entry` matches a query already folded with `options`; the same
+/// test as [`SearchIndex::matches`] without an index.
+pub fn entry_matches(entry: &Entry, folded_query: &str, options: MatchOptions) -> bool {
+    folded_query.is_empty()
+        || options.fold(&entry.source_text).contains(folded_query)
+        || options.fold(&entry.target_text).contains(folded_query)
+}
+
+fn row_text(entry: &Entry, options: MatchOptions) -> String {
+    let mut text = options.fold(&entry.source_text).into_owned();
+    text.push(FIELD_SEPARATOR);
+    text.push_str(&options.fold(&entry.target_text));
+    text
+}
+
+/// Three consecutive characters packed into one key (21 bits each).
+fn trigrams(text: &str) -> impl Iterator<Item = u64> + '_ {
+    let mut window = [0u64; 3];
+    text.chars().enumerate().filter_map(move |(at, ch)| {
+        window = [window[1], window[2], ch as u64];
+        (at >= 2 && !window.contains(&(FIELD_SEPARATOR as u64)))
+            .then(|| window[0] << 42 | window[1] << 21 | window[2])
+    })
+}
+
+/// Index being built on a worker thread. Dropping it discards the result.
+pub struct PendingIndex {
+    receiver: Receiver<SearchIndex>,
+    /// Rows edited since the build started; re-indexed when it arrives.
+    pub stale_rows: Vec<usize>,
+}
+
+impl PendingIndex {
+    pub fn spawn(entries: Vec<Entry>, options: MatchOptions) -> Self {
+        let (tx, receiver) = mpsc::channel();
+        tracing::debug!(target: "xt_app::search", "indexing {} rows in background", entries.len());
+        thread::spawn(move || {
+            let _ = tx.send(SearchIndex::build(&entries, options));
+        });
+        Self {
+            receiver,
+            stale_rows: Vec::new(),
+        }
+    }
+
+    /// The built index once the worker is done; `Disconnected` if it died.
+    pub fn try_take(&self) -> Result<SearchIndex, TryRecvError> {
+        self.receiver.try_recv()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: &str, source: &str, target: &str) -> Entry {
+        Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        }
+    }
+
+    #[test]
+    fn t_search_001_trigram_index_matches_like_scan() {
+        let mut entries = vec![
+            entry("k0", "Iron Sword", "鉄の剣"),
+            entry("k1", "Steel Sword", ""),
+            entry("k2", "IRON ore", "鉄鉱石"),
+            entry("k3", "Sword", "d"),
+        ];
+        let options = MatchOptions::LOOSE;
+        let mut index = SearchIndex::build(&entries, options);
+        assert_eq!(index.len(), 4);
+        for query in [
+            "iron", "Sword", "鉄の", "鉄", "ir", "", "sword", "x", "rd\0d",
+        ] {
+            let expected = (0..entries.len())
+                .filter(|&row| entry_matches(&entries[row], &options.fold(query), options))
+                .collect::<Vec<_>>();
+            assert_eq!(index.matches(query), expected, "query {query:?}");
+        }
+        assert!(
+            index.matches("sword\0d").is_empty(),
+            "no match across fields"
+        );
+
+        entries[1].target_text = "鋼鉄の剣".into();
+        entries[2].source_text = "Gold ore".into();
+        index.update_row(1, &entries[1]);
+        index.update_row(2, &entries[2]);
+        assert_eq!(index.matches("鉄の剣"), vec![0, 1]);
+        assert_eq!(index.matches("iron"), vec![0]);
+        assert_eq!(index.matches("GOLD"), vec![2]);
+    }
+}
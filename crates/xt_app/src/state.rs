@@ -1,62 +1,121 @@
-use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::TryRecvError;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use xt_core::candidates::{CandidateMap, Proposal, Provenance, ProvenanceMap};
 use xt_core::dictionary::TranslationDictionary;
-use xt_core::diff::EntryStatus;
-use xt_core::formats::esp::ExtractedString;
+use xt_core::diff::{EntryStatus, PluginDiff, PluginDiffKind, PluginDiffRow, StringsDiff};
+use xt_core::duplicates::{duplicate_sources, DuplicateSource};
+use xt_core::encoding::Encoding;
+use xt_core::extract_cache::ExtractCache;
+use xt_core::formats::esp::{ExtractedString, PluginInfo};
 use xt_core::formats::plugin::PluginFile;
-use xt_core::formats::strings::StringsFile;
-use xt_core::hybrid::HybridEntry;
-use xt_core::import_export::XmlApplyStats;
-use xt_core::model::Entry;
+use xt_core::formats::strings::{StringsFile, StringsInfo};
+use xt_core::game::{default_encoding, GameProfile};
+use xt_core::hybrid::HybridRecord;
+use xt_core::import_export::{ApplyProfile, XmlApplyStats};
+use xt_core::katakana;
+use xt_core::loadorder::{find_overrides, LoadOrder, LoadOrderPlugin, Override};
+use xt_core::model::{EditMeta, EditMetaMap, Entry, NoteMap, UtcDateTime};
+use xt_core::pipeline::PluginContext;
+pub use xt_core::pipeline::StringsKind;
+use xt_core::record_index::RecordIndex;
+use xt_core::review_sheet::{rows_tsv, Correction, ReviewRow, ReviewSheet, RowStatus};
+use xt_core::rewrite::Rewriter;
+use xt_core::search::MatchOptions;
+use xt_core::spellcheck::SpellChecker;
 use xt_core::ui_state::TwoPaneState;
-use xt_core::validation::ValidationIssue;
+use xt_core::validation::{ValidationConfig, ValidationIssue, Validator};
 
+use crate::columns::{ColumnLayout, EntrySort};
 use crate::history::{BatchTargetChange, EntryHistory, SingleEditOp, DEFAULT_HISTORY_LIMIT};
+use crate::keymap::Keymap;
+use crate::log::{self, LogLevel, SharedLog};
 use crate::prefs::{
-    load_dictionary_prefs, save_dictionary_prefs, DictionaryPrefs, DEFAULT_DICT_ROOT,
-    DEFAULT_DICT_SOURCE_LANG, DEFAULT_DICT_TARGET_LANG,
+    extract_cache_dir, load_dictionary_prefs, load_spell_ignore, load_user_prefs,
+    load_validation_prefs, save_dictionary_prefs, save_spell_ignore, save_user_prefs,
+    save_validation_prefs, DictionaryPrefs, FileSession, Mo2Prefs, RewritePrefs, SavePrefs,
+    UserPrefs, DEFAULT_DICT_ROOT, DEFAULT_DICT_SOURCE_LANG, DEFAULT_DICT_TARGET_LANG,
+    GLOBAL_SPELL_PROJECT,
 };
+use crate::search::{entry_matches, PendingIndex, SearchIndex, BACKGROUND_INDEX_MIN_ROWS};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Tab {
     Home,
+    Hybrid,
+    Dictionary,
     Heuristic,
     Lang,
     Esp,
     Pex,
     Quest,
     Npc,
+    History,
+    Diff,
+    Overrides,
+    Duplicates,
+    Stats,
     Log,
 }
 
 impl Tab {
-    pub fn all() -> [(Tab, &'static str); 2] {
-        [(Tab::Home, "ホーム"), (Tab::Log, "ログ")]
+    /// Key used in the session prefs file.
+    pub fn id(self) -> &'static str {
+        match self {
+            Tab::Home => "home",
+            Tab::Hybrid => "hybrid",
+            Tab::Dictionary => "dictionary",
+            Tab::Heuristic => "heuristic",
+            Tab::Lang => "lang",
+            Tab::Esp => "esp",
+            Tab::Pex => "pex",
+            Tab::Quest => "quest",
+            Tab::Npc => "npc",
+            Tab::History => "history",
+            Tab::Diff => "diff",
+            Tab::Overrides => "overrides",
+            Tab::Duplicates => "duplicates",
+            Tab::Stats => "stats",
+            Tab::Log => "log",
+        }
     }
-}
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum StringsKind {
-    Strings,
-    DlStrings,
-    IlStrings,
-}
+    /// Only tabs listed in [`Tab::all`] are accepted.
+    pub fn parse(id: &str) -> Option<Self> {
+        Self::all()
+            .into_iter()
+            .map(|(tab, _)| tab)
+            .find(|tab| tab.id() == id)
+    }
 
-impl StringsKind {
-    pub fn from_extension(ext: &str) -> Option<Self> {
-        if ext.eq_ignore_ascii_case("strings") {
-            Some(Self::Strings)
-        } else if ext.eq_ignore_ascii_case("dlstrings") {
-            Some(Self::DlStrings)
-        } else if ext.eq_ignore_ascii_case("ilstrings") {
-            Some(Self::IlStrings)
-        } else {
-            None
-        }
+    pub fn all() -> [(Tab, &'static str); 9] {
+        [
+            (Tab::Home, "ホーム"),
+            (Tab::Hybrid, "ハイブリッド"),
+            (Tab::Dictionary, "辞書"),
+            (Tab::History, "履歴"),
+            (Tab::Diff, "差分"),
+            (Tab::Overrides, "上書き"),
+            (Tab::Duplicates, "重複原文"),
+            (Tab::Stats, "統計"),
+            (Tab::Log, "ログ"),
+        ]
     }
 }
 
+/// Read-only pairs of an already translated plugin, e.g. the official
+/// translation of the game's masters.
+#[derive(Clone, Debug)]
+pub struct ReferenceTranslation {
+    /// Translated Strings file it was loaded from.
+    pub path: PathBuf,
+    pub dict: TranslationDictionary,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct DictionaryBuildSummary {
     pub built_at_unix: u64,
@@ -65,6 +124,19 @@ pub struct DictionaryBuildSummary {
     pub file_pairs: usize,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StringsDiffReport {
+    pub old_path: PathBuf,
+    pub new_path: PathBuf,
+    pub diff: StringsDiff,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StringsInfoReport {
+    pub path: PathBuf,
+    pub info: StringsInfo,
+}
+
 #[derive(Clone, Default, PartialEq, Eq)]
 pub struct ChannelCounts {
     pub total: usize,
@@ -74,23 +146,217 @@ pub struct ChannelCounts {
     pub ilstrings: usize,
 }
 
+impl ChannelCounts {
+    fn add(&mut self, entry: &Entry) {
+        self.total += 1;
+        if !entry.target_text.is_empty() {
+            self.translated += 1;
+        }
+        match channel_of(&entry.key) {
+            "dlstrings" => self.dlstrings += 1,
+            "ilstrings" => self.ilstrings += 1,
+            _ => self.strings += 1,
+        }
+    }
+}
+
+/// Strings channel of an entry key: `strings`, `dlstrings` or `ilstrings`.
+pub fn channel_of(key: &str) -> &'static str {
+    let key = key.to_ascii_lowercase();
+    if key.contains("dlstrings") {
+        "dlstrings"
+    } else if key.contains("ilstrings") {
+        "ilstrings"
+    } else {
+        "strings"
+    }
+}
+
+/// A file open in a document tab. The active document lives in the
+/// [`AppState`] fields; switching tabs swaps it with a parked one.
+pub struct Document {
+    id: u64,
+    history: EntryHistory,
+    pane: TwoPaneState,
+    selected_keys: BTreeSet<String>,
+    untranslated_only: bool,
+    review_only: bool,
+    edit_source: String,
+    edit_target: String,
+    edit_meta: EditMetaMap,
+    notes: NoteMap,
+    candidates: CandidateMap,
+    provenance: ProvenanceMap,
+    validated_keys: BTreeSet<String>,
+    validation_issues: Vec<ValidationIssue>,
+    diff_status: Option<EntryStatus>,
+    plugin_diff: Option<PluginDiff>,
+    hybrid: Vec<HybridRecord>,
+    editor_ids: EditorIdMap,
+    record_index: RecordIndex,
+    loaded_strings: Option<StringsFile>,
+    loaded_strings_kind: Option<StringsKind>,
+    loaded_strings_path: Option<PathBuf>,
+    loaded_plugin: Option<PluginFile>,
+    loaded_plugin_path: Option<PathBuf>,
+    loaded_esp_strings: Option<Vec<ExtractedString>>,
+    plugin_info: Option<PluginInfo>,
+    plugin_context: PluginContext,
+    plugin_languages: Vec<String>,
+    last_xml_stats: Option<XmlApplyStats>,
+    query_input: String,
+    sort: Option<EntrySort>,
+    search_index: Option<SearchIndex>,
+    pending_index: Option<PendingIndex>,
+    revision: u64,
+    saved_revision: u64,
+}
+
+impl Document {
+    fn new(id: u64) -> Self {
+        Self {
+            id,
+            history: EntryHistory::with_limit(DEFAULT_HISTORY_LIMIT),
+            pane: TwoPaneState::new(Vec::new()),
+            selected_keys: BTreeSet::new(),
+            untranslated_only: false,
+            review_only: false,
+            edit_source: String::new(),
+            edit_target: String::new(),
+            edit_meta: EditMetaMap::new(),
+            notes: NoteMap::new(),
+            candidates: CandidateMap::default(),
+            provenance: ProvenanceMap::new(),
+            validated_keys: BTreeSet::new(),
+            validation_issues: Vec::new(),
+            diff_status: None,
+            plugin_diff: None,
+            hybrid: Vec::new(),
+            editor_ids: EditorIdMap::new(),
+            record_index: RecordIndex::default(),
+            loaded_strings: None,
+            loaded_strings_kind: None,
+            loaded_strings_path: None,
+            loaded_plugin: None,
+            loaded_plugin_path: None,
+            loaded_esp_strings: None,
+            plugin_info: None,
+            plugin_context: PluginContext::default(),
+            plugin_languages: Vec::new(),
+            last_xml_stats: None,
+            query_input: String::new(),
+            sort: None,
+            search_index: None,
+            pending_index: None,
+            revision: 0,
+            saved_revision: 0,
+        }
+    }
+
+    fn file(&self) -> Option<&Path> {
+        self.loaded_plugin_path
+            .as_deref()
+            .or(self.loaded_strings_path.as_deref())
+    }
+}
+
+/// A plugin open in a document tab; see [`AppState::open_plugins`].
+pub struct OpenPlugin<'a> {
+    /// Index of its document tab.
+    pub document: usize,
+    /// File name.
+    pub name: String,
+    pub masters: &'a [String],
+    pub entries: &'a [Entry],
+    pub revision: u64,
+}
+
+/// A document tab as the tab bar shows it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocumentTab {
+    pub title: String,
+    pub file: Option<PathBuf>,
+    pub unsaved: bool,
+}
+
+impl DocumentTab {
+    fn new(file: Option<&Path>, unsaved: bool) -> Self {
+        let title = file
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "無題".to_string());
+        Self {
+            title,
+            file: file.map(Path::to_path_buf),
+            unsaved,
+        }
+    }
+}
+
+/// Typing pause after which the search box text is applied as the query.
+pub const QUERY_DEBOUNCE: Duration = Duration::from_millis(150);
+
 pub struct AppState {
     pub history: EntryHistory,
     pub pane: TwoPaneState,
+    /// Extra rows picked with Ctrl/Shift-click, in addition to the selected row.
+    pub selected_keys: BTreeSet<String>,
+    pub untranslated_only: bool,
+    /// Shows only the review queue (要確認): rows whose target still needs a
+    /// person to approve it, see [`AppState::in_review`].
+    pub review_only: bool,
 
     pub edit_source: String,
     pub edit_target: String,
+    /// Last author/time per entry key; exported as optional XML/JSON fields.
+    pub edit_meta: EditMetaMap,
+    /// Translator notes per entry key; kept in exported XML/JSON, never in
+    /// the target text.
+    pub notes: NoteMap,
+    /// Targets proposed per entry by XML, dictionary and manual edits since
+    /// the file was loaded; disagreeing proposals are conflicts.
+    pub candidates: CandidateMap,
+    /// How each row's current target was produced (Origin column); rows
+    /// still holding their loaded target are absent.
+    pub provenance: ProvenanceMap,
+    /// Rows the translator marked as checked (検証済み) this session.
+    pub validated_keys: BTreeSet<String>,
+    /// Unix seconds the app started; the 統計 tab measures throughput from it.
+    pub session_started_at: u64,
+    pub author: String,
+    /// Game profile used for the next plugin load (persisted in user prefs).
+    pub game_profile: GameProfile,
+    pub user_prefs_error: String,
 
     pub xml_text: String,
     pub xml_error: Option<String>,
     pub file_status: String,
+    /// Message of the last failed action (file, record/offset and cause).
+    pub last_error: Option<String>,
+    /// Timestamped events shown in the ログ tab (shared with the `tracing` collector).
+    pub log: SharedLog,
+    /// Least severe level shown in the ログ tab.
+    pub log_filter: LogLevel,
 
     pub validation_issues: Vec<ValidationIssue>,
+    pub validation_config: ValidationConfig,
+    pub validation_prefs_error: String,
+    pub spellchecker: Option<Arc<SpellChecker>>,
+    pub spell_ignore: Vec<String>,
+    pub spell_status: String,
     pub diff_status: Option<EntryStatus>,
+    /// Result of the last old/new plugin comparison.
+    pub plugin_diff: Option<PluginDiff>,
+    pub strings_diff: Option<StringsDiffReport>,
+    pub strings_info: Option<StringsInfoReport>,
     pub encoding_status: String,
 
-    pub hybrid_preview: Vec<HybridEntry>,
-    pub hybrid_error: Option<String>,
+    /// Strings of the loaded ESP grouped by record (ハイブリッド tab).
+    pub hybrid: Vec<HybridRecord>,
+    /// EDID of the loaded ESP's records by entry key (EDID column).
+    pub editor_ids: EditorIdMap,
+    /// Rows of the loaded ESP by form id and EDID (移動 dialog).
+    pub record_index: RecordIndex,
 
     pub loaded_strings: Option<StringsFile>,
     pub loaded_strings_kind: Option<StringsKind>,
@@ -99,21 +365,73 @@ pub struct AppState {
     pub loaded_plugin: Option<PluginFile>,
     pub loaded_plugin_path: Option<PathBuf>,
     pub loaded_esp_strings: Option<Vec<ExtractedString>>,
+    /// TES4 header of the loaded .esp/.esm/.esl.
+    pub plugin_info: Option<PluginInfo>,
+    /// Profile, workspace root and Strings language the loaded plugin was
+    /// extracted with; saves must use the same.
+    pub plugin_context: PluginContext,
+    /// Languages with Strings files for the loaded plugin.
+    pub plugin_languages: Vec<String>,
 
     pub dict: Option<TranslationDictionary>,
+    /// Consulted before `dict`; not edited or saved.
+    pub reference: Option<ReferenceTranslation>,
     pub dict_source_lang: String,
     pub dict_target_lang: String,
     pub dict_root: String,
     pub dict_status: String,
     pub dict_prefs_error: String,
+    /// How Quick自動翻訳 matches entry sources to dictionary sources.
+    pub dict_match: MatchOptions,
+    /// See [`DictionaryPrefs::protect_vanilla`].
+    pub dict_protect_vanilla: bool,
+    /// See [`DictionaryPrefs::apply_on_load`].
+    pub dict_apply_on_load: bool,
     pub dict_build_summary: Option<DictionaryBuildSummary>,
+    /// 辞書 tab file; see [`DictionaryPrefs::user_path`].
+    pub dict_user_path: String,
+    /// Bumped whenever `dict` is replaced or edited.
+    pub dict_revision: u64,
+    /// Edits in the 辞書 tab not yet written to the user dictionary.
+    pub dict_unsaved: bool,
 
     pub active_tab: Tab,
     pub last_xml_stats: Option<XmlApplyStats>,
+    pub apply_profile: ApplyProfile,
 
+    /// Search box text; becomes the query [`QUERY_DEBOUNCE`] after the last edit.
+    query_input: String,
+    query_edited_at: Option<Instant>,
     filtered_index_cache: Vec<usize>,
     filtered_counts_cache: ChannelCounts,
     filtered_cache_dirty: bool,
+    search_options: MatchOptions,
+    /// Index of the current entries; `None` while a rebuild is pending, when
+    /// filtering scans the entries instead.
+    search_index: Option<SearchIndex>,
+    pending_index: Option<PendingIndex>,
+    columns: ColumnLayout,
+    keymap: Keymap,
+    sort: Option<EntrySort>,
+    save_prefs: SavePrefs,
+    mo2_prefs: Mo2Prefs,
+    overwrite_duplicates: bool,
+    rewrite_prefs: RewritePrefs,
+    extract_cache: bool,
+    /// The enabled rules of `rewrite_prefs`, compiled.
+    rewriter: Rewriter,
+    /// Bumped on every change to the entries.
+    revision: u64,
+    /// [`AppState::revision`] as of the last load or save.
+    saved_revision: u64,
+    /// Last revision handed out; revisions are unique across documents.
+    last_revision: u64,
+    /// Id of the active document.
+    document_id: u64,
+    last_document_id: u64,
+    /// Document tabs in order; the active one's slot is a placeholder.
+    documents: Vec<Document>,
+    active_document: usize,
 }
 
 impl Default for AppState {
@@ -127,39 +445,104 @@ impl AppState {
         let history = EntryHistory::with_limit(DEFAULT_HISTORY_LIMIT);
         let pane = TwoPaneState::new(Vec::new());
         let initial_prefs = load_dictionary_prefs().unwrap_or_default();
+        let validation_config = load_validation_prefs().unwrap_or_default();
+        let user_prefs = load_user_prefs().unwrap_or_default();
+        let (rewriter, user_prefs_error) = match Rewriter::new(&user_prefs.rewrite.rules) {
+            Ok(rewriter) => (rewriter, String::new()),
+            Err(err) => (Rewriter::default(), format!("置換ルールが不正です: {err}")),
+        };
 
-        Self {
+        let mut state = Self {
             history,
             pane,
+            selected_keys: BTreeSet::new(),
+            untranslated_only: false,
+            review_only: false,
             edit_source: String::new(),
             edit_target: String::new(),
+            edit_meta: EditMetaMap::new(),
+            notes: NoteMap::new(),
+            candidates: CandidateMap::default(),
+            provenance: ProvenanceMap::new(),
+            validated_keys: BTreeSet::new(),
+            session_started_at: now_unix_seconds(),
+            author: user_prefs.author,
+            game_profile: user_prefs.game,
+            user_prefs_error,
             xml_text: String::new(),
             xml_error: None,
             file_status: String::new(),
+            last_error: None,
+            log: log::global(),
+            log_filter: LogLevel::Info,
             validation_issues: Vec::new(),
+            validation_config,
+            validation_prefs_error: String::new(),
+            spellchecker: None,
+            spell_ignore: Vec::new(),
+            spell_status: String::new(),
             diff_status: None,
+            plugin_diff: None,
+            strings_diff: None,
+            strings_info: None,
             encoding_status: String::new(),
-            hybrid_preview: Vec::new(),
-            hybrid_error: None,
+            hybrid: Vec::new(),
+            editor_ids: EditorIdMap::new(),
+            record_index: RecordIndex::default(),
             loaded_strings: None,
             loaded_strings_kind: None,
             loaded_strings_path: None,
             loaded_plugin: None,
             loaded_plugin_path: None,
             loaded_esp_strings: None,
+            plugin_info: None,
+            plugin_context: PluginContext::default(),
+            plugin_languages: Vec::new(),
             dict: None,
+            reference: None,
             dict_source_lang: initial_prefs.source_lang,
             dict_target_lang: initial_prefs.target_lang,
             dict_root: initial_prefs.root,
             dict_status: String::new(),
             dict_prefs_error: String::new(),
+            dict_match: initial_prefs.match_options,
+            dict_protect_vanilla: initial_prefs.protect_vanilla,
+            dict_apply_on_load: initial_prefs.apply_on_load,
             dict_build_summary: None,
+            dict_user_path: initial_prefs.user_path,
+            dict_revision: 0,
+            dict_unsaved: false,
             active_tab: Tab::Home,
             last_xml_stats: None,
+            apply_profile: ApplyProfile::default(),
+            query_input: String::new(),
+            query_edited_at: None,
             filtered_index_cache: Vec::new(),
             filtered_counts_cache: ChannelCounts::default(),
             filtered_cache_dirty: true,
-        }
+            search_options: user_prefs.search,
+            search_index: Some(SearchIndex::build(&[], user_prefs.search)),
+            pending_index: None,
+            columns: user_prefs.columns,
+            keymap: user_prefs.keymap,
+            sort: None,
+            save_prefs: user_prefs.save,
+            mo2_prefs: user_prefs.mo2,
+            overwrite_duplicates: user_prefs.overwrite_duplicates,
+            rewrite_prefs: user_prefs.rewrite,
+            extract_cache: user_prefs.extract_cache,
+            rewriter,
+            revision: 0,
+            saved_revision: 0,
+            last_revision: 0,
+            document_id: 0,
+            last_document_id: 0,
+            documents: vec![Document::new(0)],
+            active_document: 0,
+        };
+        state.reload_spell_ignore();
+        state.reload_spellchecker();
+        state
     }
 
     pub fn selected_key(&self) -> Option<String> {
@@ -170,6 +553,110 @@ impl AppState {
         self.pane.selected_entry()
     }
 
+    /// Every row with its EDID, status, note and last validation results,
+    /// for the HTML review sheet titled `title`.
+    pub fn review_sheet(&self, title: &str) -> ReviewSheet {
+        let mut issues: HashMap<&str, Vec<ValidationIssue>> = HashMap::new();
+        for issue in &self.validation_issues {
+            issues
+                .entry(issue.entry_key.as_str())
+                .or_default()
+                .push(issue.clone());
+        }
+        let rows = self
+            .entries()
+            .iter()
+            .map(|entry| {
+                let status = if self.validated_keys.contains(&entry.key) {
+                    RowStatus::Validated
+                } else if self.in_review(&entry.key) {
+                    RowStatus::NeedsReview
+                } else if entry.target_text.is_empty() {
+                    RowStatus::Untranslated
+                } else {
+                    RowStatus::Translated
+                };
+                ReviewRow {
+                    key: entry.key.clone(),
+                    editor_id: self.editor_ids.get(&entry.key).cloned(),
+                    source: entry.source_text.to_string(),
+                    target: entry.target_text.to_string(),
+                    status,
+                    note: self.notes.get(&entry.key).cloned(),
+                    issues: issues.remove(entry.key.as_str()).unwrap_or_default(),
+                }
+            })
+            .collect();
+        ReviewSheet {
+            title: title.to_string(),
+            source_lang: self.dict_source_lang.clone(),
+            target_lang: self.dict_target_lang.clone(),
+            rows,
+        }
+    }
+
+    /// The record the loaded ESP's row `key` belongs to, with its other
+    /// strings and their current targets.
+    pub fn record_context(&self, key: &str) -> Option<RecordContext> {
+        let extracted = self.loaded_esp_strings.as_deref()?;
+        let record = self
+            .hybrid
+            .iter()
+            .find(|record| record.strings.iter().any(|string| string.key == key))?;
+        let first = extracted.get(record.strings.first()?.position)?;
+        let entries = self.entries();
+        let strings = record
+            .strings
+            .iter()
+            .filter_map(|string| {
+                let extracted = extracted.get(string.position)?;
+                // Entries keep their extraction order; fall back to a scan
+                // if that no longer holds.
+                let entry = entries
+                    .get(string.position)
+                    .filter(|entry| entry.key == string.key)
+                    .or_else(|| entries.iter().find(|entry| entry.key == string.key))?;
+                Some(RecordString {
+                    key: string.key.clone(),
+                    subrecord_type: String::from_utf8_lossy(&string.subrecord_type).into_owned(),
+                    index: extracted.index,
+                    source_text: entry.source_text.to_string(),
+                    target_text: entry.target_text.to_string(),
+                })
+            })
+            .collect();
+        Some(RecordContext {
+            record_type: String::from_utf8_lossy(&record.record_type).into_owned(),
+            form_id: record.form_id,
+            editor_id: first.editor_id.clone(),
+            flags: first.record_flags,
+            strings,
+        })
+    }
+
+    /// Selects the row of the record with form id or EDID `query`, clearing
+    /// the filter when it hides that row. Returns the row's key.
+    pub fn go_to_record(&mut self, query: &str) -> Option<String> {
+        let key = self.record_index.find(query)?.to_string();
+        if self.filtered_position(&key).is_none() {
+            self.set_query("");
+            self.set_untranslated_only(false);
+            self.set_review_only(false);
+        }
+        self.clear_multi_selection();
+        self.select(&key);
+        Some(key)
+    }
+
+    /// The plugin comparison row of `key` when its source changed.
+    pub fn source_change(&self, key: &str) -> Option<&PluginDiffRow> {
+        self.plugin_diff
+            .as_ref()?
+            .rows
+            .iter()
+            .find(|row| row.kind == PluginDiffKind::SourceChanged && row.key == key)
+    }
+
     pub fn filtered_len(&mut self) -> usize {
         self.ensure_filtered_cache();
         self.filtered_index_cache.len()
@@ -185,28 +672,909 @@ impl AppState {
         self.pane.entries()
     }
 
-    pub fn set_query(&mut self, query: &str) {
-        self.pane.set_query(query);
-        self.invalidate_filtered_cache();
+    /// Applies `query` now. When it contains the previous query, the cached
+    /// result is narrowed instead of searching all entries again.
+    pub fn set_query(&mut self, query: &str) {
+        query.clone_into(&mut self.query_input);
+        self.query_edited_at = None;
+        if query == self.pane.query() {
+            return;
+        }
+        let previous = self.search_options.fold(self.pane.query()).into_owned();
+        self.pane.set_query(query);
+        let next = self.search_options.fold(query);
+        if !self.filtered_cache_dirty && !previous.is_empty() && next.contains(&previous) {
+            self.narrow_filtered_cache(&next);
+        } else {
+            self.invalidate_filtered_cache();
+        }
+    }
+
+    pub fn search_options(&self) -> MatchOptions {
+        self.search_options
+    }
+
+    /// Changes how the filter compares text; re-indexes and saves user prefs.
+    pub fn set_search_options(&mut self, options: MatchOptions) {
+        if options == self.search_options {
+            return;
+        }
+        self.search_options = options;
+        self.invalidate_filtered_cache();
+        self.rebuild_search_index();
+        self.save_user_prefs();
+    }
+
+    pub fn columns(&self) -> &ColumnLayout {
+        &self.columns
+    }
+
+    /// Changes the entry list columns and saves user prefs.
+    pub fn set_columns(&mut self, columns: ColumnLayout) {
+        if columns == self.columns {
+            return;
+        }
+        self.columns = columns;
+        self.save_user_prefs();
+    }
+
+    pub fn keymap(&self) -> &Keymap {
+        &self.keymap
+    }
+
+    /// Replaces the keyboard shortcuts and saves user prefs.
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        if keymap == self.keymap {
+            return;
+        }
+        self.keymap = keymap;
+        self.save_user_prefs();
+    }
+
+    pub fn save_prefs(&self) -> &SavePrefs {
+        &self.save_prefs
+    }
+
+    /// Changes encoding, backup, autosave and output naming settings and
+    /// saves user prefs.
+    pub fn set_save_prefs(&mut self, save: SavePrefs) {
+        if save == self.save_prefs {
+            return;
+        }
+        self.save_prefs = save;
+        self.save_user_prefs();
+    }
+
+    pub fn mo2_prefs(&self) -> &Mo2Prefs {
+        &self.mo2_prefs
+    }
+
+    /// Changes the Mod Organizer 2 integration used by later plugin loads
+    /// and saves user prefs.
+    pub fn set_mo2_prefs(&mut self, mo2: Mo2Prefs) {
+        if mo2 == self.mo2_prefs {
+            return;
+        }
+        self.mo2_prefs = mo2;
+        self.save_user_prefs();
+    }
+
+    pub fn overwrite_duplicates(&self) -> bool {
+        self.overwrite_duplicates
+    }
+
+    /// Whether a 重複原文 group's target replaces the differing targets of
+    /// translated rows too; saves user prefs.
+    pub fn set_overwrite_duplicates(&mut self, overwrite: bool) {
+        if overwrite == self.overwrite_duplicates {
+            return;
+        }
+        self.overwrite_duplicates = overwrite;
+        self.save_user_prefs();
+    }
+
+    pub fn extract_cache(&self) -> bool {
+        self.extract_cache
+    }
+
+    /// Whether plugin loads reuse and store cached extraction results; saves
+    /// user prefs.
+    pub fn set_extract_cache(&mut self, enabled: bool) {
+        if enabled == self.extract_cache {
+            return;
+        }
+        self.extract_cache = enabled;
+        self.save_user_prefs();
+    }
+
+    /// The cache plugin loads go through, when it is enabled.
+    pub fn plugin_cache(&self) -> Option<ExtractCache> {
+        self.extract_cache
+            .then(extract_cache_dir)
+            .flatten()
+            .map(ExtractCache::new)
+    }
+
+    pub fn rewrite_prefs(&self) -> &RewritePrefs {
+        &self.rewrite_prefs
+    }
+
+    /// Replaces the 置換ルール and saves user prefs; refuses rules whose
+    /// pattern does not compile.
+    pub fn set_rewrite_prefs(&mut self, rewrite: RewritePrefs) -> Result<(), String> {
+        self.rewriter =
+            Rewriter::new(&rewrite.rules).map_err(|err| format!("置換ルールが不正です: {err}"))?;
+        if rewrite != self.rewrite_prefs {
+            self.rewrite_prefs = rewrite;
+            self.save_user_prefs();
+        }
+        Ok(())
+    }
+
+    /// Runs the 置換ルール over the targets auto-translation put into
+    /// `next` (those that differ from the current ones) and over the
+    /// proposals, when they are set to run after auto-translation. Returns
+    /// the number of rows rewritten.
+    pub fn rewrite_auto_targets(
+        &self,
+        next: &mut [Entry],
+        proposals: &mut [(String, Proposal)],
+    ) -> usize {
+        if !self.rewrite_prefs.after_auto || self.rewriter.is_empty() {
+            return 0;
+        }
+        for (_, proposal) in proposals.iter_mut() {
+            if let Cow::Owned(target) = self.rewriter.apply(&proposal.target) {
+                proposal.target = target.into();
+            }
+        }
+        let current = self.entries();
+        if next.len() != current.len() {
+            return 0;
+        }
+        let mut rewritten = 0;
+        for (after, before) in next.iter_mut().zip(current) {
+            if after.target_text == before.target_text {
+                continue;
+            }
+            if let Cow::Owned(target) = self.rewriter.apply(&after.target_text) {
+                after.target_text = target.into();
+                rewritten += 1;
+            }
+        }
+        rewritten
+    }
+
+    /// Runs the 置換ルール over the translated targets of the selected rows
+    /// (or every row) as one undoable edit. Returns the number of rows
+    /// changed, `None` when no rule is enabled.
+    pub fn apply_rewrite_rules(&mut self, selected_only: bool) -> Option<usize> {
+        if self.rewriter.is_empty() {
+            return None;
+        }
+        let selected = selected_only.then(|| {
+            self.selected_entries()
+                .into_iter()
+                .map(|entry| entry.key)
+                .collect::<BTreeSet<_>>()
+        });
+        let mut next = self.entries().to_vec();
+        let mut changed = Vec::new();
+        for entry in next.iter_mut() {
+            if selected
+                .as_ref()
+                .is_some_and(|keys| !keys.contains(&entry.key))
+            {
+                continue;
+            }
+            if let Cow::Owned(target) = self.rewriter.apply(&entry.target_text) {
+                entry.target_text = target.into();
+                changed.push(entry.key.clone());
+            }
+        }
+        if changed.is_empty() {
+            return Some(0);
+        }
+        for key in &changed {
+            self.stamp_edit(key);
+        }
+        Some(self.apply_target_updates_with_history("置換ルールを適用", next))
+    }
+
+    /// Fills untranslated proper nouns with the katakana reading of their
+    /// source: the selected rows, or every NPC name when `selected_only` is
+    /// off. Protected vanilla rows are left alone. The rows go to the review
+    /// queue; the whole fill is one undoable edit. Returns the number of
+    /// rows filled.
+    pub fn transliterate_names(&mut self, selected_only: bool) -> usize {
+        let selected = selected_only.then(|| {
+            self.selected_entries()
+                .into_iter()
+                .map(|entry| entry.key)
+                .collect::<BTreeSet<_>>()
+        });
+        let mut next = self.entries().to_vec();
+        let mut proposals = Vec::new();
+        for (index, entry) in next.iter_mut().enumerate() {
+            let wanted = match &selected {
+                Some(keys) => keys.contains(&entry.key),
+                None => is_npc_name(&entry.key),
+            };
+            if !wanted || !entry.target_text.is_empty() || self.is_protected(entry) {
+                continue;
+            }
+            let Some(kana) = katakana::transliterate(&entry.source_text) else {
+                continue;
+            };
+            entry.target_text = kana.into();
+            proposals.push((
+                entry.key.clone(),
+                Proposal {
+                    index,
+                    provenance: Provenance::Transliteration,
+                    target: entry.target_text.clone(),
+                    applied: true,
+                },
+            ));
+        }
+        if proposals.is_empty() {
+            return 0;
+        }
+        let filled = self.apply_target_updates_with_history("カタカナ化", next);
+        self.propose_targets(proposals);
+        filled
+    }
+
+    /// Encoding for the 文字コード check: the configured one, else the
+    /// game's default.
+    pub fn text_encoding(&self) -> Encoding {
+        self.save_prefs
+            .encoding
+            .unwrap_or_else(|| default_encoding(self.game_profile))
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Records that the entries as of `revision` are on disk.
+    pub fn mark_saved(&mut self, revision: u64) {
+        self.saved_revision = revision;
+    }
+
+    /// Entries changed since they were loaded or last saved.
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.revision != self.saved_revision
+    }
+
+    fn bump_revision(&mut self) {
+        self.last_revision += 1;
+        self.revision = self.last_revision;
+    }
+
+    /// Id of the active document; changes when another tab becomes active.
+    pub fn document_id(&self) -> u64 {
+        self.document_id
+    }
+
+    pub fn active_document(&self) -> usize {
+        self.active_document
+    }
+
+    pub fn document_tabs(&self) -> Vec<DocumentTab> {
+        self.documents
+            .iter()
+            .enumerate()
+            .map(|(index, document)| {
+                if index == self.active_document {
+                    DocumentTab::new(self.session_file(), self.has_unsaved_changes())
+                } else {
+                    DocumentTab::new(
+                        document.file(),
+                        document.revision != document.saved_revision,
+                    )
+                }
+            })
+            .collect()
+    }
+
+    /// Makes the document that loading `path` should replace active: the
+    /// tab that has it open, else the active one while it is empty, else a
+    /// new tab after the active one.
+    pub fn open_document_for(&mut self, path: &Path) {
+        let open = self
+            .document_tabs()
+            .iter()
+            .position(|tab| tab.file.as_deref() == Some(path));
+        if let Some(index) = open {
+            self.switch_document(index);
+            return;
+        }
+        if self.session_file().is_none() && self.entries().is_empty() {
+            return;
+        }
+        self.last_document_id += 1;
+        let index = self.active_document + 1;
+        self.documents
+            .insert(index, Document::new(self.last_document_id));
+        self.switch_document(index);
+    }
+
+    pub fn switch_document(&mut self, index: usize) -> bool {
+        if index == self.active_document || index >= self.documents.len() {
+            return false;
+        }
+        self.swap_document(self.active_document);
+        self.active_document = index;
+        self.swap_document(index);
+        self.invalidate_filtered_cache();
+        self.reload_spell_ignore();
+        true
+    }
+
+    /// Closes the tab at `index`, activating its neighbour when it was
+    /// active. Closing the last tab leaves an empty document.
+    pub fn close_document(&mut self, index: usize) -> bool {
+        if index >= self.documents.len() {
+            return false;
+        }
+        if self.documents.len() == 1 {
+            self.last_document_id += 1;
+            self.documents[0] = Document::new(self.last_document_id);
+            self.swap_document(0);
+            self.invalidate_filtered_cache();
+            self.reload_spell_ignore();
+            return true;
+        }
+        if index == self.active_document {
+            let next = if index + 1 < self.documents.len() {
+                index + 1
+            } else {
+                index - 1
+            };
+            self.switch_document(next);
+        }
+        self.documents.remove(index);
+        if self.active_document > index {
+            self.active_document -= 1;
+        }
+        true
+    }
+
+    /// Plugins open in document tabs, with their masters and entries, as
+    /// the load order and override lookup take them.
+    pub fn open_plugins(&self) -> Vec<OpenPlugin<'_>> {
+        self.documents
+            .iter()
+            .enumerate()
+            .filter_map(|(index, document)| {
+                let (path, info, entries, revision) = if index == self.active_document {
+                    (
+                        self.loaded_plugin_path.as_deref(),
+                        self.plugin_info.as_ref(),
+                        self.entries(),
+                        self.revision,
+                    )
+                } else {
+                    (
+                        document.loaded_plugin_path.as_deref(),
+                        document.plugin_info.as_ref(),
+                        document.pane.entries(),
+                        document.revision,
+                    )
+                };
+                Some(OpenPlugin {
+                    document: index,
+                    name: path?.file_name()?.to_string_lossy().into_owned(),
+                    masters: &info?.masters,
+                    entries,
+                    revision,
+                })
+            })
+            .collect()
+    }
+
+    /// Fields that more than one open plugin defines, with the plugin that
+    /// wins in load order.
+    pub fn overrides(&self) -> Vec<Override> {
+        let plugins = self.open_plugins();
+        let order = LoadOrder::new(
+            plugins
+                .iter()
+                .map(|plugin| LoadOrderPlugin {
+                    name: plugin.name.clone(),
+                    masters: plugin.masters.to_vec(),
+                })
+                .collect(),
+        );
+        let entries = plugins
+            .iter()
+            .map(|plugin| (plugin.name.as_str(), plugin.entries))
+            .collect::<Vec<_>>();
+        find_overrides(&order, &entries)
+    }
+
+    /// Sets the target of `key` in the document at `index` as a row edit of
+    /// that document (undoable there), leaving the active tab unchanged.
+    pub fn set_document_target(&mut self, index: usize, key: &str, target: &str) -> bool {
+        let active = self.active_document;
+        if index != active && !self.switch_document(index) {
+            return false;
+        }
+        let source = self
+            .entries()
+            .iter()
+            .find(|entry| entry.key == key)
+            .map(|entry| entry.source_text.to_string());
+        let updated = source.is_some_and(|source| self.update_entry(key, &source, target));
+        self.switch_document(active);
+        updated
+    }
+
+    /// Exchanges the active document's fields with the document parked at
+    /// `index`.
+    fn swap_document(&mut self, index: usize) {
+        let document = &mut self.documents[index];
+        macro_rules! swap {
+            ($($field:ident),* $(,)?) => {
+                $(std::mem::swap(&mut self.$field, &mut document.$field);)*
+            };
+        }
+        std::mem::swap(&mut self.document_id, &mut document.id);
+        swap!(
+            history,
+            pane,
+            selected_keys,
+            untranslated_only,
+            review_only,
+            edit_source,
+            edit_target,
+            edit_meta,
+            notes,
+            candidates,
+            provenance,
+            validated_keys,
+            validation_issues,
+            diff_status,
+            plugin_diff,
+            hybrid,
+            editor_ids,
+            record_index,
+            loaded_strings,
+            loaded_strings_kind,
+            loaded_strings_path,
+            loaded_plugin,
+            loaded_plugin_path,
+            loaded_esp_strings,
+            plugin_info,
+            plugin_context,
+            plugin_languages,
+            last_xml_stats,
+            query_input,
+            sort,
+            search_index,
+            pending_index,
+            revision,
+            saved_revision,
+        );
+    }
+
+    pub fn sort(&self) -> Option<EntrySort> {
+        self.sort
+    }
+
+    /// Sorts the entry list; `None` restores file order.
+    pub fn set_sort(&mut self, sort: Option<EntrySort>) {
+        if sort == self.sort {
+            return;
+        }
+        self.sort = sort;
+        self.invalidate_filtered_cache();
+    }
+
+    /// The plugin or Strings file whose working state is kept in the
+    /// session prefs.
+    pub fn session_file(&self) -> Option<&Path> {
+        self.loaded_plugin_path
+            .as_deref()
+            .or(self.loaded_strings_path.as_deref())
+    }
+
+    /// Filter, sort and selection to remember for the loaded file.
+    pub fn file_session(&self) -> FileSession {
+        FileSession {
+            query: self.query_input.clone(),
+            untranslated_only: self.untranslated_only,
+            sort: self.sort,
+            selected_key: self.selected_key(),
+        }
+    }
+
+    /// Restores [`AppState::file_session`]; a selected key that no longer
+    /// exists is ignored.
+    pub fn restore_file_session(&mut self, session: &FileSession) {
+        self.set_query(&session.query);
+        self.set_untranslated_only(session.untranslated_only);
+        self.set_sort(session.sort);
+        if let Some(key) = &session.selected_key {
+            if self.entries().iter().any(|entry| &entry.key == key) {
+                self.clear_multi_selection();
+                self.select(key);
+            }
+        }
+    }
+
+    pub fn query_input(&self) -> &str {
+        &self.query_input
+    }
+
+    /// Search box edit; applied by [`AppState::flush_query`] once typing pauses.
+    pub fn edit_query(&mut self, query: &str, now: Instant) {
+        query.clone_into(&mut self.query_input);
+        self.query_edited_at = Some(now);
+    }
+
+    /// Time left before the pending search box text is applied.
+    pub fn query_debounce_remaining(&self, now: Instant) -> Option<Duration> {
+        let edited_at = self.query_edited_at?;
+        Some(QUERY_DEBOUNCE.saturating_sub(now.saturating_duration_since(edited_at)))
+    }
+
+    /// Applies the search box text if typing paused for [`QUERY_DEBOUNCE`].
+    pub fn flush_query(&mut self, now: Instant) -> bool {
+        if self.query_debounce_remaining(now) != Some(Duration::ZERO) {
+            return false;
+        }
+        let query = self.query_input.clone();
+        self.set_query(&query);
+        true
+    }
+
+    pub fn set_untranslated_only(&mut self, enabled: bool) {
+        self.untranslated_only = enabled;
+        self.invalidate_filtered_cache();
+    }
+
+    pub fn set_review_only(&mut self, enabled: bool) {
+        self.review_only = enabled;
+        self.invalidate_filtered_cache();
+    }
+
+    /// Whether `key` is in the review queue (要確認): its target came from the
+    /// dictionary, machine translation or an XML source match and nobody
+    /// approved it yet.
+    pub fn in_review(&self, key: &str) -> bool {
+        self.provenance
+            .get(key)
+            .is_some_and(|provenance| provenance.needs_review())
+            && !self.validated_keys.contains(key)
+    }
+
+    /// Rows in the review queue.
+    pub fn review_count(&self) -> usize {
+        self.provenance
+            .iter()
+            .filter(|(key, provenance)| {
+                provenance.needs_review() && !self.validated_keys.contains(*key)
+            })
+            .count()
+    }
+
+    /// Entries passing the current search/filter, in list order.
+    pub fn filtered_entries(&mut self) -> Vec<Entry> {
+        self.ensure_filtered_cache();
+        let entries = self.pane.entries();
+        self.filtered_index_cache
+            .iter()
+            .filter_map(|&idx| entries.get(idx).cloned())
+            .collect()
+    }
+
+    pub fn is_row_selected(&self, key: &str) -> bool {
+        self.pane.selected_key() == Some(key) || self.selected_keys.contains(key)
+    }
+
+    /// Ctrl-click: adds or removes a row from the multi-selection.
+    pub fn toggle_selection(&mut self, key: &str) {
+        if let Some(current) = self.selected_key() {
+            self.selected_keys.insert(current);
+        }
+        if !self.selected_keys.remove(key) {
+            self.selected_keys.insert(key.to_string());
+            self.select(key);
+        }
+    }
+
+    /// Shift-click: selects the filtered rows between the selected row and `key`.
+    pub fn select_range(&mut self, key: &str) {
+        let Some(anchor) = self.selected_key() else {
+            self.select(key);
+            return;
+        };
+        self.ensure_filtered_cache();
+        let entries = self.pane.entries();
+        let position = |needle: &str| {
+            self.filtered_index_cache
+                .iter()
+                .position(|&idx| entries[idx].key == needle)
+        };
+        let (Some(from), Some(to)) = (position(&anchor), position(key)) else {
+            return;
+        };
+        let (lo, hi) = if from <= to { (from, to) } else { (to, from) };
+        let keys = self.filtered_index_cache[lo..=hi]
+            .iter()
+            .map(|&idx| entries[idx].key.clone())
+            .collect::<Vec<_>>();
+        self.selected_keys.extend(keys);
+    }
+
+    pub fn clear_multi_selection(&mut self) {
+        self.selected_keys.clear();
+    }
+
+    /// Selected rows in document order; the single selected row when no
+    /// multi-selection exists.
+    pub fn selected_entries(&self) -> Vec<Entry> {
+        let selected = self.pane.selected_key();
+        self.pane
+            .entries()
+            .iter()
+            .filter(|entry| {
+                self.selected_keys.contains(&entry.key) || selected == Some(entry.key.as_str())
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// The selected rows as TSV for the clipboard, with their count; `None`
+    /// when nothing is selected.
+    pub fn selected_rows_tsv(&self) -> Option<(String, usize)> {
+        let entries = self.selected_entries();
+        (!entries.is_empty()).then(|| (rows_tsv(&entries), entries.len()))
+    }
+
+    pub fn select(&mut self, key: &str) {
+        self.pane.select(key);
+        if let Some(entry) = self.pane.selected_entry().cloned() {
+            self.edit_source = entry.source_text.to_string();
+            self.edit_target = entry.target_text.to_string();
+        }
+    }
+
+    /// Position of `key` in the filtered list.
+    pub fn filtered_position(&mut self, key: &str) -> Option<usize> {
+        self.ensure_filtered_cache();
+        let entries = self.pane.entries();
+        self.filtered_index_cache
+            .iter()
+            .position(|&idx| entries[idx].key == key)
+    }
+
+    /// Selects the filtered row `at` alone.
+    fn select_filtered(&mut self, at: usize) -> bool {
+        let Some(key) = self.filtered_entry(at).map(|entry| entry.key.clone()) else {
+            return false;
+        };
+        self.clear_multi_selection();
+        self.select(&key);
+        true
+    }
+
+    /// Selects the row below (`forward`) or above the selected one.
+    pub fn select_adjacent(&mut self, forward: bool) -> bool {
+        let current = self
+            .selected_key()
+            .and_then(|key| self.filtered_position(&key));
+        let at = match (current, forward) {
+            (None, _) => 0,
+            (Some(at), true) => at + 1,
+            (Some(0), false) => return false,
+            (Some(at), false) => at - 1,
+        };
+        self.select_filtered(at)
+    }
+
+    /// Selects the next filtered row after (or before) the selected one that
+    /// satisfies `matches`, wrapping around the list.
+    fn select_next_matching(&mut self, forward: bool, matches: impl Fn(&Entry) -> bool) -> bool {
+        let len = self.filtered_len();
+        if len == 0 {
+            return false;
+        }
+        let current = self
+            .selected_key()
+            .and_then(|key| self.filtered_position(&key));
+        let found = (1..=len)
+            .map(|step| match (current, forward) {
+                (None, true) => step - 1,
+                (None, false) => len - step,
+                (Some(at), true) => (at + step) % len,
+                (Some(at), false) => (at + len - step % len) % len,
+            })
+            .find(|&at| {
+                let entry = &self.pane.entries()[self.filtered_index_cache[at]];
+                matches(entry)
+            });
+        match found {
+            Some(at) if Some(at) != current => self.select_filtered(at),
+            _ => false,
+        }
+    }
+
+    pub fn select_next_untranslated(&mut self, forward: bool) -> bool {
+        self.select_next_matching(forward, |entry| entry.target_text.is_empty())
+    }
+
+    /// Next row with a result from the last validation run.
+    pub fn select_next_issue(&mut self, forward: bool) -> bool {
+        let keys = self
+            .validation_issues
+            .iter()
+            .map(|issue| issue.entry_key.clone())
+            .collect::<BTreeSet<_>>();
+        self.select_next_matching(forward, |entry| keys.contains(&entry.key))
+    }
+
+    /// Next row in the review queue.
+    pub fn select_next_review(&mut self, forward: bool) -> bool {
+        let keys = self.review_keys();
+        self.select_next_matching(forward, |entry| keys.contains(&entry.key))
+    }
+
+    fn review_keys(&self) -> BTreeSet<String> {
+        self.provenance
+            .keys()
+            .filter(|key| self.in_review(key))
+            .cloned()
+            .collect()
+    }
+
+    /// Approves the selected rows of the review queue: they become checked
+    /// (検証済み). Then selects the next row in the queue. Returns how many
+    /// rows were approved.
+    pub fn approve_review(&mut self) -> usize {
+        let keys = self.selected_review_keys();
+        let next = self.next_review_key(&keys);
+        self.validated_keys.extend(keys.iter().cloned());
+        self.invalidate_filtered_cache();
+        self.select_after_review(next);
+        keys.len()
+    }
+
+    /// Rejects the selected rows of the review queue: their targets are
+    /// cleared as one undoable edit. Then selects the next row in the queue.
+    /// Returns how many rows were rejected.
+    pub fn reject_review(&mut self) -> usize {
+        let keys = self.selected_review_keys();
+        if keys.is_empty() {
+            return 0;
+        }
+        let next = self.next_review_key(&keys);
+        let entries = self
+            .pane
+            .entries()
+            .iter()
+            .map(|entry| {
+                let mut entry = entry.clone();
+                if keys.contains(&entry.key) {
+                    entry.target_text = Default::default();
+                }
+                entry
+            })
+            .collect();
+        for key in &keys {
+            self.provenance.remove(key);
+            self.stamp_edit(key);
+        }
+        self.apply_target_updates_with_history("要確認の差し戻し", entries);
+        self.select_after_review(next);
+        keys.len()
+    }
+
+    fn selected_review_keys(&self) -> BTreeSet<String> {
+        self.selected_entries()
+            .into_iter()
+            .map(|entry| entry.key)
+            .filter(|key| self.in_review(key))
+            .collect()
+    }
+
+    /// The first row of the review queue after the selected one, wrapping
+    /// around the filtered list, that is not in `done`.
+    fn next_review_key(&mut self, done: &BTreeSet<String>) -> Option<String> {
+        let keys = self.review_keys();
+        let len = self.filtered_len();
+        let current = self
+            .selected_key()
+            .and_then(|key| self.filtered_position(&key));
+        (1..=len)
+            .map(|step| current.map_or(step - 1, |at| (at + step) % len))
+            .filter_map(|at| self.filtered_entry(at).map(|entry| entry.key.clone()))
+            .find(|key| keys.contains(key) && !done.contains(key))
+    }
+
+    fn select_after_review(&mut self, next: Option<String>) {
+        match next {
+            Some(key) => {
+                self.clear_multi_selection();
+                self.select(&key);
+            }
+            None => self.clear_multi_selection(),
+        }
+    }
+
+    /// Applies the editor to the selected row, then selects the row after it.
+    /// When the edit drops the row from the filtered list (e.g. 未訳のみ), the
+    /// row that took its place is selected; at the end the row stays selected.
+    pub fn apply_edit_and_advance(&mut self) -> bool {
+        let Some(key) = self.selected_key() else {
+            return false;
+        };
+        let Some(at) = self.filtered_position(&key) else {
+            return false;
+        };
+        let source = self.edit_source.clone();
+        let target = self.edit_target.clone();
+        let applied = self.update_entry(&key, &source, &target);
+        let next = match self.filtered_position(&key) {
+            Some(now) => now + 1,
+            None => at,
+        };
+        self.select_filtered(next);
+        applied
+    }
+
+    /// Marks the selected rows checked, or unmarks them when all already are.
+    pub fn toggle_validated(&mut self) -> usize {
+        let keys = self
+            .selected_entries()
+            .into_iter()
+            .map(|entry| entry.key)
+            .collect::<Vec<_>>();
+        if keys.iter().all(|key| self.validated_keys.contains(key)) {
+            for key in &keys {
+                self.validated_keys.remove(key);
+            }
+        } else {
+            self.validated_keys.extend(keys.iter().cloned());
+        }
+        if self.review_only {
+            self.invalidate_filtered_cache();
+        }
+        keys.len()
     }
 
-    pub fn select(&mut self, key: &str) {
-        self.pane.select(key);
-        if let Some(entry) = self.pane.selected_entry().cloned() {
-            self.edit_source = entry.source_text;
-            self.edit_target = entry.target_text;
+    /// Sets the translator note of `key`; a blank note removes it. Returns
+    /// whether the note changed.
+    pub fn set_note(&mut self, key: &str, note: &str) -> bool {
+        let note = note.trim();
+        if note.is_empty() {
+            return self.notes.remove(key).is_some();
+        }
+        if self.notes.get(key).is_some_and(|current| current == note) {
+            return false;
         }
+        self.notes.insert(key.to_string(), note.to_string());
+        true
     }
 
     pub fn set_entries_with_history(&mut self, entries: Vec<Entry>) {
         self.history.clear();
-        self.pane.set_entries(entries);
-        self.invalidate_filtered_cache();
+        self.edit_meta.clear();
+        self.notes.clear();
+        self.candidates.clear();
+        self.provenance.clear();
+        self.validated_keys.clear();
+        self.set_entries_without_history(entries);
+        self.saved_revision = self.revision;
     }
 
     pub fn set_entries_without_history(&mut self, entries: Vec<Entry>) {
+        self.bump_revision();
         self.pane.set_entries(entries);
         self.invalidate_filtered_cache();
+        self.rebuild_search_index();
     }
 
     pub fn update_entry(&mut self, key: &str, source: &str, target: &str) -> bool {
@@ -227,23 +1595,118 @@ impl AppState {
             index,
             before_source: entry.source_text.clone(),
             before_target: entry.target_text.clone(),
-            after_source: source.to_string(),
-            after_target: target.to_string(),
+            after_source: source.into(),
+            after_target: target.into(),
         };
 
         if let Some(entry) = self.pane.entries_mut().get_mut(index) {
-            entry.source_text.clear();
-            entry.source_text.push_str(source);
-            entry.target_text.clear();
-            entry.target_text.push_str(target);
-            self.history.record_single_edit(op);
+            entry.source_text = op.after_source.clone();
+            entry.target_text = op.after_target.clone();
+            self.bump_revision();
+            let target = op.after_target.clone();
+            self.history
+                .record_single_edit_labeled(&format!("行編集 {key}"), op);
+            self.stamp_edit(key);
+            self.set_provenance(key, Provenance::Manual, target.is_empty());
+            self.candidates.propose(key, Provenance::Manual, target);
             self.invalidate_filtered_cache();
+            self.reindex_rows(&[index]);
             return true;
         }
         false
     }
 
-    pub fn apply_target_updates_with_history(&mut self, next: Vec<Entry>) -> usize {
+    /// Applies targets read back from a review sheet or pasted rows, matched
+    /// by key, as one undoable edit named `label`. Changed rows count as
+    /// manual edits.
+    pub fn apply_corrections(
+        &mut self,
+        label: &str,
+        corrections: &[Correction],
+    ) -> CorrectionReport {
+        let index: HashMap<&str, usize> = self
+            .entries()
+            .iter()
+            .enumerate()
+            .map(|(at, entry)| (entry.key.as_str(), at))
+            .collect();
+        let mut report = CorrectionReport::default();
+        let mut next = self.entries().to_vec();
+        let mut changed = Vec::new();
+        for correction in corrections {
+            let Some(&at) = index.get(correction.key.as_str()) else {
+                report.unmatched.push(correction.key.clone());
+                continue;
+            };
+            if next[at].target_text == correction.target {
+                report.unchanged += 1;
+                continue;
+            }
+            next[at].target_text = correction.target.as_str().into();
+            changed.push(at);
+        }
+        if changed.is_empty() {
+            return report;
+        }
+        for &at in &changed {
+            let entry = &next[at];
+            let target = entry.target_text.clone();
+            self.stamp_edit(&entry.key);
+            self.set_provenance(&entry.key, Provenance::Manual, target.is_empty());
+            self.candidates
+                .propose(&entry.key, Provenance::Manual, target);
+        }
+        report.updated = self.apply_target_updates_with_history(label, next);
+        report
+    }
+
+    /// 重複原文: source texts shared by more than one row.
+    pub fn duplicate_sources(&self) -> Vec<DuplicateSource> {
+        duplicate_sources(self.entries())
+    }
+
+    /// Sets `target` on the rows whose source is `source`, as one undoable
+    /// edit: the untranslated ones, and with `overwrite` the translated ones
+    /// too. Returns the number of rows changed, `None` when no row has the
+    /// source.
+    pub fn set_duplicate_target(
+        &mut self,
+        source: &str,
+        target: &str,
+        overwrite: bool,
+    ) -> Option<usize> {
+        let mut next = self.entries().to_vec();
+        let mut found = false;
+        let mut changed = Vec::new();
+        for (at, entry) in next.iter_mut().enumerate() {
+            if entry.source_text != source {
+                continue;
+            }
+            found = true;
+            if entry.target_text == target || !(overwrite || entry.target_text.is_empty()) {
+                continue;
+            }
+            entry.target_text = target.into();
+            changed.push(at);
+        }
+        if !found {
+            return None;
+        }
+        if changed.is_empty() {
+            return Some(0);
+        }
+        for &at in &changed {
+            let key = next[at].key.clone();
+            let target = next[at].target_text.clone();
+            self.stamp_edit(&key);
+            self.set_provenance(&key, Provenance::Manual, target.is_empty());
+            self.candidates.propose(&key, Provenance::Manual, target);
+        }
+        Some(self.apply_target_updates_with_history("重複原文の一括編集", next))
+    }
+
+    /// Replaces targets and records one undoable operation named `label`.
+    pub fn apply_target_updates_with_history(&mut self, label: &str, next: Vec<Entry>) -> usize {
         let current = self.pane.entries();
         if current.len() != next.len()
             || current
@@ -271,21 +1734,177 @@ impl AppState {
             return 0;
         }
         let updated = changes.len();
-        self.history.record_batch_target_edit(changes);
-        self.set_entries_without_history(next);
+        let rows = changes
+            .iter()
+            .map(|change| change.index)
+            .collect::<Vec<_>>();
+        self.history
+            .record_batch_target_edit_labeled(label, changes);
+        if updated < BACKGROUND_INDEX_MIN_ROWS {
+            self.bump_revision();
+            self.pane.set_entries(next);
+            self.invalidate_filtered_cache();
+            self.reindex_rows(&rows);
+        } else {
+            self.set_entries_without_history(next);
+        }
         updated
     }
 
     pub fn undo(&mut self) {
+        let position = self.history.position();
+        let rows = self
+            .history
+            .rows_between(position.saturating_sub(1), position);
         if self.history.undo(self.pane.entries_mut()) {
+            self.bump_revision();
             self.invalidate_filtered_cache();
+            self.reindex_rows(&rows);
         }
     }
 
     pub fn redo(&mut self) {
+        let position = self.history.position();
+        let rows = self.history.rows_between(position, position + 1);
         if self.history.redo(self.pane.entries_mut()) {
+            self.bump_revision();
             self.invalidate_filtered_cache();
+            self.reindex_rows(&rows);
+        }
+    }
+
+    /// Records XML or dictionary matches as candidates, sets the provenance
+    /// of the rows they updated and reports rows now in conflict or given
+    /// an ambiguous dictionary target.
+    pub fn propose_targets(&mut self, proposals: Vec<(String, Proposal)>) {
+        let mut suspect = 0;
+        for (key, proposal) in proposals {
+            if proposal.applied {
+                suspect += usize::from(proposal.provenance.is_suspect());
+                self.set_provenance(&key, proposal.provenance, proposal.target.is_empty());
+            }
+            self.candidates
+                .propose(&key, proposal.provenance, proposal.target);
+        }
+        if suspect > 0 {
+            log::emit(
+                LogLevel::Warn,
+                &format!("辞書に複数の訳がある原文を{suspect}行に適用しました（要確認）"),
+            );
+        }
+        let conflicts = self.candidates.conflicts(self.pane.entries()).len();
+        if conflicts > 0 {
+            log::emit(
+                LogLevel::Warn,
+                &format!("訳文候補の競合が{conflicts}件あります（翻訳 > 競合の解決）"),
+            );
+        }
+    }
+
+    /// An emptied target has no provenance.
+    fn set_provenance(&mut self, key: &str, provenance: Provenance, cleared: bool) {
+        if cleared {
+            self.provenance.remove(key);
+        } else {
+            self.provenance.insert(key.to_string(), provenance);
+        }
+    }
+
+    pub fn stamp_edit(&mut self, key: &str) {
+        self.edit_meta.insert(
+            key.to_string(),
+            EditMeta {
+                author: self.author.clone(),
+                modified_at: now_unix_seconds(),
+            },
+        );
+    }
+
+    pub fn set_author(&mut self, author: &str) {
+        self.author = author.trim().to_string();
+        self.save_user_prefs();
+    }
+
+    /// Shows `message` in the status bar and records it in the log.
+    pub fn set_file_status(&mut self, level: LogLevel, message: impl Into<String>) {
+        self.file_status = message.into();
+        log::emit(level, &self.file_status);
+    }
+
+    /// Shows `message` in the dictionary status line and records it in the log.
+    pub fn set_dict_status(&mut self, level: LogLevel, message: impl Into<String>) {
+        self.dict_status = message.into();
+        log::emit(level, &self.dict_status);
+    }
+
+    pub fn set_game_profile(&mut self, profile: GameProfile) {
+        self.game_profile = profile;
+        self.save_user_prefs();
+    }
+
+    fn save_user_prefs(&mut self) {
+        let prefs = UserPrefs {
+            author: self.author.clone(),
+            game: self.game_profile,
+            search: self.search_options,
+            columns: self.columns.clone(),
+            keymap: self.keymap.clone(),
+            save: self.save_prefs.clone(),
+            mo2: self.mo2_prefs.clone(),
+            overwrite_duplicates: self.overwrite_duplicates,
+            rewrite: self.rewrite_prefs.clone(),
+            extract_cache: self.extract_cache,
+        };
+        match save_user_prefs(&prefs) {
+            Ok(()) => self.user_prefs_error.clear(),
+            Err(err) => self.user_prefs_error = format!("ユーザー設定保存失敗: {err}"),
+        }
+    }
+
+    pub fn jump_to_history(&mut self, position: usize) -> bool {
+        let rows = self.history.rows_between(self.history.position(), position);
+        let jumped = self.history.jump_to(position, self.pane.entries_mut());
+        if jumped {
+            self.bump_revision();
+        }
+        self.invalidate_filtered_cache();
+        self.reindex_rows(&rows);
+        jumped
+    }
+
+    /// True while the search index is being rebuilt in the background.
+    pub fn is_indexing(&self) -> bool {
+        self.pending_index.is_some()
+    }
+
+    /// Installs a search index finished in the background. Returns true when
+    /// filtering switched to it.
+    pub fn poll_search_index(&mut self) -> bool {
+        let Some(pending) = &self.pending_index else {
+            return false;
+        };
+        let mut index = match pending.try_take() {
+            Ok(index) => index,
+            Err(TryRecvError::Empty) => return false,
+            Err(TryRecvError::Disconnected) => {
+                log::emit(LogLevel::Warn, "検索索引を作成できませんでした");
+                self.pending_index = None;
+                return false;
+            }
+        };
+        let stale_rows = self
+            .pending_index
+            .take()
+            .map(|pending| pending.stale_rows)
+            .unwrap_or_default();
+        for row in stale_rows {
+            if let Some(entry) = self.pane.entries().get(row) {
+                index.update_row(row, entry);
+            }
         }
+        self.search_index = Some(index);
+        self.invalidate_filtered_cache();
+        true
     }
 
     pub fn channel_counts(&mut self) -> ChannelCounts {
@@ -302,11 +1921,76 @@ impl AppState {
         }
     }
 
+    /// Whether `source` is a vanilla game string: the reference translation
+    /// has it.
+    pub fn is_vanilla(&self, source: &str) -> bool {
+        self.reference
+            .as_ref()
+            .is_some_and(|reference| reference.dict.get(source).is_some())
+    }
+
+    fn is_protected(&self, entry: &Entry) -> bool {
+        self.dict_protect_vanilla && self.is_vanilla(&entry.source_text)
+    }
+
+    /// Rows exports write: all of them, or those that are not vanilla while
+    /// vanilla rows are protected.
+    pub fn export_entries(&self) -> Cow<'_, [Entry]> {
+        let entries = self.entries();
+        if !entries.iter().any(|entry| self.is_protected(entry)) {
+            return Cow::Borrowed(entries);
+        }
+        Cow::Owned(self.without_protected(entries.to_vec()))
+    }
+
+    pub fn without_protected(&self, mut entries: Vec<Entry>) -> Vec<Entry> {
+        entries.retain(|entry| !self.is_protected(entry));
+        entries
+    }
+
+    /// Keeps the current target of protected vanilla rows that `next` would
+    /// change to anything but the reference translation, and marks their
+    /// proposals unapplied. Returns how many rows were kept.
+    pub fn keep_vanilla_targets(
+        &self,
+        next: &mut [Entry],
+        proposals: &mut [(String, Proposal)],
+    ) -> usize {
+        let current = self.entries();
+        if next.len() != current.len() {
+            return 0;
+        }
+        let mut kept = BTreeSet::new();
+        for (after, before) in next.iter_mut().zip(current) {
+            if after.target_text == before.target_text || !self.is_protected(before) {
+                continue;
+            }
+            let official = self
+                .reference
+                .as_ref()
+                .and_then(|reference| reference.dict.get(&before.source_text));
+            if official != Some(&after.target_text) {
+                after.target_text = before.target_text.clone();
+                kept.insert(before.key.clone());
+            }
+        }
+        for (key, proposal) in proposals {
+            if kept.contains(key) {
+                proposal.applied = false;
+            }
+        }
+        kept.len()
+    }
+
     pub fn persist_dictionary_prefs(&mut self) {
         let prefs = DictionaryPrefs {
             source_lang: self.dict_source_lang.clone(),
             target_lang: self.dict_target_lang.clone(),
             root: self.dict_root.clone(),
+            match_options: self.dict_match,
+            user_path: self.dict_user_path.clone(),
+            protect_vanilla: self.dict_protect_vanilla,
+            apply_on_load: self.dict_apply_on_load,
         };
         match save_dictionary_prefs(&prefs) {
             Ok(()) => self.dict_prefs_error.clear(),
@@ -314,6 +1998,82 @@ impl AppState {
         }
     }
 
+    pub fn persist_validation_prefs(&mut self) {
+        match save_validation_prefs(&self.validation_config) {
+            Ok(()) => self.validation_prefs_error.clear(),
+            Err(err) => self.validation_prefs_error = format!("検証設定保存失敗: {err}"),
+        }
+    }
+
+    /// Validator for the current config, including the loaded spellchecker.
+    pub fn validator(&self) -> Result<Validator, String> {
+        let validator = Validator::new(&self.validation_config).map_err(|err| err.to_string())?;
+        Ok(match &self.spellchecker {
+            Some(checker) => validator.with_spellchecker(Arc::clone(checker)),
+            None => validator,
+        })
+    }
+
+    /// Ignore lists are kept per loaded plugin/strings file name.
+    pub fn spell_project(&self) -> String {
+        self.loaded_plugin_path
+            .as_deref()
+            .or(self.loaded_strings_path.as_deref())
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| GLOBAL_SPELL_PROJECT.to_string())
+    }
+
+    pub fn reload_spellchecker(&mut self) {
+        self.spellchecker = None;
+        let path = self.validation_config.spell_dictionary.trim();
+        if path.is_empty() {
+            self.spell_status.clear();
+            return;
+        }
+        match SpellChecker::load(std::path::Path::new(path)) {
+            Ok(mut checker) => {
+                checker.set_ignored_words(&self.spell_ignore);
+                self.spell_status = format!("スペル辞書: {} 語", checker.word_count());
+                self.spellchecker = Some(Arc::new(checker));
+            }
+            Err(err) => self.spell_status = format!("スペル辞書読込失敗: {err}"),
+        }
+    }
+
+    pub fn reload_spell_ignore(&mut self) {
+        self.spell_ignore = match load_spell_ignore(&self.spell_project()) {
+            Ok(words) => words,
+            Err(err) => {
+                self.spell_status = format!("無視リスト読込失敗: {err}");
+                Vec::new()
+            }
+        };
+        if let Some(checker) = self.spellchecker.as_mut() {
+            Arc::make_mut(checker).set_ignored_words(&self.spell_ignore);
+        }
+    }
+
+    pub fn add_spell_ignore_word(&mut self, word: &str) -> Result<(), String> {
+        let word = word.trim();
+        if word.is_empty() {
+            return Ok(());
+        }
+        if !self
+            .spell_ignore
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(word))
+        {
+            self.spell_ignore.push(word.to_string());
+            save_spell_ignore(&self.spell_project(), &self.spell_ignore)
+                .map_err(|err| format!("無視リスト保存失敗: {err}"))?;
+        }
+        if let Some(checker) = self.spellchecker.as_mut() {
+            Arc::make_mut(checker).ignore_word(word);
+        }
+        Ok(())
+    }
+
     pub fn reset_dictionary_lang_pair(&mut self) {
         self.dict_source_lang = DEFAULT_DICT_SOURCE_LANG.to_string();
         self.dict_target_lang = DEFAULT_DICT_TARGET_LANG.to_string();
@@ -332,40 +2092,118 @@ impl AppState {
             files_seen,
             file_pairs,
         });
+        self.dict_revision += 1;
+        self.dict_unsaved = false;
     }
 
     fn invalidate_filtered_cache(&mut self) {
         self.filtered_cache_dirty = true;
     }
 
+    fn narrow_filtered_cache(&mut self, folded_query: &str) {
+        let mut rows = std::mem::take(&mut self.filtered_index_cache);
+        let entries = self.pane.entries();
+        let options = self.search_options;
+        let index = self.current_search_index();
+        let mut counts = ChannelCounts::default();
+        let editor_ids = &self.editor_ids;
+        rows.retain(|&idx| {
+            let matched =
+                match index {
+                    Some(index) => index.row_matches(idx, folded_query),
+                    None => entry_matches(&entries[idx], folded_query, options),
+                } || editor_id_matches(editor_ids, &entries[idx].key, folded_query, options);
+            if matched {
+                counts.add(&entries[idx]);
+            }
+            matched
+        });
+        self.filtered_index_cache = rows;
+        self.filtered_counts_cache = counts;
+    }
+
+    /// Indexes the whole entry list, on a worker thread for large lists.
+    fn rebuild_search_index(&mut self) {
+        let entries = self.pane.entries();
+        if entries.len() < BACKGROUND_INDEX_MIN_ROWS {
+            self.search_index = Some(SearchIndex::build(entries, self.search_options));
+            self.pending_index = None;
+        } else {
+            self.search_index = None;
+            self.pending_index = Some(PendingIndex::spawn(entries.to_vec(), self.search_options));
+        }
+    }
+
+    /// The search index, if it is up to date with the entries and options.
+    fn current_search_index(&self) -> Option<&SearchIndex> {
+        self.search_index.as_ref().filter(|index| {
+            index.len() == self.pane.entries().len() && index.options() == self.search_options
+        })
+    }
+
+    fn reindex_rows(&mut self, rows: &[usize]) {
+        if let Some(index) = &mut self.search_index {
+            for &row in rows {
+                if let Some(entry) = self.pane.entries().get(row) {
+                    index.update_row(row, entry);
+                }
+            }
+        }
+        if let Some(pending) = &mut self.pending_index {
+            pending.stale_rows.extend_from_slice(rows);
+        }
+    }
+
     fn ensure_filtered_cache(&mut self) {
         if !self.filtered_cache_dirty {
             return;
         }
-        let query = self.pane.query().to_string();
+        let query = self.pane.query();
         let entries = self.pane.entries();
+        let matched = if query.is_empty() {
+            (0..entries.len()).collect::<Vec<_>>()
+        } else if let Some(index) = self.current_search_index() {
+            index.matches(query)
+        } else {
+            let options = self.search_options;
+            let query = options.fold(query);
+            (0..entries.len())
+                .filter(|&idx| entry_matches(&entries[idx], &query, options))
+                .collect()
+        };
+        // Rows whose EDID contains the query match too.
+        let matched = if query.is_empty() || self.editor_ids.is_empty() {
+            matched
+        } else {
+            let options = self.search_options;
+            let query = options.fold(query);
+            let mut rows = matched;
+            rows.extend((0..entries.len()).filter(|&idx| {
+                editor_id_matches(&self.editor_ids, &entries[idx].key, &query, options)
+            }));
+            rows.sort_unstable();
+            rows.dedup();
+            rows
+        };
 
-        let mut indices = Vec::with_capacity(entries.len());
+        let mut indices = Vec::with_capacity(matched.len());
         let mut counts = ChannelCounts::default();
-        for (idx, entry) in entries.iter().enumerate() {
-            if query.is_empty()
-                || entry.source_text.contains(&query)
-                || entry.target_text.contains(&query)
-            {
-                indices.push(idx);
-                counts.total += 1;
-                if !entry.target_text.is_empty() {
-                    counts.translated += 1;
-                }
-                let key = entry.key.to_ascii_lowercase();
-                if key.contains("dlstrings") {
-                    counts.dlstrings += 1;
-                } else if key.contains("ilstrings") {
-                    counts.ilstrings += 1;
-                } else {
-                    counts.strings += 1;
-                }
+        for idx in matched {
+            let entry = &entries[idx];
+            if self.untranslated_only && !entry.target_text.is_empty() {
+                continue;
             }
+            if self.review_only && !self.in_review(&entry.key) {
+                continue;
+            }
+            indices.push(idx);
+            counts.add(entry);
+        }
+
+        if let Some(sort) = self.sort {
+            indices.sort_by(|&a, &b| {
+                sort.compare(&entries[a], &entries[b], &self.provenance, &self.editor_ids)
+            });
         }
 
         self.filtered_index_cache = indices;
@@ -374,8 +2212,73 @@ impl AppState {
     }
 }
 
-pub fn row_fields<'a>(key: &'a str, target_text: &str) -> (&'a str, &'static str, &'static str) {
-    let edid = key.split(':').next_back().unwrap_or(key);
+/// Outcome of [`AppState::apply_corrections`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CorrectionReport {
+    pub updated: usize,
+    /// Rows whose target already matched.
+    pub unchanged: usize,
+    /// Keys with no row, in sheet order.
+    pub unmatched: Vec<String>,
+}
+
+/// The record a selected row's string belongs to (ホーム tab).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordContext {
+    pub record_type: String,
+    pub form_id: u32,
+    pub editor_id: Option<String>,
+    /// Record header flags.
+    pub flags: u32,
+    /// The record's translatable strings in plugin order, the selected one
+    /// included.
+    pub strings: Vec<RecordString>,
+}
+
+/// One translatable subrecord of a [`RecordContext`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordString {
+    pub key: String,
+    pub subrecord_type: String,
+    /// Position among the record's translatable strings, as in the key.
+    pub index: usize,
+    pub source_text: String,
+    pub target_text: String,
+}
+
+/// EDID of a record by the key of each of its strings.
+pub type EditorIdMap = HashMap<String, String>;
+
+/// [`EditorIdMap`] of the strings extracted from a plugin; records without
+/// an EDID are left out.
+pub fn editor_ids(strings: &[ExtractedString]) -> EditorIdMap {
+    strings
+        .iter()
+        .filter_map(|string| {
+            let editor_id = string.editor_id.as_ref()?;
+            Some((string.get_unique_key(), editor_id.clone()))
+        })
+        .collect()
+}
+
+fn editor_id_matches(
+    editor_ids: &EditorIdMap,
+    key: &str,
+    folded_query: &str,
+    options: MatchOptions,
+) -> bool {
+    editor_ids
+        .get(key)
+        .is_some_and(|editor_id| options.fold(editor_id).contains(folded_query))
+}
+
+/// EDID, record and LD cells of a row; rows without an EDID show none.
+pub fn row_fields<'a>(
+    editor_ids: &'a EditorIdMap,
+    key: &str,
+    target_text: &str,
+) -> (&'a str, &'static str, &'static str) {
+    let edid = editor_ids.get(key).map_or("", String::as_str);
     let record_id = if key
         .split(':')
         .next()
@@ -390,29 +2293,340 @@ pub fn row_fields<'a>(key: &'a str, target_text: &str) -> (&'a str, &'static str
     (edid, record_id, ld)
 }
 
-fn now_unix_seconds() -> u64 {
+/// NPC names, full (`NPC_:00012EB8:FULL:0`) or short (`SHRT`).
+fn is_npc_name(key: &str) -> bool {
+    let mut parts = key.split(':');
+    parts.next() == Some("NPC_") && matches!(parts.nth(1), Some("FULL" | "SHRT"))
+}
+
+pub(crate) fn now_unix_seconds() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map(|d| d.as_secs())
         .unwrap_or(0)
 }
 
+/// `YYYY-MM-DD HH:MM UTC` for display.
+pub fn format_unix_utc(secs: u64) -> String {
+    let time = UtcDateTime::from_unix(secs);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        time.year, time.month, time.day, time.hour, time.minute
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::columns::EntryColumn;
     use xt_core::model::Entry;
 
+    #[test]
+    fn t_search_002_filter_uses_index_and_follows_edits() {
+        let mut state = AppState::new();
+        state.search_options = MatchOptions::LOOSE;
+        let mut entries = (0..BACKGROUND_INDEX_MIN_ROWS)
+            .map(|i| Entry {
+                key: format!("plugin:{i:08x}"),
+                source_text: format!("Line {i}").into(),
+                target_text: Default::default(),
+            })
+            .collect::<Vec<_>>();
+        entries[5].source_text = "Iron Sword".into();
+        state.set_entries_with_history(entries);
+        assert!(state.is_indexing());
+        state.set_query("iron sword");
+        assert_eq!(state.filtered_len(), 1, "scan while indexing");
+
+        assert!(state.update_entry("plugin:00000007", "Iron Sword", "鉄の剣"));
+        for _ in 0..1000 {
+            if state.poll_search_index() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert!(!state.is_indexing());
+        assert_eq!(state.filtered_len(), 2, "edit made during indexing");
+
+        state.set_query("鉄の");
+        assert_eq!(state.filtered_len(), 1);
+        state.undo();
+        assert_eq!(state.filtered_len(), 0);
+        state.redo();
+        assert_eq!(
+            state.filtered_entry(0).map(|e| e.key.as_str()),
+            Some("plugin:00000007")
+        );
+
+        let mut next = state.entries().to_vec();
+        next[9].target_text = "鉄の盾".into();
+        assert_eq!(state.apply_target_updates_with_history("XML適用", next), 1);
+        assert_eq!(state.filtered_len(), 2);
+    }
+
+    #[test]
+    fn t_search_003_debounced_query_and_narrowing() {
+        let mut state = AppState::new();
+        state.search_options = MatchOptions::LOOSE;
+        let entries = ["Iron Sword", "Iron Shield", "Steel Sword", "iron ore"]
+            .iter()
+            .enumerate()
+            .map(|(i, source)| Entry {
+                key: format!("k{i}"),
+                source_text: (*source).into(),
+                target_text: if i == 1 {
+                    "鉄の盾".into()
+                } else {
+                    Default::default()
+                },
+            })
+            .collect::<Vec<_>>();
+        state.set_entries_with_history(entries);
+
+        let t0 = Instant::now();
+        state.edit_query("iron", t0);
+        assert_eq!(state.query_input(), "iron");
+        assert!(!state.flush_query(t0 + Duration::from_millis(50)));
+        assert_eq!(state.filtered_len(), 4, "query not applied while typing");
+        state.edit_query("iron s", t0 + Duration::from_millis(100));
+        assert_eq!(
+            state.query_debounce_remaining(t0 + Duration::from_millis(200)),
+            Some(Duration::from_millis(50))
+        );
+        assert!(state.flush_query(t0 + Duration::from_millis(250)));
+        assert_eq!(state.pane.query(), "iron s");
+        assert_eq!(state.query_debounce_remaining(t0), None);
+        assert_eq!(state.filtered_len(), 2);
+
+        state.set_query("IRON SW");
+        assert_eq!(state.filtered_len(), 1);
+        assert_eq!(state.channel_counts().total, 1);
+        assert_eq!(state.filtered_entry(0).map(|e| e.key.as_str()), Some("k0"));
+
+        state.set_query("iron");
+        assert_eq!(state.filtered_len(), 3, "shorter query searches again");
+        state.set_untranslated_only(true);
+        state.set_query("iron s");
+        assert_eq!(state.filtered_len(), 1);
+        assert_eq!(state.channel_counts().translated, 0);
+    }
+
+    #[test]
+    fn t_search_004_filter_match_options() {
+        let mut state = AppState::new();
+        state.search_options = MatchOptions::EXACT;
+        let entries = [
+            "Iron Sword",
+            "Ｉｒｏｎ Shield",
+            "ｶﾞｰﾄﾞ",
+            "\u{30AB}\u{3099}ード",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, source)| Entry {
+            key: format!("k{i}"),
+            source_text: (*source).into(),
+            target_text: Default::default(),
+        })
+        .collect::<Vec<_>>();
+        state.set_entries_with_history(entries);
+
+        state.set_query("Iron");
+        assert_eq!(state.filtered_len(), 1);
+        state.set_query("ガード");
+        assert_eq!(state.filtered_len(), 1, "NFC always applies");
+
+        state.search_options = MatchOptions::LOOSE;
+        state.invalidate_filtered_cache();
+        state.set_query("iron");
+        assert_eq!(state.filtered_len(), 2, "scan before re-indexing");
+        state.rebuild_search_index();
+        state.set_query("ガード");
+        assert_eq!(state.filtered_len(), 2);
+        state.set_query("ＩＲＯＮ");
+        assert_eq!(state.filtered_len(), 2);
+    }
+
+    #[test]
+    fn t_search_005_filter_and_sort_by_editor_id() {
+        let mut state = AppState::new();
+        state.search_options = MatchOptions::LOOSE;
+        let strings = [
+            ("Iron Sword", Some("WeapIronSword")),
+            ("Steel Sword", Some("WeapSteelSword")),
+            ("Bread", None),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, (text, editor_id))| {
+            ExtractedString::new(
+                *b"WEAP",
+                *b"FULL",
+                0x0001_2EB7 + i as u32,
+                0,
+                text.to_string(),
+                xt_core::formats::esp::StringStorage::Inline,
+            )
+            .with_editor_id(editor_id.map(str::to_string))
+        })
+        .collect::<Vec<_>>();
+        let entries = strings
+            .iter()
+            .map(|string| Entry {
+                key: string.get_unique_key(),
+                source_text: string.text.clone().into(),
+                target_text: Default::default(),
+            })
+            .collect::<Vec<_>>();
+        state.editor_ids = editor_ids(&strings);
+        assert_eq!(state.editor_ids.len(), 2);
+        state.set_entries_with_history(entries);
+
+        state.set_query("weap");
+        assert_eq!(state.filtered_len(), 2);
+        state.set_query("weapsteel");
+        assert_eq!(
+            state.filtered_entry(0).map(|e| e.source_text.to_string()),
+            Some("Steel Sword".to_string())
+        );
+        state.set_query("sword");
+        assert_eq!(state.filtered_len(), 2, "text matches still apply");
+
+        state.set_query("");
+        state.set_sort(Some(EntrySort {
+            column: EntryColumn::Edid,
+            descending: true,
+        }));
+        let key = state.filtered_entry(0).map(|e| e.key.clone()).expect("row");
+        assert_eq!(row_fields(&state.editor_ids, &key, "").0, "WeapSteelSword");
+        let last = state.filtered_entry(2).map(|e| e.key.clone()).expect("row");
+        assert_eq!(row_fields(&state.editor_ids, &last, "").0, "");
+    }
+
+    #[test]
+    fn t_app_032_record_context_lists_sibling_strings() {
+        let mut state = AppState::new();
+        let string = |record: &[u8; 4], sub: &[u8; 4], form_id: u32, index: usize, text: &str| {
+            ExtractedString::new(
+                *record,
+                *sub,
+                form_id,
+                index,
+                text.to_string(),
+                xt_core::formats::esp::StringStorage::Inline,
+            )
+        };
+        let extracted = vec![
+            string(b"WEAP", b"FULL", 0x0001_2EB7, 0, "Iron Sword")
+                .with_editor_id(Some("IronSword".to_string()))
+                .with_record_flags(0x20),
+            string(b"WEAP", b"DESC", 0x0001_2EB7, 1, "Sharp")
+                .with_editor_id(Some("IronSword".to_string()))
+                .with_record_flags(0x20),
+            string(b"NPC_", b"FULL", 0x0001_2EB8, 0, "Guard"),
+        ];
+        let entries = extracted
+            .iter()
+            .map(|string| Entry {
+                key: string.get_unique_key(),
+                source_text: string.text.clone().into(),
+                target_text: Default::default(),
+            })
+            .collect::<Vec<_>>();
+        let desc = entries[1].key.clone();
+        state.hybrid = xt_core::hybrid::group_hybrid_records(&extracted);
+        state.loaded_esp_strings = Some(extracted);
+        state.set_entries_with_history(entries);
+        assert!(state.update_entry(&desc, "Sharp", "鋭い"));
+
+        let record = state.record_context(&desc).expect("record");
+        assert_eq!(record.record_type, "WEAP");
+        assert_eq!(record.form_id, 0x0001_2EB7);
+        assert_eq!(record.editor_id.as_deref(), Some("IronSword"));
+        assert_eq!(record.flags, 0x20);
+        let strings = record
+            .strings
+            .iter()
+            .map(|string| {
+                (
+                    string.subrecord_type.as_str(),
+                    string.index,
+                    string.target_text.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(strings, [("FULL", 0, ""), ("DESC", 1, "鋭い")]);
+
+        let guard = state.record_context("NPC_:00012EB8:FULL:0").expect("npc");
+        assert_eq!(guard.editor_id, None);
+        assert_eq!(guard.strings.len(), 1);
+        assert_eq!(state.record_context("strings:1"), None);
+    }
+
+    #[test]
+    fn t_app_016_sort_and_file_session_restore() {
+        let mut state = AppState::new();
+        let entries = [
+            ("k0", "Iron Sword", "鉄の剣"),
+            ("k1", "Axe", ""),
+            ("k2", "Iron Axe", ""),
+        ]
+        .iter()
+        .map(|(key, source, target)| Entry {
+            key: (*key).to_string(),
+            source_text: (*source).into(),
+            target_text: (*target).into(),
+        })
+        .collect::<Vec<_>>();
+        state.set_entries_with_history(entries);
+        let order = |state: &mut AppState| {
+            (0..state.filtered_len())
+                .map(|at| state.filtered_entry(at).unwrap().key.clone())
+                .collect::<Vec<_>>()
+        };
+
+        state.set_sort(EntrySort::cycle(None, EntryColumn::Source));
+        assert_eq!(order(&mut state), ["k1", "k2", "k0"]);
+        state.set_sort(EntrySort::cycle(state.sort(), EntryColumn::Source));
+        assert_eq!(order(&mut state), ["k0", "k2", "k1"]);
+        state.set_sort(EntrySort::cycle(state.sort(), EntryColumn::Ld));
+        assert_eq!(
+            order(&mut state),
+            ["k1", "k2", "k0"],
+            "ties keep file order"
+        );
+        state.set_query("iron");
+        assert_eq!(order(&mut state), ["k2", "k0"], "narrowing keeps the sort");
+
+        state.select("k2");
+        let saved = state.file_session();
+        state.set_query("");
+        state.set_sort(None);
+        state.select("k0");
+        state.restore_file_session(&saved);
+        assert_eq!(state.query_input(), "iron");
+        assert_eq!(state.selected_key().as_deref(), Some("k2"));
+        assert_eq!(order(&mut state), ["k2", "k0"]);
+
+        state.select("k0");
+        state.restore_file_session(&FileSession {
+            selected_key: Some("gone".to_string()),
+            ..saved
+        });
+        assert_eq!(state.selected_key().as_deref(), Some("k0"));
+    }
+
     #[test]
     fn t_perf_001_list_hot_path_baseline() {
         let mut state = AppState::new();
         let entries = (0..100_000)
             .map(|i| Entry {
                 key: format!("plugin:{i:08x}"),
-                source_text: format!("Source text {i} lorem ipsum dolor sit amet"),
+                source_text: format!("Source text {i} lorem ipsum dolor sit amet").into(),
                 target_text: if i % 5 == 0 {
-                    format!("訳文 {i}")
+                    format!("訳文 {i}").into()
                 } else {
-                    String::new()
+                    Default::default()
                 },
             })
             .collect::<Vec<_>>();
@@ -441,19 +2655,20 @@ mod tests {
         let entries = (0..80_000usize)
             .map(|i| Entry {
                 key: format!("plugin:{i:08x}"),
-                source_text: format!("Source text {i} lorem ipsum dolor sit amet"),
+                source_text: format!("Source text {i} lorem ipsum dolor sit amet").into(),
                 target_text: if i % 3 == 0 {
-                    format!("訳文 {i}")
+                    format!("訳文 {i}").into()
                 } else {
-                    String::new()
+                    Default::default()
                 },
             })
             .collect::<Vec<_>>();
 
+        let editor_ids = EditorIdMap::new();
         let mut concat_checksum = 0usize;
         let concat_start = std::time::Instant::now();
         for entry in &entries {
-            let (edid, record_id, ld) = row_fields(&entry.key, &entry.target_text);
+            let (edid, record_id, ld) = row_fields(&editor_ids, &entry.key, &entry.target_text);
             let row = format!(
                 "{} | {} | {} | {} | {}",
                 edid, record_id, entry.source_text, entry.target_text, ld
@@ -465,7 +2680,7 @@ mod tests {
         let mut cells_checksum = 0usize;
         let cells_start = std::time::Instant::now();
         for entry in &entries {
-            let (edid, record_id, ld) = row_fields(&entry.key, &entry.target_text);
+            let (edid, record_id, ld) = row_fields(&editor_ids, &entry.key, &entry.target_text);
             cells_checksum ^= std::hint::black_box(edid.len());
             cells_checksum ^= std::hint::black_box(record_id.len());
             cells_checksum ^= std::hint::black_box(entry.source_text.len());
@@ -479,4 +2694,75 @@ mod tests {
             concat_elapsed, cells_elapsed, concat_checksum, cells_checksum
         );
     }
+
+    #[test]
+    fn t_app_041_rewrite_rules_applied_on_demand_and_after_auto() {
+        use xt_core::candidates::Provenance;
+        use xt_core::rewrite::RewriteRule;
+
+        let row = |key: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: format!("Source {key}").into(),
+            target_text: target.into(),
+        };
+        let mut state = AppState::new();
+        state.rewrite_prefs = RewritePrefs::default();
+        state.rewriter = Rewriter::default();
+        state.set_entries_with_history(vec![
+            row("k0", "鉄の剣 (片手)"),
+            row("k1", "盾 。"),
+            row("k2", ""),
+        ]);
+        assert_eq!(state.apply_rewrite_rules(false), None);
+
+        let rules = vec![
+            RewriteRule {
+                name: "括弧".to_string(),
+                enabled: true,
+                pattern: r"\((.+?)\)".to_string(),
+                replacement: "（$1）".to_string(),
+            },
+            RewriteRule {
+                name: "句点".to_string(),
+                enabled: true,
+                pattern: r"\s+。".to_string(),
+                replacement: "。".to_string(),
+            },
+        ];
+        state.rewriter = Rewriter::new(&rules).expect("rules");
+        state.rewrite_prefs = RewritePrefs {
+            rules,
+            after_auto: false,
+        };
+        state.select("k1");
+        assert_eq!(state.apply_rewrite_rules(true), Some(1));
+        assert_eq!(state.entries()[1].target_text, "盾。");
+        assert_eq!(state.entries()[0].target_text, "鉄の剣 (片手)");
+        assert_eq!(state.apply_rewrite_rules(false), Some(1));
+        assert_eq!(state.entries()[0].target_text, "鉄の剣 （片手）");
+        assert!(state.edit_meta.contains_key("k0"));
+        state.undo();
+        assert_eq!(state.entries()[0].target_text, "鉄の剣 (片手)");
+
+        let mut next = state.entries().to_vec();
+        next[2].target_text = "兜 (重装)".into();
+        let mut proposals = vec![(
+            "k2".to_string(),
+            Proposal {
+                index: 2,
+                provenance: Provenance::MachineTranslation,
+                target: "兜 (重装)".into(),
+                applied: true,
+            },
+        )];
+        assert_eq!(state.rewrite_auto_targets(&mut next, &mut proposals), 0);
+        state.rewrite_prefs.after_auto = true;
+        assert_eq!(state.rewrite_auto_targets(&mut next, &mut proposals), 1);
+        assert_eq!(next[2].target_text, "兜 （重装）");
+        assert_eq!(
+            next[0].target_text, "鉄の剣 (片手)",
+            "rows auto-translation left alone"
+        );
+        assert_eq!(proposals[0].1.target, "兜 （重装）");
+    }
 }
@@ -0,0 +1,305 @@
+//! Translation progress for the 統計 tab: row, word and character counts by
+//! record type, channel and status, plus this session's throughput.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use xt_core::diff::PluginDiffKind;
+use xt_core::model::Entry;
+use xt_core::validation::ValidationIssue;
+
+use crate::state::{channel_of, AppState};
+
+/// Counts over a group of rows. Words are whitespace-separated tokens of the
+/// source text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub rows: usize,
+    pub translated: usize,
+    pub source_words: usize,
+    pub translated_source_words: usize,
+    pub source_chars: usize,
+    pub target_chars: usize,
+}
+
+impl Tally {
+    fn add(&mut self, entry: &Entry) {
+        let words = entry.source_text.split_whitespace().count();
+        self.rows += 1;
+        self.source_words += words;
+        self.source_chars += entry.source_text.chars().count();
+        if !entry.target_text.is_empty() {
+            self.translated += 1;
+            self.translated_source_words += words;
+            self.target_chars += entry.target_text.chars().count();
+        }
+    }
+
+    pub fn untranslated(&self) -> usize {
+        self.rows - self.translated
+    }
+
+    pub fn remaining_words(&self) -> usize {
+        self.source_words - self.translated_source_words
+    }
+
+    /// Translated share in percent.
+    pub fn percent(&self) -> f32 {
+        if self.rows == 0 {
+            0.0
+        } else {
+            self.translated as f32 * 100.0 / self.rows as f32
+        }
+    }
+}
+
+/// Row status groups; a row may be in several.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StatusGroup {
+    Translated,
+    Untranslated,
+    Validated,
+    Issues,
+    SourceChanged,
+}
+
+impl StatusGroup {
+    pub fn label(self) -> &'static str {
+        match self {
+            StatusGroup::Translated => "訳済み",
+            StatusGroup::Untranslated => "未訳",
+            StatusGroup::Validated => "検証済み",
+            StatusGroup::Issues => "検証結果あり",
+            StatusGroup::SourceChanged => "原文変更",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TranslationStats {
+    pub total: Tally,
+    /// Keyed by record type (`WEAP`, …); `-` for keys without one.
+    pub by_record: BTreeMap<String, Tally>,
+    pub by_channel: BTreeMap<&'static str, Tally>,
+    pub by_status: BTreeMap<StatusGroup, Tally>,
+    /// Rows edited since the session started.
+    pub session: Tally,
+    pub session_secs: u64,
+}
+
+impl TranslationStats {
+    /// Stats over all entries; the session runs from `session_start` to
+    /// `now` (unix seconds) and counts rows whose last edit falls in it.
+    pub fn collect(state: &AppState, session_start: u64, now: u64) -> Self {
+        let issue_keys = issue_keys(&state.validation_issues);
+        let changed_keys = state
+            .plugin_diff
+            .iter()
+            .flat_map(|diff| diff.rows.iter())
+            .filter(|row| row.kind == PluginDiffKind::SourceChanged)
+            .map(|row| row.key.as_str())
+            .collect::<BTreeSet<_>>();
+
+        let mut stats = Self {
+            session_secs: now.saturating_sub(session_start),
+            ..Self::default()
+        };
+        for entry in state.entries() {
+            stats.add(entry);
+            if state.validated_keys.contains(&entry.key) {
+                stats.add_status(StatusGroup::Validated, entry);
+            }
+            if issue_keys.contains(entry.key.as_str()) {
+                stats.add_status(StatusGroup::Issues, entry);
+            }
+            if changed_keys.contains(entry.key.as_str()) {
+                stats.add_status(StatusGroup::SourceChanged, entry);
+            }
+            if state
+                .edit_meta
+                .get(&entry.key)
+                .is_some_and(|meta| meta.modified_at >= session_start)
+            {
+                stats.session.add(entry);
+            }
+        }
+        stats
+    }
+
+    /// Stats of `entries` outside the app, e.g. for the batch tool: no
+    /// session, validated rows or source changes; rows with one of `issues`
+    /// count as [`StatusGroup::Issues`].
+    pub fn of_entries(entries: &[Entry], issues: &[ValidationIssue]) -> Self {
+        let issue_keys = issue_keys(issues);
+        let mut stats = Self::default();
+        for entry in entries {
+            stats.add(entry);
+            if issue_keys.contains(entry.key.as_str()) {
+                stats.add_status(StatusGroup::Issues, entry);
+            }
+        }
+        stats
+    }
+
+    /// Counts `entry` in the total, its record type, channel and
+    /// translated/untranslated group.
+    fn add(&mut self, entry: &Entry) {
+        self.total.add(entry);
+        self.by_record
+            .entry(record_type(&entry.key).to_string())
+            .or_default()
+            .add(entry);
+        self.by_channel
+            .entry(channel_of(&entry.key))
+            .or_default()
+            .add(entry);
+        let status = if entry.target_text.is_empty() {
+            StatusGroup::Untranslated
+        } else {
+            StatusGroup::Translated
+        };
+        self.add_status(status, entry);
+    }
+
+    fn add_status(&mut self, status: StatusGroup, entry: &Entry) {
+        self.by_status.entry(status).or_default().add(entry);
+    }
+
+    /// Source words of rows edited per hour this session.
+    pub fn words_per_hour(&self) -> Option<f64> {
+        (self.session_secs > 0 && self.session.source_words > 0)
+            .then(|| self.session.source_words as f64 * 3600.0 / self.session_secs as f64)
+    }
+
+    pub fn rows_per_hour(&self) -> Option<f64> {
+        (self.session_secs > 0 && self.session.rows > 0)
+            .then(|| self.session.rows as f64 * 3600.0 / self.session_secs as f64)
+    }
+
+    /// Hours to translate the remaining source words at this session's pace.
+    pub fn remaining_hours(&self) -> Option<f64> {
+        self.words_per_hour()
+            .map(|pace| self.total.remaining_words() as f64 / pace)
+    }
+
+    /// One line per group: `group,name,rows,translated,untranslated,…`.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from(
+            "group,name,rows,translated,untranslated,source_words,translated_source_words,source_chars,target_chars\n",
+        );
+        let mut line = |group: &str, name: &str, tally: &Tally| {
+            out.push_str(&format!(
+                "{group},{},{},{},{},{},{},{},{}\n",
+                csv_field(name),
+                tally.rows,
+                tally.translated,
+                tally.untranslated(),
+                tally.source_words,
+                tally.translated_source_words,
+                tally.source_chars,
+                tally.target_chars
+            ));
+        };
+        line("total", "all", &self.total);
+        for (record, tally) in &self.by_record {
+            line("record", record, tally);
+        }
+        for (channel, tally) in &self.by_channel {
+            line("channel", channel, tally);
+        }
+        for (status, tally) in &self.by_status {
+            line("status", status.label(), tally);
+        }
+        line("session", "edited", &self.session);
+        out
+    }
+}
+
+fn issue_keys(issues: &[ValidationIssue]) -> BTreeSet<&str> {
+    issues
+        .iter()
+        .map(|issue| issue.entry_key.as_str())
+        .collect()
+}
+
+/// `WEAP` for plugin keys (`WEAP:00012EB7:FULL:0`), `-` otherwise.
+pub fn record_type(key: &str) -> &str {
+    let mut parts = key.split(':');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(record), Some(_), Some(_), Some(_)) if record.len() == 4 => record,
+        _ => "-",
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xt_core::model::EditMeta;
+
+    fn entry(key: &str, source: &str, target: &str) -> Entry {
+        Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        }
+    }
+
+    #[test]
+    fn t_stats_001_counts_by_group_and_session_pace() {
+        let mut state = AppState::new();
+        state.set_entries_with_history(vec![
+            entry("WEAP:00000001:FULL:0", "Iron Sword", "鉄の剣"),
+            entry("WEAP:00000002:FULL:0", "Steel Sword", ""),
+            entry("BOOK:00000003:DESC:0", "A long tale, told twice", ""),
+            entry("strings:7", "Hello", "こんにちは"),
+        ]);
+        state.validated_keys.insert("strings:7".to_string());
+        state.edit_meta.insert(
+            "WEAP:00000001:FULL:0".to_string(),
+            EditMeta {
+                author: String::new(),
+                modified_at: 1_000,
+            },
+        );
+        state.edit_meta.insert(
+            "strings:7".to_string(),
+            EditMeta {
+                author: String::new(),
+                modified_at: 10,
+            },
+        );
+
+        let stats = TranslationStats::collect(&state, 100, 100 + 1800);
+        assert_eq!(stats.total.rows, 4);
+        assert_eq!(stats.total.translated, 2);
+        assert_eq!(stats.total.source_words, 2 + 2 + 5 + 1);
+        assert_eq!(stats.total.remaining_words(), 7);
+        assert_eq!(stats.total.target_chars, 3 + 5);
+        assert_eq!(stats.by_record["WEAP"].rows, 2);
+        assert_eq!(stats.by_record["BOOK"].untranslated(), 1);
+        assert_eq!(stats.by_record["-"].rows, 1);
+        assert_eq!(stats.by_status[&StatusGroup::Validated].rows, 1);
+        assert_eq!(stats.by_status[&StatusGroup::Untranslated].rows, 2);
+
+        assert_eq!(
+            stats.session.rows, 1,
+            "edits before the session are not counted"
+        );
+        assert_eq!(stats.words_per_hour(), Some(4.0));
+        assert_eq!(stats.remaining_hours(), Some(7.0 / 4.0));
+
+        let csv = stats.to_csv();
+        assert!(csv.starts_with("group,name,rows,"));
+        assert!(csv.contains("\ntotal,all,4,2,2,10,3,"));
+        assert!(csv.contains("\nrecord,WEAP,2,1,1,4,2,"));
+        assert!(csv.contains("\nsession,edited,1,1,0,2,2,"));
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+}
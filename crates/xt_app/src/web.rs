@@ -0,0 +1,306 @@
+//! Browser build: one plugin picked with the browser's file picker (or
+//! dropped on the page), edited in the entry list and downloaded back. There
+//! is no `Data` folder to look in, so a localized plugin's Strings files are
+//! picked together with it. The default egui fonts have no CJK glyphs; a font
+//! file can be picked to show Japanese text.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use eframe::egui::{self, FontData, FontDefinitions, FontFamily};
+use eframe::wasm_bindgen::{JsCast, JsValue};
+use xt_core::import_export::ApplyProfile;
+
+use crate::picked::{PickedFile, PickedPlugin};
+
+/// `id` of the `<canvas>` in `index.html`.
+pub const CANVAS_ID: &str = "xt_app_canvas";
+
+/// Starts the app on [`CANVAS_ID`].
+pub fn start() {
+    wasm_bindgen_futures::spawn_local(async {
+        let started = eframe::WebRunner::new()
+            .start(
+                CANVAS_ID,
+                eframe::WebOptions::default(),
+                Box::new(|_cc| Ok(Box::new(WebApp::default()))),
+            )
+            .await;
+        if let Err(err) = started {
+            web_sys::console::error_2(&"xtrans-rs failed to start".into(), &err);
+        }
+    });
+}
+
+/// A plugin and its Strings files.
+const PICK_PLUGIN: &[&str] = &["esp", "esm", "esl", "strings", "dlstrings", "ilstrings"];
+
+/// What a file picker came back with.
+enum Picked {
+    Plugin(Vec<PickedFile>),
+    Xml(PickedFile),
+    Font(PickedFile),
+}
+
+pub struct WebApp {
+    plugin: Option<PickedPlugin>,
+    status: String,
+    filter: String,
+    apply_profile: ApplyProfile,
+    picked_tx: Sender<Picked>,
+    picked_rx: Receiver<Picked>,
+}
+
+impl Default for WebApp {
+    fn default() -> Self {
+        let (picked_tx, picked_rx) = mpsc::channel();
+        Self {
+            plugin: None,
+            status: "Pick a plugin and its Strings files, or drop them here.".to_string(),
+            filter: String::new(),
+            apply_profile: ApplyProfile::default(),
+            picked_tx,
+            picked_rx,
+        }
+    }
+}
+
+impl WebApp {
+    /// Opens the browser's file picker for `extensions`; the files arrive
+    /// through `picked_rx` on a later frame.
+    fn pick(
+        &self,
+        ctx: &egui::Context,
+        extensions: &[&str],
+        what: fn(Vec<PickedFile>) -> Option<Picked>,
+    ) {
+        let dialog = rfd::AsyncFileDialog::new().add_filter("", extensions);
+        let sender = self.picked_tx.clone();
+        let ctx = ctx.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let Some(handles) = dialog.pick_files().await else {
+                return;
+            };
+            let mut files = Vec::with_capacity(handles.len());
+            for handle in handles {
+                files.push(PickedFile {
+                    name: handle.file_name(),
+                    bytes: handle.read().await,
+                });
+            }
+            if let Some(picked) = what(files) {
+                let _ = sender.send(picked);
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    fn receive(&mut self, ctx: &egui::Context) {
+        let dropped = ctx.input_mut(|input| std::mem::take(&mut input.raw.dropped_files));
+        let dropped = dropped
+            .into_iter()
+            .filter_map(|file| {
+                Some(PickedFile {
+                    name: file.name,
+                    bytes: file.bytes?.to_vec(),
+                })
+            })
+            .collect::<Vec<_>>();
+        if !dropped.is_empty() {
+            self.open(dropped);
+        }
+        while let Ok(picked) = self.picked_rx.try_recv() {
+            match picked {
+                Picked::Plugin(files) => self.open(files),
+                Picked::Xml(file) => self.import_xml(&file),
+                Picked::Font(file) => {
+                    set_font(ctx, file.bytes);
+                    self.status = format!("Font: {}", file.name);
+                }
+            }
+        }
+    }
+
+    fn open(&mut self, files: Vec<PickedFile>) {
+        match PickedPlugin::open(files) {
+            Ok(plugin) => {
+                self.status = format!("{}: {} strings", plugin.name, plugin.entries.len());
+                self.plugin = Some(plugin);
+            }
+            Err(err) => self.status = err,
+        }
+    }
+
+    fn import_xml(&mut self, file: &PickedFile) {
+        let Some(plugin) = &mut self.plugin else {
+            self.status = "Open a plugin before its translation XML.".to_string();
+            return;
+        };
+        let xml = String::from_utf8_lossy(&file.bytes);
+        self.status = match plugin.import_xml(&xml, self.apply_profile) {
+            Ok(stats) => format!(
+                "{}: updated={} unchanged={} missing={}",
+                file.name, stats.updated, stats.unchanged, stats.missing
+            ),
+            Err(err) => format!("{}: {err}", file.name),
+        };
+    }
+
+    fn download(&mut self, files: Result<Vec<PickedFile>, String>) {
+        let result = files.and_then(|files| {
+            for file in &files {
+                download(file).map_err(|err| format!("{}: {err:?}", file.name))?;
+            }
+            Ok(files.len())
+        });
+        self.status = match result {
+            Ok(count) => format!("Downloaded {count} file(s)."),
+            Err(err) => err,
+        };
+    }
+
+    fn toolbar(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        let loaded = self.plugin.is_some();
+        let mut save = None;
+        ui.horizontal_wrapped(|ui| {
+            if ui.button("Open plugin + Strings…").clicked() {
+                self.pick(ctx, PICK_PLUGIN, |files| Some(Picked::Plugin(files)));
+            }
+            if ui
+                .add_enabled(loaded, egui::Button::new("Import XML…"))
+                .clicked()
+            {
+                self.pick(ctx, &["xml"], |files| {
+                    files.into_iter().next().map(Picked::Xml)
+                });
+            }
+            egui::ComboBox::from_id_source("web_apply_profile")
+                .selected_text(self.apply_profile.as_str())
+                .show_ui(ui, |ui| {
+                    for candidate in ApplyProfile::ALL {
+                        ui.selectable_value(&mut self.apply_profile, candidate, candidate.as_str());
+                    }
+                });
+            ui.separator();
+            if ui
+                .add_enabled(loaded, egui::Button::new("Download XML"))
+                .clicked()
+            {
+                save = self
+                    .plugin
+                    .as_ref()
+                    .map(|plugin| Ok(vec![plugin.export_xml()]));
+            }
+            if ui
+                .add_enabled(loaded, egui::Button::new("Download plugin"))
+                .clicked()
+            {
+                save = self.plugin.as_ref().map(PickedPlugin::save);
+            }
+            ui.separator();
+            if ui.button("Load font…").clicked() {
+                self.pick(ctx, &["ttf", "otf", "ttc"], |files| {
+                    files.into_iter().next().map(Picked::Font)
+                });
+            }
+        });
+        if let Some(files) = save {
+            self.download(files);
+        }
+    }
+
+    fn entry_list(&mut self, ui: &mut egui::Ui) {
+        let Some(plugin) = &mut self.plugin else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.label("Filter");
+            ui.text_edit_singleline(&mut self.filter);
+        });
+        let filter = self.filter.to_lowercase();
+        let rows = plugin
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                filter.is_empty()
+                    || [
+                        entry.key.as_str(),
+                        entry.source_text.as_str(),
+                        entry.target_text.as_str(),
+                    ]
+                    .iter()
+                    .any(|text| text.to_lowercase().contains(&filter))
+            })
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        let row_height = ui.spacing().interact_size.y;
+        egui::ScrollArea::vertical().auto_shrink(false).show_rows(
+            ui,
+            row_height,
+            rows.len(),
+            |ui, range| {
+                let column = (ui.available_width() - 220.0).max(200.0) / 2.0;
+                for &index in &rows[range] {
+                    let entry = &mut plugin.entries[index];
+                    ui.horizontal(|ui| {
+                        ui.add_sized(
+                            [200.0, row_height],
+                            egui::Label::new(entry.key.as_str()).truncate(),
+                        );
+                        ui.add_sized(
+                            [column, row_height],
+                            egui::Label::new(entry.source_text.as_str()).truncate(),
+                        );
+                        let mut target = entry.target_text.to_string();
+                        if ui
+                            .add(egui::TextEdit::singleline(&mut target).desired_width(column))
+                            .changed()
+                        {
+                            entry.target_text = target.into();
+                        }
+                    });
+                }
+            },
+        );
+    }
+}
+
+impl eframe::App for WebApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.receive(ctx);
+        egui::TopBottomPanel::top("web_toolbar").show(ctx, |ui| self.toolbar(ctx, ui));
+        egui::TopBottomPanel::bottom("web_status").show(ctx, |ui| ui.label(&self.status));
+        egui::CentralPanel::default().show(ctx, |ui| self.entry_list(ui));
+    }
+}
+
+/// Uses `bytes` (a TrueType or OpenType font) ahead of the default fonts.
+fn set_font(ctx: &egui::Context, bytes: Vec<u8>) {
+    let mut fonts = FontDefinitions::default();
+    fonts
+        .font_data
+        .insert("xtrans-picked".to_string(), FontData::from_owned(bytes));
+    for family in [FontFamily::Proportional, FontFamily::Monospace] {
+        if let Some(fonts) = fonts.families.get_mut(&family) {
+            fonts.insert(0, "xtrans-picked".to_string());
+        }
+    }
+    ctx.set_fonts(fonts);
+}
+
+/// Hands `file` to the browser as a download.
+fn download(file: &PickedFile) -> Result<(), JsValue> {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(file.bytes.as_slice()));
+    let blob = web_sys::Blob::new_with_u8_array_sequence(&parts)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or("no document")?;
+    let link = document
+        .create_element("a")?
+        .dyn_into::<web_sys::HtmlAnchorElement>()?;
+    link.set_href(&url);
+    link.set_download(&file.name);
+    link.click();
+    web_sys::Url::revoke_object_url(&url)
+}
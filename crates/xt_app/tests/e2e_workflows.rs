@@ -15,18 +15,16 @@ fn e2e_io_str_001_load_edit_save_round_trip() {
 
     write_strings_file(
         &input,
-        StringsFile {
-            entries: vec![
-                StringsEntry {
-                    id: 1,
-                    text: "Iron Sword".to_string(),
-                },
-                StringsEntry {
-                    id: 2,
-                    text: "Steel Sword".to_string(),
-                },
-            ],
-        },
+        StringsFile::new(vec![
+            StringsEntry {
+                id: 1,
+                text: "Iron Sword".to_string(),
+            },
+            StringsEntry {
+                id: 2,
+                text: "Steel Sword".to_string(),
+            },
+        ]),
     );
 
     let mut driver = AppDriver::new();
@@ -57,12 +55,10 @@ fn e2e_xml_001_apply_from_editor_updates_target() {
 
     write_strings_file(
         &input,
-        StringsFile {
-            entries: vec![StringsEntry {
-                id: 7,
-                text: "Iron Armor".to_string(),
-            }],
-        },
+        StringsFile::new(vec![StringsEntry {
+            id: 7,
+            text: "Iron Armor".to_string(),
+        }]),
     );
 
     let mut driver = AppDriver::new();
@@ -72,8 +68,8 @@ fn e2e_xml_001_apply_from_editor_updates_target() {
 
     let xml = export_entries(&[Entry {
         key: "strings:7".to_string(),
-        source_text: "Iron Armor".to_string(),
-        target_text: "鉄の鎧".to_string(),
+        source_text: "Iron Armor".into(),
+        target_text: "鉄の鎧".into(),
     }]);
 
     driver
@@ -106,12 +102,7 @@ fn e2e_xml_002_apply_large_batch_updates_all_targets() {
             text: format!("Source Text {id}"),
         })
         .collect::<Vec<_>>();
-    write_strings_file(
-        &input,
-        StringsFile {
-            entries: source_entries,
-        },
-    );
+    write_strings_file(&input, StringsFile::new(source_entries));
 
     let mut driver = AppDriver::new();
     driver
@@ -121,8 +112,8 @@ fn e2e_xml_002_apply_large_batch_updates_all_targets() {
     let xml_entries = (1..=total)
         .map(|id| Entry {
             key: format!("strings:{id}"),
-            source_text: format!("Source Text {id}"),
-            target_text: format!("訳文{id}"),
+            source_text: format!("Source Text {id}").into(),
+            target_text: format!("訳文{id}").into(),
         })
         .collect::<Vec<_>>();
     let xml = export_entries(&xml_entries);
@@ -155,32 +146,26 @@ fn e2e_dict_001_build_and_quick_auto_selection() {
 
     write_strings_file(
         &dict_dir.join("skyrim_english.strings"),
-        StringsFile {
-            entries: vec![StringsEntry {
-                id: 9,
-                text: "Steel Shield".to_string(),
-            }],
-        },
+        StringsFile::new(vec![StringsEntry {
+            id: 9,
+            text: "Steel Shield".to_string(),
+        }]),
     );
     write_strings_file(
         &dict_dir.join("skyrim_japanese.strings"),
-        StringsFile {
-            entries: vec![StringsEntry {
-                id: 9,
-                text: "鋼鉄の盾".to_string(),
-            }],
-        },
+        StringsFile::new(vec![StringsEntry {
+            id: 9,
+            text: "鋼鉄の盾".to_string(),
+        }]),
     );
 
     let input = root.join("runtime_english.strings");
     write_strings_file(
         &input,
-        StringsFile {
-            entries: vec![StringsEntry {
-                id: 9,
-                text: "Steel Shield".to_string(),
-            }],
-        },
+        StringsFile::new(vec![StringsEntry {
+            id: 9,
+            text: "Steel Shield".to_string(),
+        }]),
     );
 
     let mut driver = AppDriver::new();
@@ -210,6 +195,75 @@ fn e2e_dict_001_build_and_quick_auto_selection() {
     assert_eq!(target, "鋼鉄の盾");
 }
 
+#[test]
+fn e2e_diff_001_carry_forward_after_plugin_update() {
+    let root = test_temp_dir("plugin_diff");
+    let old_path = root.join("mod_v1.xtplugin");
+    let new_path = root.join("mod_v2.xtplugin");
+    std::fs::write(
+        &old_path,
+        "XTPLUGIN1\n1|WEAP|Iron Sword\n2|BOOK|Old Book\n3|MISC|Gone\n",
+    )
+    .expect("write old");
+    std::fs::write(
+        &new_path,
+        "XTPLUGIN1\n1|WEAP|Iron Sword\n2|BOOK|Old Book, 2nd Ed.\n4|MISC|Brand new\n",
+    )
+    .expect("write new");
+
+    let mut driver = AppDriver::new();
+    driver
+        .dispatch(AppAction::LoadPlugin(old_path))
+        .expect("load old");
+    let xml = export_entries(&[
+        Entry {
+            key: "plugin:1".to_string(),
+            source_text: "Iron Sword".into(),
+            target_text: "鉄の剣".into(),
+        },
+        Entry {
+            key: "plugin:2".to_string(),
+            source_text: "Old Book".into(),
+            target_text: "古い本".into(),
+        },
+    ]);
+    driver
+        .dispatch(AppAction::SetXmlText(xml))
+        .expect("set xml text");
+    driver
+        .dispatch(AppAction::ApplyXmlFromEditor)
+        .expect("apply xml");
+
+    driver
+        .dispatch(AppAction::CompareWithNewPlugin(new_path))
+        .expect("compare");
+    let targets = driver
+        .state()
+        .entries()
+        .iter()
+        .map(|entry| (entry.key.as_str(), entry.target_text.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        targets,
+        vec![("plugin:1", "鉄の剣"), ("plugin:2", ""), ("plugin:4", "")]
+    );
+    let summary = driver.state().plugin_diff.as_ref().expect("diff").summary;
+    assert_eq!(
+        (
+            summary.carried,
+            summary.source_changed,
+            summary.added,
+            summary.removed
+        ),
+        (1, 1, 1, 1)
+    );
+
+    driver
+        .dispatch(AppAction::UsePreviousTranslation("plugin:2".to_string()))
+        .expect("use previous");
+    assert_eq!(driver.state().entries()[1].target_text, "古い本");
+}
+
 fn write_strings_file(path: &Path, strings: StringsFile) {
     let bytes = write_strings(&strings).expect("encode strings");
     std::fs::write(path, bytes).expect("write strings");
@@ -0,0 +1,91 @@
+//! XML apply and dictionary hot paths: `cargo bench -p xt_core`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use xt_core::dictionary::TranslationDictionary;
+use xt_core::import_export::{apply_xml_default, export_entries, import_entries};
+use xt_core::model::Entry;
+use xt_core::search::MatchOptions;
+
+const ENTRIES: usize = 50_000;
+
+/// Untranslated rows; every tenth source repeats an earlier one.
+fn entries() -> Vec<Entry> {
+    (0..ENTRIES)
+        .map(|i| {
+            let text = if i % 10 == 9 { i - 9 } else { i };
+            Entry {
+                key: format!("BOOK:{:08X}:FULL:0", 0x0100_0000 + i),
+                source_text: format!("Book title {text}, lorem ipsum").into(),
+                target_text: Default::default(),
+            }
+        })
+        .collect()
+}
+
+/// Translations of every other row, half of them under another key so only
+/// their source text matches.
+fn translated(entries: &[Entry]) -> Vec<Entry> {
+    entries
+        .iter()
+        .step_by(2)
+        .enumerate()
+        .map(|(i, entry)| Entry {
+            key: if i % 2 == 0 {
+                entry.key.clone()
+            } else {
+                format!("{}:moved", entry.key)
+            },
+            source_text: entry.source_text.clone(),
+            target_text: format!("訳 {}", entry.source_text).into(),
+        })
+        .collect()
+}
+
+fn bench_xml(c: &mut Criterion) {
+    let current = entries();
+    let imported = translated(&current);
+    let xml = export_entries(&imported);
+
+    let mut group = c.benchmark_group("xml");
+    group.throughput(Throughput::Elements(current.len() as u64));
+    group.bench_function("import_entries", |b| {
+        b.iter(|| import_entries(black_box(&xml)).expect("import"))
+    });
+    group.bench_function("apply_xml_default", |b| {
+        b.iter(|| apply_xml_default(black_box(&current), black_box(&imported)))
+    });
+    group.finish();
+}
+
+fn bench_dictionary(c: &mut Criterion) {
+    let current = entries();
+    let pairs = translated(&current);
+    let dict = TranslationDictionary::build_from_entries(&pairs);
+
+    let mut group = c.benchmark_group("dictionary");
+    group.throughput(Throughput::Elements(current.len() as u64));
+    group.bench_function("build_from_entries", |b| {
+        b.iter(|| TranslationDictionary::build_from_entries(black_box(&pairs)))
+    });
+    group.bench_function("apply_quick/exact", |b| {
+        b.iter(|| dict.apply_quick_with(black_box(&current), &[], true, MatchOptions::EXACT))
+    });
+    group.bench_function("apply_quick/loose", |b| {
+        b.iter(|| dict.apply_quick_with(black_box(&current), &[], true, MatchOptions::LOOSE))
+    });
+    group.bench_function("lookup_targets/exact", |b| {
+        b.iter(|| {
+            current
+                .iter()
+                .filter(|entry| {
+                    dict.lookup_targets(black_box(&entry.source_text), MatchOptions::EXACT)
+                        .is_some()
+                })
+                .count()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_xml, bench_dictionary);
+criterion_main!(benches);
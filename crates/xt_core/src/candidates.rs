@@ -0,0 +1,314 @@
+use crate::model::{Entry, SharedText};
+use std::collections::HashMap;
+
+/// Where a proposed target came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CandidateSource {
+    Manual,
+    Xml,
+    /// A loaded reference translation, e.g. the game's official one.
+    Reference,
+    Dictionary,
+    MachineTranslation,
+    /// Rule-based katakana reading of a proper noun.
+    Transliteration,
+}
+
+impl CandidateSource {
+    /// Default resolution order: manual edits win over XML, XML over the
+    /// reference translation, that over the dictionary, the dictionary over
+    /// machine translation and that over katakana readings.
+    pub const PRIORITY: [CandidateSource; 6] = [
+        CandidateSource::Manual,
+        CandidateSource::Xml,
+        CandidateSource::Reference,
+        CandidateSource::Dictionary,
+        CandidateSource::MachineTranslation,
+        CandidateSource::Transliteration,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CandidateSource::Manual => "manual",
+            CandidateSource::Xml => "xml",
+            CandidateSource::Reference => "ref",
+            CandidateSource::Dictionary => "dict",
+            CandidateSource::MachineTranslation => "mt",
+            CandidateSource::Transliteration => "kana",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CandidateSource::Manual => "手動",
+            CandidateSource::Xml => "XML",
+            CandidateSource::Reference => "参照訳",
+            CandidateSource::Dictionary => "辞書",
+            CandidateSource::MachineTranslation => "機械翻訳",
+            CandidateSource::Transliteration => "カタカナ化",
+        }
+    }
+}
+
+/// How an entry's target was produced. Entries without one keep the target
+/// they were loaded with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Provenance {
+    Manual,
+    /// Imported target of the row with the same key.
+    XmlKey,
+    /// Imported target of the only row with the same source text.
+    XmlSource,
+    /// Target of the same source text in the reference translation.
+    Reference,
+    /// Dictionary target; `score` is the similarity of the dictionary's
+    /// source to the row's, in percent (100 for an exact hit). An
+    /// `ambiguous` source has other targets too, so the row needs review.
+    Dictionary {
+        score: u8,
+        ambiguous: bool,
+    },
+    MachineTranslation,
+    Transliteration,
+}
+
+impl Provenance {
+    pub fn source(self) -> CandidateSource {
+        match self {
+            Provenance::Manual => CandidateSource::Manual,
+            Provenance::XmlKey | Provenance::XmlSource => CandidateSource::Xml,
+            Provenance::Reference => CandidateSource::Reference,
+            Provenance::Dictionary { .. } => CandidateSource::Dictionary,
+            Provenance::MachineTranslation => CandidateSource::MachineTranslation,
+            Provenance::Transliteration => CandidateSource::Transliteration,
+        }
+    }
+
+    /// A dictionary target picked among several.
+    pub fn is_suspect(self) -> bool {
+        matches!(
+            self,
+            Provenance::Dictionary {
+                ambiguous: true,
+                ..
+            }
+        )
+    }
+
+    /// A target nobody chose: dictionary and machine translations, katakana
+    /// readings, and XML targets matched by source text only. Such rows go
+    /// to the review queue (要確認) until approved.
+    pub fn needs_review(self) -> bool {
+        matches!(
+            self,
+            Provenance::XmlSource
+                | Provenance::Dictionary { .. }
+                | Provenance::MachineTranslation
+                | Provenance::Transliteration
+        )
+    }
+
+    pub fn label(self) -> String {
+        let label = match self {
+            Provenance::XmlKey => "XML キー一致".to_string(),
+            Provenance::XmlSource => "XML 原文一致".to_string(),
+            Provenance::Dictionary { score: 100, .. } => "辞書 完全一致".to_string(),
+            Provenance::Dictionary { score, .. } => format!("辞書 {score}%"),
+            other => other.source().label().to_string(),
+        };
+        if self.is_suspect() {
+            format!("{label}（要確認）")
+        } else {
+            label
+        }
+    }
+}
+
+/// Provenance of the current target by entry key.
+pub type ProvenanceMap = HashMap<String, Provenance>;
+
+/// A target that XML or the dictionary matched to a row, by index into the
+/// entries it was given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proposal {
+    pub index: usize,
+    pub provenance: Provenance,
+    pub target: SharedText,
+    /// Whether the row's target was replaced with it.
+    pub applied: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub provenance: Provenance,
+    pub target: SharedText,
+}
+
+impl Candidate {
+    pub fn source(&self) -> CandidateSource {
+        self.provenance.source()
+    }
+}
+
+/// Targets proposed for each entry key, at most one per source (the latest).
+/// An entry is in conflict when its candidates disagree.
+#[derive(Debug, Clone, Default)]
+pub struct CandidateMap {
+    by_key: HashMap<String, Vec<Candidate>>,
+}
+
+impl CandidateMap {
+    pub fn is_empty(&self) -> bool {
+        self.by_key.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.by_key.clear();
+    }
+
+    /// Records `target` as a proposal for `key`, replacing the earlier one
+    /// from the same source. Empty targets are not proposals.
+    pub fn propose(&mut self, key: &str, provenance: Provenance, target: SharedText) {
+        if target.is_empty() {
+            return;
+        }
+        let candidate = Candidate { provenance, target };
+        let candidates = self.by_key.entry(key.to_string()).or_default();
+        match candidates
+            .iter_mut()
+            .find(|c| c.source() == candidate.source())
+        {
+            Some(slot) => *slot = candidate,
+            None => candidates.push(candidate),
+        }
+    }
+
+    pub fn candidates(&self, key: &str) -> &[Candidate] {
+        self.by_key.get(key).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn is_conflict(&self, key: &str) -> bool {
+        let candidates = self.candidates(key);
+        candidates
+            .iter()
+            .any(|candidate| candidate.target != candidates[0].target)
+    }
+
+    pub fn conflict_count(&self) -> usize {
+        self.by_key
+            .keys()
+            .filter(|key| self.is_conflict(key))
+            .count()
+    }
+
+    /// Keys of conflicting entries, in entry order.
+    pub fn conflicts<'a>(&self, entries: &'a [Entry]) -> Vec<&'a str> {
+        entries
+            .iter()
+            .map(|entry| entry.key.as_str())
+            .filter(|key| self.is_conflict(key))
+            .collect()
+    }
+
+    /// Keeps only `source`'s candidate for `key` and returns it, so the entry
+    /// is no longer in conflict.
+    pub fn choose(&mut self, key: &str, source: CandidateSource) -> Option<Candidate> {
+        let candidates = self.by_key.get_mut(key)?;
+        let chosen = candidates.iter().find(|c| c.source() == source)?.clone();
+        *candidates = vec![chosen.clone()];
+        Some(chosen)
+    }
+
+    /// Resolves every conflict in `entries` to the candidate of the first
+    /// source in `priority` that proposed one. Returns the updated entries
+    /// and the provenance of each resolved row, by index.
+    pub fn resolve_by_priority(
+        &mut self,
+        entries: &[Entry],
+        priority: &[CandidateSource],
+    ) -> (Vec<Entry>, Vec<(usize, Provenance)>) {
+        let mut resolved = Vec::new();
+        let next = entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let mut next = entry.clone();
+                if !self.is_conflict(&entry.key) {
+                    return next;
+                }
+                let candidates = self.candidates(&entry.key);
+                let source = priority
+                    .iter()
+                    .copied()
+                    .find(|source| candidates.iter().any(|c| c.source() == *source));
+                if let Some(chosen) = source.and_then(|source| self.choose(&entry.key, source)) {
+                    next.target_text = chosen.target;
+                    resolved.push((index, chosen.provenance));
+                }
+                next
+            })
+            .collect();
+        (next, resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: &str, target: &str) -> Entry {
+        Entry {
+            key: key.to_string(),
+            source_text: "Iron Sword".into(),
+            target_text: target.into(),
+        }
+    }
+
+    #[test]
+    fn t_cand_001_conflicts_flagged_and_resolved_by_priority() {
+        let mut map = CandidateMap::default();
+        let dict = Provenance::Dictionary {
+            score: 100,
+            ambiguous: false,
+        };
+        map.propose("k1", Provenance::XmlKey, "鉄の剣".into());
+        map.propose("k1", dict, "鉄のソード".into());
+        map.propose("k2", Provenance::XmlKey, "鉄の剣".into());
+        map.propose("k2", dict, "鉄の剣".into());
+        map.propose("k3", dict, "".into());
+        map.propose("k3", Provenance::XmlKey, "古い訳".into());
+        map.propose("k3", Provenance::XmlSource, "新しい訳".into());
+        map.propose("k3", Provenance::Manual, "手直し".into());
+
+        let entries = vec![
+            entry("k1", "鉄のソード"),
+            entry("k2", "鉄の剣"),
+            entry("k3", "手直し"),
+        ];
+        assert_eq!(map.conflicts(&entries), vec!["k1", "k3"]);
+        assert_eq!(map.conflict_count(), 2);
+        assert_eq!(map.candidates("k3").len(), 2);
+        assert_eq!(map.candidates("k3")[0].target, "新しい訳");
+        assert_eq!(map.candidates("k3")[0].provenance, Provenance::XmlSource);
+
+        let (next, resolved) = map.resolve_by_priority(&entries, &CandidateSource::PRIORITY);
+        assert_eq!(resolved, [(0, Provenance::XmlKey), (2, Provenance::Manual)]);
+        assert_eq!(next[0].target_text, "鉄の剣");
+        assert_eq!(next[2].target_text, "手直し");
+        assert!(map.conflicts(&next).is_empty());
+
+        map.propose(
+            "k1",
+            Provenance::Dictionary {
+                score: 80,
+                ambiguous: true,
+            },
+            "鉄のソード".into(),
+        );
+        let chosen = map.choose("k1", CandidateSource::Dictionary).expect("dict");
+        assert_eq!(chosen.target, "鉄のソード");
+        assert_eq!(chosen.provenance.label(), "辞書 80%（要確認）");
+        assert!(!map.is_conflict("k1"));
+        assert_eq!(map.choose("k1", CandidateSource::Xml), None);
+    }
+}
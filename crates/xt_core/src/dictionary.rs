@@ -1,15 +1,87 @@
+use crate::candidates::{Proposal, Provenance};
+use crate::formats::esp::Canceled;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::formats::esp::{CancelToken, NoProgress, ProgressSink};
+use crate::formats::strings::StringsError;
+#[cfg(not(target_arch = "wasm32"))]
 use crate::formats::strings::{
     read_dlstrings, read_ilstrings, read_strings, StringsEntry, StringsFile,
 };
-use crate::model::Entry;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::import_export::import_entries_any;
+use crate::import_export::ImportError;
+use crate::model::{Entry, SharedText};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::pipeline::{load_plugin_entries, PipelineError, PluginContext};
+use crate::placeholders::{self, mask};
+use crate::search::MatchOptions;
+use crate::tm::similarity_percent;
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::fmt;
+use std::convert::Infallible;
+#[cfg(not(target_arch = "wasm32"))]
 use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
 use std::path::Path;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Default)]
 pub struct TranslationDictionary {
-    pairs: HashMap<String, String>,
+    /// Targets of each source, most used first.
+    targets: HashMap<SharedText, Vec<DictionaryTarget>>,
+}
+
+/// One translation of a dictionary source and how many pairs used it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DictionaryTarget {
+    pub text: SharedText,
+    pub count: u32,
+}
+
+/// What [`TranslationDictionary::merge`] does with a source both
+/// dictionaries translate differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    KeepExisting,
+    PreferOther,
+    /// Keep the existing target and add the other one as an alternative.
+    KeepBoth,
+}
+
+impl MergePolicy {
+    pub const ALL: [MergePolicy; 3] = [
+        MergePolicy::KeepExisting,
+        MergePolicy::PreferOther,
+        MergePolicy::KeepBoth,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MergePolicy::KeepExisting => "keep-existing",
+            MergePolicy::PreferOther => "prefer-other",
+            MergePolicy::KeepBoth => "keep-both",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.as_str() == value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergeStats {
+    /// Sources only the other dictionary had.
+    pub added: usize,
+    /// Sources both had with the same target.
+    pub unchanged: usize,
+    /// Conflicting sources whose existing target was kept.
+    pub kept: usize,
+    /// Conflicting sources that took the other target.
+    pub replaced: usize,
+    /// Conflicting sources that gained the other target as an alternative.
+    pub alternatives: usize,
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
@@ -19,50 +91,214 @@ pub struct DictionaryBuildStats {
     pub entries_added: usize,
 }
 
-#[derive(Debug)]
+/// Counts of [`TranslationDictionary::build_from_plugin_pair`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PluginPairStats {
+    /// Strings of the original found in the translated plugin.
+    pub matched: usize,
+    /// Matched strings left as in the original, not used.
+    pub untranslated: usize,
+    /// Strings of the original missing from the translated plugin.
+    pub missing: usize,
+    pub entries_added: usize,
+}
+
+#[derive(Debug, thiserror::Error)]
 pub enum DictionaryError {
-    Io(std::io::Error),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{action} {}: {source}", path.display())]
+    File {
+        action: &'static str,
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid file name")]
     InvalidFileName,
+    #[error("invalid utf-8 in file name")]
     InvalidUtf8Name,
+    #[error("invalid dictionary format")]
     InvalidFormat,
+    /// Line `line` (1-based) of a dictionary file is not `source<TAB>target`.
+    #[error("{}:{line}: invalid dictionary line", path.display())]
+    InvalidLine { path: PathBuf, line: usize },
+    #[error("strings file {}: {source}", path.display())]
+    Strings {
+        path: PathBuf,
+        #[source]
+        source: StringsError,
+    },
+    #[error("canceled")]
+    Canceled(#[from] Canceled),
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error(transparent)]
+    Plugin(#[from] PipelineError),
+    #[error("translation file {}: {source}", path.display())]
+    Import {
+        path: PathBuf,
+        #[source]
+        source: ImportError,
+    },
+    #[cfg(feature = "sqlite")]
+    #[error("sqlite dictionary {}: {source}", path.display())]
+    Sqlite {
+        path: PathBuf,
+        #[source]
+        source: rusqlite::Error,
+    },
 }
 
-impl fmt::Display for DictionaryError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            DictionaryError::Io(err) => write!(f, "io error: {err}"),
-            DictionaryError::InvalidFileName => write!(f, "invalid file name"),
-            DictionaryError::InvalidUtf8Name => write!(f, "invalid utf-8 in file name"),
-            DictionaryError::InvalidFormat => write!(f, "invalid dictionary format"),
-        }
-    }
-}
-
-impl std::error::Error for DictionaryError {}
-
-impl From<std::io::Error> for DictionaryError {
-    fn from(err: std::io::Error) -> Self {
-        DictionaryError::Io(err)
+#[cfg(not(target_arch = "wasm32"))]
+fn file_error(action: &'static str, path: &Path) -> impl FnOnce(std::io::Error) -> DictionaryError {
+    let path = path.to_path_buf();
+    move |source| DictionaryError::File {
+        action,
+        path,
+        source,
     }
 }
 
 impl TranslationDictionary {
     pub fn is_empty(&self) -> bool {
-        self.pairs.is_empty()
+        self.targets.is_empty()
     }
 
+    /// Number of sources.
     pub fn len(&self) -> usize {
-        self.pairs.len()
+        self.targets.len()
+    }
+
+    /// Most used target of exactly `source`.
+    pub fn get(&self, source: &str) -> Option<&SharedText> {
+        self.targets(source).first().map(|target| &target.text)
+    }
+
+    /// Sources with their most used target, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&SharedText, &SharedText)> {
+        self.targets
+            .iter()
+            .map(|(source, targets)| (source, &targets[0].text))
+    }
+
+    /// Every target of exactly `source`, most used first.
+    pub fn targets(&self, source: &str) -> &[DictionaryTarget] {
+        self.targets
+            .get(source)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Targets of `source` besides the one [`TranslationDictionary::get`]
+    /// returns.
+    pub fn alternatives(&self, source: &str) -> &[DictionaryTarget] {
+        self.targets(source).get(1..).unwrap_or_default()
+    }
+
+    /// Replaces the target used for `source` with `target`, keeping the
+    /// alternatives; returns the replaced target.
+    pub fn insert(&mut self, source: SharedText, target: SharedText) -> Option<SharedText> {
+        let targets = self.targets.entry(source).or_default();
+        let count = targets.first().map_or(1, |first| first.count.max(1));
+        let previous = (!targets.is_empty()).then(|| targets.remove(0).text);
+        targets.retain(|t| t.text != target);
+        targets.insert(
+            0,
+            DictionaryTarget {
+                text: target,
+                count,
+            },
+        );
+        previous
+    }
+
+    /// Removes `source` with all its targets; returns the one it used.
+    pub fn remove(&mut self, source: &str) -> Option<SharedText> {
+        self.targets
+            .remove(source)
+            .map(|mut targets| targets.swap_remove(0).text)
     }
 
+    /// Counts `uses` more pairs translating `source` as `target`.
+    pub(crate) fn record(&mut self, source: SharedText, target: SharedText, uses: u32) {
+        let targets = self.targets.entry(source).or_default();
+        match targets.iter_mut().find(|t| t.text == target) {
+            Some(known) => known.count = known.count.saturating_add(uses),
+            None => targets.push(DictionaryTarget {
+                text: target,
+                count: uses,
+            }),
+        }
+        targets.sort_by_key(|target| std::cmp::Reverse(target.count));
+    }
+
+    /// Adds `target` after `source`'s current one without making it more
+    /// used than that. Returns whether anything was added.
+    fn add_alternative(&mut self, source: &SharedText, target: &DictionaryTarget) -> bool {
+        let targets = self.targets.entry(source.clone()).or_default();
+        if targets.iter().any(|t| t.text == target.text) {
+            return false;
+        }
+        let count = targets
+            .first()
+            .map_or(target.count, |first| target.count.min(first.count));
+        targets.push(DictionaryTarget {
+            text: target.text.clone(),
+            count,
+        });
+        targets.sort_by_key(|target| std::cmp::Reverse(target.count));
+        true
+    }
+
+    /// Adds `other`'s pairs, resolving sources both translate differently
+    /// by `policy`. Alternatives follow their pair's target.
+    pub fn merge(&mut self, other: &TranslationDictionary, policy: MergePolicy) -> MergeStats {
+        let mut stats = MergeStats::default();
+        let mut sources = other.targets.keys().collect::<Vec<_>>();
+        sources.sort();
+        for source in sources {
+            let theirs = &other.targets[source];
+            let conflict = match self.get(source) {
+                None => {
+                    stats.added += 1;
+                    false
+                }
+                Some(existing) if *existing == theirs[0].text => {
+                    stats.unchanged += 1;
+                    false
+                }
+                Some(_) => true,
+            };
+            match policy {
+                _ if !conflict => {}
+                MergePolicy::KeepExisting => {
+                    stats.kept += 1;
+                    continue;
+                }
+                MergePolicy::PreferOther => {
+                    stats.replaced += 1;
+                    self.targets.insert(source.clone(), theirs.clone());
+                    continue;
+                }
+                MergePolicy::KeepBoth => stats.alternatives += 1,
+            }
+            for target in theirs {
+                self.add_alternative(source, target);
+            }
+        }
+        stats
+    }
+
+    /// Dictionary of the translated rows; a source translated several ways
+    /// keeps each target with the number of rows using it.
     pub fn build_from_entries(entries: &[Entry]) -> Self {
-        let mut pairs = HashMap::new();
+        let mut dict = Self::default();
         for entry in entries {
             if !entry.source_text.is_empty() && !entry.target_text.is_empty() {
-                pairs.insert(entry.source_text.clone(), entry.target_text.clone());
+                dict.record(entry.source_text.clone(), entry.target_text.clone(), 1);
             }
         }
-        Self { pairs }
+        dict
     }
 
     pub fn apply_quick(
@@ -71,71 +307,351 @@ impl TranslationDictionary {
         selected_keys: &[String],
         only_untranslated: bool,
     ) -> (Vec<Entry>, usize) {
-        let mut selected: HashMap<&str, ()> = HashMap::new();
-        for key in selected_keys {
-            selected.insert(key.as_str(), ());
-        }
-        let use_selection = !selected.is_empty();
-        let mut updated = 0usize;
-        let next = entries
-            .iter()
-            .map(|entry| {
-                if use_selection && !selected.contains_key(entry.key.as_str()) {
-                    return entry.clone();
-                }
-                if only_untranslated && !entry.target_text.is_empty() {
-                    return entry.clone();
-                }
-                if let Some(target) = self.pairs.get(entry.source_text.as_str()) {
-                    if target != &entry.target_text {
-                        let mut out = entry.clone();
-                        out.target_text = target.clone();
-                        updated += 1;
-                        return out;
-                    }
-                }
-                entry.clone()
-            })
-            .collect::<Vec<_>>();
+        self.apply_quick_with(
+            entries,
+            selected_keys,
+            only_untranslated,
+            MatchOptions::EXACT,
+        )
+    }
+
+    /// [`TranslationDictionary::apply_quick`] matching sources under `options`.
+    /// An exact source match wins over a folded one.
+    pub fn apply_quick_with(
+        &self,
+        entries: &[Entry],
+        selected_keys: &[String],
+        only_untranslated: bool,
+        options: MatchOptions,
+    ) -> (Vec<Entry>, usize) {
+        let (next, proposals) =
+            self.apply_quick_with_proposals(entries, selected_keys, only_untranslated, options);
+        let updated = proposals.iter().filter(|proposal| proposal.applied).count();
         (next, updated)
     }
 
+    /// [`TranslationDictionary::apply_quick_with`] that also returns the
+    /// dictionary hit of every considered row, applied or not (rows kept by
+    /// `only_untranslated` are still looked up). A folded hit scores the
+    /// similarity of the two sources; canonically equivalent ones score 100.
+    pub fn apply_quick_with_proposals(
+        &self,
+        entries: &[Entry],
+        selected_keys: &[String],
+        only_untranslated: bool,
+        options: MatchOptions,
+    ) -> (Vec<Entry>, Vec<Proposal>) {
+        let mut folded = None;
+        let result = quick_proposals(entries, selected_keys, only_untranslated, |source| {
+            Ok::<_, Infallible>(
+                self.lookup(source, options, &mut folded)
+                    .map(|(source, targets)| (source.clone(), Cow::Borrowed(targets))),
+            )
+        });
+        match result {
+            Ok(filled) => filled,
+            Err(never) => match never {},
+        }
+    }
+
+    /// [`TranslationDictionary::apply_quick_with_proposals`] for a reference
+    /// translation: sources match exactly and the proposals are
+    /// [`Provenance::Reference`].
+    pub fn apply_reference_with_proposals(
+        &self,
+        entries: &[Entry],
+        selected_keys: &[String],
+        only_untranslated: bool,
+    ) -> (Vec<Entry>, Vec<Proposal>) {
+        let (next, mut proposals) = self.apply_quick_with_proposals(
+            entries,
+            selected_keys,
+            only_untranslated,
+            MatchOptions::EXACT,
+        );
+        for proposal in &mut proposals {
+            proposal.provenance = Provenance::Reference;
+        }
+        (next, proposals)
+    }
+
+    /// Dictionary source and targets matching `source` under `options`,
+    /// preferring an exact match. Placeholders match any placeholder; use
+    /// [`placeholders::transfer`] to give a target the row's own.
+    pub fn lookup_targets(
+        &self,
+        source: &str,
+        options: MatchOptions,
+    ) -> Option<(&SharedText, &[DictionaryTarget])> {
+        self.lookup(source, options, &mut None)
+    }
+
+    /// Dictionary source and targets matching `source`. Other sources are
+    /// compared folded with their placeholders masked.
+    fn lookup<'a>(
+        &'a self,
+        source: &str,
+        options: MatchOptions,
+        folded: &mut Option<HashMap<String, &'a SharedText>>,
+    ) -> Option<(&'a SharedText, &'a [DictionaryTarget])> {
+        if let Some((source, targets)) = self.targets.get_key_value(source) {
+            return Some((source, targets));
+        }
+        let key = folded_key(source, options)?;
+        let folded = folded.get_or_insert_with(|| {
+            let mut sources = self.targets.keys().collect::<Vec<_>>();
+            sources.sort();
+            let mut out = HashMap::with_capacity(sources.len());
+            for source in sources {
+                out.entry(fold_source(source, options)).or_insert(source);
+            }
+            out
+        });
+        let source = *folded.get(&key)?;
+        Some((source, &self.targets[source]))
+    }
+
+    /// Writes one `source<TAB>target` line per target, most used first,
+    /// with a third `<TAB>count` field when more than one pair used it.
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn save_to_path(&self, path: &Path) -> Result<(), DictionaryError> {
         let mut rows = Vec::new();
-        for (source, target) in &self.pairs {
-            rows.push(format!("{}\t{}", escape_line(source), escape_line(target)));
+        let mut sources = self.targets.keys().collect::<Vec<_>>();
+        sources.sort();
+        for source in sources {
+            for target in &self.targets[source] {
+                let mut row = format!("{}\t{}", escape_line(source), escape_line(&target.text));
+                if target.count != 1 {
+                    row.push_str(&format!("\t{}", target.count));
+                }
+                rows.push(row);
+            }
         }
-        rows.sort();
-        fs::write(path, rows.join("\n"))?;
+        crate::formats::esp::write_atomic(path, rows.join("\n"))
+            .map_err(file_error("write", path))?;
         Ok(())
     }
 
+    /// Loads a file written by [`TranslationDictionary::save_to_path`]. A
+    /// line without a count counts once; a repeated pair adds up.
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn load_from_path(path: &Path) -> Result<Self, DictionaryError> {
-        let mut pairs = HashMap::new();
-        let data = fs::read_to_string(path)?;
-        for line in data.lines() {
+        let mut dict = Self::default();
+        let data = fs::read_to_string(path).map_err(file_error("read", path))?;
+        for (index, line) in data.lines().enumerate() {
             if line.trim().is_empty() {
                 continue;
             }
-            let Some((source, target)) = line.split_once('\t') else {
-                return Err(DictionaryError::InvalidFormat);
+            let Some((source, target, count)) = parse_line(line) else {
+                return Err(DictionaryError::InvalidLine {
+                    path: path.to_path_buf(),
+                    line: index + 1,
+                });
             };
-            pairs.insert(unescape_line(source)?, unescape_line(target)?);
+            dict.record(source.into(), target.into(), count);
         }
-        Ok(Self { pairs })
+        Ok(dict)
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn build_from_strings_dir(
         dir: &Path,
         source_lang: &str,
         target_lang: &str,
     ) -> Result<(Self, DictionaryBuildStats), DictionaryError> {
-        let mut pairs = HashMap::new();
+        Self::build_from_strings_dir_cancelable(
+            dir,
+            source_lang,
+            target_lang,
+            &CancelToken::new(),
+            &NoProgress,
+        )
+    }
+
+    /// [`Self::build_from_strings_dir`] that stops with
+    /// [`DictionaryError::Canceled`] once `cancel` is canceled (checked per
+    /// file) and reports the source-language files as items to `progress`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn build_from_strings_dir_cancelable(
+        dir: &Path,
+        source_lang: &str,
+        target_lang: &str,
+        cancel: &CancelToken,
+        progress: &dyn ProgressSink,
+    ) -> Result<(Self, DictionaryBuildStats), DictionaryError> {
+        Self::build_from_strings(dir, None, source_lang, target_lang, cancel, progress)
+    }
+
+    /// Reference translation of one plugin: pairs the Strings files of
+    /// `translated` (`Skyrim_japanese.strings`) with the `source_lang` files
+    /// beside it, for each of the three kinds present.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn build_reference(
+        translated: &Path,
+        source_lang: &str,
+    ) -> Result<(Self, DictionaryBuildStats), DictionaryError> {
+        let name = translated
+            .file_name()
+            .ok_or(DictionaryError::InvalidFileName)?
+            .to_str()
+            .ok_or(DictionaryError::InvalidUtf8Name)?;
+        let (stem, target_lang, _) =
+            parse_lang_file_name(name).ok_or(DictionaryError::InvalidFileName)?;
+        let dir = translated
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        Self::build_from_strings(
+            dir,
+            Some(&stem),
+            source_lang,
+            &target_lang,
+            &CancelToken::new(),
+            &NoProgress,
+        )
+    }
+
+    /// Dictionary of the translation files in `dir` (xtrans XML or JSON,
+    /// xTranslator SSTXML), read in name order. A pair found in several
+    /// files counts once per row; rows left untranslated (target equal to
+    /// the source) are skipped.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn build_from_xml_dir(dir: &Path) -> Result<(Self, DictionaryBuildStats), DictionaryError> {
+        Self::build_from_xml_dir_cancelable(dir, &CancelToken::new(), &NoProgress)
+    }
+
+    /// [`Self::build_from_xml_dir`] that stops with
+    /// [`DictionaryError::Canceled`] once `cancel` is canceled (checked per
+    /// file) and reports the files as items to `progress`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn build_from_xml_dir_cancelable(
+        dir: &Path,
+        cancel: &CancelToken,
+        progress: &dyn ProgressSink,
+    ) -> Result<(Self, DictionaryBuildStats), DictionaryError> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(dir).map_err(file_error("read dir", dir))? {
+            let path = entry?.path();
+            let is_translation = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| matches!(ext.to_ascii_lowercase().as_str(), "xml" | "json"));
+            if path.is_file() && is_translation {
+                files.push(path);
+            }
+        }
+        files.sort();
+        progress.set_total(files.len());
+        let mut dict = Self::default();
+        let mut stats = DictionaryBuildStats::default();
+        for path in files {
+            cancel.check()?;
+            if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+                progress.set_current_file(name);
+            }
+            stats.files_seen += 1;
+            let text = fs::read_to_string(&path).map_err(file_error("read", &path))?;
+            let entries = import_entries_any(&text).map_err(|source| DictionaryError::Import {
+                path: path.clone(),
+                source,
+            })?;
+            let mut used = false;
+            for entry in entries {
+                if entry.source_text.is_empty()
+                    || entry.target_text.is_empty()
+                    || entry.source_text == entry.target_text
+                {
+                    continue;
+                }
+                dict.record(entry.source_text, entry.target_text, 1);
+                used = true;
+            }
+            if used {
+                stats.file_pairs += 1;
+            }
+            progress.advance(1);
+        }
+        stats.entries_added = dict.len();
+        Ok((dict, stats))
+    }
+
+    /// Mines a translated plugin: pairs each string of `original` with the
+    /// string of `translated` under the same record, form id and subrecord.
+    /// Strings the translation left unchanged are skipped. Localized plugins
+    /// read their Strings files in the language found beside them.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn build_from_plugin_pair(
+        original: &Path,
+        translated: &Path,
+    ) -> Result<(Self, PluginPairStats), DictionaryError> {
+        let original_context = PluginContext::resolve(original, None, None, None)?;
+        let translated_context = PluginContext::resolve(translated, None, None, None)?;
+        Self::build_from_plugin_pair_with(
+            original,
+            &original_context,
+            translated,
+            &translated_context,
+        )
+    }
+
+    /// [`Self::build_from_plugin_pair`] with the contexts (Strings folder,
+    /// game, language) the plugins are read with.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn build_from_plugin_pair_with(
+        original: &Path,
+        original_context: &PluginContext,
+        translated: &Path,
+        translated_context: &PluginContext,
+    ) -> Result<(Self, PluginPairStats), DictionaryError> {
+        let (sources, _) = load_plugin_entries(original, original_context)?;
+        let (targets, _) = load_plugin_entries(translated, translated_context)?;
+        let by_key = targets
+            .iter()
+            .map(|entry| (entry.key.as_str(), &entry.source_text))
+            .collect::<HashMap<_, _>>();
+        let mut dict = Self::default();
+        let mut stats = PluginPairStats::default();
+        for entry in &sources {
+            let Some(target) = by_key.get(entry.key.as_str()) else {
+                stats.missing += 1;
+                continue;
+            };
+            stats.matched += 1;
+            if entry.source_text.is_empty() || target.is_empty() {
+                continue;
+            }
+            if entry.source_text == **target {
+                stats.untranslated += 1;
+                continue;
+            }
+            dict.record(entry.source_text.clone(), (*target).clone(), 1);
+        }
+        stats.entries_added = dict.len();
+        Ok((dict, stats))
+    }
+
+    /// Pairs of the `source_lang`/`target_lang` Strings files in `dir`, of
+    /// every plugin or only the one named `only` (lowercase).
+    #[cfg(not(target_arch = "wasm32"))]
+    fn build_from_strings(
+        dir: &Path,
+        only: Option<&str>,
+        source_lang: &str,
+        target_lang: &str,
+        cancel: &CancelToken,
+        progress: &dyn ProgressSink,
+    ) -> Result<(Self, DictionaryBuildStats), DictionaryError> {
+        let mut dict = Self::default();
         let mut stats = DictionaryBuildStats::default();
         let source_lower = source_lang.to_ascii_lowercase();
         let target_lower = target_lang.to_ascii_lowercase();
-        let entries = fs::read_dir(dir)?;
+        let entries = fs::read_dir(dir).map_err(file_error("read dir", dir))?;
+        let mut sources = Vec::new();
+        // Matched by lowercased name: `Skyrim_English.STRINGS` pairs with
+        // `skyrim_japanese.strings` on case-sensitive file systems too.
+        let mut targets = HashMap::new();
         for entry in entries {
+            cancel.check()?;
             let path = entry?.path();
             if !path.is_file() {
                 continue;
@@ -145,38 +661,63 @@ impl TranslationDictionary {
             let Some((stem, lang, ext)) = parse_lang_file_name(name) else {
                 continue;
             };
-            if lang != source_lower {
-                continue;
+            if lang == source_lower && only.is_none_or(|only| only == stem) {
+                sources.push((name.to_string(), path.clone(), stem, ext));
+            } else if lang == target_lower {
+                targets.insert((stem, ext), path);
             }
+        }
+        progress.set_total(sources.len());
+        for (name, path, stem, ext) in sources {
+            cancel.check()?;
+            progress.set_current_file(&name);
             stats.files_seen += 1;
-            let target_name = format!("{stem}_{target_lower}.{ext}");
-            let target_path = dir.join(target_name);
-            if !target_path.exists() {
-                continue;
-            }
-            let source_file = read_strings_file(&path, ext)?;
-            let target_file = read_strings_file(&target_path, ext)?;
-            let mut by_id = HashMap::new();
-            for StringsEntry { id, text } in &target_file.entries {
-                by_id.insert(*id, text.as_str());
-            }
-            let before = pairs.len();
-            for StringsEntry { id, text } in &source_file.entries {
-                if let Some(target) = by_id.get(id) {
-                    if !text.is_empty() && !target.is_empty() {
-                        pairs.insert(text.clone(), (*target).to_string());
+            if let Some(target_path) = targets.get(&(stem, ext)) {
+                let source_file = read_strings_file(&path, ext)?;
+                let target_file = read_strings_file(target_path, ext)?;
+                let mut by_id = HashMap::new();
+                for StringsEntry { id, text } in &target_file.entries {
+                    by_id.insert(*id, text.as_str());
+                }
+                let before = dict.len();
+                for StringsEntry { id, text } in &source_file.entries {
+                    if let Some(target) = by_id.get(id) {
+                        if !text.is_empty() && !target.is_empty() {
+                            dict.record(text.as_str().into(), (*target).into(), 1);
+                        }
                     }
                 }
+                if dict.len() > before {
+                    stats.file_pairs += 1;
+                }
             }
-            if pairs.len() > before {
-                stats.file_pairs += 1;
-            }
+            progress.advance(1);
         }
-        stats.entries_added = pairs.len();
-        Ok((Self { pairs }, stats))
+        stats.entries_added = dict.len();
+        Ok((dict, stats))
     }
 }
 
+/// Languages with Strings files in `dir` (`skyrim_english.strings` →
+/// `english`), sorted and lowercased.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn detect_languages(dir: &Path) -> Result<Vec<String>, DictionaryError> {
+    let mut languages = Vec::new();
+    for entry in fs::read_dir(dir).map_err(file_error("read dir", dir))? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if let Some((_, lang, _)) = parse_lang_file_name(name) {
+            languages.push(lang);
+        }
+    }
+    languages.sort();
+    languages.dedup();
+    Ok(languages)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
 fn parse_lang_file_name(name: &str) -> Option<(String, String, &'static str)> {
     let lower = name.to_ascii_lowercase();
     let (stem_ext, ext) = if lower.ends_with(".strings") {
@@ -192,25 +733,112 @@ fn parse_lang_file_name(name: &str) -> Option<(String, String, &'static str)> {
     Some((stem.to_string(), lang.to_string(), ext))
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 fn read_strings_file(path: &Path, ext: &str) -> Result<StringsFile, DictionaryError> {
-    let bytes = fs::read(path)?;
+    let bytes = fs::read(path).map_err(file_error("read", path))?;
     let file = match ext {
         "strings" => read_strings(&bytes),
         "dlstrings" => read_dlstrings(&bytes),
         "ilstrings" => read_ilstrings(&bytes),
         _ => return Err(DictionaryError::InvalidFormat),
     }
-    .map_err(|_| DictionaryError::InvalidFormat)?;
+    .map_err(|source| DictionaryError::Strings {
+        path: path.to_path_buf(),
+        source,
+    })?;
     Ok(file)
 }
 
+/// `source<TAB>target[<TAB>count]`.
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_line(line: &str) -> Option<(String, String, u32)> {
+    let mut fields = line.split('\t');
+    let (source, target) = (fields.next()?, fields.next()?);
+    let count = match fields.next() {
+        Some(count) => count.parse().ok()?,
+        None => 1,
+    };
+    if fields.next().is_some() {
+        return None;
+    }
+    Some((unescape_line(source)?, unescape_line(target)?, count))
+}
+
+/// Fills `entries` with the first target `lookup` finds for each row's
+/// source and returns the proposals, as
+/// [`TranslationDictionary::apply_quick_with_proposals`] describes. `lookup`
+/// returns the dictionary source that matched along with its targets.
+pub(crate) fn quick_proposals<'a, E>(
+    entries: &[Entry],
+    selected_keys: &[String],
+    only_untranslated: bool,
+    mut lookup: impl FnMut(&str) -> Result<Option<(SharedText, Cow<'a, [DictionaryTarget]>)>, E>,
+) -> Result<(Vec<Entry>, Vec<Proposal>), E> {
+    let mut selected: HashMap<&str, ()> = HashMap::new();
+    for key in selected_keys {
+        selected.insert(key.as_str(), ());
+    }
+    let use_selection = !selected.is_empty();
+    let mut proposals = Vec::new();
+    let mut next = Vec::with_capacity(entries.len());
+    for (index, entry) in entries.iter().enumerate() {
+        if use_selection && !selected.contains_key(entry.key.as_str()) {
+            next.push(entry.clone());
+            continue;
+        }
+        let Some((source, targets)) = lookup(&entry.source_text)? else {
+            next.push(entry.clone());
+            continue;
+        };
+        let target = placeholders::transfer(&targets[0].text, &source, &entry.source_text)
+            .map_or_else(|| targets[0].text.clone(), SharedText::from);
+        let applied =
+            (!only_untranslated || entry.target_text.is_empty()) && target != entry.target_text;
+        proposals.push(Proposal {
+            index,
+            provenance: Provenance::Dictionary {
+                score: similarity_percent(
+                    &MatchOptions::EXACT.fold(&mask(&source).text),
+                    &MatchOptions::EXACT.fold(&mask(&entry.source_text).text),
+                ),
+                ambiguous: targets.len() > 1,
+            },
+            target: target.clone(),
+            applied,
+        });
+        let mut out = entry.clone();
+        if applied {
+            out.target_text = target;
+        }
+        next.push(out);
+    }
+    Ok((next, proposals))
+}
+
+/// Key a dictionary source is indexed by for folded lookups.
+pub(crate) fn fold_source(source: &str, options: MatchOptions) -> String {
+    options.fold(&mask(source).text).into_owned()
+}
+
+/// [`fold_source`] of a row's source, or `None` when no other source can
+/// match it under `options`.
+pub(crate) fn folded_key(source: &str, options: MatchOptions) -> Option<String> {
+    let masked = mask(source);
+    if options == MatchOptions::EXACT && source.is_ascii() && masked.placeholders.is_empty() {
+        return None;
+    }
+    Some(options.fold(&masked.text).into_owned())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
 fn escape_line(s: &str) -> String {
     s.replace('\\', "\\\\")
         .replace('\t', "\\t")
         .replace('\n', "\\n")
 }
 
-fn unescape_line(s: &str) -> Result<String, DictionaryError> {
+#[cfg(not(target_arch = "wasm32"))]
+fn unescape_line(s: &str) -> Option<String> {
     let mut out = String::new();
     let mut chars = s.chars();
     while let Some(ch) = chars.next() {
@@ -218,17 +846,14 @@ fn unescape_line(s: &str) -> Result<String, DictionaryError> {
             out.push(ch);
             continue;
         }
-        let Some(next) = chars.next() else {
-            return Err(DictionaryError::InvalidFormat);
-        };
-        match next {
+        match chars.next()? {
             '\\' => out.push('\\'),
             't' => out.push('\t'),
             'n' => out.push('\n'),
-            _ => return Err(DictionaryError::InvalidFormat),
+            _ => return None,
         }
     }
-    Ok(out)
+    Some(out)
 }
 
 #[cfg(test)]
@@ -238,19 +863,21 @@ mod tests {
 
     #[test]
     fn t_dict_001_apply_quick_selection_only() {
-        let dict = TranslationDictionary {
-            pairs: HashMap::from([("Hello".to_string(), "こんにちは".to_string())]),
-        };
+        let dict = TranslationDictionary::build_from_entries(&[Entry {
+            key: "k0".to_string(),
+            source_text: "Hello".into(),
+            target_text: "こんにちは".into(),
+        }]);
         let entries = vec![
             Entry {
                 key: "k1".to_string(),
-                source_text: "Hello".to_string(),
-                target_text: String::new(),
+                source_text: "Hello".into(),
+                target_text: Default::default(),
             },
             Entry {
                 key: "k2".to_string(),
-                source_text: "Hello".to_string(),
-                target_text: String::new(),
+                source_text: "Hello".into(),
+                target_text: Default::default(),
             },
         ];
         let (updated, count) = dict.apply_quick(&entries, &[String::from("k2")], true);
@@ -259,23 +886,94 @@ mod tests {
         assert_eq!(updated[1].target_text, "こんにちは");
     }
 
+    #[test]
+    fn t_dict_004_apply_quick_match_options() {
+        let mut dict = TranslationDictionary::default();
+        dict.insert("Iron Sword".into(), "鉄の剣".into());
+        dict.insert("iron sword".into(), "てつのけん".into());
+        dict.insert("ガード".into(), "防御".into());
+        let entries = ["IRON SWORD", "ｶﾞｰﾄﾞ", "\u{30AB}\u{3099}ード", "iron sword"]
+            .iter()
+            .enumerate()
+            .map(|(i, source)| Entry {
+                key: format!("k{i}"),
+                source_text: (*source).into(),
+                target_text: Default::default(),
+            })
+            .collect::<Vec<_>>();
+
+        let (exact, count) = dict.apply_quick(&entries, &[], true);
+        assert_eq!(count, 2, "NFC only");
+        assert_eq!(exact[2].target_text, "防御");
+        assert_eq!(exact[3].target_text, "てつのけん");
+
+        let (loose, count) = dict.apply_quick_with(&entries, &[], true, MatchOptions::LOOSE);
+        assert_eq!(count, 4);
+        assert_eq!(loose[0].target_text, "鉄の剣", "first source in sort order");
+        assert_eq!(loose[1].target_text, "防御");
+        assert_eq!(loose[3].target_text, "てつのけん", "exact match wins");
+
+        let mut translated = entries.clone();
+        translated[3].target_text = "訳済み".into();
+        let (_, proposals) =
+            dict.apply_quick_with_proposals(&translated, &[], true, MatchOptions::LOOSE);
+        let scored = proposals
+            .iter()
+            .map(|p| (p.index, p.provenance, p.applied))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            scored,
+            [
+                (
+                    0,
+                    Provenance::Dictionary {
+                        score: 30,
+                        ambiguous: false
+                    },
+                    true
+                ),
+                (
+                    1,
+                    Provenance::Dictionary {
+                        score: 0,
+                        ambiguous: false
+                    },
+                    true
+                ),
+                (
+                    2,
+                    Provenance::Dictionary {
+                        score: 100,
+                        ambiguous: false
+                    },
+                    true
+                ),
+                (
+                    3,
+                    Provenance::Dictionary {
+                        score: 100,
+                        ambiguous: false
+                    },
+                    false
+                ),
+            ]
+        );
+        assert_eq!(proposals[3].target, "てつのけん", "kept rows still propose");
+    }
+
     #[test]
     fn t_dict_002_build_from_strings_dir() {
         let dir = std::env::temp_dir().join(format!("xt_dict_test_{}", std::process::id()));
         let _ = fs::remove_dir_all(&dir);
         fs::create_dir_all(&dir).expect("create");
-        let en = StringsFile {
-            entries: vec![StringsEntry {
-                id: 1,
-                text: "Iron Sword".to_string(),
-            }],
-        };
-        let ja = StringsFile {
-            entries: vec![StringsEntry {
-                id: 1,
-                text: "鉄の剣".to_string(),
-            }],
-        };
+        let en = StringsFile::new(vec![StringsEntry {
+            id: 1,
+            text: "Iron Sword".to_string(),
+        }]);
+        let ja = StringsFile::new(vec![StringsEntry {
+            id: 1,
+            text: "鉄の剣".to_string(),
+        }]);
         fs::write(
             dir.join("skyrim_english.strings"),
             write_strings(&en).expect("write en"),
@@ -292,6 +990,501 @@ mod tests {
         assert_eq!(stats.entries_added, 1);
         assert_eq!(stats.file_pairs, 1);
         assert_eq!(dict.len(), 1);
+        assert_eq!(
+            detect_languages(&dir).expect("languages"),
+            ["english", "japanese"]
+        );
+
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let err = TranslationDictionary::build_from_strings_dir_cancelable(
+            &dir,
+            "english",
+            "japanese",
+            &cancel,
+            &NoProgress,
+        )
+        .expect_err("canceled");
+        assert!(matches!(err, DictionaryError::Canceled(Canceled)));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn t_dict_003_load_errors_name_file_and_line() {
+        let dir = std::env::temp_dir().join(format!("xt_dict_err_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("create");
+        let path = dir.join("dict.tsv");
+        fs::write(&path, "Iron Sword\t鉄の剣\n\nbroken line\n").expect("write");
+        let err = TranslationDictionary::load_from_path(&path).expect_err("line 3");
+        assert!(matches!(&err, DictionaryError::InvalidLine { line: 3, .. }));
+        assert_eq!(
+            err.to_string(),
+            format!("{}:3: invalid dictionary line", path.display())
+        );
+
+        let missing = dir.join("missing.tsv");
+        let err = TranslationDictionary::load_from_path(&missing).expect_err("missing");
+        assert!(err
+            .to_string()
+            .starts_with(&format!("read {}: ", missing.display())));
+        assert!(std::error::Error::source(&err).is_some());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn t_dict_005_edit_pairs_and_save() {
+        let mut dict = TranslationDictionary::default();
+        assert_eq!(dict.insert("Iron Sword".into(), "鉄の剣".into()), None);
+        assert_eq!(dict.insert("Steel".into(), "鋼".into()), None);
+        assert_eq!(
+            dict.insert("Iron Sword".into(), "鉄のソード".into())
+                .as_deref(),
+            Some("鉄の剣")
+        );
+        assert_eq!(
+            dict.get("Iron Sword").map(SharedText::as_str),
+            Some("鉄のソード")
+        );
+        assert_eq!(dict.remove("Steel").as_deref(), Some("鋼"));
+        assert_eq!(dict.remove("Steel"), None);
+        assert_eq!(dict.iter().count(), 1);
+
+        let dir = std::env::temp_dir().join(format!("xt_dict_edit_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("create");
+        let path = dir.join("user.tsv");
+        dict.save_to_path(&path).expect("save");
+        let loaded = TranslationDictionary::load_from_path(&path).expect("load");
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(
+            loaded.get("Iron Sword").map(SharedText::as_str),
+            Some("鉄のソード")
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn t_dict_006_merge_policies() {
+        let dict = |pairs: &[(&str, &str)]| {
+            let mut dict = TranslationDictionary::default();
+            for (source, target) in pairs {
+                dict.insert((*source).into(), (*target).into());
+            }
+            dict
+        };
+        let base = dict(&[("Iron Sword", "鉄の剣"), ("Steel", "鋼")]);
+        let other = dict(&[("Iron Sword", "鉄のソード"), ("Steel", "鋼"), ("Bow", "弓")]);
+        let get = |dict: &TranslationDictionary, source: &str| {
+            dict.get(source).map(|target| target.to_string())
+        };
+
+        let mut kept = base.clone();
+        let stats = kept.merge(&other, MergePolicy::KeepExisting);
+        assert_eq!(
+            stats,
+            MergeStats {
+                added: 1,
+                unchanged: 1,
+                kept: 1,
+                ..MergeStats::default()
+            }
+        );
+        assert_eq!(get(&kept, "Iron Sword").as_deref(), Some("鉄の剣"));
+        assert_eq!(get(&kept, "Bow").as_deref(), Some("弓"));
+        assert!(kept.alternatives("Iron Sword").is_empty());
+
+        let mut replaced = base.clone();
+        assert_eq!(replaced.merge(&other, MergePolicy::PreferOther).replaced, 1);
+        assert_eq!(get(&replaced, "Iron Sword").as_deref(), Some("鉄のソード"));
+
+        let mut both = base.clone();
+        let stats = both.merge(&other, MergePolicy::KeepBoth);
+        assert_eq!((stats.added, stats.alternatives), (1, 1));
+        assert_eq!(both.merge(&other, MergePolicy::KeepBoth).added, 0);
+        assert_eq!(get(&both, "Iron Sword").as_deref(), Some("鉄の剣"));
+        assert_eq!(both.alternatives("Iron Sword")[0].text, "鉄のソード");
+        assert_eq!(MergePolicy::parse("keep-both"), Some(MergePolicy::KeepBoth));
+        assert_eq!(MergePolicy::parse("both"), None);
+
+        let dir = std::env::temp_dir().join(format!("xt_dict_merge_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("create");
+        let path = dir.join("merged.tsv");
+        both.save_to_path(&path).expect("save");
+        let loaded = TranslationDictionary::load_from_path(&path).expect("load");
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(get(&loaded, "Iron Sword").as_deref(), Some("鉄の剣"));
+        assert_eq!(loaded.alternatives("Iron Sword")[0].text, "鉄のソード");
+
+        let mut edited = loaded;
+        edited.insert("Iron Sword".into(), "鉄のソード".into());
+        assert!(edited.alternatives("Iron Sword").is_empty());
+        edited.remove("Iron Sword");
+        assert_eq!(edited.get("Iron Sword"), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn t_dict_007_alternatives_by_usage() {
+        let row = |key: &str, source: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        };
+        let dict = TranslationDictionary::build_from_entries(&[
+            row("a", "Iron Sword", "鉄のソード"),
+            row("b", "Iron Sword", "鉄の剣"),
+            row("c", "Iron Sword", "鉄の剣"),
+            row("d", "Steel", "鋼"),
+        ]);
+        let counts = |dict: &TranslationDictionary, source: &str| {
+            dict.targets(source)
+                .iter()
+                .map(|target| (target.text.to_string(), target.count))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            counts(&dict, "Iron Sword"),
+            [("鉄の剣".to_string(), 2), ("鉄のソード".to_string(), 1)]
+        );
+        assert_eq!(dict.len(), 2);
+
+        let entries = vec![row("k0", "iron sword", ""), row("k1", "Steel", "")];
+        let (next, proposals) =
+            dict.apply_quick_with_proposals(&entries, &[], true, MatchOptions::LOOSE);
+        assert_eq!(next[0].target_text, "鉄の剣");
+        assert!(proposals[0].provenance.is_suspect());
+        assert!(!proposals[1].provenance.is_suspect());
+        let (source, targets) = dict
+            .lookup_targets("IRON SWORD", MatchOptions::LOOSE)
+            .expect("folded");
+        assert_eq!((source.as_str(), targets.len()), ("Iron Sword", 2));
+        assert_eq!(dict.lookup_targets("IRON SWORD", MatchOptions::EXACT), None);
+
+        let dir = std::env::temp_dir().join(format!("xt_dict_alt_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("create");
+        let path = dir.join("dict.tsv");
+        dict.save_to_path(&path).expect("save");
+        assert_eq!(
+            fs::read_to_string(&path).expect("read"),
+            "Iron Sword\t鉄の剣\t2\nIron Sword\t鉄のソード\nSteel\t鋼"
+        );
+        let mut loaded = TranslationDictionary::load_from_path(&path).expect("load");
+        assert_eq!(counts(&loaded, "Iron Sword"), counts(&dict, "Iron Sword"));
+
+        loaded.insert("Iron Sword".into(), "鉄の大剣".into());
+        assert_eq!(
+            counts(&loaded, "Iron Sword"),
+            [("鉄の大剣".to_string(), 2), ("鉄のソード".to_string(), 1)]
+        );
+
+        fs::write(&path, "Iron Sword\t鉄の剣\tmany\n").expect("write");
+        assert!(matches!(
+            TranslationDictionary::load_from_path(&path),
+            Err(DictionaryError::InvalidLine { line: 1, .. })
+        ));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn t_dict_008_placeholders_masked_and_restored() {
+        let mut dict = TranslationDictionary::default();
+        dict.insert(
+            "Found <Alias=Weapon>".into(),
+            "<Alias=Weapon>を見つけた".into(),
+        );
+        dict.insert("Deal %d damage".into(), "%dダメージ".into());
+        let entries = [
+            "Found <Alias=Item>",
+            "Deal {0} damage",
+            "Found <Alias=Weapon>",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, source)| Entry {
+            key: format!("k{i}"),
+            source_text: (*source).into(),
+            target_text: Default::default(),
+        })
+        .collect::<Vec<_>>();
+
+        let (next, proposals) =
+            dict.apply_quick_with_proposals(&entries, &[], true, MatchOptions::EXACT);
+        let targets = next
+            .iter()
+            .map(|entry| entry.target_text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            targets,
+            [
+                "<Alias=Item>を見つけた",
+                "{0}ダメージ",
+                "<Alias=Weapon>を見つけた"
+            ]
+        );
+        assert!(proposals.iter().all(|proposal| proposal.provenance
+            == Provenance::Dictionary {
+                score: 100,
+                ambiguous: false
+            }));
+        let validator = crate::validation::Validator::new(&Default::default()).expect("validator");
+        for entry in &next {
+            let issues = validator.validate(&entry.key, &entry.source_text, &entry.target_text);
+            assert!(issues.is_empty(), "{}: {issues:?}", entry.key);
+        }
+        let (source, _) = dict
+            .lookup_targets("Found <Alias=Item>", MatchOptions::EXACT)
+            .expect("masked");
+        assert_eq!(source.as_str(), "Found <Alias=Weapon>");
+        assert_eq!(dict.lookup_targets("Found", MatchOptions::EXACT), None);
+    }
+
+    #[test]
+    fn t_dict_009_reference_from_one_plugin() {
+        let dir = std::env::temp_dir().join(format!("xt_dict_ref_{}", std::process::id()));
         let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("create");
+        let files = [
+            ("Skyrim_english.strings", "Iron Sword"),
+            ("Skyrim_japanese.strings", "鉄の剣"),
+            ("Skyrim_english.dlstrings", "A plain blade."),
+            ("Skyrim_japanese.dlstrings", "飾り気のない刃。"),
+            ("Dawnguard_english.strings", "Crossbow"),
+            ("Dawnguard_japanese.strings", "クロスボウ"),
+        ];
+        for (name, text) in files {
+            let file = StringsFile::new(vec![StringsEntry {
+                id: 1,
+                text: text.to_string(),
+            }]);
+            let bytes = if name.ends_with(".dlstrings") {
+                crate::formats::strings::write_dlstrings(&file)
+            } else {
+                write_strings(&file)
+            };
+            fs::write(dir.join(name), bytes.expect("encode")).expect("write");
+        }
+
+        let (reference, stats) =
+            TranslationDictionary::build_reference(&dir.join("Skyrim_japanese.strings"), "english")
+                .expect("reference");
+        assert_eq!(stats.file_pairs, 2);
+        assert_eq!(reference.len(), 2);
+        assert_eq!(reference.get("Crossbow"), None);
+
+        let entries = vec![
+            Entry {
+                key: "k1".to_string(),
+                source_text: "Iron Sword".into(),
+                target_text: "".into(),
+            },
+            Entry {
+                key: "k2".to_string(),
+                source_text: "iron sword".into(),
+                target_text: "".into(),
+            },
+        ];
+        let (next, proposals) = reference.apply_reference_with_proposals(&entries, &[], true);
+        assert_eq!(next[0].target_text, "鉄の剣");
+        assert_eq!(next[1].target_text, "", "references match exactly");
+        assert_eq!(proposals.len(), 1);
+        assert_eq!(proposals[0].provenance, Provenance::Reference);
+        assert_eq!(proposals[0].provenance.label(), "参照訳");
+
+        assert!(matches!(
+            TranslationDictionary::build_reference(&dir.join("Skyrim.esm"), "english"),
+            Err(DictionaryError::InvalidFileName)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn t_dict_010_build_from_plugin_pair() {
+        let dir = std::env::temp_dir().join(format!("xt_dict_pair_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("original")).expect("create");
+        fs::create_dir_all(dir.join("translated")).expect("create");
+        let record = |kind: &[u8; 4], form_id: u32, name: &str| {
+            let name = [name.as_bytes(), b"\0"].concat();
+            let data = [
+                b"FULL".as_slice(),
+                &(name.len() as u16).to_le_bytes(),
+                &name,
+            ]
+            .concat();
+            [
+                kind.as_slice(),
+                &(data.len() as u32).to_le_bytes(),
+                &0u32.to_le_bytes(),
+                &form_id.to_le_bytes(),
+                &[0; 8],
+                &data,
+            ]
+            .concat()
+        };
+        let original = dir.join("original/Mod.esp");
+        let translated = dir.join("translated/Mod.esp");
+        fs::write(
+            &original,
+            [
+                record(b"WEAP", 0x0001_2EB7, "Iron Sword"),
+                record(b"WEAP", 0x0001_2EB8, "Steel Sword"),
+                record(b"ARMO", 0x0001_2E4D, "Hide Armor"),
+                record(b"MISC", 0x0000_000F, "Gold"),
+            ]
+            .concat(),
+        )
+        .expect("write original");
+        fs::write(
+            &translated,
+            [
+                record(b"WEAP", 0x0001_2EB7, "鉄の剣"),
+                record(b"WEAP", 0x0001_2EB8, "鋼鉄の剣"),
+                record(b"ARMO", 0x0001_2E4D, "Hide Armor"),
+                record(b"ARMO", 0x0001_2E4E, "革の鎧"),
+            ]
+            .concat(),
+        )
+        .expect("write translated");
+
+        let (dict, stats) =
+            TranslationDictionary::build_from_plugin_pair(&original, &translated).expect("build");
+        assert_eq!(
+            stats,
+            PluginPairStats {
+                matched: 3,
+                untranslated: 1,
+                missing: 1,
+                entries_added: 2,
+            }
+        );
+        assert_eq!(dict.get("Iron Sword").map(|t| &**t), Some("鉄の剣"));
+        assert_eq!(dict.get("Steel Sword").map(|t| &**t), Some("鋼鉄の剣"));
+        assert_eq!(dict.get("Hide Armor"), None);
+
+        let err = TranslationDictionary::build_from_plugin_pair(&original, &dir.join("none.esp"))
+            .expect_err("missing plugin");
+        assert!(matches!(err, DictionaryError::Plugin(_)));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn t_dict_011_build_from_xml_dir() {
+        use crate::import_export::{
+            export_entries, export_entries_json, export_entries_xtranslator,
+            XtranslatorExportParams,
+        };
+        let dir = std::env::temp_dir().join(format!("xt_dict_xml_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("create");
+        let entry = |key: &str, source: &str, target: &str| Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        };
+        fs::write(
+            dir.join("a.xml"),
+            export_entries(&[
+                entry("k1", "Iron Sword", "鉄の剣"),
+                entry("k2", "Gold", "Gold"),
+                entry("k3", "Bread", ""),
+            ]),
+        )
+        .expect("write xtrans");
+        fs::write(
+            dir.join("b.XML"),
+            export_entries_xtranslator(
+                &[
+                    entry("WEAP:00012EB7:FULL:0", "Iron Sword", "鉄の剣"),
+                    entry("WEAP:00012EB8:FULL:0", "Steel Sword", "鋼鉄の剣"),
+                ],
+                &XtranslatorExportParams::new("Mod.esp", "english", "japanese"),
+            ),
+        )
+        .expect("write sstxml");
+        fs::write(
+            dir.join("c.json"),
+            export_entries_json(&[entry("k1", "Iron Sword", "鉄のつるぎ")]),
+        )
+        .expect("write json");
+        fs::write(dir.join("notes.txt"), "not a translation").expect("write notes");
+
+        let (dict, stats) = TranslationDictionary::build_from_xml_dir(&dir).expect("build");
+        assert_eq!(
+            stats,
+            DictionaryBuildStats {
+                files_seen: 3,
+                file_pairs: 3,
+                entries_added: 2,
+            }
+        );
+        let targets = dict
+            .targets("Iron Sword")
+            .iter()
+            .map(|target| (&*target.text, target.count))
+            .collect::<Vec<_>>();
+        assert_eq!(targets, [("鉄の剣", 2), ("鉄のつるぎ", 1)]);
+        assert_eq!(dict.get("Steel Sword").map(|t| &**t), Some("鋼鉄の剣"));
+        assert_eq!(dict.get("Gold"), None);
+
+        fs::write(dir.join("d.json"), "[{\"key\": 1").expect("write broken");
+        let err = TranslationDictionary::build_from_xml_dir(&dir).expect_err("broken");
+        assert!(err.to_string().contains("d.json"), "{err}");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn t_dict_014_normalized_keys_keep_exact_forms() {
+        let mut dict = TranslationDictionary::default();
+        dict.insert("Iron Sword".into(), "鉄の剣".into());
+        dict.insert("Iron Sword.".into(), "鉄の剣。".into());
+        dict.insert("...".into(), "……".into());
+        let entries = [
+            "Iron Sword.",
+            "  iron  sword!",
+            "Iron\tSword",
+            "!",
+            "Steel Sword.",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, source)| Entry {
+            key: format!("k{i}"),
+            source_text: (*source).into(),
+            target_text: Default::default(),
+        })
+        .collect::<Vec<_>>();
+        let normalized = MatchOptions {
+            ignore_case: true,
+            trim: true,
+            collapse_whitespace: true,
+            strip_trailing_punctuation: true,
+            ..MatchOptions::EXACT
+        };
+
+        let (exact, count) = dict.apply_quick(&entries, &[], true);
+        assert_eq!(count, 1);
+        assert_eq!(exact[0].target_text, "鉄の剣。");
+
+        let (next, proposals) = dict.apply_quick_with_proposals(&entries, &[], true, normalized);
+        assert_eq!(next[0].target_text, "鉄の剣。", "exact source wins");
+        assert_eq!(next[1].target_text, "鉄の剣", "first source in sort order");
+        assert_eq!(next[2].target_text, "鉄の剣");
+        assert_eq!(
+            next[3].target_text, "",
+            "punctuation-only sources stay whole"
+        );
+        assert_eq!(next[4].target_text, "");
+        let scores = proposals
+            .iter()
+            .map(|p| match p.provenance {
+                Provenance::Dictionary { score, .. } => score,
+                other => panic!("{other:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(scores[0], 100);
+        assert!(scores[1] < 100 && scores[2] < 100, "{scores:?}");
     }
 }
@@ -0,0 +1,607 @@
+//! Where dictionary pairs live. [`FileDictionaryStore`] keeps the
+//! `source<TAB>target` file in memory as a [`TranslationDictionary`];
+//! [`SqliteDictionaryStore`] (feature `sqlite`) looks sources up in an SQLite
+//! database, so a dictionary of millions of pairs never has to be loaded
+//! whole, not even for folded (case-, width-insensitive) matching.
+
+use crate::candidates::Proposal;
+use crate::dictionary::{
+    fold_source, folded_key, quick_proposals, DictionaryError, DictionaryTarget,
+    TranslationDictionary,
+};
+use crate::model::{Entry, SharedText};
+use crate::search::MatchOptions;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub trait DictionaryStore {
+    /// Opens the existing store at `path`.
+    fn load(path: &Path) -> Result<Self, DictionaryError>
+    where
+        Self: Sized;
+
+    /// Writes pending changes to the store's file.
+    fn save(&mut self) -> Result<(), DictionaryError>;
+
+    /// Number of sources.
+    fn len(&self) -> Result<usize, DictionaryError>;
+
+    fn is_empty(&self) -> Result<bool, DictionaryError> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Every target of exactly `source`, most used first.
+    fn targets(&self, source: &str) -> Result<Vec<DictionaryTarget>, DictionaryError>;
+
+    /// Calls `f` with each source and its targets (most used first), in
+    /// ascending source order.
+    fn for_each(
+        &self,
+        f: &mut dyn FnMut(&SharedText, &[DictionaryTarget]),
+    ) -> Result<(), DictionaryError>;
+
+    /// Counts `uses` more pairs translating `source` as `target`.
+    fn append(&mut self, source: &str, target: &str, uses: u32) -> Result<(), DictionaryError>;
+
+    /// [`TranslationDictionary::apply_quick_with_proposals`] against the
+    /// store. Exact sources are looked up one by one; by default other
+    /// `options` index the folded sources in memory once, on the first row
+    /// without an exact hit.
+    fn apply_quick(
+        &self,
+        entries: &[Entry],
+        selected_keys: &[String],
+        only_untranslated: bool,
+        options: MatchOptions,
+    ) -> Result<(Vec<Entry>, Vec<Proposal>), DictionaryError> {
+        let mut folded: Option<HashMap<String, SharedText>> = None;
+        quick_proposals(entries, selected_keys, only_untranslated, |source| {
+            let targets = self.targets(source)?;
+            if !targets.is_empty() {
+                return Ok(Some((SharedText::from(source), Cow::Owned(targets))));
+            }
+            let Some(key) = folded_key(source, options) else {
+                return Ok(None);
+            };
+            if folded.is_none() {
+                let mut index = HashMap::new();
+                self.for_each(&mut |source, _| {
+                    index
+                        .entry(fold_source(source, options))
+                        .or_insert_with(|| source.clone());
+                })?;
+                folded = Some(index);
+            }
+            let Some(source) = folded.as_ref().and_then(|index| index.get(&key)) else {
+                return Ok(None);
+            };
+            Ok(Some((source.clone(), Cow::Owned(self.targets(source)?))))
+        })
+    }
+
+    /// Appends every pair of `dict` with its count.
+    fn append_all(&mut self, dict: &TranslationDictionary) -> Result<(), DictionaryError> {
+        let mut sources = dict.iter().map(|(source, _)| source).collect::<Vec<_>>();
+        sources.sort();
+        for source in sources {
+            for target in dict.targets(source) {
+                self.append(source, &target.text, target.count)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies the whole store into memory.
+    fn to_dictionary(&self) -> Result<TranslationDictionary, DictionaryError> {
+        let mut dict = TranslationDictionary::default();
+        self.for_each(&mut |source, targets| {
+            for target in targets {
+                dict.record(source.clone(), target.text.clone(), target.count);
+            }
+        })?;
+        Ok(dict)
+    }
+}
+
+/// Opens the store at `path` by its extension: `.sqlite`, `.sqlite3` and
+/// `.db` are SQLite databases (with feature `sqlite`), anything else a
+/// dictionary file.
+pub fn open_store(path: &Path) -> Result<Box<dyn DictionaryStore + Send>, DictionaryError> {
+    #[cfg(feature = "sqlite")]
+    if is_sqlite_path(path) {
+        return Ok(Box::new(SqliteDictionaryStore::load(path)?));
+    }
+    Ok(Box::new(FileDictionaryStore::load(path)?))
+}
+
+/// Whether [`open_store`] treats `path` as an SQLite database.
+pub fn is_sqlite_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            matches!(
+                ext.to_ascii_lowercase().as_str(),
+                "sqlite" | "sqlite3" | "db"
+            )
+        })
+}
+
+/// A dictionary file ([`TranslationDictionary::save_to_path`]) held in
+/// memory until saved.
+#[derive(Debug, Clone)]
+pub struct FileDictionaryStore {
+    path: PathBuf,
+    dict: TranslationDictionary,
+}
+
+impl FileDictionaryStore {
+    /// Empty store written to `path` on save.
+    pub fn create(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            dict: TranslationDictionary::default(),
+        }
+    }
+
+    pub fn dictionary(&self) -> &TranslationDictionary {
+        &self.dict
+    }
+
+    pub fn into_dictionary(self) -> TranslationDictionary {
+        self.dict
+    }
+}
+
+impl DictionaryStore for FileDictionaryStore {
+    fn load(path: &Path) -> Result<Self, DictionaryError> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            dict: TranslationDictionary::load_from_path(path)?,
+        })
+    }
+
+    fn save(&mut self) -> Result<(), DictionaryError> {
+        self.dict.save_to_path(&self.path)
+    }
+
+    fn len(&self) -> Result<usize, DictionaryError> {
+        Ok(self.dict.len())
+    }
+
+    fn targets(&self, source: &str) -> Result<Vec<DictionaryTarget>, DictionaryError> {
+        Ok(self.dict.targets(source).to_vec())
+    }
+
+    fn for_each(
+        &self,
+        f: &mut dyn FnMut(&SharedText, &[DictionaryTarget]),
+    ) -> Result<(), DictionaryError> {
+        let mut sources = self
+            .dict
+            .iter()
+            .map(|(source, _)| source)
+            .collect::<Vec<_>>();
+        sources.sort();
+        for source in sources {
+            f(source, self.dict.targets(source));
+        }
+        Ok(())
+    }
+
+    fn append(&mut self, source: &str, target: &str, uses: u32) -> Result<(), DictionaryError> {
+        self.dict.record(source.into(), target.into(), uses);
+        Ok(())
+    }
+
+    fn apply_quick(
+        &self,
+        entries: &[Entry],
+        selected_keys: &[String],
+        only_untranslated: bool,
+        options: MatchOptions,
+    ) -> Result<(Vec<Entry>, Vec<Proposal>), DictionaryError> {
+        Ok(self
+            .dict
+            .apply_quick_with_proposals(entries, selected_keys, only_untranslated, options))
+    }
+
+    fn to_dictionary(&self) -> Result<TranslationDictionary, DictionaryError> {
+        Ok(self.dict.clone())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteDictionaryStore;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::*;
+    use rusqlite::{params, Connection, OpenFlags};
+
+    const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS pairs (
+        source TEXT NOT NULL,
+        target TEXT NOT NULL,
+        count INTEGER NOT NULL,
+        PRIMARY KEY (source, target)
+    );
+    CREATE TABLE IF NOT EXISTS folded_options (options INTEGER PRIMARY KEY);
+    CREATE TABLE IF NOT EXISTS folded (
+        options INTEGER NOT NULL,
+        key TEXT NOT NULL,
+        source TEXT NOT NULL,
+        PRIMARY KEY (options, key, source)
+    ) WITHOUT ROWID";
+
+    /// Pairs in the `pairs(source, target, count)` table of an SQLite
+    /// database. Appends go into one transaction committed on save; unsaved
+    /// ones are rolled back when the store is dropped.
+    ///
+    /// Folded sources live in the indexed `folded(options, key, source)`
+    /// table, filled for a [`MatchOptions`] on its first non-exact lookup
+    /// and kept up to date by later appends.
+    #[derive(Debug)]
+    pub struct SqliteDictionaryStore {
+        path: PathBuf,
+        conn: Connection,
+    }
+
+    impl SqliteDictionaryStore {
+        /// Opens the database at `path`, creating it if missing.
+        pub fn create(path: &Path) -> Result<Self, DictionaryError> {
+            Self::open(path, OpenFlags::SQLITE_OPEN_CREATE)
+        }
+
+        fn open(path: &Path, extra: OpenFlags) -> Result<Self, DictionaryError> {
+            let sql_error = |source| DictionaryError::Sqlite {
+                path: path.to_path_buf(),
+                source,
+            };
+            let flags = OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX | extra;
+            let conn = Connection::open_with_flags(path, flags).map_err(sql_error)?;
+            conn.execute_batch(SCHEMA).map_err(sql_error)?;
+            Ok(Self {
+                path: path.to_path_buf(),
+                conn,
+            })
+        }
+
+        fn sql_error(&self) -> impl FnOnce(rusqlite::Error) -> DictionaryError {
+            let path = self.path.clone();
+            move |source| DictionaryError::Sqlite { path, source }
+        }
+
+        /// Folds every source under `options` into the `folded` table, unless
+        /// already done. Runs in the pending append transaction if there is
+        /// one, so rolling that back drops the index too.
+        fn index_folded(&self, options: MatchOptions) -> Result<(), DictionaryError> {
+            let bits = options_bits(options);
+            let indexed = self
+                .conn
+                .prepare_cached("SELECT 1 FROM folded_options WHERE options = ?1")
+                .and_then(|mut statement| statement.exists([bits]))
+                .map_err(self.sql_error())?;
+            if indexed {
+                return Ok(());
+            }
+            let own_transaction = self.conn.is_autocommit();
+            if own_transaction {
+                self.conn.execute_batch("BEGIN").map_err(self.sql_error())?;
+            }
+            let filled = (|| {
+                let mut sources = self.conn.prepare("SELECT DISTINCT source FROM pairs")?;
+                let mut insert = self.conn.prepare_cached(
+                    "INSERT OR IGNORE INTO folded (options, key, source) VALUES (?1, ?2, ?3)",
+                )?;
+                let mut rows = sources.query([])?;
+                while let Some(row) = rows.next()? {
+                    let source = row.get::<_, String>(0)?;
+                    insert.execute(params![bits, fold_source(&source, options), source])?;
+                }
+                self.conn
+                    .execute("INSERT INTO folded_options (options) VALUES (?1)", [bits])?;
+                Ok(())
+            })();
+            if own_transaction {
+                let end = if filled.is_ok() { "COMMIT" } else { "ROLLBACK" };
+                self.conn.execute_batch(end).map_err(self.sql_error())?;
+            }
+            filled.map_err(self.sql_error())
+        }
+
+        /// Least source folding to `key` under `options`, as the in-memory
+        /// index (built in ascending source order) would pick.
+        fn folded_source(
+            &self,
+            options: MatchOptions,
+            key: &str,
+        ) -> Result<Option<String>, DictionaryError> {
+            self.conn
+                .prepare_cached("SELECT MIN(source) FROM folded WHERE options = ?1 AND key = ?2")
+                .and_then(|mut statement| {
+                    statement.query_row(params![options_bits(options), key], |row| row.get(0))
+                })
+                .map_err(self.sql_error())
+        }
+    }
+
+    fn options_bits(options: MatchOptions) -> i64 {
+        [
+            options.ignore_case,
+            options.ignore_width,
+            options.trim,
+            options.collapse_whitespace,
+            options.strip_trailing_punctuation,
+        ]
+        .iter()
+        .enumerate()
+        .fold(0, |bits, (bit, &on)| bits | (i64::from(on) << bit))
+    }
+
+    fn options_from_bits(bits: i64) -> MatchOptions {
+        let on = |bit: u32| bits & (1 << bit) != 0;
+        MatchOptions {
+            ignore_case: on(0),
+            ignore_width: on(1),
+            trim: on(2),
+            collapse_whitespace: on(3),
+            strip_trailing_punctuation: on(4),
+        }
+    }
+
+    impl DictionaryStore for SqliteDictionaryStore {
+        fn load(path: &Path) -> Result<Self, DictionaryError> {
+            Self::open(path, OpenFlags::empty())
+        }
+
+        fn save(&mut self) -> Result<(), DictionaryError> {
+            if !self.conn.is_autocommit() {
+                self.conn
+                    .execute_batch("COMMIT")
+                    .map_err(self.sql_error())?;
+            }
+            Ok(())
+        }
+
+        fn len(&self) -> Result<usize, DictionaryError> {
+            self.conn
+                .query_row("SELECT COUNT(DISTINCT source) FROM pairs", [], |row| {
+                    row.get::<_, i64>(0)
+                })
+                .map(|len| len as usize)
+                .map_err(self.sql_error())
+        }
+
+        fn targets(&self, source: &str) -> Result<Vec<DictionaryTarget>, DictionaryError> {
+            let mut statement = self
+                .conn
+                .prepare_cached(
+                    "SELECT target, count FROM pairs WHERE source = ?1 \
+                     ORDER BY count DESC, rowid",
+                )
+                .map_err(self.sql_error())?;
+            let rows = statement
+                .query_map([source], |row| {
+                    Ok(DictionaryTarget {
+                        text: row.get::<_, String>(0)?.into(),
+                        count: row.get(1)?,
+                    })
+                })
+                .map_err(self.sql_error())?;
+            rows.collect::<Result<_, _>>().map_err(self.sql_error())
+        }
+
+        fn for_each(
+            &self,
+            f: &mut dyn FnMut(&SharedText, &[DictionaryTarget]),
+        ) -> Result<(), DictionaryError> {
+            let mut statement = self
+                .conn
+                .prepare(
+                    "SELECT source, target, count FROM pairs ORDER BY source, count DESC, rowid",
+                )
+                .map_err(self.sql_error())?;
+            let mut rows = statement.query([]).map_err(self.sql_error())?;
+            let mut current: Option<(SharedText, Vec<DictionaryTarget>)> = None;
+            while let Some(row) = rows.next().map_err(self.sql_error())? {
+                let source = row.get::<_, String>(0).map_err(self.sql_error())?;
+                let target = DictionaryTarget {
+                    text: row.get::<_, String>(1).map_err(self.sql_error())?.into(),
+                    count: row.get(2).map_err(self.sql_error())?,
+                };
+                match &mut current {
+                    Some((known, targets)) if **known == *source => targets.push(target),
+                    _ => {
+                        if let Some((known, targets)) = current.take() {
+                            f(&known, &targets);
+                        }
+                        current = Some((source.into(), vec![target]));
+                    }
+                }
+            }
+            if let Some((source, targets)) = current {
+                f(&source, &targets);
+            }
+            Ok(())
+        }
+
+        fn append(&mut self, source: &str, target: &str, uses: u32) -> Result<(), DictionaryError> {
+            if self.conn.is_autocommit() {
+                self.conn.execute_batch("BEGIN").map_err(self.sql_error())?;
+            }
+            self.conn
+                .prepare_cached(
+                    "INSERT INTO pairs (source, target, count) VALUES (?1, ?2, ?3) \
+                     ON CONFLICT (source, target) DO UPDATE SET count = count + excluded.count",
+                )
+                .and_then(|mut statement| statement.execute(params![source, target, uses]))
+                .map_err(self.sql_error())?;
+            let indexed = self
+                .conn
+                .prepare_cached("SELECT options FROM folded_options")
+                .and_then(|mut statement| {
+                    statement
+                        .query_map([], |row| row.get::<_, i64>(0))?
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .map_err(self.sql_error())?;
+            for bits in indexed {
+                let key = fold_source(source, options_from_bits(bits));
+                self.conn
+                    .prepare_cached(
+                        "INSERT OR IGNORE INTO folded (options, key, source) VALUES (?1, ?2, ?3)",
+                    )
+                    .and_then(|mut statement| statement.execute(params![bits, key, source]))
+                    .map_err(self.sql_error())?;
+            }
+            Ok(())
+        }
+
+        fn apply_quick(
+            &self,
+            entries: &[Entry],
+            selected_keys: &[String],
+            only_untranslated: bool,
+            options: MatchOptions,
+        ) -> Result<(Vec<Entry>, Vec<Proposal>), DictionaryError> {
+            let mut indexed = false;
+            quick_proposals(entries, selected_keys, only_untranslated, |source| {
+                let targets = self.targets(source)?;
+                if !targets.is_empty() {
+                    return Ok(Some((SharedText::from(source), Cow::Owned(targets))));
+                }
+                let Some(key) = folded_key(source, options) else {
+                    return Ok(None);
+                };
+                if !indexed {
+                    self.index_folded(options)?;
+                    indexed = true;
+                }
+                let Some(source) = self.folded_source(options, &key)? else {
+                    return Ok(None);
+                };
+                let targets = self.targets(&source)?;
+                Ok(Some((source.into(), Cow::Owned(targets))))
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: &str, source: &str, target: &str) -> Entry {
+        Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        }
+    }
+
+    fn fill(store: &mut dyn DictionaryStore) {
+        store.append("Iron Sword", "鉄の剣", 1).expect("append");
+        store.append("Iron Sword", "鉄のソード", 2).expect("append");
+        store.append("Iron Sword", "鉄の剣", 2).expect("append");
+        store
+            .append("Gold <Alias=A>", "<Alias=A>の金", 1)
+            .expect("append");
+        store.append("Apple", "リンゴ", 1).expect("append");
+    }
+
+    fn check(store: &dyn DictionaryStore) {
+        assert_eq!(store.len().expect("len"), 3);
+        let targets = store.targets("Iron Sword").expect("targets");
+        let texts = targets.iter().map(|t| &*t.text).collect::<Vec<_>>();
+        assert_eq!(texts, ["鉄の剣", "鉄のソード"]);
+        assert_eq!(targets[0].count, 3);
+        assert!(store.targets("Steel Sword").expect("targets").is_empty());
+
+        let mut sources = Vec::new();
+        store
+            .for_each(&mut |source, targets| sources.push((source.to_string(), targets.len())))
+            .expect("for_each");
+        assert_eq!(
+            sources,
+            [
+                ("Apple".to_string(), 1),
+                ("Gold <Alias=A>".to_string(), 1),
+                ("Iron Sword".to_string(), 2),
+            ]
+        );
+
+        let memory = store.to_dictionary().expect("to_dictionary");
+        let entries = vec![
+            entry("k1", "Iron Sword", ""),
+            entry("k2", "iron sword", ""),
+            entry("k3", "Gold <Alias=B>", ""),
+            entry("k4", "Apple", "林檎"),
+            entry("k5", "Pear", ""),
+        ];
+        for options in [MatchOptions::EXACT, MatchOptions::LOOSE] {
+            let expected = memory.apply_quick_with_proposals(&entries, &[], true, options);
+            let actual = store
+                .apply_quick(&entries, &[], true, options)
+                .expect("apply_quick");
+            assert_eq!(actual, expected);
+        }
+        let (next, proposals) = store
+            .apply_quick(&entries, &[], true, MatchOptions::LOOSE)
+            .expect("apply_quick");
+        assert_eq!(next[0].target_text, "鉄の剣");
+        assert_eq!(next[1].target_text, "鉄の剣");
+        assert_eq!(next[2].target_text, "<Alias=B>の金");
+        assert_eq!(next[3].target_text, "林檎");
+        assert_eq!(proposals.len(), 4);
+    }
+
+    #[test]
+    fn t_dict_012_file_store_load_save_append_apply() {
+        let path = std::env::temp_dir().join(format!("xt_dict_store_{}.tsv", std::process::id()));
+        let mut store = FileDictionaryStore::create(&path);
+        fill(&mut store);
+        check(&store);
+        store.save().expect("save");
+        let store = open_store(&path).expect("reopen");
+        check(store.as_ref());
+        assert!(open_store(&path.with_extension("missing")).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn t_dict_013_sqlite_store_matches_file_store() {
+        let path =
+            std::env::temp_dir().join(format!("xt_dict_store_{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        assert!(open_store(&path).is_err());
+        {
+            let mut store = SqliteDictionaryStore::create(&path).expect("create");
+            fill(&mut store);
+            check(&store);
+        }
+        let store = open_store(&path).expect("reopen");
+        assert!(
+            store.is_empty().expect("is_empty"),
+            "unsaved appends dropped"
+        );
+        drop(store);
+
+        let mut store = SqliteDictionaryStore::create(&path).expect("create");
+        fill(&mut store);
+        store.save().expect("save");
+        drop(store);
+        let mut store = SqliteDictionaryStore::load(&path).expect("reopen");
+        check(&store);
+        store.append("STEEL SWORD", "鋼の剣", 1).expect("append");
+        let entries = [entry("k1", "steel sword", "")];
+        let (next, _) = store
+            .apply_quick(&entries, &[], true, MatchOptions::LOOSE)
+            .expect("apply_quick");
+        assert_eq!(
+            next[0].target_text, "鋼の剣",
+            "folded index follows appends"
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+}
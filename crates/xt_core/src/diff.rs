@@ -1,3 +1,8 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::formats::strings::{StringsEntry, StringsFile};
+use crate::model::{Entry, SharedText};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[allow(dead_code)]
 pub enum EntryStatus {
@@ -51,6 +56,362 @@ pub fn hash_source(text: &str) -> u64 {
     hash
 }
 
+/// Classification of an entry when comparing two versions of a plugin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginDiffKind {
+    Unchanged,
+    SourceChanged,
+    New,
+    Removed,
+}
+
+impl PluginDiffKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            PluginDiffKind::Unchanged => "変更なし",
+            PluginDiffKind::SourceChanged => "原文変更",
+            PluginDiffKind::New => "新規",
+            PluginDiffKind::Removed => "削除",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginDiffRow {
+    pub kind: PluginDiffKind,
+    /// Key in the new version; the old key for [`PluginDiffKind::Removed`].
+    pub key: String,
+    pub old_source: SharedText,
+    pub new_source: SharedText,
+    /// Translation of the old version, if any.
+    pub old_target: SharedText,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PluginDiffSummary {
+    pub unchanged: usize,
+    pub source_changed: usize,
+    pub added: usize,
+    pub removed: usize,
+    /// Unchanged rows whose translation was carried forward.
+    pub carried: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginDiff {
+    /// New-version entries; unchanged rows carry the old translation.
+    pub entries: Vec<Entry>,
+    /// Every old/new row in new-version order, removed rows last.
+    pub rows: Vec<PluginDiffRow>,
+    pub summary: PluginDiffSummary,
+}
+
+impl PluginDiff {
+    /// Rows that need attention (everything except unchanged).
+    pub fn review_rows(&self) -> impl Iterator<Item = &PluginDiffRow> {
+        self.rows
+            .iter()
+            .filter(|row| row.kind != PluginDiffKind::Unchanged)
+    }
+}
+
+/// Compares a translated old version against freshly extracted new entries.
+///
+/// ESP keys (`REC:FORMID:SUB:index`) are matched by form id, record and
+/// subrecord type plus the occurrence within that subrecord type, so strings
+/// added elsewhere in the record do not shift the match. Other keys are
+/// matched verbatim.
+pub fn diff_plugin_versions(old: &[Entry], new: &[Entry]) -> PluginDiff {
+    let old_ids = match_ids(old);
+    let new_ids = match_ids(new);
+    let by_id: HashMap<&str, usize> = old_ids
+        .iter()
+        .enumerate()
+        .map(|(idx, id)| (id.as_str(), idx))
+        .collect();
+    let mut matched = vec![false; old.len()];
+    let mut summary = PluginDiffSummary::default();
+    let mut rows = Vec::with_capacity(new.len());
+    let mut entries = Vec::with_capacity(new.len());
+
+    for (entry, id) in new.iter().zip(&new_ids) {
+        let mut next = entry.clone();
+        let row = match by_id.get(id.as_str()) {
+            Some(&old_idx) => {
+                matched[old_idx] = true;
+                let prev = &old[old_idx];
+                let kind = if prev.source_text == entry.source_text {
+                    summary.unchanged += 1;
+                    if next.target_text.is_empty() && !prev.target_text.is_empty() {
+                        next.target_text = prev.target_text.clone();
+                        summary.carried += 1;
+                    }
+                    PluginDiffKind::Unchanged
+                } else {
+                    summary.source_changed += 1;
+                    PluginDiffKind::SourceChanged
+                };
+                PluginDiffRow {
+                    kind,
+                    key: entry.key.clone(),
+                    old_source: prev.source_text.clone(),
+                    new_source: entry.source_text.clone(),
+                    old_target: prev.target_text.clone(),
+                }
+            }
+            None => {
+                summary.added += 1;
+                PluginDiffRow {
+                    kind: PluginDiffKind::New,
+                    key: entry.key.clone(),
+                    old_source: SharedText::default(),
+                    new_source: entry.source_text.clone(),
+                    old_target: SharedText::default(),
+                }
+            }
+        };
+        rows.push(row);
+        entries.push(next);
+    }
+    for (prev, _) in old.iter().zip(&matched).filter(|(_, seen)| !**seen) {
+        summary.removed += 1;
+        rows.push(PluginDiffRow {
+            kind: PluginDiffKind::Removed,
+            key: prev.key.clone(),
+            old_source: prev.source_text.clone(),
+            new_source: SharedText::default(),
+            old_target: prev.target_text.clone(),
+        });
+    }
+
+    PluginDiff {
+        entries,
+        rows,
+        summary,
+    }
+}
+
+fn match_ids(entries: &[Entry]) -> Vec<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    entries
+        .iter()
+        .map(|entry| match esp_group(&entry.key) {
+            Some(group) => {
+                let ordinal = seen.entry(group.clone()).or_default();
+                let id = format!("{group}#{ordinal}");
+                *ordinal += 1;
+                id
+            }
+            None => entry.key.clone(),
+        })
+        .collect()
+}
+
+/// `REC:FORMID:SUB` of an ESP entry key, without the record-wide index.
+fn esp_group(key: &str) -> Option<String> {
+    let mut parts = key.split(':');
+    let rec = parts.next()?;
+    let form_id = parts.next()?;
+    let sub = parts.next()?;
+    let index = parts.next()?;
+    let is_sig = |part: &str| part.len() == 4;
+    if parts.next().is_some()
+        || !is_sig(rec)
+        || !is_sig(sub)
+        || u32::from_str_radix(form_id, 16).is_err()
+        || index.parse::<usize>().is_err()
+    {
+        return None;
+    }
+    Some(format!("{rec}:{}:{sub}", form_id.to_ascii_uppercase()))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringsChange {
+    pub id: u32,
+    pub before: String,
+    pub after: String,
+}
+
+/// Id-level differences between two string tables, each list sorted by id.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StringsDiff {
+    pub added: Vec<StringsEntry>,
+    pub removed: Vec<StringsEntry>,
+    pub changed: Vec<StringsChange>,
+    pub unchanged: usize,
+}
+
+impl StringsDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Plain-text report, one line per differing id.
+    pub fn report(&self) -> String {
+        let mut out = format!(
+            "added={} removed={} changed={} unchanged={}\n",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len(),
+            self.unchanged
+        );
+        for entry in &self.added {
+            out.push_str(&format!("+ {:08X} {}\n", entry.id, one_line(&entry.text)));
+        }
+        for entry in &self.removed {
+            out.push_str(&format!("- {:08X} {}\n", entry.id, one_line(&entry.text)));
+        }
+        for change in &self.changed {
+            out.push_str(&format!(
+                "~ {:08X} {} => {}\n",
+                change.id,
+                one_line(&change.before),
+                one_line(&change.after)
+            ));
+        }
+        out
+    }
+}
+
+/// `text` with line breaks escaped (`\r`, `\n`), for one-line reports.
+pub fn one_line(text: &str) -> String {
+    text.replace('\r', "\\r").replace('\n', "\\n")
+}
+
+fn index_strings(file: &StringsFile) -> BTreeMap<u32, &StringsEntry> {
+    let mut map = BTreeMap::new();
+    for entry in &file.entries {
+        map.entry(entry.id).or_insert(entry);
+    }
+    map
+}
+
+/// Compares string tables by id; `a` is the old file and `b` the new one.
+/// When an id repeats, its first occurrence is used.
+pub fn diff_strings(a: &StringsFile, b: &StringsFile) -> StringsDiff {
+    let old = index_strings(a);
+    let new = index_strings(b);
+    let mut diff = StringsDiff::default();
+    for (id, entry) in &new {
+        match old.get(id) {
+            Some(prev) if prev.text == entry.text => diff.unchanged += 1,
+            Some(prev) => diff.changed.push(StringsChange {
+                id: *id,
+                before: prev.text.clone(),
+                after: entry.text.clone(),
+            }),
+            None => diff.added.push((*entry).clone()),
+        }
+    }
+    diff.removed = old
+        .iter()
+        .filter(|(id, _)| !new.contains_key(id))
+        .map(|(_, entry)| (*entry).clone())
+        .collect();
+    diff
+}
+
+/// Side of a [`TextSpan`] in a character diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanKind {
+    Equal,
+    /// Only in the new text.
+    Insert,
+    /// Only in the old text.
+    Delete,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextSpan {
+    pub kind: SpanKind,
+    pub text: String,
+}
+
+/// Middles (after the common prefix and suffix) larger than this many
+/// char pairs are reported as one deletion plus one insertion.
+const CHAR_DIFF_MAX_CELLS: usize = 4_000_000;
+
+/// Character-level diff from `old` to `new`: a longest common subsequence,
+/// with adjacent spans of the same kind merged. Concatenating the `Equal`
+/// and `Delete` spans gives `old`; `Equal` and `Insert` spans give `new`.
+pub fn char_diff(old: &str, new: &str) -> Vec<TextSpan> {
+    let old_chars = old.chars().collect::<Vec<_>>();
+    let new_chars = new.chars().collect::<Vec<_>>();
+    let prefix = old_chars
+        .iter()
+        .zip(&new_chars)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_chars[prefix..]
+        .iter()
+        .rev()
+        .zip(new_chars[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old_chars[prefix..old_chars.len() - suffix];
+    let new_mid = &new_chars[prefix..new_chars.len() - suffix];
+
+    let mut spans = Vec::new();
+    push_span(&mut spans, SpanKind::Equal, &old_chars[..prefix]);
+    if old_mid.len().saturating_mul(new_mid.len()) > CHAR_DIFF_MAX_CELLS {
+        push_span(&mut spans, SpanKind::Delete, old_mid);
+        push_span(&mut spans, SpanKind::Insert, new_mid);
+    } else {
+        lcs_spans(&mut spans, old_mid, new_mid);
+    }
+    push_span(
+        &mut spans,
+        SpanKind::Equal,
+        &old_chars[old_chars.len() - suffix..],
+    );
+    spans
+}
+
+fn lcs_spans(spans: &mut Vec<TextSpan>, old: &[char], new: &[char]) {
+    let width = new.len() + 1;
+    // lengths[i * width + j]: LCS length of old[i..] and new[j..].
+    let mut lengths = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * width + j] = if old[i] == new[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            push_span(spans, SpanKind::Equal, &old[i..=i]);
+            i += 1;
+            j += 1;
+        } else if j < new.len()
+            && (i == old.len() || lengths[i * width + j + 1] >= lengths[(i + 1) * width + j])
+        {
+            push_span(spans, SpanKind::Insert, &new[j..=j]);
+            j += 1;
+        } else {
+            push_span(spans, SpanKind::Delete, &old[i..=i]);
+            i += 1;
+        }
+    }
+}
+
+fn push_span(spans: &mut Vec<TextSpan>, kind: SpanKind, chars: &[char]) {
+    if chars.is_empty() {
+        return;
+    }
+    match spans.last_mut() {
+        Some(last) if last.kind == kind => last.text.extend(chars),
+        _ => spans.push(TextSpan {
+            kind,
+            text: chars.iter().collect(),
+        }),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -62,4 +423,143 @@ mod tests {
         update_source(&mut entry, "Hello world");
         assert_eq!(entry.status, EntryStatus::NeedsReview);
     }
+
+    fn entry(key: &str, source: &str, target: &str) -> Entry {
+        Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        }
+    }
+
+    #[test]
+    fn t_diff_002_plugin_versions_classify_and_carry() {
+        let old = vec![
+            entry("WEAP:00012EB7:FULL:0", "Iron Sword", "鉄の剣"),
+            entry("BOOK:00000D63:FULL:0", "Old Book", "古い本"),
+            entry("BOOK:00000D63:DESC:1", "Text", "本文"),
+            entry("QUST:00000D62:NNAM:0", "Gone", "消えた"),
+            entry("strings:7", "Same", "同じ"),
+        ];
+        let new = vec![
+            entry("WEAP:00012EB7:FULL:0", "Iron Sword", ""),
+            entry("BOOK:00000D63:FULL:0", "Old Book, 2nd Ed.", ""),
+            // An inserted subrecord shifts the record-wide index of DESC.
+            entry("BOOK:00000D63:CNAM:1", "Inserted", ""),
+            entry("BOOK:00000D63:DESC:2", "Text", ""),
+            entry("strings:7", "Same", ""),
+            entry("strings:8", "Brand new", ""),
+        ];
+        let diff = diff_plugin_versions(&old, &new);
+
+        assert_eq!(
+            diff.summary,
+            PluginDiffSummary {
+                unchanged: 3,
+                source_changed: 1,
+                added: 2,
+                removed: 1,
+                carried: 3,
+            }
+        );
+        let targets = diff
+            .entries
+            .iter()
+            .map(|e| e.target_text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(targets, vec!["鉄の剣", "", "", "本文", "同じ", ""]);
+        let review = diff
+            .review_rows()
+            .map(|row| (row.kind, row.key.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            review,
+            vec![
+                (PluginDiffKind::SourceChanged, "BOOK:00000D63:FULL:0"),
+                (PluginDiffKind::New, "BOOK:00000D63:CNAM:1"),
+                (PluginDiffKind::New, "strings:8"),
+                (PluginDiffKind::Removed, "QUST:00000D62:NNAM:0"),
+            ]
+        );
+        assert_eq!(diff.rows[1].old_target, "古い本");
+    }
+
+    #[test]
+    fn t_diff_003_strings_added_removed_changed() {
+        let file = |items: &[(u32, &str)]| {
+            StringsFile::new(
+                items
+                    .iter()
+                    .map(|(id, text)| StringsEntry {
+                        id: *id,
+                        text: text.to_string(),
+                    })
+                    .collect(),
+            )
+        };
+        let a = file(&[(3, "Iron"), (1, "Steel"), (2, "Gone"), (3, "dup ignored")]);
+        let b = file(&[(1, "Steel"), (3, "Iron\nSword"), (9, "New")]);
+        let diff = diff_strings(&a, &b);
+
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.added.iter().map(|e| e.id).collect::<Vec<_>>(), vec![9]);
+        assert_eq!(
+            diff.removed.iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![2]
+        );
+        assert_eq!(
+            diff.changed,
+            vec![StringsChange {
+                id: 3,
+                before: "Iron".to_string(),
+                after: "Iron\nSword".to_string(),
+            }]
+        );
+        assert_eq!(
+            diff.report(),
+            "added=1 removed=1 changed=1 unchanged=1\n\
+             + 00000009 New\n\
+             - 00000002 Gone\n\
+             ~ 00000003 Iron => Iron\\nSword\n"
+        );
+        assert!(diff_strings(&a, &a).is_empty());
+    }
+
+    #[test]
+    fn t_diff_004_char_diff_reconstructs_both_texts() {
+        let rebuild = |spans: &[TextSpan], skip: SpanKind| {
+            spans
+                .iter()
+                .filter(|span| span.kind != skip)
+                .map(|span| span.text.as_str())
+                .collect::<String>()
+        };
+        for (old, new) in [
+            ("Iron Sword", "Steel Sword"),
+            ("鉄の剣を手に入れた", "鋼鉄の剣を手に入れた。"),
+            ("", "new"),
+            ("same", "same"),
+            ("abcdef", "azced"),
+        ] {
+            let spans = char_diff(old, new);
+            assert_eq!(rebuild(&spans, SpanKind::Insert), old);
+            assert_eq!(rebuild(&spans, SpanKind::Delete), new);
+            assert!(spans.windows(2).all(|pair| pair[0].kind != pair[1].kind));
+        }
+        let spans = char_diff("Iron Sword", "Steel Sword");
+        assert_eq!(spans.last().map(|span| span.text.as_str()), Some(" Sword"));
+        assert_eq!(
+            char_diff("鉄の剣", "鋼鉄の剣"),
+            vec![
+                TextSpan {
+                    kind: SpanKind::Insert,
+                    text: "鋼".to_string()
+                },
+                TextSpan {
+                    kind: SpanKind::Equal,
+                    text: "鉄の剣".to_string()
+                },
+            ]
+        );
+    }
 }
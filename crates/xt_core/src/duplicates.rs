@@ -0,0 +1,136 @@
+//! Rows sharing one source text (重複原文), e.g. an item name used by many
+//! records, so it can be translated once for all of them.
+
+use std::collections::HashMap;
+
+use crate::model::{Entry, SharedText};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateSource {
+    pub source: SharedText,
+    /// Indices of the rows with this source, in entry order.
+    pub rows: Vec<usize>,
+}
+
+impl DuplicateSource {
+    /// Distinct non-empty targets of the rows, in entry order.
+    pub fn targets<'a>(&self, entries: &'a [Entry]) -> Vec<&'a SharedText> {
+        let mut targets: Vec<&SharedText> = Vec::new();
+        for &row in &self.rows {
+            let target = &entries[row].target_text;
+            if !target.is_empty() && !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        targets
+    }
+
+    /// Distinct non-empty targets with how many rows use each, the most
+    /// used first (then in entry order).
+    pub fn target_counts<'a>(&self, entries: &'a [Entry]) -> Vec<(&'a SharedText, usize)> {
+        let mut counts: Vec<(&SharedText, usize)> = Vec::new();
+        for &row in &self.rows {
+            let target = &entries[row].target_text;
+            if target.is_empty() {
+                continue;
+            }
+            match counts.iter_mut().find(|(seen, _)| *seen == target) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((target, 1)),
+            }
+        }
+        counts.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+        counts
+    }
+
+    /// The rows do not agree on a target (訳揺れ).
+    pub fn is_inconsistent(&self, entries: &[Entry]) -> bool {
+        self.targets(entries).len() > 1
+    }
+
+    pub fn untranslated(&self, entries: &[Entry]) -> usize {
+        self.rows
+            .iter()
+            .filter(|&&row| entries[row].target_text.is_empty())
+            .count()
+    }
+}
+
+/// Source texts that more than one row has, the most repeated first (then
+/// by first row). Blank sources are not grouped.
+pub fn duplicate_sources(entries: &[Entry]) -> Vec<DuplicateSource> {
+    let mut by_source: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        if !entry.source_text.trim().is_empty() {
+            by_source
+                .entry(entry.source_text.as_str())
+                .or_default()
+                .push(index);
+        }
+    }
+    let mut groups = by_source
+        .into_values()
+        .filter(|rows| rows.len() > 1)
+        .map(|rows| DuplicateSource {
+            source: entries[rows[0]].source_text.clone(),
+            rows,
+        })
+        .collect::<Vec<_>>();
+    groups.sort_by_key(|group| (std::cmp::Reverse(group.rows.len()), group.rows[0]));
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: &str, source: &str, target: &str) -> Entry {
+        Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        }
+    }
+
+    #[test]
+    fn t_dup_001_rows_grouped_by_identical_source() {
+        let entries = vec![
+            entry("k0", "Iron Sword", "鉄の剣"),
+            entry("k1", "Gold", ""),
+            entry("k2", "Iron Sword", ""),
+            entry("k3", "Gold", "金貨"),
+            entry("k4", "iron sword", ""),
+            entry("k5", "Gold", "ゴールド"),
+            entry("k6", "", ""),
+            entry("k7", "", ""),
+            entry("k8", "Iron Sword", "鉄の剣"),
+        ];
+        let groups = duplicate_sources(&entries);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].source, "Iron Sword");
+        assert_eq!(groups[0].rows, [0, 2, 8]);
+        assert_eq!(groups[0].targets(&entries), ["鉄の剣"]);
+        assert_eq!(groups[0].untranslated(&entries), 1);
+        assert_eq!(groups[1].source, "Gold");
+        assert_eq!(groups[1].targets(&entries), ["金貨", "ゴールド"]);
+    }
+
+    #[test]
+    fn t_dup_002_differing_targets_counted() {
+        let entries = vec![
+            entry("k0", "Gold", "金"),
+            entry("k1", "Gold", "ゴールド"),
+            entry("k2", "Gold", ""),
+            entry("k3", "Gold", "ゴールド"),
+            entry("k4", "Iron", "鉄"),
+            entry("k5", "Iron", ""),
+        ];
+        let groups = duplicate_sources(&entries);
+        assert!(groups[0].is_inconsistent(&entries));
+        assert_eq!(
+            groups[0].target_counts(&entries),
+            [(&"ゴールド".into(), 2), (&"金".into(), 1)]
+        );
+        assert!(!groups[1].is_inconsistent(&entries));
+    }
+}
@@ -5,9 +5,36 @@ pub enum Encoding {
     Latin1,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+impl Encoding {
+    pub const ALL: [Encoding; 2] = [Encoding::Utf8, Encoding::Latin1];
+
+    /// Key used in prefs files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "utf8",
+            Encoding::Latin1 => "latin1",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|encoding| encoding.name() == name)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Latin1 => "Latin-1",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
 pub enum EncodingError {
+    #[error("invalid utf-8")]
     InvalidUtf8,
+    #[error("character not representable in the target encoding")]
     UnrepresentableChar,
 }
 
@@ -0,0 +1,241 @@
+//! On-disk cache of plugin extraction results, so loading an unchanged
+//! plugin again skips parsing it.
+//!
+//! Each plugin path (per game and Strings language) has one cache file. It
+//! starts with the size and a hash of the plugin and the Strings files it was
+//! extracted with; a [`CacheKey`] that differs is a miss, and the next store
+//! replaces the stale file.
+
+use std::path::{Path, PathBuf};
+
+use crate::formats::esp::{write_atomic, ExtractedString, StringStorage, StringsKind};
+use crate::game::GameProfile;
+
+/// Extension of the cache files in an [`ExtractCache`] folder.
+pub const CACHE_EXTENSION: &str = "xtcache";
+const MAGIC: &[u8; 4] = b"XTEC";
+/// Bumped whenever the layout or what extraction yields changes.
+const FORMAT_VERSION: u32 = 1;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Which cache file a plugin uses and the contents it must have been
+/// extracted from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    file_name: String,
+    size: u64,
+    hash: u64,
+}
+
+impl CacheKey {
+    /// Key of the plugin at `plugin_path` with contents `plugin`, read with
+    /// the Strings file contents `strings` as `profile` in `language`.
+    pub fn new(
+        plugin_path: &Path,
+        profile: GameProfile,
+        language: &str,
+        plugin: &[u8],
+        strings: &[Vec<u8>],
+    ) -> Self {
+        let slot = format!(
+            "{}\0{}\0{}",
+            plugin_path.to_string_lossy(),
+            profile.name(),
+            language.to_lowercase()
+        );
+        let mut hash = fnv1a(FNV_OFFSET, plugin);
+        for file in strings {
+            hash = fnv1a(hash, &(file.len() as u64).to_le_bytes());
+            hash = fnv1a(hash, file);
+        }
+        Self {
+            file_name: format!(
+                "{:016x}.{CACHE_EXTENSION}",
+                fnv1a(FNV_OFFSET, slot.as_bytes())
+            ),
+            size: plugin.len() as u64,
+            hash,
+        }
+    }
+}
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// Folder of cached extraction results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractCache {
+    dir: PathBuf,
+}
+
+impl ExtractCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The strings stored for `key`, or `None` when there are none, they
+    /// belong to other contents or the file cannot be read.
+    pub fn load(&self, key: &CacheKey) -> Option<Vec<ExtractedString>> {
+        let bytes = std::fs::read(self.dir.join(&key.file_name)).ok()?;
+        decode(&bytes, key)
+    }
+
+    /// Stores `strings` for `key`, replacing what the plugin's file held.
+    pub fn store(&self, key: &CacheKey, strings: &[ExtractedString]) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        write_atomic(&self.dir.join(&key.file_name), encode(key, strings))
+    }
+
+    /// Deletes every cache file; returns how many there were.
+    pub fn clear(&self) -> std::io::Result<usize> {
+        let items = match std::fs::read_dir(&self.dir) {
+            Ok(items) => items,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err),
+        };
+        let mut removed = 0;
+        for item in items {
+            let path = item?.path();
+            if path.extension().is_some_and(|ext| ext == CACHE_EXTENSION) {
+                std::fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+fn encode(key: &CacheKey, strings: &[ExtractedString]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    out.extend_from_slice(&key.size.to_le_bytes());
+    out.extend_from_slice(&key.hash.to_le_bytes());
+    out.extend_from_slice(&(strings.len() as u64).to_le_bytes());
+    for item in strings {
+        out.extend_from_slice(&item.record_type);
+        out.extend_from_slice(&item.subrecord_type);
+        out.extend_from_slice(&item.form_id.to_le_bytes());
+        out.extend_from_slice(&(item.index as u64).to_le_bytes());
+        out.extend_from_slice(&item.record_flags.to_le_bytes());
+        let (tag, id) = match item.storage {
+            StringStorage::Inline => (0u8, 0),
+            StringStorage::Localized { kind, id } => (
+                match kind {
+                    StringsKind::Strings => 1,
+                    StringsKind::DlStrings => 2,
+                    StringsKind::IlStrings => 3,
+                },
+                id,
+            ),
+        };
+        out.push(tag);
+        out.extend_from_slice(&id.to_le_bytes());
+        push_text(&mut out, &item.text);
+        match &item.editor_id {
+            Some(editor_id) => {
+                out.push(1);
+                push_text(&mut out, editor_id);
+            }
+            None => out.push(0),
+        }
+    }
+    out
+}
+
+fn push_text(out: &mut Vec<u8>, text: &str) {
+    out.extend_from_slice(&(text.len() as u32).to_le_bytes());
+    out.extend_from_slice(text.as_bytes());
+}
+
+fn decode(bytes: &[u8], key: &CacheKey) -> Option<Vec<ExtractedString>> {
+    let mut reader = Reader { bytes, offset: 0 };
+    if reader.take(4)? != MAGIC
+        || reader.u32()? != FORMAT_VERSION
+        || reader.u64()? != key.size
+        || reader.u64()? != key.hash
+    {
+        return None;
+    }
+    let count = usize::try_from(reader.u64()?).ok()?;
+    let mut strings = Vec::with_capacity(count.min(bytes.len()));
+    for _ in 0..count {
+        let record_type = reader.tag()?;
+        let subrecord_type = reader.tag()?;
+        let form_id = reader.u32()?;
+        let index = usize::try_from(reader.u64()?).ok()?;
+        let record_flags = reader.u32()?;
+        let tag = reader.take(1)?[0];
+        let id = reader.u32()?;
+        let storage = match tag {
+            0 => StringStorage::Inline,
+            1 => StringStorage::Localized {
+                kind: StringsKind::Strings,
+                id,
+            },
+            2 => StringStorage::Localized {
+                kind: StringsKind::DlStrings,
+                id,
+            },
+            3 => StringStorage::Localized {
+                kind: StringsKind::IlStrings,
+                id,
+            },
+            _ => return None,
+        };
+        let text = reader.text()?;
+        let editor_id = match reader.take(1)?[0] {
+            0 => None,
+            1 => Some(reader.text()?),
+            _ => return None,
+        };
+        strings.push(
+            ExtractedString::new(record_type, subrecord_type, form_id, index, text, storage)
+                .with_editor_id(editor_id)
+                .with_record_flags(record_flags),
+        );
+    }
+    (reader.offset == bytes.len()).then_some(strings)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.offset.checked_add(len)?;
+        let slice = self.bytes.get(self.offset..end)?;
+        self.offset = end;
+        Some(slice)
+    }
+
+    fn tag(&mut self) -> Option<[u8; 4]> {
+        self.take(4)?.try_into().ok()
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn text(&mut self) -> Option<String> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+}
@@ -0,0 +1,179 @@
+//! Dynamic String Distributor (DSD) JSON export.
+//!
+//! DSD reads `Data/SKSE/Plugins/DynamicStringDistributor/<Plugin>/*.json` and
+//! replaces strings at runtime by form id + record/subrecord type.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::formats::esp::ExtractedString;
+use crate::model::Entry;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DsdExportParams {
+    /// File name of the translated plugin, e.g. `MyMod.esp`.
+    pub plugin_name: String,
+    /// Masters from the plugin header, used to resolve the form id owner.
+    pub masters: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DsdExportStats {
+    pub written: usize,
+    /// Untranslated entries or entries without plugin metadata.
+    pub skipped: usize,
+}
+
+/// One replacement in a DSD file.
+#[derive(Serialize)]
+struct DsdItem<'a> {
+    form_id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    /// Which of the record's same-type subrecords, when it has several.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    index: Option<usize>,
+    original: &'a str,
+    string: &'a str,
+}
+
+/// Directory DSD scans for the given plugin, relative to the game root.
+pub fn dsd_output_dir(plugin_name: &str) -> String {
+    format!("Data/SKSE/Plugins/DynamicStringDistributor/{plugin_name}")
+}
+
+/// Writes translated entries as a DSD JSON array.
+pub fn export_dsd_json(
+    entries: &[Entry],
+    strings: &[ExtractedString],
+    params: &DsdExportParams,
+) -> (String, DsdExportStats) {
+    let by_key: HashMap<String, &ExtractedString> = strings
+        .iter()
+        .map(|string| (string.get_unique_key(), string))
+        .collect();
+    let mut totals: HashMap<([u8; 4], u32, [u8; 4]), usize> = HashMap::new();
+    for string in strings {
+        *totals
+            .entry((string.record_type, string.form_id, string.subrecord_type))
+            .or_default() += 1;
+    }
+    let mut ordinals: HashMap<String, usize> = HashMap::new();
+    let mut seen: HashMap<([u8; 4], u32, [u8; 4]), usize> = HashMap::new();
+    for string in strings {
+        let group = (string.record_type, string.form_id, string.subrecord_type);
+        let ordinal = seen.entry(group).or_default();
+        ordinals.insert(string.get_unique_key(), *ordinal);
+        *ordinal += 1;
+    }
+
+    let mut stats = DsdExportStats::default();
+    let mut items = Vec::new();
+    for entry in entries {
+        let Some(string) = by_key.get(&entry.key) else {
+            stats.skipped += 1;
+            continue;
+        };
+        if entry.target_text.is_empty() {
+            stats.skipped += 1;
+            continue;
+        }
+        let group = (string.record_type, string.form_id, string.subrecord_type);
+        let index = (totals.get(&group).copied().unwrap_or(0) > 1)
+            .then(|| ordinals.get(&entry.key).copied().unwrap_or(0));
+        items.push(DsdItem {
+            form_id: dsd_form_id(string.form_id, params),
+            kind: format!(
+                "{} {}",
+                String::from_utf8_lossy(&string.record_type),
+                String::from_utf8_lossy(&string.subrecord_type)
+            ),
+            index,
+            original: &entry.source_text,
+            string: &entry.target_text,
+        });
+        stats.written += 1;
+    }
+
+    // Strings and integers only, which always serialize.
+    let mut json = serde_json::to_string_pretty(&items).expect("serialize dsd items");
+    json.push('\n');
+    (json, stats)
+}
+
+/// `FFFFFF|Owner.esm`, where the owner is the master at the form id's load
+/// order index or the plugin itself.
+fn dsd_form_id(form_id: u32, params: &DsdExportParams) -> String {
+    let master_index = (form_id >> 24) as usize;
+    let owner = params
+        .masters
+        .get(master_index)
+        .map(String::as_str)
+        .unwrap_or(params.plugin_name.as_str());
+    format!("{:06X}|{owner}", form_id & 0x00FF_FFFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::esp::StringStorage;
+
+    fn string(rec: &[u8; 4], sub: &[u8; 4], form_id: u32, index: usize) -> ExtractedString {
+        ExtractedString::new(
+            *rec,
+            *sub,
+            form_id,
+            index,
+            "src".to_string(),
+            StringStorage::Inline,
+        )
+    }
+
+    #[test]
+    fn t_dsd_001_export_form_ids_and_types() {
+        let strings = vec![
+            string(b"WEAP", b"FULL", 0x0001_2EB7, 0),
+            string(b"QUST", b"NNAM", 0x0100_0D62, 0),
+            string(b"QUST", b"NNAM", 0x0100_0D62, 1),
+            string(b"BOOK", b"DESC", 0x0100_0D63, 0),
+        ];
+        let entries = vec![
+            Entry {
+                key: strings[0].get_unique_key(),
+                source_text: "Iron Sword".into(),
+                target_text: "鉄の剣".into(),
+            },
+            Entry {
+                key: strings[2].get_unique_key(),
+                source_text: "Go \"there\"".into(),
+                target_text: "行け\n「そこ」".into(),
+            },
+            Entry {
+                key: strings[3].get_unique_key(),
+                source_text: "Book".into(),
+                target_text: Default::default(),
+            },
+            Entry {
+                key: "strings:7".to_string(),
+                source_text: "x".into(),
+                target_text: "y".into(),
+            },
+        ];
+        let params = DsdExportParams {
+            plugin_name: "MyMod.esp".to_string(),
+            masters: vec!["Skyrim.esm".to_string()],
+        };
+        let (json, stats) = export_dsd_json(&entries, &strings, &params);
+
+        assert_eq!(stats.written, 2);
+        assert_eq!(stats.skipped, 2);
+        assert!(json.contains(r#""form_id": "012EB7|Skyrim.esm""#));
+        assert!(json.contains(r#""type": "WEAP FULL""#));
+        assert!(json.contains(r#""form_id": "000D62|MyMod.esp""#));
+        assert!(json.contains(r#""index": 1"#));
+        assert!(json.contains(r#""original": "Go \"there\"""#));
+        assert!(json.contains(r#""string": "行け\n「そこ」""#));
+        assert!(json.starts_with("[\n") && json.ends_with("]\n"));
+    }
+}
@@ -1,3 +1,51 @@
+use std::collections::HashMap;
+
+use crate::model::Entry;
+
+#[cfg(not(target_arch = "wasm32"))]
 pub use xt_esp::{
-    apply_translations, extract_strings, EspError, ExtractedString, StringStorage, StringsKind,
+    apply_translations, available_languages, delocalize_plugin, extract_strings,
+    extract_strings_cancelable, extract_strings_lenient, localize_plugin, read_masters,
+    read_plugin_info, strings_file_paths, write_atomic, write_atomic_with,
 };
+pub use xt_esp::{
+    apply_translations_to_bytes, extract_strings_from_bytes, extract_strings_lenient_from_bytes,
+    parse_plugin_info, record_flag_names, CancelToken, Canceled, EspError, ExtractedString,
+    GameProfile, LocalizationReport, NoProgress, PartialExtraction, PluginInfo, PluginStrings,
+    ProgressSink, StringStorage, StringsKind, StringsLocation, PLUGIN_FLAG_LIGHT,
+    PLUGIN_FLAG_LOCALIZED, PLUGIN_FLAG_MASTER,
+};
+
+/// Entries for extracted strings, keyed by [`ExtractedString::get_unique_key`]
+/// and not yet translated.
+pub fn plugin_entries(extracted: &[ExtractedString]) -> Vec<Entry> {
+    extracted
+        .iter()
+        .map(|item| Entry {
+            key: item.get_unique_key(),
+            source_text: item.text.as_str().into(),
+            target_text: Default::default(),
+        })
+        .collect()
+}
+
+/// `extracted` with texts replaced by the non-empty targets of `entries`.
+pub fn translated_strings(
+    entries: &[Entry],
+    extracted: &[ExtractedString],
+) -> Vec<ExtractedString> {
+    let mut targets: HashMap<&str, &str> = HashMap::new();
+    for entry in entries {
+        if !entry.target_text.is_empty() {
+            targets.insert(entry.key.as_str(), entry.target_text.as_str());
+        }
+    }
+    let mut translated = extracted.to_vec();
+    for item in &mut translated {
+        let key = item.get_unique_key();
+        if let Some(target) = targets.get(key.as_str()) {
+            item.text = (*target).to_string();
+        }
+    }
+    translated
+}
@@ -1,3 +1,4 @@
+pub mod dsd;
 pub mod esp;
 pub mod plugin;
 pub mod plugin_binary;
@@ -10,13 +10,19 @@ pub struct PluginFile {
     pub entries: Vec<PluginEntry>,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
 pub enum PluginError {
+    #[error("missing XTPLUGIN1 header")]
     InvalidHeader,
-    InvalidLine,
-    InvalidId,
+    /// `line` (1-based) does not have the `id|context|source` fields.
+    #[error("line {line}: expected id|context|source")]
+    InvalidLine { line: usize },
+    #[error("line {line}: invalid id")]
+    InvalidId { line: usize },
+    #[error("duplicate id: {0}")]
     DuplicateId(u32),
-    InvalidField,
+    #[error("entry {id}: context or source contains '|'")]
+    InvalidField { id: u32 },
 }
 
 pub fn read_plugin(input: &str) -> Result<PluginFile, PluginError> {
@@ -27,15 +33,24 @@ pub fn read_plugin(input: &str) -> Result<PluginFile, PluginError> {
     }
 
     let mut entries = Vec::new();
-    for line in lines {
+    for (index, line) in lines.enumerate() {
         if line.trim().is_empty() {
             continue;
         }
+        let line_no = index + 2;
         let mut parts = line.splitn(3, '|');
-        let id_str = parts.next().ok_or(PluginError::InvalidLine)?;
-        let context = parts.next().ok_or(PluginError::InvalidLine)?;
-        let source_text = parts.next().ok_or(PluginError::InvalidLine)?;
-        let id = id_str.parse::<u32>().map_err(|_| PluginError::InvalidId)?;
+        let id_str = parts
+            .next()
+            .ok_or(PluginError::InvalidLine { line: line_no })?;
+        let context = parts
+            .next()
+            .ok_or(PluginError::InvalidLine { line: line_no })?;
+        let source_text = parts
+            .next()
+            .ok_or(PluginError::InvalidLine { line: line_no })?;
+        let id = id_str
+            .parse::<u32>()
+            .map_err(|_| PluginError::InvalidId { line: line_no })?;
         entries.push(PluginEntry {
             id,
             context: context.to_string(),
@@ -59,7 +74,7 @@ pub fn write_plugin(file: &PluginFile) -> Result<String, PluginError> {
     out.push_str("XTPLUGIN1\n");
     for entry in entries {
         if entry.context.contains('|') || entry.source_text.contains('|') {
-            return Err(PluginError::InvalidField);
+            return Err(PluginError::InvalidField { id: entry.id });
         }
         out.push_str(&format!(
             "{}|{}|{}\n",
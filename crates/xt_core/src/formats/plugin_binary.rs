@@ -5,10 +5,12 @@ pub struct ExtractedText {
     pub text: String,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
 #[allow(dead_code)]
 pub enum PluginBinaryError {
+    #[error("invalid utf-8")]
     InvalidUtf8,
+    #[error("replacement length differs from the original text")]
     LengthMismatch,
 }
 
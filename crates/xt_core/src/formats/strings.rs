@@ -1,215 +1,14 @@
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct StringsEntry {
-    pub id: u32,
-    pub text: String,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
-pub struct StringsFile {
-    pub entries: Vec<StringsEntry>,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum StringsError {
-    UnexpectedEof,
-    InvalidHeader,
-    InvalidOffset,
-    InvalidLength,
-    MissingTerminator,
-    Utf8,
-    DuplicateId(u32),
-}
-
-pub fn read_strings(input: &[u8]) -> Result<StringsFile, StringsError> {
-    if input.len() < 8 {
-        return Err(StringsError::InvalidHeader);
-    }
-    let count = read_u32(input, 0)?;
-    let data_size = read_u32(input, 4)? as usize;
-    let directory_size = count.checked_mul(8).ok_or(StringsError::InvalidHeader)? as usize;
-    let data_start = 8usize
-        .checked_add(directory_size)
-        .ok_or(StringsError::InvalidHeader)?;
-    let data_end = data_start
-        .checked_add(data_size)
-        .ok_or(StringsError::InvalidHeader)?;
-    if data_end > input.len() {
-        return Err(StringsError::UnexpectedEof);
-    }
-
-    let mut entries = Vec::with_capacity(count as usize);
-    for i in 0..count as usize {
-        let base = 8usize + i * 8;
-        let id = read_u32(input, base)?;
-        let offset = read_u32(input, base + 4)? as usize;
-        if offset >= data_size {
-            return Err(StringsError::InvalidOffset);
-        }
-        let start = data_start + offset;
-        let mut end = start;
-        while end < data_end && input[end] != 0 {
-            end += 1;
-        }
-        if end >= data_end {
-            return Err(StringsError::MissingTerminator);
-        }
-        let text = std::str::from_utf8(&input[start..end])
-            .map_err(|_| StringsError::Utf8)?
-            .to_string();
-        entries.push(StringsEntry { id, text });
-    }
-
-    Ok(StringsFile { entries })
-}
-
-pub fn read_dlstrings(input: &[u8]) -> Result<StringsFile, StringsError> {
-    read_length_prefixed_strings(input)
-}
-
-pub fn read_ilstrings(input: &[u8]) -> Result<StringsFile, StringsError> {
-    read_length_prefixed_strings(input)
-}
-
-pub fn write_strings(file: &StringsFile) -> Result<Vec<u8>, StringsError> {
-    let mut entries = file.entries.clone();
-    entries.sort_by_key(|entry| entry.id);
-    for window in entries.windows(2) {
-        if window[0].id == window[1].id {
-            return Err(StringsError::DuplicateId(window[0].id));
-        }
-    }
-
-    let mut directory = Vec::with_capacity(entries.len());
-    let mut data_block: Vec<u8> = Vec::new();
-    for entry in &entries {
-        let offset = data_block.len() as u32;
-        data_block.extend_from_slice(entry.text.as_bytes());
-        data_block.push(0);
-        directory.push((entry.id, offset));
-    }
-
-    let count = entries.len() as u32;
-    let data_size = data_block.len() as u32;
-    let mut output = Vec::with_capacity(8 + directory.len() * 8 + data_block.len());
-    output.extend_from_slice(&count.to_le_bytes());
-    output.extend_from_slice(&data_size.to_le_bytes());
-    for (id, offset) in directory {
-        output.extend_from_slice(&id.to_le_bytes());
-        output.extend_from_slice(&offset.to_le_bytes());
-    }
-    output.extend_from_slice(&data_block);
-
-    Ok(output)
-}
-
-pub fn write_dlstrings(file: &StringsFile) -> Result<Vec<u8>, StringsError> {
-    write_length_prefixed_strings(file)
-}
-
-pub fn write_ilstrings(file: &StringsFile) -> Result<Vec<u8>, StringsError> {
-    write_length_prefixed_strings(file)
-}
-
-fn read_u32(input: &[u8], offset: usize) -> Result<u32, StringsError> {
-    if offset + 4 > input.len() {
-        return Err(StringsError::UnexpectedEof);
-    }
-    let mut bytes = [0u8; 4];
-    bytes.copy_from_slice(&input[offset..offset + 4]);
-    Ok(u32::from_le_bytes(bytes))
-}
-
-fn read_length_prefixed_strings(input: &[u8]) -> Result<StringsFile, StringsError> {
-    if input.len() < 8 {
-        return Err(StringsError::InvalidHeader);
-    }
-    let count = read_u32(input, 0)?;
-    let data_size = read_u32(input, 4)? as usize;
-    let directory_size = count.checked_mul(8).ok_or(StringsError::InvalidHeader)? as usize;
-    let data_start = 8usize
-        .checked_add(directory_size)
-        .ok_or(StringsError::InvalidHeader)?;
-    let data_end = data_start
-        .checked_add(data_size)
-        .ok_or(StringsError::InvalidHeader)?;
-    if data_end > input.len() {
-        return Err(StringsError::UnexpectedEof);
-    }
-
-    let mut entries = Vec::with_capacity(count as usize);
-    for i in 0..count as usize {
-        let base = 8usize + i * 8;
-        let id = read_u32(input, base)?;
-        let offset = read_u32(input, base + 4)? as usize;
-        if offset >= data_size {
-            return Err(StringsError::InvalidOffset);
-        }
-        let len_offset = data_start + offset;
-        let len = read_u32(input, len_offset)? as usize;
-        if len == 0 {
-            return Err(StringsError::InvalidLength);
-        }
-        let text_start = len_offset + 4;
-        let text_end = text_start
-            .checked_add(len)
-            .ok_or(StringsError::UnexpectedEof)?;
-        if text_end > data_end {
-            return Err(StringsError::UnexpectedEof);
-        }
-        let slice = &input[text_start..text_end];
-        if *slice.last().unwrap_or(&0) != 0 {
-            return Err(StringsError::MissingTerminator);
-        }
-        let text = std::str::from_utf8(&slice[..slice.len() - 1])
-            .map_err(|_| StringsError::Utf8)?
-            .to_string();
-        entries.push(StringsEntry { id, text });
-    }
-
-    Ok(StringsFile { entries })
-}
-
-fn write_length_prefixed_strings(file: &StringsFile) -> Result<Vec<u8>, StringsError> {
-    let mut entries = file.entries.clone();
-    entries.sort_by_key(|entry| entry.id);
-    for window in entries.windows(2) {
-        if window[0].id == window[1].id {
-            return Err(StringsError::DuplicateId(window[0].id));
-        }
-    }
-
-    let mut directory = Vec::with_capacity(entries.len());
-    let mut data_block: Vec<u8> = Vec::new();
-    for entry in &entries {
-        let offset = data_block.len() as u32;
-        let bytes = entry.text.as_bytes();
-        let len = bytes
-            .len()
-            .checked_add(1)
-            .ok_or(StringsError::UnexpectedEof)? as u32;
-        data_block.extend_from_slice(&len.to_le_bytes());
-        data_block.extend_from_slice(bytes);
-        data_block.push(0);
-        directory.push((entry.id, offset));
-    }
-
-    let count = entries.len() as u32;
-    let data_size = data_block.len() as u32;
-    let mut output = Vec::with_capacity(8 + directory.len() * 8 + data_block.len());
-    output.extend_from_slice(&count.to_le_bytes());
-    output.extend_from_slice(&data_size.to_le_bytes());
-    for (id, offset) in directory {
-        output.extend_from_slice(&id.to_le_bytes());
-        output.extend_from_slice(&offset.to_le_bytes());
-    }
-    output.extend_from_slice(&data_block);
-
-    Ok(output)
-}
+pub use xt_esp::{
+    read_dlstrings, read_ilstrings, read_strings, write_dlstrings, write_dlstrings_to,
+    write_dlstrings_with, write_ilstrings, write_ilstrings_to, write_ilstrings_with, write_strings,
+    write_strings_to, write_strings_with, RawStrings, StringsEntry, StringsError, StringsFile,
+    StringsInfo, StringsWarning, WriteOptions,
+};
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     const DL_FIXTURE: &[u8] = include_bytes!(concat!(
         env!("CARGO_MANIFEST_DIR"),
@@ -222,22 +21,20 @@ mod tests {
 
     #[test]
     fn t_str_rt_001_strings_round_trip() {
-        let file = StringsFile {
-            entries: vec![
-                StringsEntry {
-                    id: 10,
-                    text: "Hello".to_string(),
-                },
-                StringsEntry {
-                    id: 20,
-                    text: "こんにちは".to_string(),
-                },
-                StringsEntry {
-                    id: 30,
-                    text: "Line1\nLine2".to_string(),
-                },
-            ],
-        };
+        let file = StringsFile::new(vec![
+            StringsEntry {
+                id: 10,
+                text: "Hello".to_string(),
+            },
+            StringsEntry {
+                id: 20,
+                text: "こんにちは".to_string(),
+            },
+            StringsEntry {
+                id: 30,
+                text: "Line1\nLine2".to_string(),
+            },
+        ]);
         let bytes = write_strings(&file).expect("write strings");
         let decoded = read_strings(&bytes).expect("read strings");
         assert_eq!(decoded, file);
@@ -245,22 +42,20 @@ mod tests {
 
     #[test]
     fn t_str_rt_002_dlstrings_round_trip() {
-        let file = StringsFile {
-            entries: vec![
-                StringsEntry {
-                    id: 1,
-                    text: "Hello".to_string(),
-                },
-                StringsEntry {
-                    id: 2,
-                    text: "こんにちは".to_string(),
-                },
-                StringsEntry {
-                    id: 3,
-                    text: "Line1\nLine2".to_string(),
-                },
-            ],
-        };
+        let file = StringsFile::new(vec![
+            StringsEntry {
+                id: 1,
+                text: "Hello".to_string(),
+            },
+            StringsEntry {
+                id: 2,
+                text: "こんにちは".to_string(),
+            },
+            StringsEntry {
+                id: 3,
+                text: "Line1\nLine2".to_string(),
+            },
+        ]);
         let bytes = write_dlstrings(&file).expect("write dlstrings");
         let decoded = read_dlstrings(&bytes).expect("read dlstrings");
         assert_eq!(decoded, file);
@@ -268,22 +63,20 @@ mod tests {
 
     #[test]
     fn t_str_rt_003_ilstrings_round_trip() {
-        let file = StringsFile {
-            entries: vec![
-                StringsEntry {
-                    id: 11,
-                    text: "Hello".to_string(),
-                },
-                StringsEntry {
-                    id: 12,
-                    text: "こんにちは".to_string(),
-                },
-                StringsEntry {
-                    id: 13,
-                    text: "Line1\nLine2".to_string(),
-                },
-            ],
-        };
+        let file = StringsFile::new(vec![
+            StringsEntry {
+                id: 11,
+                text: "Hello".to_string(),
+            },
+            StringsEntry {
+                id: 12,
+                text: "こんにちは".to_string(),
+            },
+            StringsEntry {
+                id: 13,
+                text: "Line1\nLine2".to_string(),
+            },
+        ]);
         let bytes = write_ilstrings(&file).expect("write ilstrings");
         let decoded = read_ilstrings(&bytes).expect("read ilstrings");
         assert_eq!(decoded, file);
@@ -349,4 +142,340 @@ mod tests {
         let encoded = write_ilstrings(&file).expect("write ilstrings fixture");
         assert_eq!(encoded, IL_FIXTURE);
     }
+
+    #[test]
+    fn t_str_rt_004_raw_bytes_preserved() {
+        // Directory out of id order, two ids sharing one payload, an
+        // embedded null and a cp1252 byte that is not UTF-8.
+        let payloads: [&[u8]; 2] = [b"Caf\xe9", b"A\0B"];
+        let mut data = Vec::new();
+        let mut offsets = Vec::new();
+        for payload in payloads {
+            offsets.push(data.len() as u32);
+            data.extend_from_slice(&(payload.len() as u32 + 1).to_le_bytes());
+            data.extend_from_slice(payload);
+            data.push(0);
+        }
+        let directory = [(30u32, offsets[1]), (10, offsets[0]), (20, offsets[0])];
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        for (id, offset) in directory {
+            bytes.extend_from_slice(&id.to_le_bytes());
+            bytes.extend_from_slice(&offset.to_le_bytes());
+        }
+        bytes.extend_from_slice(&data);
+
+        let file = read_dlstrings(&bytes).expect("read");
+        let texts = file
+            .entries
+            .iter()
+            .map(|entry| (entry.id, entry.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            [(30, "A\0B"), (10, "Caf\u{FFFD}"), (20, "Caf\u{FFFD}")]
+        );
+        assert_eq!(file.raw_payload(10), Some(&b"Caf\xe9"[..]));
+        assert_eq!(write_dlstrings(&file).expect("write"), bytes, "unedited");
+
+        let mut edited = file.clone();
+        edited.entries[2].text = "カフェ".to_string();
+        let written = write_dlstrings(&edited).expect("write edited");
+        let reread = read_dlstrings(&written).expect("reread");
+        assert_eq!(reread.raw_payload(10), Some(&b"Caf\xe9"[..]));
+        assert_eq!(reread.raw_payload(20), Some("カフェ".as_bytes()));
+        assert_eq!(reread.raw_payload(30), Some(&b"A\0B"[..]));
+
+        let mut plain = write_strings(&StringsFile::new(vec![StringsEntry {
+            id: 1,
+            text: "x".to_string(),
+        }]))
+        .expect("write strings");
+        let at = plain.len() - 2;
+        plain[at] = 0xe9;
+        let file = read_strings(&plain).expect("read non-utf8");
+        assert_eq!(file.entries[0].text, "\u{FFFD}");
+        assert_eq!(write_strings(&file).expect("write"), plain);
+    }
+
+    #[test]
+    fn t_str_rt_005_preserve_layout_keeps_order_sharing_and_padding() {
+        // "Hello" shared by ids 1 and 3, its tail "llo" used by id 4, padding
+        // between strings and after the last one.
+        let data = b"Hello\0\xab\xabWorld\0\0\0";
+        let directory = [(5u32, 8u32), (1, 0), (3, 0), (4, 2)];
+        let build = |directory: &[(u32, u32)], data: &[u8]| {
+            let mut bytes = Vec::new();
+            bytes.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            for (id, offset) in directory {
+                bytes.extend_from_slice(&id.to_le_bytes());
+                bytes.extend_from_slice(&offset.to_le_bytes());
+            }
+            bytes.extend_from_slice(data);
+            bytes
+        };
+        let bytes = build(&directory, data);
+        let file = read_strings(&bytes).expect("read");
+        assert_eq!(file.entries[3].text, "llo");
+
+        let preserve = WriteOptions {
+            preserve_layout: true,
+        };
+        assert_eq!(write_strings_with(&file, &preserve).expect("write"), bytes);
+
+        let mut edited = file.clone();
+        edited.entries[0].text = "Welt".to_string();
+        edited.entries.push(StringsEntry {
+            id: 9,
+            text: "Welt".to_string(),
+        });
+        let written = write_strings_with(&edited, &preserve).expect("write edited");
+        assert_eq!(
+            written,
+            build(
+                &[(5, 8), (1, 0), (3, 0), (4, 2), (9, 8)],
+                b"Hello\0\xab\xabWelt\0\0\0",
+            )
+        );
+        assert_eq!(read_strings(&written).expect("reread"), edited);
+
+        let compact = write_strings(&edited).expect("write compact");
+        let ids = read_strings(&compact)
+            .expect("reread compact")
+            .entries
+            .iter()
+            .map(|entry| entry.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, [1, 3, 4, 5, 9]);
+
+        let mut duplicate = edited.clone();
+        duplicate.entries.push(StringsEntry {
+            id: 9,
+            text: "Mond".to_string(),
+        });
+        assert_eq!(
+            write_strings_with(&duplicate, &preserve),
+            Err(StringsError::DuplicateId(9))
+        );
+
+        // A file read in another format is re-encoded, not copied.
+        let dl = read_dlstrings(&write_dlstrings(&file).expect("write dl")).expect("read dl");
+        let plain = write_strings_with(&dl, &preserve).expect("write plain");
+        assert_eq!(read_strings(&plain).expect("reread plain"), dl);
+    }
+
+    #[test]
+    fn t_str_info_001_statistics_and_warnings() {
+        let mut data = Vec::new();
+        data.extend_from_slice(&5u32.to_le_bytes());
+        data.extend_from_slice(b"Caf\xe9\0");
+        data.extend_from_slice(&[0; 3]);
+        data.extend_from_slice(&4u32.to_le_bytes());
+        data.extend_from_slice(b"A\0B\0");
+        let directory = [(10u32, 0u32), (20, 12), (10, 0)];
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        for (id, offset) in directory {
+            bytes.extend_from_slice(&id.to_le_bytes());
+            bytes.extend_from_slice(&offset.to_le_bytes());
+        }
+        bytes.extend_from_slice(&data);
+        bytes.extend_from_slice(&[0xff, 0xff]);
+
+        let file = read_dlstrings(&bytes).expect("read despite warnings");
+        let info = file.info().expect("info");
+        assert_eq!(
+            info,
+            StringsInfo {
+                size: 54,
+                entries: 3,
+                strings: 2,
+                duplicate_ids: vec![10],
+                unused_bytes: 3,
+                trailing_bytes: 2,
+                non_utf8_ids: vec![10, 10],
+                embedded_null_ids: vec![20],
+            }
+        );
+        assert_eq!(
+            info.warnings(),
+            [
+                StringsWarning::DuplicateId(10),
+                StringsWarning::NonUtf8(10),
+                StringsWarning::NonUtf8(10),
+                StringsWarning::EmbeddedNull(20),
+                StringsWarning::TrailingBytes(2),
+            ]
+        );
+        let report = info.report();
+        assert!(report.starts_with(
+            "size=54 entries=3 strings=2 unused_bytes=3 trailing_bytes=2\n! duplicate string id: 0000000A\n"
+        ));
+        assert_eq!(StringsFile::new(Vec::new()).info(), None);
+    }
+
+    #[test]
+    fn t_str_rt_006_writers_stream_the_same_bytes() {
+        let mut file = read_dlstrings(DL_FIXTURE).expect("read dl");
+        let preserve = WriteOptions {
+            preserve_layout: true,
+        };
+        let compact = WriteOptions::default();
+        let mut out = Vec::new();
+        write_dlstrings_to(&file, &preserve, &mut out).expect("unchanged");
+        assert_eq!(out, DL_FIXTURE);
+
+        file.entries[0].text = "編集済み".to_string();
+        for options in [&preserve, &compact] {
+            let mut dl = Vec::new();
+            write_dlstrings_to(&file, options, &mut dl).expect("stream dl");
+            assert_eq!(dl, write_dlstrings_with(&file, options).expect("write dl"));
+            let mut il = Vec::new();
+            write_ilstrings_to(&file, options, &mut il).expect("stream il");
+            assert_eq!(il, write_ilstrings_with(&file, options).expect("write il"));
+            let mut plain = Vec::new();
+            write_strings_to(&file, options, &mut plain).expect("stream plain");
+            assert_eq!(
+                plain,
+                write_strings_with(&file, options).expect("write plain")
+            );
+        }
+
+        file.entries.push(file.entries[0].clone());
+        let mut untouched = Vec::new();
+        let err = write_dlstrings_to(&file, &compact, &mut untouched).expect_err("duplicate");
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        let id = file.entries[0].id;
+        assert_eq!(
+            err.get_ref()
+                .and_then(|inner| inner.downcast_ref::<StringsError>()),
+            Some(&StringsError::DuplicateId(id))
+        );
+        assert!(untouched.is_empty(), "nothing is written before the layout");
+    }
+
+    type Read = fn(&[u8]) -> Result<StringsFile, StringsError>;
+    type WriteWith = fn(&StringsFile, &WriteOptions) -> Result<Vec<u8>, StringsError>;
+
+    /// Reader and writer of each format; only DLSTRINGS and ILSTRINGS keep
+    /// nulls inside a string.
+    const FORMATS: [(Read, WriteWith, bool); 3] = [
+        (read_strings, write_strings_with, false),
+        (read_dlstrings, write_dlstrings_with, true),
+        (read_ilstrings, write_ilstrings_with, true),
+    ];
+
+    fn arb_entries() -> impl Strategy<Value = Vec<StringsEntry>> {
+        prop::collection::btree_map(any::<u32>(), "\\PC{0,24}", 0..16).prop_map(|entries| {
+            entries
+                .into_iter()
+                .map(|(id, text)| StringsEntry { id, text })
+                .collect()
+        })
+    }
+
+    /// A directory and data block that are mostly well formed: strings with
+    /// length prefixes and terminators, directory entries with repeated ids
+    /// and offsets that may point anywhere, and stray bytes in between.
+    fn arb_strings_bytes() -> impl Strategy<Value = Vec<u8>> {
+        let chunk = prop_oneof![
+            prop::collection::vec(any::<u8>(), 0..12).prop_map(|payload| {
+                let mut chunk = (payload.len() as u32 + 1).to_le_bytes().to_vec();
+                chunk.extend(payload);
+                chunk.push(0);
+                chunk
+            }),
+            prop::collection::vec(any::<u8>(), 0..6),
+        ];
+        (
+            prop::collection::vec(chunk, 0..6),
+            prop::collection::vec((0u32..4, any::<prop::sample::Index>()), 0..6),
+            prop::collection::vec(any::<u8>(), 0..3),
+        )
+            .prop_map(|(chunks, directory, trailing)| {
+                let mut starts = Vec::new();
+                let mut data = Vec::new();
+                for chunk in chunks {
+                    starts.push(data.len() as u32);
+                    data.extend(chunk);
+                }
+                starts.push(data.len() as u32);
+                let mut bytes = (directory.len() as u32).to_le_bytes().to_vec();
+                bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                for (id, start) in directory {
+                    bytes.extend_from_slice(&id.to_le_bytes());
+                    bytes.extend_from_slice(&start.get(&starts).to_le_bytes());
+                }
+                bytes.extend(data);
+                bytes.extend(trailing);
+                bytes
+            })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(128))]
+
+        #[test]
+        fn t_str_prop_001_written_files_read_back(entries in arb_entries()) {
+            let file = StringsFile::new(entries);
+            for (read, write, _) in FORMATS {
+                let bytes = write(&file, &WriteOptions::default()).expect("write");
+                let decoded = read(&bytes).expect("read written file");
+                prop_assert_eq!(&decoded, &file);
+                prop_assert_eq!(write(&decoded, &WriteOptions::default()).expect("rewrite"), bytes);
+            }
+        }
+
+        #[test]
+        fn t_str_prop_002_arbitrary_bytes_error_or_round_trip(
+            bytes in prop_oneof![
+                prop::collection::vec(any::<u8>(), 0..160),
+                arb_strings_bytes(),
+            ],
+            edit in any::<prop::sample::Index>(),
+            text in "\\PC{0,12}",
+        ) {
+            for (read, write, keeps_nulls) in FORMATS {
+                let Ok(mut file) = read(&bytes) else {
+                    continue;
+                };
+                prop_assert_eq!(&write(&file, &WriteOptions::default()).expect("unchanged"), &bytes);
+                if file.entries.is_empty() {
+                    continue;
+                }
+                let at = edit.index(file.entries.len());
+                file.entries[at].text = text.clone();
+                let mut sorted = file.entries.clone();
+                sorted.sort_by_key(|entry| entry.id);
+                for preserve_layout in [false, true] {
+                    let options = WriteOptions { preserve_layout };
+                    // A compact file is sorted by id.
+                    let expected = if preserve_layout { &file.entries } else { &sorted };
+                    match write(&file, &options) {
+                        Ok(written) => {
+                            let decoded = read(&written).expect("read edited file");
+                            prop_assert_eq!(&decoded.entries, expected);
+                        }
+                        Err(StringsError::DuplicateId(_)) => {}
+                        Err(StringsError::EmbeddedNull(_)) => prop_assert!(!keeps_nulls),
+                        Err(err) => prop_assert!(false, "unexpected error: {err}"),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn t_str_prop_003_strings_with_nulls_are_not_truncated() {
+        let file = StringsFile::new(vec![StringsEntry {
+            id: 7,
+            text: "before\0after".to_string(),
+        }]);
+        assert_eq!(write_strings(&file), Err(StringsError::EmbeddedNull(7)));
+        let bytes = write_dlstrings(&file).expect("dlstrings keep nulls");
+        assert_eq!(read_dlstrings(&bytes).expect("read"), file);
+    }
 }
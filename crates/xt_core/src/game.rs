@@ -0,0 +1,51 @@
+//! Per-game settings: plugin subrecord tables and Strings layout come from
+//! [`GameProfile`] (defined next to the ESP parser), plus the text encoding
+//! used when checking or writing translations.
+
+use crate::encoding::Encoding;
+use crate::workspace::Game;
+
+pub use crate::formats::esp::GameProfile;
+
+/// Encoding a game expects for plugin and Strings text. Skyrim LE only
+/// handles single-byte code pages; later games read UTF-8.
+pub fn default_encoding(profile: GameProfile) -> Encoding {
+    match profile {
+        GameProfile::SkyrimLe => Encoding::Latin1,
+        GameProfile::SkyrimSe | GameProfile::Fallout4 | GameProfile::Starfield => Encoding::Utf8,
+    }
+}
+
+impl Game {
+    pub fn profile(&self) -> GameProfile {
+        match self {
+            Game::Skyrim => GameProfile::SkyrimLe,
+            Game::SkyrimSeAe => GameProfile::SkyrimSe,
+            Game::Fallout4 => GameProfile::Fallout4,
+            Game::Starfield => GameProfile::Starfield,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::{decode, encode};
+
+    #[test]
+    fn t_game_001_profile_names_encoding_and_workspace_mapping() {
+        for profile in GameProfile::ALL {
+            assert_eq!(GameProfile::parse(profile.name()), Some(profile));
+        }
+        assert_eq!(GameProfile::parse("skyrim"), Some(GameProfile::SkyrimSe));
+        assert_eq!(GameProfile::parse("oblivion"), None);
+        assert_eq!(Game::Skyrim.profile(), GameProfile::SkyrimLe);
+        assert_eq!(Game::Starfield.profile(), GameProfile::Starfield);
+
+        let le = default_encoding(GameProfile::SkyrimLe);
+        assert!(encode("鉄の剣", le).is_err());
+        let sf = default_encoding(GameProfile::Starfield);
+        let bytes = encode("鉄の剣", sf).expect("utf8");
+        assert_eq!(decode(&bytes, sf).expect("decode"), "鉄の剣");
+    }
+}
@@ -1,3 +1,4 @@
+use crate::formats::esp::{ExtractedString, StringStorage};
 use crate::formats::plugin::PluginFile;
 use crate::formats::strings::StringsFile;
 use std::collections::HashMap;
@@ -29,6 +30,71 @@ pub fn build_hybrid_entries(plugin: &PluginFile, strings: &StringsFile) -> Vec<H
     entries
 }
 
+/// One translatable string of a record and where it is stored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HybridString {
+    /// Entry key ([`ExtractedString::get_unique_key`]).
+    pub key: String,
+    /// Position in the extracted list, which is also the entry's position
+    /// right after the plugin is loaded.
+    pub position: usize,
+    pub subrecord_type: [u8; 4],
+    pub storage: StringStorage,
+}
+
+/// A record's strings in plugin order, inline texts and Strings ids side by
+/// side. Saving a plugin writes each one back to its own storage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HybridRecord {
+    pub record_type: [u8; 4],
+    pub form_id: u32,
+    pub strings: Vec<HybridString>,
+}
+
+/// Number of strings stored in the plugin and in its Strings files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HybridCounts {
+    pub inline: usize,
+    pub localized: usize,
+}
+
+/// Groups extracted strings by record, in order of each record's first
+/// string.
+pub fn group_hybrid_records(extracted: &[ExtractedString]) -> Vec<HybridRecord> {
+    let mut records: Vec<HybridRecord> = Vec::new();
+    let mut positions: HashMap<([u8; 4], u32), usize> = HashMap::new();
+    for (position, string) in extracted.iter().enumerate() {
+        let at = *positions
+            .entry((string.record_type, string.form_id))
+            .or_insert_with(|| {
+                records.push(HybridRecord {
+                    record_type: string.record_type,
+                    form_id: string.form_id,
+                    strings: Vec::new(),
+                });
+                records.len() - 1
+            });
+        records[at].strings.push(HybridString {
+            key: string.get_unique_key(),
+            position,
+            subrecord_type: string.subrecord_type,
+            storage: string.storage.clone(),
+        });
+    }
+    records
+}
+
+pub fn hybrid_counts(records: &[HybridRecord]) -> HybridCounts {
+    let mut counts = HybridCounts::default();
+    for string in records.iter().flat_map(|record| &record.strings) {
+        match string.storage {
+            StringStorage::Inline => counts.inline += 1,
+            StringStorage::Localized { .. } => counts.localized += 1,
+        }
+    }
+    counts
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -44,15 +110,65 @@ mod tests {
                 source_text: "Hello".to_string(),
             }],
         };
-        let strings = StringsFile {
-            entries: vec![StringsEntry {
-                id: 100,
-                text: "こんにちは".to_string(),
-            }],
-        };
+        let strings = StringsFile::new(vec![StringsEntry {
+            id: 100,
+            text: "こんにちは".to_string(),
+        }]);
         let hybrid = build_hybrid_entries(&plugin, &strings);
         assert_eq!(hybrid.len(), 1);
         assert_eq!(hybrid[0].context, "Greeting");
         assert_eq!(hybrid[0].target_text, "こんにちは");
     }
+
+    #[test]
+    fn t_hyb_002_groups_inline_and_localized_strings_per_record() {
+        use crate::formats::esp::StringsKind;
+
+        let string = |record: &[u8; 4], form_id, sub: &[u8; 4], index, storage| {
+            ExtractedString::new(*record, *sub, form_id, index, String::new(), storage)
+        };
+        let extracted = vec![
+            string(
+                b"WEAP",
+                0x800,
+                b"FULL",
+                0,
+                StringStorage::Localized {
+                    kind: StringsKind::Strings,
+                    id: 7,
+                },
+            ),
+            string(b"BOOK", 0x801, b"FULL", 0, StringStorage::Inline),
+            string(
+                b"WEAP",
+                0x800,
+                b"DESC",
+                1,
+                StringStorage::Localized {
+                    kind: StringsKind::DlStrings,
+                    id: 8,
+                },
+            ),
+            // An id missing from the Strings files stays inline.
+            string(b"WEAP", 0x800, b"FULL", 2, StringStorage::Inline),
+        ];
+        let records = group_hybrid_records(&extracted);
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| (record.record_type, record.form_id, record.strings.len()))
+                .collect::<Vec<_>>(),
+            vec![(*b"WEAP", 0x800, 3), (*b"BOOK", 0x801, 1)]
+        );
+        assert_eq!(records[0].strings[1].key, extracted[2].get_unique_key());
+        assert_eq!(records[0].strings[1].position, 2);
+        assert_eq!(records[0].strings[2].storage, StringStorage::Inline);
+        assert_eq!(
+            hybrid_counts(&records),
+            HybridCounts {
+                inline: 2,
+                localized: 2
+            }
+        );
+    }
 }
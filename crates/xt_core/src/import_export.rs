@@ -1,11 +1,36 @@
-use crate::model::Entry;
+use crate::candidates::{Proposal, Provenance};
+use crate::formats::esp::{
+    CancelToken, Canceled, ExtractedString, NoProgress, ProgressSink, StringStorage, StringsKind,
+};
+use crate::model::{EditMeta, EditMetaMap, Entry, NoteMap};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
 pub enum XmlError {
+    #[error("invalid xml format")]
     InvalidFormat,
+    #[error("missing attribute: {0}")]
     MissingAttr(&'static str),
+    #[error("invalid escape sequence")]
     InvalidEscape,
+    /// Failure inside the `<entry>`/`<String>` element starting at byte `offset`.
+    #[error("element at byte {offset}: {source}")]
+    Element {
+        offset: usize,
+        #[source]
+        source: Box<XmlError>,
+    },
+}
+
+impl XmlError {
+    fn at(offset: usize) -> impl FnOnce(XmlError) -> XmlError {
+        move |source| XmlError::Element {
+            offset,
+            source: Box::new(source),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
@@ -13,9 +38,88 @@ pub struct XmlApplyStats {
     pub updated: usize,
     pub unchanged: usize,
     pub missing: usize,
+    /// Updates matched by key.
+    pub by_key: usize,
+    /// Updates matched by source text fallback.
+    pub by_source: usize,
+    /// Rows left untouched because the source matched several different targets.
+    pub ambiguous: usize,
+    /// Rows left untouched because they already had a translation
+    /// ([`ApplyProfile::OnlyEmptyTargets`]); also counted in `unchanged`.
+    pub kept_existing: usize,
+    /// Updates that cleared a translation ([`ApplyProfile::ForceAll`]).
+    pub cleared: usize,
+}
+
+/// Merge policy used when applying imported translations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ApplyProfile {
+    /// Only entries whose key matches.
+    KeyExact,
+    /// Key match first, then a unique source-text match.
+    #[default]
+    SourceFallback,
+    /// Like `PreferNewer`, and empty imported targets also clear translations.
+    ForceAll,
+    /// Like `SourceFallback`, but rows that already have a translation are kept.
+    OnlyEmptyTargets,
+    /// Like `SourceFallback`, but a conflicting source match takes the last
+    /// (newest) target in the import instead of being skipped.
+    PreferNewer,
+}
+
+impl ApplyProfile {
+    pub const ALL: [ApplyProfile; 5] = [
+        ApplyProfile::KeyExact,
+        ApplyProfile::SourceFallback,
+        ApplyProfile::ForceAll,
+        ApplyProfile::OnlyEmptyTargets,
+        ApplyProfile::PreferNewer,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ApplyProfile::KeyExact => "key-exact",
+            ApplyProfile::SourceFallback => "source-fallback",
+            ApplyProfile::ForceAll => "force-all",
+            ApplyProfile::OnlyEmptyTargets => "only-empty",
+            ApplyProfile::PreferNewer => "prefer-newer",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|profile| profile.as_str() == value)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ApplyProfile::KeyExact => "キー一致のみ",
+            ApplyProfile::SourceFallback => "キー一致＋原文一致（一意）",
+            ApplyProfile::ForceAll => "すべて上書き（空訳で消去）",
+            ApplyProfile::OnlyEmptyTargets => "未翻訳行のみ",
+            ApplyProfile::PreferNewer => "競合時は後勝ち",
+        }
+    }
+
+    fn uses_source(self) -> bool {
+        self != ApplyProfile::KeyExact
+    }
+
+    fn last_source_wins(self) -> bool {
+        matches!(self, ApplyProfile::ForceAll | ApplyProfile::PreferNewer)
+    }
 }
 
 pub fn export_entries(entries: &[Entry]) -> String {
+    export_entries_with_meta(entries, &EditMetaMap::new(), &NoteMap::new())
+}
+
+/// Like [`export_entries`], adding optional `author`/`modified` attributes
+/// for entries that have edit metadata and a `note` attribute for entries
+/// with a translator note.
+pub fn export_entries_with_meta(entries: &[Entry], meta: &EditMetaMap, notes: &NoteMap) -> String {
     let mut out = String::new();
     out.push_str(r#"<?xml version="1.0" encoding="utf-8"?>"#);
     out.push('\n');
@@ -32,38 +136,289 @@ pub fn export_entries(entries: &[Entry]) -> String {
         out.push_str(r#" target=""#);
         out.push_str(&escape_xml(&entry.target_text));
         out.push('"');
+        if let Some(edit) = meta.get(&entry.key) {
+            if !edit.author.is_empty() {
+                out.push_str(r#" author=""#);
+                out.push_str(&escape_xml(&edit.author));
+                out.push('"');
+            }
+            out.push_str(&format!(r#" modified="{}""#, edit.modified_at));
+        }
+        if let Some(note) = notes.get(&entry.key).filter(|note| !note.is_empty()) {
+            out.push_str(r#" note=""#);
+            out.push_str(&escape_xml(note));
+            out.push('"');
+        }
         out.push_str(" />\n");
     }
     out.push_str("</xtrans>\n");
     out
 }
 
+/// Per-string metadata written into `<String>` of an xTranslator XML.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XtranslatorStringMeta {
+    /// 0 = STRINGS/inline, 1 = DLSTRINGS, 2 = ILSTRINGS.
+    pub list: u8,
+    pub sid: String,
+    pub edid: String,
+    /// `REC:SUB`, e.g. `WEAP:FULL`.
+    pub rec: String,
+    pub rec_index: usize,
+    pub rec_index_max: usize,
+}
+
+impl XtranslatorStringMeta {
+    pub fn from_extracted(string: &ExtractedString) -> Self {
+        let list = match string.storage {
+            StringStorage::Inline => 0,
+            StringStorage::Localized { kind, .. } => strings_kind_list(kind),
+        };
+        Self {
+            list,
+            sid: format!("{:06X}", string.form_id & 0x00FF_FFFF),
+            edid: string.editor_id.clone().unwrap_or_default(),
+            rec: format!(
+                "{}:{}",
+                String::from_utf8_lossy(&string.record_type),
+                String::from_utf8_lossy(&string.subrecord_type)
+            ),
+            rec_index: 0,
+            rec_index_max: 1,
+        }
+    }
+
+    /// Best-effort metadata from an entry key when no extraction data is available.
+    pub fn from_key(key: &str) -> Self {
+        let parts: Vec<&str> = key.split(':').collect();
+        match parts.as_slice() {
+            [rec, form_id, sub, _] if rec.len() == 4 && sub.len() == 4 => {
+                let form_id = u32::from_str_radix(form_id, 16).unwrap_or(0);
+                Self {
+                    list: 0,
+                    sid: format!("{:06X}", form_id & 0x00FF_FFFF),
+                    edid: String::new(),
+                    rec: format!("{rec}:{sub}"),
+                    rec_index: 0,
+                    rec_index_max: 1,
+                }
+            }
+            [_, id] => Self {
+                sid: (*id).to_string(),
+                ..Self::default()
+            },
+            _ => Self::default(),
+        }
+    }
+}
+
+fn strings_kind_list(kind: StringsKind) -> u8 {
+    match kind {
+        StringsKind::Strings => 0,
+        StringsKind::DlStrings => 1,
+        StringsKind::IlStrings => 2,
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XtranslatorExportParams {
+    pub addon: String,
+    pub source_lang: String,
+    pub dest_lang: String,
+    /// Metadata keyed by entry key; entries without metadata fall back to
+    /// [`XtranslatorStringMeta::from_key`].
+    pub metadata: HashMap<String, XtranslatorStringMeta>,
+}
+
+impl XtranslatorExportParams {
+    pub fn new(addon: &str, source_lang: &str, dest_lang: &str) -> Self {
+        Self {
+            addon: addon.to_string(),
+            source_lang: source_lang.to_string(),
+            dest_lang: dest_lang.to_string(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    pub fn with_extracted(mut self, strings: &[ExtractedString]) -> Self {
+        // xTranslator numbers REC id/idMax per subrecord type within a record.
+        let group =
+            |string: &ExtractedString| (string.record_type, string.form_id, string.subrecord_type);
+        let mut totals: HashMap<([u8; 4], u32, [u8; 4]), usize> = HashMap::new();
+        for string in strings {
+            *totals.entry(group(string)).or_default() += 1;
+        }
+        let mut seen: HashMap<([u8; 4], u32, [u8; 4]), usize> = HashMap::new();
+        for string in strings {
+            let ordinal = seen.entry(group(string)).or_default();
+            let mut meta = XtranslatorStringMeta::from_extracted(string);
+            meta.rec_index = *ordinal;
+            meta.rec_index_max = totals.get(&group(string)).copied().unwrap_or(1);
+            *ordinal += 1;
+            self.metadata.insert(string.get_unique_key(), meta);
+        }
+        self
+    }
+}
+
+/// Writes entries in xTranslator's `SSTXMLRessources` schema.
+pub fn export_entries_xtranslator(entries: &[Entry], params: &XtranslatorExportParams) -> String {
+    let mut out = String::new();
+    out.push_str(r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#);
+    out.push('\n');
+    out.push_str("<SSTXMLRessources>\n");
+    out.push_str("  <Params>\n");
+    push_element(&mut out, "    ", "Addon", &params.addon);
+    push_element(&mut out, "    ", "Source", &params.source_lang);
+    push_element(&mut out, "    ", "Dest", &params.dest_lang);
+    out.push_str("    <Version>2</Version>\n");
+    out.push_str("  </Params>\n");
+    out.push_str("  <Content>\n");
+    for entry in entries {
+        let meta = params
+            .metadata
+            .get(&entry.key)
+            .cloned()
+            .unwrap_or_else(|| XtranslatorStringMeta::from_key(&entry.key));
+        out.push_str(&format!(
+            "    <String List=\"{}\" sID=\"{}\">\n",
+            meta.list,
+            escape_xml(&meta.sid)
+        ));
+        push_element(&mut out, "      ", "EDID", &meta.edid);
+        if !meta.rec.is_empty() {
+            out.push_str(&format!(
+                "      <REC id=\"{}\" idMax=\"{}\">{}</REC>\n",
+                meta.rec_index,
+                meta.rec_index_max,
+                escape_xml(&meta.rec)
+            ));
+        }
+        push_element(&mut out, "      ", "Source", &entry.source_text);
+        push_element(&mut out, "      ", "Dest", &entry.target_text);
+        out.push_str("    </String>\n");
+    }
+    out.push_str("  </Content>\n");
+    out.push_str("</SSTXMLRessources>\n");
+    out
+}
+
+/// `entries` as CSV with `key`, `edid`, `rec`, `source` and `target`
+/// columns, the record metadata taken from `params` as in
+/// [`export_entries_xtranslator`]. A translator fills the target column in a
+/// spreadsheet; [`crate::review_sheet::parse_corrections`] reads it back.
+pub fn export_entries_csv(entries: &[Entry], params: &XtranslatorExportParams) -> String {
+    let mut out = String::from("key,edid,rec,source,target\n");
+    for entry in entries {
+        let meta = params
+            .metadata
+            .get(&entry.key)
+            .cloned()
+            .unwrap_or_else(|| XtranslatorStringMeta::from_key(&entry.key));
+        let fields = [
+            entry.key.as_str(),
+            &meta.edid,
+            &meta.rec,
+            &entry.source_text,
+            &entry.target_text,
+        ];
+        for (at, field) in fields.into_iter().enumerate() {
+            if at > 0 {
+                out.push(',');
+            }
+            if field.contains([',', '"', '\n', '\r']) {
+                out.push('"');
+                out.push_str(&field.replace('"', "\"\""));
+                out.push('"');
+            } else {
+                out.push_str(field);
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn push_element(out: &mut String, indent: &str, name: &str, text: &str) {
+    out.push_str(indent);
+    out.push('<');
+    out.push_str(name);
+    out.push('>');
+    out.push_str(&escape_xml(text));
+    out.push_str("</");
+    out.push_str(name);
+    out.push_str(">\n");
+}
+
 pub fn import_entries(xml: &str) -> Result<Vec<Entry>, XmlError> {
+    import_entries_with_meta(xml).map(|(entries, ..)| entries)
+}
+
+/// Entries read from an xtrans XML or JSON file, with their edit metadata and
+/// translator notes.
+pub type ImportedEntries = (Vec<Entry>, EditMetaMap, NoteMap);
+
+/// Imports entries together with the optional edit metadata and notes of the
+/// xtrans schema. xTranslator XML carries neither.
+pub fn import_entries_with_meta(xml: &str) -> Result<ImportedEntries, XmlError> {
     let xml = strip_bom(xml);
     if xml.contains("<SSTXMLRessources") {
-        return import_entries_xtranslator(xml);
+        return import_entries_xtranslator(xml)
+            .map(|entries| (entries, EditMetaMap::new(), NoteMap::new()));
     }
     import_entries_xtrans(xml)
 }
 
-fn import_entries_xtrans(xml: &str) -> Result<Vec<Entry>, XmlError> {
+fn import_entries_xtrans(xml: &str) -> Result<ImportedEntries, XmlError> {
     let mut entries = Vec::new();
+    let mut meta = EditMetaMap::new();
+    let mut notes = NoteMap::new();
     let mut rest = xml;
     while let Some(start) = rest.find("<entry") {
+        let offset = xml.len() - rest.len() + start;
         rest = &rest[start + 6..];
-        let end = rest.find("/>").ok_or(XmlError::InvalidFormat)?;
+        let end = rest
+            .find("/>")
+            .ok_or(XmlError::InvalidFormat)
+            .map_err(XmlError::at(offset))?;
         let tag = &rest[..end];
-        let key = parse_attr(tag, "key")?;
-        let source_text = parse_attr(tag, "source")?;
-        let target_text = parse_attr(tag, "target")?;
-        entries.push(Entry {
-            key,
-            source_text,
-            target_text,
-        });
+        let (entry, entry_meta) = parse_xtrans_entry(tag).map_err(XmlError::at(offset))?;
+        if let Some(entry_meta) = entry_meta {
+            meta.insert(entry.key.clone(), entry_meta);
+        }
+        if let Some(note) = parse_optional_attr(tag, "note")
+            .map_err(XmlError::at(offset))?
+            .filter(|note| !note.is_empty())
+        {
+            notes.insert(entry.key.clone(), note);
+        }
+        entries.push(entry);
         rest = &rest[end + 2..];
     }
-    Ok(entries)
+    Ok((entries, meta, notes))
+}
+
+fn parse_xtrans_entry(tag: &str) -> Result<(Entry, Option<EditMeta>), XmlError> {
+    let key = parse_attr(tag, "key")?;
+    let source_text = parse_attr(tag, "source")?;
+    let target_text = parse_attr(tag, "target")?;
+    let meta = match parse_optional_attr(tag, "modified")? {
+        Some(modified_at) => Some(EditMeta {
+            author: parse_optional_attr(tag, "author")?.unwrap_or_default(),
+            modified_at: modified_at
+                .parse::<u64>()
+                .map_err(|_| XmlError::InvalidFormat)?,
+        }),
+        None => None,
+    };
+    Ok((
+        Entry {
+            key,
+            source_text: source_text.into(),
+            target_text: target_text.into(),
+        },
+        meta,
+    ))
 }
 
 fn import_entries_xtranslator(xml: &str) -> Result<Vec<Entry>, XmlError> {
@@ -72,17 +427,22 @@ fn import_entries_xtranslator(xml: &str) -> Result<Vec<Entry>, XmlError> {
     let mut index = 0usize;
 
     while let Some(start) = find_string_tag(rest) {
+        let offset = xml.len() - rest.len() + start;
         let block = &rest[start..];
-        let open_end = block.find('>').ok_or(XmlError::InvalidFormat)?;
+        let open_end = block
+            .find('>')
+            .ok_or(XmlError::InvalidFormat)
+            .map_err(XmlError::at(offset))?;
         let open_tag = &block[..=open_end];
         let body_with_tail = &block[open_end + 1..];
         let close = body_with_tail
             .find("</String>")
-            .ok_or(XmlError::InvalidFormat)?;
+            .ok_or(XmlError::InvalidFormat)
+            .map_err(XmlError::at(offset))?;
         let body = &body_with_tail[..close];
 
-        let source_text = parse_element_text(body, "Source")?;
-        let target_text = parse_element_text(body, "Dest")?;
+        let source_text = parse_element_text(body, "Source").map_err(XmlError::at(offset))?;
+        let target_text = parse_element_text(body, "Dest").map_err(XmlError::at(offset))?;
 
         // xTranslator XML has no stable key for our internal entries.
         // We keep a synthetic key and rely on source-text fallback matching.
@@ -97,8 +457,8 @@ fn import_entries_xtranslator(xml: &str) -> Result<Vec<Entry>, XmlError> {
 
         entries.push(Entry {
             key,
-            source_text,
-            target_text,
+            source_text: source_text.into(),
+            target_text: target_text.into(),
         });
         index = index.saturating_add(1);
         rest = &body_with_tail[close + "</String>".len()..];
@@ -110,47 +470,286 @@ fn import_entries_xtranslator(xml: &str) -> Result<Vec<Entry>, XmlError> {
     Ok(entries)
 }
 
+/// One item of the JSON array format, as written and as read back.
+#[derive(Serialize, Deserialize)]
+struct JsonEntry<'a> {
+    #[serde(borrow)]
+    key: Cow<'a, str>,
+    #[serde(borrow, default)]
+    source: Cow<'a, str>,
+    #[serde(borrow, default)]
+    target: Cow<'a, str>,
+    /// Informational only.
+    #[serde(borrow, default, skip_serializing_if = "str::is_empty")]
+    status: Cow<'a, str>,
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    author: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    modified: Option<u64>,
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    note: Option<Cow<'a, str>>,
+}
+
+/// Writes entries as `[{"key", "source", "target", "status"}]`, one per
+/// line.
+///
+/// `status` is `translated` or `untranslated`; it is informational only and
+/// ignored on import.
+pub fn export_entries_json(entries: &[Entry]) -> String {
+    export_entries_json_with_meta(entries, &EditMetaMap::new(), &NoteMap::new())
+}
+
+/// Like [`export_entries_json`], adding `author`/`modified` fields for
+/// entries that have edit metadata and `note` for entries with a note.
+pub fn export_entries_json_with_meta(
+    entries: &[Entry],
+    meta: &EditMetaMap,
+    notes: &NoteMap,
+) -> String {
+    if entries.is_empty() {
+        return "[]\n".to_string();
+    }
+    let items = entries
+        .iter()
+        .map(|entry| {
+            let status = if entry.target_text.is_empty() {
+                "untranslated"
+            } else {
+                "translated"
+            };
+            let edit = meta.get(&entry.key);
+            let item = JsonEntry {
+                key: Cow::Borrowed(&entry.key),
+                source: Cow::Borrowed(&entry.source_text),
+                target: Cow::Borrowed(&entry.target_text),
+                status: Cow::Borrowed(status),
+                author: edit.map(|edit| Cow::Borrowed(edit.author.as_str())),
+                modified: edit.map(|edit| edit.modified_at),
+                note: notes
+                    .get(&entry.key)
+                    .filter(|note| !note.is_empty())
+                    .map(|note| Cow::Borrowed(note.as_str())),
+            };
+            // Strings and integers only, which always serialize.
+            let item = serde_json::to_string(&item).expect("serialize entry");
+            format!("  {item}")
+        })
+        .collect::<Vec<_>>();
+    format!("[\n{}\n]\n", items.join(",\n"))
+}
+
+/// Reads the array written by [`export_entries_json`]. Imported entries merge
+/// with [`apply_xml_default`] like XML imports.
+pub fn import_entries_json(input: &str) -> Result<Vec<Entry>, serde_json::Error> {
+    import_entries_json_with_meta(input).map(|(entries, ..)| entries)
+}
+
+pub fn import_entries_json_with_meta(input: &str) -> Result<ImportedEntries, serde_json::Error> {
+    let items: Vec<JsonEntry> = serde_json::from_str(strip_bom(input))?;
+    let mut entries = Vec::with_capacity(items.len());
+    let mut meta = EditMetaMap::new();
+    let mut notes = NoteMap::new();
+    for item in items {
+        let key = item.key.into_owned();
+        if let Some(modified) = item.modified {
+            meta.insert(
+                key.clone(),
+                EditMeta {
+                    author: item.author.map(Cow::into_owned).unwrap_or_default(),
+                    modified_at: modified,
+                },
+            );
+        }
+        if let Some(note) = item.note.filter(|note| !note.is_empty()) {
+            notes.insert(key.clone(), note.into_owned());
+        }
+        entries.push(Entry {
+            key,
+            source_text: item.source.as_ref().into(),
+            target_text: item.target.as_ref().into(),
+        });
+    }
+    Ok((entries, meta, notes))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    #[error("xml: {0}")]
+    Xml(#[source] XmlError),
+    #[error("json: {0}")]
+    Json(#[source] serde_json::Error),
+}
+
+/// Imports either the JSON array format or any supported XML schema,
+/// detected from the first non-blank character.
+pub fn import_entries_any(input: &str) -> Result<Vec<Entry>, ImportError> {
+    import_entries_any_with_meta(input).map(|(entries, ..)| entries)
+}
+
+pub fn import_entries_any_with_meta(input: &str) -> Result<ImportedEntries, ImportError> {
+    if strip_bom(input).trim_start().starts_with('[') {
+        import_entries_json_with_meta(input).map_err(ImportError::Json)
+    } else {
+        import_entries_with_meta(input).map_err(ImportError::Xml)
+    }
+}
+
+/// Entries merged between cancellation checks and progress updates.
+const CANCEL_CHECK_INTERVAL: usize = 1024;
+
 pub fn apply_xml_default(current: &[Entry], imported: &[Entry]) -> (Vec<Entry>, XmlApplyStats) {
+    apply_xml_with_profile(current, imported, ApplyProfile::SourceFallback)
+}
+
+pub fn apply_xml_with_profile(
+    current: &[Entry],
+    imported: &[Entry],
+    profile: ApplyProfile,
+) -> (Vec<Entry>, XmlApplyStats) {
+    match apply_xml_with_profile_cancelable(
+        current,
+        imported,
+        profile,
+        &CancelToken::new(),
+        &NoProgress,
+    ) {
+        Ok(applied) => applied,
+        Err(Canceled) => unreachable!("a new token is never canceled"),
+    }
+}
+
+/// [`apply_xml_with_profile`] that stops with [`Canceled`] once `cancel` is
+/// canceled and reports imported plus current entries as items to `progress`
+/// (both every [`CANCEL_CHECK_INTERVAL`] entries).
+pub fn apply_xml_with_profile_cancelable(
+    current: &[Entry],
+    imported: &[Entry],
+    profile: ApplyProfile,
+    cancel: &CancelToken,
+    progress: &dyn ProgressSink,
+) -> Result<(Vec<Entry>, XmlApplyStats), Canceled> {
+    apply_xml_with_proposals_cancelable(current, imported, profile, cancel, progress)
+        .map(|(merged, stats, _)| (merged, stats))
+}
+
+/// [`apply_xml_with_profile_cancelable`] that also returns the imported
+/// target matched to each row of `current`, whether or not the profile
+/// applied it.
+pub fn apply_xml_with_proposals_cancelable(
+    current: &[Entry],
+    imported: &[Entry],
+    profile: ApplyProfile,
+    cancel: &CancelToken,
+    progress: &dyn ProgressSink,
+) -> Result<(Vec<Entry>, XmlApplyStats, Vec<Proposal>), Canceled> {
+    progress.set_total(imported.len() + current.len());
+    let force = profile == ApplyProfile::ForceAll;
     let mut import_map: HashMap<&str, &str> = HashMap::new();
     let mut source_map: HashMap<&str, Option<&str>> = HashMap::new();
-    for entry in imported {
-        if !entry.target_text.is_empty() {
-            import_map.insert(entry.key.as_str(), entry.target_text.as_str());
-            match source_map.get(entry.source_text.as_str()) {
-                None => {
-                    source_map.insert(entry.source_text.as_str(), Some(entry.target_text.as_str()));
-                }
-                Some(Some(prev)) if *prev != entry.target_text.as_str() => {
-                    source_map.insert(entry.source_text.as_str(), None);
-                }
-                _ => {}
+    for (index, entry) in imported.iter().enumerate() {
+        if index % CANCEL_CHECK_INTERVAL == 0 {
+            cancel.check()?;
+            progress.advance(CANCEL_CHECK_INTERVAL.min(imported.len() - index));
+        }
+        if entry.target_text.is_empty() && !force {
+            continue;
+        }
+        import_map.insert(entry.key.as_str(), entry.target_text.as_str());
+        if entry.target_text.is_empty() {
+            continue;
+        }
+        match source_map.get(entry.source_text.as_str()) {
+            Some(Some(prev)) if *prev != entry.target_text.as_str() => {
+                let next = profile
+                    .last_source_wins()
+                    .then_some(entry.target_text.as_str());
+                source_map.insert(entry.source_text.as_str(), next);
+            }
+            Some(None) if profile.last_source_wins() => {
+                source_map.insert(entry.source_text.as_str(), Some(entry.target_text.as_str()));
             }
+            None => {
+                source_map.insert(entry.source_text.as_str(), Some(entry.target_text.as_str()));
+            }
+            _ => {}
         }
     }
     let mut stats = XmlApplyStats::default();
+    let mut proposals = Vec::new();
     let merged = current
         .iter()
-        .map(|entry| {
+        .enumerate()
+        .map(|(index, entry)| {
+            if index % CANCEL_CHECK_INTERVAL == 0 {
+                cancel.check()?;
+                progress.advance(CANCEL_CHECK_INTERVAL.min(current.len() - index));
+            }
             let mut next = entry.clone();
             let key_target = import_map.get(entry.key.as_str()).copied();
-            let source_target = source_map
-                .get(entry.source_text.as_str())
-                .and_then(|v| v.as_ref().copied());
-            match key_target.or(source_target) {
-                Some(target) => {
-                    if next.target_text != target {
-                        next.target_text = target.to_string();
-                        stats.updated += 1;
-                    } else {
-                        stats.unchanged += 1;
-                    }
+            let source_match = if profile.uses_source() {
+                source_map.get(entry.source_text.as_str()).copied()
+            } else {
+                None
+            };
+            let (target, by_key) = match (key_target, source_match) {
+                (Some(target), _) => (target, true),
+                (None, Some(Some(target))) => (target, false),
+                (None, Some(None)) => {
+                    stats.ambiguous += 1;
+                    stats.missing += 1;
+                    return Ok(next);
                 }
-                None => stats.missing += 1,
-            }
-            next
+                (None, None) => {
+                    stats.missing += 1;
+                    return Ok(next);
+                }
+            };
+            let applied = if next.target_text == target {
+                stats.unchanged += 1;
+                false
+            } else if profile == ApplyProfile::OnlyEmptyTargets && !next.target_text.is_empty() {
+                stats.kept_existing += 1;
+                stats.unchanged += 1;
+                false
+            } else {
+                next.target_text = target.into();
+                stats.updated += 1;
+                if by_key {
+                    stats.by_key += 1;
+                } else {
+                    stats.by_source += 1;
+                }
+                if target.is_empty() {
+                    stats.cleared += 1;
+                }
+                true
+            };
+            proposals.push(Proposal {
+                index,
+                provenance: if by_key {
+                    Provenance::XmlKey
+                } else {
+                    Provenance::XmlSource
+                },
+                target: if applied {
+                    next.target_text.clone()
+                } else {
+                    target.into()
+                },
+                applied,
+            });
+            Ok(next)
         })
-        .collect::<Vec<_>>();
-    (merged, stats)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((merged, stats, proposals))
+}
+
+fn parse_optional_attr(tag: &str, name: &'static str) -> Result<Option<String>, XmlError> {
+    match parse_attr(tag, name) {
+        Ok(value) => Ok(Some(value)),
+        Err(XmlError::MissingAttr(_)) => Ok(None),
+        Err(err) => Err(err),
+    }
 }
 
 fn parse_attr(tag: &str, name: &'static str) -> Result<String, XmlError> {
@@ -267,13 +866,13 @@ mod tests {
         let entries = vec![
             Entry {
                 key: "strings:1".to_string(),
-                source_text: "Hello & <world>".to_string(),
-                target_text: "こんにちは".to_string(),
+                source_text: "Hello & <world>".into(),
+                target_text: "こんにちは".into(),
             },
             Entry {
                 key: "strings:2".to_string(),
-                source_text: "Line1\nLine2".to_string(),
-                target_text: "A\"B'".to_string(),
+                source_text: "Line1\nLine2".into(),
+                target_text: "A\"B'".into(),
             },
         ];
         let xml = export_entries(&entries);
@@ -286,30 +885,30 @@ mod tests {
         let current = vec![
             Entry {
                 key: "k1".to_string(),
-                source_text: "A".to_string(),
-                target_text: String::new(),
+                source_text: "A".into(),
+                target_text: Default::default(),
             },
             Entry {
                 key: "k2".to_string(),
-                source_text: "B".to_string(),
-                target_text: "X".to_string(),
+                source_text: "B".into(),
+                target_text: "X".into(),
             },
             Entry {
                 key: "k3".to_string(),
-                source_text: "C".to_string(),
-                target_text: String::new(),
+                source_text: "C".into(),
+                target_text: Default::default(),
             },
         ];
         let imported = vec![
             Entry {
                 key: "k1".to_string(),
-                source_text: "A".to_string(),
-                target_text: "AA".to_string(),
+                source_text: "A".into(),
+                target_text: "AA".into(),
             },
             Entry {
                 key: "k2".to_string(),
-                source_text: "B".to_string(),
-                target_text: "X".to_string(),
+                source_text: "B".into(),
+                target_text: "X".into(),
             },
         ];
         let (merged, stats) = apply_xml_default(&current, &imported);
@@ -356,13 +955,13 @@ mod tests {
         let current = vec![
             Entry {
                 key: "WEAP:00012EB7:FULL:0".to_string(),
-                source_text: "Iron Sword".to_string(),
-                target_text: String::new(),
+                source_text: "Iron Sword".into(),
+                target_text: Default::default(),
             },
             Entry {
                 key: "WEAP:00013989:FULL:0".to_string(),
-                source_text: "Steel Sword".to_string(),
-                target_text: String::new(),
+                source_text: "Steel Sword".into(),
+                target_text: Default::default(),
             },
         ];
 
@@ -370,13 +969,13 @@ mod tests {
         let imported = vec![
             Entry {
                 key: "xtr:0:000001:0".to_string(),
-                source_text: "Iron Sword".to_string(),
-                target_text: "鉄の剣".to_string(),
+                source_text: "Iron Sword".into(),
+                target_text: "鉄の剣".into(),
             },
             Entry {
                 key: "xtr:0:000002:1".to_string(),
-                source_text: "Steel Sword".to_string(),
-                target_text: "鋼鉄の剣".to_string(),
+                source_text: "Steel Sword".into(),
+                target_text: "鋼鉄の剣".into(),
             },
         ];
 
@@ -392,19 +991,19 @@ mod tests {
     fn t_xml_apply_003_source_fallback_skips_ambiguous_targets() {
         let current = vec![Entry {
             key: "k1".to_string(),
-            source_text: "Moonforge".to_string(),
-            target_text: String::new(),
+            source_text: "Moonforge".into(),
+            target_text: Default::default(),
         }];
         let imported = vec![
             Entry {
                 key: "xtr:a".to_string(),
-                source_text: "Moonforge".to_string(),
-                target_text: "ムーンフォージ".to_string(),
+                source_text: "Moonforge".into(),
+                target_text: "ムーンフォージ".into(),
             },
             Entry {
                 key: "xtr:b".to_string(),
-                source_text: "Moonforge".to_string(),
-                target_text: "月鍛冶".to_string(),
+                source_text: "Moonforge".into(),
+                target_text: "月鍛冶".into(),
             },
         ];
 
@@ -414,4 +1013,353 @@ mod tests {
         assert_eq!(stats.missing, 1);
         assert_eq!(merged[0].target_text, "");
     }
+
+    #[test]
+    fn t_xml_export_001_xtranslator_schema_round_trip() {
+        let strings = vec![
+            ExtractedString::new(
+                *b"WEAP",
+                *b"FULL",
+                0x0101_2EB7,
+                0,
+                "Iron Sword".to_string(),
+                StringStorage::Localized {
+                    kind: StringsKind::Strings,
+                    id: 7,
+                },
+            )
+            .with_editor_id(Some("IronSword".to_string())),
+            ExtractedString::new(
+                *b"BOOK",
+                *b"DESC",
+                0x0000_0D62,
+                1,
+                "Line 1\nA & B".to_string(),
+                StringStorage::Localized {
+                    kind: StringsKind::DlStrings,
+                    id: 8,
+                },
+            ),
+        ];
+        let entries: Vec<Entry> = strings
+            .iter()
+            .map(|string| Entry {
+                key: string.get_unique_key(),
+                source_text: string.text.clone().into(),
+                target_text: format!("訳:{}", string.text).into(),
+            })
+            .collect();
+        let params = XtranslatorExportParams::new("Test.esp", "english", "japanese")
+            .with_extracted(&strings);
+        let xml = export_entries_xtranslator(&entries, &params);
+
+        assert!(xml.contains("<Addon>Test.esp</Addon>"));
+        assert!(xml.contains(r#"<String List="0" sID="012EB7">"#));
+        assert!(xml.contains(r#"<REC id="0" idMax="1">WEAP:FULL</REC>"#));
+        assert!(xml.contains(r#"<String List="1" sID="000D62">"#));
+        assert!(xml.contains("<EDID>IronSword</EDID>"));
+        assert!(xml.contains("<EDID></EDID>"));
+
+        let parsed = import_entries(&xml).expect("import exported xml");
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].source_text, "Line 1\nA & B");
+        assert_eq!(parsed[1].target_text, "訳:Line 1\nA & B");
+        let (merged, stats) = apply_xml_default(&entries, &parsed);
+        assert_eq!(stats.unchanged, 2);
+        assert_eq!(merged, entries);
+    }
+
+    #[test]
+    fn t_json_rt_001_entries_round_trip_and_merge() {
+        let entries = vec![
+            Entry {
+                key: "WEAP:00012EB7:FULL:0".to_string(),
+                source_text: "Iron \"Sword\"".into(),
+                target_text: "鉄の剣\n(改)".into(),
+            },
+            Entry {
+                key: "strings:2".to_string(),
+                source_text: "Steel Sword".into(),
+                target_text: Default::default(),
+            },
+        ];
+        let json = export_entries_json(&entries);
+        assert!(json.contains(r#""status":"translated""#));
+        assert!(json.contains(r#""status":"untranslated""#));
+        assert_eq!(import_entries_json(&json).expect("import json"), entries);
+
+        let current = vec![Entry {
+            key: "WEAP:0000FFFF:FULL:0".to_string(),
+            source_text: "Iron \"Sword\"".into(),
+            target_text: Default::default(),
+        }];
+        let (merged, stats) =
+            apply_xml_default(&current, &import_entries_json(&json).expect("import"));
+        assert_eq!(stats.updated, 1);
+        assert_eq!(merged[0].target_text, "鉄の剣\n(改)");
+
+        assert_eq!(
+            import_entries_any(&json).expect("detect json").len(),
+            entries.len()
+        );
+        assert!(import_entries_json(r#"[{"source": "x"}]"#).is_err());
+        assert!(import_entries_json(r#"{"key": "x"}"#).is_err());
+    }
+
+    #[test]
+    fn t_xml_meta_001_edit_meta_attributes_round_trip() {
+        let entries = vec![
+            Entry {
+                key: "k1".to_string(),
+                source_text: "Iron Sword".into(),
+                target_text: "鉄の剣".into(),
+            },
+            Entry {
+                key: "k2".to_string(),
+                source_text: "Steel Sword".into(),
+                target_text: Default::default(),
+            },
+        ];
+        let mut meta = EditMetaMap::new();
+        meta.insert(
+            "k1".to_string(),
+            EditMeta {
+                author: "Taro & \"Hanako\"".to_string(),
+                modified_at: 1_760_000_000,
+            },
+        );
+
+        let no_notes = NoteMap::new();
+        let xml = export_entries_with_meta(&entries, &meta, &no_notes);
+        assert!(xml.contains(r#"author="Taro &amp; &quot;Hanako&quot;" modified="1760000000""#));
+        assert_eq!(
+            import_entries_with_meta(&xml).expect("xml"),
+            (entries.clone(), meta.clone(), no_notes.clone())
+        );
+        assert_eq!(import_entries(&xml).expect("plain"), entries);
+        assert_eq!(
+            export_entries(&entries),
+            export_entries_with_meta(&entries, &EditMetaMap::new(), &no_notes)
+        );
+
+        let json = export_entries_json_with_meta(&entries, &meta, &no_notes);
+        assert_eq!(
+            import_entries_any_with_meta(&json).expect("json"),
+            (entries, meta, no_notes)
+        );
+        assert!(
+            import_entries_with_meta(r#"<entry key="a" source="" target="" modified="x" />"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn t_xml_meta_002_notes_round_trip_as_attribute() {
+        let entries = vec![
+            Entry {
+                key: "k1".to_string(),
+                source_text: "Her Sword".into(),
+                target_text: "彼女の剣".into(),
+            },
+            Entry {
+                key: "k2".to_string(),
+                source_text: "Steel Sword".into(),
+                target_text: Default::default(),
+            },
+        ];
+        let mut notes = NoteMap::new();
+        notes.insert("k1".to_string(), "check gender\n\"要確認\"".to_string());
+        notes.insert("k2".to_string(), String::new());
+
+        let xml = export_entries_with_meta(&entries, &EditMetaMap::new(), &notes);
+        assert!(xml.contains(r#"target="彼女の剣" note="check gender&#10;&quot;要確認&quot;" />"#));
+        assert!(!xml.contains(r#"key="k2" source="Steel Sword" target="" note"#));
+        notes.remove("k2");
+        let (imported, meta, imported_notes) = import_entries_with_meta(&xml).expect("xml");
+        assert_eq!(imported, entries);
+        assert!(meta.is_empty());
+        assert_eq!(imported_notes, notes);
+
+        let json = export_entries_json_with_meta(&entries, &EditMetaMap::new(), &notes);
+        assert_eq!(import_entries_any_with_meta(&json).expect("json").2, notes);
+        assert!(
+            import_entries_with_meta(r#"<entry key="a" source="" target="" note="&bad;" />"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn t_xml_apply_004_profiles_and_decision_stats() {
+        let current = vec![
+            Entry {
+                key: "k1".to_string(),
+                source_text: "A".into(),
+                target_text: "old".into(),
+            },
+            Entry {
+                key: "k2".to_string(),
+                source_text: "B".into(),
+                target_text: Default::default(),
+            },
+            Entry {
+                key: "k3".to_string(),
+                source_text: "C".into(),
+                target_text: "keep".into(),
+            },
+        ];
+        let imported = vec![
+            Entry {
+                key: "k1".to_string(),
+                source_text: "A".into(),
+                target_text: "new".into(),
+            },
+            Entry {
+                key: "x1".to_string(),
+                source_text: "B".into(),
+                target_text: "B1".into(),
+            },
+            Entry {
+                key: "x2".to_string(),
+                source_text: "B".into(),
+                target_text: "B2".into(),
+            },
+            Entry {
+                key: "k3".to_string(),
+                source_text: "C".into(),
+                target_text: Default::default(),
+            },
+        ];
+        let targets = |entries: &[Entry]| {
+            entries
+                .iter()
+                .map(|entry| entry.target_text.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let (merged, stats) = apply_xml_with_profile(&current, &imported, ApplyProfile::KeyExact);
+        assert_eq!(targets(&merged), ["new", "", "keep"]);
+        assert_eq!((stats.by_key, stats.by_source, stats.missing), (1, 0, 2));
+
+        let (merged, stats) =
+            apply_xml_with_profile(&current, &imported, ApplyProfile::SourceFallback);
+        assert_eq!(targets(&merged), ["new", "", "keep"]);
+        assert_eq!(stats.ambiguous, 1);
+
+        let (merged, stats) =
+            apply_xml_with_profile(&current, &imported, ApplyProfile::PreferNewer);
+        assert_eq!(targets(&merged), ["new", "B2", "keep"]);
+        assert_eq!((stats.by_key, stats.by_source), (1, 1));
+
+        let (merged, stats) =
+            apply_xml_with_profile(&current, &imported, ApplyProfile::OnlyEmptyTargets);
+        assert_eq!(targets(&merged), ["old", "", "keep"]);
+        assert_eq!(stats.kept_existing, 1);
+        let (_, _, proposals) = apply_xml_with_proposals_cancelable(
+            &current,
+            &imported,
+            ApplyProfile::PreferNewer,
+            &CancelToken::new(),
+            &NoProgress,
+        )
+        .expect("apply");
+        let matched = proposals
+            .iter()
+            .map(|p| (p.index, p.provenance, p.target.as_str(), p.applied))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            matched,
+            [
+                (0, Provenance::XmlKey, "new", true),
+                (1, Provenance::XmlSource, "B2", true),
+            ]
+        );
+        let (_, _, proposals) = apply_xml_with_proposals_cancelable(
+            &current,
+            &imported,
+            ApplyProfile::OnlyEmptyTargets,
+            &CancelToken::new(),
+            &NoProgress,
+        )
+        .expect("apply");
+        assert_eq!(proposals[0].target, "new", "kept rows still propose");
+        assert!(!proposals[0].applied);
+
+        let (merged, stats) = apply_xml_with_profile(&current, &imported, ApplyProfile::ForceAll);
+        assert_eq!(targets(&merged), ["new", "B2", ""]);
+        assert_eq!((stats.updated, stats.cleared), (3, 1));
+
+        for profile in ApplyProfile::ALL {
+            assert_eq!(ApplyProfile::parse(profile.as_str()), Some(profile));
+        }
+    }
+
+    #[test]
+    fn t_xml_err_001_errors_report_element_offset() {
+        let xml = concat!(
+            "<xtrans>\n",
+            "<entry key=\"a\" source=\"A\" target=\"\"/>\n",
+            "<entry key=\"b\" target=\"B\"/>\n",
+            "</xtrans>"
+        );
+        let offset = xml.rfind("<entry").expect("second entry");
+        let err = import_entries(xml).expect_err("missing source");
+        assert_eq!(
+            err,
+            XmlError::Element {
+                offset,
+                source: Box::new(XmlError::MissingAttr("source")),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            format!("element at byte {offset}: missing attribute: source")
+        );
+
+        let err = import_entries_any("[{\"key\": 1}").expect_err("bad json");
+        assert!(matches!(err, ImportError::Json(_)));
+        assert!(err.to_string().starts_with("json: "));
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
+    #[test]
+    fn t_csv_export_001_sheet_with_record_metadata_reads_back() {
+        let strings = vec![ExtractedString::new(
+            *b"WEAP",
+            *b"FULL",
+            0x0001_2EB7,
+            0,
+            "Sword, \"Iron\"".to_string(),
+            StringStorage::Inline,
+        )
+        .with_editor_id(Some("IronSword".to_string()))];
+        let mut entries: Vec<Entry> = strings
+            .iter()
+            .map(|string| Entry {
+                key: string.get_unique_key(),
+                source_text: string.text.clone().into(),
+                target_text: Default::default(),
+            })
+            .collect();
+        entries.push(Entry {
+            key: "strings:7".to_string(),
+            source_text: "Line 1\nLine 2".into(),
+            target_text: Default::default(),
+        });
+        let params = XtranslatorExportParams::new("Test.esp", "english", "japanese")
+            .with_extracted(&strings);
+        let csv = export_entries_csv(&entries, &params);
+        assert_eq!(
+            csv,
+            "key,edid,rec,source,target\n\
+             WEAP:00012EB7:FULL:0,IronSword,WEAP:FULL,\"Sword, \"\"Iron\"\"\",\n\
+             strings:7,,,\"Line 1\nLine 2\",\n"
+        );
+
+        let filled = csv.replace("Iron\"\"\",\n", "Iron\"\"\",鉄の剣\n");
+        let corrections = crate::review_sheet::parse_corrections(&filled).expect("parse");
+        assert_eq!(corrections.len(), 2);
+        assert_eq!(corrections[0].key, "WEAP:00012EB7:FULL:0");
+        assert_eq!(corrections[0].target, "鉄の剣");
+        assert_eq!(corrections[1].target, "");
+    }
 }
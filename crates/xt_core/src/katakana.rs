@@ -0,0 +1,308 @@
+//! Rule-based katakana readings of English proper nouns (NPC names and the
+//! like), as a first draft for rows nobody translated yet. English spelling
+//! only loosely follows its sound, so the result is a guess that needs
+//! review: `Ulfric Stormcloak` becomes `ウルフリック・ストームクローク`, but
+//! `Hunt` becomes `フント`.
+
+/// Lowercase words allowed inside a proper noun, with fixed readings.
+const CONNECTORS: [(&str, &str); 3] = [("of", "オブ"), ("the", "ザ"), ("and", "アンド")];
+
+const A: usize = 0;
+const I: usize = 1;
+const U: usize = 2;
+const E: usize = 3;
+const O: usize = 4;
+
+/// Kana of a consonant followed by a, i, u, e and o, and of the consonant
+/// on its own.
+struct Row {
+    consonant: &'static str,
+    kana: [&'static str; 5],
+    alone: &'static str,
+}
+
+const fn row(consonant: &'static str, kana: [&'static str; 5], alone: &'static str) -> Row {
+    Row {
+        consonant,
+        kana,
+        alone,
+    }
+}
+
+const VOWELS: [&str; 5] = ["ア", "イ", "ウ", "エ", "オ"];
+
+/// Digraphs first, so they win over their first letter.
+const ROWS: [Row; 22] = [
+    row("ch", ["チャ", "チ", "チュ", "チェ", "チョ"], "チ"),
+    row("sh", ["シャ", "シ", "シュ", "シェ", "ショ"], "シュ"),
+    row("th", ["サ", "シ", "ス", "セ", "ソ"], "ス"),
+    row("ts", ["ツァ", "ツィ", "ツ", "ツェ", "ツォ"], "ツ"),
+    row("k", ["カ", "キ", "ク", "ケ", "コ"], "ク"),
+    row("g", ["ガ", "ギ", "グ", "ゲ", "ゴ"], "グ"),
+    row("s", ["サ", "シ", "ス", "セ", "ソ"], "ス"),
+    row("z", ["ザ", "ジ", "ズ", "ゼ", "ゾ"], "ズ"),
+    row("t", ["タ", "ティ", "トゥ", "テ", "ト"], "ト"),
+    row("d", ["ダ", "ディ", "ドゥ", "デ", "ド"], "ド"),
+    row("n", ["ナ", "ニ", "ヌ", "ネ", "ノ"], "ン"),
+    row("h", ["ハ", "ヒ", "フ", "ヘ", "ホ"], ""),
+    row("b", ["バ", "ビ", "ブ", "ベ", "ボ"], "ブ"),
+    row("p", ["パ", "ピ", "プ", "ペ", "ポ"], "プ"),
+    row("m", ["マ", "ミ", "ム", "メ", "モ"], "ム"),
+    row("y", ["ヤ", "イ", "ユ", "イェ", "ヨ"], "イ"),
+    row("r", ["ラ", "リ", "ル", "レ", "ロ"], "ル"),
+    row("l", ["ラ", "リ", "ル", "レ", "ロ"], "ル"),
+    row("w", ["ワ", "ウィ", "ウ", "ウェ", "ウォ"], "ウ"),
+    row("f", ["ファ", "フィ", "フ", "フェ", "フォ"], "フ"),
+    row("v", ["ヴァ", "ヴィ", "ヴ", "ヴェ", "ヴォ"], "ヴ"),
+    row("j", ["ジャ", "ジ", "ジュ", "ジェ", "ジョ"], "ジ"),
+];
+
+/// Whether `source` reads as a proper noun: one to four capitalized words
+/// of ASCII letters (apostrophes and hyphens allowed), with `of`, `the` and
+/// `and` allowed after the first.
+pub fn is_proper_noun(source: &str) -> bool {
+    let words = source.split_whitespace().collect::<Vec<_>>();
+    if words.is_empty() || words.len() > 4 {
+        return false;
+    }
+    words.iter().enumerate().all(|(index, word)| {
+        let letters = word
+            .chars()
+            .all(|c| c.is_ascii_alphabetic() || c == '\'' || c == '-');
+        let capitalized = word.starts_with(|c: char| c.is_ascii_uppercase());
+        let connector = index > 0 && CONNECTORS.iter().any(|(w, _)| w == word);
+        letters && (capitalized || connector)
+    })
+}
+
+/// Katakana reading of `source`, words joined with `・`; `None` unless it
+/// is a proper noun.
+pub fn transliterate(source: &str) -> Option<String> {
+    if !is_proper_noun(source) {
+        return None;
+    }
+    let words = source
+        .split_whitespace()
+        .flat_map(|word| word.split('-'))
+        .map(|word| transliterate_word(&word.to_ascii_lowercase()))
+        .filter(|kana| !kana.is_empty())
+        .collect::<Vec<_>>();
+    (!words.is_empty()).then(|| words.join("・"))
+}
+
+fn transliterate_word(word: &str) -> String {
+    if let Some((_, kana)) = CONNECTORS.iter().find(|(w, _)| *w == word) {
+        return kana.to_string();
+    }
+    let letters = spell_out(word);
+    let magic = magic_e(&letters);
+    let len = letters.len();
+    let mut out = String::new();
+    // Whether the last syllable ended in a single short vowel, which
+    // doubles a word-final k or t (`Ulfric` → ウルフリック).
+    let mut short = false;
+    let mut i = 0;
+    while i < len {
+        if is_vowel_at(&letters, i) {
+            let (vowel, long, used) = vowel_group(&letters, i, magic);
+            out.push_str(VOWELS[vowel]);
+            out.push_str(long);
+            short = long.is_empty();
+            i += used;
+            continue;
+        }
+        let row = consonant_row(&letters[i..]);
+        let mut used = row.consonant.len();
+        let doubled = letters.get(i + used) == Some(&letters[i + used - 1]);
+        if doubled && matches!(letters[i], b'k' | b't' | b'p' | b'g' | b'd' | b'b') {
+            // No small tsu before a final -le (`Battle` → バトル).
+            if !out.is_empty() && &letters[i + used + 1..] != b"le" {
+                out.push('ッ');
+            }
+            used += 1;
+        } else if doubled && !matches!(letters[i], b'n' | b'm') {
+            used += 1;
+        }
+        let next = i + used;
+        // Final e after a consonant is silent once the word had a vowel.
+        let silent_e = next + 1 == len
+            && letters[next] == b'e'
+            && len >= 4
+            && letters[..i].iter().any(|&c| is_vowel(c));
+        if next < len && is_vowel_at(&letters, next) && !silent_e {
+            let (vowel, long, used) = vowel_group(&letters, next, magic);
+            out.push_str(row.kana[vowel]);
+            out.push_str(long);
+            short = long.is_empty();
+            i = next + used;
+            continue;
+        }
+        let last = next + usize::from(silent_e) == len;
+        if row.consonant == "m" && matches!(letters.get(next), Some(b'b' | b'p')) {
+            out.push('ン');
+        } else {
+            if last && short && matches!(row.consonant, "k" | "t") && !out.ends_with('ッ') {
+                out.push('ッ');
+            }
+            out.push_str(row.alone);
+        }
+        short = false;
+        i = next + usize::from(silent_e);
+    }
+    out
+}
+
+/// Letters of `word` with the spellings that stand for other letters
+/// replaced: `c` by `s` or `k`, `ph` by `f`, `x` by `ks` and `q` by `k`.
+fn spell_out(word: &str) -> Vec<u8> {
+    let bytes = word
+        .bytes()
+        .filter(u8::is_ascii_alphabetic)
+        .collect::<Vec<_>>();
+    let mut letters = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let next = bytes.get(i + 1).copied();
+        match (bytes[i], next) {
+            (b'c', Some(b'h')) => letters.push(b'c'),
+            (b'c', Some(b'e' | b'i' | b'y')) => letters.push(b's'),
+            (b'c' | b'q', _) => letters.push(b'k'),
+            (b'p', Some(b'h')) => {
+                letters.push(b'f');
+                i += 1;
+            }
+            (b'x', _) => letters.extend_from_slice(b"ks"),
+            (letter, _) => letters.push(letter),
+        }
+        i += 1;
+    }
+    letters
+}
+
+fn is_vowel(letter: u8) -> bool {
+    matches!(letter, b'a' | b'e' | b'i' | b'o' | b'u')
+}
+
+/// Vowels, and `y` unless it starts a syllable (`Yrsa`, `Maya`).
+fn is_vowel_at(letters: &[u8], i: usize) -> bool {
+    match letters[i] {
+        b'y' => {
+            let before_vowel = letters.get(i + 1).is_some_and(|&c| is_vowel(c));
+            !(before_vowel && (i == 0 || is_vowel(letters[i - 1])))
+        }
+        letter => is_vowel(letter),
+    }
+}
+
+fn consonant_row(rest: &[u8]) -> &'static Row {
+    ROWS.iter()
+        .find(|row| rest.starts_with(row.consonant.as_bytes()))
+        .unwrap_or(&ROWS[4])
+}
+
+/// Index of the vowel lengthened by a silent final e (`Blade`, `Stone`),
+/// in words of one syllable only: longer names rarely follow it
+/// (`Delphine`).
+fn magic_e(letters: &[u8]) -> Option<usize> {
+    let len = letters.len();
+    if len < 4 || letters[len - 1] != b'e' || is_vowel(letters[len - 2]) {
+        return None;
+    }
+    let vowel = len - 3;
+    let one_syllable = !letters[..vowel].iter().any(|&c| is_vowel(c));
+    (matches!(letters[vowel], b'a' | b'i' | b'o' | b'u') && one_syllable).then_some(vowel)
+}
+
+/// The vowel sound starting at `i`: which of a/i/u/e/o, what lengthens it
+/// (`ー`, `イ`, `ウ` or nothing) and how many letters it took.
+fn vowel_group(letters: &[u8], i: usize, magic: Option<usize>) -> (usize, &'static str, usize) {
+    if magic == Some(i) {
+        return match letters[i] {
+            b'a' => (E, "イ", 1),
+            b'i' => (A, "イ", 1),
+            b'o' => (O, "ー", 1),
+            _ => (U, "ー", 1),
+        };
+    }
+    let second = letters
+        .get(i + 1)
+        .copied()
+        .filter(|_| is_vowel_at(letters, i + 1));
+    let pair = match (letters[i], second) {
+        (b'e', Some(b'e' | b'a')) | (b'i', Some(b'e')) => Some((I, "ー")),
+        (b'o', Some(b'o')) | (b'u', Some(b'u')) => Some((U, "ー")),
+        (b'o', Some(b'a')) | (b'a', Some(b'u')) => Some((O, "ー")),
+        (b'o', Some(b'u')) => Some((A, "ウ")),
+        (b'a' | b'e', Some(b'i' | b'y')) => Some((E, "イ")),
+        (b'o', Some(b'i' | b'y')) => Some((O, "イ")),
+        _ => None,
+    };
+    if let Some((vowel, long)) = pair {
+        return (vowel, long, 2);
+    }
+    let vowel = match letters[i] {
+        b'a' => A,
+        b'e' => E,
+        b'o' => O,
+        b'u' => U,
+        _ => I,
+    };
+    // A vowel before an r that starts no syllable is long (`Farkas`,
+    // `Esbern`).
+    let r_after = letters.get(i + 1) == Some(&b'r')
+        && letters
+            .get(i + 2)
+            .is_none_or(|&c| !is_vowel(c) && c != b'r' && c != b'y');
+    if r_after {
+        let vowel = if matches!(vowel, A | O) { vowel } else { A };
+        return (vowel, "ー", 2);
+    }
+    // So are a and o before a w that starts no syllable (`Dawnbreaker`,
+    // `Crow`).
+    let w_after =
+        letters.get(i + 1) == Some(&b'w') && letters.get(i + 2).is_none_or(|&c| !is_vowel(c));
+    if w_after && matches!(vowel, A | O) {
+        return (O, "ー", 2);
+    }
+    if letters[i] == b'y' && i > 0 && i + 1 == letters.len() {
+        return (I, "ー", 1);
+    }
+    (vowel, "", 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_kana_001_proper_nouns_transliterated() {
+        let cases = [
+            ("Ulfric Stormcloak", "ウルフリック・ストームクローク"),
+            ("Lydia", "リディア"),
+            ("Balgruuf the Greater", "バルグルーフ・ザ・グリーター"),
+            ("Farkas", "ファーカス"),
+            ("Irileth", "イリレス"),
+            ("Delphine", "デルフィン"),
+            ("Esbern", "エスバーン"),
+            ("Nazeem", "ナジーム"),
+            ("Rikke", "リック"),
+            ("Mjoll the Lioness", "ムジョル・ザ・リオネス"),
+            ("Hadvar", "ハドヴァー"),
+            ("Maya", "マヤ"),
+            ("Jon Battle-Born", "ジョン・バトル・ボーン"),
+            ("Blade", "ブレイド"),
+            ("Anna", "アンナ"),
+            ("Dawnbreaker", "ドーンブリーカー"),
+        ];
+        for (source, kana) in cases {
+            assert_eq!(transliterate(source).as_deref(), Some(kana), "{source}");
+        }
+        assert!(is_proper_noun("Heimskr"));
+        assert!(!is_proper_noun("the Bannered Mare"));
+        assert!(!is_proper_noun("Iron sword"));
+        assert!(!is_proper_noun("Take the sword."));
+        assert!(!is_proper_noun("A B C D E"));
+        assert!(!is_proper_noun("鉄の剣"));
+        assert_eq!(transliterate(""), None);
+    }
+}
@@ -1,12 +1,33 @@
+pub mod candidates;
+pub mod checksum;
 pub mod dictionary;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dictionary_store;
 pub mod diff;
+pub mod duplicates;
 pub mod encoding;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod extract_cache;
 pub mod formats;
+pub mod game;
 pub mod heuristics;
 pub mod hybrid;
 pub mod import_export;
+pub mod katakana;
+pub mod loadorder;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod mo2;
 pub mod model;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod package;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod pipeline;
+pub mod placeholders;
+pub mod record_index;
+pub mod review_sheet;
+pub mod rewrite;
 pub mod search;
+pub mod spellcheck;
 pub mod tm;
 pub mod ui_state;
 pub mod undo;
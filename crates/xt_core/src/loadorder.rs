@@ -0,0 +1,326 @@
+//! Load order of plugins and which plugin's record wins for a form id.
+//!
+//! A plugin's form ids carry a master index in the top byte: an index into
+//! its own master list, or one past it for records the plugin creates.
+//! Resolving that index names the plugin that created the record, so the
+//! same record can be recognized in a master and in the patches that
+//! override it; the last of them in load order wins.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::model::{Entry, SharedText};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadOrderPlugin {
+    /// File name, e.g. `Skyrim.esm`.
+    pub name: String,
+    /// MAST entries, in the order the plugin lists them.
+    pub masters: Vec<String>,
+}
+
+/// Plugins in the order the game loads them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadOrder {
+    plugins: Vec<LoadOrderPlugin>,
+}
+
+/// A record identified independently of the plugin it was read from.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GlobalFormId {
+    /// Lowercased file name of the plugin that created the record.
+    pub origin: String,
+    /// Form id without its master index.
+    pub object_id: u32,
+}
+
+impl std::fmt::Display for GlobalFormId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:06X}@{}", self.object_id, self.origin)
+    }
+}
+
+impl LoadOrder {
+    /// Orders `plugins` so each loads after those of its masters that are
+    /// present, otherwise keeping the given order.
+    pub fn new(plugins: Vec<LoadOrderPlugin>) -> Self {
+        let mut pending = plugins;
+        let mut ordered: Vec<LoadOrderPlugin> = Vec::with_capacity(pending.len());
+        while !pending.is_empty() {
+            let ready = pending
+                .iter()
+                .position(|plugin| {
+                    plugin.masters.iter().all(|master| {
+                        !pending.iter().any(|other| same_name(&other.name, master))
+                            || same_name(&plugin.name, master)
+                    })
+                })
+                // Masters that depend on each other: keep the given order.
+                .unwrap_or(0);
+            ordered.push(pending.remove(ready));
+        }
+        Self { plugins: ordered }
+    }
+
+    pub fn plugins(&self) -> &[LoadOrderPlugin] {
+        &self.plugins
+    }
+
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.plugins
+            .iter()
+            .position(|plugin| same_name(&plugin.name, name))
+    }
+
+    /// The record `form_id` of the plugin at `plugin` refers to.
+    pub fn resolve(&self, plugin: usize, form_id: u32) -> GlobalFormId {
+        self.plugins[plugin].resolve(form_id)
+    }
+}
+
+impl LoadOrderPlugin {
+    /// The record this plugin's `form_id` refers to.
+    pub fn resolve(&self, form_id: u32) -> GlobalFormId {
+        let index = (form_id >> 24) as usize;
+        let origin = self.masters.get(index).unwrap_or(&self.name);
+        GlobalFormId {
+            origin: origin.to_ascii_lowercase(),
+            object_id: form_id & 0x00FF_FFFF,
+        }
+    }
+}
+
+fn same_name(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+/// One plugin's text for an overridden field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    /// Position in the load order.
+    pub position: usize,
+    pub plugin: String,
+    pub key: String,
+    pub source: SharedText,
+    pub target: SharedText,
+}
+
+/// A record field (`FULL`, `DESC`, ...) that more than one plugin defines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Override {
+    pub form: GlobalFormId,
+    pub record_type: String,
+    pub subrecord_type: String,
+    pub index: usize,
+    /// In load order; the last one wins.
+    pub definitions: Vec<Definition>,
+}
+
+impl Override {
+    pub fn winner(&self) -> &Definition {
+        &self.definitions[self.definitions.len() - 1]
+    }
+
+    /// Whether the plugins disagree on the source text.
+    pub fn source_differs(&self) -> bool {
+        let first = &self.definitions[0].source;
+        self.definitions
+            .iter()
+            .any(|definition| &definition.source != first)
+    }
+}
+
+/// Fields defined by more than one of `plugins` (file name and entries, as
+/// extracted from the plugin), ordered by record. Plugins missing from
+/// `order` and entries that are not plugin record fields are ignored.
+pub fn find_overrides(order: &LoadOrder, plugins: &[(&str, &[Entry])]) -> Vec<Override> {
+    type Field = (GlobalFormId, String, String, usize);
+    let mut fields: BTreeMap<Field, Vec<Definition>> = BTreeMap::new();
+    for &(name, entries) in plugins {
+        let Some(position) = order.position(name) else {
+            continue;
+        };
+        for entry in entries {
+            let Some((record_type, form_id, subrecord_type, index)) = parse_record_key(&entry.key)
+            else {
+                continue;
+            };
+            let field = (
+                order.resolve(position, form_id),
+                record_type.to_string(),
+                subrecord_type.to_string(),
+                index,
+            );
+            fields.entry(field).or_default().push(Definition {
+                position,
+                plugin: order.plugins[position].name.clone(),
+                key: entry.key.clone(),
+                source: entry.source_text.clone(),
+                target: entry.target_text.clone(),
+            });
+        }
+    }
+    fields
+        .into_iter()
+        .filter(|(_, definitions)| definitions.len() > 1)
+        .map(
+            |((form, record_type, subrecord_type, index), mut definitions)| {
+                definitions.sort_by_key(|definition| definition.position);
+                Override {
+                    form,
+                    record_type,
+                    subrecord_type,
+                    index,
+                    definitions,
+                }
+            },
+        )
+        .collect()
+}
+
+/// For each of `entries` (extracted from `plugin`), the index of the entry
+/// of `other` that holds the same field of the same record, whatever master
+/// indices the two plugins give it. Entries that are not plugin record
+/// fields match nothing.
+pub fn match_fields(
+    plugin: &LoadOrderPlugin,
+    entries: &[Entry],
+    other: &LoadOrderPlugin,
+    other_entries: &[Entry],
+) -> Vec<Option<usize>> {
+    let field = |owner: &LoadOrderPlugin, key: &str| {
+        let (record_type, form_id, subrecord_type, index) = parse_record_key(key)?;
+        Some((
+            owner.resolve(form_id),
+            record_type.to_string(),
+            subrecord_type.to_string(),
+            index,
+        ))
+    };
+    let mut by_field = HashMap::new();
+    for (position, entry) in other_entries.iter().enumerate() {
+        if let Some(field) = field(other, &entry.key) {
+            by_field.entry(field).or_insert(position);
+        }
+    }
+    entries
+        .iter()
+        .map(|entry| by_field.get(&field(plugin, &entry.key)?).copied())
+        .collect()
+}
+
+/// Splits a plugin entry key `WEAP:00012EB7:FULL:0`.
+fn parse_record_key(key: &str) -> Option<(&str, u32, &str, usize)> {
+    let mut parts = key.split(':');
+    let record_type = parts.next()?;
+    let form_id = u32::from_str_radix(parts.next()?, 16).ok()?;
+    let subrecord_type = parts.next()?;
+    let index = parts.next()?.parse().ok()?;
+    if parts.next().is_some()
+        || record_type.chars().count() != 4
+        || subrecord_type.chars().count() != 4
+    {
+        return None;
+    }
+    Some((record_type, form_id, subrecord_type, index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugin(name: &str, masters: &[&str]) -> LoadOrderPlugin {
+        LoadOrderPlugin {
+            name: name.to_string(),
+            masters: masters.iter().map(|master| master.to_string()).collect(),
+        }
+    }
+
+    fn entry(key: &str, source: &str, target: &str) -> Entry {
+        Entry {
+            key: key.to_string(),
+            source_text: source.into(),
+            target_text: target.into(),
+        }
+    }
+
+    #[test]
+    fn t_lo_001_overrides_resolved_through_master_indices() {
+        let order = LoadOrder::new(vec![
+            plugin("Patch.esp", &["Skyrim.esm", "Dawnguard.esm"]),
+            plugin("Dawnguard.esm", &["Skyrim.esm"]),
+            plugin("Skyrim.esm", &[]),
+        ]);
+        let names = order
+            .plugins()
+            .iter()
+            .map(|plugin| plugin.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Skyrim.esm", "Dawnguard.esm", "Patch.esp"]);
+        assert_eq!(
+            order.resolve(2, 0x0100_0ABC).to_string(),
+            "000ABC@dawnguard.esm"
+        );
+        assert_eq!(order.resolve(2, 0x0200_0001).origin, "patch.esp");
+
+        let skyrim = vec![
+            entry("WEAP:00012EB7:FULL:0", "Iron Sword", "鉄の剣"),
+            entry("WEAP:00012EB8:FULL:0", "Steel Sword", ""),
+            entry("strings:5", "Loose", ""),
+        ];
+        let dawnguard = vec![entry("WEAP:01000ABC:FULL:0", "Crossbow", "クロスボウ")];
+        let patch = vec![
+            entry("WEAP:00012EB7:FULL:0", "Iron Longsword", ""),
+            entry("WEAP:01000ABC:FULL:0", "Crossbow", ""),
+            entry("WEAP:02000001:FULL:0", "New Sword", ""),
+        ];
+        let overrides = find_overrides(
+            &order,
+            &[
+                ("patch.esp", &patch),
+                ("Skyrim.esm", &skyrim),
+                ("Dawnguard.esm", &dawnguard),
+                ("Missing.esp", &skyrim),
+            ],
+        );
+        assert_eq!(overrides.len(), 2);
+        let crossbow = &overrides[0];
+        assert_eq!(crossbow.form.origin, "dawnguard.esm");
+        assert_eq!(crossbow.winner().plugin, "Patch.esp");
+        assert_eq!(crossbow.definitions[0].target, "クロスボウ");
+        assert!(!crossbow.source_differs());
+        let sword = &overrides[1];
+        assert_eq!(
+            (
+                sword.record_type.as_str(),
+                sword.subrecord_type.as_str(),
+                sword.index
+            ),
+            ("WEAP", "FULL", 0)
+        );
+        assert_eq!(sword.definitions[0].plugin, "Skyrim.esm");
+        assert_eq!(sword.winner().source, "Iron Longsword");
+        assert!(sword.source_differs());
+    }
+
+    #[test]
+    fn t_lo_002_fields_matched_across_master_lists() {
+        let patch = plugin("Patch.esp", &["Skyrim.esm", "Dawnguard.esm"]);
+        let other = plugin("Other.esp", &["Dawnguard.esm", "Skyrim.esm"]);
+        let entries = vec![
+            entry("WEAP:00012EB7:FULL:0", "Iron Sword", ""),
+            entry("WEAP:01000ABC:DESC:0", "Bolts", ""),
+            entry("WEAP:02000001:FULL:0", "New Sword", ""),
+            entry("plugin:00000010", "Raw", ""),
+        ];
+        let other_entries = vec![
+            entry("WEAP:00000ABC:DESC:0", "Bolts", "矢弾"),
+            entry("WEAP:01012EB7:FULL:0", "Iron Sword", "鉄の剣"),
+            entry("WEAP:01012EB7:FULL:1", "Iron Sword", "鉄の剣"),
+            entry("WEAP:02000001:FULL:0", "Other Sword", "別の剣"),
+        ];
+        assert_eq!(
+            match_fields(&patch, &entries, &other, &other_entries),
+            [Some(1), Some(0), None, None]
+        );
+    }
+}
@@ -0,0 +1,325 @@
+//! Mod Organizer 2 instances: how a profile's enabled mods stack into the
+//! game's virtual `Data` folder, so a plugin loaded from
+//! `<instance>/mods/<mod>/` finds the Strings files the game would see.
+
+use std::path::{Path, PathBuf};
+
+use crate::formats::esp::{GameProfile, StringsLocation};
+
+/// Profile used when `ModOrganizer.ini` does not name one.
+pub const DEFAULT_PROFILE: &str = "Default";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Mo2Error {
+    #[error("{} is not inside a Mod Organizer 2 mods folder", .0.display())]
+    NotInMods(PathBuf),
+    #[error("Mod Organizer 2 profile {profile} not found in {}", dir.display())]
+    MissingProfile { dir: PathBuf, profile: String },
+    #[error("read {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+/// The instance a plugin under `<root>/mods/<mod>/` belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mo2Instance {
+    /// Folder holding `mods/`, `profiles/` and `overwrite/`.
+    pub root: PathBuf,
+    /// Mod folder the plugin lives in.
+    pub mod_name: String,
+    pub profile: String,
+    /// Game folder from `ModOrganizer.ini`, when it names one.
+    pub game_dir: Option<PathBuf>,
+}
+
+/// Strings folders of an instance in the order the game sees them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mo2Overlay {
+    /// Highest priority first.
+    pub search: Vec<PathBuf>,
+    /// Where the plugin's Strings files are written: the folder the game
+    /// reads them from now, else the plugin's own mod.
+    pub output: PathBuf,
+}
+
+impl Mo2Overlay {
+    pub fn location(&self) -> StringsLocation<'_> {
+        StringsLocation::Overlay {
+            search: &self.search,
+            output: &self.output,
+        }
+    }
+}
+
+impl Mo2Instance {
+    /// Finds the instance of the plugin at `plugin_path`. `profile`
+    /// overrides the one selected in `ModOrganizer.ini`.
+    pub fn detect(plugin_path: &Path, profile: Option<&str>) -> Result<Self, Mo2Error> {
+        let not_in_mods = || Mo2Error::NotInMods(plugin_path.to_path_buf());
+        let mod_dir = plugin_path.parent().ok_or_else(not_in_mods)?;
+        let mods_dir = mod_dir.parent().ok_or_else(not_in_mods)?;
+        let is_mods = mods_dir
+            .file_name()
+            .is_some_and(|name| name.eq_ignore_ascii_case("mods"));
+        let root = mods_dir.parent().ok_or_else(not_in_mods)?;
+        if !is_mods || !root.join("profiles").is_dir() {
+            return Err(not_in_mods());
+        }
+        let mod_name = mod_dir
+            .file_name()
+            .ok_or_else(not_in_mods)?
+            .to_string_lossy()
+            .into_owned();
+
+        let ini = read_ini(&root.join("ModOrganizer.ini"))?;
+        let profile = profile
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .or(ini.selected_profile)
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+        let profile_dir = root.join("profiles").join(&profile);
+        if !profile_dir.is_dir() {
+            return Err(Mo2Error::MissingProfile {
+                dir: root.join("profiles"),
+                profile,
+            });
+        }
+        Ok(Self {
+            root: root.to_path_buf(),
+            mod_name,
+            profile,
+            game_dir: ini.game_dir,
+        })
+    }
+
+    /// Enabled mods from the profile's `modlist.txt`, highest priority first
+    /// (MO2 lists them top-down that way). Separators are skipped.
+    pub fn enabled_mods(&self) -> Result<Vec<String>, Mo2Error> {
+        let path = self
+            .root
+            .join("profiles")
+            .join(&self.profile)
+            .join("modlist.txt");
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(source) => return Err(Mo2Error::Io { path, source }),
+        };
+        Ok(text
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix('+'))
+            .filter(|name| !name.is_empty() && !name.ends_with("_separator"))
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// `overwrite`, the enabled mods, the plugin's own mod when the profile
+    /// has it disabled, then the game's `Data` folder — each one's `Strings`
+    /// folder.
+    pub fn strings_overlay(
+        &self,
+        plugin_path: &Path,
+        profile: GameProfile,
+    ) -> Result<Mo2Overlay, Mo2Error> {
+        let mods = self.enabled_mods()?;
+        let mod_strings = |name: &str| child_dir(&self.root.join("mods").join(name), "Strings");
+        let own = mod_strings(&self.mod_name);
+        let mut search = vec![child_dir(&self.root.join("overwrite"), "Strings")];
+        search.extend(mods.iter().map(|name| mod_strings(name)));
+        if !mods.contains(&self.mod_name) {
+            search.push(own.clone());
+        }
+        if let Some(game_dir) = &self.game_dir {
+            search.push(profile.strings_dir(game_dir));
+        }
+
+        let prefix = plugin_path
+            .file_stem()
+            .map(|stem| format!("{}_", stem.to_string_lossy().to_lowercase()))
+            .unwrap_or_default();
+        let output = search
+            .iter()
+            .find(|dir| has_strings_for(dir, &prefix))
+            .cloned()
+            .unwrap_or(own);
+        Ok(Mo2Overlay { search, output })
+    }
+}
+
+#[derive(Default)]
+struct Mo2Ini {
+    selected_profile: Option<String>,
+    game_dir: Option<PathBuf>,
+}
+
+/// `selected_profile` and `gamePath` from `[General]`; a missing file reads
+/// as empty.
+fn read_ini(path: &Path) -> Result<Mo2Ini, Mo2Error> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Mo2Ini::default()),
+        Err(source) => {
+            return Err(Mo2Error::Io {
+                path: path.to_path_buf(),
+                source,
+            })
+        }
+    };
+    let mut ini = Mo2Ini::default();
+    let mut general = false;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('[') {
+            general = line.eq_ignore_ascii_case("[General]");
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=').filter(|_| general) else {
+            continue;
+        };
+        let value = ini_value(value);
+        if value.is_empty() {
+            continue;
+        }
+        match key.trim() {
+            "selected_profile" => ini.selected_profile = Some(value),
+            "gamePath" => ini.game_dir = Some(PathBuf::from(value)),
+            _ => {}
+        }
+    }
+    Ok(ini)
+}
+
+/// Unwraps Qt's `@ByteArray(...)` and its doubled backslashes.
+fn ini_value(raw: &str) -> String {
+    let raw = raw.trim();
+    let inner = raw
+        .strip_prefix("@ByteArray(")
+        .and_then(|rest| rest.strip_suffix(')'))
+        .unwrap_or(raw);
+    inner.replace("\\\\", "\\")
+}
+
+/// `dir/name`, matching an existing child's case (mods often ship
+/// `strings/`).
+fn child_dir(dir: &Path, name: &str) -> PathBuf {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.is_dir()
+                && path
+                    .file_name()
+                    .is_some_and(|found| found.eq_ignore_ascii_case(name))
+        })
+        .unwrap_or_else(|| dir.join(name))
+}
+
+fn has_strings_for(dir: &Path, prefix: &str) -> bool {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .any(|entry| {
+            let name = entry.file_name().to_string_lossy().to_lowercase();
+            name.starts_with(prefix)
+                && [".strings", ".dlstrings", ".ilstrings"]
+                    .iter()
+                    .any(|ext| name.ends_with(ext))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::{plugin_languages, PluginContext};
+
+    #[test]
+    fn t_mo2_001_profile_overlay_follows_modlist_priority() {
+        let root = std::env::temp_dir().join(format!("xt_mo2_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let game = root.join("game");
+        let instance = root.join("MO2");
+        for dir in [
+            game.join("Data").join("Strings"),
+            instance.join("profiles").join("Default"),
+            instance.join("profiles").join("Japanese"),
+            instance.join("mods").join("ModA").join("Strings"),
+            instance.join("mods").join("ModA JP").join("strings"),
+            instance.join("mods").join("Off").join("Strings"),
+            instance.join("overwrite"),
+        ] {
+            std::fs::create_dir_all(dir).expect("create");
+        }
+        std::fs::write(
+            instance.join("ModOrganizer.ini"),
+            format!(
+                "[General]\ngamePath=@ByteArray({})\nselected_profile=@ByteArray(Japanese)\n",
+                game.display().to_string().replace('\\', "\\\\")
+            ),
+        )
+        .expect("ini");
+        std::fs::write(
+            instance
+                .join("profiles")
+                .join("Japanese")
+                .join("modlist.txt"),
+            "# managed by MO2\n+ModA JP\n-Off\n+Tools_separator\n*DLC: Dawnguard\n+ModA\n",
+        )
+        .expect("modlist");
+        let jp_strings = instance.join("mods").join("ModA JP").join("strings");
+        std::fs::write(jp_strings.join("moda_english.strings"), b"").expect("strings");
+        let plugin = instance.join("mods").join("ModA").join("ModA.esp");
+
+        let mo2 = Mo2Instance::detect(&plugin, None).expect("detect");
+        assert_eq!(mo2.profile, "Japanese", "selected in ModOrganizer.ini");
+        assert_eq!(mo2.mod_name, "ModA");
+        assert_eq!(mo2.game_dir.as_deref(), Some(game.as_path()));
+        assert_eq!(mo2.enabled_mods().expect("mods"), ["ModA JP", "ModA"]);
+
+        let overlay = mo2
+            .strings_overlay(&plugin, GameProfile::default())
+            .expect("overlay");
+        assert_eq!(
+            overlay.search,
+            [
+                instance.join("overwrite").join("Strings"),
+                jp_strings.clone(),
+                instance.join("mods").join("ModA").join("Strings"),
+                game.join("Data").join("Strings"),
+            ]
+        );
+        assert_eq!(overlay.output, jp_strings, "written where the game reads");
+        std::fs::write(jp_strings.join("ModA_japanese.STRINGS"), b"").expect("strings");
+        let context =
+            PluginContext::resolve_mo2(&plugin, None, None, None).expect("resolve context");
+        assert_eq!(context.workspace_root, game);
+        assert_eq!(context.language, "english");
+        assert_eq!(
+            plugin_languages(&plugin, context.strings_location(), context.profile)
+                .expect("languages"),
+            ["english", "japanese"]
+        );
+
+        let default = Mo2Instance::detect(&plugin, Some("Default")).expect("default");
+        let overlay = default
+            .strings_overlay(&plugin, GameProfile::default())
+            .expect("overlay");
+        assert_eq!(
+            overlay.output,
+            instance.join("mods").join("ModA").join("Strings"),
+            "a disabled plugin mod still holds its own Strings"
+        );
+        assert!(matches!(
+            Mo2Instance::detect(&plugin, Some("Missing")),
+            Err(Mo2Error::MissingProfile { .. })
+        ));
+        assert!(matches!(
+            Mo2Instance::detect(&game.join("Data").join("ModA.esp"), None),
+            Err(Mo2Error::NotInMods(_))
+        ));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
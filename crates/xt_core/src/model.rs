@@ -1,6 +1,217 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Entry {
     pub key: String,
-    pub source_text: String,
-    pub target_text: String,
+    pub source_text: SharedText,
+    pub target_text: SharedText,
+}
+
+/// Immutable text behind an `Arc<str>`. Cloning an [`Entry`] (job snapshots,
+/// edit history) shares its texts instead of copying them; a change replaces
+/// the whole value.
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SharedText(Arc<str>);
+
+impl SharedText {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Both values point at the same allocation.
+    pub fn ptr_eq(a: &Self, b: &Self) -> bool {
+        Arc::ptr_eq(&a.0, &b.0)
+    }
+}
+
+impl Deref for SharedText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for SharedText {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for SharedText {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SharedText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for SharedText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for SharedText {
+    fn from(text: &str) -> Self {
+        Self(Arc::from(text))
+    }
+}
+
+impl From<String> for SharedText {
+    fn from(text: String) -> Self {
+        Self(Arc::from(text))
+    }
+}
+
+impl From<&String> for SharedText {
+    fn from(text: &String) -> Self {
+        Self(Arc::from(text.as_str()))
+    }
+}
+
+impl From<SharedText> for String {
+    fn from(text: SharedText) -> Self {
+        text.0.to_string()
+    }
+}
+
+impl PartialEq<str> for SharedText {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for SharedText {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for SharedText {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl PartialEq<SharedText> for str {
+    fn eq(&self, other: &SharedText) -> bool {
+        self == &*other.0
+    }
+}
+
+impl PartialEq<SharedText> for &str {
+    fn eq(&self, other: &SharedText) -> bool {
+        *self == &*other.0
+    }
+}
+
+impl PartialEq<SharedText> for String {
+    fn eq(&self, other: &SharedText) -> bool {
+        **self == *other.0
+    }
+}
+
+/// Who last changed an entry's target, and when.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditMeta {
+    pub author: String,
+    /// Unix seconds.
+    pub modified_at: u64,
+}
+
+/// Edit metadata by entry key. Entries without a record are simply absent.
+pub type EditMetaMap = HashMap<String, EditMeta>;
+
+/// Translator notes by entry key, e.g. "check gender". Entries without a
+/// note are absent; notes never touch the target text.
+pub type NoteMap = HashMap<String, String>;
+
+/// Calendar fields of a unix time in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UtcDateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl UtcDateTime {
+    pub fn from_unix(secs: u64) -> Self {
+        let days = (secs / 86_400) as i64;
+        let rest = (secs % 86_400) as u32;
+        // Civil-from-days (Howard Hinnant).
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        Self {
+            year: yoe + era * 400 + i64::from(month <= 2),
+            month: month as u32,
+            day: day as u32,
+            hour: rest / 3600,
+            minute: rest % 3600 / 60,
+            second: rest % 60,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_model_001_shared_text_clone_shares_and_compares_as_str() {
+        let entry = Entry {
+            key: "k1".to_string(),
+            source_text: "Iron Sword".into(),
+            target_text: String::from("鉄の剣").into(),
+        };
+        let copy = entry.clone();
+        assert!(SharedText::ptr_eq(&entry.source_text, &copy.source_text));
+        assert!(SharedText::ptr_eq(&entry.target_text, &copy.target_text));
+
+        assert_eq!(copy.source_text, "Iron Sword");
+        assert_eq!("鉄の剣", copy.target_text);
+        assert_eq!(copy.target_text, "鉄の剣".to_string());
+        assert_eq!(copy.source_text.len(), 10);
+        assert!(SharedText::default().is_empty());
+        assert_eq!(String::from(copy.target_text), "鉄の剣");
+
+        let by_source = HashMap::from([(entry.source_text.clone(), 1)]);
+        assert_eq!(by_source.get("Iron Sword"), Some(&1));
+    }
+
+    #[test]
+    fn t_model_002_utc_date_time_from_unix() {
+        assert_eq!(
+            UtcDateTime::from_unix(0),
+            UtcDateTime {
+                year: 1970,
+                month: 1,
+                day: 1,
+                hour: 0,
+                minute: 0,
+                second: 0,
+            }
+        );
+        // 2024-02-29 23:59:58 UTC
+        let leap = UtcDateTime::from_unix(1_709_251_198);
+        assert_eq!((leap.year, leap.month, leap.day), (2024, 2, 29));
+        assert_eq!((leap.hour, leap.minute, leap.second), (23, 59, 58));
+    }
 }
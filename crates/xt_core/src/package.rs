@@ -0,0 +1,518 @@
+//! Translation packages for upload: the translated files laid out like the
+//! game's `Data` folder, plus the translation XML and a manifest under
+//! `xtrans/`, in one zip named after the mod, language and version.
+
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use zip::result::ZipError;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, DateTime, ZipWriter};
+
+use crate::formats::dsd::{dsd_output_dir, export_dsd_json, DsdExportParams};
+use crate::formats::esp::{
+    apply_translations, read_masters, translated_strings, EspError, ExtractedString,
+    StringsLocation,
+};
+use crate::formats::strings::StringsFile;
+use crate::model::{Entry, UtcDateTime};
+use crate::pipeline::{
+    ensure_writable, save_strings, write_atomic, write_error, PipelineError, PluginContext,
+    StringsKind,
+};
+
+/// Folder in the archive for files that are not meant for `Data`.
+pub const PACKAGE_META_DIR: &str = "xtrans";
+/// Manifest path in the archive.
+pub const MANIFEST_PATH: &str = "xtrans/package.json";
+/// Bumped when the manifest fields change meaning.
+pub const MANIFEST_FORMAT: u32 = 1;
+
+static NEXT_STAGE: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, thiserror::Error)]
+pub enum PackageError {
+    #[error("package {0} is empty")]
+    MissingField(&'static str),
+    #[error("zip: {0}")]
+    Zip(#[from] ZipError),
+    #[error("{action} {}: {source}", path.display())]
+    Io {
+        action: &'static str,
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Pipeline(#[from] PipelineError),
+}
+
+/// What the package says it is.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageManifest {
+    /// Name of the translated mod, as on its download page.
+    pub mod_name: String,
+    /// Version of the translated mod this translation was made for.
+    pub mod_version: String,
+    pub language: String,
+    pub author: String,
+    /// Unix seconds; also the time stamp of every archived file.
+    pub created_at: u64,
+}
+
+impl PackageManifest {
+    /// `<mod>-<language>-<version>.zip`, with characters Windows rejects in
+    /// file names replaced by `_`.
+    pub fn file_name(&self) -> String {
+        let parts = [&self.mod_name, &self.language, &self.mod_version]
+            .into_iter()
+            .map(|part| file_name_part(part.trim()))
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>();
+        format!("{}.zip", parts.join("-"))
+    }
+
+    fn check(&self) -> Result<(), PackageError> {
+        for (field, value) in [
+            ("mod name", &self.mod_name),
+            ("mod version", &self.mod_version),
+            ("language", &self.language),
+        ] {
+            if value.trim().is_empty() {
+                return Err(PackageError::MissingField(field));
+            }
+        }
+        Ok(())
+    }
+
+    fn to_json(&self, content: &str, files: &[String]) -> String {
+        let at = UtcDateTime::from_unix(self.created_at);
+        let archived = ArchiveManifest {
+            format: MANIFEST_FORMAT,
+            mod_name: self.mod_name.trim().to_string(),
+            mod_version: self.mod_version.trim().to_string(),
+            language: self.language.trim().to_string(),
+            author: self.author.trim().to_string(),
+            created_at: format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+                at.year, at.month, at.day, at.hour, at.minute, at.second
+            ),
+            content: content.to_string(),
+            files: files.to_vec(),
+        };
+        // Strings and integers only, which always serialize.
+        let mut json = serde_json::to_string_pretty(&archived).expect("serialize manifest");
+        json.push('\n');
+        json
+    }
+}
+
+/// [`MANIFEST_PATH`] in the archive: the [`PackageManifest`] plus what the
+/// package holds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    /// [`MANIFEST_FORMAT`].
+    pub format: u32,
+    pub mod_name: String,
+    pub mod_version: String,
+    pub language: String,
+    pub author: String,
+    /// UTC, `YYYY-MM-DDTHH:MM:SSZ`.
+    pub created_at: String,
+    /// [`PackageContent::id`].
+    pub content: String,
+    /// Archived paths, the manifest included.
+    pub files: Vec<String>,
+}
+
+/// The files that carry the translation.
+#[derive(Debug, Clone, Copy)]
+pub enum PackageContent<'a> {
+    /// The translated plugin, plus its Strings files when it is localized.
+    Plugin {
+        input_path: &'a Path,
+        extracted: &'a [ExtractedString],
+        context: &'a PluginContext,
+    },
+    /// A DSD JSON for the untouched plugin.
+    Dsd {
+        input_path: &'a Path,
+        extracted: &'a [ExtractedString],
+    },
+    /// One translated Strings file, archived under `Strings/`.
+    Strings {
+        base: &'a StringsFile,
+        kind: StringsKind,
+        file_name: &'a str,
+    },
+}
+
+impl PackageContent<'_> {
+    pub fn id(&self) -> &'static str {
+        match self {
+            PackageContent::Plugin { .. } => "plugin",
+            PackageContent::Dsd { .. } => "dsd",
+            PackageContent::Strings { .. } => "strings",
+        }
+    }
+
+    /// Stem of the translated file, used to name the XML.
+    fn stem(&self) -> String {
+        let name = match self {
+            PackageContent::Plugin { input_path, .. } | PackageContent::Dsd { input_path, .. } => {
+                input_path.file_name().unwrap_or_default().to_string_lossy()
+            }
+            PackageContent::Strings { file_name, .. } => (*file_name).into(),
+        };
+        Path::new(name.as_ref())
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// Archive paths written by [`create_package`], in archive order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageReport {
+    pub files: Vec<String>,
+}
+
+/// Writes the package to `path`. `xml` is the translation XML, archived as
+/// `xtrans/<stem>_<language>.xml` next to the manifest.
+pub fn create_package(
+    path: &Path,
+    entries: &[Entry],
+    content: PackageContent<'_>,
+    xml: &str,
+    manifest: &PackageManifest,
+) -> Result<PackageReport, PackageError> {
+    manifest.check()?;
+    ensure_writable(path)?;
+    let stage = std::env::temp_dir().join(format!(
+        "xt_package_{}_{}",
+        std::process::id(),
+        NEXT_STAGE.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = stage_and_zip(&stage, path, entries, content, xml, manifest);
+    let _ = std::fs::remove_dir_all(&stage);
+    result
+}
+
+fn stage_and_zip(
+    stage: &Path,
+    path: &Path,
+    entries: &[Entry],
+    content: PackageContent<'_>,
+    xml: &str,
+    manifest: &PackageManifest,
+) -> Result<PackageReport, PackageError> {
+    stage_content(stage, entries, content)?;
+    let mut files = collect_files(stage, "")?;
+    let meta = stage.join(PACKAGE_META_DIR);
+    create_dir(&meta)?;
+    let xml_name = format!(
+        "{}_{}.xml",
+        content.stem(),
+        file_name_part(manifest.language.trim())
+    );
+    write_file(&meta.join(&xml_name), xml.as_bytes())?;
+    files.push((
+        format!("{PACKAGE_META_DIR}/{xml_name}"),
+        meta.join(&xml_name),
+    ));
+
+    let mut names = files
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<_>>();
+    names.push(MANIFEST_PATH.to_string());
+    let manifest_file = stage.join(MANIFEST_PATH);
+    write_file(
+        &manifest_file,
+        manifest.to_json(content.id(), &names).as_bytes(),
+    )?;
+    files.push((MANIFEST_PATH.to_string(), manifest_file));
+
+    let mut archive = Vec::new();
+    for (name, file) in &files {
+        let bytes = std::fs::read(file).map_err(|source| PackageError::Io {
+            action: "read",
+            path: file.clone(),
+            source,
+        })?;
+        archive.push((name.as_str(), bytes));
+    }
+    let zip = build_zip(&archive, manifest.created_at)?;
+    write_atomic(path, zip).map_err(write_error(path))?;
+    Ok(PackageReport { files: names })
+}
+
+fn stage_content(
+    stage: &Path,
+    entries: &[Entry],
+    content: PackageContent<'_>,
+) -> Result<(), PackageError> {
+    create_dir(stage)?;
+    match content {
+        PackageContent::Plugin {
+            input_path,
+            extracted,
+            context,
+        } => {
+            let search = context.strings_search_dirs();
+            let output = stage.join("Strings");
+            let plugin_name = input_path.file_name().ok_or_else(|| PipelineError::Esp {
+                path: input_path.to_path_buf(),
+                error: EspError::InvalidStringsPath,
+            })?;
+            apply_translations(
+                input_path,
+                StringsLocation::Overlay {
+                    search: &search,
+                    output: &output,
+                },
+                &stage.join(plugin_name),
+                translated_strings(entries, extracted),
+                Some(&context.language),
+                context.profile,
+            )
+            .map_err(|error| PipelineError::Esp {
+                path: input_path.to_path_buf(),
+                error,
+            })?;
+        }
+        PackageContent::Dsd {
+            input_path,
+            extracted,
+        } => {
+            let plugin_name = input_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+            let params = DsdExportParams {
+                masters: read_masters(input_path).map_err(|error| PipelineError::Esp {
+                    path: input_path.to_path_buf(),
+                    error,
+                })?,
+                plugin_name,
+            };
+            let (json, _) = export_dsd_json(entries, extracted, &params);
+            let dir_name = dsd_output_dir(&params.plugin_name);
+            let dir = stage.join(dir_name.strip_prefix("Data/").unwrap_or(&dir_name));
+            create_dir(&dir)?;
+            write_file(
+                &dir.join(format!("{}.json", content.stem())),
+                json.as_bytes(),
+            )?;
+        }
+        PackageContent::Strings {
+            base,
+            kind,
+            file_name,
+        } => {
+            let dir = stage.join("Strings");
+            create_dir(&dir)?;
+            save_strings(entries, base, kind, &dir.join(file_name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Files under `dir` as `(archive path, file)`, sorted by archive path.
+fn collect_files(dir: &Path, prefix: &str) -> Result<Vec<(String, PathBuf)>, PackageError> {
+    let read = std::fs::read_dir(dir).map_err(|source| PackageError::Io {
+        action: "list",
+        path: dir.to_path_buf(),
+        source,
+    })?;
+    let mut children = read
+        .flatten()
+        .map(|entry| {
+            (
+                entry.file_name().to_string_lossy().into_owned(),
+                entry.path(),
+            )
+        })
+        .collect::<Vec<_>>();
+    children.sort();
+    let mut files = Vec::new();
+    for (name, path) in children {
+        let name = format!("{prefix}{name}");
+        if path.is_dir() {
+            files.extend(collect_files(&path, &format!("{name}/"))?);
+        } else {
+            files.push((name, path));
+        }
+    }
+    Ok(files)
+}
+
+fn create_dir(dir: &Path) -> Result<(), PackageError> {
+    std::fs::create_dir_all(dir).map_err(|source| PackageError::Io {
+        action: "create",
+        path: dir.to_path_buf(),
+        source,
+    })
+}
+
+/// Plain write: the stage is thrown away after zipping, and only the zip
+/// itself is written atomically.
+fn write_file(path: &Path, bytes: &[u8]) -> Result<(), PackageError> {
+    std::fs::write(path, bytes).map_err(|source| PackageError::Io {
+        action: "write",
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn file_name_part(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_control() || matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*') {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// A deflated zip archive of `files` (UTF-8 names) dated `modified`.
+pub fn build_zip(files: &[(&str, Vec<u8>)], modified: u64) -> Result<Vec<u8>, PackageError> {
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .last_modified_time(zip_date_time(modified))
+        .large_file(
+            files
+                .iter()
+                .any(|(_, bytes)| bytes.len() >= u32::MAX as usize),
+        );
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, bytes) in files {
+        zip.start_file(*name, options)?;
+        zip.write_all(bytes).map_err(ZipError::Io)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Zip cannot date files before 1980; those get its earliest time.
+fn zip_date_time(secs: u64) -> DateTime {
+    let at = UtcDateTime::from_unix(secs);
+    u16::try_from(at.year)
+        .ok()
+        .and_then(|year| {
+            DateTime::from_date_and_time(
+                year,
+                at.month as u8,
+                at.day as u8,
+                at.hour as u8,
+                at.minute as u8,
+                at.second as u8,
+            )
+            .ok()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::strings::StringsEntry;
+    use crate::pipeline::{read_strings_file, strings_entries};
+
+    /// `(name, data)` of each archived file, in archive order; reading to
+    /// the end checks the CRC.
+    fn read_zip(bytes: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).expect("zip");
+        (0..archive.len())
+            .map(|index| {
+                let mut file = archive.by_index(index).expect("entry");
+                assert_eq!(file.compression(), CompressionMethod::Deflated);
+                assert_eq!(
+                    file.last_modified(),
+                    DateTime::from_date_and_time(2024, 3, 5, 6, 7, 8).ok()
+                );
+                let mut data = Vec::new();
+                std::io::Read::read_to_end(&mut file, &mut data).expect("data");
+                (file.name().to_string(), data)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn t_package_001_strings_package_zips_data_layout_and_manifest() {
+        let root = std::env::temp_dir().join(format!("xt_package_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let base = StringsFile::new(vec![StringsEntry {
+            id: 1,
+            text: "Iron Sword".to_string(),
+        }]);
+        let mut entries = strings_entries(&base);
+        entries[0].target_text = "鉄の剣".into();
+        let manifest = PackageManifest {
+            mod_name: "Mod: A".to_string(),
+            mod_version: "1.2".to_string(),
+            language: "japanese".to_string(),
+            author: "hanako".to_string(),
+            // 2024-03-05 06:07:08 UTC
+            created_at: 1_709_618_828,
+        };
+        assert_eq!(manifest.file_name(), "Mod_ A-japanese-1.2.zip");
+        let path = root.join(manifest.file_name());
+        let content = PackageContent::Strings {
+            base: &base,
+            kind: StringsKind::Strings,
+            file_name: "moda_english.strings",
+        };
+
+        let report =
+            create_package(&path, &entries, content, "<xml/>", &manifest).expect("package");
+        let names = [
+            "Strings/moda_english.strings",
+            "xtrans/moda_english_japanese.xml",
+            MANIFEST_PATH,
+        ];
+        assert_eq!(report.files, names);
+        let files = read_zip(&std::fs::read(&path).expect("read zip"));
+        assert_eq!(
+            files
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            names
+        );
+        let strings = root.join("check.strings");
+        std::fs::write(&strings, &files[0].1).expect("write strings");
+        let (translated, _) = read_strings_file(&strings).expect("read strings");
+        assert_eq!(translated.entries[0].text, "鉄の剣");
+        assert_eq!(files[1].1, b"<xml/>");
+        let archived: ArchiveManifest = serde_json::from_slice(&files[2].1).expect("manifest json");
+        assert_eq!(
+            archived,
+            ArchiveManifest {
+                format: MANIFEST_FORMAT,
+                mod_name: "Mod: A".to_string(),
+                mod_version: "1.2".to_string(),
+                language: "japanese".to_string(),
+                author: "hanako".to_string(),
+                created_at: "2024-03-05T06:07:08Z".to_string(),
+                content: "strings".to_string(),
+                files: names.iter().map(|name| name.to_string()).collect(),
+            }
+        );
+
+        let unversioned = PackageManifest {
+            mod_version: " ".to_string(),
+            ..manifest
+        };
+        assert!(matches!(
+            create_package(&root.join("x.zip"), &entries, content, "", &unversioned),
+            Err(PackageError::MissingField("mod version"))
+        ));
+        assert!(!root.join("x.zip").exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
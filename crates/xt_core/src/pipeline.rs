@@ -0,0 +1,1889 @@
+//! Load/apply/save steps shared by the desktop app and `xt_batch`.
+//!
+//! Front ends decide *when* to back up or which path to write; the functions
+//! here only do the I/O and translation mapping, so every tool writes the same
+//! bytes for the same entries.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::checksum::sha256_hex;
+use crate::extract_cache::{CacheKey, ExtractCache};
+use crate::formats::esp::{
+    apply_translations, available_languages, delocalize_plugin, extract_strings_cancelable,
+    extract_strings_lenient, localize_plugin, plugin_entries, read_plugin_info, strings_file_paths,
+    translated_strings, EspError, ExtractedString, LocalizationReport, PartialExtraction,
+    StringStorage, StringsLocation,
+};
+use crate::formats::strings::{
+    read_dlstrings, read_ilstrings, read_strings, write_dlstrings_to, write_ilstrings_to,
+    write_strings_to, StringsEntry, StringsError, StringsFile, WriteOptions,
+};
+use crate::import_export::{export_entries, export_entries_json};
+use crate::mo2::{Mo2Error, Mo2Instance, Mo2Overlay};
+use crate::model::{Entry, UtcDateTime};
+
+pub use crate::formats::esp::{
+    write_atomic, write_atomic_with, CancelToken, Canceled, NoProgress, ProgressSink, StringsKind,
+};
+pub use crate::game::GameProfile;
+
+/// Language whose Strings files are read/written for localized plugins.
+pub const PLUGIN_LANGUAGE: &str = "english";
+
+/// Load/apply/save failures; each names the file involved and keeps the
+/// underlying error as its source.
+#[derive(Debug, thiserror::Error)]
+pub enum PipelineError {
+    #[error("{action} {}: {source}", path.display())]
+    Io {
+        action: &'static str,
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("unsupported strings extension: {0}")]
+    UnsupportedExtension(String),
+    #[error("strings {}: {error}", path.display())]
+    Strings {
+        path: PathBuf,
+        #[source]
+        error: StringsError,
+    },
+    #[error("{}: {}", path.display(), block.label())]
+    Blocked { path: PathBuf, block: WriteBlock },
+    #[error(transparent)]
+    Mo2(#[from] Mo2Error),
+    #[error("plugin {}: {error}", path.display())]
+    Esp {
+        path: PathBuf,
+        #[source]
+        error: EspError,
+    },
+}
+
+fn io_error(action: &'static str, path: &Path) -> impl FnOnce(std::io::Error) -> PipelineError {
+    let path = path.to_path_buf();
+    move |source| PipelineError::Io {
+        action,
+        path,
+        source,
+    }
+}
+
+/// Like [`io_error`], but names a read-only or in-use file as such.
+pub(crate) fn write_error(path: &Path) -> impl FnOnce(std::io::Error) -> PipelineError {
+    let path = path.to_path_buf();
+    move |source| match WriteBlock::from_io_error(&source) {
+        Some(block) => PipelineError::Blocked { path, block },
+        None => PipelineError::Io {
+            action: "write",
+            path,
+            source,
+        },
+    }
+}
+
+/// Why a file cannot be saved over right now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteBlock {
+    /// The file (or its file system) is read-only.
+    ReadOnly,
+    /// Another program, e.g. the game or Mod Organizer 2, has the file open.
+    InUse,
+    /// The user may not write the file or its folder.
+    Denied,
+}
+
+impl WriteBlock {
+    pub fn label(self) -> &'static str {
+        match self {
+            WriteBlock::ReadOnly => "読み取り専用です",
+            WriteBlock::InUse => "他のプログラム（ゲーム・MO2 など）が使用中です",
+            WriteBlock::Denied => "書き込みが拒否されました",
+        }
+    }
+
+    /// The block behind a failed open or write, if it was one.
+    pub fn from_io_error(err: &std::io::Error) -> Option<Self> {
+        // ERROR_SHARING_VIOLATION / ERROR_LOCK_VIOLATION
+        if cfg!(windows) && matches!(err.raw_os_error(), Some(32 | 33)) {
+            return Some(WriteBlock::InUse);
+        }
+        match err.kind() {
+            std::io::ErrorKind::ReadOnlyFilesystem => Some(WriteBlock::ReadOnly),
+            std::io::ErrorKind::PermissionDenied => Some(WriteBlock::Denied),
+            _ => None,
+        }
+    }
+}
+
+/// Checks before saving that an existing `path` is not read-only and can be
+/// opened for writing; on Windows the open is exclusive, so a file another
+/// program holds open is reported as [`WriteBlock::InUse`]. A missing file
+/// passes.
+pub fn check_writable(path: &Path) -> Result<(), WriteBlock> {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return Ok(());
+    };
+    if metadata.permissions().readonly() {
+        return Err(WriteBlock::ReadOnly);
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true);
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        options.share_mode(0);
+    }
+    match options.open(path) {
+        Ok(_) => Ok(()),
+        Err(err) => WriteBlock::from_io_error(&err).map_or(Ok(()), Err),
+    }
+}
+
+pub(crate) fn ensure_writable(path: &Path) -> Result<(), PipelineError> {
+    check_writable(path).map_err(|block| PipelineError::Blocked {
+        path: path.to_path_buf(),
+        block,
+    })
+}
+
+pub fn strings_kind_from_path(path: &Path) -> Result<StringsKind, PipelineError> {
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "strings" => Ok(StringsKind::Strings),
+        "dlstrings" => Ok(StringsKind::DlStrings),
+        "ilstrings" => Ok(StringsKind::IlStrings),
+        _ => Err(PipelineError::UnsupportedExtension(ext)),
+    }
+}
+
+/// Reads a `.strings`/`.dlstrings`/`.ilstrings` file, picking the format
+/// from the extension.
+pub fn read_strings_file(path: &Path) -> Result<(StringsFile, StringsKind), PipelineError> {
+    let kind = strings_kind_from_path(path)?;
+    let bytes = std::fs::read(path).map_err(io_error("read", path))?;
+    let file = match kind {
+        StringsKind::Strings => read_strings(&bytes),
+        StringsKind::DlStrings => read_dlstrings(&bytes),
+        StringsKind::IlStrings => read_ilstrings(&bytes),
+    }
+    .map_err(|error| PipelineError::Strings {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    Ok((file, kind))
+}
+
+/// Entries keyed `strings:{id}` with empty targets.
+pub fn strings_entries(file: &StringsFile) -> Vec<Entry> {
+    file.entries
+        .iter()
+        .map(|entry| Entry {
+            key: format!("strings:{}", entry.id),
+            source_text: entry.text.as_str().into(),
+            target_text: Default::default(),
+        })
+        .collect()
+}
+
+pub fn parse_strings_id(key: &str) -> Option<u32> {
+    let (_, id) = key.rsplit_once(':')?;
+    id.parse::<u32>().ok()
+}
+
+/// Copies `base`, replacing texts whose id has a non-empty target.
+pub fn apply_entries_to_strings(base: &StringsFile, entries: &[Entry]) -> StringsFile {
+    let mut by_id: HashMap<u32, &str> = HashMap::new();
+    for entry in entries {
+        if let Some(id) = parse_strings_id(&entry.key) {
+            if !entry.target_text.is_empty() {
+                by_id.insert(id, entry.target_text.as_str());
+            }
+        }
+    }
+    let out = base
+        .entries
+        .iter()
+        .map(|entry| {
+            if let Some(target) = by_id.get(&entry.id) {
+                StringsEntry {
+                    id: entry.id,
+                    text: (*target).to_string(),
+                }
+            } else {
+                entry.clone()
+            }
+        })
+        .collect::<Vec<_>>();
+    StringsFile {
+        entries: out,
+        raw: base.raw.clone(),
+    }
+}
+
+/// Applies `entries` to `base` and writes it to `path` in `kind` format,
+/// keeping the layout `base` was read with so the file differs from it only
+/// in the edited strings.
+pub fn save_strings(
+    entries: &[Entry],
+    base: &StringsFile,
+    kind: StringsKind,
+    path: &Path,
+) -> Result<SaveReport, PipelineError> {
+    let plan = plan_strings_save(entries, base, path);
+    let updated = apply_entries_to_strings(base, entries);
+    let options = WriteOptions {
+        preserve_layout: true,
+    };
+    ensure_writable(path)?;
+    write_atomic_with(path, |mut out| match kind {
+        StringsKind::Strings => write_strings_to(&updated, &options, &mut out),
+        StringsKind::DlStrings => write_dlstrings_to(&updated, &options, &mut out),
+        StringsKind::IlStrings => write_ilstrings_to(&updated, &options, &mut out),
+    })
+    .map_err(|source| {
+        match source
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<StringsError>())
+        {
+            Some(error) => PipelineError::Strings {
+                path: path.to_path_buf(),
+                error: error.clone(),
+            },
+            None => write_error(path)(source),
+        }
+    })?;
+    SaveReport::from_plan(path, &plan)
+}
+
+/// Game root for a plugin: the parent of `Data` when the plugin lives in a
+/// `Data` folder, otherwise the plugin's own folder.
+pub fn workspace_root_from_plugin(path: &Path) -> PathBuf {
+    let Some(parent) = path.parent() else {
+        return PathBuf::from(".");
+    };
+    let is_data_dir = parent
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.eq_ignore_ascii_case("Data"))
+        .unwrap_or(false);
+    if is_data_dir {
+        if let Some(root) = parent.parent() {
+            return root.to_path_buf();
+        }
+    }
+    parent.to_path_buf()
+}
+
+/// Game profile implied by the plugin's TES4 header, if it can be read.
+pub fn detect_game_profile(path: &Path) -> Option<GameProfile> {
+    read_plugin_info(path)
+        .ok()
+        .map(|info| GameProfile::detect(&info))
+}
+
+/// Where a plugin's localized strings are read from and written to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginContext {
+    pub workspace_root: PathBuf,
+    pub profile: GameProfile,
+    /// Language part of the plugin's Strings file names (`english`, `en`, ...).
+    pub language: String,
+    /// Mod Organizer 2 Strings folders, used instead of `workspace_root`.
+    pub mo2: Option<Mo2Overlay>,
+}
+
+impl Default for PluginContext {
+    fn default() -> Self {
+        Self {
+            workspace_root: PathBuf::from("."),
+            profile: GameProfile::default(),
+            language: PLUGIN_LANGUAGE.to_string(),
+            mo2: None,
+        }
+    }
+}
+
+impl PluginContext {
+    /// Fills in what the caller did not choose: the workspace root from the
+    /// plugin path, the profile from its header, and the language from the
+    /// Strings files present ([`PLUGIN_LANGUAGE`] when available, else the
+    /// first one found).
+    pub fn resolve(
+        path: &Path,
+        workspace_root: Option<&Path>,
+        profile: Option<GameProfile>,
+        language: Option<&str>,
+    ) -> Result<Self, PipelineError> {
+        let workspace_root = workspace_root
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| workspace_root_from_plugin(path));
+        let profile = profile
+            .or_else(|| detect_game_profile(path))
+            .unwrap_or_default();
+        let language = pick_language(path, &workspace_root, profile, language)?;
+        Ok(Self {
+            workspace_root,
+            profile,
+            language,
+            mo2: None,
+        })
+    }
+
+    /// [`PluginContext::resolve`] for a plugin inside a Mod Organizer 2
+    /// `mods` folder: Strings files are looked up through the enabled mods
+    /// of `mo2_profile` (the instance's selected profile when `None`).
+    pub fn resolve_mo2(
+        path: &Path,
+        mo2_profile: Option<&str>,
+        profile: Option<GameProfile>,
+        language: Option<&str>,
+    ) -> Result<Self, PipelineError> {
+        let instance = Mo2Instance::detect(path, mo2_profile)?;
+        let profile = profile
+            .or_else(|| detect_game_profile(path))
+            .unwrap_or_default();
+        let overlay = instance.strings_overlay(path, profile)?;
+        let language = pick_language(path, overlay.location(), profile, language)?;
+        Ok(Self {
+            workspace_root: instance
+                .game_dir
+                .unwrap_or_else(|| workspace_root_from_plugin(path)),
+            profile,
+            language,
+            mo2: Some(overlay),
+        })
+    }
+
+    pub fn strings_location(&self) -> StringsLocation<'_> {
+        match &self.mo2 {
+            Some(overlay) => overlay.location(),
+            None => StringsLocation::Root(&self.workspace_root),
+        }
+    }
+
+    /// Folders the plugin's Strings files are read from, in order.
+    pub fn strings_search_dirs(&self) -> Vec<PathBuf> {
+        match self.strings_location() {
+            StringsLocation::Root(root) => vec![self.profile.strings_dir(root)],
+            StringsLocation::Overlay { search, .. } => search.to_vec(),
+        }
+    }
+
+    /// Folder a save over the plugin writes its Strings files to.
+    pub fn strings_output_dir(&self) -> PathBuf {
+        match self.strings_location() {
+            StringsLocation::Root(root) => self.profile.strings_dir(root),
+            StringsLocation::Overlay { output, .. } => output.to_path_buf(),
+        }
+    }
+}
+
+/// `language` lower-cased, else [`PLUGIN_LANGUAGE`] when its Strings files
+/// exist (or none do), else the first language found.
+fn pick_language<'a>(
+    path: &Path,
+    strings_location: impl Into<StringsLocation<'a>>,
+    profile: GameProfile,
+    language: Option<&str>,
+) -> Result<String, PipelineError> {
+    if let Some(language) = language {
+        return Ok(language.to_lowercase());
+    }
+    let preferred = profile.strings_language(PLUGIN_LANGUAGE);
+    let available = plugin_languages(path, strings_location, profile)?;
+    Ok(if available.is_empty() || available.contains(&preferred) {
+        preferred
+    } else {
+        available[0].clone()
+    })
+}
+
+/// Languages with Strings files for the plugin at `path`, sorted.
+pub fn plugin_languages<'a>(
+    path: &Path,
+    strings_location: impl Into<StringsLocation<'a>>,
+    profile: GameProfile,
+) -> Result<Vec<String>, PipelineError> {
+    available_languages(path, strings_location, profile).map_err(|error| PipelineError::Esp {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+/// Extracts a plugin's strings (inline and localized) as entries keyed by
+/// [`ExtractedString::get_unique_key`].
+pub fn load_plugin_entries(
+    path: &Path,
+    context: &PluginContext,
+) -> Result<(Vec<Entry>, Vec<ExtractedString>), PipelineError> {
+    load_plugin_entries_cancelable(path, context, &CancelToken::new(), &NoProgress)
+}
+
+/// [`load_plugin_entries`] that fails with [`EspError::Canceled`] once
+/// `cancel` is canceled, reporting extraction progress to `progress`.
+pub fn load_plugin_entries_cancelable(
+    path: &Path,
+    context: &PluginContext,
+    cancel: &CancelToken,
+    progress: &dyn ProgressSink,
+) -> Result<(Vec<Entry>, Vec<ExtractedString>), PipelineError> {
+    let extracted = extract_strings_cancelable(
+        path,
+        context.strings_location(),
+        Some(&context.language),
+        context.profile,
+        cancel,
+        progress,
+    )
+    .map_err(|error| PipelineError::Esp {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    Ok((plugin_entries(&extracted), extracted))
+}
+
+/// [`load_plugin_entries_cancelable`] that skips the records it cannot
+/// parse; their errors come back with the strings that did parse.
+pub fn load_plugin_entries_lenient(
+    path: &Path,
+    context: &PluginContext,
+    cancel: &CancelToken,
+    progress: &dyn ProgressSink,
+) -> Result<(Vec<Entry>, PartialExtraction), PipelineError> {
+    let partial = extract_strings_lenient(
+        path,
+        context.strings_location(),
+        Some(&context.language),
+        context.profile,
+        cancel,
+        progress,
+    )
+    .map_err(|error| PipelineError::Esp {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    Ok((plugin_entries(&partial.strings), partial))
+}
+
+/// Where the strings of [`load_plugin_entries_cached`] came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheStatus {
+    Hit,
+    /// Extracted and written to the cache.
+    Stored,
+    /// Extracted, but the cache could not be written.
+    StoreFailed(String),
+}
+
+/// [`load_plugin_entries_cancelable`] that reuses the strings `cache` holds
+/// for the same plugin and Strings file contents, and stores fresh ones.
+pub fn load_plugin_entries_cached(
+    path: &Path,
+    context: &PluginContext,
+    cache: &ExtractCache,
+    cancel: &CancelToken,
+    progress: &dyn ProgressSink,
+) -> Result<(Vec<Entry>, Vec<ExtractedString>, CacheStatus), PipelineError> {
+    // The contents are only hashed; extraction reads the files itself.
+    let key = {
+        let plugin = std::fs::read(path).map_err(io_error("read", path))?;
+        let strings_paths = strings_file_paths(
+            path,
+            context.strings_location(),
+            Some(&context.language),
+            context.profile,
+        )
+        .map_err(|error| PipelineError::Esp {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        let strings = strings_paths
+            .iter()
+            .map(|strings_path| std::fs::read(strings_path).map_err(io_error("read", strings_path)))
+            .collect::<Result<Vec<_>, _>>()?;
+        CacheKey::new(path, context.profile, &context.language, &plugin, &strings)
+    };
+    if let Some(extracted) = cache.load(&key) {
+        return Ok((plugin_entries(&extracted), extracted, CacheStatus::Hit));
+    }
+    let (entries, extracted) = load_plugin_entries_cancelable(path, context, cancel, progress)?;
+    let status = match cache.store(&key, &extracted) {
+        Ok(()) => CacheStatus::Stored,
+        Err(err) => CacheStatus::StoreFailed(format!("{}: {err}", cache.dir().display())),
+    };
+    Ok((entries, extracted, status))
+}
+
+/// Writes a translated copy of `input_path` (and its Strings files, when
+/// localized) and reports what it wrote. With `output_root` the Strings files
+/// go to its `Strings/` folder, so a save-as leaves the game's `Data/Strings`
+/// (or the MO2 output folder) untouched; without it they are written back
+/// where they were read from.
+pub fn save_esp(
+    entries: &[Entry],
+    input_path: &Path,
+    output_path: &Path,
+    output_root: Option<&Path>,
+    extracted: &[ExtractedString],
+    context: &PluginContext,
+) -> Result<SaveReport, PipelineError> {
+    ensure_writable(output_path)?;
+    let plan = plan_esp_save(
+        entries,
+        input_path,
+        output_path,
+        output_root,
+        extracted,
+        context,
+    );
+    let translated = translated_strings(entries, extracted);
+    let search = context.strings_search_dirs();
+    let strings_dir = output_root.map(|root| root.join("Strings"));
+    let location = match &strings_dir {
+        Some(output) => StringsLocation::Overlay {
+            search: &search,
+            output,
+        },
+        None => context.strings_location(),
+    };
+    apply_translations(
+        input_path,
+        location,
+        output_path,
+        translated,
+        Some(&context.language),
+        context.profile,
+    )
+    .map_err(|error| PipelineError::Esp {
+        path: input_path.to_path_buf(),
+        error,
+    })?;
+    SaveReport::from_plan(output_path, &plan)
+}
+
+/// What a save does to one file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlannedChange {
+    Create,
+    Modify,
+    /// A copy of the file about to be overwritten.
+    Backup,
+}
+
+impl PlannedChange {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PlannedChange::Create => "create",
+            PlannedChange::Modify => "modify",
+            PlannedChange::Backup => "backup",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PlannedChange::Create => "作成",
+            PlannedChange::Modify => "変更",
+            PlannedChange::Backup => "バックアップ",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedFile {
+    pub path: PathBuf,
+    pub change: PlannedChange,
+    /// Strings whose text differs from the file being translated.
+    pub changed_strings: usize,
+}
+
+impl PlannedFile {
+    /// Writing `path`: a modification when it exists, else a creation.
+    pub fn write(path: &Path, changed_strings: usize) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            change: if path.exists() {
+                PlannedChange::Modify
+            } else {
+                PlannedChange::Create
+            },
+            changed_strings,
+        }
+    }
+}
+
+/// Files a save would write, worked out without writing anything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SavePlan {
+    pub files: Vec<PlannedFile>,
+}
+
+impl SavePlan {
+    /// Adds, first, the copy `policy` would make of `path` before it is
+    /// overwritten.
+    pub fn with_backup(mut self, path: &Path, policy: BackupPolicy, now: u64) -> Self {
+        if let Some(backup) = backup_target(path, policy, now) {
+            self.files.insert(
+                0,
+                PlannedFile {
+                    path: backup,
+                    change: PlannedChange::Backup,
+                    changed_strings: 0,
+                },
+            );
+        }
+        self
+    }
+
+    /// Strings changed across the written files.
+    pub fn changed_strings(&self) -> usize {
+        self.files.iter().map(|file| file.changed_strings).sum()
+    }
+}
+
+/// Bumped when the save manifest fields change meaning.
+pub const SAVE_MANIFEST_FORMAT: u32 = 1;
+
+/// A file a save wrote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrittenFile {
+    pub path: PathBuf,
+    pub size: u64,
+    /// Lower-case hex SHA-256 of the written bytes.
+    pub sha256: String,
+    /// Strings whose text differs from the file that was translated.
+    pub changed_strings: usize,
+}
+
+/// Contents of [`SaveReport::manifest_path`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SaveManifest {
+    /// [`SAVE_MANIFEST_FORMAT`].
+    pub format: u32,
+    pub changed_strings: usize,
+    pub files: Vec<ManifestFile>,
+}
+
+/// One [`WrittenFile`] in a [`SaveManifest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// Relative to the output's folder, with `/`.
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+    pub changed_strings: usize,
+}
+
+/// What a save wrote: the requested output and every file written for it,
+/// with checksums for packaging scripts and mod managers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveReport {
+    pub output: PathBuf,
+    pub files: Vec<WrittenFile>,
+}
+
+impl SaveReport {
+    /// Reads back the files `plan` (made before writing) lists, except
+    /// backups.
+    pub fn from_plan(output: &Path, plan: &SavePlan) -> Result<Self, PipelineError> {
+        let mut files = Vec::with_capacity(plan.files.len());
+        for planned in &plan.files {
+            if planned.change == PlannedChange::Backup {
+                continue;
+            }
+            let bytes = std::fs::read(&planned.path).map_err(io_error("read", &planned.path))?;
+            files.push(WrittenFile {
+                path: planned.path.clone(),
+                size: bytes.len() as u64,
+                sha256: sha256_hex(&bytes),
+                changed_strings: planned.changed_strings,
+            });
+        }
+        Ok(Self {
+            output: output.to_path_buf(),
+            files,
+        })
+    }
+
+    pub fn changed_strings(&self) -> usize {
+        self.files.iter().map(|file| file.changed_strings).sum()
+    }
+
+    /// `<output file name>.manifest.json` next to the output.
+    pub fn manifest_path(&self) -> PathBuf {
+        let mut name = self.output.file_name().unwrap_or_default().to_os_string();
+        name.push(".manifest.json");
+        self.output.with_file_name(name)
+    }
+
+    /// Each file by its path relative to the output's folder (with `/`),
+    /// size, SHA-256 and changed strings.
+    pub fn manifest(&self) -> SaveManifest {
+        let dir = self.output.parent().unwrap_or_else(|| Path::new(""));
+        let files = self
+            .files
+            .iter()
+            .map(|file| {
+                let path = file.path.strip_prefix(dir).unwrap_or(&file.path);
+                ManifestFile {
+                    path: path
+                        .components()
+                        .map(|part| part.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/"),
+                    size: file.size,
+                    sha256: file.sha256.clone(),
+                    changed_strings: file.changed_strings,
+                }
+            })
+            .collect();
+        SaveManifest {
+            format: SAVE_MANIFEST_FORMAT,
+            changed_strings: self.changed_strings(),
+            files,
+        }
+    }
+
+    /// [`Self::manifest`] as pretty-printed JSON.
+    pub fn manifest_json(&self) -> String {
+        // Strings and integers only, which always serialize.
+        let mut json = serde_json::to_string_pretty(&self.manifest()).expect("serialize manifest");
+        json.push('\n');
+        json
+    }
+
+    /// Writes [`Self::manifest_json`] to [`Self::manifest_path`].
+    pub fn write_manifest(&self) -> Result<PathBuf, PipelineError> {
+        let path = self.manifest_path();
+        write_atomic(&path, self.manifest_json().as_bytes()).map_err(write_error(&path))?;
+        Ok(path)
+    }
+}
+
+/// Plan of [`save_strings`] writing `entries` over `base` to `path`.
+pub fn plan_strings_save(entries: &[Entry], base: &StringsFile, path: &Path) -> SavePlan {
+    let updated = apply_entries_to_strings(base, entries);
+    let changed = base
+        .entries
+        .iter()
+        .zip(&updated.entries)
+        .filter(|(before, after)| before.text != after.text)
+        .count();
+    SavePlan {
+        files: vec![PlannedFile::write(path, changed)],
+    }
+}
+
+/// Plan of [`save_esp`] with the same arguments: the plugin, with its
+/// changed inline texts, then each Strings file it reads, with its changed
+/// strings.
+pub fn plan_esp_save(
+    entries: &[Entry],
+    input_path: &Path,
+    output_path: &Path,
+    output_root: Option<&Path>,
+    extracted: &[ExtractedString],
+    context: &PluginContext,
+) -> SavePlan {
+    let mut inline = 0;
+    let mut localized: HashMap<StringsKind, usize> = HashMap::new();
+    for (before, after) in extracted.iter().zip(translated_strings(entries, extracted)) {
+        if before.text == after.text {
+            continue;
+        }
+        match before.storage {
+            StringStorage::Inline => inline += 1,
+            StringStorage::Localized { kind, .. } => *localized.entry(kind).or_default() += 1,
+        }
+    }
+    let mut files = vec![PlannedFile::write(output_path, inline)];
+    let base_name = input_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    let language = context.profile.strings_language(&context.language);
+    let search = context.strings_search_dirs();
+    let output_dir = match output_root {
+        Some(root) => root.join("Strings"),
+        None => context.strings_output_dir(),
+    };
+    for kind in [
+        StringsKind::Strings,
+        StringsKind::DlStrings,
+        StringsKind::IlStrings,
+    ] {
+        let ext = kind.to_string();
+        let read = search.iter().any(|dir| {
+            [ext.clone(), ext.to_ascii_uppercase()]
+                .iter()
+                .any(|ext| dir.join(format!("{base_name}_{language}.{ext}")).exists())
+        });
+        if read {
+            let path = output_dir.join(format!("{base_name}_{language}.{ext}"));
+            let changed = localized.get(&kind).copied().unwrap_or_default();
+            files.push(PlannedFile::write(&path, changed));
+        }
+    }
+    SavePlan { files }
+}
+
+/// Direction of [`convert_esp_localization`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalizationChange {
+    /// Inline text -> string ids plus a new Strings trio.
+    Localize,
+    /// String ids -> inline text.
+    Delocalize,
+}
+
+/// Writes a translated copy of `input_path` converted to or from the
+/// localized form. Localizing writes a Strings trio for the context language
+/// to `Strings/` next to `output_path`.
+pub fn convert_esp_localization(
+    entries: &[Entry],
+    input_path: &Path,
+    output_path: &Path,
+    extracted: &[ExtractedString],
+    context: &PluginContext,
+    change: LocalizationChange,
+) -> Result<LocalizationReport, PipelineError> {
+    match change {
+        LocalizationChange::Localize => export_esp_localized(
+            entries,
+            input_path,
+            output_path,
+            extracted,
+            context,
+            &context.language,
+        ),
+        LocalizationChange::Delocalize => delocalize_plugin(
+            input_path,
+            context.strings_location(),
+            output_path,
+            translated_strings(entries, extracted),
+            Some(&context.language),
+            context.profile,
+        )
+        .map_err(|error| PipelineError::Esp {
+            path: input_path.to_path_buf(),
+            error,
+        }),
+    }
+}
+
+/// Writes a translated, localized copy of `input_path` plus a new
+/// `<output stem>_<language>.strings/.dlstrings/.ilstrings` trio in
+/// `Strings/` next to `output_path` (`language` is mapped to the profile's
+/// file-name form, e.g. `japanese` -> `ja` for Fallout 4).
+pub fn export_esp_localized(
+    entries: &[Entry],
+    input_path: &Path,
+    output_path: &Path,
+    extracted: &[ExtractedString],
+    context: &PluginContext,
+    language: &str,
+) -> Result<LocalizationReport, PipelineError> {
+    let strings_dir = output_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("Strings");
+    localize_plugin(
+        input_path,
+        context.strings_location(),
+        output_path,
+        &strings_dir,
+        translated_strings(entries, extracted),
+        Some(&context.language),
+        Some(language),
+        context.profile,
+    )
+    .map_err(|error| PipelineError::Esp {
+        path: input_path.to_path_buf(),
+        error,
+    })
+}
+
+/// Writes entries as JSON for a `.json` path, xtrans XML otherwise.
+pub fn write_entries_file(path: &Path, entries: &[Entry]) -> Result<(), PipelineError> {
+    let is_json = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let content = if is_json {
+        export_entries_json(entries)
+    } else {
+        export_entries(entries)
+    };
+    write_atomic(path, content).map_err(write_error(path))
+}
+
+/// Folder next to the saved file that holds [`BackupPolicy::Timestamped`]
+/// copies.
+pub const BACKUP_DIR: &str = "xtrans_backups";
+/// Copies kept by [`BackupPolicy::Rotating`] unless configured otherwise.
+pub const DEFAULT_BACKUP_ROTATION: u8 = 5;
+
+/// What saving over a file does with the previous contents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackupPolicy {
+    Off,
+    /// One `name.bak.ext`, replaced on every save.
+    Single,
+    /// The newest copies as `name.bak.ext` (newest), `name.bak1.ext`, ...
+    /// up to the given count; the oldest is dropped.
+    Rotating(u8),
+    /// `xtrans_backups/name.YYYYMMDD-HHMMSS.ext`, kept until deleted.
+    Timestamped,
+}
+
+impl Default for BackupPolicy {
+    fn default() -> Self {
+        BackupPolicy::Rotating(DEFAULT_BACKUP_ROTATION)
+    }
+}
+
+impl BackupPolicy {
+    /// One of each kind, for pickers.
+    pub const KINDS: [BackupPolicy; 4] = [
+        BackupPolicy::Off,
+        BackupPolicy::Single,
+        BackupPolicy::Rotating(DEFAULT_BACKUP_ROTATION),
+        BackupPolicy::Timestamped,
+    ];
+
+    /// Key used in prefs files, e.g. `rotating:5`.
+    pub fn id(self) -> String {
+        match self {
+            BackupPolicy::Off => "off".to_string(),
+            BackupPolicy::Single => "single".to_string(),
+            BackupPolicy::Rotating(count) => format!("rotating:{count}"),
+            BackupPolicy::Timestamped => "timestamped".to_string(),
+        }
+    }
+
+    /// Also accepts `numbered`, the unbounded `.bakN` scheme of earlier
+    /// versions, as the default rotation.
+    pub fn parse(id: &str) -> Option<Self> {
+        match id {
+            "off" => Some(BackupPolicy::Off),
+            "single" => Some(BackupPolicy::Single),
+            "timestamped" => Some(BackupPolicy::Timestamped),
+            "numbered" => Some(BackupPolicy::default()),
+            _ => {
+                let count = id.strip_prefix("rotating:")?.parse::<u8>().ok()?;
+                (count > 0).then_some(BackupPolicy::Rotating(count))
+            }
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BackupPolicy::Off => "作成しない",
+            BackupPolicy::Single => ".bak を1つ",
+            BackupPolicy::Rotating(_) => "世代管理",
+            BackupPolicy::Timestamped => "日時付きでフォルダに保存",
+        }
+    }
+
+    pub fn same_kind(self, other: BackupPolicy) -> bool {
+        std::mem::discriminant(&self) == std::mem::discriminant(&other)
+    }
+}
+
+/// Backs up `path` before it is overwritten at unix time `now`, as `policy`
+/// says; returns the backup written, if any.
+pub fn backup_with_policy(
+    path: &Path,
+    policy: BackupPolicy,
+    now: u64,
+) -> Result<Option<PathBuf>, PipelineError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let backup = match policy {
+        BackupPolicy::Off => return Ok(None),
+        BackupPolicy::Single => backup_slot(path, 0),
+        BackupPolicy::Rotating(count) => {
+            let count = usize::from(count.max(1));
+            let oldest = backup_slot(path, count - 1);
+            if oldest.exists() {
+                std::fs::remove_file(&oldest).map_err(io_error("remove backup", &oldest))?;
+            }
+            for slot in (0..count - 1).rev() {
+                let from = backup_slot(path, slot);
+                if from.exists() {
+                    std::fs::rename(&from, backup_slot(path, slot + 1))
+                        .map_err(io_error("rotate backup", &from))?;
+                }
+            }
+            backup_slot(path, 0)
+        }
+        BackupPolicy::Timestamped => {
+            let dir = path
+                .parent()
+                .unwrap_or_else(|| Path::new("."))
+                .join(BACKUP_DIR);
+            std::fs::create_dir_all(&dir).map_err(io_error("create", &dir))?;
+            timestamped_backup_path(&dir, path, now)
+        }
+    };
+    std::fs::copy(path, &backup).map_err(io_error("backup", path))?;
+    Ok(Some(backup))
+}
+
+/// The copy [`backup_with_policy`] would make of `path` now; `None` when it
+/// makes none.
+pub fn backup_target(path: &Path, policy: BackupPolicy, now: u64) -> Option<PathBuf> {
+    if !path.exists() {
+        return None;
+    }
+    match policy {
+        BackupPolicy::Off => None,
+        BackupPolicy::Single | BackupPolicy::Rotating(_) => Some(backup_slot(path, 0)),
+        BackupPolicy::Timestamped => {
+            let dir = path
+                .parent()
+                .unwrap_or_else(|| Path::new("."))
+                .join(BACKUP_DIR);
+            Some(timestamped_backup_path(&dir, path, now))
+        }
+    }
+}
+
+/// `name.bak.ext` for slot 0, `name.bak{slot}.ext` after it.
+fn backup_slot(path: &Path, slot: usize) -> PathBuf {
+    let (stem, ext) = stem_and_ext(path);
+    let bak = if slot == 0 {
+        "bak".to_string()
+    } else {
+        format!("bak{slot}")
+    };
+    let name = if ext.is_empty() {
+        format!("{stem}.{bak}")
+    } else {
+        format!("{stem}.{bak}.{ext}")
+    };
+    path.parent().unwrap_or_else(|| Path::new(".")).join(name)
+}
+
+fn stem_and_ext(path: &Path) -> (&str, &str) {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+    (stem, ext)
+}
+
+/// `dir/name.YYYYMMDD-HHMMSS.ext`, with `-2`, `-3`, ... on a clash.
+fn timestamped_backup_path(dir: &Path, path: &Path, now: u64) -> PathBuf {
+    let (stem, ext) = stem_and_ext(path);
+    let time = UtcDateTime::from_unix(now);
+    let stamp = format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        time.year, time.month, time.day, time.hour, time.minute, time.second
+    );
+    (1..)
+        .map(|n| {
+            let stamp = if n == 1 {
+                stamp.clone()
+            } else {
+                format!("{stamp}-{n}")
+            };
+            if ext.is_empty() {
+                dir.join(format!("{stem}.{stamp}"))
+            } else {
+                dir.join(format!("{stem}.{stamp}.{ext}"))
+            }
+        })
+        .find(|candidate| !candidate.exists())
+        .expect("unbounded candidates")
+}
+
+/// A backup copy of a saved file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BackupFile {
+    pub path: PathBuf,
+    /// Unix seconds of the copy's last modification.
+    pub modified_at: u64,
+    pub size: u64,
+}
+
+/// Backups of `path` made by any [`BackupPolicy`], newest first.
+pub fn list_backups(path: &Path) -> Result<Vec<BackupFile>, PipelineError> {
+    let (stem, ext) = stem_and_ext(path);
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+    let strip = |name: &str| -> Option<String> {
+        let rest = name.strip_prefix(stem)?.strip_prefix('.')?;
+        let middle = if ext.is_empty() {
+            rest
+        } else {
+            rest.strip_suffix(ext)?.strip_suffix('.')?
+        };
+        Some(middle.to_string())
+    };
+    let mut found = Vec::new();
+    for (dir, is_slot) in [
+        (parent.to_path_buf(), true),
+        (parent.join(BACKUP_DIR), false),
+    ] {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(io_error("read dir", &dir)(err)),
+        };
+        for entry in entries {
+            let entry = entry.map_err(io_error("read dir", &dir))?;
+            let name = entry.file_name();
+            let Some(middle) = name.to_str().and_then(strip) else {
+                continue;
+            };
+            let matches = if is_slot {
+                middle
+                    .strip_prefix("bak")
+                    .is_some_and(|n| n.chars().all(|c| c.is_ascii_digit()))
+            } else {
+                !middle.is_empty() && middle.chars().all(|c| c.is_ascii_digit() || c == '-')
+            };
+            let metadata = entry.metadata().map_err(io_error("stat", &entry.path()))?;
+            if !matches || !metadata.is_file() {
+                continue;
+            }
+            let modified_at = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or(0, |age| age.as_secs());
+            found.push(BackupFile {
+                path: entry.path(),
+                modified_at,
+                size: metadata.len(),
+            });
+        }
+    }
+    found.sort_by(|a, b| {
+        b.modified_at
+            .cmp(&a.modified_at)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    Ok(found)
+}
+
+/// Overwrites `path` with `backup`, first backing up the current contents
+/// as `policy` says; returns that new backup.
+pub fn restore_backup(
+    path: &Path,
+    backup: &Path,
+    policy: BackupPolicy,
+    now: u64,
+) -> Result<Option<PathBuf>, PipelineError> {
+    let bytes = std::fs::read(backup).map_err(io_error("read", backup))?;
+    ensure_writable(path)?;
+    let previous = backup_with_policy(path, policy, now)?;
+    write_atomic(path, &bytes).map_err(write_error(path))?;
+    Ok(previous)
+}
+
+/// Copies an existing file to the next free backup name; returns the backup.
+pub fn ensure_backup(path: &Path) -> Result<Option<PathBuf>, PipelineError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let backup = next_backup_path(path);
+    std::fs::copy(path, &backup).map_err(io_error("backup", path))?;
+    Ok(Some(backup))
+}
+
+/// `name.bak.ext`, then `name.bak1.ext`, `name.bak2.ext`, ...
+pub fn next_backup_path(path: &Path) -> PathBuf {
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+
+    for i in 0usize..1000usize {
+        let name = if i == 0 {
+            if ext.is_empty() {
+                format!("{stem}.bak")
+            } else {
+                format!("{stem}.bak.{ext}")
+            }
+        } else if ext.is_empty() {
+            format!("{stem}.bak{i}")
+        } else {
+            format!("{stem}.bak{i}.{ext}")
+        };
+        let p = parent.join(name);
+        if !p.exists() {
+            return p;
+        }
+    }
+
+    with_suffix_path(path, ".bak999")
+}
+
+/// `dir/name{suffix}.ext`
+pub fn with_suffix_path(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("output");
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+    let file = if ext.is_empty() {
+        format!("{stem}{suffix}")
+    } else {
+        format!("{stem}{suffix}.{ext}")
+    };
+    path.parent().unwrap_or_else(|| Path::new(".")).join(file)
+}
+
+/// File name pattern for save-as and batch outputs, relative to the input's
+/// folder unless absolute. `/` starts a subfolder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputTemplate(String);
+
+impl Default for OutputTemplate {
+    fn default() -> Self {
+        OutputTemplate(Self::DEFAULT.to_string())
+    }
+}
+
+impl std::fmt::Display for OutputTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl OutputTemplate {
+    /// `mod.esp` → `mod_translated.esp`, as [`with_suffix_path`] names it.
+    pub const DEFAULT: &'static str = "{stem}_translated.{ext}";
+
+    /// Placeholders: the input's file stem and extension, the plugin name
+    /// (the stem without a Strings file's `_<language>`), the output
+    /// language and the date (`YYYY-MM-DD`, UTC).
+    pub const PLACEHOLDERS: [&'static str; 5] = ["stem", "ext", "plugin", "lang", "date"];
+
+    /// `None` for an unknown or unclosed placeholder, or a template without a
+    /// file name.
+    pub fn parse(template: &str) -> Option<Self> {
+        let mut rest = template;
+        loop {
+            match (rest.find('{'), rest.find('}')) {
+                (None, None) => break,
+                (Some(open), Some(close)) if open < close => {
+                    if !Self::PLACEHOLDERS.contains(&&rest[open + 1..close]) {
+                        return None;
+                    }
+                    rest = &rest[close + 1..];
+                }
+                _ => return None,
+            }
+        }
+        let name = template.rsplit(['/', '\\']).next().unwrap_or_default();
+        (!name.trim().is_empty()).then(|| OutputTemplate(template.to_string()))
+    }
+
+    /// Output path for `input`, relative templates resolved against `dir`.
+    pub fn expand(&self, dir: &Path, input: &Path, language: &str, now: u64) -> PathBuf {
+        let stem = input
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("output");
+        let ext = input.extension().and_then(|s| s.to_str()).unwrap_or("");
+        let plugin = match ext.to_ascii_lowercase().as_str() {
+            "strings" | "dlstrings" | "ilstrings" => stem.rsplit_once('_').map_or(stem, |s| s.0),
+            _ => stem,
+        };
+        let date = UtcDateTime::from_unix(now);
+        let date = format!("{:04}-{:02}-{:02}", date.year, date.month, date.day);
+        let mut out = self
+            .0
+            .replace("{stem}", stem)
+            .replace("{ext}", ext)
+            .replace("{plugin}", plugin)
+            .replace("{lang}", language)
+            .replace("{date}", &date);
+        // `{stem}_translated.{ext}` of a file without an extension.
+        if ext.is_empty() && out.ends_with('.') {
+            out.pop();
+        }
+        dir.join(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_pipeline_001_strings_save_load_and_paths() {
+        let root = std::env::temp_dir().join(format!("xt_pipeline_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let base = StringsFile::new(vec![
+            StringsEntry {
+                id: 1,
+                text: "Iron Sword".to_string(),
+            },
+            StringsEntry {
+                id: 2,
+                text: "Bread".to_string(),
+            },
+        ]);
+        let mut entries = strings_entries(&base);
+        assert_eq!(entries[0].key, "strings:1");
+        entries[1].target_text = "パン".into();
+
+        let path = root.join("mod_japanese.DLSTRINGS");
+        save_strings(&entries, &base, StringsKind::DlStrings, &path).expect("save");
+        let (loaded, kind) = read_strings_file(&path).expect("read");
+        assert_eq!(kind, StringsKind::DlStrings);
+        assert_eq!(loaded.entries[0].text, "Iron Sword");
+        assert_eq!(loaded.entries[1].text, "パン");
+
+        assert_eq!(
+            ensure_backup(&path).expect("backup"),
+            Some(root.join("mod_japanese.bak.DLSTRINGS"))
+        );
+        assert_eq!(
+            ensure_backup(&root.join("missing.strings")).expect("none"),
+            None
+        );
+        assert!(matches!(
+            read_strings_file(&root.join("a.txt")),
+            Err(PipelineError::UnsupportedExtension(ext)) if ext == "txt"
+        ));
+
+        assert_eq!(
+            workspace_root_from_plugin(Path::new("/game/Data/mod.esp")),
+            PathBuf::from("/game")
+        );
+        assert_eq!(
+            workspace_root_from_plugin(Path::new("/mods/mod.esp")),
+            PathBuf::from("/mods")
+        );
+        assert_eq!(
+            with_suffix_path(Path::new("/x/mod.esp"), "_translated"),
+            PathBuf::from("/x/mod_translated.esp")
+        );
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_pipeline_002_plugin_context_picks_strings_language() {
+        let root = std::env::temp_dir().join(format!("xt_pipeline_lang_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let strings_dir = root.join("Data").join("Strings");
+        std::fs::create_dir_all(&strings_dir).expect("create");
+        let plugin = root.join("Data").join("mod.esp");
+        let resolve = |profile, language| {
+            PluginContext::resolve(&plugin, None, Some(profile), language).expect("resolve")
+        };
+
+        let context = resolve(GameProfile::SkyrimSe, None);
+        assert_eq!(context.workspace_root, root);
+        assert_eq!(context.language, PLUGIN_LANGUAGE);
+
+        std::fs::write(strings_dir.join("mod_german.strings"), b"").expect("write");
+        assert_eq!(resolve(GameProfile::SkyrimSe, None).language, "german");
+        std::fs::write(strings_dir.join("mod_English.DLSTRINGS"), b"").expect("write");
+        assert_eq!(resolve(GameProfile::SkyrimSe, None).language, "english");
+        assert_eq!(
+            plugin_languages(&plugin, &root, GameProfile::SkyrimSe).expect("languages"),
+            vec!["english", "german"]
+        );
+        assert_eq!(
+            resolve(GameProfile::SkyrimSe, Some("German")).language,
+            "german"
+        );
+
+        std::fs::write(strings_dir.join("mod_en.strings"), b"").expect("write");
+        assert_eq!(resolve(GameProfile::Fallout4, None).language, "en");
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_pipeline_003_backup_policies_list_and_restore() {
+        let root = std::env::temp_dir().join(format!("xt_pipeline_bak_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let path = root.join("mod.esp");
+        let save = |text: &str, policy: BackupPolicy, now: u64| {
+            let backup = backup_with_policy(&path, policy, now).expect("backup");
+            std::fs::write(&path, text).expect("write");
+            backup
+        };
+        let read = |path: &Path| std::fs::read_to_string(path).expect("read");
+
+        assert_eq!(save("v1", BackupPolicy::Off, 0), None, "nothing to back up");
+        assert_eq!(save("v2", BackupPolicy::Off, 0), None);
+        save("v3", BackupPolicy::Single, 0);
+        save("v4", BackupPolicy::Single, 0);
+        assert_eq!(read(&root.join("mod.bak.esp")), "v3");
+        assert!(!root.join("mod.bak1.esp").exists());
+
+        for text in ["v5", "v6", "v7"] {
+            save(text, BackupPolicy::Rotating(2), 0);
+        }
+        assert_eq!(read(&root.join("mod.bak.esp")), "v6");
+        assert_eq!(read(&root.join("mod.bak1.esp")), "v5");
+        assert!(!root.join("mod.bak2.esp").exists(), "oldest dropped");
+
+        // 2024-02-29 23:59:58 UTC, twice in the same second.
+        let first = save("v8", BackupPolicy::Timestamped, 1_709_251_198).expect("stamped");
+        let second = save("v9", BackupPolicy::Timestamped, 1_709_251_198).expect("stamped");
+        assert_eq!(first, root.join(BACKUP_DIR).join("mod.20240229-235958.esp"));
+        assert_eq!(
+            second,
+            root.join(BACKUP_DIR).join("mod.20240229-235958-2.esp")
+        );
+        std::fs::write(root.join("mod.bakup.esp"), "x").expect("write");
+        std::fs::write(root.join("other.bak.esp"), "x").expect("write");
+
+        let mut listed = list_backups(&path)
+            .expect("list")
+            .into_iter()
+            .map(|backup| backup.path)
+            .collect::<Vec<_>>();
+        listed.sort();
+        assert_eq!(
+            listed,
+            [
+                root.join("mod.bak.esp"),
+                root.join("mod.bak1.esp"),
+                second.clone(),
+                first,
+            ]
+        );
+
+        let previous = restore_backup(&path, &second, BackupPolicy::Single, 0).expect("restore");
+        assert_eq!(read(&path), "v8");
+        assert_eq!(previous, Some(root.join("mod.bak.esp")));
+        assert_eq!(read(&root.join("mod.bak.esp")), "v9");
+
+        for id in ["off", "single", "rotating:3", "timestamped"] {
+            assert_eq!(BackupPolicy::parse(id).expect(id).id(), id);
+        }
+        assert_eq!(
+            BackupPolicy::parse("numbered"),
+            Some(BackupPolicy::default())
+        );
+        assert_eq!(BackupPolicy::parse("rotating:0"), None);
+        assert_eq!(
+            list_backups(&root.join("missing").join("x.esp")).expect("no dir"),
+            []
+        );
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_pipeline_004_read_only_target_is_reported_before_writing() {
+        let root = std::env::temp_dir().join(format!("xt_pipeline_ro_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).expect("create");
+        let path = root.join("mod_japanese.strings");
+        let base = StringsFile::new(vec![StringsEntry {
+            id: 1,
+            text: "Iron Sword".to_string(),
+        }]);
+        let entries = vec![Entry {
+            key: "strings:1".to_string(),
+            source_text: "Iron Sword".into(),
+            target_text: "鉄の剣".into(),
+        }];
+        assert_eq!(check_writable(&path), Ok(()), "a new file is writable");
+        save_strings(&[], &base, StringsKind::Strings, &path).expect("first save");
+        let before = std::fs::read(&path).expect("read");
+
+        let mut permissions = std::fs::metadata(&path).expect("meta").permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&path, permissions.clone()).expect("read-only");
+        assert_eq!(check_writable(&path), Err(WriteBlock::ReadOnly));
+        let err = save_strings(&entries, &base, StringsKind::Strings, &path).unwrap_err();
+        assert!(matches!(
+            err,
+            PipelineError::Blocked {
+                block: WriteBlock::ReadOnly,
+                ..
+            }
+        ));
+        assert!(err.to_string().contains("読み取り専用"));
+        assert_eq!(
+            std::fs::read(&path).expect("read"),
+            before,
+            "left untouched"
+        );
+
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        std::fs::set_permissions(&path, permissions).expect("writable");
+        save_strings(&entries, &base, StringsKind::Strings, &path).expect("save");
+        assert_eq!(
+            WriteBlock::from_io_error(&std::io::Error::from(std::io::ErrorKind::PermissionDenied)),
+            Some(WriteBlock::Denied)
+        );
+        assert_eq!(
+            WriteBlock::from_io_error(&std::io::Error::from(std::io::ErrorKind::NotFound)),
+            None
+        );
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_pipeline_005_output_template_expands_placeholders() {
+        // 2026-10-16 12:00:00 UTC
+        let now = 1_792_152_000;
+        let dir = Path::new("/data");
+        let default = OutputTemplate::default();
+        assert_eq!(
+            default.expand(dir, Path::new("/data/mod.esp"), "japanese", now),
+            with_suffix_path(Path::new("/data/mod.esp"), "_translated")
+        );
+        assert_eq!(
+            default.expand(dir, Path::new("/data/README"), "japanese", now),
+            PathBuf::from("/data/README_translated")
+        );
+
+        let template =
+            OutputTemplate::parse("translated/{lang}/{plugin}_{lang}.{ext}").expect("valid");
+        assert_eq!(
+            template.expand(
+                dir,
+                Path::new("/data/Strings/Mod_english.dlstrings"),
+                "japanese",
+                now
+            ),
+            PathBuf::from("/data/translated/japanese/Mod_japanese.dlstrings")
+        );
+        let dated = OutputTemplate::parse("/out/{stem}-{date}.{ext}").expect("valid");
+        assert_eq!(
+            dated.expand(dir, Path::new("mod.esp"), "japanese", now),
+            PathBuf::from("/out/mod-2026-10-16.esp")
+        );
+        assert_eq!(dated.to_string(), "/out/{stem}-{date}.{ext}");
+
+        for invalid in ["{name}.esp", "{stem", "stem}.esp", "{st{em}}", "out/", ""] {
+            assert_eq!(OutputTemplate::parse(invalid), None, "{invalid}");
+        }
+    }
+
+    /// `root/Data/Mod.esp` with one FULL text, id 100 ("Hello") in
+    /// `root/Data/Strings/Mod_english.strings`.
+    fn localized_plugin(root: &Path) -> (PathBuf, PathBuf, PluginContext) {
+        let _ = std::fs::remove_dir_all(root);
+        let data = root.join("Data");
+        let strings = data.join("Strings").join("Mod_english.strings");
+        std::fs::create_dir_all(strings.parent().expect("dir")).expect("create");
+        let plugin = data.join("Mod.esp");
+        let mut record = Vec::new();
+        record.extend_from_slice(b"NPC_");
+        record.extend_from_slice(&10u32.to_le_bytes());
+        record.extend_from_slice(&0u32.to_le_bytes());
+        record.extend_from_slice(&0x0001_0001u32.to_le_bytes());
+        record.extend_from_slice(&[0; 8]);
+        record.extend_from_slice(b"FULL");
+        record.extend_from_slice(&4u16.to_le_bytes());
+        record.extend_from_slice(&100u32.to_le_bytes());
+        std::fs::write(&plugin, record).expect("write plugin");
+        let encoded =
+            crate::formats::strings::write_strings(&StringsFile::new(vec![StringsEntry {
+                id: 100,
+                text: "Hello".to_string(),
+            }]))
+            .expect("encode");
+        std::fs::write(&strings, encoded).expect("write strings");
+        let context = PluginContext {
+            workspace_root: root.to_path_buf(),
+            profile: GameProfile::SkyrimSe,
+            ..PluginContext::default()
+        };
+        (plugin, strings, context)
+    }
+
+    #[test]
+    fn t_pipeline_006_save_as_writes_strings_under_output_root() {
+        let root = std::env::temp_dir().join(format!("xt_pipeline_out_{}", std::process::id()));
+        let (plugin, game_strings, context) = localized_plugin(&root);
+        let original = std::fs::read(&game_strings).expect("read");
+        let (mut entries, extracted) = load_plugin_entries(&plugin, &context).expect("load");
+        entries[0].target_text = "こんにちは".into();
+        let out_root = root.join("out");
+        let output = out_root.join("Mod.esp");
+        save_esp(
+            &entries,
+            &plugin,
+            &output,
+            Some(&out_root),
+            &extracted,
+            &context,
+        )
+        .expect("save as");
+        assert!(output.exists());
+        assert_eq!(
+            std::fs::read(&game_strings).expect("read"),
+            original,
+            "game Strings untouched"
+        );
+        let (written, _) = read_strings_file(&out_root.join("Strings").join("Mod_english.strings"))
+            .expect("output strings");
+        assert_eq!(written.entries[0].text, "こんにちは");
+
+        save_esp(&entries, &plugin, &plugin, None, &extracted, &context).expect("overwrite");
+        let (updated, _) = read_strings_file(&game_strings).expect("read");
+        assert_eq!(updated.entries[0].text, "こんにちは");
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_pipeline_007_save_plan_lists_files_without_writing() {
+        let root = std::env::temp_dir().join(format!("xt_pipeline_plan_{}", std::process::id()));
+        let (plugin, game_strings, context) = localized_plugin(&root);
+        let (mut entries, extracted) = load_plugin_entries(&plugin, &context).expect("load");
+        entries[0].target_text = "こんにちは".into();
+
+        let out_root = root.join("out");
+        let plan = plan_esp_save(
+            &entries,
+            &plugin,
+            &out_root.join("Mod.esp"),
+            Some(&out_root),
+            &extracted,
+            &context,
+        );
+        let files = plan
+            .files
+            .iter()
+            .map(|file| (file.path.clone(), file.change, file.changed_strings))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            files,
+            [
+                (out_root.join("Mod.esp"), PlannedChange::Create, 0),
+                (
+                    out_root.join("Strings").join("Mod_english.strings"),
+                    PlannedChange::Create,
+                    1
+                ),
+            ]
+        );
+        assert!(!out_root.exists(), "planning writes nothing");
+
+        let plan = plan_esp_save(&entries, &plugin, &plugin, None, &extracted, &context)
+            .with_backup(&plugin, BackupPolicy::Single, 0);
+        assert_eq!(plan.files[0].change, PlannedChange::Backup);
+        assert_eq!(plan.files[0].path, backup_slot(&plugin, 0));
+        assert_eq!(plan.files[1].change, PlannedChange::Modify);
+        assert_eq!(plan.files[2].path, game_strings);
+        assert_eq!(plan.changed_strings(), 1);
+        assert!(!plan.files[0].path.exists());
+        assert_eq!(
+            SavePlan::default()
+                .with_backup(&plugin, BackupPolicy::Off, 0)
+                .files,
+            []
+        );
+
+        let base = StringsFile::new(vec![
+            StringsEntry {
+                id: 1,
+                text: "Iron Sword".to_string(),
+            },
+            StringsEntry {
+                id: 2,
+                text: "鉄の剣".to_string(),
+            },
+        ]);
+        let strings_entries =
+            [("strings:1", "鉄の剣"), ("strings:2", "鉄の剣")].map(|(key, target)| Entry {
+                key: key.to_string(),
+                source_text: Default::default(),
+                target_text: target.into(),
+            });
+        let plan = plan_strings_save(&strings_entries, &base, &game_strings);
+        assert_eq!(plan.files[0].change, PlannedChange::Modify);
+        assert_eq!(plan.changed_strings(), 1);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_pipeline_008_save_reports_checksums_and_writes_manifest() {
+        let root = std::env::temp_dir().join(format!("xt_pipeline_report_{}", std::process::id()));
+        let (plugin, _, context) = localized_plugin(&root);
+        let (mut entries, extracted) = load_plugin_entries(&plugin, &context).expect("load");
+        entries[0].target_text = "こんにちは".into();
+
+        let out_root = root.join("out");
+        std::fs::create_dir_all(&out_root).expect("create out");
+        let output = out_root.join("Mod.esp");
+        let report = save_esp(
+            &entries,
+            &plugin,
+            &output,
+            Some(&out_root),
+            &extracted,
+            &context,
+        )
+        .expect("save");
+        assert_eq!(report.output, output);
+        assert_eq!(report.files.len(), 2);
+        let strings = out_root.join("Strings").join("Mod_english.strings");
+        assert_eq!(report.files[1].path, strings);
+        let bytes = std::fs::read(&strings).expect("strings");
+        assert_eq!(report.files[1].size, bytes.len() as u64);
+        assert_eq!(report.files[1].sha256, sha256_hex(&bytes));
+        assert_eq!(report.changed_strings(), 1);
+
+        let manifest = report.write_manifest().expect("manifest");
+        assert_eq!(manifest, out_root.join("Mod.esp.manifest.json"));
+        let read: SaveManifest =
+            serde_json::from_str(&std::fs::read_to_string(&manifest).expect("read")).expect("json");
+        assert_eq!(read, report.manifest());
+        assert_eq!(read.format, SAVE_MANIFEST_FORMAT);
+        let paths = read
+            .files
+            .iter()
+            .map(|file| file.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["Mod.esp", "Strings/Mod_english.strings"]);
+        assert_eq!(read.files[1].sha256, report.files[1].sha256);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_pipeline_009_extraction_cache_hits_until_plugin_changes() {
+        let root = std::env::temp_dir().join(format!("xt_pipeline_cache_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("Data")).expect("create");
+        let plugin_bytes = |name: &str| {
+            let full = [name.as_bytes(), b"\0"].concat();
+            let edid = b"GuardName\0";
+            let data = [
+                b"EDID".as_slice(),
+                &(edid.len() as u16).to_le_bytes(),
+                edid,
+                b"FULL",
+                &(full.len() as u16).to_le_bytes(),
+                &full,
+            ]
+            .concat();
+            [
+                b"NPC_".as_slice(),
+                &(data.len() as u32).to_le_bytes(),
+                &0x400u32.to_le_bytes(),
+                &0x0000_0D01u32.to_le_bytes(),
+                &[0u8; 8],
+                &data,
+            ]
+            .concat()
+        };
+        let plugin = root.join("Data").join("mod.esp");
+        std::fs::write(&plugin, plugin_bytes("Guard")).expect("write plugin");
+        let context = PluginContext {
+            workspace_root: root.clone(),
+            ..PluginContext::default()
+        };
+        let cache = ExtractCache::new(root.join("cache"));
+        let load = || {
+            load_plugin_entries_cached(&plugin, &context, &cache, &CancelToken::new(), &NoProgress)
+                .expect("load")
+        };
+
+        let (entries, extracted, status) = load();
+        assert_eq!(status, CacheStatus::Stored);
+        assert_eq!(entries[0].source_text.as_ref(), "Guard");
+        let (cached_entries, cached, status) = load();
+        assert_eq!(status, CacheStatus::Hit);
+        assert_eq!(cached, extracted);
+        assert_eq!(cached[0].editor_id.as_deref(), Some("GuardName"));
+        assert_eq!(cached[0].record_flags, 0x400);
+        assert_eq!(cached_entries[0].key, entries[0].key);
+
+        std::fs::write(&plugin, plugin_bytes("Guards")).expect("rewrite plugin");
+        let (entries, _, status) = load();
+        assert_eq!(status, CacheStatus::Stored);
+        assert_eq!(entries[0].source_text.as_ref(), "Guards");
+        assert_eq!(load().2, CacheStatus::Hit);
+
+        assert_eq!(cache.clear().expect("clear"), 1);
+        assert_eq!(load().2, CacheStatus::Stored);
+        assert_eq!(
+            ExtractCache::new(root.join("missing"))
+                .clear()
+                .expect("none"),
+            0
+        );
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_pipeline_010_save_beside_input_leaves_input_untouched() {
+        let root = std::env::temp_dir().join(format!("xt_pipeline_beside_{}", std::process::id()));
+        let (plugin, _, context) = localized_plugin(&root);
+        let original = std::fs::read(&plugin).expect("read");
+        let (mut entries, extracted) = load_plugin_entries(&plugin, &context).expect("load");
+        entries[0].target_text = "こんにちは".into();
+        let data = plugin.parent().expect("data");
+        let output = data.join("Mod_japanese.esp");
+        save_esp(&entries, &plugin, &output, None, &extracted, &context).expect("save");
+        assert_eq!(std::fs::read(&plugin).expect("read"), original);
+        assert!(output.exists());
+        let mut names = std::fs::read_dir(data)
+            .expect("list")
+            .map(|entry| entry.expect("entry").file_name())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["Mod.esp", "Mod_japanese.esp", "Strings"]);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn t_pipeline_011_save_writes_only_planned_files() {
+        fn files_under(dir: &Path, out: &mut Vec<PathBuf>) {
+            for entry in std::fs::read_dir(dir).expect("list") {
+                let path = entry.expect("entry").path();
+                if path.is_dir() {
+                    files_under(&path, out);
+                } else {
+                    out.push(path);
+                }
+            }
+        }
+
+        let root = std::env::temp_dir().join(format!("xt_pipeline_planned_{}", std::process::id()));
+        let (plugin, _, context) = localized_plugin(&root);
+        let (mut entries, extracted) = load_plugin_entries(&plugin, &context).expect("load");
+        entries[0].target_text = "こんにちは".into();
+        let out_root = root.join("out");
+        let output = out_root.join("Mod_japanese.esp");
+        let plan = plan_esp_save(
+            &entries,
+            &plugin,
+            &output,
+            Some(&out_root),
+            &extracted,
+            &context,
+        );
+        save_esp(
+            &entries,
+            &plugin,
+            &output,
+            Some(&out_root),
+            &extracted,
+            &context,
+        )
+        .expect("save");
+        let mut written = Vec::new();
+        files_under(&out_root, &mut written);
+        written.sort();
+        let mut planned = plan
+            .files
+            .into_iter()
+            .map(|file| file.path)
+            .collect::<Vec<_>>();
+        planned.sort();
+        assert_eq!(written, planned);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,217 @@
+//! Placeholders that translations must carry over unchanged: braced
+//! indices (`{0}`), printf conversions (`%s`, `%d`) and alias tags
+//! (`<Alias=Player>`). Validation compares them; auto-translation masks them
+//! so a dictionary pair written for other placeholders still applies.
+
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlaceholderKind {
+    Braced,
+    Printf,
+    Alias,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placeholder<'a> {
+    pub kind: PlaceholderKind,
+    /// Byte range in the text.
+    pub range: Range<usize>,
+    pub text: &'a str,
+}
+
+/// First private-use character; a masked text's `i`th placeholder becomes
+/// `MARKER_BASE + i`.
+const MARKER_BASE: u32 = 0xE000;
+const MARKER_LIMIT: usize = 0x1900;
+
+/// Every placeholder in `text`, in order. `%%` is an escaped percent sign
+/// and an alias tag without `>` is not a placeholder.
+pub fn find_placeholders(text: &str) -> Vec<Placeholder<'_>> {
+    let bytes = text.as_bytes();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let end = match bytes[i] {
+            b'{' => {
+                let digits = bytes[i + 1..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_digit())
+                    .count();
+                let close = i + 1 + digits;
+                (digits > 0 && bytes.get(close) == Some(&b'}'))
+                    .then_some((PlaceholderKind::Braced, close + 1))
+            }
+            b'%' => match bytes.get(i + 1) {
+                Some(b'%') => {
+                    i += 2;
+                    continue;
+                }
+                Some(b's' | b'd') => Some((PlaceholderKind::Printf, i + 2)),
+                _ => None,
+            },
+            b'<' if text[i..].starts_with("<Alias=") => text[i..]
+                .find('>')
+                .map(|close| (PlaceholderKind::Alias, i + close + 1)),
+            _ => None,
+        };
+        match end {
+            Some((kind, end)) => {
+                found.push(Placeholder {
+                    kind,
+                    range: i..end,
+                    text: &text[i..end],
+                });
+                i = end;
+            }
+            None => i += 1,
+        }
+    }
+    found
+}
+
+/// Placeholders of one kind in `text`, in order.
+pub fn extract(text: &str, kind: PlaceholderKind) -> Vec<&str> {
+    find_placeholders(text)
+        .into_iter()
+        .filter(|placeholder| placeholder.kind == kind)
+        .map(|placeholder| placeholder.text)
+        .collect()
+}
+
+/// A text with each placeholder replaced by a marker character, and the
+/// placeholders it replaced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Masked<'a> {
+    pub text: String,
+    pub placeholders: Vec<&'a str>,
+}
+
+/// Replaces the placeholders of `text` with markers; texts with none (or too
+/// many to mark) come back unchanged.
+pub fn mask(text: &str) -> Masked<'_> {
+    let found = find_placeholders(text);
+    if found.len() > MARKER_LIMIT {
+        return Masked {
+            text: text.to_string(),
+            placeholders: Vec::new(),
+        };
+    }
+    let mut masked = String::with_capacity(text.len());
+    let mut last = 0;
+    for (index, placeholder) in found.iter().enumerate() {
+        masked.push_str(&text[last..placeholder.range.start]);
+        masked.push(marker(index));
+        last = placeholder.range.end;
+    }
+    masked.push_str(&text[last..]);
+    Masked {
+        text: masked,
+        placeholders: found.iter().map(|placeholder| placeholder.text).collect(),
+    }
+}
+
+/// Puts `placeholders` back in place of the markers of a masked text.
+/// `None` when a marker has no placeholder.
+pub fn restore(masked: &str, placeholders: &[&str]) -> Option<String> {
+    let mut out = String::with_capacity(masked.len());
+    for ch in masked.chars() {
+        match marker_index(ch) {
+            Some(index) => out.push_str(placeholders.get(index)?),
+            None => out.push(ch),
+        }
+    }
+    Some(out)
+}
+
+/// Rewrites a translation of `from_source` for `to_source`: each
+/// placeholder `target` shares with `from_source` becomes the one at the same
+/// position in `to_source`. `None` when the sources differ in how many
+/// placeholders they have.
+pub fn transfer(target: &str, from_source: &str, to_source: &str) -> Option<String> {
+    let from = find_placeholders(from_source);
+    let to = find_placeholders(to_source);
+    if from.len() != to.len() {
+        return None;
+    }
+    if from.iter().zip(&to).all(|(a, b)| a.text == b.text) {
+        return Some(target.to_string());
+    }
+    let mut used = vec![false; from.len()];
+    let mut out = String::with_capacity(target.len());
+    let mut last = 0;
+    for placeholder in find_placeholders(target) {
+        let slot = from
+            .iter()
+            .enumerate()
+            .position(|(index, source)| !used[index] && source.text == placeholder.text);
+        let Some(slot) = slot else {
+            continue;
+        };
+        used[slot] = true;
+        out.push_str(&target[last..placeholder.range.start]);
+        out.push_str(to[slot].text);
+        last = placeholder.range.end;
+    }
+    out.push_str(&target[last..]);
+    Some(out)
+}
+
+fn marker(index: usize) -> char {
+    char::from_u32(MARKER_BASE + index as u32).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+fn marker_index(ch: char) -> Option<usize> {
+    let index = (ch as u32).checked_sub(MARKER_BASE)? as usize;
+    (index < MARKER_LIMIT).then_some(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_ph_001_mask_restore_and_transfer() {
+        let text = "Deal %d to <Alias=Player> ({0}) 100%% <Alias=broken";
+        let kinds = find_placeholders(text)
+            .iter()
+            .map(|placeholder| (placeholder.kind, placeholder.text))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                (PlaceholderKind::Printf, "%d"),
+                (PlaceholderKind::Alias, "<Alias=Player>"),
+                (PlaceholderKind::Braced, "{0}"),
+            ]
+        );
+        assert_eq!(extract(text, PlaceholderKind::Braced), ["{0}"]);
+
+        let masked = mask(text);
+        assert_eq!(
+            masked.text,
+            "Deal \u{E000} to \u{E001} (\u{E002}) 100%% <Alias=broken"
+        );
+        assert_eq!(
+            restore(&masked.text, &masked.placeholders).as_deref(),
+            Some(text)
+        );
+        assert_eq!(restore("\u{E003}", &masked.placeholders), None);
+        assert_eq!(mask("no placeholders").text, "no placeholders");
+
+        assert_eq!(
+            transfer(
+                "<Alias=Weapon>を見つけた ({0})",
+                "Found <Alias=Weapon> ({0})",
+                "Found <Alias=Item> ({1})",
+            )
+            .as_deref(),
+            Some("<Alias=Item>を見つけた ({1})")
+        );
+        assert_eq!(
+            transfer("%sと%d", "%s and %d", "%d and %s").as_deref(),
+            Some("%dと%s")
+        );
+        assert_eq!(transfer("鉄の剣", "Iron Sword", "Iron {0}"), None);
+    }
+}
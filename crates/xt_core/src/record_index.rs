@@ -0,0 +1,107 @@
+//! Finds a loaded plugin's rows by record: the first string of the record
+//! with a form id or editor ID, for the 移動 (Ctrl+G) dialog.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::formats::esp::ExtractedString;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecordIndex {
+    /// Entry key of each record's first string by form id.
+    by_form_id: BTreeMap<u32, String>,
+    /// The same by lowercased EDID.
+    by_editor_id: HashMap<String, String>,
+}
+
+impl RecordIndex {
+    pub fn build(extracted: &[ExtractedString]) -> Self {
+        let mut index = Self::default();
+        for string in extracted {
+            index
+                .by_form_id
+                .entry(string.form_id)
+                .or_insert_with(|| string.get_unique_key());
+            if let Some(editor_id) = &string.editor_id {
+                index
+                    .by_editor_id
+                    .entry(editor_id.to_lowercase())
+                    .or_insert_with(|| string.get_unique_key());
+            }
+        }
+        index
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_form_id.is_empty()
+    }
+
+    /// Key of the row to jump to for `query`: a hex form id (`0x` optional),
+    /// matched exactly and then without its master index, or else an EDID,
+    /// ignoring case.
+    pub fn find(&self, query: &str) -> Option<&str> {
+        let query = query.trim();
+        self.find_form_id(query)
+            .or_else(|| self.by_editor_id.get(&query.to_lowercase()))
+            .map(String::as_str)
+    }
+
+    fn find_form_id(&self, query: &str) -> Option<&String> {
+        let digits = query
+            .strip_prefix("0x")
+            .or_else(|| query.strip_prefix("0X"))
+            .unwrap_or(query);
+        if digits.is_empty() || digits.len() > 8 {
+            return None;
+        }
+        let form_id = u32::from_str_radix(digits, 16).ok()?;
+        self.by_form_id.get(&form_id).or_else(|| {
+            self.by_form_id
+                .iter()
+                .find(|(id, _)| *id & 0x00FF_FFFF == form_id & 0x00FF_FFFF)
+                .map(|(_, key)| key)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::esp::StringStorage;
+
+    fn string(
+        record: &[u8; 4],
+        sub: &[u8; 4],
+        form_id: u32,
+        edid: Option<&str>,
+    ) -> ExtractedString {
+        ExtractedString::new(
+            *record,
+            *sub,
+            form_id,
+            0,
+            String::new(),
+            StringStorage::Inline,
+        )
+        .with_editor_id(edid.map(str::to_string))
+    }
+
+    #[test]
+    fn t_recidx_001_find_by_form_id_or_editor_id() {
+        let index = RecordIndex::build(&[
+            string(b"WEAP", b"FULL", 0x0001_2EB7, Some("IronSword")),
+            string(b"WEAP", b"DESC", 0x0001_2EB7, Some("IronSword")),
+            string(b"NPC_", b"FULL", 0x0100_0ABC, None),
+            string(b"BOOK", b"FULL", 0x0000_0ABC, Some("ABC")),
+        ]);
+        assert!(!index.is_empty());
+        assert_eq!(index.find("00012EB7"), Some("WEAP:00012EB7:FULL:0"));
+        assert_eq!(index.find(" 0x12eb7 "), Some("WEAP:00012EB7:FULL:0"));
+        assert_eq!(index.find("ironsword"), Some("WEAP:00012EB7:FULL:0"));
+        assert_eq!(index.find("01000ABC"), Some("NPC_:01000ABC:FULL:0"));
+        assert_eq!(index.find("ABC"), Some("BOOK:00000ABC:FULL:0"), "hex first");
+        assert_eq!(index.find("05012EB7"), Some("WEAP:00012EB7:FULL:0"));
+        assert_eq!(index.find("SteelSword"), None);
+        assert_eq!(index.find(""), None);
+        assert!(RecordIndex::default().is_empty());
+    }
+}
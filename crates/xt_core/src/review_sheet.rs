@@ -0,0 +1,643 @@
+//! Bilingual review sheet: a single self-contained HTML page listing each
+//! row's key, EDID, source, target, status, note and validation issues, with
+//! the styling and filters inlined so reviewers without the tool can
+//! proofread it in a browser.
+//!
+//! Reviewers edit the target cells in place and save the page; the edited
+//! sheet, or a CSV with key and target columns, is read back with
+//! [`parse_corrections`]. Rows copied to and pasted from a spreadsheet
+//! travel as TSV through [`rows_tsv`] and [`parse_pasted_rows`].
+
+use std::fmt::Write as _;
+
+use crate::model::Entry;
+use crate::validation::ValidationIssue;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RowStatus {
+    Untranslated,
+    Translated,
+    /// Auto-translated and not approved yet (要確認).
+    NeedsReview,
+    /// Marked checked (検証済み).
+    Validated,
+}
+
+impl RowStatus {
+    pub const ALL: [RowStatus; 4] = [
+        RowStatus::Untranslated,
+        RowStatus::Translated,
+        RowStatus::NeedsReview,
+        RowStatus::Validated,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RowStatus::Untranslated => "untranslated",
+            RowStatus::Translated => "translated",
+            RowStatus::NeedsReview => "review",
+            RowStatus::Validated => "validated",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RowStatus::Untranslated => "未翻訳",
+            RowStatus::Translated => "翻訳済み",
+            RowStatus::NeedsReview => "要確認",
+            RowStatus::Validated => "検証済み",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReviewRow {
+    pub key: String,
+    pub editor_id: Option<String>,
+    pub source: String,
+    pub target: String,
+    pub status: RowStatus,
+    /// Translator note, if any.
+    pub note: Option<String>,
+    pub issues: Vec<ValidationIssue>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReviewSheet {
+    /// Page heading, e.g. the plugin's file name.
+    pub title: String,
+    pub source_lang: String,
+    pub target_lang: String,
+    pub rows: Vec<ReviewRow>,
+}
+
+const STYLE: &str = "\
+body{font-family:sans-serif;margin:1.5em;color:#222}
+header p{margin:.3em 0;color:#555}
+#filters{position:sticky;top:0;background:#fff;padding:.6em 0;display:flex;gap:1em;align-items:center;border-bottom:1px solid #ccc}
+#filters input[type=search]{width:24em}
+table{border-collapse:collapse;width:100%;margin-top:.8em}
+th,td{border:1px solid #ccc;padding:.35em .5em;vertical-align:top;text-align:left}
+th{background:#f2f2f2}
+td.text{white-space:pre-wrap;width:30%}
+td.key,td.edid{font-family:monospace;font-size:.85em;white-space:nowrap}
+td.note{color:#555;font-size:.9em;white-space:pre-wrap}
+td.target:focus{outline:2px solid #48c;background:#f6fbff}
+tr.untranslated td.status{color:#b00}
+tr.review td.status{color:#a60}
+tr.validated td.status{color:#070}
+ul.issues{margin:0;padding-left:1.2em;font-size:.9em}
+li.error{color:#b00}
+li.warn{color:#a60}
+li.info{color:#555}
+@media print{#filters{display:none}}
+";
+
+const SCRIPT: &str = "\
+const q=document.getElementById('q');
+const statusFilter=document.getElementById('status');
+const issues=document.getElementById('issues');
+const shown=document.getElementById('shown');
+const rows=Array.from(document.querySelectorAll('tbody tr'));
+function apply(){
+  const text=q.value.toLowerCase();
+  let count=0;
+  for(const row of rows){
+    const ok=(!text||row.textContent.toLowerCase().includes(text))
+      &&(!statusFilter.value||row.dataset.status===statusFilter.value)
+      &&(!issues.checked||row.dataset.issues!=='0');
+    row.hidden=!ok;
+    if(ok){count++;}
+  }
+  shown.textContent=count;
+}
+for(const input of [q,statusFilter,issues]){input.addEventListener('input',apply);input.addEventListener('change',apply);}
+apply();
+document.getElementById('save').addEventListener('click',()=>{
+  const html='<!DOCTYPE html>\n'+document.documentElement.outerHTML;
+  const link=document.createElement('a');
+  link.href=URL.createObjectURL(new Blob([html],{type:'text/html'}));
+  link.download=location.pathname.split('/').pop()||'review.html';
+  link.click();
+});
+";
+
+impl ReviewSheet {
+    /// Rows with `status`.
+    pub fn count(&self, status: RowStatus) -> usize {
+        self.rows.iter().filter(|row| row.status == status).count()
+    }
+
+    /// The sheet as one HTML document with no external resources.
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+        out.push_str("<!DOCTYPE html>\n<html lang=\"ja\">\n<head>\n<meta charset=\"utf-8\">\n");
+        let _ = writeln!(
+            out,
+            "<title>{} - レビューシート</title>",
+            escape_html(&self.title)
+        );
+        let _ = writeln!(out, "<style>\n{STYLE}</style>\n</head>\n<body>\n<header>");
+        let _ = writeln!(out, "<h1>{}</h1>", escape_html(&self.title));
+        let _ = writeln!(
+            out,
+            "<p>{} → {} / {}行</p>",
+            escape_html(&self.source_lang),
+            escape_html(&self.target_lang),
+            self.rows.len()
+        );
+        let counts = RowStatus::ALL
+            .iter()
+            .map(|&status| format!("{} {}", status.label(), self.count(status)))
+            .collect::<Vec<_>>()
+            .join(" / ");
+        let issues = self.rows.iter().map(|row| row.issues.len()).sum::<usize>();
+        let _ = writeln!(out, "<p>{counts} / 検証結果 {issues}件</p>\n</header>");
+
+        out.push_str("<div id=\"filters\">\n");
+        out.push_str(
+            "<input id=\"q\" type=\"search\" placeholder=\"キー・EDID・原文・訳文を検索\">\n",
+        );
+        out.push_str("<select id=\"status\">\n<option value=\"\">すべての状態</option>\n");
+        for status in RowStatus::ALL {
+            let _ = writeln!(
+                out,
+                "<option value=\"{}\">{}</option>",
+                status.as_str(),
+                status.label()
+            );
+        }
+        out.push_str("</select>\n");
+        out.push_str(
+            "<label><input id=\"issues\" type=\"checkbox\"> 検証結果のある行のみ</label>\n",
+        );
+        let _ = writeln!(
+            out,
+            "<span><span id=\"shown\">{}</span> 行を表示</span>\n<button id=\"save\">編集結果を保存</button>\n</div>",
+            self.rows.len()
+        );
+
+        out.push_str("<table>\n<thead><tr><th>キー</th><th>EDID</th><th>原文</th><th>訳文</th><th>状態</th><th>検証結果</th><th>メモ</th></tr></thead>\n<tbody>\n");
+        for row in &self.rows {
+            let _ = write!(
+                out,
+                "<tr class=\"{0}\" data-status=\"{0}\" data-issues=\"{1}\">",
+                row.status.as_str(),
+                row.issues.len()
+            );
+            let _ = write!(
+                out,
+                "<td class=\"key\">{}</td><td class=\"edid\">{}</td>",
+                escape_html(&row.key),
+                escape_html(row.editor_id.as_deref().unwrap_or_default())
+            );
+            let _ = write!(
+                out,
+                "<td class=\"text\">{}</td><td class=\"text target\" contenteditable=\"plaintext-only\">{}</td>",
+                escape_html(&row.source),
+                escape_html(&row.target)
+            );
+            let _ = write!(out, "<td class=\"status\">{}</td><td>", row.status.label());
+            if !row.issues.is_empty() {
+                out.push_str("<ul class=\"issues\">");
+                for issue in &row.issues {
+                    let _ = write!(
+                        out,
+                        "<li class=\"{}\">[{}] {}</li>",
+                        issue.severity.as_str(),
+                        escape_html(&issue.rule_id),
+                        escape_html(&issue.message)
+                    );
+                }
+                out.push_str("</ul>");
+            }
+            let _ = writeln!(
+                out,
+                "</td><td class=\"note\">{}</td></tr>",
+                escape_html(row.note.as_deref().unwrap_or_default())
+            );
+        }
+        out.push_str("</tbody>\n</table>\n");
+        let _ = writeln!(out, "<script>\n{SCRIPT}</script>\n</body>\n</html>");
+        out
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+/// A target read back from an edited review sheet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Correction {
+    pub key: String,
+    pub target: String,
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum CorrectionsError {
+    /// No header names a key and a target column.
+    #[error("key/target columns not found")]
+    MissingColumns,
+    #[error("line {0}: unterminated quoted field")]
+    UnterminatedQuote(usize),
+}
+
+/// Header names of the key column (the sheet's own and common ones).
+const KEY_HEADERS: [&str; 3] = ["キー", "key", "id"];
+/// Header names of the target column.
+const TARGET_HEADERS: [&str; 4] = ["訳文", "target", "translation", "dest"];
+
+/// Reads the key and target columns of an edited review sheet: the HTML
+/// written by [`ReviewSheet::to_html`] or a CSV (comma or tab separated)
+/// whose header names the two columns. Rows without a key are skipped.
+pub fn parse_corrections(input: &str) -> Result<Vec<Correction>, CorrectionsError> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let rows = if input.trim_start().starts_with('<') {
+        html_rows(input)
+    } else {
+        csv_rows(input)?
+    };
+    let mut rows = rows.into_iter();
+    let header = rows.next().ok_or(CorrectionsError::MissingColumns)?;
+    let column = |names: &[&str]| {
+        header.iter().position(|cell| {
+            let cell = cell.trim();
+            names.iter().any(|name| cell.eq_ignore_ascii_case(name))
+        })
+    };
+    let (Some(key), Some(target)) = (column(&KEY_HEADERS), column(&TARGET_HEADERS)) else {
+        return Err(CorrectionsError::MissingColumns);
+    };
+    Ok(rows
+        .filter_map(|row| {
+            let key = row.get(key)?.trim();
+            (!key.is_empty()).then(|| Correction {
+                key: key.to_string(),
+                target: row.get(target).cloned().unwrap_or_default(),
+            })
+        })
+        .collect())
+}
+
+/// `entries` as TSV with a `key`, `source`, `target` header, for the
+/// clipboard. Fields holding tabs, line breaks or quotes are quoted the way
+/// spreadsheets do.
+pub fn rows_tsv(entries: &[Entry]) -> String {
+    let mut out = String::from("key\tsource\ttarget\n");
+    for entry in entries {
+        let fields = [entry.key.as_str(), &entry.source_text, &entry.target_text];
+        for (at, field) in fields.into_iter().enumerate() {
+            if at > 0 {
+                out.push('\t');
+            }
+            if field.contains(['\t', '\n', '\r', '"']) {
+                let _ = write!(out, "\"{}\"", field.replace('"', "\"\""));
+            } else {
+                out.push_str(field);
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Reads rows pasted from a spreadsheet: with a header naming the key and
+/// target columns as [`parse_corrections`] does, otherwise key in the first
+/// column and target in the third (the layout of [`rows_tsv`]), or in the
+/// second when only two are given.
+pub fn parse_pasted_rows(input: &str) -> Result<Vec<Correction>, CorrectionsError> {
+    match parse_corrections(input) {
+        Err(CorrectionsError::MissingColumns) => {}
+        parsed => return parsed,
+    }
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    Ok(csv_rows(input)?
+        .into_iter()
+        .filter_map(|mut row| {
+            let target = match row.len() {
+                0 | 1 => return None,
+                2 => row.swap_remove(1),
+                _ => row.swap_remove(2),
+            };
+            let key = row[0].trim();
+            (!key.is_empty()).then(|| Correction {
+                key: key.to_string(),
+                target,
+            })
+        })
+        .collect())
+}
+
+/// Cell texts of each `<tr>` of the first table, header row included.
+fn html_rows(html: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find("<tr") {
+        rest = &rest[start + 3..];
+        let end = rest.find("</tr>").unwrap_or(rest.len());
+        let mut cells = Vec::new();
+        let mut row = &rest[..end];
+        while let Some(open) = row.find("<t").filter(|&at| {
+            matches!(row.as_bytes().get(at + 2), Some(b'd' | b'h'))
+                && matches!(row.as_bytes().get(at + 3), Some(b'>' | b' '))
+        }) {
+            let tag = row.as_bytes()[open + 2];
+            let Some(content) = row[open..].find('>').map(|at| open + at + 1) else {
+                break;
+            };
+            let close = if tag == b'd' { "</td>" } else { "</th>" };
+            let stop = row[content..]
+                .find(close)
+                .map_or(row.len(), |at| content + at);
+            cells.push(html_text(&row[content..stop]));
+            row = &row[(stop + close.len()).min(row.len())..];
+        }
+        rows.push(cells);
+        rest = &rest[end..];
+    }
+    rows
+}
+
+/// Text of an HTML fragment: `<br>` and block starts become line breaks,
+/// other tags are dropped and character references decoded.
+fn html_text(fragment: &str) -> String {
+    let mut out = String::with_capacity(fragment.len());
+    let mut rest = fragment;
+    while let Some(at) = rest.find(['<', '&']) {
+        out.push_str(&rest[..at]);
+        rest = &rest[at..];
+        if rest.starts_with('<') {
+            let end = rest.find('>').map_or(rest.len(), |end| end + 1);
+            let name = rest[1..end]
+                .chars()
+                .take_while(char::is_ascii_alphanumeric)
+                .collect::<String>()
+                .to_ascii_lowercase();
+            // Browsers may wrap edited lines in <div>s instead of <br>s.
+            match name.as_str() {
+                "br" => out.push('\n'),
+                "div" | "p" if !out.is_empty() && !out.ends_with('\n') => out.push('\n'),
+                _ => {}
+            }
+            rest = &rest[end..];
+            continue;
+        }
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| {
+                let entity = &rest[1..end + 1];
+                let ch = match entity {
+                    "amp" => '&',
+                    "lt" => '<',
+                    "gt" => '>',
+                    "quot" => '"',
+                    "apos" => '\'',
+                    "nbsp" => '\u{a0}',
+                    _ => {
+                        let number = entity.strip_prefix('#')?;
+                        let value = match number.strip_prefix(['x', 'X']) {
+                            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                            None => number.parse().ok()?,
+                        };
+                        char::from_u32(value)?
+                    }
+                };
+                Some((ch, end + 2))
+            });
+        match decoded {
+            Some((ch, len)) => {
+                out.push(ch);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Fields of each CSV record; quoted fields may hold separators, doubled
+/// quotes and line breaks. The header line picks tab or comma.
+fn csv_rows(input: &str) -> Result<Vec<Vec<String>>, CorrectionsError> {
+    let header = input.lines().next().unwrap_or_default();
+    let separator = if header.contains('\t') { '\t' } else { ',' };
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut quote_line = 0;
+    let mut chars = input.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => {
+                quoted = true;
+                quote_line = line;
+            }
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                line += 1;
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            ch if ch == separator && !quoted => row.push(std::mem::take(&mut field)),
+            ch => {
+                if ch == '\n' {
+                    line += 1;
+                }
+                field.push(ch);
+            }
+        }
+    }
+    if quoted {
+        return Err(CorrectionsError::UnterminatedQuote(quote_line));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::Severity;
+
+    fn row(key: &str, target: &str, status: RowStatus) -> ReviewRow {
+        ReviewRow {
+            key: key.to_string(),
+            editor_id: None,
+            source: format!("Source {key}"),
+            target: target.to_string(),
+            status,
+            note: None,
+            issues: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn t_review_001_html_sheet_escapes_text_and_embeds_filters() {
+        let mut flagged = row(
+            "WEAP:00012EB7:FULL:0",
+            "<剣> & \"盾\"",
+            RowStatus::NeedsReview,
+        );
+        flagged.editor_id = Some("IronSword".to_string());
+        flagged.note = Some("check gender".to_string());
+        flagged.issues.push(ValidationIssue {
+            entry_key: flagged.key.clone(),
+            severity: Severity::Error,
+            rule_id: "braced_placeholder".to_string(),
+            message: "Braced placeholders do not match.".to_string(),
+        });
+        let sheet = ReviewSheet {
+            title: "Mod <test>.esp".to_string(),
+            source_lang: "english".to_string(),
+            target_lang: "japanese".to_string(),
+            rows: vec![
+                flagged,
+                row("k1", "", RowStatus::Untranslated),
+                row("k2", "訳", RowStatus::Validated),
+            ],
+        };
+        assert_eq!(sheet.count(RowStatus::Untranslated), 1);
+        assert_eq!(sheet.count(RowStatus::Translated), 0);
+
+        let html = sheet.to_html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h1>Mod &lt;test&gt;.esp</h1>"));
+        assert!(html.contains("<p>english → japanese / 3行</p>"));
+        assert!(html.contains("未翻訳 1 / 翻訳済み 0 / 要確認 1 / 検証済み 1 / 検証結果 1件"));
+        assert!(html.contains(
+            "<tr class=\"review\" data-status=\"review\" data-issues=\"1\"><td class=\"key\">WEAP:00012EB7:FULL:0</td><td class=\"edid\">IronSword</td>"
+        ));
+        assert!(html.contains(
+            "<td class=\"text target\" contenteditable=\"plaintext-only\">&lt;剣&gt; &amp; &quot;盾&quot;</td>"
+        ));
+        assert!(html.contains(
+            "<li class=\"error\">[braced_placeholder] Braced placeholders do not match.</li>"
+        ));
+        assert!(html.contains("<td class=\"note\">check gender</td>"));
+        assert!(html.contains("data-status=\"untranslated\" data-issues=\"0\""));
+        assert!(html.contains("<option value=\"validated\">検証済み</option>"));
+        assert!(html.contains("id=\"issues\" type=\"checkbox\""));
+        assert!(html.contains("<script>"));
+        assert!(!html.contains("<link") && !html.contains(" src="));
+    }
+
+    #[test]
+    fn t_review_002_corrections_read_from_edited_html_and_csv() {
+        let sheet = ReviewSheet {
+            title: "Mod.esp".to_string(),
+            rows: vec![
+                row("k0", "鉄の剣 & 盾", RowStatus::Translated),
+                row("k1", "", RowStatus::Untranslated),
+            ],
+            ..ReviewSheet::default()
+        };
+        let edited = sheet.to_html().replace(
+            "contenteditable=\"plaintext-only\"></td>",
+            "contenteditable=\"plaintext-only\">鋼の&lt;剣&gt;<br>二行目&#x21;</td>",
+        );
+        let corrections = parse_corrections(&edited).expect("html");
+        assert_eq!(
+            corrections,
+            [
+                Correction {
+                    key: "k0".to_string(),
+                    target: "鉄の剣 & 盾".to_string(),
+                },
+                Correction {
+                    key: "k1".to_string(),
+                    target: "鋼の<剣>\n二行目!".to_string(),
+                },
+            ]
+        );
+
+        let csv = "\u{feff}EDID,Key,Target\r\nIronSword,k0,\"剣, \"\"改\"\"\r\n二行目\"\r\n,,ignored\r\n,k1,\n";
+        let corrections = parse_corrections(csv).expect("csv");
+        assert_eq!(corrections.len(), 2);
+        assert_eq!(corrections[0].target, "剣, \"改\"\r\n二行目");
+        assert_eq!(corrections[1].target, "");
+        let tsv = parse_corrections("キー\t訳文\nk0\t剣,盾").expect("tsv");
+        assert_eq!(tsv[0].target, "剣,盾");
+
+        assert_eq!(
+            parse_corrections("key,source\nk0,Sword"),
+            Err(CorrectionsError::MissingColumns)
+        );
+        assert_eq!(
+            parse_corrections("key,target\nk0,ok\nk1,\"open"),
+            Err(CorrectionsError::UnterminatedQuote(3))
+        );
+    }
+
+    #[test]
+    fn t_review_003_clipboard_rows_round_trip_as_tsv() {
+        let entries = vec![
+            Entry {
+                key: "k0".to_string(),
+                source_text: "Iron Sword".into(),
+                target_text: "鉄の剣".into(),
+            },
+            Entry {
+                key: "k1".to_string(),
+                source_text: "Two\tcells".into(),
+                target_text: "一行目\n\"二行目\"".into(),
+            },
+        ];
+        let tsv = rows_tsv(&entries);
+        assert_eq!(
+            tsv,
+            "key\tsource\ttarget\nk0\tIron Sword\t鉄の剣\nk1\t\"Two\tcells\"\t\"一行目\n\"\"二行目\"\"\"\n"
+        );
+        let pasted = parse_pasted_rows(&tsv).expect("with header");
+        assert_eq!(pasted.len(), 2);
+        assert_eq!(pasted[1].target, "一行目\n\"二行目\"");
+
+        let headerless = parse_pasted_rows("k0\tIron Sword\t剣\r\nk1\t盾\r\n\r\nk2\n\tnone\t-\n")
+            .expect("headerless");
+        assert_eq!(
+            headerless,
+            [
+                Correction {
+                    key: "k0".to_string(),
+                    target: "剣".to_string(),
+                },
+                Correction {
+                    key: "k1".to_string(),
+                    target: "盾".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            parse_pasted_rows("k0\t\"open"),
+            Err(CorrectionsError::UnterminatedQuote(1))
+        );
+    }
+}
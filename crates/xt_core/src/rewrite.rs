@@ -0,0 +1,135 @@
+//! Ordered regex search/replace rules run over targets (xTranslator's
+//! heuristics regex), e.g. to normalize brackets or the spacing around
+//! Japanese punctuation. Replacements use the `regex` crate syntax: `$1` or
+//! `${name}` for groups and `$$` for a literal dollar sign.
+
+use std::borrow::Cow;
+
+use regex::Regex;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewriteRule {
+    pub name: String,
+    pub enabled: bool,
+    pub pattern: String,
+    pub replacement: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RewriteError {
+    #[error("invalid pattern in rule {name}: {message}")]
+    InvalidPattern { name: String, message: String },
+}
+
+/// What one rule made of the text in [`Rewriter::trace`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewriteStep {
+    pub name: String,
+    pub output: String,
+}
+
+/// The enabled rules of a list, compiled, in order.
+#[derive(Debug, Clone, Default)]
+pub struct Rewriter {
+    rules: Vec<(String, Regex, String)>,
+}
+
+impl Rewriter {
+    pub fn new(rules: &[RewriteRule]) -> Result<Self, RewriteError> {
+        let rules = rules
+            .iter()
+            .filter(|rule| rule.enabled)
+            .map(|rule| {
+                let regex =
+                    Regex::new(&rule.pattern).map_err(|err| RewriteError::InvalidPattern {
+                        name: rule.name.clone(),
+                        message: err.to_string(),
+                    })?;
+                Ok((rule.name.clone(), regex, rule.replacement.clone()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// `text` after every rule, each applied to the previous one's output;
+    /// borrowed when no rule changed it.
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut out = Cow::Borrowed(text);
+        for (_, regex, replacement) in &self.rules {
+            let replaced = match regex.replace_all(&out, replacement.as_str()) {
+                Cow::Owned(replaced) if replaced != *out => replaced,
+                _ => continue,
+            };
+            out = Cow::Owned(replaced);
+        }
+        out
+    }
+
+    /// The rules that changed `text`, with the text after each.
+    pub fn trace(&self, text: &str) -> Vec<RewriteStep> {
+        let mut steps = Vec::new();
+        let mut current = text.to_string();
+        for (name, regex, replacement) in &self.rules {
+            if let Cow::Owned(replaced) = regex.replace_all(&current, replacement.as_str()) {
+                if replaced != current {
+                    steps.push(RewriteStep {
+                        name: name.clone(),
+                        output: replaced.clone(),
+                    });
+                    current = replaced;
+                }
+            }
+        }
+        steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, pattern: &str, replacement: &str) -> RewriteRule {
+        RewriteRule {
+            name: name.to_string(),
+            enabled: true,
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+        }
+    }
+
+    #[test]
+    fn t_rewrite_001_rules_applied_in_order() {
+        let mut rules = vec![
+            rule("brackets", r"\[(.+?)\]", "【$1】"),
+            rule("space", r"\s*([、。])\s*", "$1"),
+            rule("dollar", r"G$", "$$"),
+        ];
+        let rewriter = Rewriter::new(&rules).expect("compile");
+        assert_eq!(rewriter.apply("[鉄] の剣 。 100G"), "【鉄】 の剣。100$");
+        assert!(matches!(rewriter.apply("鉄の剣"), Cow::Borrowed(_)));
+        assert!(matches!(rewriter.apply("鉄、剣"), Cow::Borrowed(_)));
+        let steps = rewriter.trace("[鉄]。");
+        assert_eq!(
+            steps,
+            [RewriteStep {
+                name: "brackets".to_string(),
+                output: "【鉄】。".to_string(),
+            }]
+        );
+
+        rules[0].enabled = false;
+        let rewriter = Rewriter::new(&rules).expect("compile");
+        assert_eq!(rewriter.apply("[鉄] 。"), "[鉄]。");
+
+        rules[1].pattern = "(".to_string();
+        assert!(matches!(
+            Rewriter::new(&rules),
+            Err(RewriteError::InvalidPattern { name, .. }) if name == "space"
+        ));
+        assert!(Rewriter::new(&[]).expect("empty").is_empty());
+    }
+}
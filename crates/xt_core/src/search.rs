@@ -1,3 +1,7 @@
+use std::borrow::Cow;
+
+use icu_normalizer::ComposingNormalizerBorrowed;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SearchEntry {
     pub key: String,
@@ -13,6 +17,116 @@ pub enum SearchField {
     Either,
 }
 
+/// How search queries and dictionary sources are compared. Text is always
+/// compared in Unicode NFC, so precomposed and combining forms (e.g. `ガ` and
+/// `カ` + U+3099) are equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MatchOptions {
+    pub ignore_case: bool,
+    /// Full-width and half-width forms are equal (`Ｉｒｏｎ` = `Iron`,
+    /// `ｶﾞ` = `ガ`); uses NFKC instead of NFC.
+    pub ignore_width: bool,
+    /// Leading and trailing whitespace is ignored.
+    pub trim: bool,
+    /// Runs of whitespace (tabs and line breaks too) compare as one space.
+    pub collapse_whitespace: bool,
+    /// Trailing sentence punctuation and the whitespace around it are
+    /// ignored (`Iron Sword.` = `Iron Sword`), unless nothing else is left.
+    pub strip_trailing_punctuation: bool,
+}
+
+impl MatchOptions {
+    /// Canonically equivalent text only.
+    pub const EXACT: Self = Self {
+        ignore_case: false,
+        ignore_width: false,
+        trim: false,
+        collapse_whitespace: false,
+        strip_trailing_punctuation: false,
+    };
+    /// Ignores case and width.
+    pub const LOOSE: Self = Self {
+        ignore_case: true,
+        ignore_width: true,
+        ..Self::EXACT
+    };
+
+    /// `text` in the form compared under these options; two texts match when
+    /// their folded forms are equal (or one contains the other, for search).
+    pub fn fold<'a>(self, text: &'a str) -> Cow<'a, str> {
+        let normalized = if text.is_ascii() {
+            Cow::Borrowed(text)
+        } else if self.ignore_width {
+            ComposingNormalizerBorrowed::new_nfkc().normalize(text)
+        } else {
+            ComposingNormalizerBorrowed::new_nfc().normalize(text)
+        };
+        let folded = if self.ignore_case && normalized.chars().any(char::is_uppercase) {
+            Cow::Owned(normalized.to_lowercase())
+        } else {
+            normalized
+        };
+        self.fold_spacing(folded)
+    }
+
+    fn fold_spacing(self, text: Cow<'_, str>) -> Cow<'_, str> {
+        let mut text = text;
+        if self.collapse_whitespace && has_whitespace_to_collapse(&text) {
+            let mut out = String::with_capacity(text.len());
+            let mut in_space = false;
+            for ch in text.chars() {
+                if ch.is_whitespace() {
+                    if !in_space {
+                        out.push(' ');
+                    }
+                    in_space = true;
+                } else {
+                    out.push(ch);
+                    in_space = false;
+                }
+            }
+            text = Cow::Owned(out);
+        }
+        let mut end = text.len();
+        if self.strip_trailing_punctuation {
+            let stripped = text.trim_end_matches(|ch: char| {
+                ch.is_whitespace() || TRAILING_PUNCTUATION.contains(&ch)
+            });
+            if !stripped.trim().is_empty() {
+                end = stripped.len();
+            }
+        }
+        let start = if self.trim {
+            end = text[..end].trim_end().len();
+            text[..end].len() - text[..end].trim_start().len()
+        } else {
+            0
+        };
+        if start == 0 && end == text.len() {
+            return text;
+        }
+        match text {
+            Cow::Borrowed(text) => Cow::Borrowed(&text[start..end]),
+            Cow::Owned(text) => Cow::Owned(text[start..end].to_string()),
+        }
+    }
+}
+
+/// Punctuation [`MatchOptions::strip_trailing_punctuation`] ignores.
+const TRAILING_PUNCTUATION: [char; 15] = [
+    '.', '!', '?', ':', ';', ',', '…', '。', '．', '！', '？', '：', '；', '，', '、',
+];
+
+fn has_whitespace_to_collapse(text: &str) -> bool {
+    let mut previous_space = false;
+    text.chars().any(|ch| {
+        let space = ch.is_whitespace();
+        let collapse = space && (ch != ' ' || previous_space);
+        previous_space = space;
+        collapse
+    })
+}
+
 pub fn search_entries(entries: &[SearchEntry], query: &str, field: SearchField) -> Vec<String> {
     if query.is_empty() {
         return entries.iter().map(|entry| entry.key.clone()).collect();
@@ -35,6 +149,26 @@ pub fn search_entries(entries: &[SearchEntry], query: &str, field: SearchField)
 mod tests {
     use super::*;
 
+    #[test]
+    fn t_srch_002_match_options_fold() {
+        let combining = "\u{30AB}\u{3099}ード";
+        assert_eq!(MatchOptions::EXACT.fold(combining), "ガード");
+        assert_eq!(MatchOptions::EXACT.fold("Iron"), "Iron");
+        assert_eq!(MatchOptions::EXACT.fold("ｶﾞｰﾄﾞ"), "ｶﾞｰﾄﾞ");
+
+        let case_only = MatchOptions {
+            ignore_case: true,
+            ..MatchOptions::EXACT
+        };
+        assert_eq!(case_only.fold("Iron Sword"), "iron sword");
+        assert_eq!(case_only.fold("Ｉｒｏｎ"), "ｉｒｏｎ");
+
+        assert_eq!(MatchOptions::LOOSE.fold("Ｉｒｏｎ　Sword"), "iron sword");
+        assert_eq!(MatchOptions::LOOSE.fold("ｶﾞｰﾄﾞ"), "ガード");
+        assert_eq!(MatchOptions::LOOSE.fold(combining), "ガード");
+        assert!(matches!(MatchOptions::LOOSE.fold("iron"), Cow::Borrowed(_)));
+    }
+
     #[test]
     fn t_srch_001_search_source_target() {
         let entries = vec![
@@ -61,4 +195,40 @@ mod tests {
         let target_hits = search_entries(&entries, "さよう", SearchField::Target);
         assert_eq!(target_hits, vec!["key-2".to_string()]);
     }
+
+    #[test]
+    fn t_srch_003_match_options_fold_spacing_and_punctuation() {
+        let trim = MatchOptions {
+            trim: true,
+            ..MatchOptions::EXACT
+        };
+        assert_eq!(trim.fold("  Iron Sword\n"), "Iron Sword");
+        assert!(matches!(trim.fold(" Iron "), Cow::Borrowed("Iron")));
+
+        let collapse = MatchOptions {
+            collapse_whitespace: true,
+            ..MatchOptions::EXACT
+        };
+        assert_eq!(collapse.fold("Iron \t Sword\r\n"), "Iron Sword ");
+        assert!(matches!(collapse.fold("Iron Sword"), Cow::Borrowed(_)));
+
+        let punct = MatchOptions {
+            strip_trailing_punctuation: true,
+            ..MatchOptions::EXACT
+        };
+        assert_eq!(punct.fold("Iron Sword. "), "Iron Sword");
+        assert_eq!(punct.fold("Really?!"), "Really");
+        assert_eq!(punct.fold("鉄の剣。"), "鉄の剣");
+        assert_eq!(punct.fold("Mr. Smith"), "Mr. Smith");
+        assert_eq!(punct.fold("..."), "...", "nothing else left");
+
+        let all = MatchOptions {
+            trim: true,
+            collapse_whitespace: true,
+            strip_trailing_punctuation: true,
+            ..MatchOptions::LOOSE
+        };
+        assert_eq!(all.fold(" IRON  Sword ！"), "iron sword");
+        assert_eq!(MatchOptions::EXACT.fold(" Iron  Sword. "), " Iron  Sword. ");
+    }
 }
@@ -0,0 +1,459 @@
+//! Hunspell-compatible spellchecker for target text.
+//!
+//! Reads a `.dic` word list plus the prefix/suffix rules of the matching `.aff`
+//! file. Compound rules, suggestions and morphology are not supported.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SpellcheckError {
+    #[error("read {}: {source}", .0.display(), source = .1)]
+    Io(PathBuf, #[source] std::io::Error),
+    #[error("invalid dictionary: {0}")]
+    InvalidDictionary(String),
+}
+
+/// Byte range of a word inside the checked text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordSpan {
+    pub start: usize,
+    pub end: usize,
+    pub word: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SpellChecker {
+    words: HashSet<String>,
+    ignored: HashSet<String>,
+}
+
+impl SpellChecker {
+    /// Loads `path` (`*.dic`) and the sibling `*.aff` file when it exists.
+    pub fn load(dic_path: &Path) -> Result<Self, SpellcheckError> {
+        let dic = read_dictionary_text(dic_path)?;
+        let aff_path = dic_path.with_extension("aff");
+        let aff = if aff_path.is_file() {
+            Some(read_dictionary_text(&aff_path)?)
+        } else {
+            None
+        };
+        Self::from_hunspell(&dic, aff.as_deref())
+    }
+
+    pub fn from_hunspell(dic: &str, aff: Option<&str>) -> Result<Self, SpellcheckError> {
+        let affixes = match aff {
+            Some(aff) => parse_affixes(aff)?,
+            None => AffixTable::default(),
+        };
+        let mut words = HashSet::new();
+        let mut lines = dic.lines();
+        let count_line = lines
+            .next()
+            .ok_or_else(|| SpellcheckError::InvalidDictionary("empty .dic".to_string()))?;
+        if count_line.trim().parse::<usize>().is_err() {
+            return Err(SpellcheckError::InvalidDictionary(format!(
+                "missing word count: {count_line}"
+            )));
+        }
+        for line in lines {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('\t') {
+                continue;
+            }
+            let entry = line.split_whitespace().next().unwrap_or_default();
+            let (stem, flags) = split_dic_entry(entry);
+            if stem.is_empty() {
+                continue;
+            }
+            let flags = affixes.flag_mode.split(flags);
+            for form in affixes.expand(stem, &flags) {
+                words.insert(form);
+            }
+            words.insert(stem.to_string());
+        }
+        Ok(Self {
+            words,
+            ignored: HashSet::new(),
+        })
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn set_ignored_words<I, S>(&mut self, words: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.ignored = words
+            .into_iter()
+            .map(|word| word.as_ref().to_lowercase())
+            .collect();
+    }
+
+    pub fn ignore_word(&mut self, word: &str) {
+        self.ignored.insert(word.to_lowercase());
+    }
+
+    pub fn is_correct(&self, word: &str) -> bool {
+        if self.words.contains(word) || self.ignored.contains(&word.to_lowercase()) {
+            return true;
+        }
+        let lower = word.to_lowercase();
+        if lower != word && self.words.contains(&lower) {
+            return true;
+        }
+        // "Dragonborn" in the dictionary also accepts "DRAGONBORN".
+        let mut chars = lower.chars();
+        match chars.next() {
+            Some(first) => {
+                let capitalized: String = first.to_uppercase().chain(chars).collect();
+                capitalized != word && self.words.contains(&capitalized)
+            }
+            None => true,
+        }
+    }
+
+    pub fn misspelled_words(&self, text: &str) -> Vec<WordSpan> {
+        tokenize_words(text)
+            .into_iter()
+            .filter(|span| !self.is_correct(&span.word))
+            .collect()
+    }
+}
+
+/// Splits `text` into checkable words.
+///
+/// Markup (`<Alias=...>`, `{0}`, `%s`) and words containing digits or CJK
+/// characters are skipped.
+pub fn tokenize_words(text: &str) -> Vec<WordSpan> {
+    let mut spans = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut skip_word = false;
+    let mut in_tag = false;
+    let mut prev_percent = false;
+    let mut flush = |start: &mut Option<usize>, end: usize, skip: bool| {
+        if let Some(begin) = start.take() {
+            let word = text[begin..end].trim_end_matches('\'');
+            if !skip && !word.is_empty() && word.chars().count() > 1 {
+                spans.push(WordSpan {
+                    start: begin,
+                    end: begin + word.len(),
+                    word: word.to_string(),
+                });
+            }
+        }
+    };
+    for (index, ch) in text.char_indices() {
+        if in_tag {
+            in_tag = ch != '>';
+            continue;
+        }
+        let word_char = ch.is_alphanumeric() || (ch == '\'' && start.is_some());
+        if word_char {
+            if start.is_none() {
+                start = Some(index);
+                skip_word = prev_percent;
+            }
+            if ch.is_numeric() || is_cjk(ch) {
+                skip_word = true;
+            }
+        } else {
+            flush(&mut start, index, skip_word);
+            if ch == '<' {
+                in_tag = true;
+            }
+        }
+        prev_percent = ch == '%';
+    }
+    flush(&mut start, text.len(), skip_word);
+    spans
+}
+
+fn is_cjk(ch: char) -> bool {
+    matches!(
+        ch as u32,
+        0x3000..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0xFF00..=0xFFEF
+    )
+}
+
+fn read_dictionary_text(path: &Path) -> Result<String, SpellcheckError> {
+    let bytes = std::fs::read(path).map_err(|err| SpellcheckError::Io(path.to_path_buf(), err))?;
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&bytes);
+    Ok(match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        // Older dictionaries use `SET ISO8859-1`.
+        Err(_) => bytes.iter().map(|&b| b as char).collect(),
+    })
+}
+
+fn split_dic_entry(entry: &str) -> (&str, &str) {
+    let mut escaped = false;
+    for (index, ch) in entry.char_indices() {
+        match ch {
+            '\\' => escaped = !escaped,
+            '/' if !escaped => return (&entry[..index], &entry[index + 1..]),
+            _ => escaped = false,
+        }
+    }
+    (entry, "")
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum FlagMode {
+    #[default]
+    Char,
+    Long,
+    Num,
+}
+
+impl FlagMode {
+    fn split(self, flags: &str) -> Vec<String> {
+        match self {
+            FlagMode::Char => flags.chars().map(String::from).collect(),
+            FlagMode::Long => flags
+                .chars()
+                .collect::<Vec<_>>()
+                .chunks(2)
+                .map(|pair| pair.iter().collect())
+                .collect(),
+            FlagMode::Num => flags
+                .split(',')
+                .map(str::trim)
+                .filter(|flag| !flag.is_empty())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CharClass {
+    Any,
+    Literal(char),
+    Set { chars: Vec<char>, negated: bool },
+}
+
+impl CharClass {
+    fn matches(&self, ch: char) -> bool {
+        match self {
+            CharClass::Any => true,
+            CharClass::Literal(expected) => *expected == ch,
+            CharClass::Set { chars, negated } => chars.contains(&ch) != *negated,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AffixRule {
+    strip: String,
+    add: String,
+    condition: Vec<CharClass>,
+}
+
+#[derive(Debug, Clone)]
+struct AffixGroup {
+    prefix: bool,
+    cross_product: bool,
+    rules: Vec<AffixRule>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct AffixTable {
+    flag_mode: FlagMode,
+    groups: HashMap<String, AffixGroup>,
+}
+
+impl AffixTable {
+    fn expand(&self, stem: &str, flags: &[String]) -> Vec<String> {
+        let groups: Vec<&AffixGroup> = flags
+            .iter()
+            .filter_map(|flag| self.groups.get(flag))
+            .collect();
+        let mut forms = Vec::new();
+        let mut suffixed = Vec::new();
+        for group in groups.iter().filter(|group| !group.prefix) {
+            for form in group.apply(stem) {
+                if group.cross_product {
+                    suffixed.push(form.clone());
+                }
+                forms.push(form);
+            }
+        }
+        for group in groups.iter().filter(|group| group.prefix) {
+            forms.extend(group.apply(stem));
+            if group.cross_product {
+                for form in &suffixed {
+                    forms.extend(group.apply(form));
+                }
+            }
+        }
+        forms
+    }
+}
+
+impl AffixGroup {
+    fn apply(&self, word: &str) -> Vec<String> {
+        let chars: Vec<char> = word.chars().collect();
+        let mut out = Vec::new();
+        for rule in &self.rules {
+            let n = rule.condition.len();
+            if n > chars.len() {
+                continue;
+            }
+            let window = if self.prefix {
+                &chars[..n]
+            } else {
+                &chars[chars.len() - n..]
+            };
+            if !window
+                .iter()
+                .zip(&rule.condition)
+                .all(|(ch, class)| class.matches(*ch))
+            {
+                continue;
+            }
+            if self.prefix {
+                if let Some(rest) = word.strip_prefix(rule.strip.as_str()) {
+                    out.push(format!("{}{rest}", rule.add));
+                }
+            } else if let Some(rest) = word.strip_suffix(rule.strip.as_str()) {
+                out.push(format!("{rest}{}", rule.add));
+            }
+        }
+        out
+    }
+}
+
+fn parse_affixes(aff: &str) -> Result<AffixTable, SpellcheckError> {
+    let mut table = AffixTable::default();
+    for line in aff.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            ["FLAG", mode, ..] => {
+                table.flag_mode = match *mode {
+                    "long" => FlagMode::Long,
+                    "num" => FlagMode::Num,
+                    _ => FlagMode::Char,
+                };
+            }
+            [kind @ ("PFX" | "SFX"), flag, cross, count] if count.parse::<usize>().is_ok() => {
+                table.groups.insert(
+                    (*flag).to_string(),
+                    AffixGroup {
+                        prefix: *kind == "PFX",
+                        cross_product: *cross == "Y",
+                        rules: Vec::new(),
+                    },
+                );
+            }
+            ["PFX" | "SFX", flag, strip, add, rest @ ..] => {
+                let group = table.groups.get_mut(*flag).ok_or_else(|| {
+                    SpellcheckError::InvalidDictionary(format!("affix rule before header: {flag}"))
+                })?;
+                let add = add.split('/').next().unwrap_or_default();
+                let condition = rest.first().copied().unwrap_or(".");
+                group.rules.push(AffixRule {
+                    strip: zero_as_empty(strip),
+                    add: zero_as_empty(add),
+                    condition: parse_condition(condition)?,
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(table)
+}
+
+fn zero_as_empty(value: &str) -> String {
+    if value == "0" {
+        String::new()
+    } else {
+        value.to_string()
+    }
+}
+
+fn parse_condition(condition: &str) -> Result<Vec<CharClass>, SpellcheckError> {
+    if condition == "." {
+        return Ok(Vec::new());
+    }
+    let mut out = Vec::new();
+    let mut chars = condition.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '.' => out.push(CharClass::Any),
+            '[' => {
+                let mut set = Vec::new();
+                let mut negated = false;
+                let mut closed = false;
+                for inner in chars.by_ref() {
+                    match inner {
+                        ']' => {
+                            closed = true;
+                            break;
+                        }
+                        '^' if set.is_empty() && !negated => negated = true,
+                        _ => set.push(inner),
+                    }
+                }
+                if !closed {
+                    return Err(SpellcheckError::InvalidDictionary(format!(
+                        "unterminated affix condition: {condition}"
+                    )));
+                }
+                out.push(CharClass::Set {
+                    chars: set,
+                    negated,
+                });
+            }
+            _ => out.push(CharClass::Literal(ch)),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AFF: &str = "SET UTF-8\n\
+        SFX S Y 2\n\
+        SFX S   y     ies        [^aeiou]y\n\
+        SFX S   0     s          [^y]\n\
+        PFX U Y 1\n\
+        PFX U   0     un         .\n";
+    const DIC: &str = "3\nsword/S\nberry/S\nlock/US\n";
+
+    #[test]
+    fn t_spell_001_hunspell_affix_expansion() {
+        let checker = SpellChecker::from_hunspell(DIC, Some(AFF)).expect("load dictionary");
+        for word in [
+            "sword", "swords", "berries", "unlock", "unlocks", "Sword", "LOCK",
+        ] {
+            assert!(checker.is_correct(word), "{word} should be accepted");
+        }
+        for word in ["berrys", "unsword", "swrod"] {
+            assert!(!checker.is_correct(word), "{word} should be rejected");
+        }
+    }
+
+    #[test]
+    fn t_spell_002_tokenize_skips_markup_and_ignored_words() {
+        let mut checker = SpellChecker::from_hunspell(DIC, Some(AFF)).expect("load dictionary");
+        let text = "Swrod of <Alias=Dovah> %s {0} 剣 Lokc";
+        let words: Vec<String> = checker
+            .misspelled_words(text)
+            .into_iter()
+            .map(|span| span.word)
+            .collect();
+        assert_eq!(words, vec!["Swrod", "of", "Lokc"]);
+
+        checker.set_ignored_words(["of", "lokc"]);
+        let spans = checker.misspelled_words(text);
+        assert_eq!(spans.len(), 1);
+        assert_eq!(&text[spans[0].start..spans[0].end], "Swrod");
+    }
+}
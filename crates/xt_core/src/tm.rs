@@ -23,6 +23,38 @@ impl TranslationMemory {
     }
 }
 
+/// Texts longer than this (in chars, multiplied) are scored by position
+/// instead of edit distance.
+const EDIT_DISTANCE_LIMIT: usize = 4_000_000;
+
+/// Similarity of `a` and `b` in percent: 100 minus their character edit
+/// distance relative to the longer text. Identical texts score 100.
+pub fn similarity_percent(a: &str, b: &str) -> u8 {
+    if a == b {
+        return 100;
+    }
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    let longest = a.len().max(b.len());
+    let distance = if a.len() * b.len() > EDIT_DISTANCE_LIMIT {
+        longest - a.iter().zip(&b).filter(|(x, y)| x == y).count()
+    } else {
+        let mut previous = (0..=b.len()).collect::<Vec<_>>();
+        let mut current = vec![0; b.len() + 1];
+        for (i, x) in a.iter().enumerate() {
+            current[0] = i + 1;
+            for (j, y) in b.iter().enumerate() {
+                let substitute = previous[j] + usize::from(x != y);
+                current[j + 1] = substitute.min(previous[j + 1] + 1).min(current[j] + 1);
+            }
+            std::mem::swap(&mut previous, &mut current);
+        }
+        previous[b.len()]
+    };
+    // Texts that differ never round up to 100.
+    ((longest - distance) * 100 / longest).min(99) as u8
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -30,25 +62,32 @@ mod tests {
 
     #[test]
     fn t_tm_001_strings_exact_match() {
-        let file = StringsFile {
-            entries: vec![
-                StringsEntry {
-                    id: 1,
-                    text: "Hello".to_string(),
-                },
-                StringsEntry {
-                    id: 2,
-                    text: "Hello".to_string(),
-                },
-                StringsEntry {
-                    id: 3,
-                    text: "World".to_string(),
-                },
-            ],
-        };
+        let file = StringsFile::new(vec![
+            StringsEntry {
+                id: 1,
+                text: "Hello".to_string(),
+            },
+            StringsEntry {
+                id: 2,
+                text: "Hello".to_string(),
+            },
+            StringsEntry {
+                id: 3,
+                text: "World".to_string(),
+            },
+        ]);
         let tm = TranslationMemory::from_strings(&file);
         let matches = tm.exact_match("Hello").expect("match");
         assert_eq!(matches.len(), 2);
         assert!(tm.exact_match("Missing").is_none());
     }
+
+    #[test]
+    fn t_tm_002_similarity_percent() {
+        assert_eq!(similarity_percent("Iron Sword", "Iron Sword"), 100);
+        assert_eq!(similarity_percent("iron sword", "Iron Sword"), 80);
+        assert_eq!(similarity_percent("ｶﾞ", "ガ"), 0);
+        assert_eq!(similarity_percent("", "x"), 0);
+        assert_eq!(similarity_percent("abcd", "abce"), 75);
+    }
 }
@@ -44,10 +44,8 @@ impl TwoPaneState {
 
     pub fn update_entry(&mut self, key: &str, source: &str, target: &str) -> bool {
         if let Some(entry) = self.entries.iter_mut().find(|entry| entry.key == key) {
-            entry.source_text.clear();
-            entry.source_text.push_str(source);
-            entry.target_text.clear();
-            entry.target_text.push_str(target);
+            entry.source_text = source.into();
+            entry.target_text = target.into();
             return true;
         }
         false
@@ -94,13 +92,13 @@ mod tests {
         let entries = vec![
             Entry {
                 key: "k1".to_string(),
-                source_text: "Hello".to_string(),
-                target_text: "こんにちは".to_string(),
+                source_text: "Hello".into(),
+                target_text: "こんにちは".into(),
             },
             Entry {
                 key: "k2".to_string(),
-                source_text: "World".to_string(),
-                target_text: "世界".to_string(),
+                source_text: "World".into(),
+                target_text: "世界".into(),
             },
         ];
         let mut state = TwoPaneState::new(entries);
@@ -116,13 +114,13 @@ mod tests {
         let entries = vec![
             Entry {
                 key: "k1".to_string(),
-                source_text: "Hello".to_string(),
-                target_text: "こんにちは".to_string(),
+                source_text: "Hello".into(),
+                target_text: "こんにちは".into(),
             },
             Entry {
                 key: "k2".to_string(),
-                source_text: "World".to_string(),
-                target_text: "世界".to_string(),
+                source_text: "World".into(),
+                target_text: "世界".into(),
             },
         ];
         let mut state = TwoPaneState::new(entries);
@@ -136,8 +134,8 @@ mod tests {
     fn t_ui_001_update_entry_changes_data() {
         let entries = vec![Entry {
             key: "k1".to_string(),
-            source_text: "Hello".to_string(),
-            target_text: "こんにちは".to_string(),
+            source_text: "Hello".into(),
+            target_text: "こんにちは".into(),
         }];
         let mut state = TwoPaneState::new(entries);
         assert!(state.update_entry("k1", "Hi", "やあ"));
@@ -150,15 +148,15 @@ mod tests {
     fn t_ui_001_set_entries_resets_selection_when_missing() {
         let entries = vec![Entry {
             key: "k1".to_string(),
-            source_text: "Hello".to_string(),
-            target_text: "こんにちは".to_string(),
+            source_text: "Hello".into(),
+            target_text: "こんにちは".into(),
         }];
         let mut state = TwoPaneState::new(entries);
         assert!(state.select("k1"));
         state.set_entries(vec![Entry {
             key: "k2".to_string(),
-            source_text: "World".to_string(),
-            target_text: "世界".to_string(),
+            source_text: "World".into(),
+            target_text: "世界".into(),
         }]);
         assert!(state.selected_entry().is_none());
     }
@@ -1,12 +1,39 @@
+use std::mem;
+
+use crate::model::{Entry, SharedText};
+
+/// A recorded change that can be undone and redone. Commands hold the other
+/// side of the change and swap it with the target, so each call flips them.
+pub trait Command<T> {
+    fn undo(&mut self, target: &mut T);
+    fn redo(&mut self, target: &mut T);
+}
+
+/// Commands derived from the value before and after a change.
+pub trait Diff<T>: Command<T> + Sized {
+    /// `None` when `before` and `after` are equal. The command is recorded
+    /// with `after` already applied.
+    fn diff(before: &T, after: &T) -> Option<Self>;
+}
+
+/// Undo/redo over a value as a log of [`Command`]s rather than snapshots.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct UndoStack<T> {
-    past: Vec<T>,
+pub struct UndoStack<T, C = Replace<T>> {
+    past: Vec<C>,
     present: T,
-    future: Vec<T>,
+    future: Vec<C>,
 }
 
-impl<T: Clone + PartialEq> UndoStack<T> {
+impl<T> UndoStack<T> {
+    /// Stack that records whole previous values ([`Replace`]).
     pub fn new(initial: T) -> Self {
+        Self::with_commands(initial)
+    }
+}
+
+impl<T, C: Command<T>> UndoStack<T, C> {
+    /// Stack that records `C` commands, e.g. [`EntriesDelta`] for entry lists.
+    pub fn with_commands(initial: T) -> Self {
         Self {
             past: Vec::new(),
             present: initial,
@@ -18,19 +45,17 @@ impl<T: Clone + PartialEq> UndoStack<T> {
         &self.present
     }
 
-    pub fn apply(&mut self, next: T) {
-        if next == self.present {
-            return;
-        }
-        self.past.push(self.present.clone());
-        self.present = next;
+    /// Runs `command` on the present value and records it.
+    pub fn execute(&mut self, mut command: C) {
+        command.redo(&mut self.present);
+        self.past.push(command);
         self.future.clear();
     }
 
     pub fn undo(&mut self) -> bool {
-        if let Some(prev) = self.past.pop() {
-            self.future.push(self.present.clone());
-            self.present = prev;
+        if let Some(mut command) = self.past.pop() {
+            command.undo(&mut self.present);
+            self.future.push(command);
             true
         } else {
             false
@@ -38,9 +63,9 @@ impl<T: Clone + PartialEq> UndoStack<T> {
     }
 
     pub fn redo(&mut self) -> bool {
-        if let Some(next) = self.future.pop() {
-            self.past.push(self.present.clone());
-            self.present = next;
+        if let Some(mut command) = self.future.pop() {
+            command.redo(&mut self.present);
+            self.past.push(command);
             true
         } else {
             false
@@ -48,6 +73,111 @@ impl<T: Clone + PartialEq> UndoStack<T> {
     }
 }
 
+impl<T, C: Diff<T>> UndoStack<T, C> {
+    /// Replaces the present value, recording only what changed.
+    pub fn apply(&mut self, next: T) {
+        let Some(command) = C::diff(&self.present, &next) else {
+            return;
+        };
+        self.present = next;
+        self.past.push(command);
+        self.future.clear();
+    }
+}
+
+/// Swaps the whole value; the fallback for types without finer commands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replace<T> {
+    other: T,
+}
+
+impl<T> Command<T> for Replace<T> {
+    fn undo(&mut self, target: &mut T) {
+        mem::swap(&mut self.other, target);
+    }
+
+    fn redo(&mut self, target: &mut T) {
+        mem::swap(&mut self.other, target);
+    }
+}
+
+impl<T: Clone + PartialEq> Diff<T> for Replace<T> {
+    fn diff(before: &T, after: &T) -> Option<Self> {
+        (before != after).then(|| Self {
+            other: before.clone(),
+        })
+    }
+}
+
+/// Change to an entry list: the texts of the rows that changed, or the whole
+/// list when rows were added, removed or rekeyed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntriesDelta {
+    Rows(Vec<RowTexts>),
+    Replace(Vec<Entry>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowTexts {
+    pub index: usize,
+    pub source_text: SharedText,
+    pub target_text: SharedText,
+}
+
+impl EntriesDelta {
+    /// Rows the change touches.
+    pub fn rows(&self) -> usize {
+        match self {
+            EntriesDelta::Rows(rows) => rows.len(),
+            EntriesDelta::Replace(entries) => entries.len(),
+        }
+    }
+
+    fn swap(&mut self, entries: &mut Vec<Entry>) {
+        match self {
+            EntriesDelta::Rows(rows) => {
+                for row in rows {
+                    if let Some(entry) = entries.get_mut(row.index) {
+                        mem::swap(&mut entry.source_text, &mut row.source_text);
+                        mem::swap(&mut entry.target_text, &mut row.target_text);
+                    }
+                }
+            }
+            EntriesDelta::Replace(other) => mem::swap(other, entries),
+        }
+    }
+}
+
+impl Command<Vec<Entry>> for EntriesDelta {
+    fn undo(&mut self, target: &mut Vec<Entry>) {
+        self.swap(target);
+    }
+
+    fn redo(&mut self, target: &mut Vec<Entry>) {
+        self.swap(target);
+    }
+}
+
+impl Diff<Vec<Entry>> for EntriesDelta {
+    fn diff(before: &Vec<Entry>, after: &Vec<Entry>) -> Option<Self> {
+        if before.len() != after.len() || before.iter().zip(after).any(|(a, b)| a.key != b.key) {
+            return Some(EntriesDelta::Replace(before.clone()));
+        }
+        let rows = before
+            .iter()
+            .zip(after)
+            .enumerate()
+            .filter(|(_, (a, b))| a.source_text != b.source_text || a.target_text != b.target_text)
+            .map(|(index, (a, _))| RowTexts {
+                index,
+                source_text: a.source_text.clone(),
+                target_text: a.target_text.clone(),
+            })
+            .collect::<Vec<_>>();
+        (!rows.is_empty()).then_some(EntriesDelta::Rows(rows))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -74,4 +204,39 @@ mod tests {
         assert!(stack.undo());
         assert_eq!(stack.present(), &vec!["a".to_string(), "b".to_string()]);
     }
+
+    #[test]
+    fn t_undo_003_entries_delta_records_changed_rows_only() {
+        let entries = (0..1000)
+            .map(|i| Entry {
+                key: format!("k{i}"),
+                source_text: format!("Line {i}").into(),
+                target_text: Default::default(),
+            })
+            .collect::<Vec<_>>();
+        let mut stack = UndoStack::<_, EntriesDelta>::with_commands(entries.clone());
+
+        let mut next = entries.clone();
+        next[3].target_text = "訳3".into();
+        next[7].target_text = "訳7".into();
+        stack.apply(next.clone());
+        stack.apply(next.clone());
+        assert_eq!(stack.past.len(), 1, "unchanged apply is not recorded");
+        assert_eq!(stack.past[0].rows(), 2);
+
+        let mut shorter = next.clone();
+        shorter.pop();
+        stack.apply(shorter);
+        assert!(matches!(stack.past[1], EntriesDelta::Replace(_)));
+
+        assert!(stack.undo());
+        assert_eq!(stack.present(), &next);
+        assert!(stack.undo());
+        assert_eq!(stack.present(), &entries);
+        assert!(!stack.undo());
+        assert!(stack.redo());
+        assert_eq!(stack.present()[7].target_text, "訳7");
+        assert!(stack.redo());
+        assert_eq!(stack.present().len(), 999);
+    }
 }
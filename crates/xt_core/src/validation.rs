@@ -1,11 +1,50 @@
-#[derive(Debug, Clone, PartialEq, Eq)]
-#[allow(dead_code)]
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::placeholders::{extract, PlaceholderKind};
+use crate::spellcheck::SpellChecker;
+
+pub const RULE_BRACED_PLACEHOLDER: &str = "placeholder.braced.mismatch";
+pub const RULE_PRINTF_PLACEHOLDER: &str = "placeholder.printf.mismatch";
+pub const RULE_ALIAS_TAG: &str = "alias.tag.mismatch";
+pub const RULE_SPELLCHECK: &str = "spellcheck";
+/// The same source text translated differently elsewhere in the document.
+pub const RULE_CONSISTENCY: &str = "consistency.target";
+pub const BUILTIN_RULES: [&str; 5] = [
+    RULE_BRACED_PLACEHOLDER,
+    RULE_PRINTF_PLACEHOLDER,
+    RULE_ALIAS_TAG,
+    RULE_SPELLCHECK,
+    RULE_CONSISTENCY,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Severity {
     Info,
     Warn,
     Error,
 }
 
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warn => "warn",
+            Severity::Error => "error",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "info" => Some(Severity::Info),
+            "warn" | "warning" => Some(Severity::Warn),
+            "error" => Some(Severity::Error),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ValidationIssue {
     pub entry_key: String,
@@ -19,7 +58,7 @@ impl ValidationIssue {
         Self {
             entry_key: entry_key.to_string(),
             severity: Severity::Error,
-            rule_id: "placeholder.braced.mismatch".to_string(),
+            rule_id: RULE_BRACED_PLACEHOLDER.to_string(),
             message: "Braced placeholders do not match between source and target.".to_string(),
         }
     }
@@ -28,7 +67,7 @@ impl ValidationIssue {
         Self {
             entry_key: entry_key.to_string(),
             severity: Severity::Error,
-            rule_id: "placeholder.printf.mismatch".to_string(),
+            rule_id: RULE_PRINTF_PLACEHOLDER.to_string(),
             message: "Printf-style placeholders do not match between source and target."
                 .to_string(),
         }
@@ -38,7 +77,7 @@ impl ValidationIssue {
         Self {
             entry_key: entry_key.to_string(),
             severity: Severity::Error,
-            rule_id: "alias.tag.mismatch".to_string(),
+            rule_id: RULE_ALIAS_TAG.to_string(),
             message: "Alias tags do not match between source and target.".to_string(),
         }
     }
@@ -49,8 +88,8 @@ pub fn validate_braced_placeholders(
     source_text: &str,
     target_text: &str,
 ) -> Vec<ValidationIssue> {
-    let mut source = extract_braced_placeholders(source_text);
-    let mut target = extract_braced_placeholders(target_text);
+    let mut source = extract(source_text, PlaceholderKind::Braced);
+    let mut target = extract(target_text, PlaceholderKind::Braced);
     source.sort();
     target.sort();
 
@@ -66,8 +105,8 @@ pub fn validate_printf_placeholders(
     source_text: &str,
     target_text: &str,
 ) -> Vec<ValidationIssue> {
-    let mut source = extract_printf_placeholders(source_text);
-    let mut target = extract_printf_placeholders(target_text);
+    let mut source = extract(source_text, PlaceholderKind::Printf);
+    let mut target = extract(target_text, PlaceholderKind::Printf);
     source.sort();
     target.sort();
 
@@ -83,8 +122,8 @@ pub fn validate_alias_tags(
     source_text: &str,
     target_text: &str,
 ) -> Vec<ValidationIssue> {
-    let mut source = extract_alias_tags(source_text);
-    let mut target = extract_alias_tags(target_text);
+    let mut source = extract(source_text, PlaceholderKind::Alias);
+    let mut target = extract(target_text, PlaceholderKind::Alias);
     source.sort();
     target.sort();
 
@@ -95,67 +134,264 @@ pub fn validate_alias_tags(
     }
 }
 
-fn extract_braced_placeholders(text: &str) -> Vec<String> {
-    let bytes = text.as_bytes();
-    let mut placeholders = Vec::new();
-    let mut i = 0;
-    while i < bytes.len() {
-        if bytes[i] == b'{' {
-            let start = i + 1;
-            let mut j = start;
-            while j < bytes.len() && bytes[j].is_ascii_digit() {
-                j += 1;
-            }
-            if j > start && j < bytes.len() && bytes[j] == b'}' {
-                if let Ok(token) = std::str::from_utf8(&bytes[i..=j]) {
-                    placeholders.push(token.to_string());
-                }
-                i = j + 1;
-                continue;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleSetting {
+    pub rule_id: String,
+    pub enabled: bool,
+    pub severity: Severity,
+}
+
+/// User-defined rule that reports an issue when the target text matches `pattern`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomRegexRule {
+    pub rule_id: String,
+    pub enabled: bool,
+    pub severity: Severity,
+    pub pattern: String,
+    pub message: String,
+}
+
+/// What saving should do when the document still has error-level issues.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SaveGuard {
+    #[default]
+    Off,
+    Warn,
+    Refuse,
+}
+
+impl SaveGuard {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SaveGuard::Off => "off",
+            SaveGuard::Warn => "warn",
+            SaveGuard::Refuse => "refuse",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "off" => Some(SaveGuard::Off),
+            "warn" => Some(SaveGuard::Warn),
+            "refuse" => Some(SaveGuard::Refuse),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationConfig {
+    pub rules: Vec<RuleSetting>,
+    pub custom_rules: Vec<CustomRegexRule>,
+    pub save_guard: SaveGuard,
+    /// Hunspell `.dic` used by the `spellcheck` rule; empty disables it.
+    pub spell_dictionary: String,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self {
+            rules: BUILTIN_RULES
+                .iter()
+                .map(|rule_id| RuleSetting {
+                    rule_id: (*rule_id).to_string(),
+                    enabled: true,
+                    severity: if matches!(*rule_id, RULE_SPELLCHECK | RULE_CONSISTENCY) {
+                        Severity::Warn
+                    } else {
+                        Severity::Error
+                    },
+                })
+                .collect(),
+            custom_rules: Vec::new(),
+            save_guard: SaveGuard::Off,
+            spell_dictionary: String::new(),
+        }
+    }
+}
+
+impl ValidationConfig {
+    pub fn rule(&self, rule_id: &str) -> Option<&RuleSetting> {
+        self.rules.iter().find(|rule| rule.rule_id == rule_id)
+    }
+
+    pub fn rule_mut(&mut self, rule_id: &str) -> Option<&mut RuleSetting> {
+        self.rules.iter_mut().find(|rule| rule.rule_id == rule_id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ValidationConfigError {
+    #[error("invalid pattern in rule {rule_id}: {message}")]
+    InvalidPattern { rule_id: String, message: String },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ValidationSummary {
+    pub errors: usize,
+    pub warnings: usize,
+    pub infos: usize,
+}
+
+impl ValidationSummary {
+    pub fn from_issues(issues: &[ValidationIssue]) -> Self {
+        let mut summary = Self::default();
+        for issue in issues {
+            match issue.severity {
+                Severity::Error => summary.errors += 1,
+                Severity::Warn => summary.warnings += 1,
+                Severity::Info => summary.infos += 1,
             }
         }
-        i += 1;
+        summary
     }
-    placeholders
 }
 
-fn extract_printf_placeholders(text: &str) -> Vec<String> {
-    let bytes = text.as_bytes();
-    let mut placeholders = Vec::new();
-    let mut i = 0;
-    while i + 1 < bytes.len() {
-        if bytes[i] == b'%' {
-            let next = bytes[i + 1];
-            if next == b'%' {
-                i += 2;
+type BuiltinCheck = fn(&str, &str, &str) -> Vec<ValidationIssue>;
+
+/// Validation rules compiled from a [`ValidationConfig`].
+#[derive(Debug, Clone)]
+pub struct Validator {
+    config: ValidationConfig,
+    custom: Vec<(CustomRegexRule, Regex)>,
+    spellchecker: Option<Arc<SpellChecker>>,
+}
+
+impl Validator {
+    pub fn new(config: &ValidationConfig) -> Result<Self, ValidationConfigError> {
+        let mut custom = Vec::new();
+        for rule in &config.custom_rules {
+            let regex =
+                Regex::new(&rule.pattern).map_err(|err| ValidationConfigError::InvalidPattern {
+                    rule_id: rule.rule_id.clone(),
+                    message: err.to_string(),
+                })?;
+            custom.push((rule.clone(), regex));
+        }
+        Ok(Self {
+            config: config.clone(),
+            custom,
+            spellchecker: None,
+        })
+    }
+
+    /// Enables the `spellcheck` rule with an already loaded dictionary.
+    pub fn with_spellchecker(mut self, checker: Arc<SpellChecker>) -> Self {
+        self.spellchecker = Some(checker);
+        self
+    }
+
+    pub fn config(&self) -> &ValidationConfig {
+        &self.config
+    }
+
+    pub fn validate(
+        &self,
+        entry_key: &str,
+        source_text: &str,
+        target_text: &str,
+    ) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        let builtin: [(&str, BuiltinCheck); 3] = [
+            (RULE_BRACED_PLACEHOLDER, validate_braced_placeholders),
+            (RULE_PRINTF_PLACEHOLDER, validate_printf_placeholders),
+            (RULE_ALIAS_TAG, validate_alias_tags),
+        ];
+        for (rule_id, check) in builtin {
+            let (enabled, severity) = match self.config.rule(rule_id) {
+                Some(setting) => (setting.enabled, setting.severity),
+                None => (true, Severity::Error),
+            };
+            if !enabled {
                 continue;
             }
-            if next == b's' || next == b'd' {
-                if let Ok(token) = std::str::from_utf8(&bytes[i..=i + 1]) {
-                    placeholders.push(token.to_string());
+            issues.extend(
+                check(entry_key, source_text, target_text)
+                    .into_iter()
+                    .map(|issue| ValidationIssue { severity, ..issue }),
+            );
+        }
+        if let Some(checker) = &self.spellchecker {
+            let setting = self.config.rule(RULE_SPELLCHECK);
+            if setting.is_none_or(|setting| setting.enabled) {
+                let misspelled = checker.misspelled_words(target_text);
+                if !misspelled.is_empty() {
+                    let words: Vec<&str> =
+                        misspelled.iter().map(|span| span.word.as_str()).collect();
+                    issues.push(ValidationIssue {
+                        entry_key: entry_key.to_string(),
+                        severity: setting.map_or(Severity::Warn, |setting| setting.severity),
+                        rule_id: RULE_SPELLCHECK.to_string(),
+                        message: format!("Possibly misspelled: {}", words.join(", ")),
+                    });
                 }
-                i += 2;
-                continue;
             }
         }
-        i += 1;
+        for (rule, regex) in &self.custom {
+            if rule.enabled && regex.is_match(target_text) {
+                issues.push(ValidationIssue {
+                    entry_key: entry_key.to_string(),
+                    severity: rule.severity,
+                    rule_id: rule.rule_id.clone(),
+                    message: rule.message.clone(),
+                });
+            }
+        }
+        issues
+    }
+
+    /// Validates every translated entry; rows with an empty target are skipped.
+    /// Also reports rows whose source has other targets in `entries`.
+    pub fn validate_entries<'a, I>(&self, entries: I) -> Vec<ValidationIssue>
+    where
+        I: IntoIterator<Item = (&'a str, &'a str, &'a str)>,
+    {
+        let translated = entries
+            .into_iter()
+            .filter(|(_, _, target)| !target.is_empty())
+            .collect::<Vec<_>>();
+        let mut issues = translated
+            .iter()
+            .flat_map(|&(key, source, target)| self.validate(key, source, target))
+            .collect::<Vec<_>>();
+        let setting = self.config.rule(RULE_CONSISTENCY);
+        if setting.is_none_or(|setting| setting.enabled) {
+            let severity = setting.map_or(Severity::Warn, |setting| setting.severity);
+            issues.extend(inconsistent_targets(&translated, severity));
+        }
+        issues
     }
-    placeholders
 }
 
-fn extract_alias_tags(text: &str) -> Vec<String> {
-    let mut tags = Vec::new();
-    let mut rest = text;
-    while let Some(start) = rest.find("<Alias=") {
-        rest = &rest[start + 7..];
-        let end = match rest.find('>') {
-            Some(end) => end,
-            None => break,
-        };
-        tags.push(rest[..end].to_string());
-        rest = &rest[end + 1..];
+/// One issue per translated row whose source has more than one distinct
+/// target among `translated`.
+fn inconsistent_targets(
+    translated: &[(&str, &str, &str)],
+    severity: Severity,
+) -> Vec<ValidationIssue> {
+    let mut targets: HashMap<&str, Vec<&str>> = HashMap::new();
+    for &(_, source, target) in translated {
+        let seen = targets.entry(source).or_default();
+        if !seen.contains(&target) {
+            seen.push(target);
+        }
     }
-    tags
+    translated
+        .iter()
+        .filter_map(|&(key, source, _)| {
+            let seen = &targets[source];
+            (seen.len() > 1).then(|| ValidationIssue {
+                entry_key: key.to_string(),
+                severity,
+                rule_id: RULE_CONSISTENCY.to_string(),
+                message: format!(
+                    "Same source is translated {} ways: {}",
+                    seen.len(),
+                    seen.join(" / ")
+                ),
+            })
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -205,4 +441,99 @@ mod tests {
         );
         assert!(issues.is_empty());
     }
+
+    #[test]
+    fn t_val_cfg_001_disabled_rule_and_severity_override() {
+        let mut config = ValidationConfig::default();
+        config.rule_mut(RULE_BRACED_PLACEHOLDER).unwrap().enabled = false;
+        config.rule_mut(RULE_PRINTF_PLACEHOLDER).unwrap().severity = Severity::Warn;
+        let validator = Validator::new(&config).expect("compile");
+
+        let issues = validator.validate("entry:7", "Hello {0} %s", "こんにちは");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].rule_id, RULE_PRINTF_PLACEHOLDER);
+        assert_eq!(issues[0].severity, Severity::Warn);
+    }
+
+    #[test]
+    fn t_val_cfg_002_custom_regex_rule() {
+        let mut config = ValidationConfig::default();
+        config.custom_rules.push(CustomRegexRule {
+            rule_id: "custom.double-space".to_string(),
+            enabled: true,
+            severity: Severity::Warn,
+            pattern: "  ".to_string(),
+            message: "Double space.".to_string(),
+        });
+        let validator = Validator::new(&config).expect("compile");
+
+        let issues =
+            validator.validate_entries([("k1", "A", "a  b"), ("k2", "B", "b"), ("k3", "C", "")]);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].entry_key, "k1");
+        let summary = ValidationSummary::from_issues(&issues);
+        assert_eq!(summary.warnings, 1);
+        assert_eq!(summary.errors, 0);
+
+        config.custom_rules[0].pattern = "(".to_string();
+        assert!(matches!(
+            Validator::new(&config),
+            Err(ValidationConfigError::InvalidPattern { .. })
+        ));
+    }
+
+    #[test]
+    fn t_val_cfg_003_spellcheck_rule() {
+        let checker = SpellChecker::from_hunspell("2\nIron\nsword\n", None).expect("dictionary");
+        let mut config = ValidationConfig::default();
+        let validator = Validator::new(&config)
+            .expect("validator")
+            .with_spellchecker(Arc::new(checker.clone()));
+        let issues = validator.validate("k", "Iron Sword", "Iron Swrod");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].rule_id, RULE_SPELLCHECK);
+        assert_eq!(issues[0].severity, Severity::Warn);
+        assert!(issues[0].message.contains("Swrod"));
+
+        config.rule_mut(RULE_SPELLCHECK).expect("rule").enabled = false;
+        let validator = Validator::new(&config)
+            .expect("validator")
+            .with_spellchecker(Arc::new(checker));
+        assert!(validator
+            .validate("k", "Iron Sword", "Iron Swrod")
+            .is_empty());
+    }
+
+    #[test]
+    fn t_val_cfg_004_same_source_translated_differently() {
+        let mut config = ValidationConfig::default();
+        let rows = [
+            ("k1", "Gold", "金"),
+            ("k2", "Gold", "ゴールド"),
+            ("k3", "Gold", ""),
+            ("k4", "Iron", "鉄"),
+            ("k5", "Iron", "鉄"),
+            ("k6", "Gold", "金"),
+        ];
+        let issues = Validator::new(&config)
+            .expect("validator")
+            .validate_entries(rows);
+        let keys = issues
+            .iter()
+            .map(|issue| issue.entry_key.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, ["k1", "k2", "k6"]);
+        assert_eq!(issues[0].rule_id, RULE_CONSISTENCY);
+        assert_eq!(issues[0].severity, Severity::Warn);
+        assert_eq!(
+            issues[0].message,
+            "Same source is translated 2 ways: 金 / ゴールド"
+        );
+
+        config.rule_mut(RULE_CONSISTENCY).expect("rule").enabled = false;
+        assert!(Validator::new(&config)
+            .expect("validator")
+            .validate_entries(rows)
+            .is_empty());
+    }
 }
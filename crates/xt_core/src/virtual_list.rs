@@ -10,6 +10,10 @@ impl VirtualWindow {
     pub fn len(&self) -> usize {
         self.end.saturating_sub(self.start)
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 pub fn virtual_window(
@@ -1,3 +1,4 @@
+#[cfg(not(target_arch = "wasm32"))]
 use std::path::Path;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -31,7 +32,19 @@ pub struct Workspace {
 }
 
 impl Workspace {
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn save_to_path(&self, path: &Path) -> Result<(), WorkspaceError> {
+        crate::formats::esp::write_atomic(path, self.to_text()).map_err(WorkspaceError::Io)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_from_path(path: &Path) -> Result<Self, WorkspaceError> {
+        let content = std::fs::read_to_string(path).map_err(WorkspaceError::Io)?;
+        Self::from_text(&content)
+    }
+
+    /// The `key=value` lines [`Self::save_to_path`] writes.
+    pub fn to_text(&self) -> String {
         let mut lines = Vec::new();
         lines.push("version=1".to_string());
         lines.push(format!("name={}", escape_value(&self.name)));
@@ -47,12 +60,11 @@ impl Workspace {
             lines.push(format!("cache_dir={}", escape_value(cache_dir)));
         }
         lines.push(format!("cache_policy={}", self.cache_policy.as_str()));
-        let content = lines.join("\n");
-        std::fs::write(path, content).map_err(WorkspaceError::Io)
+        lines.join("\n")
     }
 
-    pub fn load_from_path(path: &Path) -> Result<Self, WorkspaceError> {
-        let content = std::fs::read_to_string(path).map_err(WorkspaceError::Io)?;
+    /// Parses the text of a workspace file.
+    pub fn from_text(content: &str) -> Result<Self, WorkspaceError> {
         let mut version: Option<u32> = None;
         let mut name: Option<String> = None;
         let mut game: Option<Game> = None;
@@ -133,15 +145,22 @@ impl CachePolicy {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, thiserror::Error)]
 #[allow(dead_code)]
 pub enum WorkspaceError {
-    Io(std::io::Error),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid workspace format")]
     InvalidFormat,
+    #[error("missing field: {0}")]
     MissingField(&'static str),
+    #[error("unsupported workspace version: {0}")]
     UnsupportedVersion(u32),
+    #[error("unknown game")]
     UnknownGame,
+    #[error("unknown cache policy")]
     UnknownCachePolicy,
+    #[error("invalid escape sequence")]
     InvalidEscape,
 }
 
@@ -0,0 +1,170 @@
+//! Strings and plugin hot paths: `cargo bench -p xt_esp`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::Write;
+use xt_esp::{
+    apply_translations_to_bytes, extract_strings_from_bytes, read_dlstrings, write_dlstrings_to,
+    write_dlstrings_with, CancelToken, GameProfile, NoProgress, PluginStrings, StringsEntry,
+    StringsFile, WriteOptions,
+};
+
+const STRINGS: u32 = 50_000;
+const RECORDS: u32 = 20_000;
+
+fn strings_file() -> StringsFile {
+    StringsFile::new(
+        (1..=STRINGS)
+            .map(|id| StringsEntry {
+                id,
+                text: format!("Item description {id}: lorem ipsum dolor sit amet"),
+            })
+            .collect(),
+    )
+}
+
+fn subrecord(out: &mut Vec<u8>, tag: &[u8; 4], text: &str) {
+    out.extend_from_slice(tag);
+    out.extend_from_slice(&(text.len() as u16 + 1).to_le_bytes());
+    out.extend_from_slice(text.as_bytes());
+    out.push(0);
+}
+
+fn record(out: &mut Vec<u8>, tag: &[u8; 4], form_id: u32, flags: u32, data: &[u8]) {
+    out.extend_from_slice(tag);
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(&flags.to_le_bytes());
+    out.extend_from_slice(&form_id.to_le_bytes());
+    out.extend_from_slice(&[0u8; 8]);
+    out.extend_from_slice(data);
+}
+
+/// A plugin with one `BOOK` group; every fourth record is compressed.
+fn plugin() -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut header = Vec::new();
+    header.extend_from_slice(b"HEDR");
+    header.extend_from_slice(&12u16.to_le_bytes());
+    header.extend_from_slice(&[0u8; 12]);
+    record(&mut bytes, b"TES4", 0, 0, &header);
+
+    let mut records = Vec::new();
+    for i in 0..RECORDS {
+        let mut data = Vec::new();
+        subrecord(&mut data, b"EDID", &format!("Book{i:05}"));
+        subrecord(&mut data, b"FULL", &format!("Book title {i}"));
+        subrecord(
+            &mut data,
+            b"DESC",
+            &format!("Book {i} text: lorem ipsum dolor sit amet, consectetur adipiscing"),
+        );
+        if i % 4 == 0 {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&data).expect("compress");
+            let mut compressed = (data.len() as u32).to_le_bytes().to_vec();
+            compressed.extend(encoder.finish().expect("compress"));
+            record(
+                &mut records,
+                b"BOOK",
+                0x0100_0000 + i,
+                0x0004_0000,
+                &compressed,
+            );
+        } else {
+            record(&mut records, b"BOOK", 0x0100_0000 + i, 0, &data);
+        }
+    }
+    bytes.extend_from_slice(b"GRUP");
+    bytes.extend_from_slice(&(24 + records.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(b"BOOK");
+    bytes.extend_from_slice(&[0u8; 12]);
+    bytes.extend(records);
+    bytes
+}
+
+fn bench_strings(c: &mut Criterion) {
+    let file = strings_file();
+    let compact = WriteOptions::default();
+    let preserve = WriteOptions {
+        preserve_layout: true,
+    };
+    let bytes = write_dlstrings_with(&file, &compact).expect("write");
+    let mut edited = read_dlstrings(&bytes).expect("read");
+    for entry in edited.entries.iter_mut().step_by(10) {
+        entry.text.push_str(" (edited)");
+    }
+
+    let mut group = c.benchmark_group("strings");
+    group.throughput(Throughput::Bytes(bytes.len() as u64));
+    group.bench_function("read_dlstrings", |b| {
+        b.iter(|| read_dlstrings(black_box(&bytes)).expect("read"))
+    });
+    group.bench_function("write_dlstrings/compact", |b| {
+        b.iter(|| write_dlstrings_with(black_box(&edited), &compact).expect("write"))
+    });
+    group.bench_function("write_dlstrings/preserve_layout", |b| {
+        b.iter(|| write_dlstrings_with(black_box(&edited), &preserve).expect("write"))
+    });
+    group.bench_function("write_dlstrings_to/sink", |b| {
+        b.iter(|| {
+            write_dlstrings_to(black_box(&edited), &preserve, &mut std::io::sink()).expect("write")
+        })
+    });
+    group.finish();
+}
+
+fn bench_plugin(c: &mut Criterion) {
+    let bytes = plugin();
+    let strings = PluginStrings::default();
+    let extracted = extract_strings_from_bytes(
+        &bytes,
+        &strings,
+        GameProfile::SkyrimSe,
+        &CancelToken::new(),
+        &NoProgress,
+    )
+    .expect("extract");
+    let translations = extracted
+        .iter()
+        .cloned()
+        .map(|mut string| {
+            string.text = format!("訳 {}", string.text);
+            string
+        })
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("plugin");
+    group.throughput(Throughput::Bytes(bytes.len() as u64));
+    group.bench_function("extract_strings_from_bytes", |b| {
+        b.iter(|| {
+            extract_strings_from_bytes(
+                black_box(&bytes),
+                &strings,
+                GameProfile::SkyrimSe,
+                &CancelToken::new(),
+                &NoProgress,
+            )
+            .expect("extract")
+        })
+    });
+    group.bench_function("apply_translations_to_bytes", |b| {
+        b.iter_batched(
+            || (PluginStrings::default(), translations.clone()),
+            |(mut strings, translations)| {
+                apply_translations_to_bytes(
+                    black_box(&bytes),
+                    &mut strings,
+                    translations,
+                    GameProfile::SkyrimSe,
+                )
+                .expect("apply")
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_strings, bench_plugin);
+criterion_main!(benches);
@@ -0,0 +1,60 @@
+//! Plugin parsing on arbitrary bytes: `cargo fuzz run plugin`.
+//!
+//! Whatever extracts must also re-emit, and the re-emitted plugin must
+//! extract to the same strings.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use xt_esp::{
+    apply_translations_to_bytes, extract_strings_from_bytes, parse_plugin_info, CancelToken,
+    ExtractedString, GameProfile, NoProgress, PluginStrings, PluginTree, TreeNode,
+};
+
+fn extract(bytes: &[u8]) -> Option<Vec<ExtractedString>> {
+    extract_strings_from_bytes(
+        bytes,
+        &PluginStrings::default(),
+        GameProfile::SkyrimSe,
+        &CancelToken::new(),
+        &NoProgress,
+    )
+    .ok()
+}
+
+fuzz_target!(|bytes: &[u8]| {
+    let _ = parse_plugin_info(bytes);
+
+    if let Ok(tree) = PluginTree::new(bytes) {
+        let mut pending = tree.roots().iter().collect::<Vec<_>>();
+        while let Some(node) = pending.pop() {
+            match node {
+                TreeNode::Group(group) => {
+                    if let Ok(children) = tree.children(group) {
+                        pending.extend(children);
+                    }
+                }
+                TreeNode::Record(record) => {
+                    let _ = tree.subrecords(record);
+                }
+            }
+        }
+    }
+
+    let Some(strings) = extract(bytes) else {
+        return;
+    };
+    let Ok(written) = apply_translations_to_bytes(
+        bytes,
+        &mut PluginStrings::default(),
+        Vec::new(),
+        GameProfile::SkyrimSe,
+    ) else {
+        return;
+    };
+    assert_eq!(
+        extract(&written),
+        Some(strings),
+        "re-emitted plugin differs"
+    );
+});
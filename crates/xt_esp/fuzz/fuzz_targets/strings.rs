@@ -0,0 +1,51 @@
+//! Strings parsing on arbitrary bytes: `cargo fuzz run strings`.
+//!
+//! A file that reads must write back byte for byte, and survive an edit in
+//! either layout.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use xt_esp::{
+    read_dlstrings, read_ilstrings, read_strings, write_dlstrings_with, write_ilstrings_with,
+    write_strings_with, StringsError, StringsFile, WriteOptions,
+};
+
+type Read = fn(&[u8]) -> Result<StringsFile, StringsError>;
+type WriteWith = fn(&StringsFile, &WriteOptions) -> Result<Vec<u8>, StringsError>;
+
+const FORMATS: [(Read, WriteWith); 3] = [
+    (read_strings, write_strings_with),
+    (read_dlstrings, write_dlstrings_with),
+    (read_ilstrings, write_ilstrings_with),
+];
+
+fuzz_target!(|bytes: &[u8]| {
+    for (read, write) in FORMATS {
+        let Ok(mut file) = read(bytes) else {
+            continue;
+        };
+        if let Some(info) = file.info() {
+            let _ = info.report();
+        }
+        let unchanged = write(&file, &WriteOptions::default()).expect("unchanged file writes");
+        assert_eq!(unchanged, bytes, "unchanged file differs");
+
+        let Some(first) = file.entries.first_mut() else {
+            continue;
+        };
+        first.text.push_str(" (edited)");
+        for preserve_layout in [false, true] {
+            let options = WriteOptions { preserve_layout };
+            let Ok(written) = write(&file, &options) else {
+                continue;
+            };
+            let decoded = read(&written).expect("written file reads");
+            let mut expected = file.entries.clone();
+            if !preserve_layout {
+                expected.sort_by_key(|entry| entry.id);
+            }
+            assert_eq!(decoded.entries, expected, "edited file differs");
+        }
+    }
+});
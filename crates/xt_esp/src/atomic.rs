@@ -0,0 +1,139 @@
+//! Crash-safe file replacement: write a temporary file next to the target,
+//! check it, then rename it over the target.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, File, OpenOptions};
+use std::hash::Hasher;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NEXT_TEMP: AtomicU64 = AtomicU64::new(0);
+
+/// Writes `bytes` to `path` so that `path` holds either its old contents or
+/// all of `bytes`, never a partial write. The temporary file is read back
+/// and compared by size and hash before it replaces `path`; an existing
+/// file's permissions carry over.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    write_atomic_with(path, |out| out.write_all(contents.as_ref()))
+}
+
+/// [`write_atomic`] for contents that `write` streams into the temporary
+/// file, so large outputs are never held in memory as a whole. The file is
+/// checked against the size and hash of what was streamed.
+pub fn write_atomic_with(
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<()> {
+    let temp = temp_path(path);
+    let result = write_and_replace(path, &temp, write);
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+fn write_and_replace(
+    path: &Path,
+    temp: &Path,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<()> {
+    let file = OpenOptions::new().write(true).create_new(true).open(temp)?;
+    let mut out = HashingWriter::new(BufWriter::new(file));
+    write(&mut out)?;
+    let (expected_len, expected_hash) = (out.len, out.hasher.finish());
+    let file = out
+        .inner
+        .into_inner()
+        .map_err(io::IntoInnerError::into_error)?;
+    file.sync_all()?;
+    drop(file);
+
+    let mut written = HashingWriter::new(io::sink());
+    io::copy(&mut File::open(temp)?, &mut written)?;
+    if written.len != expected_len || written.hasher.finish() != expected_hash {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "temporary file {} does not match the data written ({} of {} bytes)",
+                temp.display(),
+                written.len,
+                expected_len
+            ),
+        ));
+    }
+
+    if let Ok(metadata) = fs::metadata(path) {
+        fs::set_permissions(temp, metadata.permissions())?;
+    }
+    fs::rename(temp, path)
+}
+
+/// Passes writes on to `inner`, counting and hashing the bytes accepted.
+struct HashingWriter<W> {
+    inner: W,
+    hasher: DefaultHasher,
+    len: u64,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: DefaultHasher::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.write(&buf[..written]);
+        self.len += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// `.name.xt-tmp-<pid>-<n>` in the target's folder, so the rename never
+/// crosses file systems.
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let n = NEXT_TEMP.fetch_add(1, Ordering::Relaxed);
+    path.with_file_name(format!(".{name}.xt-tmp-{}-{n}", std::process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_esp_atomic_001_replaces_file_and_leaves_no_temp() {
+        let root = std::env::temp_dir().join(format!("xt_esp_atomic_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).expect("create");
+        let path = root.join("mod.esp");
+
+        write_atomic(&path, b"first").expect("create");
+        assert_eq!(fs::read(&path).expect("read"), b"first");
+        write_atomic(&path, b"second, longer").expect("replace");
+        assert_eq!(fs::read(&path).expect("read"), b"second, longer");
+        let names = fs::read_dir(&root)
+            .expect("list")
+            .map(|entry| entry.expect("entry").file_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["mod.esp"], "temporary files are renamed away");
+
+        let missing = root.join("missing").join("mod.esp");
+        assert!(write_atomic(&missing, b"x").is_err());
+        assert!(!missing.exists());
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,37 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Shared flag a long-running job polls to stop early. Clones observe the
+/// same flag, so the UI keeps one and the worker thread another.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    canceled: Arc<AtomicBool>,
+}
+
+/// Returned by work that stopped because its [`CancelToken`] was canceled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("canceled")]
+pub struct Canceled;
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.canceled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_canceled(&self) -> bool {
+        self.canceled.load(Ordering::Relaxed)
+    }
+
+    /// `Err(Canceled)` once [`CancelToken::cancel`] has been called.
+    pub fn check(&self) -> Result<(), Canceled> {
+        if self.is_canceled() {
+            Err(Canceled)
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -1,63 +1,150 @@
+use crate::cancel::{CancelToken, Canceled};
+use crate::profile::GameProfile;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::progress::NoProgress;
+use crate::progress::ProgressSink;
+use crate::strings::{read_dlstrings, read_ilstrings, read_strings, StringsError, StringsFile};
+#[cfg(not(target_arch = "wasm32"))]
 use crate::strings::{
-    read_dlstrings, read_ilstrings, read_strings, write_dlstrings, write_ilstrings, write_strings,
-    StringsFile,
+    write_dlstrings_to, write_ilstrings_to, write_strings_to, StringsEntry, WriteOptions,
 };
 use flate2::read::ZlibDecoder;
 use flate2::write::ZlibEncoder;
 use flate2::Compression;
 use std::collections::HashMap;
 use std::fmt;
-use std::io::{Read, Write};
+use std::io::{self, Read, Write};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
+mod tree;
+
+pub use tree::{PluginTree, TreeGroup, TreeNode, TreeRecord};
+
 pub type EspResult<T> = Result<T, EspError>;
 
 const RECORD_HEADER_SIZE: usize = 24;
 const GROUP_HEADER_SIZE: usize = 24;
 const RECORD_COMPRESSED: u32 = 0x0004_0000;
+/// Deepest group nesting accepted; real plugins nest a handful of levels, so
+/// anything deeper is corrupt and would only exhaust the stack.
+const MAX_GROUP_DEPTH: usize = 64;
+
+// TES4 record flags.
+pub const PLUGIN_FLAG_MASTER: u32 = 0x0000_0001;
+pub const PLUGIN_FLAG_LOCALIZED: u32 = 0x0000_0080;
+pub const PLUGIN_FLAG_LIGHT: u32 = 0x0000_0200;
+
+/// Record header flags shared by most record types, with their names.
+const RECORD_FLAG_NAMES: [(u32, &str); 6] = [
+    (0x0000_0020, "Deleted"),
+    (0x0000_0400, "Persistent"),
+    (0x0000_0800, "Initially Disabled"),
+    (0x0000_1000, "Ignored"),
+    (0x0000_8000, "Visible When Distant"),
+    (RECORD_COMPRESSED, "Compressed"),
+];
+
+/// Names of the [`RECORD_FLAG_NAMES`] set in `flags`, lowest bit first.
+pub fn record_flag_names(flags: u32) -> Vec<&'static str> {
+    RECORD_FLAG_NAMES
+        .iter()
+        .filter(|(bit, _)| flags & bit != 0)
+        .map(|(_, name)| *name)
+        .collect()
+}
 
-#[derive(Debug)]
+/// Errors from plugin parsing and Strings handling. Context variants wrap the
+/// underlying error (see [`std::error::Error::source`]) and repeat its message
+/// after the file, record or string key they apply to.
+#[derive(Debug, thiserror::Error)]
 pub enum EspError {
-    Io(std::io::Error),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    /// I/O failure on a known file.
+    #[error("{action} {}: {source}", path.display())]
+    File {
+        action: &'static str,
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid header")]
     InvalidHeader,
-    InvalidRecord,
-    InvalidGroup,
-    InvalidSubrecord,
+    /// Truncated or oversized record header at byte `offset` of the plugin.
+    #[error("invalid record at offset 0x{offset:X}")]
+    InvalidRecord { offset: usize },
+    /// Truncated or oversized group at byte `offset` of the plugin.
+    #[error("invalid group at offset 0x{offset:X}")]
+    InvalidGroup { offset: usize },
+    /// Malformed subrecord at byte `offset` of the (decompressed) record data.
+    #[error("invalid subrecord at data offset 0x{offset:X}")]
+    InvalidSubrecord { offset: usize },
+    #[error("invalid compressed record data: {0}")]
+    Decompress(#[source] std::io::Error),
+    #[error("invalid utf-8")]
     InvalidUtf8,
+    #[error("missing strings file: {0}")]
     MissingStringsFile(StringsKind),
+    #[error("missing string id: {0}")]
     MissingStringId(u32),
+    #[error("invalid strings path")]
     InvalidStringsPath,
+    /// No free string id is left when localizing.
+    #[error("string ids exhausted")]
+    StringIdOverflow,
+    /// A Strings file that could not be parsed or written.
+    #[error("strings file {}: {source}", path.display())]
+    StringsFile {
+        path: PathBuf,
+        #[source]
+        source: StringsError,
+    },
+    /// A localized plugin has no Strings files for `requested`.
+    #[error(
+        "no strings files for language '{requested}' ({})",
+        found_languages(available)
+    )]
+    MissingLanguage {
+        requested: String,
+        available: Vec<String>,
+    },
+    /// Failure while parsing the record that starts at byte `offset`.
+    #[error("{record_type} {form_id:08X} at offset 0x{offset:X}: {source}")]
+    Record {
+        record_type: String,
+        form_id: u32,
+        offset: usize,
+        #[source]
+        source: Box<EspError>,
+    },
+    /// The job's [`CancelToken`] was canceled.
+    #[error("canceled")]
+    Canceled(#[from] Canceled),
+    /// Failure while applying the string with unique key `key`.
+    #[error("{key}: {source}")]
+    Entry {
+        key: String,
+        #[source]
+        source: Box<EspError>,
+    },
 }
 
-impl From<std::io::Error> for EspError {
-    fn from(err: std::io::Error) -> Self {
-        EspError::Io(err)
-    }
-}
-
-impl fmt::Display for EspError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            EspError::Io(err) => write!(f, "io error: {err}"),
-            EspError::InvalidHeader => write!(f, "invalid header"),
-            EspError::InvalidRecord => write!(f, "invalid record"),
-            EspError::InvalidGroup => write!(f, "invalid group"),
-            EspError::InvalidSubrecord => write!(f, "invalid subrecord"),
-            EspError::InvalidUtf8 => write!(f, "invalid utf-8"),
-            EspError::MissingStringsFile(kind) => write!(f, "missing strings file: {kind}"),
-            EspError::MissingStringId(id) => write!(f, "missing string id: {id}"),
-            EspError::InvalidStringsPath => write!(f, "invalid strings path"),
-        }
+fn found_languages(available: &[String]) -> String {
+    if available.is_empty() {
+        "none found".to_string()
+    } else {
+        format!("found: {}", available.join(", "))
     }
 }
 
-impl std::error::Error for EspError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        match self {
-            EspError::Io(err) => Some(err),
-            _ => None,
-        }
-    }
+#[cfg(not(target_arch = "wasm32"))]
+fn read_file(path: &Path) -> EspResult<Vec<u8>> {
+    std::fs::read(path).map_err(|source| EspError::File {
+        action: "read",
+        path: path.to_path_buf(),
+        source,
+    })
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -98,9 +185,45 @@ pub struct ExtractedString {
     pub index: usize,
     pub text: String,
     pub storage: StringStorage,
+    /// EDID of the record the string belongs to, if it has one.
+    pub editor_id: Option<String>,
+    /// Header flags of that record.
+    pub record_flags: u32,
 }
 
 impl ExtractedString {
+    pub fn new(
+        record_type: [u8; 4],
+        subrecord_type: [u8; 4],
+        form_id: u32,
+        index: usize,
+        text: String,
+        storage: StringStorage,
+    ) -> Self {
+        let key = string_key(record_type, form_id, subrecord_type, index);
+        Self {
+            key,
+            record_type,
+            subrecord_type,
+            form_id,
+            index,
+            text,
+            storage,
+            editor_id: None,
+            record_flags: 0,
+        }
+    }
+
+    pub fn with_editor_id(mut self, editor_id: Option<String>) -> Self {
+        self.editor_id = editor_id;
+        self
+    }
+
+    pub fn with_record_flags(mut self, flags: u32) -> Self {
+        self.record_flags = flags;
+        self
+    }
+
     pub fn get_unique_key(&self) -> String {
         self.key.clone()
     }
@@ -139,53 +262,402 @@ enum Block {
     Group(Group),
 }
 
+/// One subrecord of a record, with its payload decompressed.
 #[derive(Debug, Clone)]
-struct Subrecord {
+pub struct Subrecord {
     sub_type: [u8; 4],
     data: Vec<u8>,
+    /// For a subrecord read after an `XXXX` marker, its own size field, so
+    /// it is written back the same way until its data is replaced.
+    extended: Option<u16>,
+}
+
+impl Subrecord {
+    pub fn sub_type(&self) -> [u8; 4] {
+        self.sub_type
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Whether the subrecord is written after an `XXXX` marker: always when
+    /// its data does not fit a 16-bit size, and as read otherwise.
+    fn is_extended(&self) -> bool {
+        self.data.len() > u16::MAX as usize || self.extended.is_some()
+    }
+
+    fn set_data(&mut self, data: Vec<u8>) {
+        self.data = data;
+        self.extended = None;
+    }
+}
+
+/// A plugin's Strings files of one language, held in memory.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PluginStrings {
+    pub strings: Option<StringsFile>,
+    pub dlstrings: Option<StringsFile>,
+    pub ilstrings: Option<StringsFile>,
+}
+
+impl PluginStrings {
+    /// Parses whichever of the three files are given.
+    pub fn from_bytes(
+        strings: Option<&[u8]>,
+        dlstrings: Option<&[u8]>,
+        ilstrings: Option<&[u8]>,
+    ) -> Result<Self, StringsError> {
+        Ok(Self {
+            strings: strings.map(read_strings).transpose()?,
+            dlstrings: dlstrings.map(read_dlstrings).transpose()?,
+            ilstrings: ilstrings.map(read_ilstrings).transpose()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_none() && self.dlstrings.is_none() && self.ilstrings.is_none()
+    }
+
+    pub fn get(&self, kind: StringsKind) -> Option<&StringsFile> {
+        match kind {
+            StringsKind::Strings => self.strings.as_ref(),
+            StringsKind::DlStrings => self.dlstrings.as_ref(),
+            StringsKind::IlStrings => self.ilstrings.as_ref(),
+        }
+    }
+
+    pub fn get_mut(&mut self, kind: StringsKind) -> Option<&mut StringsFile> {
+        match kind {
+            StringsKind::Strings => self.strings.as_mut(),
+            StringsKind::DlStrings => self.dlstrings.as_mut(),
+            StringsKind::IlStrings => self.ilstrings.as_mut(),
+        }
+    }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 #[derive(Debug, Clone)]
 struct StringsBundle {
-    strings: Option<StringsFile>,
-    dlstrings: Option<StringsFile>,
-    ilstrings: Option<StringsFile>,
+    files: PluginStrings,
     base_name: String,
     language: String,
 }
 
-pub fn extract_strings(
+/// Where a plugin's Strings files are read from and written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringsLocation<'a> {
+    /// [`GameProfile::strings_dir`] of a game (or workspace) root.
+    Root(&'a Path),
+    /// Folders searched in order, as a mod manager's virtual `Data` folder
+    /// stacks them; Strings files are written to `output`.
+    Overlay {
+        search: &'a [PathBuf],
+        output: &'a Path,
+    },
+}
+
+impl StringsLocation<'_> {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn search_dirs(self, profile: GameProfile) -> Vec<PathBuf> {
+        match self {
+            StringsLocation::Root(root) => vec![profile.strings_dir(root)],
+            StringsLocation::Overlay { search, .. } => search.to_vec(),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn output_dir(self, profile: GameProfile) -> PathBuf {
+        match self {
+            StringsLocation::Root(root) => profile.strings_dir(root),
+            StringsLocation::Overlay { output, .. } => output.to_path_buf(),
+        }
+    }
+}
+
+impl<'a> From<&'a Path> for StringsLocation<'a> {
+    fn from(root: &'a Path) -> Self {
+        StringsLocation::Root(root)
+    }
+}
+
+impl<'a> From<&'a PathBuf> for StringsLocation<'a> {
+    fn from(root: &'a PathBuf) -> Self {
+        StringsLocation::Root(root)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn extract_strings<'a>(
     path: &Path,
-    workspace_root: &Path,
+    strings_location: impl Into<StringsLocation<'a>>,
     language: Option<&str>,
+    profile: GameProfile,
 ) -> EspResult<Vec<ExtractedString>> {
-    let bytes = std::fs::read(path)?;
-    let bundle = load_strings_bundle(path, workspace_root, language)?;
-    let strings_map = build_strings_map(&bundle);
-    let blocks = parse_plugin(&bytes)?;
+    extract_strings_cancelable(
+        path,
+        strings_location,
+        language,
+        profile,
+        &CancelToken::new(),
+        &NoProgress,
+    )
+}
+
+/// [`extract_strings`] that stops with [`EspError::Canceled`] once `cancel`
+/// is canceled (checked between Strings files and records) and reports the
+/// plugin's top-level groups as processed items to `progress`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn extract_strings_cancelable<'a>(
+    path: &Path,
+    strings_location: impl Into<StringsLocation<'a>>,
+    language: Option<&str>,
+    profile: GameProfile,
+    cancel: &CancelToken,
+    progress: &dyn ProgressSink,
+) -> EspResult<Vec<ExtractedString>> {
+    let strings_location = strings_location.into();
+    progress.set_current_file(&path.file_name().unwrap_or_default().to_string_lossy());
+    let bytes = read_file(path)?;
+    cancel.check()?;
+    let bundle = load_strings_bundle(path, strings_location, language, profile)?;
+    ensure_language_loaded(&bytes, &bundle, path, strings_location, profile)?;
+    extract_strings_from_bytes(&bytes, &bundle.files, profile, cancel, progress)
+}
+
+/// Strings extracted from a damaged plugin, with the errors of what was
+/// skipped to get them.
+#[derive(Debug, Default)]
+pub struct PartialExtraction {
+    /// Strings of every record that parsed, in extraction order.
+    pub strings: Vec<ExtractedString>,
+    /// One error per record that failed to parse, and per group or plugin
+    /// whose remaining blocks were skipped because a header was unreadable.
+    pub skipped: Vec<EspError>,
+}
+
+impl PartialExtraction {
+    /// True when nothing was skipped.
+    pub fn is_complete(&self) -> bool {
+        self.skipped.is_empty()
+    }
+}
+
+/// [`extract_strings_cancelable`] that skips records it cannot parse instead
+/// of failing: a broken record is left out, and a block header that cannot
+/// be read ends its group (or the plugin), since nothing after it can be
+/// located. Missing files and cancellation still fail.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn extract_strings_lenient<'a>(
+    path: &Path,
+    strings_location: impl Into<StringsLocation<'a>>,
+    language: Option<&str>,
+    profile: GameProfile,
+    cancel: &CancelToken,
+    progress: &dyn ProgressSink,
+) -> EspResult<PartialExtraction> {
+    let strings_location = strings_location.into();
+    progress.set_current_file(&path.file_name().unwrap_or_default().to_string_lossy());
+    let bytes = read_file(path)?;
+    cancel.check()?;
+    let bundle = load_strings_bundle(path, strings_location, language, profile)?;
+    ensure_language_loaded(&bytes, &bundle, path, strings_location, profile)?;
+    extract_strings_lenient_from_bytes(&bytes, &bundle.files, profile, cancel, progress)
+}
+
+/// [`extract_strings_cancelable`] on a plugin and its Strings files already
+/// in memory, e.g. picked in a browser; nothing touches the file system.
+/// A localized plugin needs its Strings files in `strings`.
+pub fn extract_strings_from_bytes(
+    bytes: &[u8],
+    strings: &PluginStrings,
+    profile: GameProfile,
+    cancel: &CancelToken,
+    progress: &dyn ProgressSink,
+) -> EspResult<Vec<ExtractedString>> {
+    extract_from_bytes(bytes, strings, profile, cancel, progress, None)
+}
+
+/// [`extract_strings_lenient`] on a plugin and its Strings files already in
+/// memory.
+pub fn extract_strings_lenient_from_bytes(
+    bytes: &[u8],
+    strings: &PluginStrings,
+    profile: GameProfile,
+    cancel: &CancelToken,
+    progress: &dyn ProgressSink,
+) -> EspResult<PartialExtraction> {
+    let mut skipped = Vec::new();
+    let strings = extract_from_bytes(
+        bytes,
+        strings,
+        profile,
+        cancel,
+        progress,
+        Some(&mut skipped),
+    )?;
+    Ok(PartialExtraction { strings, skipped })
+}
+
+/// Extracts strings, failing on the first parse error unless `skipped`
+/// collects them.
+fn extract_from_bytes(
+    bytes: &[u8],
+    strings: &PluginStrings,
+    profile: GameProfile,
+    cancel: &CancelToken,
+    progress: &dyn ProgressSink,
+    mut skipped: Option<&mut Vec<EspError>>,
+) -> EspResult<Vec<ExtractedString>> {
+    if strings.is_empty() && parse_plugin_info(bytes).is_ok_and(|info| info.is_localized()) {
+        return Err(EspError::MissingStringsFile(StringsKind::Strings));
+    }
+    cancel.check()?;
+    let strings_map = StringsMap::new(strings);
+    let roots = tree::scan_nodes_lenient(bytes, 0, bytes.len(), skipped.as_deref_mut())?;
+    progress.set_total(roots.len());
 
     let mut results = Vec::new();
-    let mut stack = Vec::new();
-    stack.extend(blocks.iter());
-    while let Some(block) = stack.pop() {
-        match block {
-            Block::Record(record) => collect_strings(record, &strings_map, &mut results),
-            Block::Group(group) => stack.extend(group.children.iter()),
-        }
+    // Last block first, each subtree depth-first; records are parsed one at a
+    // time so the plugin is never materialized as a whole.
+    for top in roots.into_iter().rev() {
+        tree::collect_branch(
+            bytes,
+            top,
+            &strings_map,
+            profile,
+            cancel,
+            &mut results,
+            skipped.as_deref_mut(),
+        )?;
+        progress.advance(1);
     }
     Ok(results)
 }
 
-pub fn apply_translations(
+/// Contents of a plugin's TES4 header record.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PluginInfo {
+    /// TES4 record flags (`PLUGIN_FLAG_*`).
+    pub flags: u32,
+    /// TES4 record header form version (Skyrim SE 44, Fallout 4 131).
+    pub form_version: u16,
+    /// HEDR version.
+    pub version: f32,
+    /// HEDR record count.
+    pub record_count: u32,
+    pub next_object_id: u32,
+    /// CNAM
+    pub author: String,
+    /// SNAM
+    pub description: String,
+    /// MAST, in load order.
+    pub masters: Vec<String>,
+}
+
+impl PluginInfo {
+    pub fn is_master(&self) -> bool {
+        self.flags & PLUGIN_FLAG_MASTER != 0
+    }
+
+    pub fn is_localized(&self) -> bool {
+        self.flags & PLUGIN_FLAG_LOCALIZED != 0
+    }
+
+    /// ESL flag in the header; `.esl` files are light regardless of it.
+    pub fn is_light(&self) -> bool {
+        self.flags & PLUGIN_FLAG_LIGHT != 0
+    }
+}
+
+/// Reads only the TES4 header of the plugin at `path`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_plugin_info(path: &Path) -> EspResult<PluginInfo> {
+    let bytes = read_file(path)?;
+    parse_plugin_info(&bytes)
+}
+
+pub fn parse_plugin_info(bytes: &[u8]) -> EspResult<PluginInfo> {
+    if read_tag(bytes, 0)? != *b"TES4" {
+        return Err(EspError::InvalidHeader);
+    }
+    let (header, _) = parse_record(bytes, 0)?;
+    let mut info = PluginInfo {
+        flags: header.header.flags,
+        form_version: header.header.version,
+        ..PluginInfo::default()
+    };
+    for sub in &header.subrecords {
+        match &sub.sub_type {
+            b"HEDR" if sub.data.len() >= 12 => {
+                info.version =
+                    f32::from_le_bytes([sub.data[0], sub.data[1], sub.data[2], sub.data[3]]);
+                info.record_count = read_u32(&sub.data, 4)?;
+                info.next_object_id = read_u32(&sub.data, 8)?;
+            }
+            b"CNAM" => info.author = zstring(&sub.data),
+            b"SNAM" => info.description = zstring(&sub.data),
+            b"MAST" => info.masters.push(zstring(&sub.data)),
+            _ => {}
+        }
+    }
+    Ok(info)
+}
+
+fn zstring(data: &[u8]) -> String {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).into_owned()
+}
+
+/// Returns the master files (`MAST`) listed in the plugin's TES4 header.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_masters(path: &Path) -> EspResult<Vec<String>> {
+    Ok(read_plugin_info(path)?.masters)
+}
+
+/// Writes the translated plugin to `output_path` and, for a localized plugin,
+/// the updated Strings files to the output folder of `strings_location`.
+/// Nothing else is written, so `output_path` may sit next to the input.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn apply_translations<'a>(
     input_path: &Path,
-    workspace_root: &Path,
-    output_dir: &Path,
+    strings_location: impl Into<StringsLocation<'a>>,
+    output_path: &Path,
     translations: Vec<ExtractedString>,
     language: Option<&str>,
-) -> EspResult<PathBuf> {
-    let bytes = std::fs::read(input_path)?;
-    let mut bundle = load_strings_bundle(input_path, workspace_root, language)?;
-    let mut blocks = parse_plugin(&bytes)?;
+    profile: GameProfile,
+) -> EspResult<()> {
+    let strings_location = strings_location.into();
+    let bytes = read_file(input_path)?;
+    let mut bundle = load_strings_bundle(input_path, strings_location, language, profile)?;
+    ensure_language_loaded(&bytes, &bundle, input_path, strings_location, profile)?;
+    let blocks = translate_blocks(&bytes, &mut bundle.files, translations, profile)?;
+    drop(bytes);
+    write_plugin_file(output_path, &blocks)?;
+    write_strings_bundle(&bundle, &strings_location.output_dir(profile))?;
+    Ok(())
+}
+
+/// [`apply_translations`] in memory: returns the translated plugin and
+/// updates the localized texts in `strings`, which the caller writes back
+/// (e.g. with [`crate::write_strings`]) wherever it keeps them.
+pub fn apply_translations_to_bytes(
+    bytes: &[u8],
+    strings: &mut PluginStrings,
+    translations: Vec<ExtractedString>,
+    profile: GameProfile,
+) -> EspResult<Vec<u8>> {
+    let blocks = translate_blocks(bytes, strings, translations, profile)?;
+    serialize_blocks(&blocks)
+}
+
+fn translate_blocks(
+    bytes: &[u8],
+    strings: &mut PluginStrings,
+    translations: Vec<ExtractedString>,
+    profile: GameProfile,
+) -> EspResult<Vec<Block>> {
+    let mut blocks = parse_plugin(bytes)?;
     let mut translation_map: HashMap<String, ExtractedString> = translations
         .into_iter()
         .map(|entry| (entry.get_unique_key(), entry))
@@ -194,44 +666,283 @@ pub fn apply_translations(
     let mut stack: Vec<&mut Block> = blocks.iter_mut().collect();
     while let Some(block) = stack.pop() {
         match block {
-            Block::Record(record) => apply_to_record(record, &mut bundle, &mut translation_map)?,
+            Block::Record(record) => {
+                apply_to_record(record, strings, profile, &mut translation_map)?
+            }
+            Block::Group(group) => stack.extend(group.children.iter_mut()),
+        }
+    }
+    Ok(blocks)
+}
+
+/// Outcome of [`localize_plugin`] / [`delocalize_plugin`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LocalizationReport {
+    /// String subrecords rewritten between inline text and a string id.
+    pub converted: usize,
+    /// String subrecords left untouched (not text, or an unknown id).
+    pub skipped: usize,
+    /// Strings files written by [`localize_plugin`].
+    pub strings_files: Vec<PathBuf>,
+}
+
+/// Writes `output_path` as a localized plugin.
+///
+/// Every FULL/DESC text moves to a freshly numbered Strings trio
+/// (`<output stem>_<strings_language>.strings/.dlstrings/.ilstrings`) in
+/// `strings_dir`, and the TES4 localized flag is set. The input may be inline
+/// or already localized (its files are read for `language`); `translations`
+/// replace texts by unique key. `strings_language` defaults to `language`.
+#[allow(clippy::too_many_arguments)]
+#[cfg(not(target_arch = "wasm32"))]
+pub fn localize_plugin<'a>(
+    input_path: &Path,
+    strings_location: impl Into<StringsLocation<'a>>,
+    output_path: &Path,
+    strings_dir: &Path,
+    translations: Vec<ExtractedString>,
+    language: Option<&str>,
+    strings_language: Option<&str>,
+    profile: GameProfile,
+) -> EspResult<LocalizationReport> {
+    let strings_location = strings_location.into();
+    let bytes = read_file(input_path)?;
+    let source = load_strings_bundle(input_path, strings_location, language, profile)?;
+    let strings_map = build_strings_map(&source);
+    let mut blocks = parse_plugin(&bytes)?;
+    set_plugin_flag(&mut blocks, PLUGIN_FLAG_LOCALIZED, true)?;
+    let texts = translation_texts(translations);
+
+    let mut bundle = StringsBundle {
+        files: PluginStrings {
+            strings: Some(StringsFile::default()),
+            dlstrings: Some(StringsFile::default()),
+            ilstrings: Some(StringsFile::default()),
+        },
+        base_name: output_path
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .ok_or(EspError::InvalidStringsPath)?
+            .to_string(),
+        language: strings_language
+            .map(|language| profile.strings_language(language))
+            .unwrap_or_else(|| source.language.clone()),
+    };
+    let mut next_id = 1u32;
+    let mut report = LocalizationReport::default();
+    visit_records(&mut blocks, &mut |record| {
+        let mut index = 0usize;
+        for subrecord in &mut record.subrecords {
+            if !profile.is_string_subrecord(&record.header.record_type, &subrecord.sub_type) {
+                continue;
+            }
+            if subrecord.data.is_empty() || subrecord.data == [0] {
+                // Id 0 is the empty string in localized plugins.
+                subrecord.set_data(0u32.to_le_bytes().to_vec());
+                report.converted += 1;
+                continue;
+            }
+            let Some((text, storage)) = decode_subrecord_string(&subrecord.data, &strings_map)
+            else {
+                report.skipped += 1;
+                continue;
+            };
+            let key = string_key(
+                record.header.record_type,
+                record.header.form_id,
+                subrecord.sub_type,
+                index,
+            );
+            index = index.saturating_add(1);
+            let kind = match storage {
+                StringStorage::Localized { kind, .. } => kind,
+                StringStorage::Inline => profile.strings_kind(&subrecord.sub_type),
+            };
+            let text = texts.get(&key).cloned().unwrap_or(text);
+            if let Some(file) = bundle.files.get_mut(kind) {
+                file.entries.push(StringsEntry { id: next_id, text });
+            }
+            subrecord.set_data(next_id.to_le_bytes().to_vec());
+            next_id = next_id.checked_add(1).ok_or(EspError::StringIdOverflow)?;
+            report.converted += 1;
+        }
+        Ok(())
+    })?;
+
+    write_plugin_file(output_path, &blocks)?;
+    report.strings_files = write_strings_bundle(&bundle, strings_dir)?;
+    Ok(report)
+}
+
+/// Writes `output_path` with every localized FULL/DESC string embedded as
+/// inline text and the TES4 localized flag cleared. Strings files are read
+/// from `strings_location` and left in place; `translations` replace texts by
+/// unique key.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn delocalize_plugin<'a>(
+    input_path: &Path,
+    strings_location: impl Into<StringsLocation<'a>>,
+    output_path: &Path,
+    translations: Vec<ExtractedString>,
+    language: Option<&str>,
+    profile: GameProfile,
+) -> EspResult<LocalizationReport> {
+    let strings_location = strings_location.into();
+    let bytes = read_file(input_path)?;
+    let bundle = load_strings_bundle(input_path, strings_location, language, profile)?;
+    ensure_language_loaded(&bytes, &bundle, input_path, strings_location, profile)?;
+    let mut blocks = parse_plugin(&bytes)?;
+    let localized = parse_plugin_info(&bytes).is_ok_and(|info| info.is_localized());
+    if localized && bundle.files.strings.is_none() && bundle.files.dlstrings.is_none() {
+        return Err(EspError::MissingStringsFile(StringsKind::Strings));
+    }
+    let strings_map = build_strings_map(&bundle);
+    set_plugin_flag(&mut blocks, PLUGIN_FLAG_LOCALIZED, false)?;
+    let texts = translation_texts(translations);
+
+    let mut report = LocalizationReport::default();
+    visit_records(&mut blocks, &mut |record| {
+        let mut index = 0usize;
+        for subrecord in &mut record.subrecords {
+            if !profile.is_string_subrecord(&record.header.record_type, &subrecord.sub_type) {
+                continue;
+            }
+            if subrecord.data == [0; 4] {
+                subrecord.set_data(vec![0]);
+                report.converted += 1;
+                continue;
+            }
+            let Some((text, storage)) = decode_subrecord_string(&subrecord.data, &strings_map)
+            else {
+                report.skipped += 1;
+                continue;
+            };
+            let key = string_key(
+                record.header.record_type,
+                record.header.form_id,
+                subrecord.sub_type,
+                index,
+            );
+            index = index.saturating_add(1);
+            let override_text = texts.get(&key);
+            match storage {
+                StringStorage::Localized { .. } => {
+                    let text = override_text.cloned().unwrap_or(text);
+                    subrecord.set_data(encode_string(&text, true));
+                    report.converted += 1;
+                }
+                StringStorage::Inline => {
+                    if let Some(text) = override_text {
+                        let null_terminated = subrecord.data.last().copied() == Some(0);
+                        subrecord.set_data(encode_string(text, null_terminated));
+                    }
+                }
+            }
+        }
+        Ok(())
+    })?;
+
+    write_plugin_file(output_path, &blocks)?;
+    Ok(report)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn translation_texts(translations: Vec<ExtractedString>) -> HashMap<String, String> {
+    translations
+        .into_iter()
+        .map(|entry| (entry.get_unique_key(), entry.text))
+        .collect()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn visit_records(
+    blocks: &mut [Block],
+    visit: &mut impl FnMut(&mut Record) -> EspResult<()>,
+) -> EspResult<()> {
+    let mut stack: Vec<&mut Block> = blocks.iter_mut().collect();
+    while let Some(block) = stack.pop() {
+        match block {
+            Block::Record(record) => visit(record)?,
             Block::Group(group) => stack.extend(group.children.iter_mut()),
         }
     }
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn set_plugin_flag(blocks: &mut [Block], flag: u32, enabled: bool) -> EspResult<()> {
+    match blocks.first_mut() {
+        Some(Block::Record(record)) if &record.header.record_type == b"TES4" => {
+            if enabled {
+                record.header.flags |= flag;
+            } else {
+                record.header.flags &= !flag;
+            }
+            Ok(())
+        }
+        _ => Err(EspError::InvalidHeader),
+    }
+}
+
+/// Streams `blocks` into `output_path`; only compressed records are
+/// assembled in memory, while their sizes are computed.
+#[cfg(not(target_arch = "wasm32"))]
+fn write_plugin_file(output_path: &Path, blocks: &[Block]) -> EspResult<()> {
+    let layout = BlockLayout::new(blocks)?;
+    if let Some(parent) = output_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    crate::write_atomic_with(output_path, |mut out| layout.write_to(blocks, &mut out)).map_err(
+        |source| EspError::File {
+            action: "write",
+            path: output_path.to_path_buf(),
+            source,
+        },
+    )
+}
 
-    let output_path = output_dir.join(input_path.file_name().ok_or(EspError::InvalidStringsPath)?);
-    let output_bytes = serialize_blocks(&blocks)?;
-    std::fs::create_dir_all(output_dir)?;
-    std::fs::write(&output_path, output_bytes)?;
-    write_strings_bundle(&bundle, workspace_root)?;
-    Ok(output_path)
+fn string_key(record_type: [u8; 4], form_id: u32, subrecord_type: [u8; 4], index: usize) -> String {
+    format!(
+        "{}:{:08X}:{}:{}",
+        tag_to_string(record_type),
+        form_id,
+        tag_to_string(subrecord_type),
+        index
+    )
 }
 
-fn collect_strings(record: &Record, strings_map: &StringsMap, results: &mut Vec<ExtractedString>) {
+fn collect_strings(
+    header: &RecordHeader,
+    subrecords: &[Subrecord],
+    strings_map: &StringsMap,
+    profile: GameProfile,
+    results: &mut Vec<ExtractedString>,
+) {
+    let editor_id = subrecords
+        .iter()
+        .find(|subrecord| &subrecord.sub_type == b"EDID")
+        .map(|subrecord| zstring(&subrecord.data))
+        .filter(|editor_id| !editor_id.is_empty());
     let mut index = 0usize;
-    for subrecord in &record.subrecords {
-        if !is_string_subrecord(&subrecord.sub_type) {
+    for subrecord in subrecords {
+        if !profile.is_string_subrecord(&header.record_type, &subrecord.sub_type) {
             continue;
         }
         if let Some((text, storage)) = decode_subrecord_string(&subrecord.data, strings_map) {
-            let record_type = record.header.record_type;
+            let record_type = header.record_type;
             let subrecord_type = subrecord.sub_type;
-            let key = format!(
-                "{}:{:08X}:{}:{}",
-                tag_to_string(record_type),
-                record.header.form_id,
-                tag_to_string(subrecord_type),
-                index
+            results.push(
+                ExtractedString::new(
+                    record_type,
+                    subrecord_type,
+                    header.form_id,
+                    index,
+                    text,
+                    storage,
+                )
+                .with_editor_id(editor_id.clone())
+                .with_record_flags(header.flags),
             );
-            results.push(ExtractedString {
-                key,
-                record_type,
-                subrecord_type,
-                form_id: record.header.form_id,
-                index,
-                text,
-                storage,
-            });
             index = index.saturating_add(1);
         }
     }
@@ -239,29 +950,34 @@ fn collect_strings(record: &Record, strings_map: &StringsMap, results: &mut Vec<
 
 fn apply_to_record(
     record: &mut Record,
-    bundle: &mut StringsBundle,
+    strings: &mut PluginStrings,
+    profile: GameProfile,
     translations: &mut HashMap<String, ExtractedString>,
 ) -> EspResult<()> {
     let mut index = 0usize;
     for subrecord in &mut record.subrecords {
-        if !is_string_subrecord(&subrecord.sub_type) {
+        if !profile.is_string_subrecord(&record.header.record_type, &subrecord.sub_type) {
             continue;
         }
-        let key = format!(
-            "{}:{:08X}:{}:{}",
-            tag_to_string(record.header.record_type),
+        let key = string_key(
+            record.header.record_type,
             record.header.form_id,
-            tag_to_string(subrecord.sub_type),
-            index
+            subrecord.sub_type,
+            index,
         );
         if let Some(updated) = translations.remove(&key) {
             match updated.storage {
                 StringStorage::Inline => {
                     let null_terminated = subrecord.data.last().copied() == Some(0);
-                    subrecord.data = encode_string(&updated.text, null_terminated);
+                    subrecord.set_data(encode_string(&updated.text, null_terminated));
                 }
                 StringStorage::Localized { kind, id } => {
-                    update_strings_bundle(bundle, kind, id, &updated.text)?;
+                    update_strings(strings, kind, id, &updated.text).map_err(|source| {
+                        EspError::Entry {
+                            key,
+                            source: Box::new(source),
+                        }
+                    })?;
                 }
             }
         }
@@ -276,7 +992,7 @@ fn parse_plugin(bytes: &[u8]) -> EspResult<Vec<Block>> {
     while offset < bytes.len() {
         let tag = read_tag(bytes, offset)?;
         if &tag == b"GRUP" {
-            let (group, next) = parse_group(bytes, offset)?;
+            let (group, next) = parse_group(bytes, offset, 0)?;
             blocks.push(Block::Group(group));
             offset = next;
         } else {
@@ -288,88 +1004,109 @@ fn parse_plugin(bytes: &[u8]) -> EspResult<Vec<Block>> {
     Ok(blocks)
 }
 
-fn parse_group(bytes: &[u8], offset: usize) -> EspResult<(Group, usize)> {
-    if offset + GROUP_HEADER_SIZE > bytes.len() {
-        return Err(EspError::InvalidGroup);
-    }
-    let size = read_u32(bytes, offset + 4)? as usize;
-    if size < GROUP_HEADER_SIZE || offset + size > bytes.len() {
-        return Err(EspError::InvalidGroup);
+/// Parses the group at `offset`, nested `depth` groups deep; its children
+/// must end within it.
+fn parse_group(bytes: &[u8], offset: usize, depth: usize) -> EspResult<(Group, usize)> {
+    if depth >= MAX_GROUP_DEPTH {
+        return Err(EspError::InvalidGroup { offset });
     }
-    let label = read_tag(bytes, offset + 8)?;
-    let group_type = read_u32(bytes, offset + 12)?;
-    let stamp = read_u32(bytes, offset + 16)?;
-    let unknown = read_u32(bytes, offset + 20)?;
-    let mut children = Vec::new();
+    let (mut group, end) = read_group_header(bytes, offset)?;
+    let inner = &bytes[..end];
     let mut cursor = offset + GROUP_HEADER_SIZE;
-    let end = offset + size;
     while cursor < end {
-        let tag = read_tag(bytes, cursor)?;
+        let tag = read_tag(inner, cursor)?;
         if &tag == b"GRUP" {
-            let (group, next) = parse_group(bytes, cursor)?;
-            children.push(Block::Group(group));
+            let (child, next) = parse_group(inner, cursor, depth + 1)?;
+            group.children.push(Block::Group(child));
             cursor = next;
         } else {
-            let (record, next) = parse_record(bytes, cursor)?;
-            children.push(Block::Record(record));
+            let (record, next) = parse_record(inner, cursor)?;
+            group.children.push(Block::Record(record));
             cursor = next;
         }
     }
+    Ok((group, end))
+}
+
+/// Reads the header of the group at `offset`; the returned group has no
+/// children yet. Also returns the end offset of the whole group.
+fn read_group_header(bytes: &[u8], offset: usize) -> EspResult<(Group, usize)> {
+    if offset + GROUP_HEADER_SIZE > bytes.len() {
+        return Err(EspError::InvalidGroup { offset });
+    }
+    let size = read_u32(bytes, offset + 4)? as usize;
+    if size < GROUP_HEADER_SIZE || offset + size > bytes.len() {
+        return Err(EspError::InvalidGroup { offset });
+    }
     Ok((
         Group {
-            label,
-            group_type,
-            stamp,
-            unknown,
-            children,
+            label: read_tag(bytes, offset + 8)?,
+            group_type: read_u32(bytes, offset + 12)?,
+            stamp: read_u32(bytes, offset + 16)?,
+            unknown: read_u32(bytes, offset + 20)?,
+            children: Vec::new(),
         },
-        end,
+        offset + size,
     ))
 }
 
 fn parse_record(bytes: &[u8], offset: usize) -> EspResult<(Record, usize)> {
+    let (header, data) = read_record_header(bytes, offset)?;
+    let subrecords = parse_record_data(&header, &bytes[data.clone()], offset)?;
+    Ok((
+        Record {
+            compressed: (header.flags & RECORD_COMPRESSED) != 0,
+            header,
+            subrecords,
+        },
+        data.end,
+    ))
+}
+
+/// Reads the header of the record at `offset` and returns it with the byte
+/// range of its stored (possibly compressed) data.
+fn read_record_header(bytes: &[u8], offset: usize) -> EspResult<(RecordHeader, Range<usize>)> {
     if offset + RECORD_HEADER_SIZE > bytes.len() {
-        return Err(EspError::InvalidRecord);
+        return Err(EspError::InvalidRecord { offset });
     }
-    let record_type = read_tag(bytes, offset)?;
     let data_size = read_u32(bytes, offset + 4)? as usize;
-    let flags = read_u32(bytes, offset + 8)?;
-    let form_id = read_u32(bytes, offset + 12)?;
-    let stamp = read_u16(bytes, offset + 16)?;
-    let version_control = read_u16(bytes, offset + 18)?;
-    let version = read_u16(bytes, offset + 20)?;
-    let unknown = read_u16(bytes, offset + 22)?;
+    let header = RecordHeader {
+        record_type: read_tag(bytes, offset)?,
+        flags: read_u32(bytes, offset + 8)?,
+        form_id: read_u32(bytes, offset + 12)?,
+        stamp: read_u16(bytes, offset + 16)?,
+        version_control: read_u16(bytes, offset + 18)?,
+        version: read_u16(bytes, offset + 20)?,
+        unknown: read_u16(bytes, offset + 22)?,
+    };
     let data_start = offset + RECORD_HEADER_SIZE;
     let data_end = data_start
         .checked_add(data_size)
-        .ok_or(EspError::InvalidRecord)?;
+        .ok_or(EspError::InvalidRecord { offset })?;
     if data_end > bytes.len() {
-        return Err(EspError::InvalidRecord);
+        return Err(EspError::InvalidRecord { offset });
     }
-    let stored_data = &bytes[data_start..data_end];
-    let compressed = (flags & RECORD_COMPRESSED) != 0;
-    let data = if compressed {
-        decompress_record_data(stored_data)?
+    Ok((header, data_start..data_end))
+}
+
+/// Decompresses and splits the stored data of the record at `offset`.
+fn parse_record_data(
+    header: &RecordHeader,
+    stored_data: &[u8],
+    offset: usize,
+) -> EspResult<Vec<Subrecord>> {
+    if (header.flags & RECORD_COMPRESSED) != 0 {
+        decompress_record_data(stored_data)
     } else {
-        stored_data.to_vec()
-    };
-    let subrecords = parse_subrecords(&data)?;
-    Ok((
-        Record {
-            header: RecordHeader {
-                record_type,
-                flags,
-                form_id,
-                stamp,
-                version_control,
-                version,
-                unknown,
-            },
-            subrecords,
-            compressed,
-        },
-        data_end,
-    ))
+        Ok(stored_data.to_vec())
+    }
+    .and_then(|data| parse_subrecords(&data))
+    .map_err(|source| EspError::Record {
+        record_type: tag_to_string(header.record_type),
+        form_id: header.form_id,
+        offset,
+        source: Box::new(source),
+    })
 }
 
 fn parse_subrecords(data: &[u8]) -> EspResult<Vec<Subrecord>> {
@@ -378,113 +1115,225 @@ fn parse_subrecords(data: &[u8]) -> EspResult<Vec<Subrecord>> {
     let mut extended_len: Option<u32> = None;
     while cursor + 6 <= data.len() {
         let sub_type = read_tag(data, cursor)?;
-        let len = read_u16(data, cursor + 4)? as usize;
+        let len = read_u16(data, cursor + 4)?;
         let payload_start = cursor + 6;
         if &sub_type == b"XXXX" {
-            if len != 4 || payload_start + 4 > data.len() {
-                return Err(EspError::InvalidSubrecord);
+            if len != 4 || payload_start + 4 > data.len() || extended_len.is_some() {
+                return Err(EspError::InvalidSubrecord { offset: cursor });
             }
             extended_len = Some(read_u32(data, payload_start)?);
-            cursor = payload_start + len;
+            cursor = payload_start + 4;
             continue;
         }
-        let actual_len = extended_len
-            .take()
-            .map(|value| value as usize)
-            .unwrap_or(len);
+        // After XXXX the subrecord's own size field is not its size.
+        let extended = extended_len.take();
+        let actual_len = extended.map(|value| value as usize).unwrap_or(len as usize);
         let payload_end = payload_start
             .checked_add(actual_len)
-            .ok_or(EspError::InvalidSubrecord)?;
+            .ok_or(EspError::InvalidSubrecord { offset: cursor })?;
         if payload_end > data.len() {
-            return Err(EspError::InvalidSubrecord);
+            return Err(EspError::InvalidSubrecord { offset: cursor });
         }
         let payload = data[payload_start..payload_end].to_vec();
         subrecords.push(Subrecord {
             sub_type,
             data: payload,
+            extended: extended.map(|_| len),
         });
         cursor = payload_end;
     }
+    // Bytes too short for a subrecord header, or an XXXX size with no
+    // subrecord after it, would be dropped on write.
+    if cursor < data.len() || extended_len.is_some() {
+        return Err(EspError::InvalidSubrecord { offset: cursor });
+    }
     Ok(subrecords)
 }
 
 fn serialize_blocks(blocks: &[Block]) -> EspResult<Vec<u8>> {
-    let mut out = Vec::new();
-    for block in blocks {
-        match block {
-            Block::Record(record) => out.extend_from_slice(&serialize_record(record)?),
-            Block::Group(group) => out.extend_from_slice(&serialize_group(group)?),
-        }
+    let layout = BlockLayout::new(blocks)?;
+    let mut out = Vec::with_capacity(layout.size);
+    if let Err(err) = layout.write_to(blocks, &mut out) {
+        unreachable!("writing to a Vec never fails: {err}");
     }
     Ok(out)
 }
 
-fn serialize_group(group: &Group) -> EspResult<Vec<u8>> {
-    let mut data = Vec::new();
-    for child in &group.children {
-        match child {
-            Block::Record(record) => data.extend_from_slice(&serialize_record(record)?),
-            Block::Group(nested) => data.extend_from_slice(&serialize_group(nested)?),
-        }
-    }
-    let size = (GROUP_HEADER_SIZE + data.len()) as u32;
-    let mut out = Vec::with_capacity(GROUP_HEADER_SIZE + data.len());
-    out.extend_from_slice(b"GRUP");
-    out.extend_from_slice(&size.to_le_bytes());
-    out.extend_from_slice(&group.label);
-    out.extend_from_slice(&group.group_type.to_le_bytes());
-    out.extend_from_slice(&group.stamp.to_le_bytes());
-    out.extend_from_slice(&group.unknown.to_le_bytes());
-    out.extend_from_slice(&data);
-    Ok(out)
+/// Writes `blocks` into `out` as [`serialize_blocks`] lays them out.
+#[cfg(test)]
+fn serialize_blocks_to<W: Write + ?Sized>(blocks: &[Block], out: &mut W) -> EspResult<()> {
+    BlockLayout::new(blocks)?.write_to(blocks, out)?;
+    Ok(())
 }
 
-fn serialize_record(record: &Record) -> EspResult<Vec<u8>> {
-    let mut data = serialize_subrecords(&record.subrecords);
-    if record.compressed {
-        let compressed = compress_record_data(&data)?;
-        data = compressed;
-    }
-    let data_size = data.len() as u32;
-    let mut out = Vec::with_capacity(RECORD_HEADER_SIZE + data.len());
-    out.extend_from_slice(&record.header.record_type);
-    out.extend_from_slice(&data_size.to_le_bytes());
-    out.extend_from_slice(&record.header.flags.to_le_bytes());
-    out.extend_from_slice(&record.header.form_id.to_le_bytes());
-    out.extend_from_slice(&record.header.stamp.to_le_bytes());
-    out.extend_from_slice(&record.header.version_control.to_le_bytes());
-    out.extend_from_slice(&record.header.version.to_le_bytes());
-    out.extend_from_slice(&record.header.unknown.to_le_bytes());
-    out.extend_from_slice(&data);
-    Ok(out)
+/// Sizes of a block tree, worked out before any of it is written so the
+/// output can be streamed with every group size already known.
+struct BlockLayout {
+    /// Size of each group including its header, in preorder.
+    group_sizes: Vec<u32>,
+    /// Stored data of each compressed record, in preorder.
+    compressed: Vec<Vec<u8>>,
+    /// Size of the whole output.
+    size: usize,
 }
 
-fn serialize_subrecords(subrecords: &[Subrecord]) -> Vec<u8> {
-    let mut out = Vec::new();
-    for subrecord in subrecords {
-        let len = subrecord.data.len();
-        if len > u16::MAX as usize {
-            out.extend_from_slice(b"XXXX");
-            out.extend_from_slice(&(4u16).to_le_bytes());
-            out.extend_from_slice(&(len as u32).to_le_bytes());
-            out.extend_from_slice(&subrecord.sub_type);
-            out.extend_from_slice(&0u16.to_le_bytes());
-        } else {
-            out.extend_from_slice(&subrecord.sub_type);
-            out.extend_from_slice(&(len as u16).to_le_bytes());
+impl BlockLayout {
+    fn new(blocks: &[Block]) -> EspResult<Self> {
+        let mut layout = Self {
+            group_sizes: Vec::new(),
+            compressed: Vec::new(),
+            size: 0,
+        };
+        for block in blocks {
+            layout.size += layout.measure(block)?;
         }
-        out.extend_from_slice(&subrecord.data);
+        Ok(layout)
     }
-    out
-}
 
-fn decompress_record_data(data: &[u8]) -> EspResult<Vec<u8>> {
-    if data.len() < 4 {
-        return Err(EspError::InvalidRecord);
+    fn measure(&mut self, block: &Block) -> EspResult<usize> {
+        match block {
+            Block::Record(record) if record.compressed => {
+                let data = compress_record_data(&serialize_subrecords(&record.subrecords))?;
+                let size = RECORD_HEADER_SIZE + data.len();
+                self.compressed.push(data);
+                Ok(size)
+            }
+            Block::Record(record) => Ok(RECORD_HEADER_SIZE + subrecords_size(&record.subrecords)),
+            Block::Group(group) => {
+                let slot = self.group_sizes.len();
+                self.group_sizes.push(0);
+                let mut size = GROUP_HEADER_SIZE;
+                for child in &group.children {
+                    size += self.measure(child)?;
+                }
+                self.group_sizes[slot] = size as u32;
+                Ok(size)
+            }
+        }
     }
-    let mut decoder = ZlibDecoder::new(&data[4..]);
+
+    fn write_to<W: Write + ?Sized>(&self, blocks: &[Block], out: &mut W) -> io::Result<()> {
+        let mut cursor = LayoutCursor {
+            group_sizes: self.group_sizes.iter(),
+            compressed: self.compressed.iter(),
+        };
+        for block in blocks {
+            cursor.write_block(block, out)?;
+        }
+        Ok(())
+    }
+}
+
+/// Position in a [`BlockLayout`] while its blocks are written in preorder.
+struct LayoutCursor<'a> {
+    group_sizes: std::slice::Iter<'a, u32>,
+    compressed: std::slice::Iter<'a, Vec<u8>>,
+}
+
+impl LayoutCursor<'_> {
+    fn write_block<W: Write + ?Sized>(&mut self, block: &Block, out: &mut W) -> io::Result<()> {
+        match block {
+            Block::Record(record) => {
+                let compressed = if record.compressed {
+                    Some(self.compressed.next().expect("measured compressed record"))
+                } else {
+                    None
+                };
+                let data_size = match compressed {
+                    Some(data) => data.len(),
+                    None => subrecords_size(&record.subrecords),
+                };
+                out.write_all(&record.header.record_type)?;
+                out.write_all(&(data_size as u32).to_le_bytes())?;
+                out.write_all(&record.header.flags.to_le_bytes())?;
+                out.write_all(&record.header.form_id.to_le_bytes())?;
+                out.write_all(&record.header.stamp.to_le_bytes())?;
+                out.write_all(&record.header.version_control.to_le_bytes())?;
+                out.write_all(&record.header.version.to_le_bytes())?;
+                out.write_all(&record.header.unknown.to_le_bytes())?;
+                match compressed {
+                    Some(data) => out.write_all(data),
+                    None => write_subrecords(&record.subrecords, out),
+                }
+            }
+            Block::Group(group) => {
+                let size = self.group_sizes.next().expect("measured group");
+                out.write_all(b"GRUP")?;
+                out.write_all(&size.to_le_bytes())?;
+                out.write_all(&group.label)?;
+                out.write_all(&group.group_type.to_le_bytes())?;
+                out.write_all(&group.stamp.to_le_bytes())?;
+                out.write_all(&group.unknown.to_le_bytes())?;
+                for child in &group.children {
+                    self.write_block(child, out)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn subrecords_size(subrecords: &[Subrecord]) -> usize {
+    subrecords
+        .iter()
+        .map(|subrecord| {
+            let header = if subrecord.is_extended() { 16 } else { 6 };
+            header + subrecord.data.len()
+        })
+        .sum()
+}
+
+fn serialize_subrecords(subrecords: &[Subrecord]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(subrecords_size(subrecords));
+    if let Err(err) = write_subrecords(subrecords, &mut out) {
+        unreachable!("writing to a Vec never fails: {err}");
+    }
+    out
+}
+
+fn write_subrecords<W: Write + ?Sized>(subrecords: &[Subrecord], out: &mut W) -> io::Result<()> {
+    for subrecord in subrecords {
+        let len = subrecord.data.len();
+        if subrecord.is_extended() {
+            // The XXXX marker carries the real size and comes first; the
+            // subrecord's own size field follows it and is ignored on read.
+            out.write_all(b"XXXX")?;
+            out.write_all(&(4u16).to_le_bytes())?;
+            out.write_all(&(len as u32).to_le_bytes())?;
+            out.write_all(&subrecord.sub_type)?;
+            out.write_all(&subrecord.extended.unwrap_or(0).to_le_bytes())?;
+        } else {
+            out.write_all(&subrecord.sub_type)?;
+            out.write_all(&(len as u16).to_le_bytes())?;
+        }
+        out.write_all(&subrecord.data)?;
+    }
+    Ok(())
+}
+
+fn decompress_record_data(data: &[u8]) -> EspResult<Vec<u8>> {
+    if data.len() < 4 {
+        return Err(EspError::Decompress(
+            std::io::ErrorKind::UnexpectedEof.into(),
+        ));
+    }
+    // Reading stops past the declared size, so a corrupt stream cannot
+    // inflate without bound.
+    let declared = read_u32(data, 0)? as usize;
+    let mut decoder = ZlibDecoder::new(&data[4..]).take(declared as u64 + 1);
     let mut out = Vec::new();
-    decoder.read_to_end(&mut out)?;
+    decoder
+        .read_to_end(&mut out)
+        .map_err(EspError::Decompress)?;
+    if out.len() != declared {
+        return Err(EspError::Decompress(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "decompressed {} bytes, header declares {declared}",
+                out.len()
+            ),
+        )));
+    }
     Ok(out)
 }
 
@@ -498,10 +1347,6 @@ fn compress_record_data(data: &[u8]) -> EspResult<Vec<u8>> {
     Ok(out)
 }
 
-fn is_string_subrecord(tag: &[u8; 4]) -> bool {
-    tag == b"FULL" || tag == b"DESC"
-}
-
 fn decode_subrecord_string(
     data: &[u8],
     strings_map: &StringsMap,
@@ -579,84 +1424,181 @@ fn tag_to_string(tag: [u8; 4]) -> String {
     tag.iter().map(|b| *b as char).collect()
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 fn load_strings_bundle(
     path: &Path,
-    workspace_root: &Path,
+    strings_location: StringsLocation<'_>,
     language: Option<&str>,
+    profile: GameProfile,
 ) -> EspResult<StringsBundle> {
-    let base_name = path
-        .file_stem()
-        .and_then(|name| name.to_str())
-        .ok_or(EspError::InvalidStringsPath)?
-        .to_string();
-    let language = language.unwrap_or("english").to_lowercase();
-    let strings_dir = workspace_root.join("Data").join("Strings");
-
-    let strings_path =
-        resolve_strings_path(&strings_dir, &base_name, &language, StringsKind::Strings);
-    let dlstrings_path =
-        resolve_strings_path(&strings_dir, &base_name, &language, StringsKind::DlStrings);
-    let ilstrings_path =
-        resolve_strings_path(&strings_dir, &base_name, &language, StringsKind::IlStrings);
-
+    let (base_name, language, [strings_path, dlstrings_path, ilstrings_path]) =
+        resolve_strings_paths(path, strings_location, language, profile)?;
     let strings = load_strings_file(strings_path.as_deref(), StringsKind::Strings)?;
     let dlstrings = load_strings_file(dlstrings_path.as_deref(), StringsKind::DlStrings)?;
     let ilstrings = load_strings_file(ilstrings_path.as_deref(), StringsKind::IlStrings)?;
 
     Ok(StringsBundle {
-        strings,
-        dlstrings,
-        ilstrings,
+        files: PluginStrings {
+            strings,
+            dlstrings,
+            ilstrings,
+        },
         base_name,
         language,
     })
 }
 
+/// The Strings, DLStrings and ILStrings files extraction reads for
+/// `plugin_path`, skipping kinds that have none.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn strings_file_paths<'a>(
+    plugin_path: &Path,
+    strings_location: impl Into<StringsLocation<'a>>,
+    language: Option<&str>,
+    profile: GameProfile,
+) -> EspResult<Vec<PathBuf>> {
+    let (_, _, paths) =
+        resolve_strings_paths(plugin_path, strings_location.into(), language, profile)?;
+    Ok(paths.into_iter().flatten().collect())
+}
+
+/// Base name, file-name language and the file of each [`StringsKind`].
+#[cfg(not(target_arch = "wasm32"))]
+fn resolve_strings_paths(
+    path: &Path,
+    strings_location: StringsLocation<'_>,
+    language: Option<&str>,
+    profile: GameProfile,
+) -> EspResult<(String, String, [Option<PathBuf>; 3])> {
+    let base_name = path
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .ok_or(EspError::InvalidStringsPath)?
+        .to_string();
+    let language = profile.strings_language(language.unwrap_or("english"));
+    let strings_dirs = strings_location.search_dirs(profile);
+    let paths = [
+        StringsKind::Strings,
+        StringsKind::DlStrings,
+        StringsKind::IlStrings,
+    ]
+    .map(|kind| resolve_strings_path(&strings_dirs, &base_name, &language, kind));
+    Ok((base_name, language, paths))
+}
+
+/// Languages that have at least one Strings file for `plugin_path`, in the
+/// file-name form of `profile` (e.g. `english`, or `en` for Fallout 4).
+#[cfg(not(target_arch = "wasm32"))]
+pub fn available_languages<'a>(
+    plugin_path: &Path,
+    strings_location: impl Into<StringsLocation<'a>>,
+    profile: GameProfile,
+) -> EspResult<Vec<String>> {
+    let strings_location = strings_location.into();
+    let base_name = plugin_path
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .ok_or(EspError::InvalidStringsPath)?
+        .to_lowercase();
+    let prefix = format!("{base_name}_");
+    let mut languages = Vec::new();
+    for strings_dir in strings_location.search_dirs(profile) {
+        if !strings_dir.is_dir() {
+            continue;
+        }
+        for item in std::fs::read_dir(&strings_dir)? {
+            let file_name = item?.file_name().to_string_lossy().to_lowercase();
+            let Some((stem, ext)) = file_name.rsplit_once('.') else {
+                continue;
+            };
+            if !matches!(ext, "strings" | "dlstrings" | "ilstrings") {
+                continue;
+            }
+            let Some(language) = stem.strip_prefix(&prefix) else {
+                continue;
+            };
+            if !language.is_empty() && !language.contains('_') {
+                languages.push(language.to_string());
+            }
+        }
+    }
+    languages.sort();
+    languages.dedup();
+    Ok(languages)
+}
+
+/// Fails with [`EspError::MissingLanguage`] when a localized plugin has no
+/// Strings file for the requested language.
+#[cfg(not(target_arch = "wasm32"))]
+fn ensure_language_loaded(
+    bytes: &[u8],
+    bundle: &StringsBundle,
+    path: &Path,
+    strings_location: StringsLocation<'_>,
+    profile: GameProfile,
+) -> EspResult<()> {
+    if !bundle.files.is_empty() || !parse_plugin_info(bytes).is_ok_and(|info| info.is_localized()) {
+        return Ok(());
+    }
+    Err(EspError::MissingLanguage {
+        requested: bundle.language.clone(),
+        available: available_languages(path, strings_location, profile)?,
+    })
+}
+
+/// The file in the first of `strings_dirs` that has one.
+#[cfg(not(target_arch = "wasm32"))]
 fn resolve_strings_path(
-    strings_dir: &Path,
+    strings_dirs: &[PathBuf],
     base_name: &str,
     language: &str,
     kind: StringsKind,
 ) -> Option<PathBuf> {
-    let file_name = format!("{base_name}_{language}.{}", kind.extension());
-    let candidate = strings_dir.join(&file_name);
-    if candidate.exists() {
-        Some(candidate)
-    } else {
-        None
-    }
+    // Fallout 4 ships upper-case extensions (`Fallout4_en.STRINGS`).
+    let extensions = [
+        kind.extension().to_string(),
+        kind.extension().to_ascii_uppercase(),
+    ];
+    strings_dirs
+        .iter()
+        .flat_map(|dir| {
+            extensions
+                .iter()
+                .map(move |ext| dir.join(format!("{base_name}_{language}.{ext}")))
+        })
+        .find(|candidate| candidate.exists())
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 fn load_strings_file(path: Option<&Path>, kind: StringsKind) -> EspResult<Option<StringsFile>> {
     let Some(path) = path else {
         return Ok(None);
     };
-    let bytes = std::fs::read(path)?;
+    let bytes = read_file(path)?;
     let file = match kind {
         StringsKind::Strings => read_strings(&bytes),
         StringsKind::DlStrings => read_dlstrings(&bytes),
         StringsKind::IlStrings => read_ilstrings(&bytes),
     }
-    .map_err(|_| EspError::InvalidHeader)?;
+    .map_err(|source| EspError::StringsFile {
+        path: path.to_path_buf(),
+        source,
+    })?;
     Ok(Some(file))
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 fn build_strings_map(bundle: &StringsBundle) -> StringsMap {
-    StringsMap::new(bundle)
+    StringsMap::new(&bundle.files)
 }
 
-fn update_strings_bundle(
-    bundle: &mut StringsBundle,
+fn update_strings(
+    strings: &mut PluginStrings,
     kind: StringsKind,
     id: u32,
     text: &str,
 ) -> EspResult<()> {
-    let target = match kind {
-        StringsKind::Strings => bundle.strings.as_mut(),
-        StringsKind::DlStrings => bundle.dlstrings.as_mut(),
-        StringsKind::IlStrings => bundle.ilstrings.as_mut(),
-    };
-    let Some(file) = target else {
+    let Some(file) = strings.get_mut(kind) else {
         return Err(EspError::MissingStringsFile(kind));
     };
     if let Some(entry) = file.entries.iter_mut().find(|entry| entry.id == id) {
@@ -667,41 +1609,55 @@ fn update_strings_bundle(
     }
 }
 
-fn write_strings_bundle(bundle: &StringsBundle, workspace_root: &Path) -> EspResult<()> {
-    let output_strings = workspace_root.join("Data").join("Strings");
-    std::fs::create_dir_all(&output_strings)?;
-
-    if let Some(file) = &bundle.strings {
-        let bytes = write_strings(file).map_err(|_| EspError::InvalidHeader)?;
-        let path = output_strings.join(format!(
-            "{}_{}.{}",
-            bundle.base_name,
-            bundle.language,
-            StringsKind::Strings.extension()
-        ));
-        std::fs::write(path, bytes)?;
-    }
-    if let Some(file) = &bundle.dlstrings {
-        let bytes = write_dlstrings(file).map_err(|_| EspError::InvalidHeader)?;
-        let path = output_strings.join(format!(
+/// Writes the loaded files of `bundle` into `dir`; returns the written paths.
+#[cfg(not(target_arch = "wasm32"))]
+fn write_strings_bundle(bundle: &StringsBundle, dir: &Path) -> EspResult<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    let mut written = Vec::new();
+    for kind in [
+        StringsKind::Strings,
+        StringsKind::DlStrings,
+        StringsKind::IlStrings,
+    ] {
+        let Some(file) = bundle.files.get(kind) else {
+            continue;
+        };
+        let path = dir.join(format!(
             "{}_{}.{}",
             bundle.base_name,
             bundle.language,
-            StringsKind::DlStrings.extension()
+            kind.extension()
         ));
-        std::fs::write(path, bytes)?;
+        let options = WriteOptions::default();
+        crate::write_atomic_with(&path, |mut out| match kind {
+            StringsKind::Strings => write_strings_to(file, &options, &mut out),
+            StringsKind::DlStrings => write_dlstrings_to(file, &options, &mut out),
+            StringsKind::IlStrings => write_ilstrings_to(file, &options, &mut out),
+        })
+        .map_err(|source| strings_write_error(&path, source))?;
+        written.push(path);
     }
-    if let Some(file) = &bundle.ilstrings {
-        let bytes = write_ilstrings(file).map_err(|_| EspError::InvalidHeader)?;
-        let path = output_strings.join(format!(
-            "{}_{}.{}",
-            bundle.base_name,
-            bundle.language,
-            StringsKind::IlStrings.extension()
-        ));
-        std::fs::write(path, bytes)?;
+    Ok(written)
+}
+
+/// The [`StringsError`] a streamed Strings write failed with, or the file
+/// error it ran into.
+#[cfg(not(target_arch = "wasm32"))]
+fn strings_write_error(path: &Path, source: io::Error) -> EspError {
+    match source
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<StringsError>())
+    {
+        Some(err) => EspError::StringsFile {
+            path: path.to_path_buf(),
+            source: err.clone(),
+        },
+        None => EspError::File {
+            action: "write",
+            path: path.to_path_buf(),
+            source,
+        },
     }
-    Ok(())
 }
 
 #[derive(Debug)]
@@ -712,22 +1668,22 @@ struct StringsMap {
 }
 
 impl StringsMap {
-    fn new(bundle: &StringsBundle) -> Self {
+    fn new(files: &PluginStrings) -> Self {
         Self {
-            strings: bundle
+            strings: files
                 .strings
                 .as_ref()
-                .map(|file| build_string_index(file))
+                .map(build_string_index)
                 .unwrap_or_default(),
-            dlstrings: bundle
+            dlstrings: files
                 .dlstrings
                 .as_ref()
-                .map(|file| build_string_index(file))
+                .map(build_string_index)
                 .unwrap_or_default(),
-            ilstrings: bundle
+            ilstrings: files
                 .ilstrings
                 .as_ref()
-                .map(|file| build_string_index(file))
+                .map(build_string_index)
                 .unwrap_or_default(),
         }
     }
@@ -756,7 +1712,8 @@ fn build_string_index(file: &StringsFile) -> HashMap<u32, String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::strings::StringsEntry;
+    use crate::strings::{write_dlstrings, write_ilstrings, write_strings};
+    use proptest::prelude::*;
     use std::sync::atomic::{AtomicUsize, Ordering};
 
     fn temp_path(name: &str, ext: &str) -> PathBuf {
@@ -845,27 +1802,100 @@ mod tests {
         std::fs::write(&path, &record).expect("write plugin");
         let workspace_root = temp_dir("inline-root");
 
-        let extracted =
-            extract_strings(&path, &workspace_root, Some("english")).expect("extract strings");
+        let extracted = extract_strings(
+            &path,
+            &workspace_root,
+            Some("english"),
+            GameProfile::SkyrimSe,
+        )
+        .expect("extract strings");
         assert_eq!(extracted.len(), 1);
         assert_eq!(extracted[0].text, "Hello");
 
         let mut updated = extracted[0].clone();
         updated.text = "Hi".to_string();
         let out_dir = temp_dir("inline-out");
-        let out_path = apply_translations(
+        let out_path = out_dir.join(path.file_name().expect("file name"));
+        apply_translations(
             &path,
             &workspace_root,
-            &out_dir,
+            &out_path,
             vec![updated],
             Some("english"),
+            GameProfile::SkyrimSe,
         )
         .expect("apply");
-        let refreshed =
-            extract_strings(&out_path, &workspace_root, Some("english")).expect("extract updated");
+        let refreshed = extract_strings(
+            &out_path,
+            &workspace_root,
+            Some("english"),
+            GameProfile::SkyrimSe,
+        )
+        .expect("extract updated");
         assert_eq!(refreshed[0].text, "Hi");
     }
 
+    #[test]
+    fn t_esp_edid_001_strings_carry_record_editor_id() {
+        let mut plugin = make_record(
+            b"WEAP",
+            0x0001_2EB7,
+            0x0000_0420,
+            vec![
+                make_subrecord(b"EDID", b"IronSword\0"),
+                make_subrecord(b"FULL", b"Iron Sword\0"),
+                make_subrecord(b"DESC", b"Sharp\0"),
+            ],
+            false,
+        );
+        plugin.extend(make_record(
+            b"NPC_",
+            0x0001_2EB8,
+            0,
+            vec![
+                make_subrecord(b"EDID", b"\0"),
+                make_subrecord(b"FULL", b"Guard\0"),
+            ],
+            false,
+        ));
+        plugin.extend(make_record(
+            b"NPC_",
+            0x0001_2EB9,
+            0,
+            vec![make_subrecord(b"FULL", b"Bandit\0")],
+            false,
+        ));
+        let path = temp_path("edid", "esp");
+        std::fs::write(&path, &plugin).expect("write plugin");
+        let mut extracted = extract_strings(
+            &path,
+            &temp_dir("edid-root"),
+            Some("english"),
+            GameProfile::SkyrimSe,
+        )
+        .expect("extract strings");
+        extracted.sort_by_key(|string| (string.form_id, string.subrecord_type));
+        let editor_ids = extracted
+            .iter()
+            .map(|string| (string.text.as_str(), string.editor_id.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            editor_ids,
+            [
+                ("Sharp", Some("IronSword")),
+                ("Iron Sword", Some("IronSword")),
+                ("Guard", None),
+                ("Bandit", None),
+            ]
+        );
+        assert_eq!(extracted[0].record_flags, 0x0000_0420);
+        assert_eq!(
+            record_flag_names(extracted[0].record_flags),
+            ["Deleted", "Persistent"]
+        );
+        assert!(record_flag_names(extracted[2].record_flags).is_empty());
+    }
+
     #[test]
     fn t_esp_ex_001_localized_round_trip_edit() {
         let base_name = "TestPlugin";
@@ -885,12 +1915,10 @@ mod tests {
         );
         std::fs::write(&plugin_path, &record).expect("write plugin");
 
-        let strings_file = StringsFile {
-            entries: vec![StringsEntry {
-                id: string_id,
-                text: "Hello".to_string(),
-            }],
-        };
+        let strings_file = StringsFile::new(vec![StringsEntry {
+            id: string_id,
+            text: "Hello".to_string(),
+        }]);
         write_strings_fixture(
             &workspace_root,
             base_name,
@@ -899,8 +1927,13 @@ mod tests {
             &strings_file,
         );
 
-        let extracted = extract_strings(&plugin_path, &workspace_root, Some(language))
-            .expect("extract localized");
+        let extracted = extract_strings(
+            &plugin_path,
+            &workspace_root,
+            Some(language),
+            GameProfile::SkyrimSe,
+        )
+        .expect("extract localized");
         assert_eq!(extracted.len(), 1);
         assert_eq!(extracted[0].text, "Hello");
         match extracted[0].storage {
@@ -914,16 +1947,23 @@ mod tests {
         let mut updated = extracted[0].clone();
         updated.text = "こんにちは".to_string();
         let out_dir = data_dir.clone();
-        let out_path = apply_translations(
+        let out_path = out_dir.join(plugin_path.file_name().expect("file name"));
+        apply_translations(
             &plugin_path,
             &workspace_root,
-            &out_dir,
+            &out_path,
             vec![updated],
             Some(language),
+            GameProfile::SkyrimSe,
         )
         .expect("apply");
-        let refreshed =
-            extract_strings(&out_path, &workspace_root, Some(language)).expect("extract updated");
+        let refreshed = extract_strings(
+            &out_path,
+            &workspace_root,
+            Some(language),
+            GameProfile::SkyrimSe,
+        )
+        .expect("extract updated");
         assert_eq!(refreshed[0].text, "こんにちは");
     }
 
@@ -941,24 +1981,1203 @@ mod tests {
         std::fs::write(&path, &record).expect("write plugin");
         let workspace_root = temp_dir("compressed-root");
 
-        let extracted =
-            extract_strings(&path, &workspace_root, Some("english")).expect("extract strings");
+        let extracted = extract_strings(
+            &path,
+            &workspace_root,
+            Some("english"),
+            GameProfile::SkyrimSe,
+        )
+        .expect("extract strings");
         assert_eq!(extracted.len(), 1);
         assert_eq!(extracted[0].text, "Compressed");
 
         let mut updated = extracted[0].clone();
         updated.text = "Updated".to_string();
         let out_dir = temp_dir("compressed-out");
-        let out_path = apply_translations(
+        let out_path = out_dir.join(path.file_name().expect("file name"));
+        apply_translations(
             &path,
             &workspace_root,
-            &out_dir,
+            &out_path,
             vec![updated],
             Some("english"),
+            GameProfile::SkyrimSe,
         )
         .expect("apply");
-        let refreshed =
-            extract_strings(&out_path, &workspace_root, Some("english")).expect("extract updated");
+        let refreshed = extract_strings(
+            &out_path,
+            &workspace_root,
+            Some("english"),
+            GameProfile::SkyrimSe,
+        )
+        .expect("extract updated");
         assert_eq!(refreshed[0].text, "Updated");
     }
+
+    #[test]
+    fn t_esp_mast_001_read_masters_from_header() {
+        let mut bytes = make_record(
+            b"TES4",
+            0,
+            0,
+            vec![
+                make_subrecord(b"HEDR", &[0u8; 12]),
+                make_subrecord(b"MAST", b"Skyrim.esm\0"),
+                make_subrecord(b"DATA", &[0u8; 8]),
+                make_subrecord(b"MAST", b"Update.esm\0"),
+            ],
+            false,
+        );
+        bytes.extend(make_record(
+            b"NPC_",
+            0x0100_0001,
+            0,
+            vec![make_subrecord(b"FULL", b"Hello\0")],
+            false,
+        ));
+        let path = temp_path("masters", "esp");
+        std::fs::write(&path, &bytes).expect("write plugin");
+        let masters = read_masters(&path).expect("read masters");
+        assert_eq!(
+            masters,
+            vec!["Skyrim.esm".to_string(), "Update.esm".to_string()]
+        );
+    }
+
+    #[test]
+    fn t_esp_info_001_parse_header_fields_and_flags() {
+        let mut hedr = Vec::new();
+        hedr.extend_from_slice(&1.71f32.to_le_bytes());
+        hedr.extend_from_slice(&42u32.to_le_bytes());
+        hedr.extend_from_slice(&0x800u32.to_le_bytes());
+        let bytes = make_record(
+            b"TES4",
+            0,
+            PLUGIN_FLAG_LOCALIZED | PLUGIN_FLAG_LIGHT,
+            vec![
+                make_subrecord(b"HEDR", &hedr),
+                make_subrecord(b"CNAM", b"Author\0"),
+                make_subrecord(b"SNAM", b"A small mod\0"),
+                make_subrecord(b"MAST", b"Skyrim.esm\0"),
+                make_subrecord(b"DATA", &[0u8; 8]),
+            ],
+            false,
+        );
+        let info = parse_plugin_info(&bytes).expect("info");
+        assert!((info.version - 1.71).abs() < f32::EPSILON);
+        assert_eq!(info.record_count, 42);
+        assert_eq!(info.next_object_id, 0x800);
+        assert_eq!(info.author, "Author");
+        assert_eq!(info.description, "A small mod");
+        assert_eq!(info.masters, vec!["Skyrim.esm".to_string()]);
+        assert!(info.is_localized());
+        assert!(info.is_light());
+        assert!(!info.is_master());
+
+        let npc = make_record(b"NPC_", 1, 0, Vec::new(), false);
+        assert!(matches!(
+            parse_plugin_info(&npc),
+            Err(EspError::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn t_esp_loc_001_localize_then_delocalize_round_trip() {
+        let root = temp_dir("loc-root");
+        let data_dir = root.join("Data");
+        std::fs::create_dir_all(&data_dir).expect("create data dir");
+        let input = data_dir.join("Inline.esp");
+        let mut bytes = make_record(
+            b"TES4",
+            0,
+            PLUGIN_FLAG_MASTER,
+            vec![make_subrecord(b"HEDR", &[0u8; 12])],
+            false,
+        );
+        bytes.extend(make_record(
+            b"BOOK",
+            0x0100_0001,
+            0,
+            vec![
+                make_subrecord(b"FULL", b"Old Book\0"),
+                make_subrecord(b"DESC", b"A dusty tome.\0"),
+            ],
+            false,
+        ));
+        bytes.extend(make_record(
+            b"NPC_",
+            0x0100_0002,
+            0,
+            vec![make_subrecord(b"FULL", b"\0")],
+            false,
+        ));
+        std::fs::write(&input, &bytes).expect("write plugin");
+
+        let mut renamed = extract_strings(&input, &root, None, GameProfile::SkyrimSe)
+            .expect("extract")
+            .into_iter()
+            .find(|item| &item.subrecord_type == b"FULL")
+            .expect("full");
+        renamed.text = "古い本".to_string();
+        let localized = data_dir.join("Localized.esp");
+        let report = localize_plugin(
+            &input,
+            &root,
+            &localized,
+            &data_dir.join("Strings"),
+            vec![renamed],
+            None,
+            None,
+            GameProfile::SkyrimSe,
+        )
+        .expect("localize");
+        assert_eq!(report.converted, 3);
+        assert_eq!(report.skipped, 0);
+        assert_eq!(report.strings_files.len(), 3);
+        let info = read_plugin_info(&localized).expect("info");
+        assert!(info.is_localized());
+        assert!(info.is_master());
+
+        let mut extracted = extract_strings(&localized, &root, None, GameProfile::SkyrimSe)
+            .expect("extract localized");
+        extracted.sort_by_key(|item| item.get_unique_key());
+        assert_eq!(extracted.len(), 2);
+        assert_eq!(extracted[0].text, "A dusty tome.");
+        assert!(matches!(
+            extracted[0].storage,
+            StringStorage::Localized {
+                kind: StringsKind::DlStrings,
+                ..
+            }
+        ));
+        assert_eq!(extracted[1].text, "古い本");
+        assert!(matches!(
+            extracted[1].storage,
+            StringStorage::Localized {
+                kind: StringsKind::Strings,
+                ..
+            }
+        ));
+
+        let delocalized = data_dir.join("Delocalized.esp");
+        let report = delocalize_plugin(
+            &localized,
+            &root,
+            &delocalized,
+            Vec::new(),
+            None,
+            GameProfile::SkyrimSe,
+        )
+        .expect("delocalize");
+        assert_eq!(report.converted, 3);
+        assert!(!read_plugin_info(&delocalized).expect("info").is_localized());
+        let mut inline = extract_strings(&delocalized, &root, None, GameProfile::SkyrimSe)
+            .expect("extract inline");
+        inline.sort_by_key(|item| item.get_unique_key());
+        assert_eq!(inline[0].text, "A dusty tome.");
+        assert_eq!(inline[1].text, "古い本");
+        assert!(inline
+            .iter()
+            .all(|item| item.storage == StringStorage::Inline));
+
+        std::fs::remove_file(data_dir.join("Strings").join("Localized_english.strings"))
+            .expect("remove strings");
+        std::fs::remove_file(data_dir.join("Strings").join("Localized_english.dlstrings"))
+            .expect("remove dlstrings");
+        assert!(matches!(
+            delocalize_plugin(
+                &localized,
+                &root,
+                &delocalized,
+                Vec::new(),
+                None,
+                GameProfile::SkyrimSe
+            ),
+            Err(EspError::MissingStringsFile(StringsKind::Strings))
+        ));
+    }
+
+    #[test]
+    fn t_esp_fo4_001_profile_subrecords_and_strings_layout() {
+        let root = temp_dir("fo4-root");
+        let data_dir = root.join("Data");
+        std::fs::create_dir_all(&data_dir).expect("create data dir");
+        let input = data_dir.join("Vault.esp");
+        let mut bytes = make_record(
+            b"TES4",
+            0,
+            0,
+            vec![make_subrecord(b"HEDR", &[0u8; 12])],
+            false,
+        );
+        bytes[20..22].copy_from_slice(&131u16.to_le_bytes());
+        bytes.extend(make_record(
+            b"TERM",
+            0x0100_0001,
+            0,
+            vec![
+                make_subrecord(b"FULL", b"Terminal\0"),
+                make_subrecord(b"WNAM", b"Welcome, Overseer\0"),
+                make_subrecord(b"BTXT", b"Entry log 1\0"),
+                make_subrecord(b"ITXT", b"Open door\0"),
+            ],
+            false,
+        ));
+        bytes.extend(make_record(
+            b"MESG",
+            0x0100_0002,
+            0,
+            vec![make_subrecord(b"ITXT", b"OK\0")],
+            false,
+        ));
+        std::fs::write(&input, &bytes).expect("write plugin");
+
+        let info = read_plugin_info(&input).expect("info");
+        assert_eq!(info.form_version, 131);
+        assert_eq!(GameProfile::detect(&info), GameProfile::Fallout4);
+        assert_eq!(
+            GameProfile::detect(&PluginInfo {
+                form_version: 44,
+                ..PluginInfo::default()
+            }),
+            GameProfile::SkyrimSe
+        );
+        assert_eq!(GameProfile::parse("FO4"), Some(GameProfile::Fallout4));
+        assert_eq!(GameProfile::Fallout4.strings_language("Japanese"), "ja");
+        assert_eq!(
+            GameProfile::SkyrimSe.strings_language("Japanese"),
+            "japanese"
+        );
+        assert_eq!(
+            GameProfile::Starfield.strings_file_path(
+                &root,
+                "Vault",
+                "english",
+                StringsKind::DlStrings
+            ),
+            root.join("Data/Strings/Vault_en.dlstrings")
+        );
+
+        let skyrim = extract_strings(&input, &root, None, GameProfile::SkyrimSe).expect("skyrim");
+        assert_eq!(skyrim.len(), 1);
+        let fo4 = extract_strings(&input, &root, None, GameProfile::Fallout4).expect("fo4");
+        assert_eq!(fo4.len(), 5);
+        assert!(fo4
+            .iter()
+            .any(|item| item.get_unique_key() == "TERM:01000001:ITXT:3"));
+
+        let localized = data_dir.join("VaultLoc.esp");
+        let report = localize_plugin(
+            &input,
+            &root,
+            &localized,
+            &data_dir.join("Strings"),
+            Vec::new(),
+            None,
+            None,
+            GameProfile::Fallout4,
+        )
+        .expect("localize");
+        assert_eq!(report.converted, 5);
+        let strings = data_dir.join("Strings").join("VaultLoc_en.strings");
+        assert_eq!(report.strings_files[0], strings);
+        std::fs::rename(
+            &strings,
+            data_dir.join("Strings").join("VaultLoc_en.STRINGS"),
+        )
+        .expect("rename");
+
+        let extracted = extract_strings(&localized, &root, Some("english"), GameProfile::Fallout4)
+            .expect("extract localized");
+        let body = extracted
+            .iter()
+            .find(|item| &item.subrecord_type == b"BTXT")
+            .expect("btxt");
+        assert_eq!(body.text, "Entry log 1");
+        assert!(matches!(
+            body.storage,
+            StringStorage::Localized {
+                kind: StringsKind::DlStrings,
+                ..
+            }
+        ));
+        assert_eq!(
+            extracted
+                .iter()
+                .filter(|item| matches!(
+                    item.storage,
+                    StringStorage::Localized {
+                        kind: StringsKind::Strings,
+                        ..
+                    }
+                ))
+                .count(),
+            4
+        );
+    }
+
+    #[test]
+    fn t_esp_lang_001_detect_languages_and_report_missing() {
+        let root = temp_dir("lang-root");
+        let _ = std::fs::remove_dir_all(&root);
+        let data_dir = root.join("Data");
+        std::fs::create_dir_all(&data_dir).expect("create data dir");
+        let input = data_dir.join("Lang.esp");
+        let mut bytes = make_record(
+            b"TES4",
+            0,
+            PLUGIN_FLAG_LOCALIZED,
+            vec![make_subrecord(b"HEDR", &[0u8; 12])],
+            false,
+        );
+        bytes.extend(make_record(
+            b"NPC_",
+            0x0100_0001,
+            0,
+            vec![make_subrecord(b"FULL", &7u32.to_le_bytes())],
+            false,
+        ));
+        std::fs::write(&input, &bytes).expect("write plugin");
+
+        assert!(available_languages(&input, &root, GameProfile::SkyrimSe)
+            .expect("no strings dir")
+            .is_empty());
+        for (language, text) in [("german", "Hallo"), ("French", "Bonjour")] {
+            let file = StringsFile::new(vec![StringsEntry {
+                id: 7,
+                text: text.to_string(),
+            }]);
+            write_strings_fixture(&root, "Lang", language, StringsKind::Strings, &file);
+            write_strings_fixture(&root, "Lang", language, StringsKind::DlStrings, &file);
+        }
+        write_strings_fixture(
+            &root,
+            "Lang_Extra",
+            "english",
+            StringsKind::Strings,
+            &StringsFile::default(),
+        );
+
+        let languages =
+            available_languages(&input, &root, GameProfile::SkyrimSe).expect("languages");
+        assert_eq!(languages, vec!["french", "german"]);
+
+        let err = extract_strings(&input, &root, Some("english"), GameProfile::SkyrimSe)
+            .expect_err("english missing");
+        assert!(matches!(err, EspError::MissingLanguage { .. }));
+        assert_eq!(
+            err.to_string(),
+            "no strings files for language 'english' (found: french, german)"
+        );
+
+        let extracted = extract_strings(&input, &root, Some("german"), GameProfile::SkyrimSe)
+            .expect("extract german");
+        assert_eq!(extracted[0].text, "Hallo");
+    }
+
+    #[test]
+    fn t_esp_err_001_errors_carry_path_record_and_offset() {
+        let missing = temp_path("missing", "esp");
+        let err = read_plugin_info(&missing).expect_err("missing file");
+        assert!(matches!(&err, EspError::File { action: "read", path, .. } if path == &missing));
+        assert!(std::error::Error::source(&err).is_some());
+
+        let mut bytes = make_record(
+            b"TES4",
+            0,
+            0,
+            vec![make_subrecord(b"HEDR", &[0u8; 12])],
+            false,
+        );
+        let record_offset = bytes.len();
+        let mut broken = make_record(
+            b"WEAP",
+            0x0001_0F00,
+            0,
+            vec![make_subrecord(b"FULL", b"Sword\0")],
+            false,
+        );
+        // FULL claims 0x20 bytes but only 6 follow.
+        broken[RECORD_HEADER_SIZE + 4] = 0x20;
+        bytes.extend(broken);
+        let path = temp_path("broken", "esp");
+        std::fs::write(&path, &bytes).expect("write plugin");
+
+        let err = extract_strings(&path, &temp_dir("broken-root"), None, GameProfile::SkyrimSe)
+            .expect_err("broken subrecord");
+        let EspError::Record {
+            record_type,
+            form_id,
+            offset,
+            source,
+        } = &err
+        else {
+            panic!("expected record context, got {err:?}");
+        };
+        assert_eq!(record_type, "WEAP");
+        assert_eq!(*form_id, 0x0001_0F00);
+        assert_eq!(*offset, record_offset);
+        assert!(matches!(**source, EspError::InvalidSubrecord { offset: 0 }));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "WEAP 00010F00 at offset 0x{record_offset:X}: invalid subrecord at data offset 0x0"
+            )
+        );
+    }
+
+    #[test]
+    fn t_esp_cancel_001_extract_stops_when_canceled() {
+        let record = make_record(
+            b"NPC_",
+            0x0000_0D01,
+            0,
+            vec![make_subrecord(b"FULL", b"Guard\0")],
+            false,
+        );
+        let path = temp_path("cancel", "esp");
+        std::fs::write(&path, &record).expect("write plugin");
+        let workspace_root = temp_dir("cancel-root");
+
+        let cancel = CancelToken::new();
+        let extracted = extract_strings_cancelable(
+            &path,
+            &workspace_root,
+            None,
+            GameProfile::SkyrimSe,
+            &cancel.clone(),
+            &NoProgress,
+        )
+        .expect("not canceled");
+        assert_eq!(extracted.len(), 1);
+
+        cancel.cancel();
+        let err = extract_strings_cancelable(
+            &path,
+            &workspace_root,
+            None,
+            GameProfile::SkyrimSe,
+            &cancel,
+            &NoProgress,
+        )
+        .expect_err("canceled");
+        assert!(matches!(err, EspError::Canceled(Canceled)));
+        assert_eq!(err.to_string(), "canceled");
+    }
+
+    #[test]
+    fn t_esp_mem_001_extract_and_apply_without_files() {
+        let mut hedr = Vec::new();
+        hedr.extend_from_slice(&1.71f32.to_le_bytes());
+        hedr.extend_from_slice(&1u32.to_le_bytes());
+        hedr.extend_from_slice(&0x800u32.to_le_bytes());
+        let mut plugin = make_record(
+            b"TES4",
+            0,
+            PLUGIN_FLAG_LOCALIZED,
+            vec![make_subrecord(b"HEDR", &hedr)],
+            false,
+        );
+        plugin.extend(make_record(
+            b"NPC_",
+            0x0000_0E01,
+            0,
+            vec![make_subrecord(b"FULL", &100u32.to_le_bytes())],
+            false,
+        ));
+        let strings_bytes = write_strings(&StringsFile::new(vec![StringsEntry {
+            id: 100,
+            text: "Guard".to_string(),
+        }]))
+        .expect("write strings");
+
+        let err = extract_strings_from_bytes(
+            &plugin,
+            &PluginStrings::default(),
+            GameProfile::SkyrimSe,
+            &CancelToken::new(),
+            &NoProgress,
+        )
+        .expect_err("no strings");
+        assert!(matches!(
+            err,
+            EspError::MissingStringsFile(StringsKind::Strings)
+        ));
+
+        let mut strings =
+            PluginStrings::from_bytes(Some(&strings_bytes), None, None).expect("parse strings");
+        let extracted = extract_strings_from_bytes(
+            &plugin,
+            &strings,
+            GameProfile::SkyrimSe,
+            &CancelToken::new(),
+            &NoProgress,
+        )
+        .expect("extract");
+        assert_eq!(extracted.len(), 1);
+        assert_eq!(extracted[0].text, "Guard");
+
+        let mut updated = extracted[0].clone();
+        updated.text = "衛兵".to_string();
+        let output = apply_translations_to_bytes(
+            &plugin,
+            &mut strings,
+            vec![updated],
+            GameProfile::SkyrimSe,
+        )
+        .expect("apply");
+        assert_eq!(output, plugin);
+        let saved = write_strings(strings.get(StringsKind::Strings).expect("strings"))
+            .expect("write strings");
+        let reread = PluginStrings::from_bytes(Some(&saved), None, None).expect("reparse");
+        let refreshed = extract_strings_from_bytes(
+            &output,
+            &reread,
+            GameProfile::SkyrimSe,
+            &CancelToken::new(),
+            &NoProgress,
+        )
+        .expect("extract updated");
+        assert_eq!(refreshed[0].text, "衛兵");
+    }
+
+    #[test]
+    fn t_esp_tree_001_lazy_tree_expands_groups_on_demand() {
+        let mut plugin = make_record(
+            b"TES4",
+            0,
+            0,
+            vec![make_subrecord(b"HEDR", &[0u8; 12])],
+            false,
+        );
+        let mut records = make_record(
+            b"WEAP",
+            0x0000_0F01,
+            0,
+            vec![
+                make_subrecord(b"EDID", b"IronSword\0"),
+                make_subrecord(b"FULL", b"Iron Sword\0"),
+            ],
+            false,
+        );
+        records.extend(make_record(
+            b"WEAP",
+            0x0000_0F02,
+            RECORD_COMPRESSED,
+            vec![make_subrecord(b"FULL", b"Steel Sword\0")],
+            true,
+        ));
+        let group_size = (GROUP_HEADER_SIZE + records.len()) as u32;
+        let group_offset = plugin.len();
+        plugin.extend_from_slice(b"GRUP");
+        plugin.extend_from_slice(&group_size.to_le_bytes());
+        plugin.extend_from_slice(b"WEAP");
+        plugin.extend_from_slice(&[0u8; 12]);
+        plugin.extend(records);
+
+        let tree = PluginTree::new(plugin.as_slice()).expect("index");
+        assert_eq!(tree.roots().len(), 2);
+        let TreeNode::Group(group) = &tree.roots()[1] else {
+            panic!("expected group");
+        };
+        assert_eq!(&group.label(), b"WEAP");
+        assert_eq!(group.size(), group_size as usize);
+        assert!(!group.is_expanded());
+
+        let all = tree
+            .strings(&tree.roots()[1], GameProfile::SkyrimSe, &CancelToken::new())
+            .expect("branch strings");
+        let texts: Vec<&str> = all.iter().map(|s| s.text.as_str()).collect();
+        let expected = extract_strings_from_bytes(
+            &plugin,
+            &PluginStrings::default(),
+            GameProfile::SkyrimSe,
+            &CancelToken::new(),
+            &NoProgress,
+        )
+        .expect("extract");
+        assert_eq!(
+            texts,
+            expected.iter().map(|s| s.text.as_str()).collect::<Vec<_>>()
+        );
+        assert_eq!(texts, ["Steel Sword", "Iron Sword"]);
+        assert!(!group.is_expanded());
+
+        let children = tree.children(group).expect("expand");
+        assert!(group.is_expanded());
+        assert_eq!(children.len(), 2);
+        let TreeNode::Record(record) = &children[1] else {
+            panic!("expected record");
+        };
+        assert_eq!(record.form_id(), 0x0000_0F02);
+        assert!(record.is_compressed());
+        assert!(!record.is_materialized());
+        let subrecords = tree.subrecords(record).expect("materialize");
+        assert_eq!(&subrecords[0].sub_type(), b"FULL");
+        assert_eq!(subrecords[0].data(), b"Steel Sword\0");
+        assert!(record.is_materialized());
+        let TreeNode::Record(first) = &children[0] else {
+            panic!("expected record");
+        };
+        assert!(!first.is_materialized());
+
+        let mut broken = plugin.clone();
+        let child_offset = group_offset + GROUP_HEADER_SIZE;
+        broken[child_offset + 4..child_offset + 8].copy_from_slice(&u32::MAX.to_le_bytes());
+        let tree = PluginTree::new(broken).expect("top level still indexes");
+        let err = tree
+            .strings(&tree.roots()[1], GameProfile::SkyrimSe, &CancelToken::new())
+            .expect_err("broken child");
+        assert!(matches!(err, EspError::InvalidRecord { offset } if offset == child_offset));
+    }
+
+    #[test]
+    fn t_esp_write_001_streamed_plugin_matches_serialized_bytes() {
+        let mut plugin = make_record(
+            b"TES4",
+            0,
+            0,
+            vec![make_subrecord(b"HEDR", &[0u8; 12])],
+            false,
+        );
+        let long_text = vec![b'a'; 70_000];
+        let mut long_subrecord = Vec::new();
+        long_subrecord.extend_from_slice(b"XXXX");
+        long_subrecord.extend_from_slice(&4u16.to_le_bytes());
+        long_subrecord.extend_from_slice(&(long_text.len() as u32).to_le_bytes());
+        long_subrecord.extend_from_slice(b"DESC");
+        long_subrecord.extend_from_slice(&0u16.to_le_bytes());
+        long_subrecord.extend_from_slice(&long_text);
+        let mut records = make_record(
+            b"BOOK",
+            0x0000_0B01,
+            0,
+            vec![make_subrecord(b"FULL", b"Tome\0"), long_subrecord],
+            false,
+        );
+        records.extend(make_record(
+            b"BOOK",
+            0x0000_0B02,
+            RECORD_COMPRESSED,
+            vec![make_subrecord(b"FULL", b"Journal\0")],
+            true,
+        ));
+        plugin.extend_from_slice(b"GRUP");
+        plugin.extend_from_slice(&((GROUP_HEADER_SIZE + records.len()) as u32).to_le_bytes());
+        plugin.extend_from_slice(b"BOOK");
+        plugin.extend_from_slice(&[0u8; 12]);
+        plugin.extend(records);
+
+        let blocks = parse_plugin(&plugin).expect("parse");
+        assert_eq!(
+            BlockLayout::new(&blocks).expect("layout").size,
+            plugin.len()
+        );
+        assert_eq!(serialize_blocks(&blocks).expect("serialize"), plugin);
+        let mut streamed = Vec::new();
+        serialize_blocks_to(&blocks, &mut streamed).expect("stream");
+        assert_eq!(streamed, plugin);
+
+        let path = temp_path("streamed", "esp");
+        write_plugin_file(&path, &blocks).expect("write plugin");
+        assert_eq!(std::fs::read(&path).expect("read back"), plugin);
+        let _ = std::fs::remove_file(&path);
+
+        struct Full(usize);
+        impl Write for Full {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                if self.0 == 0 {
+                    return Err(io::ErrorKind::WriteZero.into());
+                }
+                let written = buf.len().min(self.0);
+                self.0 -= written;
+                Ok(written)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        assert!(matches!(
+            serialize_blocks_to(&blocks, &mut Full(100)),
+            Err(EspError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn t_esp_lenient_001_partial_extraction_skips_broken_records() {
+        let mut plugin = make_record(
+            b"TES4",
+            0,
+            0,
+            vec![make_subrecord(b"HEDR", &[0u8; 12])],
+            false,
+        );
+        plugin.extend(make_record(
+            b"WEAP",
+            0x0000_0A01,
+            0,
+            vec![make_subrecord(b"FULL", b"Iron Sword\0")],
+            false,
+        ));
+        let broken_offset = plugin.len();
+        let mut broken = make_record(
+            b"WEAP",
+            0x0000_0A02,
+            0,
+            vec![make_subrecord(b"FULL", b"Broken\0")],
+            false,
+        );
+        // FULL claims 0x20 bytes but only 7 follow.
+        broken[RECORD_HEADER_SIZE + 4] = 0x20;
+        plugin.extend(broken);
+
+        let mut records = make_record(
+            b"BOOK",
+            0x0000_0B01,
+            0,
+            vec![make_subrecord(b"FULL", b"Tome\0")],
+            false,
+        );
+        let corrupt_offset = GROUP_HEADER_SIZE + records.len();
+        let mut corrupt = make_record(
+            b"BOOK",
+            0x0000_0B02,
+            RECORD_COMPRESSED,
+            vec![make_subrecord(b"FULL", b"Journal\0")],
+            true,
+        );
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 0xFF;
+        records.extend(corrupt);
+        records.extend(make_record(
+            b"BOOK",
+            0x0000_0B03,
+            0,
+            vec![make_subrecord(b"FULL", b"Diary\0")],
+            false,
+        ));
+        let group_offset = plugin.len();
+        plugin.extend_from_slice(b"GRUP");
+        plugin.extend_from_slice(&((GROUP_HEADER_SIZE + records.len()) as u32).to_le_bytes());
+        plugin.extend_from_slice(b"BOOK");
+        plugin.extend_from_slice(&[0u8; 12]);
+        plugin.extend(records);
+        // A record cut off after its header started.
+        let truncated_offset = plugin.len();
+        plugin.extend_from_slice(b"NPC_\x40\0\0\0");
+
+        let strings = PluginStrings::default();
+        let cancel = CancelToken::new();
+        assert!(extract_strings_from_bytes(
+            &plugin,
+            &strings,
+            GameProfile::SkyrimSe,
+            &cancel,
+            &NoProgress
+        )
+        .is_err());
+
+        let partial = extract_strings_lenient_from_bytes(
+            &plugin,
+            &strings,
+            GameProfile::SkyrimSe,
+            &cancel,
+            &NoProgress,
+        )
+        .expect("lenient");
+        assert!(!partial.is_complete());
+        let mut texts = partial
+            .strings
+            .iter()
+            .map(|string| string.text.as_str())
+            .collect::<Vec<_>>();
+        texts.sort_unstable();
+        assert_eq!(texts, ["Diary", "Iron Sword", "Tome"]);
+
+        let mut skipped = partial
+            .skipped
+            .iter()
+            .map(|err| match err {
+                EspError::Record { offset, source, .. } => (*offset, source.to_string()),
+                EspError::InvalidRecord { offset } => (*offset, err.to_string()),
+                other => panic!("unexpected error: {other:?}"),
+            })
+            .collect::<Vec<_>>();
+        skipped.sort_unstable();
+        assert_eq!(skipped.len(), 3, "{skipped:?}");
+        assert_eq!(skipped[0].0, broken_offset);
+        assert_eq!(skipped[0].1, "invalid subrecord at data offset 0x0");
+        assert_eq!(skipped[1].0, group_offset + corrupt_offset);
+        assert!(skipped[1].1.starts_with("invalid compressed record data"));
+        assert_eq!(skipped[2].0, truncated_offset);
+
+        let path = temp_path("lenient", "esp");
+        std::fs::write(&path, &plugin).expect("write plugin");
+        let from_file = extract_strings_lenient(
+            &path,
+            &temp_dir("lenient-root"),
+            None,
+            GameProfile::SkyrimSe,
+            &cancel,
+            &NoProgress,
+        )
+        .expect("lenient from file");
+        assert_eq!(from_file.strings, partial.strings);
+        assert_eq!(from_file.skipped.len(), 3);
+        let _ = std::fs::remove_file(&path);
+
+        cancel.cancel();
+        assert!(matches!(
+            extract_strings_lenient_from_bytes(
+                &plugin,
+                &strings,
+                GameProfile::SkyrimSe,
+                &cancel,
+                &NoProgress,
+            ),
+            Err(EspError::Canceled(_))
+        ));
+    }
+
+    fn make_extended_subrecord(tag: &[u8; 4], data: &[u8], own_size: u16) -> Vec<u8> {
+        let mut out = make_subrecord(b"XXXX", &(data.len() as u32).to_le_bytes());
+        out.extend_from_slice(tag);
+        out.extend_from_slice(&own_size.to_le_bytes());
+        out.extend_from_slice(data);
+        out
+    }
+
+    #[test]
+    fn t_esp_xxxx_001_oversized_subrecords_round_trip_byte_for_byte() {
+        let mut plugin = make_record(
+            b"TES4",
+            0,
+            0,
+            vec![make_subrecord(b"HEDR", &[0u8; 12])],
+            false,
+        );
+        let mut huge = vec![b'x'; 100_000];
+        huge.push(0);
+        let book = |form_id, flags, compress| {
+            make_record(
+                b"BOOK",
+                form_id,
+                flags,
+                vec![
+                    make_subrecord(b"EDID", b"HugeBook\0"),
+                    make_subrecord(b"FULL", b"Huge Book\0"),
+                    make_extended_subrecord(b"DESC", &huge, 0),
+                    // Small, yet stored after XXXX with a nonzero size field.
+                    make_extended_subrecord(b"CNAM", b"Note\0", 5),
+                ],
+                compress,
+            )
+        };
+        let mut records = book(0x0B01, 0, false);
+        records.extend(book(0x0B02, RECORD_COMPRESSED, true));
+        plugin.extend_from_slice(b"GRUP");
+        plugin.extend_from_slice(&((GROUP_HEADER_SIZE + records.len()) as u32).to_le_bytes());
+        plugin.extend_from_slice(b"BOOK");
+        plugin.extend_from_slice(&[0u8; 12]);
+        plugin.extend(records);
+
+        let blocks = parse_plugin(&plugin).expect("parse");
+        let written = serialize_blocks(&blocks).expect("serialize");
+        assert_eq!(written, plugin, "read then write keeps every byte");
+        let reparsed = parse_plugin(&written).expect("parse written");
+        assert_eq!(serialize_blocks(&reparsed).expect("serialize"), plugin);
+
+        let extract = |bytes: &[u8]| {
+            extract_strings_from_bytes(
+                bytes,
+                &PluginStrings::default(),
+                GameProfile::SkyrimSe,
+                &CancelToken::new(),
+                &NoProgress,
+            )
+            .expect("extract")
+        };
+        let extracted = extract(&plugin);
+        let desc = extracted
+            .iter()
+            .filter(|string| &string.subrecord_type == b"DESC")
+            .collect::<Vec<_>>();
+        assert_eq!(desc.len(), 2);
+        assert!(desc.iter().all(|string| string.text.len() == 100_000));
+
+        // Shrinking the huge DESC drops its XXXX; growing FULL past 64 KiB
+        // adds one.
+        let long_name = "名".repeat(30_000);
+        let translations = extracted
+            .iter()
+            .filter_map(|string| {
+                let text = match (&string.subrecord_type, string.form_id) {
+                    (b"DESC", 0x0B01) => "短い本文".to_string(),
+                    (b"FULL", 0x0B02) => long_name.clone(),
+                    _ => return None,
+                };
+                Some(ExtractedString {
+                    text,
+                    ..string.clone()
+                })
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(translations.len(), 2);
+        let translated = apply_translations_to_bytes(
+            &plugin,
+            &mut PluginStrings::default(),
+            translations,
+            GameProfile::SkyrimSe,
+        )
+        .expect("apply");
+        let blocks = parse_plugin(&translated).expect("parse translated");
+        let Block::Group(group) = &blocks[1] else {
+            panic!("expected group");
+        };
+        let [Block::Record(first), Block::Record(second)] = group.children.as_slice() else {
+            panic!("expected two records");
+        };
+        let extended = |record: &Record| {
+            record
+                .subrecords
+                .iter()
+                .filter(|subrecord| subrecord.extended.is_some())
+                .map(|subrecord| tag_to_string(subrecord.sub_type))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(extended(first), ["CNAM"]);
+        assert_eq!(extended(second), ["FULL", "DESC", "CNAM"]);
+        assert_eq!(
+            serialize_blocks(&blocks).expect("serialize translated"),
+            translated
+        );
+
+        let texts = extract(&translated)
+            .into_iter()
+            .map(|string| (string.form_id, string.subrecord_type, string.text.len()))
+            .collect::<Vec<_>>();
+        assert!(texts.contains(&(0x0B01, *b"DESC", "短い本文".len())));
+        assert!(texts.contains(&(0x0B02, *b"FULL", long_name.len())));
+        assert!(texts.contains(&(0x0B02, *b"DESC", 100_000)));
+    }
+
+    fn arb_tag() -> impl Strategy<Value = [u8; 4]> {
+        prop::array::uniform4(b'A'..=b'Z')
+            .prop_filter("reserved tag", |tag| tag != b"GRUP" && tag != b"XXXX")
+    }
+
+    fn arb_record() -> impl Strategy<Value = Block> {
+        (
+            arb_tag(),
+            any::<u32>(),
+            any::<u32>(),
+            any::<bool>(),
+            prop::collection::vec(
+                (
+                    arb_tag(),
+                    prop::collection::vec(any::<u8>(), 0..48),
+                    any::<Option<u16>>(),
+                ),
+                0..6,
+            ),
+        )
+            .prop_map(|(record_type, flags, form_id, compressed, subrecords)| {
+                let flags = if compressed {
+                    flags | RECORD_COMPRESSED
+                } else {
+                    flags & !RECORD_COMPRESSED
+                };
+                Block::Record(Record {
+                    header: RecordHeader {
+                        record_type,
+                        flags,
+                        form_id,
+                        stamp: 0,
+                        version_control: 0,
+                        version: 44,
+                        unknown: 0,
+                    },
+                    subrecords: subrecords
+                        .into_iter()
+                        .map(|(sub_type, data, extended)| Subrecord {
+                            sub_type,
+                            data,
+                            extended,
+                        })
+                        .collect(),
+                    compressed,
+                })
+            })
+    }
+
+    fn arb_blocks() -> impl Strategy<Value = Vec<Block>> {
+        let block = arb_record().prop_recursive(4, 32, 6, |inner| {
+            (arb_tag(), any::<u32>(), prop::collection::vec(inner, 0..6)).prop_map(
+                |(label, group_type, children)| {
+                    Block::Group(Group {
+                        label,
+                        group_type,
+                        stamp: 0,
+                        unknown: 0,
+                        children,
+                    })
+                },
+            )
+        });
+        prop::collection::vec(block, 0..6)
+    }
+
+    /// Parses `bytes` every way a plugin is read; none of them may panic.
+    fn parse_every_way(bytes: &[u8]) -> EspResult<Vec<Block>> {
+        let _ = parse_plugin_info(bytes);
+        let _ = extract_strings_from_bytes(
+            bytes,
+            &PluginStrings::default(),
+            GameProfile::SkyrimSe,
+            &CancelToken::new(),
+            &NoProgress,
+        );
+        if let Ok(tree) = PluginTree::new(bytes) {
+            let mut pending = tree.roots().iter().collect::<Vec<_>>();
+            while let Some(node) = pending.pop() {
+                match node {
+                    TreeNode::Group(group) => {
+                        if let Ok(children) = tree.children(group) {
+                            pending.extend(children);
+                        }
+                    }
+                    TreeNode::Record(record) => {
+                        let _ = tree.subrecords(record);
+                    }
+                }
+            }
+        }
+        parse_plugin(bytes)
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn t_esp_prop_001_serialized_plugins_parse_back_byte_for_byte(
+            blocks in arb_blocks(),
+        ) {
+            let bytes = serialize_blocks(&blocks).expect("serialize");
+            let parsed = parse_every_way(&bytes).expect("parse serialized plugin");
+            prop_assert_eq!(serialize_blocks(&parsed).expect("serialize again"), bytes);
+        }
+
+        #[test]
+        fn t_esp_prop_002_damaged_plugins_fail_without_panicking(
+            blocks in arb_blocks(),
+            cut in any::<prop::sample::Index>(),
+            flips in prop::collection::vec((any::<prop::sample::Index>(), any::<u8>()), 0..4),
+        ) {
+            let mut bytes = serialize_blocks(&blocks).expect("serialize");
+            for (index, value) in flips {
+                if !bytes.is_empty() {
+                    let at = index.index(bytes.len());
+                    bytes[at] = value;
+                }
+            }
+            bytes.truncate(cut.index(bytes.len() + 1));
+            // Whatever still parses must survive being written and read back.
+            if let Ok(parsed) = parse_every_way(&bytes) {
+                let written = serialize_blocks(&parsed).expect("serialize");
+                let reparsed = parse_plugin(&written).expect("parse written plugin");
+                prop_assert_eq!(serialize_blocks(&reparsed).expect("serialize again"), written);
+            }
+        }
+
+        #[test]
+        fn t_esp_prop_003_arbitrary_bytes_fail_without_panicking(
+            bytes in prop::collection::vec(any::<u8>(), 0..256),
+        ) {
+            let _ = parse_every_way(&bytes);
+        }
+    }
+
+    #[test]
+    fn t_esp_prop_004_malformed_blocks_are_errors_not_silent_loss() {
+        // Bytes after the last subrecord that cannot hold a header.
+        let mut data = make_subrecord(b"FULL", b"Name\0");
+        data.extend_from_slice(b"DE");
+        assert!(matches!(
+            parse_subrecords(&data),
+            Err(EspError::InvalidSubrecord { offset: 11 })
+        ));
+        // An XXXX size with no subrecord after it.
+        let mut data = make_subrecord(b"FULL", b"Name\0");
+        data.extend(make_subrecord(b"XXXX", &70_000u32.to_le_bytes()));
+        assert!(matches!(
+            parse_subrecords(&data),
+            Err(EspError::InvalidSubrecord { offset: 21 })
+        ));
+
+        // A record whose data runs into the record after its group.
+        let next = make_record(
+            b"BOOK",
+            0x0B02,
+            0,
+            vec![make_subrecord(b"FULL", b"B\0")],
+            false,
+        );
+        let mut record = make_record(
+            b"BOOK",
+            0x0B01,
+            0,
+            vec![make_subrecord(b"FULL", b"A\0")],
+            false,
+        );
+        let data_size = (record.len() - RECORD_HEADER_SIZE + next.len()) as u32;
+        record[4..8].copy_from_slice(&data_size.to_le_bytes());
+        let mut plugin = Vec::new();
+        plugin.extend_from_slice(b"GRUP");
+        plugin.extend_from_slice(&((GROUP_HEADER_SIZE + record.len()) as u32).to_le_bytes());
+        plugin.extend_from_slice(b"BOOK");
+        plugin.extend_from_slice(&[0u8; 12]);
+        plugin.extend(&record);
+        plugin.extend(&next);
+        assert!(matches!(
+            parse_plugin(&plugin),
+            Err(EspError::InvalidRecord {
+                offset: GROUP_HEADER_SIZE
+            })
+        ));
+        let tree = PluginTree::new(plugin.as_slice()).expect("index");
+        let TreeNode::Group(group) = &tree.roots()[0] else {
+            panic!("expected group");
+        };
+        assert!(matches!(
+            tree.children(group),
+            Err(EspError::InvalidRecord {
+                offset: GROUP_HEADER_SIZE
+            })
+        ));
+
+        // Groups nested deeper than any real plugin.
+        let mut nested = Vec::new();
+        for depth in (0..MAX_GROUP_DEPTH + 1).rev() {
+            let mut group = Vec::new();
+            group.extend_from_slice(b"GRUP");
+            group.extend_from_slice(
+                &(((MAX_GROUP_DEPTH + 1 - depth) * GROUP_HEADER_SIZE) as u32).to_le_bytes(),
+            );
+            group.extend_from_slice(&[0u8; 16]);
+            group.extend(nested);
+            nested = group;
+        }
+        assert!(matches!(
+            parse_plugin(&nested),
+            Err(EspError::InvalidGroup { offset }) if offset == MAX_GROUP_DEPTH * GROUP_HEADER_SIZE
+        ));
+
+        // Compressed data that inflates to more than its declared size.
+        let mut compressed = compress_record_data(&[b'a'; 64]).expect("compress");
+        compressed[..4].copy_from_slice(&8u32.to_le_bytes());
+        assert!(matches!(
+            decompress_record_data(&compressed),
+            Err(EspError::Decompress(_))
+        ));
+    }
 }
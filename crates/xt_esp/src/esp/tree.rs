@@ -0,0 +1,309 @@
+//! Offset index of a plugin for browsing large masters: only the top-level
+//! headers are read up front, a group's children are scanned when it is
+//! expanded and a record's subrecords are parsed when they are asked for.
+
+#[cfg(not(target_arch = "wasm32"))]
+use super::read_file;
+use super::{
+    collect_strings, parse_plugin_info, parse_record_data, read_group_header, read_record_header,
+    read_tag, EspError, EspResult, ExtractedString, PluginStrings, RecordHeader, StringsKind,
+    StringsMap, Subrecord, GROUP_HEADER_SIZE, RECORD_COMPRESSED,
+};
+use crate::cancel::CancelToken;
+use crate::profile::GameProfile;
+use std::borrow::Cow;
+use std::ops::Range;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// A plugin's bytes with a lazily built index of its groups and records.
+#[derive(Debug)]
+pub struct PluginTree<'a> {
+    bytes: Cow<'a, [u8]>,
+    roots: Vec<TreeNode>,
+    localized: bool,
+    strings_map: StringsMap,
+}
+
+/// A group or record header in a [`PluginTree`].
+#[derive(Debug)]
+pub enum TreeNode {
+    Group(TreeGroup),
+    Record(TreeRecord),
+}
+
+/// Header of a group; its children are scanned by [`PluginTree::children`].
+#[derive(Debug)]
+pub struct TreeGroup {
+    label: [u8; 4],
+    group_type: u32,
+    range: Range<usize>,
+    children: OnceLock<Vec<TreeNode>>,
+}
+
+/// Header of a record; its subrecords are parsed by [`PluginTree::subrecords`].
+#[derive(Debug)]
+pub struct TreeRecord {
+    header: RecordHeader,
+    offset: usize,
+    data: Range<usize>,
+    subrecords: OnceLock<Vec<Subrecord>>,
+}
+
+impl TreeNode {
+    /// Byte offset of the node's header in the plugin.
+    pub fn offset(&self) -> usize {
+        match self {
+            Self::Group(group) => group.range.start,
+            Self::Record(record) => record.offset,
+        }
+    }
+}
+
+impl TreeGroup {
+    pub fn label(&self) -> [u8; 4] {
+        self.label
+    }
+
+    pub fn group_type(&self) -> u32 {
+        self.group_type
+    }
+
+    /// Size of the group including its header, as stored in the plugin.
+    pub fn size(&self) -> usize {
+        self.range.len()
+    }
+
+    /// True once [`PluginTree::children`] scanned the group.
+    pub fn is_expanded(&self) -> bool {
+        self.children.get().is_some()
+    }
+}
+
+impl TreeRecord {
+    pub fn record_type(&self) -> [u8; 4] {
+        self.header.record_type
+    }
+
+    pub fn form_id(&self) -> u32 {
+        self.header.form_id
+    }
+
+    pub fn flags(&self) -> u32 {
+        self.header.flags
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        (self.header.flags & RECORD_COMPRESSED) != 0
+    }
+
+    /// True once [`PluginTree::subrecords`] parsed the record.
+    pub fn is_materialized(&self) -> bool {
+        self.subrecords.get().is_some()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PluginTree<'static> {
+    pub fn open(path: &Path) -> EspResult<Self> {
+        Self::new(read_file(path)?)
+    }
+}
+
+impl<'a> PluginTree<'a> {
+    /// Indexes the top-level groups and records of `bytes` without reading
+    /// anything inside them.
+    pub fn new(bytes: impl Into<Cow<'a, [u8]>>) -> EspResult<Self> {
+        let bytes = bytes.into();
+        let roots = scan_nodes(&bytes, 0, bytes.len())?;
+        let localized = parse_plugin_info(&bytes).is_ok_and(|info| info.is_localized());
+        Ok(Self {
+            bytes,
+            roots,
+            localized,
+            strings_map: StringsMap::new(&PluginStrings::default()),
+        })
+    }
+
+    /// Resolves localized strings of [`Self::strings`] through `strings`.
+    pub fn with_strings(mut self, strings: &PluginStrings) -> Self {
+        if !strings.is_empty() {
+            self.localized = false;
+        }
+        self.strings_map = StringsMap::new(strings);
+        self
+    }
+
+    pub fn roots(&self) -> &[TreeNode] {
+        &self.roots
+    }
+
+    /// Children of `group`, which must belong to this tree; scanned on the
+    /// first call and kept afterwards.
+    pub fn children<'t>(&'t self, group: &'t TreeGroup) -> EspResult<&'t [TreeNode]> {
+        if let Some(children) = group.children.get() {
+            return Ok(children);
+        }
+        let start = group.range.start + GROUP_HEADER_SIZE;
+        let children = scan_nodes(&self.bytes, start, group.range.end)?;
+        Ok(group.children.get_or_init(|| children))
+    }
+
+    /// Subrecords of `record`, which must belong to this tree; parsed on the
+    /// first call and kept afterwards.
+    pub fn subrecords<'t>(&'t self, record: &'t TreeRecord) -> EspResult<&'t [Subrecord]> {
+        if let Some(subrecords) = record.subrecords.get() {
+            return Ok(subrecords);
+        }
+        let subrecords = parse_record_data(
+            &record.header,
+            &self.bytes[record.data.clone()],
+            record.offset,
+        )?;
+        Ok(record.subrecords.get_or_init(|| subrecords))
+    }
+
+    /// Strings of the records under `node`, in the order of
+    /// [`crate::extract_strings_from_bytes`]. Records that were not
+    /// materialized are parsed only for the duration of the call.
+    pub fn strings(
+        &self,
+        node: &TreeNode,
+        profile: GameProfile,
+        cancel: &CancelToken,
+    ) -> EspResult<Vec<ExtractedString>> {
+        if self.localized {
+            return Err(EspError::MissingStringsFile(StringsKind::Strings));
+        }
+        let mut results = Vec::new();
+        match node {
+            TreeNode::Record(record) => {
+                cancel.check()?;
+                let subrecords = self.subrecords(record)?;
+                collect_strings(
+                    &record.header,
+                    subrecords,
+                    &self.strings_map,
+                    profile,
+                    &mut results,
+                );
+            }
+            TreeNode::Group(group) => {
+                cancel.check()?;
+                let start = group.range.start + GROUP_HEADER_SIZE;
+                let mut children = scan_nodes(&self.bytes, start, group.range.end)?;
+                while let Some(child) = children.pop() {
+                    collect_branch(
+                        &self.bytes,
+                        child,
+                        &self.strings_map,
+                        profile,
+                        cancel,
+                        &mut results,
+                        None,
+                    )?;
+                }
+            }
+        }
+        Ok(results)
+    }
+}
+
+/// Headers of the blocks stored between `start` and `end`.
+pub(super) fn scan_nodes(bytes: &[u8], start: usize, end: usize) -> EspResult<Vec<TreeNode>> {
+    scan_nodes_lenient(bytes, start, end, None)
+}
+
+/// [`scan_nodes`] that, given `skipped`, stops at the first unreadable
+/// header, records its error there and returns the blocks before it.
+pub(super) fn scan_nodes_lenient(
+    bytes: &[u8],
+    start: usize,
+    end: usize,
+    skipped: Option<&mut Vec<EspError>>,
+) -> EspResult<Vec<TreeNode>> {
+    let mut nodes = Vec::new();
+    match scan_into(bytes, start, end, &mut nodes) {
+        Ok(()) => Ok(nodes),
+        Err(err) => match skipped {
+            Some(skipped) => {
+                skipped.push(err);
+                Ok(nodes)
+            }
+            None => Err(err),
+        },
+    }
+}
+
+fn scan_into(bytes: &[u8], start: usize, end: usize, nodes: &mut Vec<TreeNode>) -> EspResult<()> {
+    // Blocks must end within the group they are stored in.
+    let bytes = &bytes[..end];
+    let mut cursor = start;
+    while cursor < end {
+        let tag =
+            read_tag(bytes, cursor).map_err(|_| EspError::InvalidRecord { offset: cursor })?;
+        if &tag == b"GRUP" {
+            let (group, next) = read_group_header(bytes, cursor)?;
+            nodes.push(TreeNode::Group(TreeGroup {
+                label: group.label,
+                group_type: group.group_type,
+                range: cursor..next,
+                children: OnceLock::new(),
+            }));
+            cursor = next;
+        } else {
+            let (header, data) = read_record_header(bytes, cursor)?;
+            let next = data.end;
+            nodes.push(TreeNode::Record(TreeRecord {
+                header,
+                offset: cursor,
+                data,
+                subrecords: OnceLock::new(),
+            }));
+            cursor = next;
+        }
+    }
+    Ok(())
+}
+
+/// Collects the strings under `node` depth-first, last child first, parsing
+/// one record at a time. Given `skipped`, records that fail to parse are
+/// left out and their errors recorded there.
+pub(super) fn collect_branch(
+    bytes: &[u8],
+    node: TreeNode,
+    strings_map: &StringsMap,
+    profile: GameProfile,
+    cancel: &CancelToken,
+    results: &mut Vec<ExtractedString>,
+    mut skipped: Option<&mut Vec<EspError>>,
+) -> EspResult<()> {
+    let mut stack = vec![node];
+    while let Some(node) = stack.pop() {
+        cancel.check()?;
+        match node {
+            TreeNode::Record(record) => {
+                match parse_record_data(&record.header, &bytes[record.data], record.offset) {
+                    Ok(subrecords) => {
+                        collect_strings(&record.header, &subrecords, strings_map, profile, results)
+                    }
+                    Err(err) => match skipped.as_deref_mut() {
+                        Some(skipped) => skipped.push(err),
+                        None => return Err(err),
+                    },
+                }
+            }
+            TreeNode::Group(group) => {
+                let start = group.range.start + GROUP_HEADER_SIZE;
+                stack.extend(scan_nodes_lenient(
+                    bytes,
+                    start,
+                    group.range.end,
+                    skipped.as_deref_mut(),
+                )?);
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,6 +1,36 @@
+//! Plugin and Strings file parsing. The byte-level API builds for
+//! `wasm32-unknown-unknown`; everything that reads or writes files is
+//! native only.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod atomic;
+mod cancel;
 pub mod esp;
+mod profile;
+mod progress;
 mod strings;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub use atomic::{write_atomic, write_atomic_with};
+pub use cancel::{CancelToken, Canceled};
+#[cfg(not(target_arch = "wasm32"))]
+pub use esp::{
+    apply_translations, available_languages, delocalize_plugin, extract_strings,
+    extract_strings_cancelable, extract_strings_lenient, localize_plugin, read_masters,
+    read_plugin_info, strings_file_paths,
+};
 pub use esp::{
-    apply_translations, extract_strings, EspError, ExtractedString, StringStorage, StringsKind,
+    apply_translations_to_bytes, extract_strings_from_bytes, extract_strings_lenient_from_bytes,
+    parse_plugin_info, record_flag_names, EspError, ExtractedString, LocalizationReport,
+    PartialExtraction, PluginInfo, PluginStrings, PluginTree, StringStorage, StringsKind,
+    StringsLocation, Subrecord, TreeGroup, TreeNode, TreeRecord, PLUGIN_FLAG_LIGHT,
+    PLUGIN_FLAG_LOCALIZED, PLUGIN_FLAG_MASTER,
+};
+pub use profile::GameProfile;
+pub use progress::{NoProgress, ProgressSink};
+pub use strings::{
+    read_dlstrings, read_ilstrings, read_strings, write_dlstrings, write_dlstrings_to,
+    write_dlstrings_with, write_ilstrings, write_ilstrings_to, write_ilstrings_with, write_strings,
+    write_strings_to, write_strings_with, RawStrings, StringsEntry, StringsError, StringsFile,
+    StringsInfo, StringsWarning, WriteOptions,
 };
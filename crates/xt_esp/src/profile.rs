@@ -0,0 +1,148 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::esp::{PluginInfo, StringsKind};
+
+/// TES4 form versions: Skyrim SE writes 44, Fallout 4 131, Starfield 554+.
+const SKYRIM_SE_FORM_VERSION: u16 = 44;
+const FALLOUT4_FORM_VERSION: u16 = 131;
+const STARFIELD_FORM_VERSION: u16 = 554;
+
+/// Game whose translatable subrecords and Strings layout a plugin follows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GameProfile {
+    /// Skyrim (2011): `FULL`/`DESC`, `<plugin>_english.strings`.
+    SkyrimLe,
+    /// Skyrim Special/Anniversary Edition; same layout as LE.
+    #[default]
+    SkyrimSe,
+    /// Fallout 4: adds terminal/message/book texts, `<plugin>_en.strings`.
+    Fallout4,
+    /// Starfield: Fallout 4 subrecord table and short language codes.
+    Starfield,
+}
+
+impl GameProfile {
+    pub const ALL: [GameProfile; 4] = [
+        GameProfile::SkyrimLe,
+        GameProfile::SkyrimSe,
+        GameProfile::Fallout4,
+        GameProfile::Starfield,
+    ];
+
+    /// Identifier used in settings files and on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            GameProfile::SkyrimLe => "skyrimle",
+            GameProfile::SkyrimSe => "skyrimse",
+            GameProfile::Fallout4 => "fallout4",
+            GameProfile::Starfield => "starfield",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GameProfile::SkyrimLe => "Skyrim LE",
+            GameProfile::SkyrimSe => "Skyrim SE/AE",
+            GameProfile::Fallout4 => "Fallout 4",
+            GameProfile::Starfield => "Starfield",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "skyrimle" | "le" | "tes5" => Some(GameProfile::SkyrimLe),
+            "skyrim" | "skyrimse" | "sse" | "skyrimae" => Some(GameProfile::SkyrimSe),
+            "fallout4" | "fo4" => Some(GameProfile::Fallout4),
+            "starfield" | "sf1" => Some(GameProfile::Starfield),
+            _ => None,
+        }
+    }
+
+    /// Guesses the game from the TES4 header's form version.
+    pub fn detect(info: &PluginInfo) -> Self {
+        match info.form_version {
+            v if v >= STARFIELD_FORM_VERSION => GameProfile::Starfield,
+            v if v >= FALLOUT4_FORM_VERSION => GameProfile::Fallout4,
+            v if v >= SKYRIM_SE_FORM_VERSION => GameProfile::SkyrimSe,
+            _ => GameProfile::SkyrimLe,
+        }
+    }
+
+    fn is_skyrim(self) -> bool {
+        matches!(self, GameProfile::SkyrimLe | GameProfile::SkyrimSe)
+    }
+
+    /// Whether `sub_type` in a `record_type` record holds a (possibly
+    /// localized) string.
+    pub fn is_string_subrecord(self, record_type: &[u8; 4], sub_type: &[u8; 4]) -> bool {
+        if sub_type == b"FULL" || sub_type == b"DESC" {
+            return true;
+        }
+        !self.is_skyrim()
+            && matches!(
+                (record_type, sub_type),
+                (b"TERM", b"WNAM" | b"BTXT" | b"ITXT" | b"RNAM")
+                    | (b"BOOK", b"TNAM")
+                    | (b"MESG", b"ITXT")
+            )
+    }
+
+    /// Strings file that receives a newly localized subrecord.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn strings_kind(self, sub_type: &[u8; 4]) -> StringsKind {
+        match sub_type {
+            b"DESC" => StringsKind::DlStrings,
+            b"BTXT" | b"TNAM" if !self.is_skyrim() => StringsKind::DlStrings,
+            _ => StringsKind::Strings,
+        }
+    }
+
+    /// Language part of Strings file names: Fallout 4 and Starfield use short
+    /// codes (`english` -> `en`); other values pass through lowercased.
+    pub fn strings_language(self, language: &str) -> String {
+        let language = language.to_lowercase();
+        if self.is_skyrim() {
+            return language;
+        }
+        let code = match language.as_str() {
+            "english" => "en",
+            "french" => "fr",
+            "german" => "de",
+            "italian" => "it",
+            "spanish" => "es",
+            "polish" => "pl",
+            "russian" => "ru",
+            "japanese" => "ja",
+            "chinese" => "cn",
+            "portuguese" | "brazilian" => "ptbr",
+            _ => return language,
+        };
+        code.to_string()
+    }
+
+    /// Folder holding the Strings files of plugins under `workspace_root`.
+    pub fn strings_dir(self, workspace_root: &Path) -> PathBuf {
+        workspace_root.join("Data").join("Strings")
+    }
+
+    /// `<strings_dir>/<base_name>_<language>.<ext>` for this game.
+    pub fn strings_file_path(
+        self,
+        workspace_root: &Path,
+        base_name: &str,
+        language: &str,
+        kind: StringsKind,
+    ) -> PathBuf {
+        self.strings_dir(workspace_root).join(format!(
+            "{base_name}_{}.{kind}",
+            self.strings_language(language)
+        ))
+    }
+}
+
+impl fmt::Display for GameProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
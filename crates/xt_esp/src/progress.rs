@@ -0,0 +1,22 @@
+/// Receives progress from a long-running job. Workers call it from their
+/// inner loops, so implementations should be cheap (atomics, no I/O).
+pub trait ProgressSink: Send + Sync {
+    /// Starts counting `total` items (0 when unknown) from zero.
+    fn set_total(&self, total: usize);
+    /// Names the file being processed.
+    fn set_current_file(&self, file: &str);
+    /// Marks `items` more items as processed.
+    fn advance(&self, items: usize);
+}
+
+/// Sink for callers that do not show progress.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn set_total(&self, _total: usize) {}
+
+    fn set_current_file(&self, _file: &str) {}
+
+    fn advance(&self, _items: usize) {}
+}
@@ -1,23 +1,256 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::{self, Write};
+use std::ops::Range;
+use std::sync::Arc;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StringsEntry {
     pub id: u32,
+    /// Decoded view of the string; bytes that are not UTF-8 show as U+FFFD.
     pub text: String,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// Entries of a Strings file and, for a file that was read, the bytes it was
+/// read from. Files compare equal when their entries do.
+#[derive(Debug, Clone, Default)]
 pub struct StringsFile {
     pub entries: Vec<StringsEntry>,
+    pub raw: Option<RawStrings>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+impl PartialEq for StringsFile {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl Eq for StringsFile {}
+
+impl StringsFile {
+    pub fn new(entries: Vec<StringsEntry>) -> Self {
+        Self { entries, raw: None }
+    }
+
+    /// Bytes of `id`'s string as read, without length prefix or terminator.
+    pub fn raw_payload(&self, id: u32) -> Option<&[u8]> {
+        self.raw.as_ref()?.payload(id)
+    }
+
+    /// Statistics and warnings of the file as read; `None` for a file that
+    /// was not read.
+    pub fn info(&self) -> Option<StringsInfo> {
+        self.raw.as_ref().map(RawStrings::info)
+    }
+}
+
+/// The bytes a Strings file was read from, so strings that were not edited
+/// are written back exactly as read (embedded nulls and non-UTF-8 included)
+/// and an unedited file is written back byte for byte.
+#[derive(Clone)]
+pub struct RawStrings {
+    bytes: Arc<[u8]>,
+    /// Whether strings carry a length prefix (DLSTRINGS and ILSTRINGS).
+    prefixed: bool,
+    /// The string data block within `bytes`.
+    data: Range<usize>,
+    /// Directory entries in file order.
+    directory: Arc<[RawEntry]>,
+}
+
+#[derive(Debug, Clone)]
+struct RawEntry {
+    id: u32,
+    /// Offset of the string within the data block.
+    offset: usize,
+    /// Payload within `bytes`, without length prefix or terminator.
+    payload: Range<usize>,
+}
+
+impl std::fmt::Debug for RawStrings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RawStrings")
+            .field("bytes", &self.bytes.len())
+            .field("strings", &self.directory.len())
+            .finish()
+    }
+}
+
+impl RawStrings {
+    /// Payload of the first directory entry for `id`.
+    pub fn payload(&self, id: u32) -> Option<&[u8]> {
+        self.directory
+            .iter()
+            .find(|entry| entry.id == id)
+            .map(|entry| &self.bytes[entry.payload.clone()])
+    }
+
+    /// Whether `entries` are exactly the strings read, in file order, and
+    /// the file is written in the format it was read in.
+    fn unchanged(&self, entries: &[StringsEntry], prefixed: bool) -> bool {
+        self.prefixed == prefixed
+            && self.directory.len() == entries.len()
+            && self
+                .directory
+                .iter()
+                .zip(entries)
+                .all(|(read, entry)| read.id == entry.id && self.is_text(read, &entry.text))
+    }
+
+    pub fn info(&self) -> StringsInfo {
+        let mut seen = HashSet::new();
+        let mut duplicate_ids = BTreeSet::new();
+        let mut non_utf8_ids = Vec::new();
+        let mut embedded_null_ids = Vec::new();
+        for read in self.directory.iter() {
+            if !seen.insert(read.id) {
+                duplicate_ids.insert(read.id);
+            }
+            let payload = &self.bytes[read.payload.clone()];
+            if std::str::from_utf8(payload).is_err() {
+                non_utf8_ids.push(read.id);
+            }
+            if payload.contains(&0) {
+                embedded_null_ids.push(read.id);
+            }
+        }
+
+        let mut strings = self
+            .directory
+            .iter()
+            .map(|read| (read.offset, self.end(read)))
+            .collect::<Vec<_>>();
+        strings.sort_unstable();
+        strings.dedup();
+        let mut covered = 0;
+        let mut reached = 0;
+        for &(start, end) in &strings {
+            covered += end.saturating_sub(start.max(reached));
+            reached = reached.max(end);
+        }
+
+        StringsInfo {
+            size: self.bytes.len(),
+            entries: self.directory.len(),
+            strings: strings
+                .iter()
+                .map(|&(start, _)| start)
+                .collect::<HashSet<_>>()
+                .len(),
+            duplicate_ids: duplicate_ids.into_iter().collect(),
+            unused_bytes: self.data.len() - covered,
+            trailing_bytes: self.bytes.len() - self.data.end,
+            non_utf8_ids,
+            embedded_null_ids,
+        }
+    }
+
+    fn is_text(&self, read: &RawEntry, text: &str) -> bool {
+        decode(&self.bytes[read.payload.clone()]) == text
+    }
+
+    /// End of the string's bytes, terminator included, within the data
+    /// block.
+    fn end(&self, read: &RawEntry) -> usize {
+        read.payload.end + 1 - self.data.start
+    }
+}
+
+/// Statistics of a Strings file as read, and what in it may not survive
+/// editing as expected.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StringsInfo {
+    /// Size of the file in bytes.
+    pub size: usize,
+    /// Directory entries.
+    pub entries: usize,
+    /// Distinct string offsets; fewer than `entries` when entries share a
+    /// string.
+    pub strings: usize,
+    /// Ids with more than one directory entry, ascending.
+    pub duplicate_ids: Vec<u32>,
+    /// Bytes of the data block that no string covers.
+    pub unused_bytes: usize,
+    /// Bytes after the data block.
+    pub trailing_bytes: usize,
+    /// Ids whose string is not valid UTF-8, in file order.
+    pub non_utf8_ids: Vec<u32>,
+    /// Ids whose string holds a null before its terminator, in file order.
+    pub embedded_null_ids: Vec<u32>,
+}
+
+/// A problem found reading a Strings file that did not stop it being read.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum StringsWarning {
+    #[error("duplicate string id: {0:08X}")]
+    DuplicateId(u32),
+    #[error("string {0:08X} is not valid utf-8")]
+    NonUtf8(u32),
+    #[error("string {0:08X} contains a null")]
+    EmbeddedNull(u32),
+    #[error("{0} bytes after the data block")]
+    TrailingBytes(usize),
+}
+
+impl StringsInfo {
+    pub fn warnings(&self) -> Vec<StringsWarning> {
+        let mut warnings = Vec::new();
+        warnings.extend(
+            self.duplicate_ids
+                .iter()
+                .map(|&id| StringsWarning::DuplicateId(id)),
+        );
+        warnings.extend(
+            self.non_utf8_ids
+                .iter()
+                .map(|&id| StringsWarning::NonUtf8(id)),
+        );
+        warnings.extend(
+            self.embedded_null_ids
+                .iter()
+                .map(|&id| StringsWarning::EmbeddedNull(id)),
+        );
+        if self.trailing_bytes > 0 {
+            warnings.push(StringsWarning::TrailingBytes(self.trailing_bytes));
+        }
+        warnings
+    }
+
+    pub fn report(&self) -> String {
+        let mut out = format!(
+            "size={} entries={} strings={} unused_bytes={} trailing_bytes={}\n",
+            self.size, self.entries, self.strings, self.unused_bytes, self.trailing_bytes
+        );
+        for warning in self.warnings() {
+            out.push_str(&format!("! {warning}\n"));
+        }
+        out
+    }
+}
+
+fn decode(payload: &[u8]) -> std::borrow::Cow<'_, str> {
+    String::from_utf8_lossy(payload)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
 pub enum StringsError {
+    #[error("unexpected end of file")]
     UnexpectedEof,
+    #[error("invalid header")]
     InvalidHeader,
+    #[error("string offset out of range")]
     InvalidOffset,
+    #[error("invalid string length")]
     InvalidLength,
+    #[error("missing string terminator")]
     MissingTerminator,
+    #[error("invalid utf-8")]
     Utf8,
+    #[error("duplicate string id: {0}")]
     DuplicateId(u32),
+    /// A string with a null cannot be written to a STRINGS file, whose
+    /// strings end at the first null.
+    #[error("string {0:08X} contains a null")]
+    EmbeddedNull(u32),
 }
 
 pub fn read_strings(input: &[u8]) -> Result<StringsFile, StringsError> {
@@ -37,7 +270,7 @@ pub fn read_strings(input: &[u8]) -> Result<StringsFile, StringsError> {
         return Err(StringsError::UnexpectedEof);
     }
 
-    let mut entries = Vec::with_capacity(count as usize);
+    let mut directory = Vec::with_capacity(count as usize);
     for i in 0..count as usize {
         let base = 8usize + i * 8;
         let id = read_u32(input, base)?;
@@ -53,13 +286,19 @@ pub fn read_strings(input: &[u8]) -> Result<StringsFile, StringsError> {
         if end >= data_end {
             return Err(StringsError::MissingTerminator);
         }
-        let text = std::str::from_utf8(&input[start..end])
-            .map_err(|_| StringsError::Utf8)?
-            .to_string();
-        entries.push(StringsEntry { id, text });
+        directory.push(RawEntry {
+            id,
+            offset,
+            payload: start..end,
+        });
     }
 
-    Ok(StringsFile { entries })
+    Ok(file_from_directory(
+        input,
+        directory,
+        data_start..data_end,
+        false,
+    ))
 }
 
 pub fn read_dlstrings(input: &[u8]) -> Result<StringsFile, StringsError> {
@@ -70,44 +309,76 @@ pub fn read_ilstrings(input: &[u8]) -> Result<StringsFile, StringsError> {
     read_length_prefixed_strings(input)
 }
 
+/// How a Strings file is laid out when written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Keeps the layout of a file that was read in the same format: its
+    /// directory order, and the order, shared offsets and padding of the
+    /// strings that were not edited. Edited and added strings are appended
+    /// after them. Otherwise strings are written one each, sorted by id.
+    pub preserve_layout: bool,
+}
+
 pub fn write_strings(file: &StringsFile) -> Result<Vec<u8>, StringsError> {
-    let mut entries = file.entries.clone();
-    entries.sort_by_key(|entry| entry.id);
-    for window in entries.windows(2) {
-        if window[0].id == window[1].id {
-            return Err(StringsError::DuplicateId(window[0].id));
-        }
-    }
+    write_strings_with(file, &WriteOptions::default())
+}
 
-    let mut directory = Vec::with_capacity(entries.len());
-    let mut data_block: Vec<u8> = Vec::new();
-    for entry in &entries {
-        let offset = data_block.len() as u32;
-        data_block.extend_from_slice(entry.text.as_bytes());
-        data_block.push(0);
-        directory.push((entry.id, offset));
-    }
+pub fn write_dlstrings(file: &StringsFile) -> Result<Vec<u8>, StringsError> {
+    write_dlstrings_with(file, &WriteOptions::default())
+}
 
-    let count = entries.len() as u32;
-    let data_size = data_block.len() as u32;
-    let mut output = Vec::with_capacity(8 + directory.len() * 8 + data_block.len());
-    output.extend_from_slice(&count.to_le_bytes());
-    output.extend_from_slice(&data_size.to_le_bytes());
-    for (id, offset) in directory {
-        output.extend_from_slice(&id.to_le_bytes());
-        output.extend_from_slice(&offset.to_le_bytes());
-    }
-    output.extend_from_slice(&data_block);
+pub fn write_ilstrings(file: &StringsFile) -> Result<Vec<u8>, StringsError> {
+    write_ilstrings_with(file, &WriteOptions::default())
+}
 
-    Ok(output)
+pub fn write_strings_with(
+    file: &StringsFile,
+    options: &WriteOptions,
+) -> Result<Vec<u8>, StringsError> {
+    write_file(file, options, false)
 }
 
-pub fn write_dlstrings(file: &StringsFile) -> Result<Vec<u8>, StringsError> {
-    write_length_prefixed_strings(file)
+pub fn write_dlstrings_with(
+    file: &StringsFile,
+    options: &WriteOptions,
+) -> Result<Vec<u8>, StringsError> {
+    write_file(file, options, true)
 }
 
-pub fn write_ilstrings(file: &StringsFile) -> Result<Vec<u8>, StringsError> {
-    write_length_prefixed_strings(file)
+pub fn write_ilstrings_with(
+    file: &StringsFile,
+    options: &WriteOptions,
+) -> Result<Vec<u8>, StringsError> {
+    write_file(file, options, true)
+}
+
+/// [`write_strings_with`] straight into `out`, without assembling the file
+/// in memory first. A [`StringsError`] is reported before anything is
+/// written, as an [`io::ErrorKind::InvalidData`] error wrapping it.
+pub fn write_strings_to<W: Write + ?Sized>(
+    file: &StringsFile,
+    options: &WriteOptions,
+    out: &mut W,
+) -> io::Result<()> {
+    write_file_to(file, options, false, out)
+}
+
+/// [`write_dlstrings_with`] straight into `out`; see [`write_strings_to`].
+pub fn write_dlstrings_to<W: Write + ?Sized>(
+    file: &StringsFile,
+    options: &WriteOptions,
+    out: &mut W,
+) -> io::Result<()> {
+    write_file_to(file, options, true, out)
+}
+
+/// [`write_ilstrings_with`] straight into `out`; see [`write_strings_to`].
+pub fn write_ilstrings_to<W: Write + ?Sized>(
+    file: &StringsFile,
+    options: &WriteOptions,
+    out: &mut W,
+) -> io::Result<()> {
+    write_file_to(file, options, true, out)
 }
 
 fn read_u32(input: &[u8], offset: usize) -> Result<u32, StringsError> {
@@ -136,7 +407,7 @@ fn read_length_prefixed_strings(input: &[u8]) -> Result<StringsFile, StringsErro
         return Err(StringsError::UnexpectedEof);
     }
 
-    let mut entries = Vec::with_capacity(count as usize);
+    let mut directory = Vec::with_capacity(count as usize);
     for i in 0..count as usize {
         let base = 8usize + i * 8;
         let id = read_u32(input, base)?;
@@ -156,53 +427,331 @@ fn read_length_prefixed_strings(input: &[u8]) -> Result<StringsFile, StringsErro
         if text_end > data_end {
             return Err(StringsError::UnexpectedEof);
         }
-        let slice = &input[text_start..text_end];
-        if *slice.last().unwrap_or(&0) != 0 {
+        // The length covers the terminator; earlier nulls are part of the
+        // string.
+        if input[text_end - 1] != 0 {
             return Err(StringsError::MissingTerminator);
         }
-        let text = std::str::from_utf8(&slice[..slice.len() - 1])
-            .map_err(|_| StringsError::Utf8)?
-            .to_string();
-        entries.push(StringsEntry { id, text });
+        directory.push(RawEntry {
+            id,
+            offset,
+            payload: text_start..text_end - 1,
+        });
     }
 
-    Ok(StringsFile { entries })
+    Ok(file_from_directory(
+        input,
+        directory,
+        data_start..data_end,
+        true,
+    ))
 }
 
-fn write_length_prefixed_strings(file: &StringsFile) -> Result<Vec<u8>, StringsError> {
-    let mut entries = file.entries.clone();
-    entries.sort_by_key(|entry| entry.id);
-    for window in entries.windows(2) {
-        if window[0].id == window[1].id {
-            return Err(StringsError::DuplicateId(window[0].id));
+fn file_from_directory(
+    input: &[u8],
+    directory: Vec<RawEntry>,
+    data: Range<usize>,
+    prefixed: bool,
+) -> StringsFile {
+    let entries = directory
+        .iter()
+        .map(|read| StringsEntry {
+            id: read.id,
+            text: decode(&input[read.payload.clone()]).into_owned(),
+        })
+        .collect();
+    StringsFile {
+        entries,
+        raw: Some(RawStrings {
+            bytes: input.into(),
+            prefixed,
+            data,
+            directory: directory.into(),
+        }),
+    }
+}
+
+fn write_file(
+    file: &StringsFile,
+    options: &WriteOptions,
+    prefixed: bool,
+) -> Result<Vec<u8>, StringsError> {
+    match plan_file(file, options, prefixed)? {
+        Output::Unchanged(bytes) => Ok(bytes.to_vec()),
+        Output::Layout(layout) => {
+            let mut output = Vec::with_capacity(layout.size());
+            if let Err(err) = layout.write_to(&mut output) {
+                unreachable!("writing to a Vec never fails: {err}");
+            }
+            Ok(output)
         }
     }
+}
+
+fn write_file_to<W: Write + ?Sized>(
+    file: &StringsFile,
+    options: &WriteOptions,
+    prefixed: bool,
+    out: &mut W,
+) -> io::Result<()> {
+    match plan_file(file, options, prefixed)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?
+    {
+        Output::Unchanged(bytes) => out.write_all(bytes),
+        Output::Layout(layout) => layout.write_to(out),
+    }
+}
+
+/// What writing a file amounts to: the bytes it was read from, when nothing
+/// was edited, or a new layout.
+enum Output<'a> {
+    Unchanged(&'a [u8]),
+    Layout(Layout<'a>),
+}
+
+fn plan_file<'a>(
+    file: &'a StringsFile,
+    options: &WriteOptions,
+    prefixed: bool,
+) -> Result<Output<'a>, StringsError> {
+    if let Some(raw) = &file.raw {
+        if raw.unchanged(&file.entries, prefixed) {
+            return Ok(Output::Unchanged(&raw.bytes));
+        }
+    }
+    let preserved = file
+        .raw
+        .as_ref()
+        .filter(|raw| options.preserve_layout && raw.prefixed == prefixed);
+    Ok(Output::Layout(match preserved {
+        Some(raw) => preserved_layout(raw, &file.entries, prefixed)?,
+        None => compact_layout(file, prefixed)?,
+    }))
+}
+
+/// Directory of (id, offset) pairs and the data block they point into.
+struct Layout<'a> {
+    directory: Vec<(u32, u32)>,
+    data: DataBlock<'a>,
+}
+
+impl Layout<'_> {
+    fn size(&self) -> usize {
+        8 + self.directory.len() * 8 + self.data.len
+    }
+
+    fn write_to<W: Write + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        out.write_all(&(self.directory.len() as u32).to_le_bytes())?;
+        out.write_all(&(self.data.len as u32).to_le_bytes())?;
+        for (id, offset) in &self.directory {
+            out.write_all(&id.to_le_bytes())?;
+            out.write_all(&offset.to_le_bytes())?;
+        }
+        self.data.write_to(out)
+    }
+}
+
+/// A data block as pieces borrowed from the file being written, so its size
+/// is known before anything is copied.
+#[derive(Default)]
+struct DataBlock<'a> {
+    pieces: Vec<Piece<'a>>,
+    len: usize,
+}
+
+enum Piece<'a> {
+    /// Bytes copied as they are.
+    Bytes(&'a [u8]),
+    /// A string followed by its terminator, after its length if `prefixed`.
+    String { payload: &'a [u8], prefixed: bool },
+}
 
+impl<'a> DataBlock<'a> {
+    /// Offset of the next piece; fails once the block outgrows a `u32`.
+    fn offset(&self) -> Result<u32, StringsError> {
+        to_offset(self.len)
+    }
+
+    fn push_bytes(&mut self, bytes: &'a [u8]) {
+        if !bytes.is_empty() {
+            self.len += bytes.len();
+            self.pieces.push(Piece::Bytes(bytes));
+        }
+    }
+
+    fn push_string(
+        &mut self,
+        id: u32,
+        payload: &'a [u8],
+        prefixed: bool,
+    ) -> Result<(), StringsError> {
+        if !prefixed && payload.contains(&0) {
+            return Err(StringsError::EmbeddedNull(id));
+        }
+        if prefixed {
+            u32::try_from(payload.len() + 1).map_err(|_| StringsError::InvalidLength)?;
+            self.len += 4;
+        }
+        self.len += payload.len() + 1;
+        self.pieces.push(Piece::String { payload, prefixed });
+        Ok(())
+    }
+
+    fn write_to<W: Write + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        for piece in &self.pieces {
+            match piece {
+                Piece::Bytes(bytes) => out.write_all(bytes)?,
+                Piece::String { payload, prefixed } => {
+                    if *prefixed {
+                        out.write_all(&(payload.len() as u32 + 1).to_le_bytes())?;
+                    }
+                    out.write_all(payload)?;
+                    out.write_all(&[0])?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One string per entry, sorted by id.
+fn compact_layout(file: &StringsFile, prefixed: bool) -> Result<Layout<'_>, StringsError> {
+    let entries = payloads(file)?;
     let mut directory = Vec::with_capacity(entries.len());
-    let mut data_block: Vec<u8> = Vec::new();
-    for entry in &entries {
-        let offset = data_block.len() as u32;
-        let bytes = entry.text.as_bytes();
-        let len = bytes
-            .len()
-            .checked_add(1)
-            .ok_or(StringsError::UnexpectedEof)? as u32;
-        data_block.extend_from_slice(&len.to_le_bytes());
-        data_block.extend_from_slice(bytes);
-        data_block.push(0);
-        directory.push((entry.id, offset));
+    let mut data = DataBlock::default();
+    for (id, bytes) in entries {
+        directory.push((id, data.offset()?));
+        data.push_string(id, bytes, prefixed)?;
+    }
+    Ok(Layout { directory, data })
+}
+
+/// Payload to write for each entry, sorted by id: the bytes read for it
+/// when its text was not changed, otherwise its text.
+fn payloads(file: &StringsFile) -> Result<Vec<(u32, &[u8])>, StringsError> {
+    let mut read = HashMap::new();
+    if let Some(raw) = &file.raw {
+        for entry in raw.directory.iter() {
+            read.entry(entry.id)
+                .or_insert(&raw.bytes[entry.payload.clone()]);
+        }
+    }
+    let mut entries = file
+        .entries
+        .iter()
+        .map(|entry| {
+            let raw = read
+                .get(&entry.id)
+                .copied()
+                .filter(|raw| decode(raw) == entry.text);
+            (entry.id, raw.unwrap_or(entry.text.as_bytes()))
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|(id, _)| *id);
+    for window in entries.windows(2) {
+        if window[0].0 == window[1].0 {
+            return Err(StringsError::DuplicateId(window[0].0));
+        }
+    }
+    Ok(entries)
+}
+
+/// The layout `raw` was read with: entries in their order, the padding and
+/// the strings that were not edited copied in file order with their shared
+/// offsets, then edited and added strings (one per distinct text) and the
+/// padding that ended the data block.
+fn preserved_layout<'a>(
+    raw: &'a RawStrings,
+    entries: &'a [StringsEntry],
+    prefixed: bool,
+) -> Result<Layout<'a>, StringsError> {
+    // The directory entry each entry was read from, while its text is
+    // unchanged; entries are matched by position first so duplicate ids keep
+    // their own strings.
+    let kept = entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let read = raw
+                .directory
+                .get(index)
+                .filter(|read| read.id == entry.id)
+                .or_else(|| raw.directory.iter().find(|read| read.id == entry.id))?;
+            raw.is_text(read, &entry.text).then_some(read)
+        })
+        .collect::<Vec<_>>();
+    let used = kept
+        .iter()
+        .flatten()
+        .map(|read| read.offset)
+        .collect::<HashSet<_>>();
+
+    let old = &raw.bytes[raw.data.clone()];
+    let mut strings = raw
+        .directory
+        .iter()
+        .map(|read| (read.offset, raw.end(read)))
+        .collect::<Vec<_>>();
+    strings.sort_unstable();
+    strings.dedup();
+    let tail = strings.iter().map(|&(_, end)| end).max().unwrap_or(0);
+
+    let mut data = DataBlock::default();
+    let mut moved = HashMap::new();
+    // Bytes of the old data block before `consumed` were copied or dropped;
+    // `last` is the last string copied, as (old start, old end, new start).
+    let mut consumed = 0;
+    let mut last: Option<(usize, usize, usize)> = None;
+    for (start, end) in strings {
+        if start > consumed {
+            data.push_bytes(&old[consumed..start]);
+            consumed = start;
+        }
+        if !used.contains(&start) {
+            consumed = consumed.max(end);
+            continue;
+        }
+        // A string that is the tail of the one copied before it still is.
+        if let Some((last_start, last_end, new_start)) = last {
+            if start >= last_start && end <= last_end {
+                moved.insert(start, to_offset(new_start + start - last_start)?);
+                continue;
+            }
+        }
+        let new_start = data.len;
+        moved.insert(start, to_offset(new_start)?);
+        data.push_bytes(&old[start..end]);
+        last = Some((start, end, new_start));
+        consumed = consumed.max(end);
     }
 
-    let count = entries.len() as u32;
-    let data_size = data_block.len() as u32;
-    let mut output = Vec::with_capacity(8 + directory.len() * 8 + data_block.len());
-    output.extend_from_slice(&count.to_le_bytes());
-    output.extend_from_slice(&data_size.to_le_bytes());
-    for (id, offset) in directory {
-        output.extend_from_slice(&id.to_le_bytes());
-        output.extend_from_slice(&offset.to_le_bytes());
+    let mut directory = Vec::with_capacity(entries.len());
+    let mut appended: HashMap<&[u8], u32> = HashMap::new();
+    let mut seen = HashSet::new();
+    for (entry, read) in entries.iter().zip(&kept) {
+        let first = seen.insert(entry.id);
+        let offset = match read {
+            Some(read) => moved[&read.offset],
+            None if !first => return Err(StringsError::DuplicateId(entry.id)),
+            None => {
+                let bytes = entry.text.as_bytes();
+                match appended.get(bytes) {
+                    Some(&offset) => offset,
+                    None => {
+                        let offset = data.offset()?;
+                        data.push_string(entry.id, bytes, prefixed)?;
+                        appended.insert(bytes, offset);
+                        offset
+                    }
+                }
+            }
+        };
+        directory.push((entry.id, offset));
     }
-    output.extend_from_slice(&data_block);
+    data.push_bytes(&old[tail.min(old.len())..]);
+    Ok(Layout { directory, data })
+}
 
-    Ok(output)
+fn to_offset(offset: usize) -> Result<u32, StringsError> {
+    u32::try_from(offset).map_err(|_| StringsError::InvalidOffset)
 }